@@ -0,0 +1,59 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    compress_offline_docs();
+    embed_git_hash();
+}
+
+/// Compresses the bundled offline command docs so they can be embedded in
+/// the binary with `include_bytes!`.
+fn compress_offline_docs() {
+    let source = Path::new("assets/offline-docs.md");
+    println!("cargo:rerun-if-changed={}", source.display());
+
+    let docs = std::fs::read(source).expect("failed to read offline docs");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&docs).expect("failed to compress offline docs");
+    let compressed = encoder.finish().expect("failed to compress offline docs");
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is not set");
+    std::fs::write(Path::new(&out_dir).join("offline-docs.md.gz"), compressed)
+        .expect("failed to write compressed offline docs");
+}
+
+/// Sets `ANTRAFT_GIT_HASH` to the commit being built, for `--version`, or
+/// to "unknown" outside a git checkout.
+fn embed_git_hash() {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let hash = git(&["rev-parse", "--short=9", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ANTRAFT_GIT_HASH={}", hash);
+
+    // Built again when HEAD moves: to another branch, or along this one.
+    // Paths that don't exist would have cargo run this every build.
+    let Some(git_dir) = git(&["rev-parse", "--git-dir"]) else {
+        return;
+    };
+    let git_dir = Path::new(&git_dir);
+    let head = git_dir.join("HEAD");
+    let mut watched = vec![head.clone(), git_dir.join("packed-refs")];
+    if let Some(reference) = std::fs::read_to_string(&head)
+        .ok()
+        .and_then(|head| head.strip_prefix("ref: ").map(|reference| reference.trim().to_string()))
+    {
+        watched.push(git_dir.join(reference));
+    }
+    for path in watched.iter().filter(|path| path.exists()) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
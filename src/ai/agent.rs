@@ -1,150 +1,371 @@
 use super::{
-    AiConfig, AiRequest, AiResponse, ChatMessage,
-    GeminiClient
+    AiConfig, AiProvider, AiRequest, AiRequestOptions, AiResponse, ChatMessage,
+    CodeSnippet, CommitConvention, GeminiClient, ModelInfo, ScriptCommand, SessionBlockSummary
 };
-use super::chat::ChatSessionManager;
+use super::cache::{default_cache_path, ResponseCache};
+use super::chat::{ChatSession, ChatSessionManager, MessageRole};
+use super::commit_message;
+use super::injection::{self, GuardedPrompt};
+use super::offline::OfflineExplainer;
+use super::prompts;
+use super::provider::parse_response;
+use super::queue::{QueueTicket, RequestQueue};
+use super::redaction::Redactor;
+use super::system_prompt::{render_system_prompt, PromptContext, SECURITY_SYSTEM_PROMPT};
+use super::tools::{self, ToolCall, ToolStep};
+use super::script;
+use super::session_summary::{self, SummaryProgress};
 use anyhow::Result;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use std::future::Future;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Generation defaults for one kind of request.
+struct TaskDefaults {
+    task: &'static str,
+    temperature: Option<f32>,
+    system_prompt: Option<&'static str>,
+}
+
+/// Per-task defaults, applied before `[ai.overrides.<task>]` from the config.
+/// Tasks that produce commands or code run cooler than chat, which keeps
+/// the configured temperature.
+const TASK_DEFAULTS: &[TaskDefaults] = &[
+    TaskDefaults { task: "chat", temperature: None, system_prompt: None },
+    TaskDefaults { task: "explain_command", temperature: Some(0.3), system_prompt: None },
+    TaskDefaults { task: "generate_command", temperature: Some(0.1), system_prompt: None },
+    TaskDefaults { task: "fix_error", temperature: Some(0.2), system_prompt: None },
+    TaskDefaults { task: "code_review", temperature: Some(0.3), system_prompt: None },
+    TaskDefaults {
+        task: "security_analysis",
+        temperature: Some(0.1),
+        system_prompt: Some(SECURITY_SYSTEM_PROMPT),
+    },
+    TaskDefaults { task: "summarize_session", temperature: Some(0.3), system_prompt: None },
+    TaskDefaults { task: "generate_script", temperature: Some(0.2), system_prompt: None },
+    TaskDefaults { task: "commit_message", temperature: Some(0.2), system_prompt: None },
+    TaskDefaults { task: "suggest_commands", temperature: Some(0.2), system_prompt: None },
+];
+
+/// Earlier chat messages included with each chat prompt.
+pub const CHAT_CONTEXT_MESSAGES: usize = 10;
+
+/// Label of the code blocks in chat messages, which are untrusted.
+const CHAT_BLOCK_LABEL: &str = "content included in the message";
+
+/// Task names accepted in `[ai.overrides.<task>]`.
+pub fn known_tasks() -> impl Iterator<Item = &'static str> {
+    TASK_DEFAULTS.iter().map(|defaults| defaults.task)
+}
+
 pub struct AiAgent {
-    gemini_client: GeminiClient,
+    provider: Box<dyn AiProvider>,
     chat_manager: Arc<RwLock<ChatSessionManager>>,
+    offline_explainer: OfflineExplainer,
+    queue: Arc<RequestQueue>,
     config: AiConfig,
+    cache: ResponseCache,
+    /// Applied to every prompt before it reaches the provider
+    redactor: Redactor,
+    /// Secrets redacted so far, across config changes
+    redactions: Arc<AtomicUsize>,
+    /// Shell reported to the model through `{shell}` in the system prompt
+    shell: String,
+    /// Directory reported through `{cwd}`: the terminal session's, which
+    /// isn't the process's
+    working_directory: String,
 }
 
 impl AiAgent {
     pub fn new(config: AiConfig) -> Self {
         let gemini_client = GeminiClient::new(config.clone());
+        Self::with_provider(config, Box::new(gemini_client))
+    }
+
+    /// An agent that sends its prompts to `provider` instead of Gemini.
+    pub fn with_provider(config: AiConfig, provider: Box<dyn AiProvider>) -> Self {
         let chat_manager = Arc::new(RwLock::new(ChatSessionManager::new()));
+        let offline_explainer = OfflineExplainer::bundled().unwrap_or_else(|e| {
+            warn!("Offline command docs unavailable: {}", e);
+            OfflineExplainer::default()
+        });
+
+        let queue = RequestQueue::new(config.max_concurrent_requests);
+        for task in config.overrides.keys() {
+            if !known_tasks().any(|known| known == task) {
+                warn!("Ignoring [ai.overrides.{}]: unknown task", task);
+            }
+        }
+
+        let cache = ResponseCache::load(config.cache.clone(), &default_cache_path());
+        let redactions = Arc::new(AtomicUsize::new(0));
+        let redactor = Redactor::new(&config.redaction, redactions.clone());
 
         Self {
-            gemini_client,
+            provider,
             chat_manager,
+            offline_explainer,
+            queue,
+            cache,
             config,
+            redactor,
+            redactions,
+            shell: default_shell(),
+            working_directory: std::env::current_dir()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+
+    /// The queue every request waits in. Callers that want to show a
+    /// request's place in line enqueue it themselves and pass the ticket.
+    pub fn queue(&self) -> Arc<RequestQueue> {
+        self.queue.clone()
+    }
+
+    /// How many secrets have been redacted from prompts.
+    pub fn redactions(&self) -> Arc<AtomicUsize> {
+        self.redactions.clone()
+    }
+
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = shell.into();
+        self
+    }
+
+    /// Follows the terminal session into `directory`.
+    pub fn set_working_directory(&mut self, directory: impl Into<String>) {
+        self.working_directory = directory.into();
+    }
+
+    /// The system prompt for a request, the configured one unless `options`
+    /// overrides it, with its variables filled in for the current environment.
+    fn system_prompt(&self, options: &AiRequestOptions) -> String {
+        let template = options
+            .system_prompt_override
+            .as_deref()
+            .unwrap_or(&self.config.system_prompt);
+        render_system_prompt(template, &PromptContext::current(&self.shell, &self.working_directory))
+    }
+
+    /// Generation options for `task`: its defaults from `TASK_DEFAULTS`, then
+    /// any `[ai.overrides.<task>]` from the config.
+    pub fn request_options(&self, task: &str) -> AiRequestOptions {
+        let defaults = TASK_DEFAULTS
+            .iter()
+            .find(|defaults| defaults.task == task)
+            .map(|defaults| AiRequestOptions {
+                temperature: defaults.temperature,
+                max_tokens: None,
+                system_prompt_override: defaults.system_prompt.map(str::to_string),
+            })
+            .unwrap_or_default();
+        match self.config.overrides.get(task) {
+            Some(overrides) => defaults.overridden_by(overrides),
+            None => defaults,
         }
     }
 
     pub async fn process_request(&self, request: AiRequest) -> Result<AiResponse> {
+        self.answer(request, true).await
+    }
+
+    /// Like `process_request`, but always asks the provider, replacing any
+    /// cached response. For regenerating an answer.
+    pub async fn process_request_fresh(&self, request: AiRequest) -> Result<AiResponse> {
+        self.answer(request, false).await
+    }
+
+    /// Forgets every cached response.
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    async fn answer(&self, request: AiRequest, use_cache: bool) -> Result<AiResponse> {
         debug!("Processing AI request: {:?}", request);
+        let options = self.request_options(request.task());
+        let _permit = self.queue.enqueue().wait().await;
+
+        let history_message = request.history_message();
+        if let Some(message) = &history_message {
+            let mut chat_manager = self.chat_manager.write().await;
+            chat_manager.create_default_session_if_needed();
+            chat_manager.add_message_to_active(ChatMessage::user(message.clone()));
+        }
+
+        // Without a provider the answers are local fallbacks, not worth keeping
+        let temperature = options.temperature.unwrap_or(self.config.temperature);
+        let cache_key = match self.config.api_key.is_empty() {
+            true => None,
+            false => self.cache.cache_key(&request, &self.config.model, temperature),
+        };
+        let cached = cache_key
+            .as_deref()
+            .filter(|_| use_cache)
+            .and_then(|key| self.cache.get(key));
+
+        let response = match cached {
+            Some(response) => {
+                debug!("Answering {} request from the cache", request.task());
+                response
+            }
+            None => match (self.dispatch(&request, &options).await, &request) {
+                (Ok(response), _) => {
+                    if let Some(key) = cache_key {
+                        self.cache.insert(key, response.clone());
+                    }
+                    response
+                }
+                // Fall back to the bundled docs when the provider fails
+                (Err(e), AiRequest::ExplainCommand { command }) => {
+                    warn!("AI explanation failed, using offline docs: {}", e);
+                    self.offline_explanation(command, "The AI provider couldn't be reached.")
+                        .map_err(|_| e)?
+                }
+                (Err(e), _) => return Err(e),
+            },
+        };
+
+        if history_message.is_some() {
+            let mut chat_manager = self.chat_manager.write().await;
+            chat_manager.add_message_to_active(ChatMessage::assistant(response.content.clone()));
+        }
+
+        Ok(response)
+    }
 
+    async fn dispatch(&self, request: &AiRequest, options: &AiRequestOptions) -> Result<AiResponse> {
         match request {
             AiRequest::ExplainCommand { command } => {
-                self.explain_command(&command).await
+                self.explain_command(command, options).await
             }
             AiRequest::GenerateCommand { description } => {
-                self.generate_command(&description).await
+                self.generate_command(description, options).await
             }
             AiRequest::FixError { error, context } => {
-                self.fix_error(&error, context.as_deref()).await
+                self.fix_error(error, context.as_deref(), options).await
             }
             AiRequest::CodeReview { code, language } => {
-                self.review_code(&code, language.as_deref()).await
+                self.review_code(code, language.as_deref(), options).await
             }
             AiRequest::SecurityAnalysis { code, language } => {
-                self.analyze_security(&code, &language).await
+                self.analyze_security(code, language, options).await
             }
             AiRequest::Chat { message } => {
-                self.handle_chat_message(&message).await
+                self.handle_chat_message(message, options).await
+            }
+            AiRequest::SummarizeSession { blocks } => {
+                self.summarize_blocks(blocks, options, |_| {}).await
+            }
+            AiRequest::GenerateScript { commands, shell } => {
+                self.generate_script(commands, shell, options).await
+            }
+            AiRequest::CommitMessage { diff, convention } => {
+                self.generate_commit_message(diff, *convention, options).await
             }
         }
     }
 
-    async fn explain_command(&self, command: &str) -> Result<AiResponse> {
+    async fn explain_command(&self, command: &str, options: &AiRequestOptions) -> Result<AiResponse> {
         info!("Explaining command: {}", command);
-        
-        // Add to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.create_default_session_if_needed();
-            chat_manager.add_message_to_active(ChatMessage::user(
-                format!("Explain this command: {}", command)
-            ));
-        }
 
-        let response = self.gemini_client.explain_command(command).await?;
-
-        // Add response to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.add_message_to_active(ChatMessage::assistant(
-                response.content.clone()
-            ));
+        // Without a provider, fall back to the bundled docs
+        if self.config.api_key.is_empty() {
+            return self.offline_explanation(command, "No AI provider is configured.");
         }
+        let prompt = prompts::explain_command_prompt(&self.system_prompt(options), command);
+        self.generate_response(prompt, options).await
+    }
 
+    /// Sends `prompt`, guarded, and splits the answer into prose, code and
+    /// suggestions.
+    async fn generate_response(&self, prompt: String, options: &AiRequestOptions) -> Result<AiResponse> {
+        let guarded = guard(prompt);
+        let content = self.send(guarded.prompt, options).await?;
+        let mut response = parse_response(&content);
+        add_signals(&mut response.injection_signals, &guarded.signals);
         Ok(response)
     }
 
-    async fn generate_command(&self, description: &str) -> Result<AiResponse> {
-        info!("Generating command for: {}", description);
+    /// Sends `prompt` with its secrets redacted, restoring them in the
+    /// response unless `restore_placeholders` is off.
+    async fn send(&self, prompt: String, options: &AiRequestOptions) -> Result<String> {
+        let redaction = self.redactor.redact(&prompt);
+        let text = self.provider.generate_text(redaction.text.clone(), options).await?;
+        Ok(match self.config.redaction.restore_placeholders {
+            true => redaction.restore(&text),
+            false => text,
+        })
+    }
 
-        // Add to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.create_default_session_if_needed();
-            chat_manager.add_message_to_active(ChatMessage::user(
-                format!("Generate a command to: {}", description)
-            ));
+    /// Like `send`, restoring secrets in the chunks passed to `on_chunk` too.
+    async fn send_stream(
+        &self,
+        prompt: String,
+        options: &AiRequestOptions,
+        on_chunk: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String> {
+        let redaction = self.redactor.redact(&prompt);
+        if !self.config.redaction.restore_placeholders {
+            return self.provider.generate_text_stream(redaction.text, options, on_chunk).await;
         }
 
-        let response = self.gemini_client.generate_command(description).await?;
-
-        // Add response to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.add_message_to_active(ChatMessage::assistant(
-                response.content.clone()
-            ));
+        let mut restorer = redaction.stream_restorer();
+        let mut emit = |chunk: &str| {
+            let chunk = restorer.push(chunk);
+            if !chunk.is_empty() {
+                on_chunk(&chunk);
+            }
+        };
+        let text = self
+            .provider
+            .generate_text_stream(redaction.text.clone(), options, &mut emit)
+            .await?;
+        let rest = restorer.finish();
+        if !rest.is_empty() {
+            on_chunk(&rest);
         }
-
-        Ok(response)
+        Ok(redaction.restore(&text))
     }
 
-    async fn fix_error(&self, error: &str, context: Option<&str>) -> Result<AiResponse> {
-        info!("Fixing error: {}", error);
-
-        // Add to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.create_default_session_if_needed();
-            
-            let message = if let Some(ctx) = context {
-                format!("Fix this error: {}\nContext: {}", error, ctx)
-            } else {
-                format!("Fix this error: {}", error)
-            };
-            
-            chat_manager.add_message_to_active(ChatMessage::user(message));
-        }
+    fn offline_explanation(&self, command: &str, reason: &str) -> Result<AiResponse> {
+        self.offline_explainer
+            .explain(command, reason)
+            .ok_or_else(|| anyhow::anyhow!("{} No offline docs are available for `{}`.", reason, command))
+    }
 
-        let response = self.gemini_client.fix_error(error, context).await?;
+    async fn generate_command(&self, description: &str, options: &AiRequestOptions) -> Result<AiResponse> {
+        info!("Generating command for: {}", description);
+        let prompt = prompts::generate_command_prompt(&self.system_prompt(options), description);
+        self.generate_response(prompt, options).await
+    }
 
-        // Add response to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.add_message_to_active(ChatMessage::assistant(
-                response.content.clone()
-            ));
-        }
+    async fn fix_error(&self, error: &str, context: Option<&str>, options: &AiRequestOptions) -> Result<AiResponse> {
+        info!("Fixing error: {}", error);
+        let prompt = prompts::fix_error_prompt(&self.system_prompt(options), error, context);
+        self.generate_response(prompt, options).await
+    }
 
-        Ok(response)
+    async fn review_code(&self, code: &str, language: Option<&str>, options: &AiRequestOptions) -> Result<AiResponse> {
+        info!("Reviewing {} code", language.unwrap_or("unknown"));
+        let prompt = prompts::review_code_prompt(&self.system_prompt(options), code, language);
+        self.generate_response(prompt, options).await
     }
 
-    async fn review_code(&self, code: &str, language: Option<&str>) -> Result<AiResponse> {
-        let lang_str = language.unwrap_or("unknown");
-        info!("Reviewing {} code", lang_str);
+    async fn analyze_security(&self, code: &str, language: &str, options: &AiRequestOptions) -> Result<AiResponse> {
+        info!("Analyzing security for {} code", language);
+        let prompt = prompts::security_analysis_prompt(&self.system_prompt(options), code, language);
+        self.generate_response(prompt, options).await
+    }
 
-        // Add to chat history
-        {
-            let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.create_default_session_if_needed();
-            chat_manager.add_message_to_active(ChatMessage::user(
-                format!("Review this {} code:\n\n```{}\n{}\n```", lang_str, lang_str, code)
-            ));
-        }
+    async fn handle_chat_message(&self, message: &str, options: &AiRequestOptions) -> Result<AiResponse> {
+        info!("Handling chat message");
 
-        let response = self.gemini_client.review_code(code, language).await?;
+        let prompt = self.prepare_chat_prompt(message, options).await;
+        let response = self.generate_response(prompt, options).await?;
 
         // Add response to chat history
         {
@@ -157,34 +378,86 @@ impl AiAgent {
         Ok(response)
     }
 
-    async fn analyze_security(&self, code: &str, language: &str) -> Result<AiResponse> {
-        info!("Analyzing security for {} code", language);
+    /// Like a `Chat` request, but calls `on_chunk` with the response text as it
+    /// streams in. The returned content is the full, unparsed response.
+    pub async fn chat_stream(
+        &self,
+        ticket: QueueTicket,
+        message: &str,
+        mut on_chunk: impl FnMut(&str) + Send,
+    ) -> Result<AiResponse> {
+        let options = self.request_options("chat");
+        let _permit = ticket.wait().await;
+        info!("Handling streaming chat message");
+
+        let guarded = guard(self.prepare_chat_prompt(message, &options).await);
+        let content = self.send_stream(guarded.prompt, &options, &mut on_chunk).await?;
 
-        // Add to chat history
         {
             let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.create_default_session_if_needed();
-            chat_manager.add_message_to_active(ChatMessage::user(
-                format!("Analyze security of this {} code:\n\n```{}\n{}\n```", language, language, code)
-            ));
+            chat_manager.add_message_to_active(ChatMessage::assistant(content.clone()));
         }
 
-        let response = self.gemini_client.analyze_security(code, language).await?;
+        Ok(AiResponse {
+            content,
+            suggestions: vec![],
+            code_snippets: vec![],
+            confidence: 0.8,
+            injection_signals: guarded.signals.iter().map(|signal| signal.to_string()).collect(),
+        })
+    }
+
+    /// Answers `message`, letting the model call read-only tools first.
+    /// `execute` runs each tool call; `on_step` reports it once done.
+    pub async fn chat_with_tools<E, EF>(
+        &self,
+        ticket: QueueTicket,
+        message: &str,
+        execute: E,
+        on_step: impl FnMut(&ToolStep),
+    ) -> Result<AiResponse>
+    where
+        E: FnMut(ToolCall) -> EF,
+        EF: Future<Output = ToolStep>,
+    {
+        let options = self.request_options("chat");
+        let _permit = ticket.wait().await;
+        info!("Handling chat message with tools");
+
+        let prompt = self.prepare_chat_prompt(message, &options).await;
+        // Each step's prompt carries the tool results so far
+        let mut signals = Vec::new();
+        let content = tools::run_tool_loop(
+            prompt,
+            self.config.max_tool_steps,
+            |prompt| {
+                let guarded = guard(prompt);
+                add_signals(&mut signals, &guarded.signals);
+                self.send(guarded.prompt, &options)
+            },
+            execute,
+            on_step,
+        )
+        .await?;
 
-        // Add response to chat history
         {
             let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.add_message_to_active(ChatMessage::assistant(
-                response.content.clone()
-            ));
+            chat_manager.add_message_to_active(ChatMessage::assistant(content.clone()));
         }
 
-        Ok(response)
+        Ok(AiResponse {
+            content,
+            suggestions: vec![],
+            code_snippets: vec![],
+            confidence: 0.8,
+            injection_signals: signals,
+        })
     }
 
-    async fn handle_chat_message(&self, message: &str) -> Result<AiResponse> {
-        info!("Handling chat message");
-
+    /// Records `message` in the active chat session and builds the prompt for
+    /// it, including recent conversation context. Code blocks in the user's
+    /// messages, where the app puts output, files and findings, are untrusted.
+    async fn prepare_chat_prompt(&self, message: &str, options: &AiRequestOptions) -> String {
         // Add user message to chat history
         {
             let mut chat_manager = self.chat_manager.write().await;
@@ -196,9 +469,16 @@ impl AiAgent {
         let context = {
             let chat_manager = self.chat_manager.read().await;
             if let Some(session) = chat_manager.get_active_session() {
-                session.get_context_for_ai(10) // Get last 10 messages for context
+                session.get_context_for_ai(CHAT_CONTEXT_MESSAGES)
                     .into_iter()
-                    .map(|msg| format!("{:?}: {}", msg.role, msg.content))
+                    .map(|msg| match msg.role {
+                        MessageRole::User => format!(
+                            "{:?}: {}",
+                            msg.role,
+                            injection::untrusted_fenced_blocks(&msg.content, CHAT_BLOCK_LABEL)
+                        ),
+                        _ => format!("{:?}: {}", msg.role, msg.content),
+                    })
                     .collect::<Vec<_>>()
                     .join("\n")
             } else {
@@ -207,26 +487,126 @@ impl AiAgent {
         };
 
         // Create prompt with context
-        let prompt = if context.is_empty() {
-            format!("{}\n\nUser: {}", self.config.system_prompt, message)
+        let message = injection::untrusted_fenced_blocks(message, CHAT_BLOCK_LABEL);
+        if context.is_empty() {
+            format!("{}\n\nUser: {}", self.system_prompt(options), message)
         } else {
             format!(
                 "{}\n\nConversation history:\n{}\n\nUser: {}",
-                self.config.system_prompt, context, message
+                self.system_prompt(options), context, message
             )
-        };
+        }
+    }
 
-        let response = self.gemini_client.generate_response(prompt).await?;
+    /// Turns a sequence of commands into a script. Without an API key this
+    /// falls back to `script::fallback_script`.
+    async fn generate_script(
+        &self,
+        commands: &[ScriptCommand],
+        shell: &str,
+        options: &AiRequestOptions,
+    ) -> Result<AiResponse> {
+        let shell = script::script_shell(shell);
+        info!("Generating {} script from {} commands", shell, commands.len());
+
+        if self.config.api_key.is_empty() {
+            let code = script::fallback_script(commands, &shell);
+            return Ok(AiResponse {
+                content: "No AI provider is configured, so the commands were combined as-is.".to_string(),
+                suggestions: vec![],
+                code_snippets: vec![CodeSnippet::new(shell, code, "Generated script".to_string())],
+                confidence: 1.0,
+                injection_signals: Vec::new(),
+            });
+        }
+
+        let mut response = self
+            .generate_response(script::script_prompt(commands, &shell), options)
+            .await?;
+
+        if response.code_snippets.is_empty() {
+            let code = std::mem::take(&mut response.content);
+            response.code_snippets.push(CodeSnippet::new(shell, code, "Generated script".to_string()));
+        }
+
+        Ok(response)
+    }
+
+    /// Writes a commit message for a staged diff. Like every prompt, the diff
+    /// has its secrets redacted before it is sent.
+    async fn generate_commit_message(
+        &self,
+        diff: &str,
+        convention: CommitConvention,
+        options: &AiRequestOptions,
+    ) -> Result<AiResponse> {
+        info!("Generating {:?} commit message for a {} byte diff", convention, diff.len());
+
+        let guarded = guard(commit_message::commit_message_prompt(diff, convention));
+        let text = self.send(guarded.prompt, options).await?;
+
+        Ok(AiResponse {
+            content: commit_message::clean_commit_message(&text),
+            suggestions: vec![],
+            code_snippets: vec![],
+            confidence: 0.8,
+            injection_signals: guarded.signals.iter().map(|signal| signal.to_string()).collect(),
+        })
+    }
+
+    /// Summarizes a terminal session into a markdown writeup, reporting
+    /// progress as chunks of a long session are summarized.
+    pub async fn summarize_session(
+        &self,
+        blocks: &[SessionBlockSummary],
+        on_progress: impl FnMut(SummaryProgress),
+    ) -> Result<AiResponse> {
+        let options = self.request_options("summarize_session");
+        let _permit = self.queue.enqueue().wait().await;
+        self.summarize_blocks(blocks, &options, on_progress).await
+    }
+
+    async fn summarize_blocks(
+        &self,
+        blocks: &[SessionBlockSummary],
+        options: &AiRequestOptions,
+        on_progress: impl FnMut(SummaryProgress),
+    ) -> Result<AiResponse> {
+        info!("Summarizing session of {} blocks", blocks.len());
 
-        // Add response to chat history
         {
             let mut chat_manager = self.chat_manager.write().await;
-            chat_manager.add_message_to_active(ChatMessage::assistant(
-                response.content.clone()
+            chat_manager.create_default_session_if_needed();
+            chat_manager.add_message_to_active(ChatMessage::user(
+                "Summarize this terminal session".to_string()
             ));
         }
 
-        Ok(response)
+        let mut signals = Vec::new();
+        let summary = session_summary::summarize_session(
+            blocks,
+            session_summary::DEFAULT_CONTEXT_BUDGET,
+            |prompt| {
+                let guarded = guard(prompt);
+                add_signals(&mut signals, &guarded.signals);
+                self.send(guarded.prompt, options)
+            },
+            on_progress,
+        )
+        .await?;
+
+        {
+            let mut chat_manager = self.chat_manager.write().await;
+            chat_manager.add_message_to_active(ChatMessage::assistant(summary.clone()));
+        }
+
+        Ok(AiResponse {
+            content: summary,
+            suggestions: vec![],
+            code_snippets: vec![],
+            confidence: 0.8,
+            injection_signals: signals,
+        })
     }
 
     pub async fn create_chat_session(&self, title: String) -> uuid::Uuid {
@@ -274,9 +654,37 @@ impl AiAgent {
         chat_manager.get_active_session().map(|s| s.export_to_markdown())
     }
 
+    pub async fn export_chat_to_json(&self) -> Option<Result<String>> {
+        let chat_manager = self.chat_manager.read().await;
+        chat_manager.get_active_session().map(|s| s.to_json())
+    }
+
+    /// Loads a conversation exported with `export_chat_to_json` as a new,
+    /// active session, returning its messages.
+    pub async fn import_chat_from_json(&self, json: &str) -> Result<Vec<ChatMessage>> {
+        let session = ChatSession::from_json(json)?;
+        let messages = session.messages.iter().cloned().collect();
+        info!("Importing conversation '{}' ({} messages)", session.title, session.messages.len());
+        self.chat_manager.write().await.import_session(session);
+        Ok(messages)
+    }
+
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        self.provider.list_models().await
+    }
+
+    /// Switches the model used by later requests.
+    pub fn set_model(&mut self, model: &str) {
+        let mut config = self.config.clone();
+        config.model = model.to_string();
+        self.update_config(config);
+    }
+
     pub fn update_config(&mut self, config: AiConfig) {
+        self.cache.reconfigure(config.cache.clone());
+        self.redactor = Redactor::new(&config.redaction, self.redactions.clone());
         self.config = config.clone();
-        self.gemini_client.update_config(config);
+        self.provider.update_config(config);
     }
 
     pub fn get_config(&self) -> &AiConfig {
@@ -291,12 +699,13 @@ impl AiAgent {
             recent_commands.join(", ")
         );
 
+        let options = self.request_options("suggest_commands");
         let prompt = format!(
             "{}\n\nBased on this context: {}\n\nSuggest 5 useful commands the user might want to run next. Return only the commands, one per line.",
-            self.config.system_prompt, context
+            self.system_prompt(&options), context
         );
 
-        match self.gemini_client.generate_response(prompt).await {
+        match self.generate_response(prompt, &options).await {
             Ok(response) => {
                 let suggestions = response.content
                     .lines()
@@ -318,3 +727,29 @@ impl AiAgent {
         }
     }
 }
+
+/// `prompt` as it's sent: every request path goes through here.
+fn guard(prompt: String) -> GuardedPrompt {
+    let guarded = injection::guard_prompt(prompt);
+    if !guarded.signals.is_empty() {
+        warn!("Possible prompt injection in included content: {}", guarded.signals.join(", "));
+    }
+    guarded
+}
+
+/// Adds the `signals` not in `found` yet.
+fn add_signals(found: &mut Vec<String>, signals: &[&str]) {
+    for signal in signals {
+        if !found.iter().any(|known| known == signal) {
+            found.push(signal.to_string());
+        }
+    }
+}
+
+fn default_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .and_then(|shell| shell.rsplit('/').next().map(str::to_string))
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "powershell" } else { "sh" }.to_string())
+}
@@ -0,0 +1,130 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The environment variable read when no key is configured, and when loading
+/// it from a keyring or secrets file fails.
+pub const API_KEY_ENV_VAR: &str = "GEMINI_API_KEY";
+
+/// Where the API key comes from, e.g.
+///
+/// ```toml
+/// [ai]
+/// api_key_source = { type = "keyring", service = "antraft", account = "gemini" }
+/// # or
+/// api_key_source = { type = "file", path = "~/.config/antraft/gemini.key" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ApiKeySource {
+    /// `api_key` in the config, or the environment variable
+    #[default]
+    Config,
+    /// The OS keyring: the Secret Service on Linux, the login keychain on
+    /// macOS and the Credential Manager on Windows
+    Keyring {
+        #[serde(default = "default_service")]
+        service: String,
+        #[serde(default = "default_account")]
+        account: String,
+    },
+    /// A file holding just the key, readable only by its owner
+    File { path: PathBuf },
+}
+
+fn default_service() -> String {
+    "antraft".to_string()
+}
+
+fn default_account() -> String {
+    "gemini".to_string()
+}
+
+impl ApiKeySource {
+    pub fn is_config(&self) -> bool {
+        matches!(self, ApiKeySource::Config)
+    }
+
+    /// Reads the key, or `None` for `Config`, whose key is already in place.
+    pub fn load(&self) -> Result<Option<String>> {
+        self.load_from(&OsKeyring)
+    }
+
+    /// `load`, reading keyring entries from `keyring`.
+    pub fn load_from(&self, keyring: &dyn Keyring) -> Result<Option<String>> {
+        let key = match self {
+            ApiKeySource::Config => return Ok(None),
+            ApiKeySource::Keyring { service, account } => keyring.read(service, account)?,
+            ApiKeySource::File { path } => read_secrets_file(&expand_home(path))?,
+        };
+        let key = key.trim().to_string();
+        if key.is_empty() {
+            bail!("{} is empty", self);
+        }
+        Ok(Some(key))
+    }
+}
+
+impl fmt::Display for ApiKeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiKeySource::Config => write!(f, "the config"),
+            ApiKeySource::Keyring { service, account } => {
+                write!(f, "the keyring entry for service \"{}\", account \"{}\"", service, account)
+            }
+            ApiKeySource::File { path } => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Reads a secrets file, refusing one that other users can read.
+fn read_secrets_file(path: &Path) -> Result<String> {
+    let metadata = std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            bail!(
+                "{} can be read by other users (mode {:o}); run `chmod 600 {}`",
+                path.display(),
+                mode,
+                path.display()
+            );
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Where `ApiKeySource::Keyring` entries are read from.
+pub trait Keyring {
+    /// The secret stored for `service` and `account`. Errors must not
+    /// include it.
+    fn read(&self, service: &str, account: &str) -> Result<String>;
+}
+
+/// The OS keyring, through the `keyring` crate.
+pub struct OsKeyring;
+
+impl Keyring for OsKeyring {
+    fn read(&self, service: &str, account: &str) -> Result<String> {
+        let entry = keyring::Entry::new(service, account).context("Failed to open the keyring")?;
+        entry.get_password().map_err(|e| match e {
+            keyring::Error::NoEntry => {
+                anyhow!("No keyring entry for service \"{}\", account \"{}\"", service, account)
+            }
+            keyring::Error::BadEncoding(_) => anyhow!("The keyring entry is not valid UTF-8"),
+            e => anyhow!("Failed to read the keyring: {}", e),
+        })
+    }
+}
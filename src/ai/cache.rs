@@ -0,0 +1,174 @@
+use super::{AiRequest, AiResponse};
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseCacheConfig {
+    /// Answer repeated requests from earlier responses instead of the API
+    pub enabled: bool,
+    /// Responses kept; the least recently used are dropped first
+    pub max_entries: usize,
+    /// Only requests at or below this temperature are cached, since hotter
+    /// ones are meant to vary
+    pub max_temperature: f32,
+    /// Keep cached responses across restarts, in `ai_cache.json` in the
+    /// data directory
+    pub persist: bool,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 200,
+            max_temperature: 0.3,
+            persist: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    response: AiResponse,
+}
+
+/// Responses to deterministic requests, keyed by `cache_key`.
+pub struct ResponseCache {
+    config: ResponseCacheConfig,
+    path: PathBuf,
+    /// Least recently used first
+    entries: Mutex<Vec<CacheEntry>>,
+}
+
+impl ResponseCache {
+    /// A cache saved to `path`, starting from what's there when the config
+    /// persists it. An unreadable file only costs the saved responses.
+    pub fn load(config: ResponseCacheConfig, path: &Path) -> Self {
+        let mut entries = Vec::new();
+        if config.persist && path.exists() {
+            match read_entries(path) {
+                Ok(saved) => entries = saved,
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+        trim(&mut entries, config.max_entries);
+        Self {
+            config,
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn config(&self) -> &ResponseCacheConfig {
+        &self.config
+    }
+
+    /// Applies a changed config, dropping responses over the new limit.
+    pub fn reconfigure(&mut self, config: ResponseCacheConfig) {
+        self.config = config;
+        trim(self.entries.get_mut().unwrap(), self.config.max_entries);
+    }
+
+    /// The key `request` is cached under when sent to `model` at
+    /// `temperature`, or `None` when its response shouldn't be reused:
+    /// caching is off, the temperature is too high, or it's a chat message,
+    /// whose answer depends on the conversation before it.
+    pub fn cache_key(&self, request: &AiRequest, model: &str, temperature: f32) -> Option<String> {
+        if !self.config.enabled || temperature > self.config.max_temperature {
+            return None;
+        }
+        let inputs = request_inputs(request)?;
+        let key = serde_json::json!([request.task(), model, format!("{:.2}", temperature), inputs]);
+        Some(key.to_string())
+    }
+
+    pub fn get(&self, key: &str) -> Option<AiResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries.iter().position(|entry| entry.key == key)?;
+        let entry = entries.remove(index);
+        let response = entry.response.clone();
+        entries.push(entry);
+        Some(response)
+    }
+
+    /// Stores `response` under `key`, and saves the cache when it persists.
+    pub fn insert(&self, key: String, response: AiResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.key != key);
+        entries.push(CacheEntry { key, response });
+        trim(&mut entries, self.config.max_entries);
+        if self.config.persist {
+            if let Err(e) = self.save(&entries) {
+                warn!("{:#}", e);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every response, on disk too.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clear();
+        if self.path.exists() {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                warn!("Failed to remove AI cache {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    fn save(&self, entries: &[CacheEntry]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(entries)?)
+            .with_context(|| format!("Failed to write AI cache {}", self.path.display()))
+    }
+}
+
+/// Drops the least recently used entries over `max_entries`.
+fn trim(entries: &mut Vec<CacheEntry>, max_entries: usize) {
+    let excess = entries.len().saturating_sub(max_entries);
+    entries.drain(..excess);
+}
+
+fn read_entries(path: &Path) -> Result<Vec<CacheEntry>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid AI cache {}", path.display()))
+}
+
+/// What a request's answer depends on besides the model and temperature,
+/// normalized so trivially different requests share a response. Commands
+/// and descriptions have their whitespace collapsed; code and diffs are
+/// only trimmed, since their whitespace can matter.
+fn request_inputs(request: &AiRequest) -> Option<serde_json::Value> {
+    let collapse = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let inputs = match request {
+        AiRequest::ExplainCommand { command } => serde_json::json!([collapse(command)]),
+        AiRequest::GenerateCommand { description } => serde_json::json!([collapse(description)]),
+        AiRequest::FixError { error, context } => {
+            serde_json::json!([error.trim(), context.as_deref().map(str::trim)])
+        }
+        AiRequest::CodeReview { code, language } => serde_json::json!([code.trim(), language]),
+        AiRequest::SecurityAnalysis { code, language } => serde_json::json!([code.trim(), language]),
+        AiRequest::GenerateScript { commands, shell } => serde_json::json!([commands, shell]),
+        AiRequest::CommitMessage { diff, convention } => serde_json::json!([diff.trim(), convention]),
+        AiRequest::Chat { .. } | AiRequest::SummarizeSession { .. } => return None,
+    };
+    Some(inputs)
+}
+
+pub fn default_cache_path() -> PathBuf {
+    crate::config::data_dir().join("ai_cache.json")
+}
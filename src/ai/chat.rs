@@ -1,8 +1,17 @@
+use anyhow::{anyhow, bail, Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::VecDeque;
 use uuid::Uuid;
 
+/// Version of the conversation files written by `ChatSession::to_json`.
+///
+/// 1. Unversioned: no `schema_version`, and messages without `id` or
+///    `metadata`.
+/// 2. Adds `schema_version`, message `id`s and `metadata`.
+pub const CHAT_SCHEMA_VERSION: u64 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageRole {
     User,
@@ -52,6 +61,17 @@ impl ChatMessage {
     }
 }
 
+/// The on-disk shape of an exported conversation.
+#[derive(Serialize, Deserialize)]
+struct ChatSessionFile {
+    schema_version: u64,
+    id: Uuid,
+    title: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    messages: Vec<ChatMessage>,
+}
+
 #[derive(Debug)]
 pub struct ChatSession {
     pub id: Uuid,
@@ -138,6 +158,54 @@ impl ChatSession {
         markdown
     }
 
+    /// The session as a versioned JSON document that `from_json` reads back.
+    pub fn to_json(&self) -> Result<String> {
+        let file = ChatSessionFile {
+            schema_version: CHAT_SCHEMA_VERSION,
+            id: self.id,
+            title: self.title.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            messages: self.messages.iter().cloned().collect(),
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+
+    /// Reads a conversation written by `to_json`, migrating older versions.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut value: Value = serde_json::from_str(json).context("Not a JSON file")?;
+        if !value.is_object() {
+            bail!("Not a conversation file");
+        }
+
+        let version = match value.get("schema_version") {
+            None => 1,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| anyhow!("Invalid schema_version: {}", version))?,
+        };
+        if version > CHAT_SCHEMA_VERSION {
+            bail!(
+                "This conversation was exported by a newer version of ANTRAFT (schema {}, this version reads up to {})",
+                version,
+                CHAT_SCHEMA_VERSION
+            );
+        }
+        if version < 2 {
+            migrate_v1(&mut value)?;
+        }
+
+        let file: ChatSessionFile =
+            serde_json::from_value(value).context("Not a valid conversation file")?;
+        let mut session = Self::new(file.title);
+        session.id = file.id;
+        session.created_at = file.created_at;
+        session.updated_at = file.updated_at;
+        session.max_messages = session.max_messages.max(file.messages.len());
+        session.messages = file.messages.into();
+        Ok(session)
+    }
+
     pub fn search_messages(&self, query: &str) -> Vec<&ChatMessage> {
         self.messages
             .iter()
@@ -164,6 +232,29 @@ impl ChatSession {
     }
 }
 
+/// Gives version 1 messages ids, and the session one if it has none.
+fn migrate_v1(value: &mut Value) -> Result<()> {
+    let object = value.as_object_mut().ok_or_else(|| anyhow!("Not a conversation file"))?;
+    object
+        .entry("id")
+        .or_insert_with(|| Value::String(Uuid::new_v4().to_string()));
+    let messages = object
+        .get_mut("messages")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| anyhow!("Conversation file has no messages"))?;
+    for message in messages {
+        let message = message
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Conversation file has an invalid message"))?;
+        message
+            .entry("id")
+            .or_insert_with(|| Value::String(Uuid::new_v4().to_string()));
+        message.entry("metadata").or_insert(Value::Null);
+    }
+    object.insert("schema_version".to_string(), Value::from(2));
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct ChatSessionManager {
     sessions: Vec<ChatSession>,
@@ -171,6 +262,12 @@ pub struct ChatSessionManager {
     max_sessions: usize,
 }
 
+impl Default for ChatSessionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ChatSessionManager {
     pub fn new() -> Self {
         Self {
@@ -239,6 +336,23 @@ impl ChatSessionManager {
         }
     }
 
+    /// Adds an imported session and makes it active. A session whose id is
+    /// already taken gets a new one.
+    pub fn import_session(&mut self, mut session: ChatSession) -> Uuid {
+        if self.sessions.iter().any(|s| s.id == session.id) {
+            session.id = Uuid::new_v4();
+        }
+        let session_id = session.id;
+
+        self.sessions.push(session);
+        while self.sessions.len() > self.max_sessions {
+            self.sessions.remove(0);
+        }
+        self.active_session_id = Some(session_id);
+
+        session_id
+    }
+
     pub fn get_all_sessions(&self) -> &[ChatSession] {
         &self.sessions
     }
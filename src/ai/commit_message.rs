@@ -0,0 +1,85 @@
+use super::injection::untrusted;
+use super::script::{script_shell, shell_quote};
+use serde::{Deserialize, Serialize};
+
+/// Style of generated commit messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitConvention {
+    /// `type(scope): summary`, as in conventionalcommits.org
+    #[default]
+    Conventional,
+    /// A plain imperative summary line
+    Plain,
+}
+
+impl CommitConvention {
+    pub const ALL: [CommitConvention; 2] = [CommitConvention::Conventional, CommitConvention::Plain];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CommitConvention::Conventional => "Conventional Commits",
+            CommitConvention::Plain => "Plain",
+        }
+    }
+}
+
+pub fn commit_message_prompt(diff: &str, convention: CommitConvention) -> String {
+    let style = match convention {
+        CommitConvention::Conventional => {
+            "Follow the Conventional Commits format: the summary line is \
+             `type(optional scope): description`, where type is one of feat, fix, \
+             docs, style, refactor, perf, test, build, ci or chore."
+        }
+        CommitConvention::Plain => {
+            "Write the summary line as a plain, capitalized, imperative sentence \
+             without a trailing period."
+        }
+    };
+
+    format!(
+        "Write a git commit message for the staged changes below. {} Keep the \
+         summary line under 72 characters. If the change needs explaining, add a \
+         blank line and a short body wrapped at 72 characters describing what \
+         changed and why. Reply with the commit message only, without code fences \
+         or commentary.\n\n{}",
+        style,
+        untrusted("staged diff", diff)
+    )
+}
+
+/// Strips code fences and surrounding whitespace a model may add around the
+/// message.
+pub fn clean_commit_message(text: &str) -> String {
+    let trimmed = text.trim();
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|inner| inner.split_once('\n').map_or(inner, |(_, body)| body))
+        .unwrap_or(trimmed);
+    unfenced.trim().to_string()
+}
+
+/// Splits a commit message into its summary line and body.
+pub fn split_commit_message(message: &str) -> (String, String) {
+    let message = message.trim();
+    match message.split_once('\n') {
+        Some((subject, body)) => (subject.trim().to_string(), body.trim().to_string()),
+        None => (message.to_string(), String::new()),
+    }
+}
+
+/// The `git commit` command line committing `message` in the configured
+/// `shell`, `None` without a summary line.
+pub fn commit_command(message: &str, shell: &str) -> Option<String> {
+    let (subject, body) = split_commit_message(message);
+    if subject.is_empty() {
+        return None;
+    }
+    let shell = script_shell(shell);
+    let mut command = format!("git commit -m {}", shell_quote(&subject, &shell));
+    if !body.is_empty() {
+        command.push_str(&format!(" -m {}", shell_quote(&body, &shell)));
+    }
+    Some(command)
+}
@@ -1,14 +1,71 @@
-use super::{AiConfig, AiResponse, CodeSnippet};
+use super::provider::{AiProvider, ProviderFuture};
+use super::{AiConfig, AiRequestOptions};
 use anyhow::{anyhow, Result};
 use log::{debug, error};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A health check taking longer than this counts as a failure.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The key goes in a header rather than the URL, which ends up in logs and
+/// error messages.
+const API_KEY_HEADER: &str = "x-goog-api-key";
 
 pub struct GeminiClient {
     client: Client,
     config: AiConfig,
     base_url: String,
+    /// Result of the last successful `list_models` for the current key
+    models: Mutex<Option<Vec<ModelInfo>>>,
+}
+
+/// A model the provider can generate text with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    /// Id used in requests and in `AiConfig::model`, e.g. `gemini-2.0-flash`
+    pub name: String,
+    pub display_name: String,
+    pub input_token_limit: Option<u32>,
+    pub output_token_limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelList {
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelEntry {
+    name: String,
+    #[serde(default)]
+    display_name: String,
+    input_token_limit: Option<u32>,
+    output_token_limit: Option<u32>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
+impl ModelEntry {
+    fn into_model_info(self) -> Option<ModelInfo> {
+        if !self.supported_generation_methods.iter().any(|m| m == "generateContent") {
+            return None;
+        }
+        let name = self.name.strip_prefix("models/").unwrap_or(&self.name).to_string();
+        Some(ModelInfo {
+            display_name: if self.display_name.is_empty() { name.clone() } else { self.display_name },
+            name,
+            input_token_limit: self.input_token_limit,
+            output_token_limit: self.output_token_limit,
+        })
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -57,6 +114,23 @@ struct ResponsePart {
     text: String,
 }
 
+/// Text carried by one line of a `streamGenerateContent` SSE response.
+fn parse_stream_line(line: &str) -> Option<String> {
+    let data = line.strip_prefix("data:")?.trim();
+    let value: Value = serde_json::from_str(data).ok()?;
+    let parts = value
+        .get("candidates")?
+        .get(0)?
+        .get("content")?
+        .get("parts")?
+        .as_array()?;
+    let text: String = parts
+        .iter()
+        .filter_map(|part| part.get("text").and_then(Value::as_str))
+        .collect();
+    (!text.is_empty()).then_some(text)
+}
+
 impl GeminiClient {
     pub fn new(config: AiConfig) -> Self {
         let client = Client::new();
@@ -66,34 +140,95 @@ impl GeminiClient {
             client,
             config,
             base_url,
+            models: Mutex::new(None),
         }
     }
 
-    pub async fn generate_response(&self, prompt: String) -> Result<AiResponse> {
+    /// The models that support `generateContent`, fetched once per API key.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        if let Some(models) = self.models.lock().unwrap().as_ref() {
+            return Ok(models.clone());
+        }
         if self.config.api_key.is_empty() {
             return Err(anyhow!("Gemini API key not configured"));
         }
 
-        let url = format!(
-            "{}/{}:generateContent?key={}",
-            self.base_url, self.config.model, self.config.api_key
-        );
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = vec![("pageSize", "1000")];
+            if let Some(token) = page_token.as_deref() {
+                query.push(("pageToken", token));
+            }
 
-        let request_body = GeminiRequest {
-            contents: vec![Content {
-                parts: vec![Part { text: prompt }],
-            }],
-            generation_config: GenerationConfig {
-                temperature: self.config.temperature,
-                max_output_tokens: self.config.max_tokens,
-            },
-        };
+            let response = self
+                .client
+                .get(&self.base_url)
+                .header(API_KEY_HEADER, &self.config.api_key)
+                .query(&query)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                error!("Gemini API error listing models: {}", error_text);
+                return Err(anyhow!("Gemini API error: {}", error_text));
+            }
+
+            let page: ModelList = response.json().await?;
+            models.extend(page.models.into_iter().filter_map(ModelEntry::into_model_info));
+            match page.next_page_token.filter(|token| !token.is_empty()) {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        *self.models.lock().unwrap() = Some(models.clone());
+        Ok(models)
+    }
+
+    /// Checks that the API key works and the configured model exists, by
+    /// fetching the model's metadata. Costs no tokens.
+    pub async fn check_health(&self) -> Result<()> {
+        if self.config.api_key.is_empty() {
+            return Err(anyhow!("Gemini API key not configured"));
+        }
+
+        let url = format!("{}/{}", self.base_url, self.config.model.trim_start_matches("models/"));
+        let response = self
+            .client
+            .get(&url)
+            .header(API_KEY_HEADER, &self.config.api_key)
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            let message = serde_json::from_str::<Value>(&error_text)
+                .ok()
+                .and_then(|error| error.pointer("/error/message")?.as_str().map(str::to_string))
+                .unwrap_or(error_text);
+            return Err(anyhow!("Gemini API error ({}): {}", status, message));
+        }
+        Ok(())
+    }
+
+    /// Sends `prompt` and returns the raw response text, code blocks included.
+    pub async fn generate_text(&self, prompt: String, options: &AiRequestOptions) -> Result<String> {
+        if self.config.api_key.is_empty() {
+            return Err(anyhow!("Gemini API key not configured"));
+        }
+
+        let url = format!("{}/{}:generateContent", self.base_url, self.config.model);
+
+        let request_body = self.build_request(&prompt, options);
 
         debug!("Sending request to Gemini API: {}", url);
 
         let response = self
             .client
             .post(&url)
+            .header(API_KEY_HEADER, &self.config.api_key)
             .json(&request_body)
             .send()
             .await?;
@@ -115,101 +250,106 @@ impl GeminiClient {
             return Err(anyhow!("Empty response from Gemini API"));
         }
 
-        let content = &candidate.content.parts[0].text;
-        let parsed_response = self.parse_response(content);
-
-        Ok(parsed_response)
+        Ok(candidate.content.parts[0].text.clone())
     }
 
-    fn parse_response(&self, content: &str) -> AiResponse {
-        let mut suggestions = Vec::new();
-        let mut code_snippets = Vec::new();
-        let mut clean_content = content.to_string();
-
-        // Extract code blocks
-        let code_block_regex = regex::Regex::new(r"```(\w+)?\n(.*?)\n```").unwrap();
-        for cap in code_block_regex.captures_iter(content) {
-            let language = cap.get(1).map_or("text".to_string(), |m| m.as_str().to_string());
-            let code = cap.get(2).map_or("", |m| m.as_str()).to_string();
-            
-            if !code.trim().is_empty() {
-                code_snippets.push(CodeSnippet::new(
-                    language,
-                    code,
-                    "Generated code snippet".to_string(),
-                ));
-            }
+    /// Like `generate_text`, but streams the response, calling `on_chunk` with
+    /// each piece of text as it arrives. Returns the full text.
+    pub async fn generate_text_stream(
+        &self,
+        prompt: String,
+        options: &AiRequestOptions,
+        on_chunk: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String> {
+        if self.config.api_key.is_empty() {
+            return Err(anyhow!("Gemini API key not configured"));
         }
 
-        // Remove code blocks from content
-        clean_content = code_block_regex.replace_all(&clean_content, "").to_string();
+        let url = format!("{}/{}:streamGenerateContent?alt=sse", self.base_url, self.config.model);
+
+        let request_body = self.build_request(&prompt, options);
+        let mut response = self
+            .client
+            .post(&url)
+            .header(API_KEY_HEADER, &self.config.api_key)
+            .json(&request_body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("Gemini API error: {}", error_text);
+            return Err(anyhow!("Gemini API error: {}", error_text));
+        }
 
-        // Extract suggestions (lines starting with "Suggestion:" or "Try:")
-        let suggestion_regex = regex::Regex::new(r"(?i)(?:suggestion|try):\s*(.+)").unwrap();
-        for cap in suggestion_regex.captures_iter(&clean_content) {
-            if let Some(suggestion) = cap.get(1) {
-                suggestions.push(suggestion.as_str().trim().to_string());
+        // Server-sent events: one `data: {json}` line per chunk
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut text = String::new();
+        while let Some(bytes) = response.chunk().await? {
+            buffer.extend_from_slice(&bytes);
+            while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line);
+                if let Some(chunk) = parse_stream_line(line.trim()) {
+                    text.push_str(&chunk);
+                    on_chunk(&chunk);
+                }
             }
         }
+        if let Some(chunk) = parse_stream_line(String::from_utf8_lossy(&buffer).trim()) {
+            text.push_str(&chunk);
+            on_chunk(&chunk);
+        }
 
-        AiResponse {
-            content: clean_content.trim().to_string(),
-            suggestions,
-            code_snippets,
-            confidence: 0.8, // Default confidence
+        if text.is_empty() {
+            return Err(anyhow!("Empty response from Gemini API"));
         }
+        Ok(text)
     }
 
-    pub async fn explain_command(&self, command: &str) -> Result<AiResponse> {
-        let prompt = format!(
-            "{}\n\nExplain this command: `{}`\n\nProvide:\n1. What it does\n2. Key options/flags\n3. Example usage\n4. Potential risks or considerations",
-            self.config.system_prompt, command
-        );
-
-        self.generate_response(prompt).await
+    /// The JSON body sent for `prompt`.
+    pub fn build_request(&self, prompt: &str, options: &AiRequestOptions) -> Value {
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part {
+                    text: prompt.to_string(),
+                }],
+            }],
+            generation_config: GenerationConfig {
+                temperature: options.temperature.unwrap_or(self.config.temperature),
+                max_output_tokens: options.max_tokens.unwrap_or(self.config.max_tokens),
+            },
+        };
+        serde_json::to_value(request).expect("Gemini requests serialize to JSON")
     }
 
-    pub async fn generate_command(&self, description: &str) -> Result<AiResponse> {
-        let prompt = format!(
-            "{}\n\nGenerate a command to: {}\n\nProvide:\n1. The command with explanation\n2. Alternative approaches if applicable\n3. Safety considerations\n\nFormat code in markdown code blocks.",
-            self.config.system_prompt, description
-        );
-
-        self.generate_response(prompt).await
+    pub fn update_config(&mut self, config: AiConfig) {
+        if config.api_key != self.config.api_key {
+            *self.models.lock().unwrap() = None;
+        }
+        self.config = config;
     }
+}
 
-    pub async fn fix_error(&self, error: &str, context: Option<&str>) -> Result<AiResponse> {
-        let context_str = context.map(|c| format!("\n\nContext: {}", c)).unwrap_or_default();
-        
-        let prompt = format!(
-            "{}\n\nFix this error: {}{}\n\nProvide:\n1. Explanation of the error\n2. Solution steps\n3. Prevention tips\n\nFormat commands in markdown code blocks.",
-            self.config.system_prompt, error, context_str
-        );
-
-        self.generate_response(prompt).await
+impl AiProvider for GeminiClient {
+    fn generate_text<'a>(&'a self, prompt: String, options: &'a AiRequestOptions) -> ProviderFuture<'a, String> {
+        Box::pin(GeminiClient::generate_text(self, prompt, options))
     }
 
-    pub async fn review_code(&self, code: &str, language: Option<&str>) -> Result<AiResponse> {
-        let language_str = language.unwrap_or("unknown");
-        
-        let prompt = format!(
-            "{}\n\nReview this {} code:\n\n```{}\n{}\n```\n\nProvide:\n1. Code quality assessment\n2. Potential issues\n3. Improvement suggestions\n4. Best practices",
-            self.config.system_prompt, language_str, language_str, code
-        );
-
-        self.generate_response(prompt).await
+    fn generate_text_stream<'a>(
+        &'a self,
+        prompt: String,
+        options: &'a AiRequestOptions,
+        on_chunk: &'a mut (dyn FnMut(&str) + Send),
+    ) -> ProviderFuture<'a, String> {
+        Box::pin(GeminiClient::generate_text_stream(self, prompt, options, on_chunk))
     }
 
-    pub async fn analyze_security(&self, code: &str, language: &str) -> Result<AiResponse> {
-        let prompt = format!(
-            "{}\n\nPerform security analysis on this {} code:\n\n```{}\n{}\n```\n\nFocus on:\n1. Security vulnerabilities\n2. Potential attack vectors\n3. Recommended fixes\n4. Security best practices\n\nBe specific and actionable.",
-            self.config.system_prompt, language, language, code
-        );
-
-        self.generate_response(prompt).await
+    fn list_models(&self) -> ProviderFuture<'_, Vec<ModelInfo>> {
+        Box::pin(GeminiClient::list_models(self))
     }
 
-    pub fn update_config(&mut self, config: AiConfig) {
-        self.config = config;
+    fn update_config(&mut self, config: AiConfig) {
+        GeminiClient::update_config(self, config)
     }
 }
@@ -0,0 +1,76 @@
+/// Whether input typed where commands are expected is a command to run or
+/// a description of one to generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputIntent {
+    Command,
+    NaturalLanguage,
+}
+
+/// Words that start questions and requests, but no common command.
+const QUESTION_WORDS: &[&str] = &[
+    "what", "how", "why", "where", "when", "who", "whats", "what's", "how's", "can", "could", "would", "should",
+    "is", "are", "does", "do", "please", "show", "list", "give", "tell", "help", "get", "delete", "remove",
+    "create", "make", "count", "check", "print", "display", "compress", "extract", "rename", "convert",
+];
+
+/// Words common in English sentences and rare as command arguments.
+const STOP_WORDS: &[&str] = &[
+    "the", "a", "an", "all", "my", "me", "to", "for", "of", "in", "on", "that", "which", "with", "from",
+    "today", "yesterday", "this", "these", "those", "are", "is", "than", "bigger", "larger", "older", "newer",
+    "every", "each", "files", "folder", "directory", "and", "by", "i", "it", "last", "modified",
+];
+
+/// Guesses what `input` is. `is_command` says whether a word is something
+/// the terminal can run: a builtin, an alias or a program on `PATH`.
+///
+/// Shell syntax (flags, pipes, redirection, variables, paths) always means
+/// a command. Otherwise a sentence-like input is taken as natural language,
+/// even when it starts with a command name, like "find all files bigger
+/// than 1GB". Short inputs starting with a command are run as typed.
+pub fn classify_input(input: &str, is_command: impl Fn(&str) -> bool) -> InputIntent {
+    let input = input.trim();
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let Some(first) = words.first() else {
+        return InputIntent::Command;
+    };
+    if has_shell_syntax(input, &words) {
+        return InputIntent::Command;
+    }
+
+    let lowercase: Vec<String> = words
+        .iter()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'').to_lowercase())
+        .collect();
+    let stop_words = lowercase.iter().filter(|word| STOP_WORDS.contains(&word.as_str())).count();
+    let is_question = input.ends_with('?');
+    let starts_like_request = QUESTION_WORDS.contains(&lowercase[0].as_str());
+    let known_command = is_command(first);
+
+    let natural = if known_command {
+        // `find`, `which` and `make` start sentences too
+        is_question || (words.len() >= 4 && stop_words >= 2)
+    } else {
+        is_question || (starts_like_request && words.len() >= 2) || (words.len() >= 3 && stop_words >= 1)
+    };
+    if natural {
+        InputIntent::NaturalLanguage
+    } else {
+        InputIntent::Command
+    }
+}
+
+fn has_shell_syntax(input: &str, words: &[&str]) -> bool {
+    const OPERATORS: &[&str] = &["|", "&&", "||", ";", ">", "<", "$(", "`", "${"];
+    OPERATORS.iter().any(|op| input.contains(op))
+        || words.iter().skip(1).any(|word| {
+            word.starts_with('-')
+                || word.starts_with('$')
+                || word.starts_with("./")
+                || word.starts_with("~/")
+                || word.contains('/')
+                || word.contains('*')
+                || word.contains('=')
+        })
+        || words[0].contains('=')
+        || words[0].contains('/')
+}
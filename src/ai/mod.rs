@@ -1,30 +1,143 @@
 pub mod agent;
+pub mod api_key;
+pub mod cache;
 pub mod chat;
+pub mod commit_message;
 pub mod gemini;
+pub mod injection;
+pub mod intent;
+pub mod offline;
+pub mod prompts;
+pub mod provider;
+pub mod queue;
+pub mod redaction;
+pub mod script;
+pub mod session_summary;
+pub mod system_prompt;
+pub mod templates;
+pub mod tools;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub use agent::AiAgent;
+pub use api_key::ApiKeySource;
 pub use chat::ChatMessage;
-pub use gemini::GeminiClient;
+pub use commit_message::CommitConvention;
+pub use gemini::{GeminiClient, ModelInfo};
+pub use provider::AiProvider;
+pub use script::ScriptCommand;
+pub use session_summary::SessionBlockSummary;
+pub use templates::{PromptTemplate, PromptTemplateStore};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AiConfig {
+    // Keys picked up from the environment are never written back to disk.
+    #[serde(skip_serializing_if = "is_env_api_key")]
     pub api_key: String,
+    /// Where `api_key` is loaded from when the config is; see `ApiKeySource`
+    #[serde(skip_serializing_if = "ApiKeySource::is_config")]
+    pub api_key_source: ApiKeySource,
     pub model: String,
     pub max_tokens: u32,
     pub temperature: f32,
+    /// May use `{os}`, `{shell}`, `{cwd}` and `{date}`, or `$os`/`${os}`; see
+    /// `system_prompt::render_system_prompt`
     pub system_prompt: String,
+    pub commit_convention: CommitConvention,
+    /// Lets the chat run read-only commands and read files to answer questions
+    pub enable_tools: bool,
+    pub max_tool_steps: usize,
+    /// AI requests allowed in flight at once; the rest wait in order
+    pub max_concurrent_requests: usize,
+    /// Per-task generation settings, keyed by task name (`generate_command`,
+    /// `security_analysis`, ...), e.g. `[ai.overrides.chat]`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<String, AiRequestOptions>,
+    /// Secrets are replaced with placeholders before anything is sent
+    pub redaction: redaction::RedactionConfig,
+    /// Reuse responses to repeated low-temperature requests
+    pub cache: cache::ResponseCacheConfig,
+    /// Treat sentences typed on the welcome screen as descriptions of a
+    /// command to generate, instead of running them
+    pub detect_natural_language: bool,
 }
 
 impl Default for AiConfig {
     fn default() -> Self {
         Self {
-            api_key: std::env::var("GEMINI_API_KEY").unwrap_or_default(),
+            api_key: std::env::var(api_key::API_KEY_ENV_VAR).unwrap_or_default(),
+            api_key_source: ApiKeySource::Config,
             model: "gemini-2.0-flash".to_string(),
             max_tokens: 2048,
             temperature: 0.7,
-            system_prompt: "You are an AI assistant integrated into ANTRAFT, a modern terminal application. You help users with command-line tasks, explain commands, suggest solutions, and provide coding assistance. Be concise but helpful.".to_string(),
+            system_prompt: system_prompt::DEFAULT_SYSTEM_PROMPT.to_string(),
+            commit_convention: CommitConvention::default(),
+            enable_tools: true,
+            max_tool_steps: tools::DEFAULT_MAX_TOOL_STEPS,
+            max_concurrent_requests: queue::DEFAULT_MAX_CONCURRENT_REQUESTS,
+            overrides: HashMap::new(),
+            redaction: redaction::RedactionConfig::default(),
+            cache: cache::ResponseCacheConfig::default(),
+            detect_natural_language: true,
+        }
+    }
+}
+
+fn is_env_api_key(key: &String) -> bool {
+    key.is_empty() || std::env::var(api_key::API_KEY_ENV_VAR).is_ok_and(|env_key| &env_key == key)
+}
+
+impl AiConfig {
+    /// Replaces `api_key` with the one from `api_key_source`. When that
+    /// fails, the environment variable is used instead and the error is
+    /// returned, so a missing keyring entry doesn't lock out a key that
+    /// still works.
+    pub fn load_api_key(&mut self) -> anyhow::Result<()> {
+        self.load_api_key_from(&api_key::OsKeyring)
+    }
+
+    /// `load_api_key`, reading keyring entries from `keyring`.
+    pub fn load_api_key_from(&mut self, keyring: &dyn api_key::Keyring) -> anyhow::Result<()> {
+        match self.api_key_source.load_from(keyring) {
+            Ok(Some(key)) => {
+                self.api_key = key;
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(e) => {
+                self.api_key = std::env::var(api_key::API_KEY_ENV_VAR).unwrap_or_default();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Generation settings for a single AI request. Unset fields fall back to
+/// the `[ai]` settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiRequestOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Replaces `system_prompt`; may use the same variables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt_override: Option<String>,
+}
+
+impl AiRequestOptions {
+    /// These options with the fields `other` sets taking precedence.
+    pub fn overridden_by(&self, other: &AiRequestOptions) -> Self {
+        Self {
+            temperature: other.temperature.or(self.temperature),
+            max_tokens: other.max_tokens.or(self.max_tokens),
+            system_prompt_override: other
+                .system_prompt_override
+                .clone()
+                .or_else(|| self.system_prompt_override.clone()),
         }
     }
 }
@@ -52,14 +165,72 @@ pub enum AiRequest {
     Chat {
         message: String,
     },
+    SummarizeSession {
+        blocks: Vec<SessionBlockSummary>,
+    },
+    GenerateScript {
+        commands: Vec<ScriptCommand>,
+        shell: String,
+    },
+    CommitMessage {
+        diff: String,
+        convention: CommitConvention,
+    },
 }
 
-#[derive(Debug, Clone)]
+impl AiRequest {
+    /// The task name used for `[ai.overrides.<task>]`.
+    pub fn task(&self) -> &'static str {
+        match self {
+            AiRequest::ExplainCommand { .. } => "explain_command",
+            AiRequest::GenerateCommand { .. } => "generate_command",
+            AiRequest::FixError { .. } => "fix_error",
+            AiRequest::CodeReview { .. } => "code_review",
+            AiRequest::SecurityAnalysis { .. } => "security_analysis",
+            AiRequest::Chat { .. } => "chat",
+            AiRequest::SummarizeSession { .. } => "summarize_session",
+            AiRequest::GenerateScript { .. } => "generate_script",
+            AiRequest::CommitMessage { .. } => "commit_message",
+        }
+    }
+
+    /// The message recorded in the chat history when this request is made,
+    /// for the requests that appear there. Chat messages and session
+    /// summaries record their own.
+    pub fn history_message(&self) -> Option<String> {
+        match self {
+            AiRequest::ExplainCommand { command } => Some(format!("Explain this command: {}", command)),
+            AiRequest::GenerateCommand { description } => Some(format!("Generate a command to: {}", description)),
+            AiRequest::FixError { error, context: Some(context) } => {
+                Some(format!("Fix this error: {}\nContext: {}", error, context))
+            }
+            AiRequest::FixError { error, context: None } => Some(format!("Fix this error: {}", error)),
+            AiRequest::CodeReview { code, language } => {
+                let language = language.as_deref().unwrap_or("unknown");
+                Some(format!("Review this {} code:\n\n```{}\n{}\n```", language, language, code))
+            }
+            AiRequest::SecurityAnalysis { code, language } => Some(format!(
+                "Analyze security of this {} code:\n\n```{}\n{}\n```",
+                language, language, code
+            )),
+            AiRequest::Chat { .. }
+            | AiRequest::SummarizeSession { .. }
+            | AiRequest::GenerateScript { .. }
+            | AiRequest::CommitMessage { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiResponse {
     pub content: String,
     pub suggestions: Vec<String>,
     pub code_snippets: Vec<CodeSnippet>,
     pub confidence: f32,
+    /// What looked like a prompt injection in the content the request
+    /// included; see `injection::detect_injection`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub injection_signals: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,203 @@
+use super::AiResponse;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// tldr-style docs for common commands, gzipped by the build script.
+static BUNDLED_DOCS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/offline-docs.md.gz"));
+
+/// Longest key tried when matching a command, e.g. `kubectl get pods`.
+const MAX_KEY_WORDS: usize = 3;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandDoc {
+    /// `binary` or `binary subcommand`
+    pub name: String,
+    pub description: String,
+    /// (what it does, command)
+    pub examples: Vec<(String, String)>,
+    /// (flag, what it does)
+    pub flags: Vec<(String, String)>,
+}
+
+impl CommandDoc {
+    fn flag(&self, flag: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .find(|(name, _)| name == flag)
+            .map(|(_, description)| description.as_str())
+    }
+}
+
+/// Explains commands from bundled docs when no AI provider is available.
+#[derive(Debug, Default)]
+pub struct OfflineExplainer {
+    docs: HashMap<String, CommandDoc>,
+}
+
+impl OfflineExplainer {
+    pub fn bundled() -> Result<Self> {
+        let mut text = String::new();
+        GzDecoder::new(BUNDLED_DOCS)
+            .read_to_string(&mut text)
+            .context("Failed to decompress bundled command docs")?;
+        Ok(Self::parse(&text))
+    }
+
+    /// Parses tldr-style pages: `# name`, a `> description`, `- example
+    /// description:` followed by a `` `command` `` line, and an extension for
+    /// flags, `` * `-f`: what it does ``.
+    pub fn parse(text: &str) -> Self {
+        let mut docs = HashMap::new();
+        let mut current: Option<CommandDoc> = None;
+        let mut pending_example: Option<String> = None;
+
+        for line in text.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("# ") {
+                if let Some(doc) = current.take() {
+                    docs.insert(doc.name.clone(), doc);
+                }
+                current = Some(CommandDoc {
+                    name: name.trim().to_string(),
+                    ..Default::default()
+                });
+                pending_example = None;
+                continue;
+            }
+            let Some(doc) = current.as_mut() else {
+                continue;
+            };
+
+            if let Some(description) = line.strip_prefix("> ") {
+                if !doc.description.is_empty() {
+                    doc.description.push(' ');
+                }
+                doc.description.push_str(description.trim());
+            } else if let Some(example) = line.strip_prefix("- ") {
+                pending_example = Some(example.trim().trim_end_matches(':').to_string());
+            } else if let Some(flag_line) = line.strip_prefix("* `") {
+                if let Some((flag, description)) = flag_line.split_once("`:") {
+                    doc.flags.push((flag.to_string(), description.trim().to_string()));
+                }
+            } else if line.starts_with('`') && line.ends_with('`') && line.len() > 1 {
+                if let Some(description) = pending_example.take() {
+                    doc.examples.push((description, line[1..line.len() - 1].to_string()));
+                }
+            }
+        }
+        if let Some(doc) = current {
+            docs.insert(doc.name.clone(), doc);
+        }
+
+        Self { docs }
+    }
+
+    /// The doc for the longest `binary subcommand…` key matching `command`.
+    pub fn lookup(&self, command: &str) -> Option<&CommandDoc> {
+        let words = command_words(command);
+        (1..=words.len().min(MAX_KEY_WORDS))
+            .rev()
+            .find_map(|count| self.docs.get(&words[..count].join(" ")))
+    }
+
+    /// A markdown explanation of `command` from the bundled docs. `reason`
+    /// says why the AI wasn't used.
+    pub fn explain(&self, command: &str, reason: &str) -> Option<AiResponse> {
+        let doc = self.lookup(command)?;
+        let words = command_words(command);
+        let args = &words[doc.name.split_whitespace().count().min(words.len())..];
+
+        let mut content = format!("**📚 Offline docs** · `{}`\n\n{}\n", doc.name, doc.description);
+
+        let used_flags = flags_used(doc, args);
+        if !used_flags.is_empty() {
+            content.push_str("\n**Flags used**\n");
+            for (flag, description) in used_flags {
+                content.push_str(&format!("- `{}`: {}\n", flag, description));
+            }
+        }
+
+        if !doc.examples.is_empty() {
+            content.push_str("\n**Common examples**\n");
+            for (description, example) in &doc.examples {
+                content.push_str(&format!("- {}: `{}`\n", description, example));
+            }
+        }
+
+        content.push_str(&format!(
+            "\n_{} Set `api_key` in the `[ai]` section of the config, or the `GEMINI_API_KEY` environment variable, for AI explanations._",
+            reason
+        ));
+
+        Some(AiResponse {
+            content,
+            suggestions: doc.examples.iter().map(|(_, example)| example.clone()).collect(),
+            code_snippets: vec![],
+            confidence: 0.5,
+            injection_signals: Vec::new(),
+        })
+    }
+}
+
+/// The words of the first command in `command`, without a leading `sudo`
+/// (and its options) or `VAR=value` assignments.
+fn command_words(command: &str) -> Vec<String> {
+    let first = command
+        .split(['|', ';', '&'])
+        .next()
+        .unwrap_or_default();
+    let mut tokens = first.split_whitespace().peekable();
+
+    loop {
+        match tokens.peek() {
+            Some(&"sudo") => {
+                tokens.next();
+                while let Some(option) = tokens.next_if(|t| t.starts_with('-')) {
+                    // Options that take a value, like `sudo -u root`
+                    if matches!(option, "-u" | "-g" | "-C" | "-D" | "-h" | "-p") {
+                        tokens.next();
+                    }
+                }
+            }
+            Some(token) if is_assignment(token) => {
+                tokens.next();
+            }
+            _ => break,
+        }
+    }
+
+    tokens.map(str::to_string).collect()
+}
+
+fn is_assignment(token: &str) -> bool {
+    token.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Documented flags that appear in `args`, expanding bundled short flags
+/// like `-rf`.
+fn flags_used<'a>(doc: &'a CommandDoc, args: &[String]) -> Vec<(String, &'a str)> {
+    let mut used: Vec<(String, &str)> = Vec::new();
+    let mut add = |flag: String, description: &'a str| {
+        if !used.iter().any(|(f, _)| *f == flag) {
+            used.push((flag, description));
+        }
+    };
+
+    for arg in args.iter().filter(|arg| arg.starts_with('-') && arg.len() > 1) {
+        let flag = arg.split('=').next().unwrap_or(arg);
+        if let Some(description) = doc.flag(flag) {
+            add(flag.to_string(), description);
+        } else if !flag.starts_with("--") {
+            for c in flag.chars().skip(1) {
+                let short = format!("-{}", c);
+                if let Some(description) = doc.flag(&short) {
+                    add(short, description);
+                }
+            }
+        }
+    }
+    used
+}
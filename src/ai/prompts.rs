@@ -0,0 +1,49 @@
+//! Prompts for the one-shot tasks, each starting with the system prompt.
+//! Errors and code are untrusted content.
+
+use super::injection::untrusted;
+
+pub fn explain_command_prompt(system_prompt: &str, command: &str) -> String {
+    format!(
+        "{}\n\nExplain this command: `{}`\n\nProvide:\n1. What it does\n2. Key options/flags\n3. Example usage\n4. Potential risks or considerations",
+        system_prompt, command
+    )
+}
+
+pub fn generate_command_prompt(system_prompt: &str, description: &str) -> String {
+    format!(
+        "{}\n\nGenerate a command to: {}\n\nProvide:\n1. The command with explanation\n2. Alternative approaches if applicable\n3. Safety considerations\n\nFormat code in markdown code blocks.",
+        system_prompt, description
+    )
+}
+
+pub fn fix_error_prompt(system_prompt: &str, error: &str, context: Option<&str>) -> String {
+    let context_str = context
+        .map(|c| format!("\n\nContext:\n{}", untrusted("context", c)))
+        .unwrap_or_default();
+    format!(
+        "{}\n\nFix this error:\n{}{}\n\nProvide:\n1. Explanation of the error\n2. Solution steps\n3. Prevention tips\n\nFormat commands in markdown code blocks.",
+        system_prompt,
+        untrusted("error", error),
+        context_str
+    )
+}
+
+pub fn review_code_prompt(system_prompt: &str, code: &str, language: Option<&str>) -> String {
+    let language_str = language.unwrap_or("unknown");
+    format!(
+        "{}\n\nReview this {} code:\n\n{}\n\nProvide:\n1. Code quality assessment\n2. Potential issues\n3. Improvement suggestions\n4. Best practices",
+        system_prompt,
+        language_str,
+        untrusted(&format!("{} code", language_str), code)
+    )
+}
+
+pub fn security_analysis_prompt(system_prompt: &str, code: &str, language: &str) -> String {
+    format!(
+        "{}\n\nPerform security analysis on this {} code:\n\n{}\n\nFocus on:\n1. Security vulnerabilities\n2. Potential attack vectors\n3. Recommended fixes\n4. Security best practices\n\nBe specific and actionable.",
+        system_prompt,
+        language,
+        untrusted(&format!("{} code", language), code)
+    )
+}
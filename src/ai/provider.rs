@@ -0,0 +1,70 @@
+use super::{AiConfig, AiRequestOptions, AiResponse, CodeSnippet, ModelInfo};
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+
+/// A provider call in flight.
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A model backend the agent sends prompts to. `GeminiClient` is the real
+/// one; tests substitute their own through `AiAgent::with_provider`. The
+/// agent redacts secrets from prompts before they get here.
+pub trait AiProvider: Send + Sync {
+    /// Sends `prompt` and returns the raw response text, code blocks included.
+    fn generate_text<'a>(&'a self, prompt: String, options: &'a AiRequestOptions) -> ProviderFuture<'a, String>;
+
+    /// Like `generate_text`, calling `on_chunk` with each piece of text as it
+    /// arrives. Returns the full text.
+    fn generate_text_stream<'a>(
+        &'a self,
+        prompt: String,
+        options: &'a AiRequestOptions,
+        on_chunk: &'a mut (dyn FnMut(&str) + Send),
+    ) -> ProviderFuture<'a, String>;
+
+    /// The models requests can be sent to.
+    fn list_models(&self) -> ProviderFuture<'_, Vec<ModelInfo>>;
+
+    fn update_config(&mut self, config: AiConfig);
+}
+
+/// Splits a response into prose, code blocks and "Suggestion:" lines.
+pub fn parse_response(content: &str) -> AiResponse {
+    let mut suggestions = Vec::new();
+    let mut code_snippets = Vec::new();
+    let mut clean_content = content.to_string();
+
+    // Extract code blocks
+    let code_block_regex = regex::Regex::new(r"(?s)```(\w+)?\n(.*?)\n```").unwrap();
+    for cap in code_block_regex.captures_iter(content) {
+        let language = cap.get(1).map_or("text".to_string(), |m| m.as_str().to_string());
+        let code = cap.get(2).map_or("", |m| m.as_str()).to_string();
+        
+        if !code.trim().is_empty() {
+            code_snippets.push(CodeSnippet::new(
+                language,
+                code,
+                "Generated code snippet".to_string(),
+            ));
+        }
+    }
+
+    // Remove code blocks from content
+    clean_content = code_block_regex.replace_all(&clean_content, "").to_string();
+
+    // Extract suggestions (lines starting with "Suggestion:" or "Try:")
+    let suggestion_regex = regex::Regex::new(r"(?i)(?:suggestion|try):\s*(.+)").unwrap();
+    for cap in suggestion_regex.captures_iter(&clean_content) {
+        if let Some(suggestion) = cap.get(1) {
+            suggestions.push(suggestion.as_str().trim().to_string());
+        }
+    }
+
+    AiResponse {
+        content: clean_content.trim().to_string(),
+        suggestions,
+        code_snippets,
+        confidence: 0.8, // Default confidence
+        injection_signals: Vec::new(),
+    }
+}
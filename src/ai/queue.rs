@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 1;
+
+/// Limits how many AI requests run at once. Requests start strictly in the
+/// order they were enqueued, so replies keep the conversation's order.
+pub struct RequestQueue {
+    limit: usize,
+    state: Mutex<QueueState>,
+    notify: Notify,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_id: u64,
+    waiting: VecDeque<u64>,
+    running: usize,
+}
+
+impl RequestQueue {
+    pub fn new(limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            limit: limit.max(1),
+            state: Mutex::new(QueueState::default()),
+            notify: Notify::new(),
+        })
+    }
+
+    /// Takes a place at the back of the queue.
+    pub fn enqueue(self: &Arc<Self>) -> QueueTicket {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiting.push_back(id);
+        QueueTicket {
+            id,
+            queue: self.clone(),
+        }
+    }
+
+    /// How many requests will start or are running before ticket `id`, or
+    /// `None` if it isn't waiting any more.
+    pub fn requests_ahead(&self, id: u64) -> Option<usize> {
+        let state = self.state.lock().unwrap();
+        let index = state.waiting.iter().position(|waiting| *waiting == id)?;
+        Some(state.running + index)
+    }
+
+    /// Starts ticket `id` if it's at the front and there's a free slot. The
+    /// ticket behind it, now at the front, is woken to try for the next slot.
+    fn try_start(&self, id: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.running < self.limit && state.waiting.front() == Some(&id) {
+            state.waiting.pop_front();
+            state.running += 1;
+            drop(state);
+            self.notify.notify_waiters();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A place in the `RequestQueue`. Dropping it gives up the place.
+pub struct QueueTicket {
+    id: u64,
+    queue: Arc<RequestQueue>,
+}
+
+impl QueueTicket {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Waits for this ticket's turn. The slot is held until the returned
+    /// permit is dropped.
+    pub async fn wait(self) -> QueuePermit {
+        loop {
+            let notified = self.queue.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.queue.try_start(self.id) {
+                return QueuePermit {
+                    queue: self.queue.clone(),
+                };
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        if let Some(index) = state.waiting.iter().position(|waiting| *waiting == self.id) {
+            state.waiting.remove(index);
+            drop(state);
+            self.queue.notify.notify_waiters();
+        }
+    }
+}
+
+/// A running request's slot in the `RequestQueue`.
+pub struct QueuePermit {
+    queue: Arc<RequestQueue>,
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().running -= 1;
+        self.queue.notify.notify_waiters();
+    }
+}
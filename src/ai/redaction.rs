@@ -0,0 +1,236 @@
+use crate::security::secrets::SECRET_PATTERNS;
+use anyhow::{Context, Result};
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Longest text held back while streaming in case it's the start of a
+/// placeholder.
+const MAX_PLACEHOLDER_LEN: usize = 96;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Replace secrets with placeholders before anything is sent to the AI
+    pub enabled: bool,
+    /// Put the original values back where placeholders appear in responses.
+    /// This happens locally; the secrets are still never sent.
+    pub restore_placeholders: bool,
+    /// Patterns redacted on top of the built-in secret patterns
+    pub patterns: Vec<RedactionPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            restore_placeholders: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// A user pattern. Like the built-in ones, it may have a `secret` group for
+/// the part to redact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+impl RedactionPattern {
+    pub fn compile(&self) -> Result<Regex> {
+        Regex::new(&self.regex).with_context(|| format!("Invalid redaction pattern \"{}\"", self.name))
+    }
+}
+
+/// Replaces secrets with stable placeholders like `«REDACTED:aws_access_key:1»`.
+pub struct Redactor {
+    enabled: bool,
+    /// (placeholder kind, pattern)
+    patterns: Vec<(String, Regex)>,
+    redactions: Arc<AtomicUsize>,
+}
+
+impl Redactor {
+    /// Built-in secret patterns plus the configured ones. Invalid user
+    /// patterns are skipped with a warning. `redactions` counts every
+    /// secret replaced.
+    pub fn new(config: &RedactionConfig, redactions: Arc<AtomicUsize>) -> Self {
+        let mut patterns: Vec<(String, Regex)> = SECRET_PATTERNS
+            .iter()
+            .filter_map(|(name, pattern)| Some((placeholder_kind(name), Regex::new(pattern).ok()?)))
+            .collect();
+        for pattern in &config.patterns {
+            match pattern.compile() {
+                Ok(regex) => patterns.push((placeholder_kind(&pattern.name), regex)),
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+
+        Self {
+            enabled: config.enabled,
+            patterns,
+            redactions,
+        }
+    }
+
+    /// Redacts `text`. The same secret always gets the same placeholder, and
+    /// placeholders already in `text` are left alone, so redacting twice
+    /// changes nothing. Where matches overlap, the earliest (then longest)
+    /// one wins.
+    pub fn redact(&self, text: &str) -> Redaction {
+        if !self.enabled {
+            return Redaction {
+                text: text.to_string(),
+                placeholders: Vec::new(),
+            };
+        }
+
+        let mut claimed: Vec<Range<usize>> = Vec::new();
+        let mut next_index: HashMap<String, usize> = HashMap::new();
+        for caps in placeholder_regex().captures_iter(text) {
+            claimed.push(caps.get(0).unwrap().range());
+            let index = caps[2].parse().unwrap_or(0);
+            let next = next_index.entry(caps[1].to_string()).or_insert(0);
+            *next = (*next).max(index);
+        }
+
+        let mut matches: Vec<(Range<usize>, &str)> = Vec::new();
+        for (kind, regex) in &self.patterns {
+            for caps in regex.captures_iter(text) {
+                let secret = caps.name("secret").unwrap_or_else(|| caps.get(0).unwrap());
+                if !secret.is_empty() {
+                    matches.push((secret.range(), kind));
+                }
+            }
+        }
+        matches.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+
+        let mut kept: Vec<(Range<usize>, &str)> = Vec::new();
+        for (range, kind) in matches {
+            let overlaps = |other: &Range<usize>| other.start < range.end && range.start < other.end;
+            if !claimed.iter().any(overlaps) {
+                claimed.push(range.clone());
+                kept.push((range, kind));
+            }
+        }
+        kept.sort_by_key(|(range, _)| range.start);
+
+        let mut placeholders: Vec<(String, String)> = Vec::new();
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, kind) in &kept {
+            let secret = &text[range.clone()];
+            let placeholder = match placeholders.iter().find(|(_, original)| original == secret) {
+                Some((placeholder, _)) => placeholder.clone(),
+                None => {
+                    let index = next_index.entry(kind.to_string()).or_insert(0);
+                    *index += 1;
+                    let placeholder = format!("«REDACTED:{}:{}»", kind, index);
+                    placeholders.push((placeholder.clone(), secret.to_string()));
+                    placeholder
+                }
+            };
+            redacted.push_str(&text[last..range.start]);
+            redacted.push_str(&placeholder);
+            last = range.end;
+        }
+        redacted.push_str(&text[last..]);
+
+        self.redactions.fetch_add(kept.len(), Ordering::Relaxed);
+        Redaction {
+            text: redacted,
+            placeholders,
+        }
+    }
+}
+
+/// Redacted text and what each placeholder stands for. The mapping stays on
+/// this machine, for showing responses with the real values.
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    pub text: String,
+    /// (placeholder, original)
+    pub placeholders: Vec<(String, String)>,
+}
+
+impl Redaction {
+    /// `text` with this redaction's placeholders replaced by the originals.
+    pub fn restore(&self, text: &str) -> String {
+        self.placeholders
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| {
+                text.replace(placeholder, original)
+            })
+    }
+
+    /// Restores placeholders in a response that arrives in chunks, which
+    /// may split a placeholder.
+    pub fn stream_restorer(&self) -> StreamRestorer<'_> {
+        StreamRestorer {
+            redaction: self,
+            pending: String::new(),
+        }
+    }
+}
+
+pub struct StreamRestorer<'a> {
+    redaction: &'a Redaction,
+    /// Text that may be the start of a placeholder
+    pending: String,
+}
+
+impl StreamRestorer<'_> {
+    /// The restored text that's safe to show after `chunk`.
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.pending.push_str(chunk);
+        let hold_from = self
+            .pending
+            .rfind('«')
+            .filter(|start| {
+                !self.pending[*start..].contains('»') && self.pending.len() - start < MAX_PLACEHOLDER_LEN
+            })
+            .unwrap_or(self.pending.len());
+        let ready: String = self.pending.drain(..hold_from).collect();
+        self.redaction.restore(&ready)
+    }
+
+    /// Whatever was held back.
+    pub fn finish(self) -> String {
+        self.redaction.restore(&self.pending)
+    }
+}
+
+/// Placeholder kinds of the patterns `config` redacts, built-in ones first.
+pub fn pattern_names(config: &RedactionConfig) -> Vec<String> {
+    SECRET_PATTERNS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(config.patterns.iter().map(|pattern| pattern.name.clone()))
+        .map(|name| placeholder_kind(&name))
+        .collect()
+}
+
+fn placeholder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"«REDACTED:([a-z0-9_]+):(\d+)»").unwrap())
+}
+
+/// `aws access key` -> `aws_access_key`
+fn placeholder_kind(name: &str) -> String {
+    let kind: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if kind.is_empty() {
+        "secret".to_string()
+    } else {
+        kind
+    }
+}
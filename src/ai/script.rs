@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptCommand {
+    pub command: String,
+    pub working_directory: String,
+}
+
+impl ScriptCommand {
+    pub fn new(command: String, working_directory: String) -> Self {
+        Self {
+            command,
+            working_directory,
+        }
+    }
+
+    /// `cd` only matters for the commands after it, and those already carry
+    /// their working directory.
+    fn is_directory_change(&self) -> bool {
+        is_directory_change(&self.command)
+    }
+}
+
+pub fn is_directory_change(command: &str) -> bool {
+    let command = command.trim();
+    command == "cd" || command.starts_with("cd ")
+}
+
+/// Normalizes a configured shell (`/bin/bash`, `pwsh.exe`, ...) to the name
+/// scripts are generated for.
+pub fn script_shell(shell: &str) -> String {
+    let name = Path::new(shell)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "zsh" | "sh" | "fish" => name,
+        "pwsh" | "powershell" => "powershell".to_string(),
+        _ => "bash".to_string(),
+    }
+}
+
+pub fn shebang(shell: &str) -> &'static str {
+    match script_shell(shell).as_str() {
+        "zsh" => "#!/usr/bin/env zsh",
+        "sh" => "#!/bin/sh",
+        "fish" => "#!/usr/bin/env fish",
+        "powershell" => "#!/usr/bin/env pwsh",
+        _ => "#!/usr/bin/env bash",
+    }
+}
+
+pub fn script_extension(shell: &str) -> &'static str {
+    match script_shell(shell).as_str() {
+        "fish" => "fish",
+        "powershell" => "ps1",
+        _ => "sh",
+    }
+}
+
+/// Deterministic script used when no AI provider is configured: the commands
+/// in order, with strict error handling and a `cd` whenever the working
+/// directory changes.
+pub fn fallback_script(commands: &[ScriptCommand], shell: &str) -> String {
+    let shell = script_shell(shell);
+    let mut lines = vec![shebang(&shell).to_string()];
+    lines.push("# Generated by ANTRAFT from terminal session commands".to_string());
+    match shell.as_str() {
+        "fish" => {}
+        "powershell" => lines.push("$ErrorActionPreference = 'Stop'".to_string()),
+        "sh" => lines.push("set -eu".to_string()),
+        _ => lines.push("set -euo pipefail".to_string()),
+    }
+    lines.push(String::new());
+
+    let mut current_directory: Option<&str> = None;
+    for command in commands.iter().filter(|c| !c.is_directory_change()) {
+        let directory = command.working_directory.as_str();
+        if !directory.is_empty() && current_directory != Some(directory) {
+            lines.push(format!("cd {}", shell_quote(directory, &shell)));
+            current_directory = Some(directory);
+        }
+        lines.push(command.command.trim().to_string());
+        if shell == "fish" {
+            lines.push("or exit $status".to_string());
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+pub fn script_prompt(commands: &[ScriptCommand], shell: &str) -> String {
+    let steps = commands
+        .iter()
+        .enumerate()
+        .map(|(index, c)| format!("{}. (in {}) {}", index + 1, c.working_directory, c.command))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "Turn these terminal commands, run in this order, into a robust {} script. \
+         Use proper error handling, change directories where needed, and add short \
+         comments explaining each step. Start with a shebang line. Return the script \
+         in a single ```{}``` code block.\n\n{}",
+        script_shell(shell),
+        script_shell(shell),
+        steps
+    )
+}
+
+/// Writes `script` to `path` as an executable file, adding a shebang if the
+/// script doesn't have one.
+pub fn write_script(path: &Path, script: &str, shell: &str) -> Result<()> {
+    let content = if script.starts_with("#!") {
+        script.to_string()
+    } else {
+        format!("{}\n{}", shebang(shell), script)
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write script {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = std::fs::metadata(path)?.permissions();
+        permissions.set_mode(permissions.mode() | 0o755);
+        std::fs::set_permissions(path, permissions)?;
+    }
+
+    Ok(())
+}
+
+/// Quotes `value` as a single word for `shell`.
+pub fn shell_quote(value: &str, shell: &str) -> String {
+    if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-~".contains(c))
+    {
+        return value.to_string();
+    }
+    match shell {
+        "powershell" => format!("'{}'", value.replace('\'', "''")),
+        "fish" => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        _ => format!("'{}'", value.replace('\'', r"'\''")),
+    }
+}
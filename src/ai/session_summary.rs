@@ -0,0 +1,161 @@
+use super::injection::untrusted;
+use crate::security::secrets::redact_secrets;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Output kept per block: the first and last half of this many characters.
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// Rough prompt budget in characters (~4 characters per token).
+pub const DEFAULT_CONTEXT_BUDGET: usize = 24_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionBlockSummary {
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub output: String,
+}
+
+impl SessionBlockSummary {
+    pub fn new(command: String, exit_code: Option<i32>, output: &str) -> Self {
+        Self {
+            command,
+            exit_code,
+            output: truncate_middle(output, MAX_OUTPUT_CHARS),
+        }
+    }
+
+    /// The block as it appears in the prompt, with secrets redacted.
+    fn to_transcript(&self) -> String {
+        let status = match self.exit_code {
+            Some(0) => "succeeded".to_string(),
+            Some(code) => format!("failed with exit code {}", code),
+            None => "still running".to_string(),
+        };
+        format!(
+            "$ {}\n({})\n{}\n",
+            redact_secrets(&self.command),
+            status,
+            redact_secrets(self.output.trim_end())
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SummaryProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Splits the session transcript into chunks of at most `budget` characters.
+/// Blocks are never split; a single block larger than the budget gets a chunk
+/// of its own.
+pub fn chunk_blocks(blocks: &[SessionBlockSummary], budget: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for block in blocks {
+        let entry = block.to_transcript();
+        if !current.is_empty() && current.len() + entry.len() > budget {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(&entry);
+        current.push('\n');
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+/// Summarizes a session with `generate`, map-reduce style: a session that
+/// fits the budget takes a single call; otherwise each chunk is summarized
+/// first and the partial summaries are combined into the final narrative.
+pub async fn summarize_session<F, Fut>(
+    blocks: &[SessionBlockSummary],
+    budget: usize,
+    mut generate: F,
+    mut on_progress: impl FnMut(SummaryProgress),
+) -> Result<String>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let chunks = chunk_blocks(blocks, budget);
+    if chunks.is_empty() {
+        anyhow::bail!("There are no commands in this session to summarize");
+    }
+
+    if chunks.len() == 1 {
+        on_progress(SummaryProgress { done: 0, total: 1 });
+        let summary = generate(final_prompt(&chunks[0], false)).await?;
+        on_progress(SummaryProgress { done: 1, total: 1 });
+        return Ok(summary);
+    }
+
+    let total = chunks.len() + 1;
+    let mut partials = Vec::with_capacity(chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        on_progress(SummaryProgress { done: index, total });
+        partials.push(generate(chunk_prompt(chunk, index + 1, chunks.len())).await?);
+    }
+
+    on_progress(SummaryProgress { done: chunks.len(), total });
+    let combined = partials
+        .iter()
+        .enumerate()
+        .map(|(index, partial)| format!("## Part {}\n{}\n", index + 1, partial))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let summary = generate(final_prompt(&combined, true)).await?;
+    on_progress(SummaryProgress { done: total, total });
+
+    Ok(summary)
+}
+
+fn chunk_prompt(chunk: &str, part: usize, parts: usize) -> String {
+    format!(
+        "This is part {} of {} of a terminal session transcript. Summarize it in a few \
+         bullet points: the commands run, which failed and why, and any fixes applied. \
+         Keep exact command names and error messages that matter.\n\n{}",
+        part,
+        parts,
+        untrusted("terminal session transcript", chunk)
+    )
+}
+
+fn final_prompt(content: &str, from_partials: bool) -> String {
+    let (source, label) = if from_partials {
+        ("the following summaries of consecutive parts of a terminal session", "session part summaries")
+    } else {
+        ("the following terminal session transcript", "terminal session transcript")
+    };
+    format!(
+        "Using {}, write a concise markdown writeup of the session with these sections:\n\
+         ## What was attempted\n## What failed\n## What fixed it\n## Final state\n\
+         Include the relevant commands in code blocks.\n\n{}",
+        source,
+        untrusted(label, content)
+    )
+}
+
+/// Keeps the start and end of `text`, dropping the middle if it is longer
+/// than `max_chars`.
+fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let char_count = text.chars().count();
+    if char_count <= max_chars {
+        return text.to_string();
+    }
+
+    let half = max_chars / 2;
+    let head: String = text.chars().take(half).collect();
+    let tail: String = text.chars().skip(char_count - half).collect();
+    format!(
+        "{}\n… [{} characters omitted] …\n{}",
+        head,
+        char_count - max_chars,
+        tail
+    )
+}
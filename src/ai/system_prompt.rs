@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are an AI assistant integrated into ANTRAFT, a modern terminal application. You help users with command-line tasks, explain commands, suggest solutions, and provide coding assistance. The user is on {os} using {shell}, working in {cwd}; today is {date}. Tailor commands to that environment. Be concise but helpful.";
+
+/// Used for security analysis instead of the conversational default.
+pub const SECURITY_SYSTEM_PROMPT: &str = "You are a security reviewer. Report concrete vulnerabilities with their location, impact and a fix, most severe first. Do not speculate or pad the report; if you find nothing, say so.";
+
+/// The user's environment, as seen by the system prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptContext {
+    pub os: String,
+    pub shell: String,
+    pub cwd: String,
+    pub date: String,
+}
+
+impl PromptContext {
+    pub fn current(shell: &str, cwd: &str) -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            shell: shell.to_string(),
+            cwd: cwd.to_string(),
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    fn values(&self) -> HashMap<&'static str, &str> {
+        HashMap::from([
+            ("os", self.os.as_str()),
+            ("shell", self.shell.as_str()),
+            ("cwd", self.cwd.as_str()),
+            ("date", self.date.as_str()),
+        ])
+    }
+}
+
+/// Substitutes the variables of a system prompt, which are:
+///
+/// - `{os}`: operating system, e.g. `linux`
+/// - `{shell}`: the terminal's configured shell, e.g. `bash`
+/// - `{cwd}`: the current working directory
+/// - `{date}`: today's date, `YYYY-MM-DD`
+///
+/// Each may also be written `${os}` or `$os`. A backslash before `{` or `$`
+/// keeps it literal, so `\{os}` renders as `{os}`. Unknown names, like
+/// `$HOME`, and lone braces are left as they are.
+pub fn render_system_prompt(template: &str, context: &PromptContext) -> String {
+    let values = context.values();
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['\\', '{', '$']) {
+        rendered.push_str(&rest[..start]);
+        let marker = &rest[start..start + 1];
+        let after = &rest[start + 1..];
+
+        let substituted = match marker {
+            "\\" if after.starts_with(['{', '$']) => {
+                // `\${os}` keeps its brace too
+                let literal = if after.starts_with("${") { 2 } else { 1 };
+                rendered.push_str(&after[..literal]);
+                rest = &after[literal..];
+                continue;
+            }
+            "{" => braced_variable(after, &values),
+            "$" => match after.strip_prefix('{') {
+                Some(braced) => braced_variable(braced, &values),
+                None => bare_variable(after, &values),
+            },
+            _ => None,
+        };
+        match substituted {
+            Some((value, remaining)) => {
+                rendered.push_str(value);
+                rest = remaining;
+            }
+            None => {
+                rendered.push_str(marker);
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// The value of the variable `text` starts with, up to a closing `}`, and
+/// the text after it.
+fn braced_variable<'a, 'v>(text: &'a str, values: &HashMap<&str, &'v str>) -> Option<(&'v str, &'a str)> {
+    let end = text.find('}')?;
+    let value = values.get(&text[..end])?;
+    Some((value, &text[end + 1..]))
+}
+
+/// The value of the `$name` variable `text` starts with, and the text after
+/// it. The name runs to the first character that can't be in one.
+fn bare_variable<'a, 'v>(text: &'a str, values: &HashMap<&str, &'v str>) -> Option<(&'v str, &'a str)> {
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let value = values.get(&text[..end])?;
+    Some((value, &text[end..]))
+}
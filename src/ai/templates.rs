@@ -0,0 +1,287 @@
+use anyhow::{anyhow, Context, Result};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A reusable AI prompt. The body may reference variables as `{{name}}`;
+/// write `\{{` for a literal `{{`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub body: String,
+    /// Shipped with ANTRAFT rather than loaded from the templates directory
+    #[serde(skip)]
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+impl PromptTemplate {
+    pub fn new(name: impl Into<String>, description: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            body: body.into(),
+            builtin: false,
+        }
+    }
+
+    /// Variables referenced by the body, in order of first use.
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables: Vec<String> = Vec::new();
+        for segment in parse(&self.body) {
+            if let Segment::Variable(name) = segment {
+                if !variables.iter().any(|v| v == name) {
+                    variables.push(name.to_string());
+                }
+            }
+        }
+        variables
+    }
+
+    /// Variables referenced by the body that have no (non-empty) value in `values`.
+    pub fn missing_variables(&self, values: &HashMap<String, String>) -> Vec<String> {
+        self.variables()
+            .into_iter()
+            .filter(|name| values.get(name).is_none_or(|value| value.is_empty()))
+            .collect()
+    }
+
+    /// Substitutes `values` into the body. Fails if a variable has no value.
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
+        let mut rendered = String::with_capacity(self.body.len());
+        for segment in parse(&self.body) {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Variable(name) => {
+                    let value = values
+                        .get(name)
+                        .ok_or_else(|| anyhow!("No value for template variable '{}'", name))?;
+                    rendered.push_str(value);
+                }
+            }
+        }
+        Ok(rendered)
+    }
+
+    fn file_name(&self) -> String {
+        let slug: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect();
+        format!("{}.toml", slug)
+    }
+}
+
+/// Splits a template body into literal text and `{{variable}}` references.
+/// `\{{` is an escaped literal `{{`, and `{{` not followed by a valid
+/// variable name and `}}` is kept as text.
+fn parse(body: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < body.len() {
+        let rest = &body[i..];
+        if rest.starts_with("\\{{") {
+            if text_start < i {
+                segments.push(Segment::Text(&body[text_start..i]));
+            }
+            segments.push(Segment::Text("{{"));
+            i += 3;
+            text_start = i;
+            continue;
+        }
+        if let Some(after_open) = rest.strip_prefix("{{") {
+            if let Some(end) = after_open.find("}}") {
+                let name = after_open[..end].trim();
+                if is_variable_name(name) {
+                    if text_start < i {
+                        segments.push(Segment::Text(&body[text_start..i]));
+                    }
+                    segments.push(Segment::Variable(name));
+                    i += 2 + end + 2;
+                    text_start = i;
+                    continue;
+                }
+            }
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+
+    if text_start < body.len() {
+        segments.push(Segment::Text(&body[text_start..]));
+    }
+    segments
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn builtin_templates() -> Vec<PromptTemplate> {
+    [
+        (
+            "review-concurrency",
+            "Review a diff for concurrency bugs",
+            "Review this diff for concurrency bugs: data races, deadlocks, missing synchronization and incorrect ordering. Point to the exact lines.\n\n```diff\n{{selection}}\n```",
+        ),
+        (
+            "commit-message",
+            "Write a commit message for these changes",
+            "Write a concise git commit message (summary line under 72 characters, then a short body) for these changes:\n\n```diff\n{{selection}}\n```",
+        ),
+        (
+            "explain-output",
+            "Explain the output of the last command",
+            "I'm in {{cwd}}. Explain this command output and anything in it I should act on:\n\n```\n{{last_output}}\n```",
+        ),
+        (
+            "fix-last-error",
+            "Suggest a fix for the last command's error",
+            "This command failed in {{cwd}}:\n\n```\n{{last_output}}\n```\n\nExplain the cause and give the commands to fix it.",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, description, body)| PromptTemplate {
+        builtin: true,
+        ..PromptTemplate::new(name, description, body)
+    })
+    .collect()
+}
+
+pub fn default_templates_dir() -> PathBuf {
+    crate::config::config_dir().join("templates")
+}
+
+/// Built-in templates plus the user's, loaded from one TOML file per template.
+/// A user template with the same name as a built-in replaces it.
+pub struct PromptTemplateStore {
+    dir: PathBuf,
+    templates: Vec<PromptTemplate>,
+}
+
+impl PromptTemplateStore {
+    /// A store with only the built-in templates.
+    pub fn builtin(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            templates: builtin_templates(),
+        }
+    }
+
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut store = Self::builtin(dir);
+
+        if !dir.exists() {
+            return Ok(store);
+        }
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read templates directory {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| toml::from_str::<PromptTemplate>(&content).map_err(Into::into))
+            {
+                Ok(template) => store.insert(template),
+                Err(e) => warn!("Skipping invalid prompt template {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(store)
+    }
+
+    pub fn templates(&self) -> &[PromptTemplate] {
+        &self.templates
+    }
+
+    /// Templates matching the text after a leading `/` in `input`, fuzzily
+    /// by name and description, best match first. `None` when `input` isn't
+    /// a template query.
+    pub fn search(&self, input: &str) -> Option<Vec<PromptTemplate>> {
+        let query = input.strip_prefix('/')?;
+        if query.is_empty() {
+            return Some(self.templates.clone());
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<(&PromptTemplate, i64)> = self
+            .templates
+            .iter()
+            .filter_map(|template| {
+                matcher
+                    .fuzzy_match(&format!("{} {}", template.name, template.description), query)
+                    .map(|score| (template, score))
+            })
+            .collect();
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+        Some(scored.into_iter().map(|(template, _)| template.clone()).collect())
+    }
+
+    /// Writes `template` to the templates directory and adds or replaces it
+    /// in the store.
+    pub fn save(&mut self, mut template: PromptTemplate) -> Result<()> {
+        if template.name.trim().is_empty() {
+            return Err(anyhow!("Template name can't be empty"));
+        }
+        template.builtin = false;
+
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(template.file_name());
+        std::fs::write(&path, toml::to_string_pretty(&template)?)
+            .with_context(|| format!("Failed to write template {}", path.display()))?;
+
+        self.insert(template);
+        Ok(())
+    }
+
+    /// Deletes a user template. Deleting an override of a built-in restores
+    /// the built-in; built-ins themselves can't be deleted.
+    pub fn delete(&mut self, name: &str) -> Result<()> {
+        let Some(index) = self.templates.iter().position(|t| t.name == name) else {
+            return Err(anyhow!("No template named '{}'", name));
+        };
+        if self.templates[index].builtin {
+            return Err(anyhow!("Built-in template '{}' can't be deleted", name));
+        }
+
+        let path = self.dir.join(self.templates[index].file_name());
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete template {}", path.display()))?;
+        }
+
+        match builtin_templates().into_iter().find(|t| t.name == name) {
+            Some(builtin) => self.templates[index] = builtin,
+            None => {
+                self.templates.remove(index);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, template: PromptTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+}
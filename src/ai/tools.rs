@@ -0,0 +1,362 @@
+use super::injection::untrusted;
+use crate::security::secrets::redact_secrets;
+use crate::terminal::TerminalEngine;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const DEFAULT_MAX_TOOL_STEPS: usize = 5;
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_FILE_BYTES: u64 = 64 * 1024;
+const MAX_DIRECTORY_ENTRIES: usize = 200;
+/// Cap for any tool result fed back to the model.
+const MAX_TOOL_OUTPUT_CHARS: usize = 8_000;
+
+/// Programs the agent may run. Everything else is refused.
+const READONLY_COMMANDS: &[&str] = &[
+    "ls", "cat", "head", "tail", "wc", "grep", "rg", "find", "tree", "file", "stat", "du", "df",
+    "ps", "lsof", "ss", "netstat", "pwd", "whoami", "id", "uname", "uptime", "free",
+    "date", "which", "git",
+];
+
+const READONLY_GIT_SUBCOMMANDS: &[&str] = &[
+    "status", "log", "diff", "show", "branch", "ls-files", "rev-parse", "blame", "remote",
+];
+
+/// Arguments that would make an otherwise read-only command write, delete,
+/// run other programs or never finish. Also caught as `--flag=value`,
+/// abbreviated like `--foll`, and bundled like `-fn5`; see `is_forbidden`.
+const FORBIDDEN_ARGS: &[(&str, &[&str])] = &[
+    ("find", &["-exec", "-execdir", "-ok", "-okdir", "-delete", "-fprint", "-fprint0", "-fprintf", "-fls"]),
+    ("tail", &["-f", "-F", "--follow"]),
+    ("rg", &["--pre"]),
+    ("date", &["-s", "--set"]),
+    ("file", &["-C", "--compile"]),
+    ("tree", &["-o"]),
+];
+
+/// `git branch` and `git remote` change things unless limited to listing.
+const GIT_LISTING_ARGS: &[(&str, &[&str])] = &[
+    ("branch", &["-a", "-r", "-v", "-vv", "--all", "--remotes", "--list", "--show-current", "--verbose"]),
+    ("remote", &["-v", "--verbose"]),
+];
+
+/// A tool invocation requested by the model, written as a fenced
+/// ```` ```tool ```` block holding `{"tool": ..., "args": {...}}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "tool", content = "args", rename_all = "snake_case")]
+pub enum ToolCall {
+    RunReadonlyCommand { command: String },
+    ReadFile { path: String },
+    ListDirectory { path: String },
+}
+
+impl ToolCall {
+    /// One-line description for the chat transcript.
+    pub fn title(&self) -> String {
+        match self {
+            ToolCall::RunReadonlyCommand { command } => format!("$ {}", command),
+            ToolCall::ReadFile { path } => format!("read_file {}", path),
+            ToolCall::ListDirectory { path } => format!("list_directory {}", path),
+        }
+    }
+}
+
+/// One executed (or refused) tool call.
+#[derive(Debug, Clone)]
+pub struct ToolStep {
+    pub title: String,
+    pub output: String,
+    pub success: bool,
+}
+
+impl ToolStep {
+    fn failed(title: String, error: impl std::fmt::Display) -> Self {
+        Self {
+            title,
+            output: error.to_string(),
+            success: false,
+        }
+    }
+}
+
+/// Instructions appended to the prompt describing the tools and how to call
+/// them.
+pub fn tools_prompt(max_steps: usize) -> String {
+    format!(
+        "You can gather information from the user's machine with these read-only tools:\n\
+         - run_readonly_command {{\"command\": string}}: runs one command without a shell \
+         (no pipes, redirection, globs or variables). Allowed programs: {}. Allowed git \
+         subcommands: {}.\n\
+         - read_file {{\"path\": string}}: reads a text file (up to {} KB).\n\
+         - list_directory {{\"path\": string}}: lists a directory.\n\
+         Relative paths are resolved against the current working directory.\n\n\
+         To call a tool, reply with only a fenced block like:\n\
+         ```tool\n{{\"tool\": \"run_readonly_command\", \"args\": {{\"command\": \"ps aux\"}}}}\n```\n\
+         You'll get the result and can call another tool, up to {} calls. When you have \
+         enough information, reply with the final answer and no tool block.",
+        READONLY_COMMANDS.join(", "),
+        READONLY_GIT_SUBCOMMANDS.join(", "),
+        MAX_FILE_BYTES / 1024,
+        max_steps
+    )
+}
+
+/// The tool call in a model reply, if it has one.
+pub fn parse_tool_call(reply: &str) -> Option<Result<ToolCall>> {
+    let start = reply.find("```tool")?;
+    let body = &reply[start + "```tool".len()..];
+    let json = match body.find("```") {
+        Some(end) => &body[..end],
+        None => body,
+    };
+    Some(serde_json::from_str(json.trim()).map_err(|e| anyhow!("Invalid tool call: {}", e)))
+}
+
+/// Runs the tool-use loop: asks the model, executes the tool it calls, feeds
+/// the result back, and repeats until it answers or `max_steps` tools have
+/// run. Returns the final answer.
+pub async fn run_tool_loop<G, GF, E, EF>(
+    prompt: String,
+    max_steps: usize,
+    mut generate: G,
+    mut execute: E,
+    mut on_step: impl FnMut(&ToolStep),
+) -> Result<String>
+where
+    G: FnMut(String) -> GF,
+    GF: Future<Output = Result<String>>,
+    E: FnMut(ToolCall) -> EF,
+    EF: Future<Output = ToolStep>,
+{
+    let mut transcript = format!("{}\n\n{}", prompt, tools_prompt(max_steps));
+
+    for _ in 0..max_steps {
+        let reply = generate(transcript.clone()).await?;
+        let step = match parse_tool_call(&reply) {
+            None => return Ok(reply),
+            Some(Ok(call)) => execute(call).await,
+            Some(Err(e)) => ToolStep::failed("invalid tool call".to_string(), e),
+        };
+        on_step(&step);
+
+        transcript.push_str(&format!(
+            "\n\nAssistant:\n{}\n\nTool result for `{}`{}:\n{}",
+            reply.trim(),
+            step.title,
+            if step.success { "" } else { " (failed)" },
+            untrusted(&format!("result of {}", step.title), &redact_secrets(&step.output))
+        ));
+    }
+
+    transcript.push_str("\n\nYou have used all your tool calls. Answer now from the results above, without calling tools.");
+    generate(transcript).await
+}
+
+/// Executes a tool call in `cwd`, enforcing the read-only allowlist.
+pub async fn execute_tool(engine: &TerminalEngine, call: ToolCall, cwd: &Path) -> ToolStep {
+    let title = call.title();
+    let result = match call {
+        ToolCall::RunReadonlyCommand { command } => run_readonly_command(engine, &command, cwd).await,
+        ToolCall::ReadFile { path } => read_file(&resolve(cwd, &path)),
+        ToolCall::ListDirectory { path } => list_directory(&resolve(cwd, &path)),
+    };
+
+    match result {
+        Ok(output) => ToolStep {
+            title,
+            output: truncate(output),
+            success: true,
+        },
+        Err(e) => ToolStep::failed(title, e),
+    }
+}
+
+async fn run_readonly_command(engine: &TerminalEngine, command: &str, cwd: &Path) -> Result<String> {
+    let argv = validate_readonly_command(command)?;
+    let output = engine.run_sandboxed(&argv, cwd, COMMAND_TIMEOUT).await?;
+
+    let mut text = output.stdout;
+    if !output.stderr.trim().is_empty() {
+        text.push_str(&format!("\n[stderr]\n{}", output.stderr));
+    }
+    if output.exit_code != 0 {
+        text.push_str(&format!("\n[exit code {}]", output.exit_code));
+    }
+    Ok(text)
+}
+
+/// Splits `command` into arguments and checks it against the allowlist.
+pub fn validate_readonly_command(command: &str) -> Result<Vec<String>> {
+    let argv = split_arguments(command)?;
+    let program = argv.first().ok_or_else(|| anyhow!("Empty command"))?;
+
+    if !READONLY_COMMANDS.contains(&program.as_str()) {
+        return Err(anyhow!("`{}` is not on the read-only allowlist", program));
+    }
+
+    if let Some((_, forbidden)) = FORBIDDEN_ARGS.iter().find(|(name, _)| name == program) {
+        if let Some(arg) = argv[1..].iter().find(|arg| forbidden.iter().any(|flag| is_forbidden(arg, flag))) {
+            return Err(anyhow!("`{} {}` is not allowed", program, arg));
+        }
+    }
+
+    if program == "git" {
+        let subcommand = argv.get(1).map(String::as_str).unwrap_or_default();
+        if !READONLY_GIT_SUBCOMMANDS.contains(&subcommand) {
+            return Err(anyhow!("`git {}` is not on the read-only allowlist", subcommand));
+        }
+        if let Some(arg) = argv.iter().find(|arg| arg.starts_with("--output")) {
+            return Err(anyhow!("`git {}` is not allowed", arg));
+        }
+        if let Some((_, listing)) = GIT_LISTING_ARGS.iter().find(|(name, _)| *name == subcommand) {
+            if let Some(arg) = argv[2..].iter().find(|arg| !listing.contains(&arg.as_str())) {
+                return Err(anyhow!("`git {} {}` is not allowed", subcommand, arg));
+            }
+        }
+    }
+
+    Ok(argv)
+}
+
+/// Whether `arg` is the forbidden `flag` in any of the forms programs
+/// accept it: `--flag=value`, a GNU-style abbreviation like `--foll` for
+/// `--follow`, or, for a short flag like `-f`, among bundled short options
+/// like `-fn5`. Single-dash words like find's `-exec` only match exactly.
+fn is_forbidden(arg: &str, flag: &str) -> bool {
+    if arg == flag {
+        return true;
+    }
+    if let Some(name) = flag.strip_prefix("--") {
+        let given = arg.strip_prefix("--").map(|given| given.split('=').next().unwrap_or_default());
+        return given.is_some_and(|given| !given.is_empty() && name.starts_with(given));
+    }
+    match (flag.strip_prefix('-'), arg.strip_prefix('-')) {
+        (Some(short), Some(bundle)) if short.chars().count() == 1 && !bundle.starts_with('-') => {
+            bundle.contains(short)
+        }
+        _ => false,
+    }
+}
+
+/// Shell-like word splitting with quotes, refusing anything a shell would
+/// treat specially since the command is never run by one.
+fn split_arguments(command: &str) -> Result<Vec<String>> {
+    let mut argv = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                    in_word = true;
+                }
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    argv.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, '|' | '&' | ';' | '<' | '>' | '(' | ')' | '$' | '`' | '*' | '?') => {
+                return Err(anyhow!(
+                    "`{}` isn't supported: commands run without a shell, so pipes, redirection, globs and variables don't work",
+                    c
+                ));
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(anyhow!("Unterminated quote"));
+    }
+    if in_word {
+        argv.push(current);
+    }
+    Ok(argv)
+}
+
+fn resolve(cwd: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path.trim());
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    }
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    let metadata = std::fs::metadata(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(anyhow!("{} is not a file", path.display()));
+    }
+
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_FILE_BYTES)
+        .read_to_end(&mut bytes)?;
+    if bytes.contains(&0) {
+        return Err(anyhow!("{} looks like a binary file", path.display()));
+    }
+
+    let mut text = String::from_utf8_lossy(&bytes).to_string();
+    if metadata.len() > MAX_FILE_BYTES {
+        text.push_str(&format!("\n[... truncated, file is {} bytes]", metadata.len()));
+    }
+    Ok(text)
+}
+
+fn list_directory(path: &Path) -> Result<String> {
+    let mut entries: Vec<String> = std::fs::read_dir(path)
+        .map_err(|e| anyhow!("{}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                format!("{}/", name)
+            } else {
+                name
+            }
+        })
+        .collect();
+    entries.sort();
+
+    let total = entries.len();
+    entries.truncate(MAX_DIRECTORY_ENTRIES);
+    let mut listing = entries.join("\n");
+    if total > MAX_DIRECTORY_ENTRIES {
+        listing.push_str(&format!("\n[... {} more entries]", total - MAX_DIRECTORY_ENTRIES));
+    }
+    Ok(listing)
+}
+
+fn truncate(mut output: String) -> String {
+    if output.chars().count() > MAX_TOOL_OUTPUT_CHARS {
+        output = output.chars().take(MAX_TOOL_OUTPUT_CHARS).collect();
+        output.push_str("\n[... output truncated]");
+    }
+    output
+}
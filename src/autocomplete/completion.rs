@@ -0,0 +1,169 @@
+use super::AutocompleteItem;
+
+/// A key the completion popup may take from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKey {
+    Tab,
+    ShiftTab,
+    Up,
+    Down,
+    Enter,
+    Escape,
+}
+
+/// What a key did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// The popup used it
+    Handled,
+    /// Enter picked a completion; this is the input now
+    Accepted(String),
+    /// The popup is closed or doesn't use it, so the input gets it: Enter
+    /// with nothing selected runs the command
+    Ignored,
+}
+
+/// The completion popup under the terminal input, without the drawing:
+/// the items for the input, which one is selected, and what keys do. Tab
+/// and Shift+Tab cycle through the items, as do ↓ and ↑; Enter puts the
+/// selected one in the input; Escape closes the popup until the input
+/// changes. Typing keeps the selected item selected while it still
+/// matches.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionState {
+    /// The input the items are for
+    query: String,
+    items: Vec<AutocompleteItem>,
+    selected: Option<usize>,
+    /// Closed by Escape or by accepting, until the input changes
+    closed: bool,
+}
+
+impl CompletionState {
+    /// Whether the items are for another input than `input`.
+    pub fn is_stale(&self, input: &str) -> bool {
+        self.query != input
+    }
+
+    /// Shows `items` for `input`, dropping the ones that wouldn't change
+    /// it. A changed input reopens the popup.
+    pub fn update(&mut self, input: &str, items: Vec<AutocompleteItem>) {
+        if self.query != input {
+            self.closed = false;
+        }
+        let selected_text = self.selected_item().map(|item| item.text.clone());
+        self.query = input.to_string();
+        self.items = items
+            .into_iter()
+            .filter(|item| apply_completion(input, &item.insert_text) != input)
+            .collect();
+        self.selected = selected_text.and_then(|text| self.items.iter().position(|item| item.text == text));
+    }
+
+    /// The input the items are for.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.closed && !self.items.is_empty()
+    }
+
+    pub fn items(&self) -> &[AutocompleteItem] {
+        &self.items
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn selected_item(&self) -> Option<&AutocompleteItem> {
+        self.items.get(self.selected?)
+    }
+
+    /// Selects an item, as hovering over it does.
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.selected = Some(index);
+        }
+    }
+
+    /// What the input becomes with the item at `index`, closing the popup.
+    pub fn accept(&mut self, index: usize) -> Option<String> {
+        let item = self.items.get(index)?;
+        let input = apply_completion(&self.query, &item.insert_text);
+        self.query = input.clone();
+        self.items.clear();
+        self.selected = None;
+        self.closed = true;
+        Some(input)
+    }
+
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.selected = None;
+    }
+
+    pub fn key(&mut self, key: CompletionKey) -> KeyOutcome {
+        if !self.is_open() {
+            return KeyOutcome::Ignored;
+        }
+        let last = self.items.len() - 1;
+        match key {
+            CompletionKey::Tab | CompletionKey::Down => {
+                self.selected = Some(match self.selected {
+                    Some(index) if index < last => index + 1,
+                    _ => 0,
+                });
+            }
+            CompletionKey::ShiftTab | CompletionKey::Up => {
+                self.selected = Some(match self.selected {
+                    Some(index) if index > 0 => index - 1,
+                    _ => last,
+                });
+            }
+            CompletionKey::Enter => {
+                return match self.selected.and_then(|index| self.accept(index)) {
+                    Some(input) => KeyOutcome::Accepted(input),
+                    None => KeyOutcome::Ignored,
+                };
+            }
+            CompletionKey::Escape => self.close(),
+        }
+        KeyOutcome::Handled
+    }
+}
+
+/// `input` with a completion put in. Most providers complete the whole
+/// line, like `git checkout main` for `git checkout ma`; file names
+/// complete the path being typed, like `main.rs` for `src/ma`.
+pub fn apply_completion(input: &str, completion: &str) -> String {
+    let word_start = input.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+    let word = &input[word_start..];
+    let directory = word.rfind(['/', '\\']).map_or(0, |slash| slash + 1);
+    let kept = &input[..word_start + directory];
+    match completion.starts_with(kept) {
+        true => completion.to_string(),
+        false => format!("{}{}", kept, completion),
+    }
+}
+
+/// The icon shown before an item, by its category, and 🚩 for options.
+pub fn item_icon(item: &AutocompleteItem) -> &'static str {
+    let last_word = item.text.rsplit(char::is_whitespace).next().unwrap_or_default();
+    if last_word.starts_with('-') {
+        return "🚩";
+    }
+    match item.category.as_str() {
+        "history" => "⏱",
+        "git" => "🌿",
+        "file" => "📄",
+        "directory" => "📁",
+        "workflow" => "⚡",
+        "learned" => "✔",
+        "docker" => "🐳",
+        "kubectl" => "☸",
+        "ssh" => "🔑",
+        _ => "›",
+    }
+}
@@ -0,0 +1,148 @@
+use super::{AutocompleteContext, AutocompleteItem, AutocompleteProvider};
+use crate::docker::{self, DockerCache};
+use std::path::Path;
+use std::sync::Arc;
+
+/// `docker` subcommands whose first argument is a container.
+const CONTAINER_COMMANDS: &[&str] = &["exec", "logs", "stop", "rm", "restart", "start", "kill", "attach", "inspect"];
+
+/// `docker` subcommands whose first argument is an image.
+const IMAGE_COMMANDS: &[&str] = &["run", "rmi"];
+
+/// `docker compose` subcommands that take service names.
+const COMPOSE_COMMANDS: &[&str] = &[
+    "up", "down", "logs", "exec", "run", "restart", "stop", "start", "build", "pull", "ps", "rm", "kill",
+];
+
+/// Flags of `docker run` and `docker exec` that take no value, so the word
+/// after them is an argument.
+const BOOLEAN_FLAGS: &[&str] = &[
+    "-i", "-t", "-it", "-ti", "-d", "-dit", "--rm", "--detach", "--interactive", "--tty", "--init", "--privileged",
+    "-f", "--follow", "--force",
+];
+
+/// Completes containers, images and compose services from a `DockerCache`,
+/// which lists them in the background: suggestions use the last listing and
+/// never wait for the daemon.
+pub struct DockerProvider {
+    cache: Arc<DockerCache>,
+}
+
+impl DockerProvider {
+    pub fn new(cache: Arc<DockerCache>) -> Self {
+        Self { cache }
+    }
+}
+
+/// What the word being typed is.
+enum Target {
+    Container,
+    Image,
+    Service,
+}
+
+/// Counts the arguments in `words`, skipping flags and the values of flags
+/// that take one.
+fn positional_count(words: &[&str]) -> usize {
+    let mut count = 0;
+    let mut expecting_value = false;
+    for word in words {
+        if expecting_value {
+            expecting_value = false;
+        } else if word.starts_with('-') {
+            expecting_value = !word.contains('=') && !BOOLEAN_FLAGS.contains(word);
+        } else {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn target(done: &[&str]) -> Option<Target> {
+    match done {
+        ["docker", "compose", subcommand, rest @ ..] | ["docker-compose", subcommand, rest @ ..]
+            if COMPOSE_COMMANDS.contains(subcommand) =>
+        {
+            // Services can be listed one after another; `exec` and `run`
+            // take one, then a command
+            let single = matches!(*subcommand, "exec" | "run");
+            (!single || positional_count(rest) == 0).then_some(Target::Service)
+        }
+        ["docker", "container", subcommand, rest @ ..] | ["docker", subcommand, rest @ ..]
+            if CONTAINER_COMMANDS.contains(subcommand) =>
+        {
+            let expecting_value = rest.last().is_some_and(|word| {
+                word.starts_with('-') && !word.contains('=') && !BOOLEAN_FLAGS.contains(word)
+            });
+            (positional_count(rest) == 0 && !expecting_value).then_some(Target::Container)
+        }
+        ["docker", subcommand, rest @ ..] if IMAGE_COMMANDS.contains(subcommand) => {
+            let expecting_value = rest.last().is_some_and(|word| {
+                word.starts_with('-') && !word.contains('=') && !BOOLEAN_FLAGS.contains(word)
+            });
+            (positional_count(rest) == 0 && !expecting_value).then_some(Target::Image)
+        }
+        _ => None,
+    }
+}
+
+impl AutocompleteProvider for DockerProvider {
+    fn get_suggestions(&self, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        if context.remote || !(input.starts_with("docker ") || input.starts_with("docker-compose ")) {
+            return Vec::new();
+        }
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (done, partial) = match input.ends_with(char::is_whitespace) {
+            true => (&words[..], ""),
+            false => (&words[..words.len() - 1], words[words.len() - 1]),
+        };
+        if partial.starts_with('-') {
+            return Vec::new();
+        }
+        let Some(target) = target(done) else {
+            return Vec::new();
+        };
+
+        let prefix = &input[..input.len() - partial.len()];
+        let item = |completion: &str, description: String, priority: i32| {
+            AutocompleteItem::new(format!("{}{}", prefix, completion), description, "docker".to_string())
+                .with_priority(priority)
+        };
+        match target {
+            Target::Container => self
+                .cache
+                .snapshot()
+                .containers
+                .iter()
+                .filter_map(|container| {
+                    let completion = if container.name.starts_with(partial) {
+                        &container.name
+                    } else if container.id.starts_with(partial) {
+                        &container.id
+                    } else {
+                        return None;
+                    };
+                    Some(item(completion, format!("{} · {}", container.image, container.status), 20))
+                })
+                .collect(),
+            Target::Image => self
+                .cache
+                .snapshot()
+                .images
+                .iter()
+                .map(|image| (image.name(), image))
+                .filter(|(name, _)| name.starts_with(partial))
+                .map(|(name, image)| item(&name, format!("image · {}", image.size), 15))
+                .collect(),
+            Target::Service => docker::compose_services(Path::new(&context.current_directory))
+                .iter()
+                .filter(|service| service.starts_with(partial) && !done.contains(&service.as_str()))
+                .map(|service| item(service, "compose service".to_string(), 20))
+                .collect(),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "docker"
+    }
+}
@@ -0,0 +1,280 @@
+use super::{AutocompleteContext, AutocompleteItem, AutocompleteProvider};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Entries read per directory; the rest are summed up in a "…N more" item.
+pub const MAX_ENTRIES_PER_DIRECTORY: usize = 1000;
+
+/// How long a listing is used before its directory's mtime is checked again.
+const CACHE_TTL: Duration = Duration::from_secs(2);
+
+/// Directories kept; the least recently read are dropped first.
+const MAX_CACHED_DIRECTORIES: usize = 64;
+
+/// A directory entry, as completions need it.
+#[derive(Debug, Clone)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Up to `MAX_ENTRIES_PER_DIRECTORY` entries of a directory, sorted by name.
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryListing {
+    pub entries: Vec<DirectoryEntry>,
+    /// Entries past the limit, which weren't read
+    pub more: usize,
+}
+
+/// How `DirectoryCache` reads the filesystem. Only ever called off the
+/// thread asking for suggestions.
+pub trait DirectoryReader: Send + Sync {
+    /// The entries of `dir` that aren't hidden, at most `limit` of them.
+    fn read_dir(&self, dir: &Path, limit: usize) -> io::Result<DirectoryListing>;
+
+    fn modified(&self, dir: &Path) -> io::Result<SystemTime>;
+}
+
+/// Reads the real filesystem.
+pub struct OsDirectoryReader;
+
+impl DirectoryReader for OsDirectoryReader {
+    fn read_dir(&self, dir: &Path, limit: usize) -> io::Result<DirectoryListing> {
+        let mut listing = DirectoryListing::default();
+        for entry in std::fs::read_dir(dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.is_empty() || name.starts_with('.') {
+                continue;
+            }
+            if listing.entries.len() == limit {
+                listing.more += 1;
+                continue;
+            }
+            // Symlinks count as what they point to
+            let is_dir = match entry.file_type() {
+                Ok(file_type) if file_type.is_symlink() => entry.path().is_dir(),
+                Ok(file_type) => file_type.is_dir(),
+                Err(_) => false,
+            };
+            listing.entries.push(DirectoryEntry { name, is_dir });
+        }
+        listing.entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(listing)
+    }
+
+    fn modified(&self, dir: &Path) -> io::Result<SystemTime> {
+        std::fs::metadata(dir)?.modified()
+    }
+}
+
+#[derive(Default)]
+struct CachedDirectory {
+    listing: Option<Arc<DirectoryListing>>,
+    modified: Option<SystemTime>,
+    checked: Option<Instant>,
+    reading: bool,
+}
+
+type Directories = HashMap<PathBuf, CachedDirectory>;
+
+/// Directory listings, read on a background thread and reused until the
+/// directory changes. Lookups never touch the filesystem: a directory not
+/// read yet has no listing until its read finishes.
+pub struct DirectoryCache {
+    reader: Arc<dyn DirectoryReader>,
+    directories: Arc<Mutex<Directories>>,
+    /// Marks listings stale when their directories change; `None` if it
+    /// couldn't start. Locked apart from `directories`, which its callback
+    /// takes.
+    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+}
+
+impl DirectoryCache {
+    pub fn new(reader: Arc<dyn DirectoryReader>) -> Self {
+        let directories = Arc::new(Mutex::new(Directories::new()));
+        let watched = Arc::downgrade(&directories);
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let (Ok(event), Some(directories)) = (event, watched.upgrade()) else {
+                return;
+            };
+            let mut directories = directories.lock().unwrap();
+            for path in &event.paths {
+                for dir in [Some(path.as_path()), path.parent()].into_iter().flatten() {
+                    if let Some(cached) = directories.get_mut(dir) {
+                        cached.checked = None;
+                        cached.modified = None;
+                    }
+                }
+            }
+        })
+        .map_err(|e| log::debug!("Directory completions won't watch for changes: {}", e))
+        .ok();
+        Self {
+            reader,
+            directories,
+            watcher: Arc::new(Mutex::new(watcher)),
+        }
+    }
+
+    /// The listing of `dir` as last read, starting a read when there is none
+    /// or it may be out of date.
+    pub fn get(&self, dir: &Path) -> Option<Arc<DirectoryListing>> {
+        let mut directories = self.directories.lock().unwrap();
+        let cached = directories.entry(dir.to_path_buf()).or_default();
+        let stale = cached.checked.is_none_or(|checked| checked.elapsed() >= CACHE_TTL);
+        let listing = cached.listing.clone();
+        if stale && !cached.reading {
+            cached.reading = true;
+            let known_modified = cached.modified.filter(|_| listing.is_some());
+            drop(directories);
+            self.refresh(dir.to_path_buf(), known_modified);
+        }
+        listing
+    }
+
+    /// Reads `dir` on a background thread, unless its mtime is still
+    /// `known_modified`.
+    fn refresh(&self, dir: PathBuf, known_modified: Option<SystemTime>) {
+        let reader = self.reader.clone();
+        let directories = self.directories.clone();
+        let watcher = self.watcher.clone();
+        std::thread::spawn(move || {
+            let modified = reader.modified(&dir).ok();
+            let listing = if known_modified.is_some() && modified == known_modified {
+                None
+            } else {
+                Some(reader.read_dir(&dir, MAX_ENTRIES_PER_DIRECTORY).unwrap_or_else(|e| {
+                    log::debug!("Failed to list {}: {}", dir.display(), e);
+                    DirectoryListing::default()
+                }))
+            };
+
+            let (newly_read, evicted) = {
+                let mut directories = directories.lock().unwrap();
+                let cached = directories.entry(dir.clone()).or_default();
+                let newly_read = cached.listing.is_none();
+                if let Some(listing) = listing {
+                    cached.listing = Some(Arc::new(listing));
+                }
+                cached.modified = modified;
+                cached.checked = Some(Instant::now());
+                cached.reading = false;
+                (newly_read, evict(&mut directories))
+            };
+
+            if let Some(watcher) = watcher.lock().unwrap().as_mut() {
+                if newly_read {
+                    let _ = watcher.watch(&dir, RecursiveMode::NonRecursive);
+                }
+                for dir in evicted {
+                    let _ = watcher.unwatch(&dir);
+                }
+            }
+        });
+    }
+}
+
+/// Drops the least recently checked listings past `MAX_CACHED_DIRECTORIES`,
+/// returning their directories.
+fn evict(directories: &mut Directories) -> Vec<PathBuf> {
+    let mut evicted = Vec::new();
+    while directories.len() > MAX_CACHED_DIRECTORIES {
+        let Some(oldest) = directories
+            .iter()
+            .filter(|(_, cached)| !cached.reading)
+            .min_by_key(|(_, cached)| cached.checked)
+            .map(|(dir, _)| dir.clone())
+        else {
+            break;
+        };
+        directories.remove(&oldest);
+        evicted.push(oldest);
+    }
+    evicted
+}
+
+/// Completes file and directory names from a `DirectoryCache`, so typing
+/// never waits on a slow or huge directory.
+pub struct FileSystemProvider {
+    cache: DirectoryCache,
+}
+
+impl Default for FileSystemProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystemProvider {
+    pub fn new() -> Self {
+        Self::with_reader(Arc::new(OsDirectoryReader))
+    }
+
+    pub fn with_reader(reader: Arc<dyn DirectoryReader>) -> Self {
+        Self {
+            cache: DirectoryCache::new(reader),
+        }
+    }
+
+    /// The entries of `dir` starting with `prefix`, and a "…N more" item
+    /// typed as `input` when the directory had too many to read.
+    fn matching_entries(&self, dir: &Path, prefix: &str, input: &str) -> Vec<AutocompleteItem> {
+        let Some(listing) = self.cache.get(dir) else {
+            return Vec::new();
+        };
+
+        let mut items: Vec<AutocompleteItem> = listing
+            .entries
+            .iter()
+            .filter(|entry| entry.name.starts_with(prefix))
+            .map(|entry| {
+                let (text, category, priority) = match entry.is_dir {
+                    true => (format!("{}/", entry.name), "directory", 8),
+                    false => (entry.name.clone(), "file", 5),
+                };
+                AutocompleteItem::new(text, format!("{} ({})", entry.name, category), category.to_string())
+                    .with_priority(priority)
+            })
+            .collect();
+        if listing.more > 0 {
+            items.push(AutocompleteItem::new(
+                input.to_string(),
+                format!("…{} more not listed", listing.more),
+                "more".to_string(),
+            ));
+        }
+        items
+    }
+}
+
+impl AutocompleteProvider for FileSystemProvider {
+    fn get_suggestions(&self, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        if context.remote {
+            return Vec::new();
+        }
+        let current_directory = Path::new(&context.current_directory);
+        // Only provide file/directory completions if input looks like a path
+        if let Some((dir_part, filename_part)) = input.rsplit_once(['/', '\\']) {
+            let search_dir = match dir_part {
+                "" => current_directory.to_path_buf(),
+                dir_part => current_directory.join(dir_part),
+            };
+            return self.matching_entries(&search_dir, filename_part, input);
+        }
+
+        // For simple filenames, search in current directory
+        if !input.is_empty() && input.chars().all(|c| c == '.' || c == '_' || c == '-' || c.is_alphanumeric()) {
+            return self.matching_entries(current_directory, input, input);
+        }
+
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "filesystem"
+    }
+}
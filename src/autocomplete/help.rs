@@ -0,0 +1,448 @@
+use super::specs::{CommandSpec, OneOrMany, OptionSpec, SpecProvider};
+use super::{AutocompleteContext, AutocompleteItem, AutocompleteProvider};
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::runtime::Handle;
+
+/// `--help` or `man` taking longer than this is abandoned.
+const HELP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Help past this is cut off; flags come first anyway.
+const MAX_HELP_BYTES: u64 = 256 * 1024;
+
+/// Programs that may not treat `--help` as a request for help, or that
+/// wouldn't say anything useful.
+const NEVER_RUN: &[&str] = &[
+    "reboot", "shutdown", "halt", "poweroff", "init", "telinit", "sudo", "doas", "su", "kill", "killall", "pkill",
+    "yes", "sh", "bash", "zsh", "fish", "dash", "pwsh", "python", "python3", "node", "ruby", "perl",
+];
+
+pub fn default_help_cache_dir() -> PathBuf {
+    crate::config::data_dir().join("help_cache")
+}
+
+/// Where a binary's help stands.
+enum Ingested {
+    /// Being read in the background
+    Pending,
+    Ready(SpecProvider),
+    /// No help, or none that parsed
+    Unavailable,
+}
+
+/// Completes the flags and subcommands of binaries without a spec from
+/// their own `--help`, or failing that their man page. The first time a
+/// binary's flags are asked for, its help is read and parsed in the
+/// background, and suggestions follow on later keystrokes; the parsed spec
+/// is cached on disk until the binary changes.
+pub struct HelpIngestor {
+    cache: HelpCache,
+    runtime: Handle,
+    binaries: Arc<Mutex<HashMap<String, Ingested>>>,
+    enabled: AtomicBool,
+}
+
+impl HelpIngestor {
+    pub fn new(runtime: Handle, cache_dir: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            cache: HelpCache::new(cache_dir),
+            runtime,
+            binaries: Arc::new(Mutex::new(HashMap::new())),
+            enabled: AtomicBool::new(true),
+        })
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Starts reading `binary`'s help unless it's known already.
+    fn ingest(&self, binary: &str) {
+        let mut binaries = self.binaries.lock().unwrap();
+        if binaries.contains_key(binary) {
+            return;
+        }
+        let Ok(path) = which::which(binary) else {
+            binaries.insert(binary.to_string(), Ingested::Unavailable);
+            return;
+        };
+        if let Some(spec) = self.cache.load(&path) {
+            binaries.insert(binary.to_string(), Ingested::Ready(SpecProvider::new(spec)));
+            return;
+        }
+        binaries.insert(binary.to_string(), Ingested::Pending);
+
+        let binaries = self.binaries.clone();
+        let cache = self.cache.clone();
+        let name = binary.to_string();
+        self.runtime.spawn(async move {
+            let spec = read_spec(&name, &path).await;
+            let ingested = match spec {
+                Some(spec) => {
+                    if let Err(e) = cache.store(&path, &spec) {
+                        warn!("Couldn't cache the help of {}: {:#}", name, e);
+                    }
+                    Ingested::Ready(SpecProvider::new(spec))
+                }
+                None => {
+                    debug!("No usable help for {}", name);
+                    Ingested::Unavailable
+                }
+            };
+            binaries.lock().unwrap().insert(name, ingested);
+        });
+    }
+}
+
+impl AutocompleteProvider for HelpIngestor {
+    fn get_suggestions(&self, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        // Help is read locally, so it would be the wrong binary's remotely
+        if context.remote || !self.enabled.load(Ordering::Relaxed) {
+            return Vec::new();
+        }
+        // Only once the binary is typed and its arguments are wanted
+        let Some((binary, _)) = input.split_once(char::is_whitespace) else {
+            return Vec::new();
+        };
+        if binary.is_empty() || binary.contains(['/', '\\']) || NEVER_RUN.contains(&binary) {
+            return Vec::new();
+        }
+        self.ingest(binary);
+        match self.binaries.lock().unwrap().get(binary) {
+            Some(Ingested::Ready(provider)) => provider.get_suggestions(input, context),
+            _ => Vec::new(),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "help"
+    }
+}
+
+/// Parsed help on disk, one file per binary name, valid while the binary
+/// at `path` has the same modification time.
+#[derive(Clone)]
+pub struct HelpCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedHelp {
+    path: PathBuf,
+    modified: u64,
+    spec: CommandSpec,
+}
+
+/// Seconds since the epoch `binary` was last changed.
+fn modified(binary: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(binary).and_then(|metadata| metadata.modified()).ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|since| since.as_secs())
+}
+
+impl HelpCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn file(&self, binary: &Path) -> Option<PathBuf> {
+        let name = binary.file_name()?.to_str()?;
+        Some(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// The spec parsed from `binary`'s help, unless it changed since.
+    pub fn load(&self, binary: &Path) -> Option<CommandSpec> {
+        let content = std::fs::read_to_string(self.file(binary)?).ok()?;
+        let cached: CachedHelp = serde_json::from_str(&content).ok()?;
+        (cached.path == binary && Some(cached.modified) == modified(binary)).then_some(cached.spec)
+    }
+
+    pub fn store(&self, binary: &Path, spec: &CommandSpec) -> Result<()> {
+        let file = self.file(binary).context("The binary has no file name")?;
+        let cached = CachedHelp {
+            path: binary.to_path_buf(),
+            modified: modified(binary).context("The binary's modification time is unknown")?,
+            spec: spec.clone(),
+        };
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create help cache directory {}", self.dir.display()))?;
+        std::fs::write(&file, serde_json::to_string(&cached)?)
+            .with_context(|| format!("Failed to write {}", file.display()))
+    }
+}
+
+/// `binary`'s flags and subcommands from `--help`, or on Unix from its man
+/// page when that says nothing parseable.
+async fn read_spec(name: &str, binary: &Path) -> Option<CommandSpec> {
+    let from_help = run_for_help(binary.as_os_str(), &["--help"])
+        .await
+        .map(|help| parse_help(name, &help))
+        .filter(|spec| !is_empty(spec));
+    #[cfg(unix)]
+    if from_help.is_none() {
+        // By name: a path would be taken for a man page file
+        let page = run_for_help("man".as_ref(), &["-P", "cat", name]).await?;
+        return Some(parse_help(name, &strip_man_formatting(&page))).filter(|spec| !is_empty(spec));
+    }
+    from_help
+}
+
+fn is_empty(spec: &CommandSpec) -> bool {
+    spec.options.is_empty() && spec.subcommands.is_empty()
+}
+
+/// What `program args` prints, stdout and stderr together, as some print
+/// their usage to stderr. `None` when it can't run or takes too long.
+pub async fn run_for_help(program: &std::ffi::OsStr, args: &[&str]) -> Option<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .env("MANWIDTH", "120")
+        .env("GROFF_NO_SGR", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+    let mut stdout = child.stdout.take()?.take(MAX_HELP_BYTES);
+    let mut stderr = child.stderr.take()?.take(MAX_HELP_BYTES);
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let read = async { tokio::join!(stdout.read_to_end(&mut out), stderr.read_to_end(&mut err)) };
+    if tokio::time::timeout(HELP_TIMEOUT, read).await.is_err() {
+        debug!("{} {} timed out", program.to_string_lossy(), args.join(" "));
+        return None;
+    }
+    out.extend_from_slice(&err);
+    Some(String::from_utf8_lossy(&out).to_string())
+}
+
+/// Removes the bold and underline of a man page rendered for a terminal:
+/// overstruck `x\bx` and `_\bx`, and escape sequences.
+pub fn strip_man_formatting(page: &str) -> String {
+    let mut text = String::with_capacity(page.len());
+    let mut chars = page.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{8}' => {
+                text.pop();
+            }
+            '\u{1b}' => {
+                if chars.next_if_eq(&'[').is_some() {
+                    while chars.next().is_some_and(|c| !c.is_ascii_alphabetic()) {}
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+/// The flags and subcommands described by `help`, as printed by common
+/// `--help` layouts and man pages: `-f, --flag <ARG>  description` lines,
+/// their descriptions on the next line when the flags fill theirs, and
+/// `name  description` lines under a heading mentioning commands. Lines
+/// that match neither are skipped.
+pub fn parse_help(name: &str, help: &str) -> CommandSpec {
+    let mut spec = CommandSpec {
+        name: name.to_string(),
+        description: String::new(),
+        subcommands: Vec::new(),
+        options: Vec::new(),
+    };
+    let lines: Vec<&str> = help.lines().collect();
+    let mut in_commands = false;
+    // Entries of a section line up; deeper lines continue a description
+    let mut command_indent = None;
+    for (index, line) in lines.iter().enumerate() {
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(heading) = heading(line) {
+            in_commands = heading.to_lowercase().contains("command");
+            command_indent = None;
+            continue;
+        }
+        if trimmed.to_lowercase().starts_with("usage:") {
+            continue;
+        }
+
+        if trimmed.starts_with('-') {
+            let Some((names, arg, mut description)) = parse_option_line(trimmed) else {
+                continue;
+            };
+            // Flags too long for their column have the description below
+            if description.is_empty() {
+                description = continuation(&lines, index, indent).unwrap_or_default();
+            }
+            let (description, values) = possible_values(&description);
+            add_option(&mut spec, names, arg, description, values);
+        } else if in_commands && indent >= 2 && command_indent.is_none_or(|column| column == indent) {
+            let Some((names, mut description)) = parse_command_line(trimmed) else {
+                continue;
+            };
+            command_indent = Some(indent);
+            if description.is_empty() {
+                description = continuation(&lines, index, indent).unwrap_or_default();
+            }
+            for name in names {
+                if !spec.subcommands.iter().any(|subcommand| subcommand.name == name) {
+                    spec.subcommands.push(CommandSpec {
+                        name,
+                        description: description.clone(),
+                        subcommands: Vec::new(),
+                        options: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    spec
+}
+
+/// The text of a section heading: `Options:` or ` Main operation mode:`
+/// barely indented, or a man page's `OPTIONS`.
+fn heading(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = line.trim();
+    if indent > 1 || trimmed.starts_with('-') {
+        return None;
+    }
+    let all_caps = trimmed.chars().any(|c| c.is_alphabetic())
+        && trimmed.chars().all(|c| c.is_uppercase() || c.is_whitespace());
+    (trimmed.ends_with(':') || (indent == 0 && all_caps)).then(|| trimmed.trim_end_matches(':'))
+}
+
+/// The line after `index` when it continues the entry there: indented
+/// further and not another flag.
+fn continuation(lines: &[&str], index: usize, indent: usize) -> Option<String> {
+    let next = lines.get(index + 1)?;
+    let next_indent = next.len() - next.trim_start().len();
+    let next = next.trim();
+    (next_indent > indent && !next.is_empty() && !next.starts_with('-')).then(|| next.to_string())
+}
+
+/// Splits `-o, --output <file>  Write to file` into its spellings, its
+/// argument and its description.
+fn parse_option_line(line: &str) -> Option<(Vec<String>, Option<String>, String)> {
+    let mut rest = line;
+    let mut names = Vec::new();
+    let mut arg = None;
+    loop {
+        let name_end = rest
+            .char_indices()
+            .find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .map_or(rest.len(), |(i, _)| i);
+        let name = &rest[..name_end];
+        let dashes = name.len() - name.trim_start_matches('-').len();
+        if !(1..=2).contains(&dashes) || name.len() == dashes || name.ends_with('-') {
+            return None;
+        }
+        names.push(name.to_string());
+        rest = &rest[name_end..];
+
+        // `--file=ARCHIVE`, `--color[=WHEN]`, `--verbose...`
+        if let Some(optional) = rest.strip_prefix("[=") {
+            let end = optional.find(']')?;
+            arg = Some(optional[..end].to_string());
+            rest = &optional[end + 1..];
+        } else if let Some(value) = rest.strip_prefix('=') {
+            let end = value.find([' ', '\t', ',']).unwrap_or(value.len());
+            arg = Some(value[..end].to_string());
+            rest = &value[end..];
+        }
+        rest = rest.trim_start_matches('.');
+        // `-o <file>`, `-C DIRECTORY`
+        if let Some(bracketed) = rest.strip_prefix(" <") {
+            let end = bracketed.find('>')?;
+            arg = Some(bracketed[..end].to_string());
+            rest = bracketed[end + 1..].trim_start_matches('.');
+        } else if let Some(word) = rest.strip_prefix(' ') {
+            let end = word.find([' ', '\t', ',']).unwrap_or(word.len());
+            let candidate = &word[..end];
+            let after = &word[end..];
+            let is_placeholder = !candidate.is_empty()
+                && candidate.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == '-')
+                && candidate.starts_with(|c: char| c.is_ascii_uppercase());
+            if is_placeholder && (after.is_empty() || after.starts_with(',') || after.starts_with("  ") || after.starts_with('\t')) {
+                arg = Some(candidate.to_string());
+                rest = after;
+            }
+        }
+
+        match rest.strip_prefix(',').map(str::trim_start) {
+            Some(next) if next.starts_with('-') => rest = next,
+            _ => break,
+        }
+    }
+    // The description is separated from the flags by whitespace
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((names, arg, rest.trim().to_string()))
+}
+
+/// Splits `build, b    Compile the current package` into the command's
+/// names and its description.
+fn parse_command_line(line: &str) -> Option<(Vec<String>, String)> {
+    let (names, description) = match line.find("  ").or_else(|| line.find('\t')) {
+        Some(split) => (&line[..split], line[split..].trim()),
+        None => (line, ""),
+    };
+    let names: Vec<String> = names.split(',').map(|name| name.trim().to_string()).collect();
+    let valid = |name: &String| {
+        name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_:".contains(c))
+    };
+    names.iter().all(valid).then(|| (names, description.to_string()))
+}
+
+/// Takes clap's `[possible values: a, b]` out of a description.
+fn possible_values(description: &str) -> (String, Vec<String>) {
+    let Some(start) = description.find("[possible values: ") else {
+        return (description.to_string(), Vec::new());
+    };
+    let Some(end) = description[start..].find(']').map(|end| start + end) else {
+        return (description.to_string(), Vec::new());
+    };
+    let values = description[start + "[possible values: ".len()..end]
+        .split(',')
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    let trimmed = format!("{}{}", &description[..start], &description[end + 1..]);
+    (trimmed.trim().to_string(), values)
+}
+
+/// Adds an option, or the spellings and description an earlier mention of
+/// it lacked.
+fn add_option(spec: &mut CommandSpec, names: Vec<String>, arg: Option<String>, description: String, values: Vec<String>) {
+    if let Some(existing) = spec
+        .options
+        .iter_mut()
+        .find(|option| option.names().iter().any(|name| names.contains(name)))
+    {
+        if existing.description.is_empty() {
+            existing.description = description;
+        }
+        let mut all = existing.names().to_vec();
+        all.extend(names.into_iter().filter(|name| !existing.names().contains(name)));
+        existing.name = OneOrMany::Many(all);
+        return;
+    }
+    spec.options.push(OptionSpec {
+        name: OneOrMany::Many(names),
+        description,
+        arg,
+        values,
+    });
+}
@@ -0,0 +1,180 @@
+//! Bash syntax highlighting for the command input and the commands in
+//! blocks, with tree-sitter-bash and a highlight query of our own.
+
+use crate::terminal::shell::{self, ShellKind};
+use std::collections::HashMap;
+use std::ops::Range;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Parser, Query, QueryCursor};
+
+/// Captures are named after the `HighlightClass` they become. Later
+/// patterns win where captures overlap, as do inner nodes, so `$HOME` in a
+/// string is a variable.
+const HIGHLIGHT_QUERY: &str = r#"
+(command_name) @command
+
+[
+  "case" "do" "done" "elif" "else" "esac" "export" "fi" "for" "function"
+  "if" "in" "local" "declare" "readonly" "select" "then" "unset" "until" "while"
+] @keyword
+
+(function_definition name: (word) @command)
+
+(command argument: (word) @flag (#match? @flag "^-"))
+
+[(number) (file_descriptor)] @number
+
+["&&" "||" "|" ">" ">>" "<" "&>" "<<"] @operator
+
+[(string) (raw_string) (ansi_c_string) (heredoc_body) (heredoc_start)] @string
+
+[(simple_expansion) (expansion) (variable_name)] @variable
+
+(comment) @comment
+"#;
+
+/// How many texts' spans are kept, so redrawing the input and the blocks
+/// doesn't parse them again.
+const CACHE_SIZE: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightClass {
+    /// The program a command runs
+    Command,
+    Keyword,
+    String,
+    Variable,
+    /// An argument starting with `-`
+    Flag,
+    Comment,
+    Number,
+    Operator,
+}
+
+impl HighlightClass {
+    pub const ALL: [HighlightClass; 8] = [
+        HighlightClass::Command,
+        HighlightClass::Keyword,
+        HighlightClass::String,
+        HighlightClass::Variable,
+        HighlightClass::Flag,
+        HighlightClass::Comment,
+        HighlightClass::Number,
+        HighlightClass::Operator,
+    ];
+
+    /// The name of the query capture it comes from.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HighlightClass::Command => "command",
+            HighlightClass::Keyword => "keyword",
+            HighlightClass::String => "string",
+            HighlightClass::Variable => "variable",
+            HighlightClass::Flag => "flag",
+            HighlightClass::Comment => "comment",
+            HighlightClass::Number => "number",
+            HighlightClass::Operator => "operator",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.name() == name)
+    }
+}
+
+/// A run of text, by byte range, in one class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub range: Range<usize>,
+    pub class: HighlightClass,
+}
+
+/// Whether commands for `shell` are highlighted as bash: POSIX shells and
+/// WSL, but not fish, nushell or the Windows shells, which are left plain.
+pub fn highlights_as_bash(shell: &str) -> bool {
+    match ShellKind::detect(shell) {
+        ShellKind::Posix | ShellKind::Wsl => {
+            !matches!(shell::program_name(shell).as_str(), "fish" | "nu" | "xonsh" | "elvish")
+        }
+        ShellKind::PowerShell | ShellKind::Cmd => false,
+    }
+}
+
+pub struct SyntaxHighlighter {
+    parser: Parser,
+    query: Query,
+    /// Class of each capture of the query, by capture index
+    classes: Vec<Option<HighlightClass>>,
+    cache: HashMap<String, Vec<HighlightSpan>>,
+}
+
+impl Default for SyntaxHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntaxHighlighter {
+    pub fn new() -> Self {
+        let language = tree_sitter::Language::from(tree_sitter_bash::LANGUAGE);
+        let mut parser = Parser::new();
+        parser.set_language(&language).expect("tree-sitter-bash doesn't match the tree-sitter version");
+        let query = Query::new(&language, HIGHLIGHT_QUERY).expect("invalid bash highlight query");
+        let classes = query.capture_names().iter().map(|name| HighlightClass::from_name(name)).collect();
+        Self {
+            parser,
+            query,
+            classes,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// The highlighted runs of `text` as bash, in order and not
+    /// overlapping; text between them is plain.
+    pub fn highlight(&mut self, text: &str) -> Vec<HighlightSpan> {
+        if let Some(spans) = self.cache.get(text) {
+            return spans.clone();
+        }
+        let spans = self.parse(text);
+        if self.cache.len() >= CACHE_SIZE {
+            self.cache.clear();
+        }
+        self.cache.insert(text.to_string(), spans.clone());
+        spans
+    }
+
+    fn parse(&mut self, text: &str) -> Vec<HighlightSpan> {
+        let Some(tree) = self.parser.parse(text, None) else {
+            return Vec::new();
+        };
+
+        // Each byte takes the class of the last, innermost capture over it
+        let mut captures = Vec::new();
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&self.query, tree.root_node(), text.as_bytes());
+        while let Some(found) = matches.next() {
+            for capture in found.captures {
+                if let Some(class) = self.classes[capture.index as usize] {
+                    captures.push((capture.node.byte_range(), found.pattern_index, class));
+                }
+            }
+        }
+        captures.sort_by_key(|(range, pattern, _)| (std::cmp::Reverse(range.len()), *pattern));
+        let mut bytes: Vec<Option<HighlightClass>> = vec![None; text.len()];
+        for (range, _, class) in captures {
+            bytes[range].fill(Some(class));
+        }
+
+        let mut spans: Vec<HighlightSpan> = Vec::new();
+        for (index, class) in bytes.into_iter().enumerate() {
+            let Some(class) = class else {
+                continue;
+            };
+            match spans.last_mut() {
+                Some(span) if span.class == class && span.range.end == index => span.range.end = index + 1,
+                _ => spans.push(HighlightSpan { range: index..index + 1, class }),
+            }
+        }
+        spans
+    }
+}
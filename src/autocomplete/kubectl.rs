@@ -0,0 +1,122 @@
+use super::{AutocompleteContext, AutocompleteItem, AutocompleteProvider};
+use crate::kubectl::{self, KubectlCache, Query};
+use std::sync::Arc;
+
+/// Verbs whose first argument is a kind, then a name of that kind.
+const KIND_VERBS: &[&str] = &["get", "describe", "delete", "edit"];
+
+/// Verbs whose first argument is a pod.
+const POD_VERBS: &[&str] = &["logs", "exec", "port-forward", "attach"];
+
+/// Kinds suggested after a `KIND_VERBS` verb.
+const SUGGESTED_KINDS: &[&str] = &["pods", "deployments", "services"];
+
+/// Flags taking a value, whose value isn't an argument.
+const VALUE_FLAGS: &[&str] = &["-n", "--namespace", "--context", "-c", "--container", "-o", "--output", "-l", "--selector"];
+
+/// Completes contexts, namespaces and pod, deployment and service names from
+/// a `KubectlCache`. Names are listed in the namespace of a typed `-n`, or
+/// the current context's. Nothing is suggested until `kubectl` has answered,
+/// and nothing at all without it or a reachable cluster.
+pub struct KubectlProvider {
+    cache: Arc<KubectlCache>,
+}
+
+impl KubectlProvider {
+    pub fn new(cache: Arc<KubectlCache>) -> Self {
+        Self { cache }
+    }
+}
+
+/// What the word being typed is.
+enum Target {
+    Context,
+    Namespace,
+    Kind,
+    Resource(&'static str),
+}
+
+/// The namespace given with `-n`, `--namespace` or `--namespace=`.
+fn typed_namespace(words: &[&str]) -> Option<String> {
+    words.iter().enumerate().find_map(|(i, word)| match *word {
+        "-n" | "--namespace" => words.get(i + 1).map(|namespace| namespace.to_string()),
+        word => word.strip_prefix("--namespace=").map(str::to_string),
+    })
+}
+
+fn target(done: &[&str]) -> Option<Target> {
+    match done.last() {
+        Some(&"-n") | Some(&"--namespace") => return Some(Target::Namespace),
+        Some(&"--context") => return Some(Target::Context),
+        Some(flag) if VALUE_FLAGS.contains(flag) => return None,
+        _ => {}
+    }
+
+    // The arguments after `kubectl`, without flags and their values
+    let mut arguments = Vec::new();
+    let mut skip_value = false;
+    for word in done.iter().skip(1) {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        if word.starts_with('-') {
+            skip_value = VALUE_FLAGS.contains(word);
+        } else {
+            arguments.push(*word);
+        }
+    }
+
+    match arguments.as_slice() {
+        ["config", "use-context"] | ["config", "rename-context"] | ["config", "delete-context"] => Some(Target::Context),
+        [verb] if KIND_VERBS.contains(verb) => Some(Target::Kind),
+        [verb, kind] if KIND_VERBS.contains(verb) => kubectl::resource_kind(kind).map(Target::Resource),
+        [verb] if POD_VERBS.contains(verb) => Some(Target::Resource("pods")),
+        _ => None,
+    }
+}
+
+impl AutocompleteProvider for KubectlProvider {
+    fn get_suggestions(&self, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        if context.remote || !input.starts_with("kubectl ") {
+            return Vec::new();
+        }
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (done, partial) = match input.ends_with(char::is_whitespace) {
+            true => (&words[..], ""),
+            false => (&words[..words.len() - 1], words[words.len() - 1]),
+        };
+        if partial.starts_with('-') {
+            return Vec::new();
+        }
+        let Some(target) = target(done) else {
+            return Vec::new();
+        };
+
+        let (values, description, priority) = match target {
+            Target::Context => (self.cache.get(Query::Contexts), "context", 20),
+            Target::Namespace => (self.cache.get(Query::Namespaces), "namespace", 20),
+            Target::Kind => (SUGGESTED_KINDS.iter().map(|kind| kind.to_string()).collect(), "resource kind", 10),
+            Target::Resource(kind) => {
+                let query = Query::Resources {
+                    kind: kind.to_string(),
+                    namespace: typed_namespace(done),
+                };
+                (self.cache.get(query), kind.trim_end_matches('s'), 20)
+            }
+        };
+
+        let prefix = &input[..input.len() - partial.len()];
+        values
+            .iter()
+            .filter(|value| value.starts_with(partial))
+            .map(|value| {
+                AutocompleteItem::new(format!("{}{}", prefix, value), description.to_string(), "kubectl".to_string())
+                    .with_priority(priority)
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "kubectl"
+    }
+}
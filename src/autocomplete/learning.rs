@@ -0,0 +1,291 @@
+use super::{AutocompleteContext, AutocompleteItem, AutocompleteProvider};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Command lines and arguments kept at most; the ones used longest ago are
+/// forgotten first.
+pub const MAX_LEARNED_ENTRIES: usize = 5000;
+
+pub fn default_learned_path() -> PathBuf {
+    crate::config::data_dir().join("learned_commands.json")
+}
+
+/// How a command line or argument has fared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Learned {
+    pub successes: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub failures: u32,
+    pub last_used: DateTime<Utc>,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+impl Learned {
+    fn new(last_used: DateTime<Utc>) -> Self {
+        Self {
+            successes: 0,
+            failures: 0,
+            last_used,
+        }
+    }
+
+    /// Up with each success, to a point, and further down with each failure.
+    pub fn priority(&self) -> i32 {
+        10 + 2 * self.successes.min(10) as i32 - 5 * self.failures.min(10) as i32
+    }
+}
+
+/// What's been learned about one binary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BinaryPatterns {
+    /// Whole command lines that have succeeded
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    invocations: BTreeMap<String, Learned>,
+    /// Arguments by the argument before them, `""` for the first
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    arguments: BTreeMap<String, BTreeMap<String, Learned>>,
+}
+
+impl BinaryPatterns {
+    fn len(&self) -> usize {
+        self.invocations.len() + self.arguments.values().map(BTreeMap::len).sum::<usize>()
+    }
+}
+
+/// The command lines that worked and the arguments used with each binary,
+/// learned from finished commands and kept in a compact JSON file. Failed
+/// commands aren't learned; a learned line that fails later is demoted.
+#[derive(Debug, Clone)]
+pub struct LearnedCommands {
+    path: PathBuf,
+    binaries: BTreeMap<String, BinaryPatterns>,
+    capacity: usize,
+}
+
+impl LearnedCommands {
+    /// Nothing learned yet, saved to `path`.
+    pub fn empty(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            binaries: BTreeMap::new(),
+            capacity: MAX_LEARNED_ENTRIES,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut learned = Self::empty(path);
+        if path.exists() {
+            let content =
+                std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            learned.binaries = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid learned commands in {}", path.display()))?;
+            learned.trim();
+        }
+        Ok(learned)
+    }
+
+    /// Writes what's been learned, or removes the file when that's nothing.
+    pub fn save(&self) -> Result<()> {
+        if self.binaries.is_empty() {
+            if self.path.exists() {
+                std::fs::remove_file(&self.path)
+                    .with_context(|| format!("Failed to remove {}", self.path.display()))?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(&self.binaries)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Keeps at most `capacity` entries, forgetting the oldest now.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    /// Learns from a finished command: a success teaches its whole line and
+    /// each of its arguments, a failure demotes the line if it's known.
+    pub fn record(&mut self, command: &str, exit_code: i32, at: DateTime<Utc>) {
+        let command = command.trim();
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let Some((binary, arguments)) = words.split_first() else {
+            return;
+        };
+
+        if exit_code != 0 {
+            let invocation = self
+                .binaries
+                .get_mut(*binary)
+                .and_then(|patterns| patterns.invocations.get_mut(command));
+            if let Some(invocation) = invocation {
+                invocation.failures += 1;
+                invocation.last_used = at;
+            }
+            return;
+        }
+
+        let patterns = self.binaries.entry(binary.to_string()).or_default();
+        let learn = |learned: &mut Learned| {
+            learned.successes += 1;
+            learned.last_used = at;
+        };
+        if !arguments.is_empty() {
+            learn(patterns.invocations.entry(command.to_string()).or_insert(Learned::new(at)));
+        }
+        let mut previous = "";
+        for argument in arguments {
+            let next = patterns.arguments.entry(previous.to_string()).or_default();
+            learn(next.entry(argument.to_string()).or_insert(Learned::new(at)));
+            previous = argument;
+        }
+        self.trim();
+    }
+
+    /// Command lines of `binary` that have succeeded, most successful first.
+    pub fn invocations(&self, binary: &str) -> Vec<(&str, Learned)> {
+        let Some(patterns) = self.binaries.get(binary) else {
+            return Vec::new();
+        };
+        let mut invocations: Vec<_> = patterns
+            .invocations
+            .iter()
+            .map(|(command, learned)| (command.as_str(), *learned))
+            .collect();
+        invocations.sort_by_key(|(_, learned)| std::cmp::Reverse(learned.priority()));
+        invocations
+    }
+
+    /// Arguments given to `binary` right after `previous`, or first with
+    /// `previous` empty, most used first.
+    pub fn next_arguments(&self, binary: &str, previous: &str) -> Vec<(&str, Learned)> {
+        let Some(next) = self.binaries.get(binary).and_then(|patterns| patterns.arguments.get(previous)) else {
+            return Vec::new();
+        };
+        let mut arguments: Vec<_> = next.iter().map(|(argument, learned)| (argument.as_str(), *learned)).collect();
+        arguments.sort_by_key(|(_, learned)| std::cmp::Reverse(learned.successes));
+        arguments
+    }
+
+    /// Command lines and arguments learned.
+    pub fn len(&self) -> usize {
+        self.binaries.values().map(BinaryPatterns::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.binaries.is_empty()
+    }
+
+    /// Forgets everything; `save` then removes the file.
+    pub fn clear(&mut self) {
+        self.binaries.clear();
+    }
+
+    /// Forgets the entries used longest ago until there are at most
+    /// `capacity`.
+    fn trim(&mut self) {
+        let excess = self.len().saturating_sub(self.capacity);
+        if excess > 0 {
+            let mut last_used: Vec<DateTime<Utc>> = self
+                .binaries
+                .values()
+                .flat_map(|patterns| {
+                    let arguments = patterns.arguments.values().flat_map(BTreeMap::values);
+                    patterns.invocations.values().chain(arguments).map(|learned| learned.last_used)
+                })
+                .collect();
+            last_used.sort();
+            // Entries used at this time or before go, so ties can take a
+            // few more than needed
+            let cutoff = last_used[excess - 1];
+            for patterns in self.binaries.values_mut() {
+                patterns.invocations.retain(|_, learned| learned.last_used > cutoff);
+                for next in patterns.arguments.values_mut() {
+                    next.retain(|_, learned| learned.last_used > cutoff);
+                }
+                patterns.arguments.retain(|_, next| !next.is_empty());
+            }
+        }
+        self.binaries.retain(|_, patterns| patterns.len() > 0);
+    }
+}
+
+/// Suggests from `LearnedCommands`: command lines of the typed binary that
+/// worked before, like a whole `docker run` with its flags, and the next
+/// argument from the ones that followed the previous one, like branches
+/// after `git checkout`.
+pub struct ArgumentPatternProvider {
+    learned: Arc<RwLock<LearnedCommands>>,
+}
+
+impl ArgumentPatternProvider {
+    pub fn new(learned: Arc<RwLock<LearnedCommands>>) -> Self {
+        Self { learned }
+    }
+}
+
+impl AutocompleteProvider for ArgumentPatternProvider {
+    fn get_suggestions(&self, input: &str, _context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        let input = input.trim_start();
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (done, partial) = match input.ends_with(char::is_whitespace) {
+            true => (&words[..], ""),
+            false => match words.split_last() {
+                Some((partial, done)) => (done, *partial),
+                None => return Vec::new(),
+            },
+        };
+        // Still typing the binary
+        let Some(binary) = done.first() else {
+            return Vec::new();
+        };
+
+        let learned = self.learned.read().unwrap();
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+        for (command, learned) in learned.invocations(binary) {
+            if command.starts_with(input) && command != input.trim_end() && seen.insert(command.to_string()) {
+                items.push(
+                    AutocompleteItem::new(command.to_string(), worked(learned), "learned".to_string())
+                        .with_priority(learned.priority()),
+                );
+            }
+        }
+
+        let previous = done[1..].last().copied().unwrap_or("");
+        let prefix = &input[..input.len() - partial.len()];
+        for (argument, learned) in learned.next_arguments(binary, previous) {
+            let text = format!("{}{}", prefix, argument);
+            if argument.starts_with(partial) && argument != partial && seen.insert(text.clone()) {
+                let description = match previous {
+                    "" => format!("Used with {} {}×", binary, learned.successes),
+                    previous => format!("Used after {} {}×", previous, learned.successes),
+                };
+                items.push(
+                    AutocompleteItem::new(text, description, "learned".to_string()).with_priority(learned.priority()),
+                );
+            }
+        }
+        items
+    }
+
+    fn name(&self) -> &str {
+        "learned"
+    }
+}
+
+fn worked(learned: Learned) -> String {
+    match learned.failures {
+        0 => format!("Worked {}×", learned.successes),
+        failures => format!("Worked {}×, failed {}×", learned.successes, failures),
+    }
+}
@@ -1,8 +1,25 @@
+use chrono::{DateTime, Duration, Utc};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tree_sitter::Parser;
+use crate::terminal::remote::{self, SshHost};
+use crate::workflows::WorkflowStore;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+pub mod completion;
+pub mod docker;
+pub mod filesystem;
+pub mod help;
+pub mod highlight;
+pub mod kubectl;
+pub mod learning;
+pub mod specs;
+
+pub use filesystem::FileSystemProvider;
+pub use help::HelpIngestor;
+pub use highlight::SyntaxHighlighter;
+pub use learning::{ArgumentPatternProvider, LearnedCommands};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutocompleteItem {
@@ -38,11 +55,64 @@ impl AutocompleteItem {
     }
 }
 
+/// Score added for a frecency of `e - 1`; see `frecency_bonus`.
+const FRECENCY_WEIGHT: f64 = 8.0;
+
+/// Score taken off a command that has always failed; see `failure_penalty`.
+const FAILURE_PENALTY: f64 = 30.0;
+
+/// How often and how recently a command was run, and how often it failed.
+#[derive(Debug, Clone, Copy)]
+struct CommandUsage {
+    count: u32,
+    last_used: DateTime<Utc>,
+    failures: u32,
+}
+
+impl CommandUsage {
+    /// Runs of the command, weighted by how long ago it last ran.
+    fn frecency(&self, now: DateTime<Utc>) -> f64 {
+        let age = now - self.last_used;
+        let weight = if age < Duration::hours(1) {
+            4.0
+        } else if age < Duration::days(1) {
+            2.0
+        } else if age < Duration::weeks(1) {
+            1.0
+        } else {
+            0.5
+        };
+        self.count as f64 * weight
+    }
+
+    /// In proportion to how many of its runs failed, so a command that
+    /// works again climbs back.
+    fn failure_penalty(&self) -> i64 {
+        let runs = self.count.max(self.failures).max(1);
+        (self.failures as f64 / runs as f64 * FAILURE_PENALTY) as i64
+    }
+}
+
+/// Grows slowly, so a habit nudges a suggestion up without drowning out a
+/// much better fuzzy match.
+fn frecency_bonus(frecency: f64) -> i64 {
+    (frecency.ln_1p() * FRECENCY_WEIGHT) as i64
+}
+
 pub struct AutocompleteEngine {
     matcher: SkimMatcherV2,
     command_providers: Vec<Box<dyn AutocompleteProvider>>,
     user_history: Vec<String>,
+    usage: HashMap<String, CommandUsage>,
     max_suggestions: usize,
+    /// Completes binaries no spec covers
+    help: Option<Arc<HelpIngestor>>,
+}
+
+impl Default for AutocompleteEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AutocompleteEngine {
@@ -51,7 +121,9 @@ impl AutocompleteEngine {
             matcher: SkimMatcherV2::default(),
             command_providers: Vec::new(),
             user_history: Vec::new(),
+            usage: HashMap::new(),
             max_suggestions: 10,
+            help: None,
         };
 
         // Add built-in providers
@@ -59,6 +131,7 @@ impl AutocompleteEngine {
         engine.add_provider(Box::new(GitCommandProvider::new()));
         engine.add_provider(Box::new(FileSystemProvider::new()));
         engine.add_provider(Box::new(HistoryProvider::new()));
+        engine.add_provider(Box::new(SshHostProvider::new(remote::load_ssh_hosts())));
 
         engine
     }
@@ -67,6 +140,17 @@ impl AutocompleteEngine {
         self.command_providers.push(provider);
     }
 
+    /// Completes the flags and subcommands of binaries without a spec from
+    /// their help.
+    pub fn set_help_ingestor(&mut self, ingestor: Arc<HelpIngestor>) {
+        self.help = Some(ingestor);
+    }
+
+    /// Drops the providers `keep` returns false for.
+    pub fn retain_providers(&mut self, mut keep: impl FnMut(&dyn AutocompleteProvider) -> bool) {
+        self.command_providers.retain(|provider| keep(provider.as_ref()));
+    }
+
     pub fn get_suggestions(
         &self,
         input: &str,
@@ -80,35 +164,98 @@ impl AutocompleteEngine {
             all_suggestions.append(&mut provider_suggestions);
         }
 
-        // Score and sort suggestions
+        // A spec describes its binary better than its help does
+        if let Some(help) = &self.help {
+            let command = input.split_whitespace().next().unwrap_or_default();
+            let has_spec = self
+                .command_providers
+                .iter()
+                .any(|provider| specs::is_spec_for(provider.as_ref(), command));
+            if !has_spec {
+                let suggested: HashSet<String> = all_suggestions.iter().map(|item| item.text.clone()).collect();
+                all_suggestions.extend(
+                    help.get_suggestions(input, context)
+                        .into_iter()
+                        .filter(|item| !suggested.contains(&item.text)),
+                );
+            }
+        }
+
+        // Score and sort suggestions; frecency boosts commands the user runs
+        // often and breaks ties, failing commands sink
+        let now = Utc::now();
         let mut scored_suggestions: Vec<_> = all_suggestions
             .into_iter()
             .filter_map(|item| {
-                self.matcher
-                    .fuzzy_match(&item.text, input)
-                    .map(|score| (item.clone(), score + item.priority as i64))
+                let frecency = self.frecency(&item.text, now);
+                let penalty = self.usage.get(&item.text).map_or(0, CommandUsage::failure_penalty);
+                self.matcher.fuzzy_match(&item.text, input).map(|score| {
+                    let score = score + item.priority as i64 + frecency_bonus(frecency) - penalty;
+                    (item, score, frecency)
+                })
             })
             .collect();
 
-        scored_suggestions.sort_by(|a, b| b.1.cmp(&a.1));
+        scored_suggestions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.2.total_cmp(&a.2)));
 
         // Return top suggestions
         scored_suggestions
             .into_iter()
             .take(self.max_suggestions)
-            .map(|(item, _)| item)
+            .map(|(item, _, _)| item)
             .collect()
     }
 
-    pub fn add_to_history(&mut self, command: String) {
-        if !command.trim().is_empty() && !self.user_history.contains(&command) {
+    /// Records `runs` runs of `command`, the last at `last_run`. Loading the
+    /// saved history through here restores frecency between sessions.
+    pub fn add_to_history(&mut self, command: String, runs: u32, last_run: DateTime<Utc>) {
+        if command.trim().is_empty() {
+            return;
+        }
+
+        let usage = self.usage.entry(command.clone()).or_insert(CommandUsage {
+            count: 0,
+            last_used: last_run,
+            failures: 0,
+        });
+        usage.count += runs;
+        usage.last_used = usage.last_used.max(last_run);
+
+        if !self.user_history.contains(&command) {
             self.user_history.push(command);
             if self.user_history.len() > 1000 {
-                self.user_history.remove(0);
+                let removed = self.user_history.remove(0);
+                self.usage.remove(&removed);
             }
         }
     }
 
+    /// Records how a run of `command` from the history ended; failures
+    /// demote it.
+    pub fn record_result(&mut self, command: &str, exit_code: i32) {
+        if exit_code == 0 {
+            return;
+        }
+        if let Some(usage) = self.usage.get_mut(command) {
+            usage.failures += 1;
+        }
+    }
+
+    fn frecency(&self, command: &str, now: DateTime<Utc>) -> f64 {
+        self.usage
+            .get(command)
+            .map(|usage| usage.frecency(now))
+            .unwrap_or(0.0)
+    }
+
+    /// The commands the providers know, without history.
+    pub fn known_commands(&self) -> Vec<String> {
+        self.command_providers
+            .iter()
+            .flat_map(|provider| provider.known_commands())
+            .collect()
+    }
+
     pub fn get_history(&self) -> &[String] {
         &self.user_history
     }
@@ -125,6 +272,9 @@ pub struct AutocompleteContext {
     pub recent_commands: Vec<String>,
     pub git_repository: bool,
     pub file_extensions: Vec<String>,
+    /// The session runs its commands on another host, so local files mean
+    /// nothing
+    pub remote: bool,
 }
 
 impl AutocompleteContext {
@@ -135,6 +285,7 @@ impl AutocompleteContext {
             recent_commands: Vec::new(),
             git_repository: false,
             file_extensions: Vec::new(),
+            remote: false,
         }
     }
 
@@ -152,17 +303,34 @@ impl AutocompleteContext {
         self.file_extensions = extensions;
         self
     }
+
+    pub fn with_remote(mut self, remote: bool) -> Self {
+        self.remote = remote;
+        self
+    }
 }
 
-pub trait AutocompleteProvider {
+pub trait AutocompleteProvider: Send + Sync {
     fn get_suggestions(&self, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem>;
     fn name(&self) -> &str;
+
+    /// Commands the provider completes, like `ls` or `git status`, for
+    /// correcting misspelled ones.
+    fn known_commands(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 pub struct BuiltinCommandProvider {
     commands: HashMap<String, AutocompleteItem>,
 }
 
+impl Default for BuiltinCommandProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BuiltinCommandProvider {
     pub fn new() -> Self {
         let mut commands = HashMap::new();
@@ -250,12 +418,22 @@ impl AutocompleteProvider for BuiltinCommandProvider {
     fn name(&self) -> &str {
         "builtin"
     }
+
+    fn known_commands(&self) -> Vec<String> {
+        self.commands.keys().cloned().collect()
+    }
 }
 
 pub struct GitCommandProvider {
     commands: HashMap<String, AutocompleteItem>,
 }
 
+impl Default for GitCommandProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GitCommandProvider {
     pub fn new() -> Self {
         let mut commands = HashMap::new();
@@ -322,95 +500,58 @@ impl AutocompleteProvider for GitCommandProvider {
     fn name(&self) -> &str {
         "git"
     }
-}
-
-pub struct FileSystemProvider;
 
-impl FileSystemProvider {
-    pub fn new() -> Self {
-        Self
+    fn known_commands(&self) -> Vec<String> {
+        self.commands.keys().cloned().collect()
     }
+}
 
-    fn get_directory_entries(&self, dir_path: &str) -> Vec<AutocompleteItem> {
-        let mut entries = Vec::new();
-
-        if let Ok(dir_entries) = std::fs::read_dir(dir_path) {
-            for entry in dir_entries.flatten() {
-                let path = entry.path();
-                let name = path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                if !name.is_empty() && !name.starts_with('.') {
-                    let is_dir = path.is_dir();
-                    let display_name = if is_dir {
-                        format!("{}/", name)
-                    } else {
-                        name.clone()
-                    };
-
-                    let category = if is_dir { "directory" } else { "file" };
-                    let description = format!("{} ({})", name, category);
-
-                    entries.push(
-                        AutocompleteItem::new(display_name, description, category.to_string())
-                            .with_priority(if is_dir { 8 } else { 5 }),
-                    );
-                }
-            }
-        }
+/// Completes `ssh <host>` with the hosts in `~/.ssh/config`.
+pub struct SshHostProvider {
+    hosts: Vec<SshHost>,
+}
 
-        entries.sort_by(|a, b| a.text.cmp(&b.text));
-        entries
+impl SshHostProvider {
+    pub fn new(hosts: Vec<SshHost>) -> Self {
+        Self { hosts }
     }
 }
 
-impl AutocompleteProvider for FileSystemProvider {
-    fn get_suggestions(&self, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem> {
-        // Only provide file/directory completions if input looks like a path
-        if input.contains('/') || input.contains('\\') {
-            let path_parts: Vec<&str> = input.rsplitn(2, |c| c == '/' || c == '\\').collect();
-            if path_parts.len() == 2 {
-                let (filename_part, dir_part) = (path_parts[0], path_parts[1]);
-                let search_dir = if dir_part.is_empty() {
-                    context.current_directory.clone()
-                } else {
-                    format!("{}/{}", context.current_directory, dir_part)
-                };
-
-                return self
-                    .get_directory_entries(&search_dir)
-                    .into_iter()
-                    .filter(|item| item.text.starts_with(filename_part))
-                    .collect();
-            }
-        }
-
-        // For simple filenames, search in current directory
-        if !input.is_empty()
-            && input
-                .chars()
-                .all(|c| c == '.' || c == '_' || c == '-' || c.is_alphanumeric())
-        {
-            return self
-                .get_directory_entries(&context.current_directory)
-                .into_iter()
-                .filter(|item| item.text.starts_with(input))
-                .collect();
+impl AutocompleteProvider for SshHostProvider {
+    fn get_suggestions(&self, input: &str, _context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        let Some(partial) = input.strip_prefix("ssh ") else {
+            return Vec::new();
+        };
+        if partial.contains(char::is_whitespace) {
+            return Vec::new();
         }
-
-        Vec::new()
+        self.hosts
+            .iter()
+            .filter(|host| host.alias.starts_with(partial))
+            .map(|host| {
+                AutocompleteItem::new(
+                    format!("ssh {}", host.alias),
+                    host.destination().unwrap_or_else(|| "From ~/.ssh/config".to_string()),
+                    "ssh".to_string(),
+                )
+                .with_priority(15)
+            })
+            .collect()
     }
 
     fn name(&self) -> &str {
-        "filesystem"
+        "ssh"
     }
 }
 
 pub struct HistoryProvider;
 
+impl Default for HistoryProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HistoryProvider {
     pub fn new() -> Self {
         Self
@@ -440,78 +581,59 @@ impl AutocompleteProvider for HistoryProvider {
     }
 }
 
-pub struct SyntaxHighlighter {
-    parsers: HashMap<String, Parser>,
+/// Saved workflows, offered when the input starts with `!`. A suggestion's
+/// `text` is `!name` and its `insert_text` the workflow's command, with
+/// placeholders still to fill in.
+pub struct WorkflowProvider {
+    workflows: Arc<RwLock<WorkflowStore>>,
+    matcher: SkimMatcherV2,
 }
 
-impl SyntaxHighlighter {
-    pub fn new() -> Self {
-        let mut highlighter = Self {
-            parsers: HashMap::new(),
-        };
-
-        // Initialize parsers for supported languages
-        // TODO: Revisit tree_sitter_bash integration due to LanguageFn error
-        // Temporarily commented out to allow compilation
-        /*
-        highlighter.parsers.insert(
-            "bash".to_string(),
-            {
-                let mut parser = Parser::new();
-                parser.set_language(unsafe { LANGUAGE() }).expect("Failed to set bash language");
-                parser
-            }
-        );
-        */
-
-        highlighter
-    }
-
-    pub fn highlight(&mut self, text: &str, language: &str) -> Vec<(usize, usize, String)> {
-        // Returns (start, end, class) tuples for highlighting
-        let mut highlights = Vec::new();
-
-        if let Some(parser) = self.parsers.get_mut(language) {
-            if let Some(tree) = parser.parse(text, None) {
-                // This is a simplified highlighter - in a real implementation,
-                // you'd use tree-sitter queries to extract syntax highlighting information
-                let root_node = tree.root_node();
-                self.highlight_node(root_node, text.as_bytes(), &mut highlights);
-            }
+impl WorkflowProvider {
+    pub fn new(workflows: Arc<RwLock<WorkflowStore>>) -> Self {
+        Self {
+            workflows,
+            matcher: SkimMatcherV2::default(),
         }
-
-        highlights
     }
+}
 
-    fn highlight_node(
-        &self,
-        node: tree_sitter::Node,
-        source: &[u8],
-        highlights: &mut Vec<(usize, usize, String)>,
-    ) {
-        let start = node.start_byte();
-        let end = node.end_byte();
-        let kind = node.kind();
-
-        // Map node kinds to CSS classes
-        let class = match kind {
-            "comment" => "comment",
-            "string" => "string",
-            "number" => "number",
-            "identifier" => "identifier",
-            "keyword" => "keyword",
-            _ => "default",
+impl AutocompleteProvider for WorkflowProvider {
+    fn get_suggestions(&self, input: &str, _context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        let Some(query) = input.strip_prefix('!') else {
+            return Vec::new();
         };
+        let workflows = self.workflows.read().unwrap();
+        let mut scored: Vec<_> = workflows
+            .workflows()
+            .iter()
+            .filter_map(|workflow| {
+                let score = if query.is_empty() {
+                    0
+                } else {
+                    self.matcher
+                        .fuzzy_match(&format!("{} {}", workflow.name, workflow.description), query)?
+                };
+                Some((workflow, score))
+            })
+            .collect();
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
 
-        if class != "default" {
-            highlights.push((start, end, class.to_string()));
-        }
+        scored
+            .into_iter()
+            .map(|(workflow, score)| {
+                AutocompleteItem::new(
+                    format!("!{}", workflow.name),
+                    workflow.description.clone(),
+                    "workflow".to_string(),
+                )
+                .with_snippet(workflow.command.clone())
+                .with_priority(score.min(i32::MAX as i64) as i32)
+            })
+            .collect()
+    }
 
-        // Recursively highlight child nodes
-        for i in 0..node.child_count() {
-            if let Some(child) = node.child(i) {
-                self.highlight_node(child, source, highlights);
-            }
-        }
+    fn name(&self) -> &str {
+        "workflows"
     }
 }
@@ -0,0 +1,258 @@
+use super::{AutocompleteContext, AutocompleteEngine, AutocompleteItem, AutocompleteProvider};
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Prefix of the names of providers built from spec files, so they can be
+/// swapped out on reload.
+const SPEC_PROVIDER_PREFIX: &str = "spec:";
+
+/// Changes closer together than this are reloaded once.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// A command's completions, declared in a JSON file: a simplified Fig spec.
+///
+/// ```json
+/// {
+///   "name": "kubectl",
+///   "description": "Kubernetes CLI",
+///   "subcommands": [{ "name": "logs", "options": [{ "name": ["-f", "--follow"] }] }],
+///   "options": [{ "name": ["-n", "--namespace"], "arg": "namespace", "values": ["default", "kube-system"] }]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub subcommands: Vec<CommandSpec>,
+    #[serde(default)]
+    pub options: Vec<OptionSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionSpec {
+    /// One spelling, or several like `["-n", "--namespace"]`
+    pub name: OneOrMany,
+    #[serde(default)]
+    pub description: String,
+    /// Name of the option's argument; options without one are flags
+    #[serde(default)]
+    pub arg: Option<String>,
+    /// Suggested values for the argument
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OptionSpec {
+    pub fn names(&self) -> &[String] {
+        match &self.name {
+            OneOrMany::One(name) => std::slice::from_ref(name),
+            OneOrMany::Many(names) => names,
+        }
+    }
+}
+
+impl CommandSpec {
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty() || self.name.contains(char::is_whitespace) {
+            bail!("'{}' isn't a valid command name", self.name);
+        }
+        for option in &self.options {
+            if option.names().is_empty() || option.names().iter().any(|name| !name.starts_with('-')) {
+                bail!("Options of '{}' must be spelled with a leading '-'", self.name);
+            }
+        }
+        self.subcommands.iter().try_for_each(CommandSpec::validate)
+    }
+
+    fn option(&self, name: &str) -> Option<&OptionSpec> {
+        self.options.iter().find(|option| option.names().iter().any(|n| n == name))
+    }
+}
+
+/// Completes one command's subcommands, options and option values from its
+/// spec.
+pub struct SpecProvider {
+    spec: CommandSpec,
+    name: String,
+}
+
+impl SpecProvider {
+    pub fn new(spec: CommandSpec) -> Self {
+        Self {
+            name: format!("{}{}", SPEC_PROVIDER_PREFIX, spec.name),
+            spec,
+        }
+    }
+}
+
+impl AutocompleteProvider for SpecProvider {
+    fn get_suggestions(&self, input: &str, _context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let Some((&command, _)) = words.split_first() else {
+            return Vec::new();
+        };
+        // The word being typed, empty after a space
+        let (done, partial) = match input.ends_with(char::is_whitespace) {
+            true => (&words[..], ""),
+            false => (&words[..words.len() - 1], words[words.len() - 1]),
+        };
+        if done.is_empty() {
+            return match self.spec.name.starts_with(partial) {
+                true => vec![AutocompleteItem::new(self.spec.name.clone(), self.spec.description.clone(), "spec".to_string())
+                    .with_priority(10)],
+                false => Vec::new(),
+            };
+        }
+        if command != self.spec.name {
+            return Vec::new();
+        }
+
+        // Follow the subcommands typed so far, noting an option still waiting
+        // for its argument
+        let mut node = &self.spec;
+        let mut pending_option = None;
+        for word in &done[1..] {
+            if pending_option.take().is_some() {
+                continue;
+            }
+            if let Some(option) = node.option(word) {
+                pending_option = option.arg.as_ref().map(|_| option);
+            } else if let Some(subcommand) = node.subcommands.iter().find(|s| s.name == *word) {
+                node = subcommand;
+            }
+        }
+
+        let prefix = &input[..input.len() - partial.len()];
+        let item = |completion: &str, description: &str, priority: i32| {
+            AutocompleteItem::new(format!("{}{}", prefix, completion), description.to_string(), "spec".to_string())
+                .with_priority(priority)
+        };
+
+        if let Some(option) = pending_option {
+            return option
+                .values
+                .iter()
+                .filter(|value| value.starts_with(partial))
+                .map(|value| item(value, option.arg.as_deref().unwrap_or_default(), 20))
+                .collect();
+        }
+
+        let mut items = Vec::new();
+        if !partial.starts_with('-') {
+            items.extend(
+                node.subcommands
+                    .iter()
+                    .filter(|subcommand| subcommand.name.starts_with(partial))
+                    .map(|subcommand| item(&subcommand.name, &subcommand.description, 15)),
+            );
+        }
+        if partial.is_empty() || partial.starts_with('-') {
+            items.extend(
+                node.options
+                    .iter()
+                    .filter(|option| !option.names().iter().any(|name| done.contains(&name.as_str())))
+                    .flat_map(|option| option.names().iter().map(move |name| (name, option)))
+                    .filter(|(name, _)| name.starts_with(partial))
+                    .map(|(name, option)| item(name, &option.description, 10)),
+            );
+        }
+        items
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn known_commands(&self) -> Vec<String> {
+        let subcommands = self
+            .spec
+            .subcommands
+            .iter()
+            .map(|subcommand| format!("{} {}", self.spec.name, subcommand.name));
+        std::iter::once(self.spec.name.clone()).chain(subcommands).collect()
+    }
+}
+
+pub fn default_specs_dir() -> PathBuf {
+    crate::config::config_dir().join("completions")
+}
+
+/// Whether `provider` was built from the spec of `command`.
+pub(super) fn is_spec_for(provider: &dyn AutocompleteProvider, command: &str) -> bool {
+    provider.name().strip_prefix(SPEC_PROVIDER_PREFIX) == Some(command)
+}
+
+/// Reads every `*.json` spec in `dir`, skipping (and logging) invalid ones.
+pub fn load_specs(dir: &Path) -> Vec<CommandSpec> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let spec = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| serde_json::from_str::<CommandSpec>(&content).map_err(Into::into))
+                .and_then(|spec| spec.validate().map(|_| spec));
+            match spec {
+                Ok(spec) => Some(spec),
+                Err(e) => {
+                    warn!("Skipping invalid completion spec {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Replaces the engine's spec providers with ones built from the specs in
+/// `dir`.
+pub fn install_specs(engine: &mut AutocompleteEngine, dir: &Path) {
+    let specs = load_specs(dir);
+    engine.retain_providers(|provider| !provider.name().starts_with(SPEC_PROVIDER_PREFIX));
+    info!("Loaded {} completion specs from {}", specs.len(), dir.display());
+    for spec in specs {
+        engine.add_provider(Box::new(SpecProvider::new(spec)));
+    }
+}
+
+/// Reloads the specs whenever something in `dir` changes, for as long as the
+/// returned watcher is kept.
+pub fn watch_specs(dir: &Path, engine: Arc<RwLock<AutocompleteEngine>>) -> Result<RecommendedWatcher> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create completion specs directory {}", dir.display()))?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    let dir = dir.to_path_buf();
+    std::thread::spawn(move || {
+        // Ends when the watcher, and with it the sender, is dropped
+        while rx.recv().is_ok() {
+            while rx.recv_timeout(RELOAD_DEBOUNCE).is_ok() {}
+            install_specs(&mut engine.blocking_write(), &dir);
+        }
+    });
+    Ok(watcher)
+}
@@ -0,0 +1,514 @@
+use crate::ai::{AiConfig, AiRequestOptions};
+use crate::logging::LoggingConfig;
+use crate::security::command_analysis;
+use crate::security::custom_rules::CustomRuleScanner;
+use crate::security::secrets::PrivacyConfig;
+use crate::security::SecurityConfig;
+use crate::terminal::audit::AuditConfig;
+use crate::terminal::output_classifier::OutputClassifiers;
+use crate::terminal::{editor, TerminalConfig};
+use anyhow::{bail, Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub mod profiles;
+
+use profiles::ActiveProfile;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Name used in the welcome greeting; defaults to the OS username.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<String>,
+    /// Profile used when `--profile` doesn't name one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub ai: AiConfig,
+    pub security: SecurityConfig,
+    pub terminal: TerminalConfig,
+    pub workspaces: WorkspaceConfig,
+    pub audit: AuditConfig,
+    pub logging: LoggingConfig,
+    pub privacy: PrivacyConfig,
+    pub accessibility: AccessibilityConfig,
+    /// `[profiles.<name>]` tables overriding `ai`, `security` and
+    /// `terminal`; see `profiles`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, toml::Table>,
+    /// The profile applied to these settings, if any
+    #[serde(skip)]
+    pub active_profile: Option<ActiveProfile>,
+    /// Where this config was loaded from and where `save` writes back to.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Keys in the loaded file that aren't settings, like `ai.modle`
+    #[serde(skip)]
+    unknown_keys: Vec<String>,
+    /// Why the API key couldn't be loaded from `ai.api_key_source`
+    #[serde(skip)]
+    api_key_error: Option<String>,
+}
+
+impl Config {
+    /// Loads the config from `path`, or from the default location when `None`.
+    /// A missing file is not an error: defaults are returned and the file is
+    /// created on the first `save`.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        Self::read(path, ProfileChoice::FromFile)
+    }
+
+    /// Loads the config like `load`, with the profile `profile` applied, or
+    /// the file's `profile` when `None`. A profile the file doesn't have is
+    /// an error.
+    pub fn load_profile(path: Option<&Path>, profile: Option<&str>) -> Result<Self> {
+        match profile {
+            Some(name) => Self::read(path, ProfileChoice::Named(name)),
+            None => Self::read(path, ProfileChoice::FromFile),
+        }
+    }
+
+    /// Loads this config's file again with the profile `profile` applied, or
+    /// none at all, for switching profiles while running.
+    pub fn reload_with_profile(&self, profile: Option<&str>) -> Result<Self> {
+        let choice = match profile {
+            Some(name) => ProfileChoice::Named(name),
+            None => ProfileChoice::Without,
+        };
+        Self::read(self.path.as_deref(), choice)
+    }
+
+    /// Why the API key couldn't be loaded from `ai.api_key_source`, when it
+    /// couldn't; `ai.api_key` then holds the environment variable's.
+    pub fn api_key_error(&self) -> Option<&str> {
+        self.api_key_error.as_deref()
+    }
+
+    /// The names of the configured profiles, sorted.
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    fn read(path: Option<&Path>, profile: ProfileChoice) -> Result<Self> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None => default_config_path(),
+        };
+
+        let mut config = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file {}", path.display()))?;
+            info!("Loaded configuration from {}", path.display());
+            let mut config: Config = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+            config.unknown_keys = unknown_keys(&content);
+            config
+        } else {
+            debug!("No config file at {}, using defaults", path.display());
+            Config::default()
+        };
+
+        let profile = match profile {
+            ProfileChoice::FromFile => config.profile.clone(),
+            ProfileChoice::Named(name) => Some(name.to_string()),
+            ProfileChoice::Without => None,
+        };
+        if let Some(name) = profile {
+            config = config.with_profile(&name)?;
+            info!("Using the {} profile", name);
+        }
+
+        if let Err(e) = config.ai.load_api_key() {
+            warn!(
+                "Couldn't load the API key from {}, using {} instead: {:#}",
+                config.ai.api_key_source,
+                crate::ai::api_key::API_KEY_ENV_VAR,
+                e
+            );
+            config.api_key_error = Some(format!("{:#}", e));
+        }
+
+        config.path = Some(path);
+        Ok(config)
+    }
+
+    /// Checks the settings serde can't: ranges, paths that must exist, the
+    /// shell, and keys that aren't settings at all (usually typos, which would
+    /// otherwise quietly fall back to defaults). The error lists every problem.
+    pub fn validate(&self) -> Result<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        let location = self
+            .path
+            .as_ref()
+            .map(|path| format!(" in {}", path.display()))
+            .unwrap_or_default();
+        bail!(
+            "{} problem{} found{}:\n{}",
+            problems.len(),
+            if problems.len() == 1 { "" } else { "s" },
+            location,
+            problems
+                .iter()
+                .map(|problem| format!("  - {}", problem))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .unknown_keys
+            .iter()
+            .map(|key| format!("`{}` is not a setting", key))
+            .collect();
+
+        let ai = &self.ai;
+        if let Some(error) = &self.api_key_error {
+            problems.push(format!("`ai.api_key_source`: {}", error));
+        }
+        if ai.model.trim().is_empty() {
+            problems.push("`ai.model` must not be empty".to_string());
+        }
+        check_temperature(&mut problems, "ai.temperature", ai.temperature);
+        if ai.max_tokens == 0 {
+            problems.push("`ai.max_tokens` must be positive".to_string());
+        }
+        if ai.max_concurrent_requests == 0 {
+            problems.push("`ai.max_concurrent_requests` must be at least 1".to_string());
+        }
+        let mut tasks: Vec<_> = ai.overrides.iter().collect();
+        tasks.sort_by_key(|(task, _)| task.as_str());
+        for (task, options) in tasks {
+            if !crate::ai::agent::known_tasks().any(|known| known == task) {
+                problems.push(format!(
+                    "`ai.overrides.{}` is not a task; tasks are {}",
+                    task,
+                    crate::ai::agent::known_tasks().collect::<Vec<_>>().join(", ")
+                ));
+                continue;
+            }
+            if let Some(temperature) = options.temperature {
+                check_temperature(&mut problems, &format!("ai.overrides.{}.temperature", task), temperature);
+            }
+            if options.max_tokens == Some(0) {
+                problems.push(format!("`ai.overrides.{}.max_tokens` must be positive", task));
+            }
+        }
+
+        for pattern in &ai.redaction.patterns {
+            if let Err(e) = pattern.compile() {
+                problems.push(format!("`ai.redaction.patterns`: {:#}", e));
+            }
+        }
+
+        let security = &self.security;
+        if security.scan_timeout_seconds == 0 {
+            problems.push("`security.scan_timeout_seconds` must be positive".to_string());
+        }
+        if security.max_file_size_mb == 0 {
+            problems.push("`security.max_file_size_mb` must be positive".to_string());
+        }
+        for (key, path) in [
+            ("security.bandit_config_path", &security.bandit_config_path),
+            ("security.semgrep_rules_path", &security.semgrep_rules_path),
+        ] {
+            if let Some(path) = path.as_ref().filter(|path| !path.exists()) {
+                problems.push(format!("`{}`: {} does not exist", key, path.display()));
+            }
+        }
+        if let Some(path) = security.custom_rules_path.as_ref().filter(|path| !path.exists()) {
+            problems.push(format!("`security.custom_rules_path`: {} does not exist", path.display()));
+        }
+        for rule in &security.disabled_command_rules {
+            if !command_analysis::rule_ids().any(|id| id == rule) {
+                problems.push(format!("`security.disabled_command_rules`: `{}` is not a command rule", rule));
+            }
+        }
+        if security.license_denylist.iter().any(|license| license.trim().is_empty()) {
+            problems.push("`security.license_denylist` must not have empty entries".to_string());
+        }
+        let rules_path = security.custom_rules_path();
+        if security.enable_custom_rules && rules_path.exists() {
+            if let Err(e) = CustomRuleScanner::load(&rules_path, security) {
+                problems.push(format!("`security.custom_rules_path`: {:#}", e));
+            }
+        }
+
+        let terminal = &self.terminal;
+        if which::which(&terminal.shell).is_err() {
+            problems.push(format!(
+                "`terminal.shell`: {} was not found on PATH",
+                terminal.shell
+            ));
+        }
+        if terminal.font_size <= 0.0 || !terminal.font_size.is_finite() {
+            problems.push(format!("`terminal.font_size` must be positive, not {}", terminal.font_size));
+        }
+        let min_font_size = self.accessibility.min_font_size;
+        if !(0.0..=MAX_MIN_FONT_SIZE).contains(&min_font_size) {
+            problems.push(format!(
+                "`accessibility.min_font_size` must be between 0 and {}, not {}",
+                MAX_MIN_FONT_SIZE, min_font_size
+            ));
+        }
+        if terminal.max_history == 0 {
+            problems.push("`terminal.max_history` must be positive".to_string());
+        }
+        let mut aliases: Vec<_> = terminal.aliases.keys().collect();
+        aliases.sort();
+        for name in aliases {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                problems.push(format!("`terminal.aliases`: \"{}\" is not a valid alias name", name));
+            }
+        }
+        if let Some(command) = &terminal.editor_command {
+            match editor::expand_command(command, Path::new("file"), None, None) {
+                Ok(args) if which::which(&args[0]).is_err() => problems.push(format!(
+                    "`terminal.editor_command`: {} was not found on PATH",
+                    args[0]
+                )),
+                Ok(args) if editor::is_terminal_editor(&args[0]) => problems.push(format!(
+                    "`terminal.editor_command`: {} needs a terminal; use a graphical editor",
+                    args[0]
+                )),
+                Ok(_) => {}
+                Err(e) => problems.push(format!("`terminal.editor_command`: {}", e)),
+            }
+        }
+        if let Some(path) = terminal.output_rules_path.as_ref().filter(|path| !path.exists()) {
+            problems.push(format!("`terminal.output_rules_path`: {} does not exist", path.display()));
+        }
+        if terminal.fold_output {
+            if let Err(e) = OutputClassifiers::load(&terminal.output_rules_path()) {
+                problems.push(format!("`terminal.output_rules_path`: {:#}", e));
+            }
+        }
+
+        problems
+    }
+
+    /// The configured user name, falling back to the OS username and then to
+    /// "there" (as in "Hello, there!").
+    pub fn display_name(&self) -> String {
+        self.user_name
+            .iter()
+            .cloned()
+            .chain(["USER", "USERNAME", "LOGNAME"].iter().filter_map(|var| std::env::var(var).ok()))
+            .map(|name| name.trim().to_string())
+            .find(|name| !name.is_empty())
+            .unwrap_or_else(|| "there".to_string())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = self.path.clone().unwrap_or_else(default_config_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // A key from the keyring or a secrets file stays there
+        let mut config = self.clone();
+        if !self.ai.api_key_source.is_config() {
+            config.ai.api_key.clear();
+        }
+        // The profile's settings go back to the profile
+        let content = match &self.active_profile {
+            Some(active) => {
+                let mut settings = settings_table(&config)?;
+                profiles::unapply_profile(&mut settings, active);
+                toml::to_string_pretty(&settings)?
+            }
+            None => toml::to_string_pretty(&config)?,
+        };
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write config file {}", path.display()))?;
+
+        debug!("Saved configuration to {}", path.display());
+        Ok(())
+    }
+}
+
+impl Config {
+    /// These settings with the profile `name` applied over them.
+    fn with_profile(self, name: &str) -> Result<Self> {
+        let settings = settings_table(&self)?;
+        let (merged, active) = profiles::apply_profile(&settings, name)?;
+        let mut config: Config = toml::Value::Table(merged)
+            .try_into()
+            .with_context(|| format!("Failed to apply the {} profile", name))?;
+        config.active_profile = Some(active);
+        config.unknown_keys = self.unknown_keys;
+        config.path = self.path;
+        Ok(config)
+    }
+}
+
+fn settings_table(config: &Config) -> Result<toml::Table> {
+    match toml::Value::try_from(config)? {
+        toml::Value::Table(table) => Ok(table),
+        _ => bail!("The settings aren't a table"),
+    }
+}
+
+/// Which profile `Config::read` applies.
+enum ProfileChoice<'a> {
+    /// The one the file names in `profile`, if any
+    FromFile,
+    Named(&'a str),
+    Without,
+}
+
+fn check_temperature(problems: &mut Vec<String>, key: &str, temperature: f32) {
+    if !(0.0..=2.0).contains(&temperature) {
+        problems.push(format!("`{}` must be between 0 and 2, not {}", key, temperature));
+    }
+}
+
+/// Tables whose keys are chosen by the user, like alias names. Their entries
+/// are checked against the table's `*` entry, if it has one.
+const FREE_FORM_TABLES: &[&str] = &["terminal.aliases", "ai.overrides", "profiles"];
+
+/// Tables whose keys depend on one of their values. Parsing the config
+/// rejects keys they don't have.
+const TAGGED_TABLES: &[&str] = &["ai.api_key_source"];
+
+/// Dotted paths of the keys in `content` that no setting reads. Files that
+/// don't parse as TOML have none; parsing the config reports those.
+fn unknown_keys(content: &str) -> Vec<String> {
+    let Ok(file) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+
+    // Every setting, including the ones left out of a saved config when unset
+    let mut all_settings = Config {
+        user_name: Some(String::new()),
+        profile: Some(String::new()),
+        ..Config::default()
+    };
+    all_settings.ai.api_key = "key".to_string();
+    all_settings.security.bandit_config_path = Some(PathBuf::new());
+    all_settings.security.semgrep_rules_path = Some(PathBuf::new());
+    all_settings.terminal.editor_command = Some(String::new());
+    all_settings.audit.path = Some(PathBuf::new());
+    all_settings.ai.overrides.insert(
+        "*".to_string(),
+        AiRequestOptions {
+            temperature: Some(0.0),
+            max_tokens: Some(0),
+            system_prompt_override: Some(String::new()),
+        },
+    );
+    let Ok(toml::Value::Table(mut known)) = toml::Value::try_from(&all_settings) else {
+        return Vec::new();
+    };
+    let profile: toml::Table = profiles::PROFILE_SECTIONS
+        .iter()
+        .filter_map(|section| Some((section.to_string(), known.get(*section)?.clone())))
+        .collect();
+    known.insert(
+        "profiles".to_string(),
+        toml::Value::Table(toml::Table::from_iter([("*".to_string(), toml::Value::Table(profile))])),
+    );
+
+    let mut unknown = Vec::new();
+    collect_unknown_keys(&file, &known, "", &mut unknown);
+    unknown
+}
+
+fn collect_unknown_keys(table: &toml::Table, known: &toml::Table, prefix: &str, unknown: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        // A profile's settings are checked like the file's own
+        let setting = match path.strip_prefix("profiles.") {
+            Some(rest) => rest.split_once('.').map_or(path.as_str(), |(_, setting)| setting),
+            None => path.as_str(),
+        };
+        match (value, known.get(key)) {
+            (_, None) => unknown.push(path),
+            (toml::Value::Table(_), Some(toml::Value::Table(_))) if TAGGED_TABLES.contains(&setting) => {}
+            (toml::Value::Table(table), Some(toml::Value::Table(known))) => {
+                if !FREE_FORM_TABLES.contains(&setting) {
+                    collect_unknown_keys(table, known, &path, unknown);
+                } else if let Some(toml::Value::Table(entry)) = known.get("*") {
+                    for (name, value) in table {
+                        if let toml::Value::Table(value) = value {
+                            collect_unknown_keys(value, entry, &format!("{}.{}", path, name), unknown);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceConfig {
+    /// Where older versions kept the recent workspaces; read once into
+    /// `workspaces.json` in the data directory and no longer written
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recent: Vec<PathBuf>,
+    /// Recent workspaces remembered; see `workspaces::RecentWorkspaces`
+    pub max_recent: usize,
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            recent: Vec::new(),
+            max_recent: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// White text on black, with bright focus outlines and a terminal
+    /// palette of at least 7:1 contrast
+    pub high_contrast: bool,
+    /// Still indicators instead of spinners, and no fading highlights or
+    /// animations
+    pub reduced_motion: bool,
+    /// No text is drawn smaller than this, in points; 0 for no floor
+    pub min_font_size: f32,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            reduced_motion: false,
+            min_font_size: 0.0,
+        }
+    }
+}
+
+/// The largest `accessibility.min_font_size` allowed, so text still fits.
+pub const MAX_MIN_FONT_SIZE: f32 = 32.0;
+
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("antraft")
+}
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("antraft")
+}
+
+pub fn default_config_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
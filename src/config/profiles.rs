@@ -0,0 +1,200 @@
+//! Named profiles in the config file, each overriding any of the `ai`,
+//! `security` and `terminal` settings:
+//!
+//! ```toml
+//! [profiles.work.ai]
+//! model = "gemini-1.5-pro"
+//! api_key_source = { type = "keyring", account = "work" }
+//!
+//! [profiles.work.security]
+//! enable_semgrep = true
+//! ```
+//!
+//! A profile's settings win over the rest of the file's, which win over
+//! the defaults. Tables merge key by key, except tagged ones like
+//! `ai.api_key_source`, which a profile replaces whole; lists and other
+//! values are replaced.
+
+use super::TAGGED_TABLES;
+use crate::ai::api_key::ApiKeySource;
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+/// The sections a profile can override.
+pub const PROFILE_SECTIONS: &[&str] = &["ai", "security", "terminal"];
+
+/// The profile in effect, and the settings it overrode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveProfile {
+    pub name: String,
+    pub overrides: Vec<Override>,
+}
+
+/// A setting the active profile changed, with the value the rest of the
+/// file had for it, so saving puts each value back where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    pub path: Vec<String>,
+    /// `None` when the file left it to the default
+    pub base: Option<toml::Value>,
+    /// Whether the profile set it; otherwise applying the profile did, as
+    /// with a keyring account, and it's never saved to the profile
+    pub from_profile: bool,
+}
+
+/// The names of the file's profiles, sorted.
+pub fn profile_names(file: &toml::Table) -> Vec<String> {
+    let mut names: Vec<String> = file
+        .get("profiles")
+        .and_then(toml::Value::as_table)
+        .map(|profiles| profiles.keys().cloned().collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// `file` with the profile `name` applied.
+///
+/// Unless the profile sets its own `ai.api_key_source`, a keyring source
+/// looks the key up under the account `<account>/<name>`, so each profile
+/// has its own key.
+pub fn apply_profile(file: &toml::Table, name: &str) -> Result<(toml::Table, ActiveProfile)> {
+    let profile = file
+        .get("profiles")
+        .and_then(|profiles| profiles.get(name))
+        .and_then(toml::Value::as_table);
+    let Some(profile) = profile else {
+        let names = profile_names(file);
+        if names.is_empty() {
+            bail!("No profile named `{}`: the config has no [profiles]", name);
+        }
+        bail!("No profile named `{}`; the config has {}", name, names.join(", "));
+    };
+
+    let mut merged = file.clone();
+    let mut overrides = Vec::new();
+    for section in PROFILE_SECTIONS {
+        if let Some(toml::Value::Table(overlay)) = profile.get(*section) {
+            merge(&mut merged, &mut overrides, vec![section.to_string()], overlay);
+        }
+    }
+
+    let source_path = path(&["ai", "api_key_source"]);
+    let own_source = overrides.iter().any(|o| o.path.starts_with(&source_path));
+    let source = get(&merged, &source_path).cloned();
+    if let (false, Some(source)) = (own_source, source) {
+        if let Ok(ApiKeySource::Keyring { account, .. }) = ApiKeySource::deserialize(source.clone()) {
+            set(&mut merged, &path(&["ai", "api_key_source", "account"]), format!("{}/{}", account, name).into());
+            overrides.push(Override {
+                path: source_path,
+                base: Some(source),
+                from_profile: false,
+            });
+        }
+    }
+    // A key loaded from elsewhere replaces `ai.api_key`; the file's stays
+    let key_path = path(&["ai", "api_key"]);
+    if !overrides.iter().any(|o| o.path == key_path) {
+        overrides.push(Override {
+            base: get(file, &key_path).cloned(),
+            path: key_path,
+            from_profile: false,
+        });
+    }
+
+    let active = ActiveProfile {
+        name: name.to_string(),
+        overrides,
+    };
+    Ok((merged, active))
+}
+
+/// Undoes `apply_profile` on the settings about to be saved: the values of
+/// the overridden settings go back to the profile, and the file's own
+/// values back in their place.
+pub fn unapply_profile(settings: &mut toml::Table, active: &ActiveProfile) {
+    for o in &active.overrides {
+        if o.from_profile {
+            let mut profile_path = path(&["profiles", &active.name]);
+            profile_path.extend(o.path.iter().cloned());
+            match get(settings, &o.path).cloned() {
+                Some(value) => set(settings, &profile_path, value),
+                None => remove(settings, &profile_path),
+            }
+        }
+        match &o.base {
+            Some(base) => set(settings, &o.path, base.clone()),
+            None => remove(settings, &o.path),
+        }
+    }
+}
+
+fn merge(merged: &mut toml::Table, overrides: &mut Vec<Override>, prefix: Vec<String>, overlay: &toml::Table) {
+    for (key, value) in overlay {
+        let mut path = prefix.clone();
+        path.push(key.clone());
+        let tagged = TAGGED_TABLES.contains(&path.join(".").as_str());
+        let base = get(merged, &path).cloned();
+        match (value, &base) {
+            (toml::Value::Table(table), Some(toml::Value::Table(_)) | None) if !tagged => {
+                merge(merged, overrides, path, table);
+            }
+            _ => {
+                set(merged, &path, value.clone());
+                overrides.push(Override {
+                    path,
+                    base,
+                    from_profile: true,
+                });
+            }
+        }
+    }
+}
+
+fn path(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+fn get<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (last, parents) = path.split_last()?;
+    let mut table = table;
+    for key in parents {
+        table = table.get(key)?.as_table()?;
+    }
+    table.get(last)
+}
+
+/// Sets the value at `path`, making the tables on the way.
+fn set(table: &mut toml::Table, path: &[String], value: toml::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for key in parents {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let toml::Value::Table(next) = entry else {
+            return;
+        };
+        table = next;
+    }
+    table.insert(last.clone(), value);
+}
+
+fn remove(table: &mut toml::Table, path: &[String]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for key in parents {
+        match table.get_mut(key) {
+            Some(toml::Value::Table(next)) => table = next,
+            _ => return,
+        }
+    }
+    table.remove(last);
+}
@@ -0,0 +1,234 @@
+//! Crash reports. A panic on the main thread, which is the UI's, writes a
+//! report with the last log lines to `crashes` in the data directory, runs
+//! the flushes registered with `on_crash` to save what it can, and leaves a
+//! marker the next launch finds with `take_crash_marker`. Panics in background tasks are caught
+//! where they're spawned, with `catch_panic`, and don't end the app.
+
+use crate::terminal::block::Block;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context as TaskContext, Poll};
+
+/// Log lines kept for the next crash report.
+pub const RECENT_LOG_LINES: usize = 50;
+
+const MARKER_FILE: &str = "crash_marker.json";
+const SESSION_FILE: &str = "crash_session.json";
+
+type Flush = Box<dyn Fn() -> Result<()> + Send + Sync>;
+
+static FLUSHES: Mutex<Vec<(&'static str, Flush)>> = Mutex::new(Vec::new());
+
+/// The last `RECENT_LOG_LINES` log lines, oldest first, from the buffer
+/// `logging::init` installed.
+pub fn recent_log_lines() -> Vec<String> {
+    crate::logging::buffer()
+        .map(|buffer| buffer.recent_lines(RECENT_LOG_LINES))
+        .unwrap_or_default()
+}
+
+/// Runs `flush` when the app crashes, to save what it can; `name` says
+/// what in the report. It runs on the panicking thread mid-panic, so it
+/// mustn't wait on locks that thread may hold.
+pub fn on_crash(name: &'static str, flush: impl Fn() -> Result<()> + Send + Sync + 'static) {
+    if let Ok(mut flushes) = FLUSHES.lock() {
+        flushes.push((name, Box::new(flush)));
+    }
+}
+
+/// What's written about a panic.
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub message: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub thread: String,
+    pub backtrace: String,
+    pub version: &'static str,
+    pub time: DateTime<Utc>,
+    pub logs: Vec<String>,
+    /// Each flush's name and whether it worked
+    pub flushes: Vec<(&'static str, Result<(), String>)>,
+}
+
+impl CrashReport {
+    pub fn new(message: String, location: Option<String>) -> Self {
+        Self {
+            message,
+            location,
+            thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            version: env!("CARGO_PKG_VERSION"),
+            time: Utc::now(),
+            logs: recent_log_lines(),
+            flushes: Vec::new(),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut report = format!(
+            "ANTRAFT {} crashed at {}\n\nPanic on thread '{}'{}:\n{}\n",
+            self.version,
+            self.time.to_rfc3339(),
+            self.thread,
+            self.location.as_ref().map(|location| format!(" at {}", location)).unwrap_or_default(),
+            self.message
+        );
+        if !self.flushes.is_empty() {
+            report.push_str("\nSaved before quitting:\n");
+            for (name, result) in &self.flushes {
+                match result {
+                    Ok(()) => report.push_str(&format!("  {}: saved\n", name)),
+                    Err(e) => report.push_str(&format!("  {}: failed: {}\n", name, e)),
+                }
+            }
+        }
+        report.push_str(&format!("\nLast {} log lines:\n", self.logs.len()));
+        for line in &self.logs {
+            report.push_str(line);
+            report.push('\n');
+        }
+        report.push_str("\nBacktrace:\n");
+        report.push_str(&self.backtrace);
+        report
+    }
+}
+
+/// Left by a crash for the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashMarker {
+    pub report: PathBuf,
+    /// The session saved by the crash, if it was
+    pub session: Option<PathBuf>,
+    pub time: DateTime<Utc>,
+}
+
+/// The blocks, chat and input of a session, saved when it crashes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub working_directory: String,
+    pub command_input: String,
+    pub blocks: Vec<Block>,
+    /// (role, message)
+    pub chat: Vec<(String, String)>,
+}
+
+/// Where a crash saves the session in `dir`.
+pub fn session_path(dir: &Path) -> PathBuf {
+    dir.join(SESSION_FILE)
+}
+
+pub fn load_session(path: &Path) -> Result<SessionSnapshot> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_session(path: &Path, session: &SessionSnapshot) -> Result<()> {
+    std::fs::write(path, serde_json::to_string(session)?).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reports panics on the main thread as crashes, in `dir`; see the module
+/// docs. The default hook still prints the panic.
+pub fn install_panic_hook(dir: PathBuf) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let message = panic_message(info.payload());
+        let location = info.location().map(|location| location.to_string());
+        let thread = std::thread::current();
+        if thread.name() != Some("main") {
+            // Caught where the task was spawned
+            log::error!("Panic on thread '{}': {}", thread.name().unwrap_or("unnamed"), message);
+            return;
+        }
+        match write_crash(&dir, CrashReport::new(message, location)) {
+            Ok(marker) => eprintln!("ANTRAFT crashed; the report is in {}", marker.report.display()),
+            Err(e) => eprintln!("ANTRAFT crashed and couldn't write a report: {:#}", e),
+        }
+    }));
+}
+
+/// Runs the registered flushes, then writes `report` and the marker to
+/// `dir`.
+pub fn write_crash(dir: &Path, mut report: CrashReport) -> Result<CrashMarker> {
+    let crashes = dir.join("crashes");
+    std::fs::create_dir_all(&crashes).with_context(|| format!("Failed to create {}", crashes.display()))?;
+
+    if let Ok(flushes) = FLUSHES.try_lock() {
+        for (name, flush) in flushes.iter() {
+            // A second panic here would abort before the report is written
+            let result = match std::panic::catch_unwind(AssertUnwindSafe(flush)) {
+                Ok(result) => result.map_err(|e| format!("{:#}", e)),
+                Err(payload) => Err(panic_message(payload.as_ref())),
+            };
+            report.flushes.push((name, result));
+        }
+    }
+
+    let report_path = crashes.join(format!("crash-{}.txt", report.time.format("%Y%m%d-%H%M%S")));
+    std::fs::write(&report_path, report.render())
+        .with_context(|| format!("Failed to write {}", report_path.display()))?;
+
+    let session = session_path(dir);
+    let marker = CrashMarker {
+        report: report_path,
+        session: session.exists().then_some(session),
+        time: report.time,
+    };
+    let marker_path = dir.join(MARKER_FILE);
+    std::fs::write(&marker_path, serde_json::to_string(&marker)?)
+        .with_context(|| format!("Failed to write {}", marker_path.display()))?;
+    Ok(marker)
+}
+
+/// The marker a crash left in `dir`, removed so it's only reported once.
+pub fn take_crash_marker(dir: &Path) -> Option<CrashMarker> {
+    let path = dir.join(MARKER_FILE);
+    let content = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    serde_json::from_str(&content)
+        .map_err(|e| log::warn!("Ignoring an unreadable crash marker: {}", e))
+        .ok()
+}
+
+/// The message a panic was given, for `panic!("...")` and `.unwrap()` alike.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "Box<dyn Any>".to_string()),
+    }
+}
+
+/// `future`, with a panic in it turned into an error carrying its message.
+pub fn catch_panic<F: Future>(future: F) -> CatchPanic<F> {
+    CatchPanic {
+        future: Box::pin(future),
+    }
+}
+
+pub struct CatchPanic<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for CatchPanic<F> {
+    type Output = Result<F::Output, String>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let future = self.future.as_mut();
+        match std::panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(panic_message(payload.as_ref()))),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::runtime::Handle;
+
+/// A `docker` call taking longer than this is abandoned, so a hung daemon
+/// can't hold anything up.
+const DOCKER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long a listing is reused before it's refreshed.
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Compose files looked for in a directory, in the order `docker compose`
+/// prefers them.
+const COMPOSE_FILES: &[&str] = &["compose.yaml", "compose.yml", "docker-compose.yml", "docker-compose.yaml"];
+
+/// A running container, from `docker ps`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Container {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(rename = "Names")]
+    pub name: String,
+    #[serde(rename = "Image")]
+    pub image: String,
+    #[serde(rename = "Status")]
+    pub status: String,
+}
+
+/// A local image, from `docker images`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Image {
+    #[serde(rename = "Repository")]
+    pub repository: String,
+    #[serde(rename = "Tag")]
+    pub tag: String,
+    #[serde(rename = "Size", default)]
+    pub size: String,
+}
+
+impl Image {
+    /// `repository:tag`, or just the repository for untagged images.
+    pub fn name(&self) -> String {
+        match self.tag.as_str() {
+            "" | "<none>" => self.repository.clone(),
+            tag => format!("{}:{}", self.repository, tag),
+        }
+    }
+}
+
+/// Whether the `docker` CLI is installed.
+pub fn is_available() -> bool {
+    which::which("docker").is_ok()
+}
+
+async fn docker(args: &[&str]) -> Result<String> {
+    let output = Command::new("docker")
+        .args(args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(DOCKER_TIMEOUT, output)
+        .await
+        .map_err(|_| anyhow!("docker {} timed out", args.join(" ")))?
+        .context("Failed to run docker")?;
+    if !output.status.success() {
+        bail!("docker {}: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parses output of `--format '{{json .}}'`: one object per line.
+fn parse_json_lines<T: for<'de> Deserialize<'de>>(output: &str) -> Result<Vec<T>> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).context("Unexpected docker output"))
+        .collect()
+}
+
+pub async fn list_containers() -> Result<Vec<Container>> {
+    parse_json_lines(&docker(&["ps", "--format", "{{json .}}"]).await?)
+}
+
+pub async fn list_images() -> Result<Vec<Image>> {
+    let images: Vec<Image> = parse_json_lines(&docker(&["images", "--format", "{{json .}}"]).await?)?;
+    Ok(images.into_iter().filter(|image| image.repository != "<none>").collect())
+}
+
+/// The services of the compose file in `dir`, if there is one.
+pub fn compose_services(dir: &Path) -> Vec<String> {
+    COMPOSE_FILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+        .map(|content| parse_compose_services(&content))
+        .unwrap_or_default()
+}
+
+/// The keys under the top-level `services:` of a compose file. Reads just
+/// enough YAML for that: block mappings, not flow style or anchors.
+pub fn parse_compose_services(content: &str) -> Vec<String> {
+    let mut services = Vec::new();
+    let mut in_services = false;
+    // Indentation of the service names, once the first is seen
+    let mut service_indent = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim_end();
+        if trimmed.trim_start().is_empty() || trimmed.trim_start().starts_with('#') {
+            continue;
+        }
+        let indent = trimmed.len() - trimmed.trim_start().len();
+        if indent == 0 {
+            in_services = trimmed.starts_with("services:");
+            service_indent = None;
+            continue;
+        }
+        if !in_services || service_indent.is_some_and(|service_indent| indent > service_indent) {
+            continue;
+        }
+        service_indent = Some(indent);
+        if let Some((name, rest)) = trimmed.trim_start().split_once(':') {
+            if rest.trim().is_empty() || rest.trim().starts_with('#') {
+                services.push(name.trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+        }
+    }
+    services
+}
+
+/// The last container and image listings.
+#[derive(Debug, Clone, Default)]
+pub struct DockerSnapshot {
+    pub containers: Vec<Container>,
+    pub images: Vec<Image>,
+    /// Why the last refresh failed, e.g. the daemon isn't running
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    snapshot: DockerSnapshot,
+    fetched: Option<Instant>,
+    refreshing: bool,
+}
+
+/// Containers and images, listed in the background and reused for a few
+/// seconds. Readers never wait for `docker`; they get the last listing.
+pub struct DockerCache {
+    state: Mutex<CacheState>,
+    runtime: Handle,
+}
+
+impl DockerCache {
+    pub fn new(runtime: Handle) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(CacheState::default()),
+            runtime,
+        })
+    }
+
+    /// The last listing, without refreshing it.
+    pub fn cached(&self) -> DockerSnapshot {
+        self.state.lock().unwrap().snapshot.clone()
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.state.lock().unwrap().refreshing
+    }
+
+    /// The last listing, starting a refresh when it's stale.
+    pub fn snapshot(self: &Arc<Self>) -> DockerSnapshot {
+        let mut state = self.state.lock().unwrap();
+        let stale = state.fetched.is_none_or(|fetched| fetched.elapsed() >= CACHE_TTL);
+        if stale && !state.refreshing {
+            state.refreshing = true;
+            let cache = self.clone();
+            self.runtime.spawn(async move {
+                let (containers, images) = tokio::join!(list_containers(), list_images());
+                let mut state = cache.state.lock().unwrap();
+                state.snapshot = match (containers, images) {
+                    (Ok(containers), Ok(images)) => DockerSnapshot {
+                        containers,
+                        images,
+                        error: None,
+                    },
+                    (Err(e), _) | (_, Err(e)) => DockerSnapshot {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                };
+                state.fetched = Some(Instant::now());
+                state.refreshing = false;
+            });
+        }
+        state.snapshot.clone()
+    }
+}
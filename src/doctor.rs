@@ -0,0 +1,152 @@
+//! `antraft doctor`: the shells and scanners found on this machine, where
+//! the config and data live, and whether there's an API key, each found the
+//! way the app finds it, through `ToolDetector` and `Config::load_profile`.
+
+use crate::ai::api_key::API_KEY_ENV_VAR;
+use crate::config::{self, Config};
+use crate::tools::{self, DetectedTool, ExternalTool, ToolDetector};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Where the API key comes from, if anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyStatus {
+    /// Found, in the place described
+    Found(String),
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnosis {
+    pub version: &'static str,
+    pub config_path: PathBuf,
+    pub config_exists: bool,
+    /// Why the config couldn't be loaded, with the defaults used instead, or
+    /// what `Config::validate` found wrong with it
+    pub config_problem: Option<String>,
+    pub profile: Option<String>,
+    pub data_dir: PathBuf,
+    /// `terminal.shell`, and the program it runs when that's installed
+    pub shell: (String, Option<PathBuf>),
+    /// The known shells that are installed
+    pub shells: Vec<(ExternalTool, DetectedTool)>,
+    /// Every external scanner, whether the config enables it, and where it
+    /// was found
+    pub scanners: Vec<(ExternalTool, bool, Option<DetectedTool>)>,
+    pub api_key: ApiKeyStatus,
+}
+
+/// Loads the config at `config_path` with `profile`, as the app would, and
+/// looks for the shells and scanners with `detector`.
+pub fn diagnose(config_path: Option<&Path>, profile: Option<&str>, detector: &ToolDetector) -> Diagnosis {
+    let (config, config_problem) = match Config::load_profile(config_path, profile) {
+        Ok(config) => {
+            let problem = config.validate().err().map(|e| format!("{:#}", e));
+            (config, problem)
+        }
+        Err(e) => (Config::default(), Some(format!("{:#}", e))),
+    };
+    let config_path = config
+        .path
+        .clone()
+        .or_else(|| config_path.map(Path::to_path_buf))
+        .unwrap_or_else(config::default_config_path);
+
+    let shell = config.terminal.shell.clone();
+    let shell_path = detector.find(&shell);
+    let shells = tools::SHELLS
+        .iter()
+        .filter_map(|tool| Some((*tool, detector.detect(tool)?)))
+        .collect();
+    let scanners = config
+        .security
+        .external_scanners()
+        .into_iter()
+        .map(|(tool, enabled)| (tool, enabled, detector.detect(&tool)))
+        .collect();
+
+    Diagnosis {
+        version: crate::VERSION,
+        config_exists: config_path.exists(),
+        config_path,
+        config_problem,
+        profile: config.active_profile.as_ref().map(|profile| profile.name.clone()),
+        data_dir: config::data_dir(),
+        shell: (shell, shell_path),
+        shells,
+        scanners,
+        api_key: api_key_status(&config),
+    }
+}
+
+fn api_key_status(config: &Config) -> ApiKeyStatus {
+    let ai = &config.ai;
+    if ai.api_key.trim().is_empty() {
+        return ApiKeyStatus::Missing;
+    }
+    let from_env = std::env::var(API_KEY_ENV_VAR).is_ok_and(|key| key == ai.api_key);
+    ApiKeyStatus::Found(if !ai.api_key_source.is_config() && config.api_key_error().is_none() {
+        ai.api_key_source.to_string()
+    } else if from_env {
+        format!("the {} environment variable", API_KEY_ENV_VAR)
+    } else {
+        "`ai.api_key` in the config".to_string()
+    })
+}
+
+fn write_tool(f: &mut fmt::Formatter<'_>, name: &str, tool: &DetectedTool) -> fmt::Result {
+    let version = tool.version.as_deref().unwrap_or("version unknown");
+    writeln!(f, "  ✓ {:<12} {}  ({})", name, version, tool.path.display())
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ANTRAFT {}", self.version)?;
+
+        writeln!(f, "\nConfig")?;
+        let created = if self.config_exists { "" } else { " (not created yet; using the defaults)" };
+        writeln!(f, "  file     {}{}", self.config_path.display(), created)?;
+        if let Some(profile) = &self.profile {
+            writeln!(f, "  profile  {}", profile)?;
+        }
+        writeln!(f, "  data     {}", self.data_dir.display())?;
+        if let Some(problem) = &self.config_problem {
+            let mut lines = problem.lines();
+            writeln!(f, "  ⚠ {}", lines.next().unwrap_or_default())?;
+            for line in lines {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+
+        writeln!(f, "\nShells")?;
+        match &self.shell {
+            (shell, Some(path)) => writeln!(f, "  terminal.shell = {}  ({})", shell, path.display())?,
+            (shell, None) => writeln!(f, "  ⚠ terminal.shell = {}, which isn't installed", shell)?,
+        }
+        if self.shells.is_empty() {
+            writeln!(f, "  ✗ No known shell on PATH")?;
+        }
+        for (tool, detected) in &self.shells {
+            write_tool(f, tool.name, detected)?;
+        }
+
+        writeln!(f, "\nSecurity scanners")?;
+        for (tool, enabled, detected) in &self.scanners {
+            match (enabled, detected) {
+                (true, Some(detected)) => write_tool(f, tool.name, detected)?,
+                (true, None) => writeln!(f, "  ✗ {:<12} not installed", tool.name)?,
+                (false, _) => writeln!(f, "  - {:<12} disabled in the config", tool.name)?,
+            }
+        }
+
+        writeln!(f, "\nAI")?;
+        match &self.api_key {
+            ApiKeyStatus::Found(from) => writeln!(f, "  ✓ API key from {}", from),
+            ApiKeyStatus::Missing => writeln!(
+                f,
+                "  ✗ No API key; set {} or `api_key`/`api_key_source` under [ai]",
+                API_KEY_ENV_VAR
+            ),
+        }
+    }
+}
@@ -1,8 +1,11 @@
+pub mod search;
+
 use anyhow::Result;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::time::SystemTime;
 use tokio::sync::mpsc as tokio_mpsc;
 
@@ -233,39 +236,77 @@ pub enum FileSystemEvent {
     Renamed { from: PathBuf, to: PathBuf },
 }
 
-pub struct FileExplorer {
+/// Report progress every this many discovered entries.
+const PROGRESS_INTERVAL: usize = 200;
+
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TreeLoadEvent {
+    Progress { discovered: usize },
+    Finished,
+    Cancelled,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeLoader {
     root_path: PathBuf,
-    root_node: Option<FileNode>,
-    watcher: Option<RecommendedWatcher>,
-    event_receiver: Option<std::sync::mpsc::Receiver<notify::Result<Event>>>,
     gitignore_patterns: Vec<String>,
     show_hidden_files: bool,
     max_depth: Option<usize>,
 }
 
-impl FileExplorer {
-    pub fn new(root_path: PathBuf) -> Result<Self> {
-        let gitignore_patterns = load_gitignore_patterns(&root_path);
-
-        Ok(Self {
-            root_path,
-            root_node: None,
-            watcher: None,
-            event_receiver: None,
-            gitignore_patterns,
-            show_hidden_files: false,
-            max_depth: Some(10), // Prevent infinite recursion
-        })
-    }
-
-    pub fn load_tree(&mut self) -> Result<()> {
-        self.root_node = Some(self.build_tree(&self.root_path.clone(), 0)?);
-        Ok(())
+impl TreeLoader {
+    /// Builds the full tree, sending `Progress` events as entries are
+    /// discovered. Returns `Ok(None)` if `token` was cancelled.
+    pub fn load(
+        &self,
+        token: &CancellationToken,
+        progress: Option<&tokio_mpsc::UnboundedSender<TreeLoadEvent>>,
+    ) -> Result<Option<FileNode>> {
+        let mut discovered = 0;
+        let node = self.build_tree(&self.root_path, 0, token, progress, &mut discovered)?;
+        if token.is_cancelled() {
+            return Ok(None);
+        }
+        Ok(Some(node))
     }
 
-    fn build_tree(&self, path: &Path, depth: usize) -> Result<FileNode> {
+    fn build_tree(
+        &self,
+        path: &Path,
+        depth: usize,
+        token: &CancellationToken,
+        progress: Option<&tokio_mpsc::UnboundedSender<TreeLoadEvent>>,
+        discovered: &mut usize,
+    ) -> Result<FileNode> {
         let mut node = FileNode::new(path.to_path_buf())?;
 
+        *discovered += 1;
+        if discovered.is_multiple_of(PROGRESS_INTERVAL) {
+            if let Some(progress) = progress {
+                let _ = progress.send(TreeLoadEvent::Progress {
+                    discovered: *discovered,
+                });
+            }
+        }
+
         // Check depth limit
         if let Some(max_depth) = self.max_depth {
             if depth >= max_depth {
@@ -283,20 +324,22 @@ impl FileExplorer {
 
             match std::fs::read_dir(path) {
                 Ok(entries) => {
-                    for entry in entries {
-                        if let Ok(entry) = entry {
-                            let entry_path = entry.path();
+                    for entry in entries.flatten() {
+                        if token.is_cancelled() {
+                            break;
+                        }
 
-                            // Skip hidden files if not showing them
-                            if !self.show_hidden_files && self.is_hidden_file(&entry_path) {
-                                continue;
-                            }
+                        let entry_path = entry.path();
+
+                        // Skip hidden files if not showing them
+                        if !self.show_hidden_files && is_hidden_file(&entry_path) {
+                            continue;
+                        }
 
-                            match self.build_tree(&entry_path, depth + 1) {
-                                Ok(child_node) => children.push(child_node),
-                                Err(e) => {
-                                    log::warn!("Failed to build tree for {:?}: {}", entry_path, e);
-                                }
+                        match self.build_tree(&entry_path, depth + 1, token, progress, discovered) {
+                            Ok(child_node) => children.push(child_node),
+                            Err(e) => {
+                                log::warn!("Failed to build tree for {:?}: {}", entry_path, e);
                             }
                         }
                     }
@@ -347,12 +390,67 @@ impl FileExplorer {
             .iter()
             .any(|pattern| path_str.contains(pattern))
     }
+}
+
+fn is_hidden_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(false)
+}
+
+pub struct FileExplorer {
+    root_path: PathBuf,
+    root_node: Option<FileNode>,
+    watcher: Option<RecommendedWatcher>,
+    gitignore_patterns: Vec<String>,
+    show_hidden_files: bool,
+    max_depth: Option<usize>,
+}
 
-    fn is_hidden_file(&self, path: &Path) -> bool {
-        path.file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| name.starts_with('.'))
-            .unwrap_or(false)
+impl FileExplorer {
+    pub fn new(root_path: PathBuf) -> Result<Self> {
+        let mut explorer = Self::unloaded(root_path);
+        explorer.load_gitignore();
+        Ok(explorer)
+    }
+
+    /// An explorer that hasn't read `.gitignore` yet, made without touching
+    /// the disk; `load_gitignore` reads it.
+    pub fn unloaded(root_path: PathBuf) -> Self {
+        Self {
+            root_path,
+            root_node: None,
+            watcher: None,
+            gitignore_patterns: Vec::new(),
+            show_hidden_files: false,
+            max_depth: Some(10), // Prevent infinite recursion
+        }
+    }
+
+    pub fn load_gitignore(&mut self) {
+        self.gitignore_patterns = load_gitignore_patterns(&self.root_path);
+    }
+
+    pub fn load_tree(&mut self) -> Result<()> {
+        let node = self.tree_loader().load(&CancellationToken::new(), None)?;
+        self.root_node = node;
+        Ok(())
+    }
+
+    /// A snapshot of the settings needed to build the tree, so it can be built
+    /// off the UI thread without holding the explorer lock.
+    pub fn tree_loader(&self) -> TreeLoader {
+        TreeLoader {
+            root_path: self.root_path.clone(),
+            gitignore_patterns: self.gitignore_patterns.clone(),
+            show_hidden_files: self.show_hidden_files,
+            max_depth: self.max_depth,
+        }
+    }
+
+    pub fn set_root_node(&mut self, node: FileNode) {
+        self.root_node = Some(node);
     }
 
     pub fn start_watching(&mut self) -> Result<tokio_mpsc::UnboundedReceiver<FileSystemEvent>> {
@@ -363,17 +461,17 @@ impl FileExplorer {
         watcher.watch(&self.root_path, RecursiveMode::Recursive)?;
 
         self.watcher = Some(watcher);
-        self.event_receiver = Some(rx);
 
-        // Spawn a task to convert notify events to our events
-        let event_receiver = self.event_receiver.take().unwrap();
+        // Spawn a task to convert notify events to our events. The receiver is
+        // moved into the task rather than kept on `self`, which keeps
+        // `FileExplorer` `Sync`.
         tokio::spawn(async move {
-            while let Ok(event) = event_receiver.recv() {
+            while let Ok(event) = rx.recv() {
                 match event {
                     Ok(notify_event) => {
                         let fs_events = convert_notify_event(notify_event);
                         for fs_event in fs_events {
-                            if let Err(_) = tokio_tx.send(fs_event) {
+                            if tokio_tx.send(fs_event).is_err() {
                                 break; // Receiver dropped
                             }
                         }
@@ -388,6 +486,26 @@ impl FileExplorer {
         Ok(tokio_rx)
     }
 
+    /// An explorer of `root_path` with this one's settings. A watcher this
+    /// one started moves over to watch `root_path` instead, so the events
+    /// from `start_watching` keep coming, from the new root.
+    pub fn rerooted(&mut self, root_path: PathBuf) -> Result<Self> {
+        let mut explorer = Self::new(root_path)?;
+        explorer.show_hidden_files = self.show_hidden_files;
+        explorer.max_depth = self.max_depth;
+        if let Some(mut watcher) = self.watcher.take() {
+            let _ = watcher.unwatch(&self.root_path);
+            watcher.watch(&explorer.root_path, RecursiveMode::Recursive)?;
+            explorer.watcher = Some(watcher);
+        }
+        Ok(explorer)
+    }
+
+    /// Drops the watcher, ending the event stream from `start_watching`.
+    pub fn stop_watching(&mut self) {
+        self.watcher = None;
+    }
+
     pub fn get_root_node(&self) -> Option<&FileNode> {
         self.root_node.as_ref()
     }
@@ -0,0 +1,342 @@
+use super::CancellationToken;
+use anyhow::{anyhow, Context, Result};
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Longest line kept for display; minified files have very long ones.
+const MAX_LINE_LEN: usize = 400;
+
+/// Files with a NUL byte in their first this many bytes are treated as binary.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ContentSearchOptions {
+    pub query: String,
+    pub case_sensitive: bool,
+    /// Treat `query` as a regular expression rather than literal text
+    pub regex: bool,
+    pub whole_word: bool,
+    /// Globs a file must match, like `*.rs`; empty for all files
+    pub include: Vec<String>,
+    /// Globs of files to skip
+    pub exclude: Vec<String>,
+    /// Directory names never searched, like `node_modules`
+    pub excluded_paths: Vec<String>,
+    pub max_matches_per_file: usize,
+    pub max_file_size: u64,
+}
+
+impl ContentSearchOptions {
+    /// The regex both engines search with.
+    pub fn pattern(&self) -> Result<Regex> {
+        if self.query.is_empty() {
+            return Err(anyhow!("Nothing to search for"));
+        }
+        let mut pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| anyhow!("Invalid regex: {}", e))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LineMatch {
+    /// 1-based
+    pub line_number: usize,
+    pub line: String,
+    /// Byte ranges of the matches within `line`
+    pub ranges: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub matches: Vec<LineMatch>,
+    /// More lines matched than `max_matches_per_file`
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchBackend {
+    Ripgrep,
+    Builtin,
+}
+
+#[derive(Debug, Clone)]
+pub enum ContentSearchEvent {
+    Started(SearchBackend),
+    File(FileMatches),
+    Finished,
+    Cancelled,
+    Failed(String),
+}
+
+/// Searches the files under `root`, sending a `File` event per file with
+/// matches as they're found. Uses `rg` when it's installed and a built-in
+/// parallel walker otherwise; both honour `.gitignore`, skip hidden and
+/// binary files, and stop when `token` is cancelled.
+pub fn search_contents(
+    root: &Path,
+    options: &ContentSearchOptions,
+    token: &CancellationToken,
+    events: &crossbeam_channel::Sender<ContentSearchEvent>,
+) {
+    let pattern = match options.pattern() {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            let _ = events.send(ContentSearchEvent::Failed(e.to_string()));
+            return;
+        }
+    };
+
+    let result = match which::which("rg") {
+        Ok(rg) => {
+            let _ = events.send(ContentSearchEvent::Started(SearchBackend::Ripgrep));
+            search_with_ripgrep(&rg, root, options, &pattern, token, events)
+        }
+        Err(_) => {
+            let _ = events.send(ContentSearchEvent::Started(SearchBackend::Builtin));
+            search_builtin(root, options, &pattern, token, events)
+        }
+    };
+
+    let _ = events.send(match result {
+        _ if token.is_cancelled() => ContentSearchEvent::Cancelled,
+        Ok(_) => ContentSearchEvent::Finished,
+        Err(e) => ContentSearchEvent::Failed(format!("{:#}", e)),
+    });
+}
+
+/// The fallback engine: walks `root` on several threads with the `ignore`
+/// crate and searches each file line by line.
+pub fn search_builtin(
+    root: &Path,
+    options: &ContentSearchOptions,
+    pattern: &Regex,
+    token: &CancellationToken,
+    events: &crossbeam_channel::Sender<ContentSearchEvent>,
+) -> Result<usize> {
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in &options.include {
+        overrides.add(glob).with_context(|| format!("Invalid include glob \"{}\"", glob))?;
+    }
+    for glob in &options.exclude {
+        overrides
+            .add(&format!("!{}", glob))
+            .with_context(|| format!("Invalid exclude glob \"{}\"", glob))?;
+    }
+
+    let excluded_paths = options.excluded_paths.clone();
+    let walker = WalkBuilder::new(root)
+        .overrides(overrides.build()?)
+        .max_filesize(Some(options.max_file_size))
+        .filter_entry(move |entry| {
+            !entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| excluded_paths.iter().any(|excluded| excluded == name))
+        })
+        .build_parallel();
+
+    let files_with_matches = Arc::new(AtomicUsize::new(0));
+    walker.run(|| {
+        let files_with_matches = files_with_matches.clone();
+        Box::new(move |entry| {
+            if token.is_cancelled() {
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                return WalkState::Continue;
+            }
+
+            match search_file(entry.path(), pattern, options.max_matches_per_file) {
+                Ok(Some(found)) => {
+                    files_with_matches.fetch_add(1, Ordering::Relaxed);
+                    if events.send(ContentSearchEvent::File(found)).is_err() {
+                        return WalkState::Quit;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::debug!("Skipping {}: {}", entry.path().display(), e),
+            }
+            WalkState::Continue
+        })
+    });
+
+    Ok(files_with_matches.load(Ordering::Relaxed))
+}
+
+/// The matching lines of one file, or `None` for no matches or a binary file.
+fn search_file(path: &Path, pattern: &Regex, max_matches: usize) -> Result<Option<FileMatches>> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?.read_to_end(&mut bytes)?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        return Ok(None);
+    }
+
+    let content = String::from_utf8_lossy(&bytes);
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    for (index, line) in content.lines().enumerate() {
+        let Some(line_match) = match_line(index + 1, line, pattern) else {
+            continue;
+        };
+        if matches.len() == max_matches {
+            truncated = true;
+            break;
+        }
+        matches.push(line_match);
+    }
+
+    Ok((!matches.is_empty()).then(|| FileMatches {
+        path: path.to_path_buf(),
+        matches,
+        truncated,
+    }))
+}
+
+fn match_line(line_number: usize, line: &str, pattern: &Regex) -> Option<LineMatch> {
+    let line = line.trim_end_matches('\r');
+    let mut end = line.len().min(MAX_LINE_LEN);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    let ranges: Vec<Range<usize>> = pattern
+        .find_iter(line)
+        .map(|found| found.range())
+        .filter(|range| range.end <= end && !range.is_empty())
+        .collect();
+    if ranges.is_empty() && !pattern.is_match(line) {
+        return None;
+    }
+
+    Some(LineMatch {
+        line_number,
+        line: line[..end].to_string(),
+        ranges,
+    })
+}
+
+/// One line of `rg --json` output; only the fields used here.
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+enum RipgrepMessage {
+    Begin { path: RipgrepText },
+    Match {
+        lines: RipgrepText,
+        line_number: Option<usize>,
+    },
+    End {},
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct RipgrepText {
+    text: Option<String>,
+}
+
+fn search_with_ripgrep(
+    rg: &Path,
+    root: &Path,
+    options: &ContentSearchOptions,
+    pattern: &Regex,
+    token: &CancellationToken,
+    events: &crossbeam_channel::Sender<ContentSearchEvent>,
+) -> Result<usize> {
+    let mut command = Command::new(rg);
+    command
+        .arg("--json")
+        .arg("--max-filesize")
+        .arg(options.max_file_size.to_string())
+        .arg("--max-count")
+        // One extra to know whether the file had more
+        .arg((options.max_matches_per_file + 1).to_string());
+    for glob in &options.include {
+        command.arg("--glob").arg(glob);
+    }
+    for glob in options.exclude.iter().chain(&options.excluded_paths) {
+        command.arg("--glob").arg(format!("!{}", glob));
+    }
+    // Case and word options are already part of the pattern
+    command
+        .arg("--regexp")
+        .arg(pattern.as_str())
+        .arg(if options.case_sensitive { "--case-sensitive" } else { "--ignore-case" })
+        .arg("--")
+        .arg(".")
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+
+    let mut child = command.spawn().context("Failed to run rg")?;
+    let stdout = child.stdout.take().context("rg has no output")?;
+
+    let mut files_with_matches = 0;
+    let mut current: Option<FileMatches> = None;
+    for line in BufReader::new(stdout).lines() {
+        if token.is_cancelled() {
+            let _ = child.kill();
+            break;
+        }
+        let Ok(message) = serde_json::from_str::<RipgrepMessage>(&line?) else {
+            continue;
+        };
+        match message {
+            RipgrepMessage::Begin { path } => {
+                current = path.text.map(|path| FileMatches {
+                    path: root.join(path.trim_start_matches("./")),
+                    matches: Vec::new(),
+                    truncated: false,
+                });
+            }
+            RipgrepMessage::Match { lines, line_number } => {
+                let (Some(file), Some(text), Some(line_number)) = (current.as_mut(), lines.text, line_number) else {
+                    continue;
+                };
+                if file.matches.len() == options.max_matches_per_file {
+                    file.truncated = true;
+                } else if let Some(line_match) = match_line(line_number, text.trim_end_matches('\n'), pattern) {
+                    file.matches.push(line_match);
+                }
+            }
+            RipgrepMessage::End {} => {
+                if let Some(file) = current.take().filter(|file| !file.matches.is_empty()) {
+                    files_with_matches += 1;
+                    if events.send(ContentSearchEvent::File(file)).is_err() {
+                        let _ = child.kill();
+                        break;
+                    }
+                }
+            }
+            RipgrepMessage::Other => {}
+        }
+    }
+
+    let status = child.wait()?;
+    // rg exits with 1 when nothing matched
+    if !token.is_cancelled() && !status.success() && status.code() != Some(1) {
+        return Err(anyhow!("rg failed ({})", status));
+    }
+    Ok(files_with_matches)
+}
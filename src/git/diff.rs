@@ -0,0 +1,221 @@
+/// One file's changes in a unified diff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    /// The path after the change, or before it for deleted files
+    pub path: String,
+    /// Set when the file was renamed or copied
+    pub old_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+    /// "Binary files differ", new file mode and other lines without hunks
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// The `@@ -1,4 +1,5 @@ fn main()` line
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    /// Without the leading `+`, `-` or space
+    pub text: String,
+}
+
+/// A parsed unified diff, like `git diff`, `git show` or `diff -u` output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff {
+    /// Lines before the first file, like `git show`'s commit header
+    pub preamble: Vec<String>,
+    pub files: Vec<FileDiff>,
+}
+
+/// Parses `text` as a unified diff. Returns `None` unless every line after
+/// the first file fits the format and at least one hunk or note was found,
+/// so callers can fall back to showing the text as it is.
+pub fn parse_unified_diff(text: &str) -> Option<Diff> {
+    let mut diff = Diff {
+        preamble: Vec::new(),
+        files: Vec::new(),
+    };
+    let mut lines = text.lines().peekable();
+    // Lines left in the current hunk: (old, new)
+    let mut remaining = (0u32, 0u32);
+    let mut next = (0u32, 0u32);
+
+    while let Some(line) = lines.next() {
+        if remaining != (0, 0) {
+            let file = diff.files.last_mut()?;
+            let hunk = file.hunks.last_mut()?;
+            let (kind, text) = match line.chars().next() {
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                // Some tools drop the space of empty context lines
+                None => (DiffLineKind::Context, ""),
+                Some('\\') => continue,
+                Some(_) => return None,
+            };
+            let (old_line, new_line) = match kind {
+                DiffLineKind::Added => (None, Some(next.1)),
+                DiffLineKind::Removed => (Some(next.0), None),
+                DiffLineKind::Context => (Some(next.0), Some(next.1)),
+            };
+            if old_line.is_some() {
+                remaining.0 = remaining.0.checked_sub(1)?;
+                next.0 += 1;
+            }
+            if new_line.is_some() {
+                remaining.1 = remaining.1.checked_sub(1)?;
+                next.1 += 1;
+            }
+            hunk.lines.push(DiffLine {
+                kind,
+                old_line,
+                new_line,
+                text: text.to_string(),
+            });
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            diff.files.push(FileDiff {
+                path: rest.rsplit_once(" b/").map(|(_, path)| path).unwrap_or(rest).to_string(),
+                old_path: None,
+                hunks: Vec::new(),
+                notes: Vec::new(),
+            });
+        } else if line.starts_with("--- ") && lines.peek().is_some_and(|next| next.starts_with("+++ ")) {
+            let old = file_path(&line[4..]);
+            let new = file_path(&lines.next()?[4..]);
+            // `diff -u` has no `diff --git` line
+            let starts_file = diff.files.last().is_none_or(|file| !file.hunks.is_empty());
+            if starts_file {
+                diff.files.push(FileDiff {
+                    path: String::new(),
+                    old_path: None,
+                    hunks: Vec::new(),
+                    notes: Vec::new(),
+                });
+            }
+            let file = diff.files.last_mut()?;
+            match (old, new) {
+                (Some(old), Some(new)) if old != new => {
+                    file.path = new;
+                    file.old_path = Some(old);
+                }
+                (_, Some(new)) => file.path = new,
+                (Some(old), None) => file.path = old,
+                (None, None) => {}
+            }
+        } else if line.starts_with("@@ ") {
+            let ((old_start, old_count), (new_start, new_count)) = parse_hunk_header(line)?;
+            let file = diff.files.last_mut()?;
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+            remaining = (old_count, new_count);
+            next = (old_start, new_start);
+        } else if let Some(file) = diff.files.last_mut() {
+            if let Some(old) = line.strip_prefix("rename from ").or_else(|| line.strip_prefix("copy from ")) {
+                file.old_path = Some(old.to_string());
+            } else if is_extended_header(line) {
+                // Renames show as `old → new` already
+                const QUIET: &[&str] = &["index ", "similarity ", "rename to ", "copy to "];
+                if !QUIET.iter().any(|prefix| line.starts_with(prefix)) {
+                    file.notes.push(line.to_string());
+                }
+            } else if !line.trim().is_empty() && !line.starts_with('\\') {
+                // Anything else after a file started isn't a diff, like the
+                // commit headers of `git log -p`
+                return None;
+            }
+        } else {
+            diff.preamble.push(line.to_string());
+        }
+    }
+
+    let has_changes = diff.files.iter().any(|file| !file.hunks.is_empty() || !file.notes.is_empty());
+    has_changes.then_some(diff)
+}
+
+/// The path of a `--- a/src/main.rs` line, `None` for `/dev/null`.
+fn file_path(spec: &str) -> Option<String> {
+    // `diff -u` puts a tab and a timestamp after the path
+    let path = spec.split('\t').next().unwrap_or(spec).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path).to_string())
+}
+
+fn is_extended_header(line: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "index ", "new file mode", "deleted file mode", "old mode", "new mode", "similarity ", "dissimilarity ",
+        "rename to ", "copy to ", "Binary files ",
+    ];
+    PREFIXES.iter().any(|prefix| line.starts_with(prefix))
+}
+
+/// `@@ -12,5 +12,7 @@` as ((12, 5), (12, 7)). A missing count means 1.
+fn parse_hunk_header(line: &str) -> Option<((u32, u32), (u32, u32))> {
+    let mut ranges = line.strip_prefix("@@ ")?.split_whitespace();
+    let old = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let new = parse_range(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// A row of a side-by-side diff: the old line and the new one.
+pub type SideBySideRow<'a> = (Option<&'a DiffLine>, Option<&'a DiffLine>);
+
+/// Pairs the lines of a hunk for side-by-side display: context lines face
+/// themselves, and each run of removals faces the run of additions after it.
+pub fn side_by_side(hunk: &Hunk) -> Vec<SideBySideRow<'_>> {
+    let mut rows = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for line in &hunk.lines {
+        match line.kind {
+            DiffLineKind::Removed => {
+                if !added.is_empty() {
+                    pair_changes(&mut rows, &mut removed, &mut added);
+                }
+                removed.push(line);
+            }
+            DiffLineKind::Added => added.push(line),
+            DiffLineKind::Context => {
+                pair_changes(&mut rows, &mut removed, &mut added);
+                rows.push((Some(line), Some(line)));
+            }
+        }
+    }
+    pair_changes(&mut rows, &mut removed, &mut added);
+    rows
+}
+
+fn pair_changes<'a>(rows: &mut Vec<SideBySideRow<'a>>, removed: &mut Vec<&'a DiffLine>, added: &mut Vec<&'a DiffLine>) {
+    for i in 0..removed.len().max(added.len()) {
+        rows.push((removed.get(i).copied(), added.get(i).copied()));
+    }
+    removed.clear();
+    added.clear();
+}
@@ -0,0 +1,142 @@
+pub mod diff;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// Per-file cap when sending a diff to the AI.
+const MAX_FILE_DIFF_CHARS: usize = 4_000;
+/// Cap for the whole diff.
+const MAX_DIFF_CHARS: usize = 16_000;
+
+/// Generated files whose diffs are noise for a commit message.
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "Pipfile.lock",
+    "composer.lock",
+    "Gemfile.lock",
+    "go.sum",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GitContext {
+    pub branch: Option<String>,
+    pub has_staged_changes: bool,
+    pub has_unstaged_changes: bool,
+}
+
+impl GitContext {
+    /// Inspects the repository containing `dir`. Returns `None` outside a
+    /// git work tree or when git isn't installed.
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let inside = git(dir, &["rev-parse", "--is-inside-work-tree"]).ok()?;
+        if inside.trim() != "true" {
+            return None;
+        }
+
+        let branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .ok()
+            .map(|b| b.trim().to_string())
+            .filter(|b| !b.is_empty());
+
+        let status = git(dir, &["status", "--porcelain"]).unwrap_or_default();
+        let mut context = GitContext {
+            branch,
+            ..Default::default()
+        };
+        for line in status.lines() {
+            let mut flags = line.chars();
+            let (index, worktree) = (flags.next().unwrap_or(' '), flags.next().unwrap_or(' '));
+            if index != ' ' && index != '?' {
+                context.has_staged_changes = true;
+            }
+            if worktree != ' ' {
+                context.has_unstaged_changes = true;
+            }
+        }
+
+        Some(context)
+    }
+}
+
+/// `git diff --cached` for the repository containing `dir`.
+pub fn staged_diff(dir: &Path) -> Result<String> {
+    git(dir, &["diff", "--cached", "--no-color"])
+}
+
+/// Shrinks a diff for an AI prompt: lockfile diffs are replaced by a note,
+/// each file's diff is capped, and so is the total.
+pub fn truncate_diff(diff: &str) -> String {
+    let mut truncated = String::new();
+
+    for file_diff in split_file_diffs(diff) {
+        let path = file_diff_path(file_diff).unwrap_or_default();
+        let file_name = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let section = if LOCKFILES.contains(&file_name.as_str()) {
+            format!("diff --git a/{0} b/{0}\n[lockfile changes omitted]\n", path)
+        } else if file_diff.chars().count() > MAX_FILE_DIFF_CHARS {
+            let kept: String = file_diff.chars().take(MAX_FILE_DIFF_CHARS).collect();
+            format!("{}\n[... rest of {} diff truncated]\n", kept.trim_end(), path)
+        } else {
+            file_diff.to_string()
+        };
+
+        if truncated.chars().count() + section.chars().count() > MAX_DIFF_CHARS {
+            truncated.push_str("\n[... remaining files omitted]\n");
+            break;
+        }
+        truncated.push_str(&section);
+    }
+
+    truncated
+}
+
+fn split_file_diffs(diff: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = diff
+        .match_indices("diff --git ")
+        .filter(|(index, _)| *index == 0 || diff.as_bytes()[index - 1] == b'\n')
+        .map(|(index, _)| index)
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| &diff[*start..starts.get(i + 1).copied().unwrap_or(diff.len())])
+        .filter(|section| !section.trim().is_empty())
+        .collect()
+}
+
+fn file_diff_path(file_diff: &str) -> Option<String> {
+    let header = file_diff.lines().next()?.strip_prefix("diff --git ")?;
+    let b_path = header.rsplit(" b/").next()?;
+    Some(b_path.to_string())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
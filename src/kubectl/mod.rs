@@ -0,0 +1,183 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::runtime::Handle;
+
+/// A `kubectl` call taking longer than this is abandoned: an unreachable
+/// cluster shouldn't leave completions waiting.
+const KUBECTL_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// How long an answer is reused before it's asked again.
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Kinds resources are completed for, by every name `kubectl` accepts, and
+/// the name they're listed by.
+pub const RESOURCE_KINDS: &[(&[&str], &str)] = &[
+    (&["pod", "pods", "po"], "pods"),
+    (&["deployment", "deployments", "deploy"], "deployments"),
+    (&["service", "services", "svc"], "services"),
+];
+
+/// Something `kubectl` is asked, and the key its answer is cached under.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Query {
+    Contexts,
+    CurrentContext,
+    /// The namespace of the current context
+    CurrentNamespace,
+    Namespaces,
+    /// Names of resources of a kind (as listed in `RESOURCE_KINDS`) in a
+    /// namespace, or the current one
+    Resources { kind: String, namespace: Option<String> },
+}
+
+impl Query {
+    fn args(&self) -> Vec<String> {
+        let args: &[&str] = match self {
+            Query::Contexts => &["config", "get-contexts", "-o", "name"],
+            Query::CurrentContext => &["config", "current-context"],
+            Query::CurrentNamespace => &["config", "view", "--minify", "-o", "jsonpath={..namespace}"],
+            Query::Namespaces => &["get", "namespaces", "-o", "name", "--request-timeout=1s"],
+            Query::Resources { kind, namespace } => {
+                let mut args = vec!["get".to_string(), kind.clone(), "-o".to_string(), "name".to_string()];
+                args.push("--request-timeout=1s".to_string());
+                if let Some(namespace) = namespace {
+                    args.extend(["-n".to_string(), namespace.clone()]);
+                }
+                return args;
+            }
+        };
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Splits an answer into values, dropping the `pod/` of `-o name`
+    /// output. Context names are kept whole: they may contain slashes, like
+    /// EKS ARNs.
+    fn parse(&self, output: &str) -> Vec<String> {
+        let values = output.lines().map(str::trim).filter(|value| !value.is_empty());
+        match self {
+            Query::Namespaces | Query::Resources { .. } => values
+                .map(|value| value.split_once('/').map(|(_, name)| name).unwrap_or(value).to_string())
+                .collect(),
+            _ => values.map(str::to_string).collect(),
+        }
+    }
+}
+
+/// Whether the `kubectl` CLI is installed.
+pub fn is_available() -> bool {
+    which::which("kubectl").is_ok()
+}
+
+/// The name a kind of resource is listed by, for any of its spellings.
+pub fn resource_kind(word: &str) -> Option<&'static str> {
+    RESOURCE_KINDS
+        .iter()
+        .find(|(names, _)| names.contains(&word))
+        .map(|(_, kind)| *kind)
+}
+
+/// Whether a finished command may have changed contexts, namespaces or
+/// resources, so cached answers should be dropped.
+pub fn invalidates_cache(command: &str) -> bool {
+    let mut words = command.split_whitespace().skip_while(|word| *word != "kubectl").skip(1);
+    words
+        .find(|word| !word.starts_with('-'))
+        .is_some_and(|verb| matches!(verb, "config" | "apply" | "delete" | "create" | "scale" | "rollout"))
+}
+
+async fn kubectl(args: &[String]) -> Result<String> {
+    let output = Command::new("kubectl")
+        .args(args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(KUBECTL_TIMEOUT, output)
+        .await
+        .map_err(|_| anyhow!("kubectl {} timed out", args.join(" ")))?
+        .context("Failed to run kubectl")?;
+    if !output.status.success() {
+        bail!("kubectl {}: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Default)]
+struct Entry {
+    values: Vec<String>,
+    fetched: Option<Instant>,
+    refreshing: bool,
+}
+
+/// `kubectl` answers, asked in the background and reused for a few seconds.
+/// Readers never wait: they get the last answer, empty at first or when
+/// `kubectl` failed.
+pub struct KubectlCache {
+    entries: Mutex<HashMap<Query, Entry>>,
+    /// Bumped by `invalidate`, so answers asked for before it are dropped
+    generation: AtomicU64,
+    runtime: Handle,
+}
+
+impl KubectlCache {
+    pub fn new(runtime: Handle) -> Arc<Self> {
+        Arc::new(Self {
+            entries: Mutex::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+            runtime,
+        })
+    }
+
+    /// The last answer to `query`, asking again when it's stale.
+    pub fn get(self: &Arc<Self>, query: Query) -> Vec<String> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(query.clone()).or_default();
+        let stale = entry.fetched.is_none_or(|fetched| fetched.elapsed() >= CACHE_TTL);
+        if stale && !entry.refreshing {
+            entry.refreshing = true;
+            let cache = self.clone();
+            let generation = self.generation.load(Ordering::SeqCst);
+            self.runtime.spawn(async move {
+                let values = match kubectl(&query.args()).await {
+                    Ok(output) => query.parse(&output),
+                    Err(e) => {
+                        debug!("{}", e);
+                        Vec::new()
+                    }
+                };
+                let mut entries = cache.entries.lock().unwrap();
+                let entry = entries.entry(query).or_default();
+                entry.refreshing = false;
+                if cache.generation.load(Ordering::SeqCst) == generation {
+                    entry.values = values;
+                    entry.fetched = Some(Instant::now());
+                }
+            });
+        }
+        entry.values.clone()
+    }
+
+    /// The single value of an answer like the current context.
+    pub fn get_one(self: &Arc<Self>, query: Query) -> Option<String> {
+        self.get(query).into_iter().next()
+    }
+
+    /// Whether any answer is being waited for.
+    pub fn is_refreshing(&self) -> bool {
+        self.entries.lock().unwrap().values().any(|entry| entry.refreshing)
+    }
+
+    /// Marks every answer stale, keeping it until a new one arrives.
+    pub fn invalidate(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        for entry in entries.values_mut() {
+            entry.fetched = None;
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! ANTRAFT's terminal engine, AI agent, autocomplete and security scanning,
+//! usable without the GUI. The egui front end lives in `ui`, behind the
+//! `gui` feature.
+
+pub mod ai;
+pub mod autocomplete;
+pub mod config;
+pub mod crash;
+pub mod docker;
+pub mod doctor;
+pub mod file_explorer;
+pub mod git;
+pub mod kubectl;
+pub mod logging;
+pub mod notifications;
+pub mod security;
+pub mod terminal;
+pub mod tools;
+pub mod workflows;
+pub mod workspaces;
+
+/// The version `--version` prints: the package's, and the commit built.
+pub const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("ANTRAFT_GIT_HASH"), ")");
+
+#[cfg(feature = "gui")]
+pub mod ui;
@@ -0,0 +1,323 @@
+//! The app's logger: env_logger's output on stderr as before, teed into a
+//! bounded in-memory buffer for the Logs view and crash reports, and into a
+//! size-capped rotating file under the data directory when
+//! `logging.file` is set. Records are kept as they came and only formatted
+//! when shown, so logging costs little while nobody's looking.
+
+use crate::security::secrets;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Records kept in memory when `logging.buffer_size` isn't set.
+pub const DEFAULT_BUFFER_SIZE: usize = 5000;
+
+static BUFFER: OnceLock<Arc<LogBuffer>> = OnceLock::new();
+static FILE: Mutex<Option<RotatingFile>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Also write the log to `logs/antraft.log` in the data directory
+    pub file: bool,
+    /// Size at which the file is rotated, in KB
+    pub max_file_size_kb: u64,
+    /// Rotated files kept besides the current one
+    pub max_files: usize,
+    /// Records the Logs view can show
+    pub buffer_size: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: false,
+            max_file_size_kb: 1024,
+            max_files: 3,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn file_path(&self) -> PathBuf {
+        crate::config::data_dir().join("logs").join("antraft.log")
+    }
+}
+
+/// A log record as it came, numbered in order.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub seq: u64,
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// `[time level target] message`, with secrets redacted unless
+    /// `privacy.redact_logs` is off.
+    pub fn format(&self) -> String {
+        format!(
+            "[{} {:<5} {}] {}",
+            self.time.format("%Y-%m-%d %H:%M:%S%.3f"),
+            self.level,
+            self.target,
+            secrets::redact_log_message(&self.message)
+        )
+    }
+}
+
+/// The most recent records, the oldest dropped past the capacity.
+pub struct LogBuffer {
+    inner: Mutex<BufferInner>,
+}
+
+struct BufferInner {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(BufferInner {
+                entries: VecDeque::new(),
+                capacity,
+                next_seq: 0,
+            }),
+        }
+    }
+
+    pub fn push(&self, level: Level, target: &str, message: String) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        if inner.capacity == 0 {
+            return;
+        }
+        while inner.entries.len() >= inner.capacity {
+            inner.entries.pop_front();
+        }
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.entries.push_back(LogEntry {
+            seq,
+            time: Local::now(),
+            level,
+            target: target.to_string(),
+            message,
+        });
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.capacity = capacity;
+            while inner.entries.len() > capacity {
+                inner.entries.pop_front();
+            }
+        }
+    }
+
+    /// The number the next record will get, which changes whenever one is
+    /// added, so callers can tell whether to look again.
+    pub fn next_seq(&self) -> u64 {
+        self.inner.lock().map(|inner| inner.next_seq).unwrap_or_default()
+    }
+
+    /// The records kept, oldest first.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.inner
+            .lock()
+            .map(|inner| inner.entries.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The last `count` records, formatted.
+    pub fn recent_lines(&self, count: usize) -> Vec<String> {
+        let Ok(inner) = self.inner.try_lock() else {
+            return Vec::new();
+        };
+        let start = inner.entries.len().saturating_sub(count);
+        inner.entries.range(start..).map(LogEntry::format).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().map(|inner| inner.entries.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A log file that's renamed to `<name>.1` when it would grow past
+/// `max_bytes`, `<name>.1` to `<name>.2` and so on, keeping `max_files`
+/// of them.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        let length = line.len() as u64 + 1;
+        if self.size > 0 && self.size + length > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += length;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let rotated = |index: usize| PathBuf::from(format!("{}.{}", self.path.display(), index));
+        let _ = std::fs::remove_file(rotated(self.max_files));
+        for index in (1..self.max_files).rev() {
+            let _ = std::fs::rename(rotated(index), rotated(index + 1));
+        }
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            std::fs::rename(&self.path, rotated(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+struct AppLogger {
+    stderr: env_logger::Logger,
+    buffer: Arc<LogBuffer>,
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        let message = record.args().to_string();
+        if let Ok(mut file) = FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                let entry = LogEntry {
+                    seq: 0,
+                    time: Local::now(),
+                    level: record.level(),
+                    target: record.target().to_string(),
+                    message: message.clone(),
+                };
+                // Nowhere to log that logging failed
+                let _ = file.write_line(&entry.format());
+            }
+        }
+        self.buffer.push(record.level(), record.target(), message);
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        if let Ok(mut file) = FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+/// Installs the logger: stderr in env_logger's usual format, at `level`
+/// unless `RUST_LOG` says otherwise and with secrets redacted from messages
+/// (until the config says otherwise), and the in-memory buffer.
+pub fn init(level: LevelFilter) -> Result<Arc<LogBuffer>> {
+    let stderr = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level.as_str()))
+        .format(|buf, record| {
+            let message = record.args().to_string();
+            writeln!(
+                buf,
+                "[{} {} {}] {}",
+                buf.timestamp(),
+                buf.default_styled_level(record.level()),
+                record.target(),
+                secrets::redact_log_message(&message)
+            )
+        })
+        .build();
+    let max_level = stderr.filter().max(level);
+    let buffer = BUFFER.get_or_init(|| Arc::new(LogBuffer::new(DEFAULT_BUFFER_SIZE))).clone();
+    log::set_boxed_logger(Box::new(AppLogger {
+        stderr,
+        buffer: buffer.clone(),
+    }))
+    .context("A logger is already installed")?;
+    log::set_max_level(max_level);
+    Ok(buffer)
+}
+
+/// Applies `[logging]`: the buffer's size, and the file, opened or closed.
+pub fn configure(config: &LoggingConfig) -> Result<()> {
+    if let Some(buffer) = BUFFER.get() {
+        buffer.set_capacity(config.buffer_size);
+    }
+    let file = match config.file {
+        true => Some(RotatingFile::open(
+            &config.file_path(),
+            config.max_file_size_kb * 1024,
+            config.max_files,
+        )?),
+        false => None,
+    };
+    if let Ok(mut current) = FILE.lock() {
+        *current = file;
+    }
+    Ok(())
+}
+
+/// The buffer `init` installed, if it has run.
+pub fn buffer() -> Option<&'static Arc<LogBuffer>> {
+    BUFFER.get()
+}
+
+/// Records at `level` and more severe are logged from now on.
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
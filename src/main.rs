@@ -1,32 +1,136 @@
 use anyhow::Result;
-use clap::Parser;
-use log::info;
-use eframe::egui;
-
-mod terminal;
-mod ai;
-mod security;
-mod file_explorer;
-mod autocomplete;
-mod ui;
-
-use ui::AnTraftApp;
+use antraft::{config, crash, doctor, logging, security, terminal, tools};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
+use log::{info, LevelFilter};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
-#[command(name = "antraft")]
-#[command(about = "Next-gen AI-powered terminal application", long_about = None)]
+#[command(name = "antraft", version = antraft::VERSION)]
+#[command(about = "Next-gen AI-powered terminal application")]
+#[command(long_about = "Next-gen AI-powered terminal application.\n\n\
+    Without a command, opens the terminal window. The commands below run \
+    without a window, for scripts and CI.")]
+#[command(after_help = "Examples:\n  \
+    antraft -w ~/src/app              Open the terminal in ~/src/app\n  \
+    antraft scan --only-new           Scan the current directory, failing on new findings\n  \
+    antraft scan --format json > report.json\n  \
+    antraft doctor                    Check what ANTRAFT finds on this machine\n  \
+    antraft completions zsh > ~/.zfunc/_antraft")]
 struct Args {
     /// Enable debug logging
     #[arg(short, long)]
     debug: bool,
     
     /// Configuration file path
-    #[arg(short, long)]
+    #[arg(short, long, global = true, value_hint = ValueHint::FilePath)]
     config: Option<String>,
+
+    /// Use the named profile from the configuration file, e.g. `work` for
+    /// its [profiles.work] settings
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
     
     /// Working directory
-    #[arg(short = 'w', long)]
+    #[arg(short = 'w', long, value_hint = ValueHint::DirPath)]
     directory: Option<String>,
+
+    /// Validate the configuration file, report any problems and exit
+    #[arg(long)]
+    check_config: bool,
+
+    /// Print audit log records matching QUERY (e.g. "ai failed since:2024-05-01 git")
+    /// after checking the log for tampering, and exit
+    #[arg(long, value_name = "QUERY", num_args = 0..=1, default_missing_value = "")]
+    audit: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Scan a directory or a container image for vulnerabilities and print
+    /// the report, or list the licenses of a directory's dependencies with
+    /// --inventory
+    Scan {
+        /// The directory or file to scan; the current directory by default
+        #[arg(value_hint = ValueHint::AnyPath)]
+        path: Option<PathBuf>,
+
+        /// Scan a container image like nginx:1.25 with trivy instead
+        #[arg(long, value_name = "IMAGE", conflicts_with_all = ["path", "inventory"])]
+        image: Option<String>,
+
+        /// List the dependencies pinned by the lockfiles and their licenses
+        #[arg(long)]
+        inventory: bool,
+
+        /// Ask the package registries for licenses not found locally
+        #[arg(long, requires = "inventory")]
+        online: bool,
+
+        /// Print the inventory as CSV
+        #[arg(long, requires = "inventory")]
+        csv: bool,
+
+        /// What to scan for
+        #[arg(long = "type", value_enum, default_value_t = ScanKind::Full, conflicts_with = "inventory")]
+        scan_type: ScanKind,
+
+        /// How to print the report
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown, conflicts_with = "inventory")]
+        format: ReportFormat,
+
+        /// Report findings silenced by ignore comments like `# nosec` too
+        #[arg(long, conflicts_with = "inventory")]
+        include_ignored: bool,
+
+        /// Exit with an error only for findings no earlier scan had, going
+        /// by the findings database
+        #[arg(long, conflicts_with = "inventory")]
+        only_new: bool,
+    },
+
+    /// Print the completion script for a shell, e.g. for bash:
+    /// `antraft completions bash > ~/.local/share/bash-completion/completions/antraft`
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Show the shells and security scanners found, with their versions,
+    /// where the config and data live, and whether an API key is set
+    Doctor,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ScanKind {
+    /// Code, dependencies and config files
+    Full,
+    /// Fast checks only, skipping the slower tools
+    Quick,
+    /// Source code only
+    Code,
+    /// Dependencies only
+    Dependencies,
+}
+
+impl From<ScanKind> for security::ScanType {
+    fn from(kind: ScanKind) -> Self {
+        match kind {
+            ScanKind::Full => security::ScanType::Full,
+            ScanKind::Quick => security::ScanType::Quick,
+            ScanKind::Code => security::ScanType::CodeOnly,
+            ScanKind::Dependencies => security::ScanType::DependenciesOnly,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    Markdown,
+    Json,
 }
 
 #[tokio::main]
@@ -34,12 +138,61 @@ async fn main() -> Result<()> {
     let args = Args::parse();
     
     // Initialize logging
-    if args.debug {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
-    } else {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-    }
+    logging::init(if args.debug { LevelFilter::Debug } else { LevelFilter::Info })?;
+    crash::install_panic_hook(config::data_dir());
     
+    let config_path = args.config.as_deref().map(PathBuf::from);
+    if args.check_config {
+        return check_config(config_path.as_deref(), args.profile.as_deref());
+    }
+    if let Some(query) = &args.audit {
+        return print_audit_log(config_path.as_deref(), args.profile.as_deref(), query);
+    }
+    match args.command {
+        Some(Command::Scan {
+            path,
+            image,
+            inventory,
+            online,
+            csv,
+            scan_type,
+            format,
+            include_ignored,
+            only_new,
+        }) => {
+            let config = config::Config::load_profile(config_path.as_deref(), args.profile.as_deref())?;
+            security::secrets::set_redact_logs(config.privacy.redact_logs);
+            let options = ReportOptions {
+                scan_type: scan_type.into(),
+                format,
+                include_ignored,
+                only_new,
+            };
+            if let Some(image) = image {
+                return print_scan_report(config, security::ScanTarget::ContainerImage(image), options).await;
+            }
+            let path = match path {
+                Some(path) => path,
+                None => std::env::current_dir()?,
+            };
+            return match inventory {
+                true => print_inventory(&config, &path, online, csv).await,
+                false => print_scan_report(config, security::ScanTarget::Path(path), options).await,
+            };
+        }
+        Some(Command::Completions { shell }) => {
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Args::command(), "antraft", &mut script);
+            return Ok(std::io::stdout().write_all(&script)?);
+        }
+        Some(Command::Doctor) => {
+            let detector = tools::ToolDetector::new();
+            print!("{}", doctor::diagnose(config_path.as_deref(), args.profile.as_deref(), &detector));
+            return Ok(());
+        }
+        None => {}
+    }
+
     info!("🚀 Starting ANTRAFT - Next-gen AI Terminal");
     
     // Set working directory if specified
@@ -48,16 +201,28 @@ async fn main() -> Result<()> {
         info!("Changed working directory to: {}", dir);
     }
     
+    let config = config::Config::load_profile(config_path.as_deref(), args.profile.as_deref())?;
+    security::secrets::set_redact_logs(config.privacy.redact_logs);
+    if let Err(e) = logging::configure(&config.logging) {
+        log::warn!("Logging only to stderr and the Logs view: {:#}", e);
+    }
+    if let Err(e) = config.validate() {
+        log::warn!("Invalid configuration: {:#}", e);
+    }
+    run_gui(config).await
+}
+
+#[cfg(feature = "gui")]
+async fn run_gui(config: config::Config) -> Result<()> {
+    use antraft::ui::AnTraftApp;
     // Launch the GUI application
     info!("🚀 Launching ANTRAFT GUI...");
-    
-    let config = ui::Config::default();
     let app = AnTraftApp::new(config).await?;
     
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1200.0, 800.0])
-            .with_title("ANTRAFT - AI Terminal"),
+        // Reopens as it was closed; the app keeps the layout itself
+        viewport: app.viewport().with_title("ANTRAFT - AI Terminal"),
+        persist_window: false,
         ..Default::default()
     };
     
@@ -72,3 +237,176 @@ async fn main() -> Result<()> {
     
     Ok(())
 }
+
+#[cfg(not(feature = "gui"))]
+async fn run_gui(_config: config::Config) -> Result<()> {
+    anyhow::bail!("ANTRAFT was built without the `gui` feature; only its commands, --check-config and --audit are available")
+}
+
+/// `--check-config`: reports whether the config parses and validates.
+fn check_config(path: Option<&Path>, profile: Option<&str>) -> Result<()> {
+    let config = config::Config::load_profile(path, profile)?;
+    let path = config.path.clone().unwrap_or_else(config::default_config_path);
+    if !path.exists() {
+        println!("No config file at {}; the defaults will be used", path.display());
+    }
+    config.validate()?;
+    println!("✅ {} is valid", path.display());
+    Ok(())
+}
+
+fn print_audit_log(config_path: Option<&Path>, profile: Option<&str>, query: &str) -> Result<()> {
+    let config = config::Config::load_profile(config_path, profile)?;
+    let path = config.audit.log_path();
+    if !path.exists() {
+        println!("No audit log at {}; enable it with `[audit] enabled = true`", path.display());
+        return Ok(());
+    }
+
+    let filter = terminal::audit::AuditFilter::parse(query)?;
+    let records = terminal::audit::read_records(&path)?;
+    if let Err(e) = terminal::audit::verify(&records) {
+        eprintln!("⚠ {}", e);
+    }
+    for record in records.iter().filter(|record| filter.matches(record)) {
+        println!(
+            "{}  {:>4}  {:>7}  {}{}  ({})",
+            record.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+            record.exit_code.map(|code| code.to_string()).unwrap_or_else(|| "-".to_string()),
+            record
+                .duration_ms
+                .map(terminal::block::format_duration)
+                .unwrap_or_default(),
+            if record.ai_originated { "[ai] " } else { "" },
+            record.command,
+            record.working_directory
+        );
+    }
+    Ok(())
+}
+
+/// How `scan` scans and prints its report.
+struct ReportOptions {
+    scan_type: security::ScanType,
+    format: ReportFormat,
+    include_ignored: bool,
+    only_new: bool,
+}
+
+/// `scan`: prints the report, and records it in the findings database
+/// when findings are tracked. With `only_new`, exits with an error when
+/// there are findings no earlier scan had.
+async fn print_scan_report(config: config::Config, target: security::ScanTarget, options: ReportOptions) -> Result<()> {
+    let ReportOptions {
+        scan_type,
+        format,
+        include_ignored,
+        only_new,
+    } = options;
+    let db_path = config.security.findings_db_path();
+    let track = config.security.track_findings || only_new;
+    let scanner = security::SecurityScanner::new(config.security)?;
+    let mut report = scanner
+        .scan(security::SecurityScanRequest {
+            target,
+            scan_type,
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            honor_ignore_comments: !include_ignored,
+        })
+        .await?;
+
+    let changes = match track {
+        true => match record_findings(&db_path, &mut report) {
+            Ok(changes) => Some(changes),
+            Err(e) if only_new => return Err(e),
+            Err(e) => {
+                eprintln!("⚠ Findings not tracked: {:#}", e);
+                None
+            }
+        },
+        false => None,
+    };
+    match format {
+        ReportFormat::Markdown => print!("{}", report.to_markdown()),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    let Some(changes) = changes else {
+        return Ok(());
+    };
+    if !changes.reopened.is_empty() || !changes.fixed.is_empty() {
+        eprintln!("{} reopened, {} fixed since the last scan", changes.reopened.len(), changes.fixed.len());
+    }
+    let new: Vec<_> = report
+        .vulnerabilities
+        .iter()
+        .filter(|v| changes.new.contains(&security::findings_db::fingerprint(&report, v)))
+        .collect();
+    if new.is_empty() {
+        eprintln!("No new findings");
+        return Ok(());
+    }
+    eprintln!("{} new finding{}:", new.len(), if new.len() == 1 { "" } else { "s" });
+    for vulnerability in &new {
+        let line = vulnerability.line_number.map(|line| format!(":{}", line)).unwrap_or_default();
+        eprintln!("  {:?} {} ({}{})", vulnerability.severity, vulnerability.title, vulnerability.file_path, line);
+    }
+    if only_new {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Records `report` in the findings database at `path`, comparing its risk
+/// score with the last scan of its target.
+fn record_findings(path: &Path, report: &mut security::SecurityReport) -> Result<security::findings_db::ScanChanges> {
+    let mut store = security::findings_db::FindingsStore::open(path)?;
+    if let Some(score) = store.last_risk_score(&report.target_name())? {
+        report.set_previous_risk_score(score);
+    }
+    store.record_scan(report)
+}
+
+/// `scan --inventory`: prints the dependencies and their licenses, then
+/// the counts per license and the denied ones.
+async fn print_inventory(config: &config::Config, path: &Path, online: bool, csv: bool) -> Result<()> {
+    let inventory = security::inventory::DependencyInventory::build(path, &config.security, online).await?;
+    for error in &inventory.errors {
+        eprintln!("⚠ {}", error);
+    }
+    if csv {
+        print!("{}", inventory.to_csv());
+        return Ok(());
+    }
+    if inventory.entries.is_empty() {
+        println!("No dependencies found in lockfiles under {}", path.display());
+        return Ok(());
+    }
+
+    for entry in &inventory.entries {
+        println!(
+            "{:<10} {:<32} {:<14} {}",
+            entry.ecosystem.name(),
+            entry.name,
+            entry.version,
+            entry.license.as_deref().unwrap_or("?")
+        );
+    }
+    println!("\nLicenses:");
+    for (license, count) in inventory.license_counts() {
+        println!("{:>6}  {}", count, license);
+    }
+    let unknown = inventory.unknown().count();
+    if unknown > 0 && !online {
+        println!("\n{} package{} without a known license; --online asks the registries", unknown, if unknown == 1 { "" } else { "s" });
+    }
+    let findings = inventory.findings();
+    if !findings.is_empty() {
+        println!("\nDenied licenses:");
+        for finding in &findings {
+            println!("  ⚠ {} ({})", finding.title, finding.file_path);
+        }
+    }
+    Ok(())
+}
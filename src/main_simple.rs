@@ -1,70 +0,0 @@
-use anyhow::Result;
-use clap::Parser;
-use log::info;
-
-#[derive(Parser, Debug)]
-#[command(name = "antraft")]
-#[command(about = "Next-gen AI-powered terminal application", long_about = None)]
-struct Args {
-    /// Enable debug logging
-    #[arg(short, long)]
-    debug: bool,
-    
-    /// Configuration file path
-    #[arg(short, long)]
-    config: Option<String>,
-    
-    /// Working directory
-    #[arg(short, long)]
-    directory: Option<String>,
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-    
-    // Initialize logging
-    if args.debug {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
-    } else {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-    }
-    
-    info!("🚀 Starting ANTRAFT - Next-gen AI Terminal");
-    
-    // Set working directory if specified
-    if let Some(dir) = args.directory {
-        std::env::set_current_dir(&dir)?;
-        info!("Changed working directory to: {}", dir);
-    }
-    
-    // For now, just run a simple demo
-    println!("🎉 Welcome to ANTRAFT!");
-    println!("📋 MVP Features Status:");
-    println!("  ✅ Project structure created");
-    println!("  ✅ AI integration (Gemini) ready");
-    println!("  ✅ Security scanning modules implemented");
-    println!("  ✅ File explorer implemented");
-    println!("  ✅ Autocomplete engine ready");
-    println!("  ⏳ GUI integration in progress...");
-    
-    println!("\n🔧 Available environment variables:");
-    if let Ok(api_key) = std::env::var("GEMINI_API_KEY") {
-        if !api_key.is_empty() {
-            println!("  ✅ GEMINI_API_KEY is configured");
-        } else {
-            println!("  ⚠️  GEMINI_API_KEY is empty");
-        }
-    } else {
-        println!("  ❌ GEMINI_API_KEY is not set");
-    }
-    
-    println!("\n🏗️  To complete the setup:");
-    println!("  1. Set GEMINI_API_KEY environment variable");
-    println!("  2. Install security tools (optional):");
-    println!("     pip install bandit semgrep");
-    println!("     go install github.com/google/osv-scanner/cmd/osv-scanner@v1");
-    println!("  3. Build and run: cargo run");
-    
-    Ok(())
-}
@@ -0,0 +1,167 @@
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Notifications kept for the history, oldest dropped first.
+pub const DEFAULT_CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl NotificationLevel {
+    /// How long the toast shows, or `None` to keep it until dismissed.
+    pub fn lifetime(self) -> Option<Duration> {
+        match self {
+            NotificationLevel::Info | NotificationLevel::Success => Some(Duration::from_secs(5)),
+            NotificationLevel::Warning => Some(Duration::from_secs(10)),
+            NotificationLevel::Error => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u64,
+    pub level: NotificationLevel,
+    pub title: String,
+    /// Usually the error message
+    pub detail: Option<String>,
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    /// When it was sent, for expiry
+    pub sent: Instant,
+    /// Still shown as a toast; it stays in the history either way
+    pub visible: bool,
+}
+
+/// Sends notifications to the app from any thread. Cheap to clone.
+#[derive(Clone)]
+pub struct NotificationSender {
+    tx: Sender<Notification>,
+}
+
+impl NotificationSender {
+    pub fn send(&self, level: NotificationLevel, title: impl Into<String>, detail: Option<String>) {
+        let _ = self.tx.send(Notification {
+            id: 0,
+            level,
+            title: title.into(),
+            detail,
+            timestamp: chrono::Local::now(),
+            sent: Instant::now(),
+            visible: true,
+        });
+    }
+
+    pub fn info(&self, title: impl Into<String>) {
+        self.send(NotificationLevel::Info, title, None);
+    }
+
+    pub fn success(&self, title: impl Into<String>, detail: impl Into<String>) {
+        self.send(NotificationLevel::Success, title, Some(detail.into()));
+    }
+
+    pub fn warning(&self, title: impl Into<String>, detail: impl ToString) {
+        self.send(NotificationLevel::Warning, title, Some(detail.to_string()));
+    }
+
+    /// An error, which stays on screen until dismissed. Also logged.
+    pub fn error(&self, title: impl Into<String>, detail: impl ToString) {
+        let (title, detail) = (title.into(), detail.to_string());
+        log::error!("{}: {}", title, detail);
+        self.send(NotificationLevel::Error, title, Some(detail));
+    }
+}
+
+/// Notifications sent through `NotificationSender`s, as toasts that expire
+/// (errors excepted) and a bounded history.
+pub struct Notifications {
+    history: VecDeque<Notification>,
+    capacity: usize,
+    next_id: u64,
+    tx: Sender<Notification>,
+    rx: Receiver<Notification>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl Notifications {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        Self {
+            history: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_id: 1,
+            tx,
+            rx,
+        }
+    }
+
+    pub fn sender(&self) -> NotificationSender {
+        NotificationSender { tx: self.tx.clone() }
+    }
+
+    /// Takes in everything sent since the last call.
+    pub fn drain(&mut self) {
+        while let Ok(notification) = self.rx.try_recv() {
+            self.push(notification);
+        }
+    }
+
+    fn push(&mut self, mut notification: Notification) {
+        notification.id = self.next_id;
+        self.next_id += 1;
+        self.history.push_back(notification);
+        while self.history.len() > self.capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// Hides the toasts that have outlived their level's lifetime at `now`.
+    pub fn expire(&mut self, now: Instant) {
+        for notification in self.history.iter_mut().filter(|n| n.visible) {
+            if notification
+                .level
+                .lifetime()
+                .is_some_and(|lifetime| now.saturating_duration_since(notification.sent) >= lifetime)
+            {
+                notification.visible = false;
+            }
+        }
+    }
+
+    /// When the next toast expires, to repaint then.
+    pub fn next_expiry(&self, now: Instant) -> Option<Duration> {
+        self.toasts()
+            .filter_map(|n| Some((n.sent + n.level.lifetime()?).saturating_duration_since(now)))
+            .min()
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        if let Some(notification) = self.history.iter_mut().find(|n| n.id == id) {
+            notification.visible = false;
+        }
+    }
+
+    /// Notifications shown as toasts, oldest first.
+    pub fn toasts(&self) -> impl DoubleEndedIterator<Item = &Notification> {
+        self.history.iter().filter(|n| n.visible)
+    }
+
+    /// Every notification kept, oldest first.
+    pub fn history(&self) -> &VecDeque<Notification> {
+        &self.history
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
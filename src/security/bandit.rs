@@ -1,4 +1,7 @@
+use super::chunks::{self, PathChunk};
+use super::scanner::ScanFuture;
 use super::{ScanResult, Severity, Vulnerability};
+use crate::tools::{self, ToolDetector};
 use anyhow::Result;
 use std::path::PathBuf;
 use tokio::process::Command;
@@ -8,72 +11,84 @@ pub struct BanditScanner {
 }
 
 impl BanditScanner {
+    /// Runs `bandit` from `PATH`; a scan fails when it isn't installed.
     pub fn new() -> Result<Self> {
-        // Try to find bandit in common locations
-        let _possible_paths: Vec<PathBuf> = vec![];
-
-        // For now, assume bandit is available. In a real implementation,
-        // we'd check if the binary exists
-        Ok(Self {
-            binary_path: PathBuf::from("bandit"),
-        })
+        let binary_path = ToolDetector::new()
+            .find(tools::BANDIT.binary)
+            .unwrap_or_else(|| PathBuf::from(tools::BANDIT.binary));
+        Ok(Self { binary_path })
     }
 
     pub async fn scan(&self, path: &PathBuf) -> Result<ScanResult> {
-        let output = Command::new(&self.binary_path)
-            .args(&["-r", &path.display().to_string(), "-f", "json"])
-            .output()
-            .await?;
+        scan_paths(self.binary_path.clone(), vec![path.clone()], Vec::new()).await
+    }
 
-        if !output.status.success() {
-            return Ok(ScanResult::Error("Bandit scan failed".to_string()));
-        }
+    /// `scan` of `chunk` without `excludes`, owning what it needs so chunks
+    /// can run side by side.
+    pub fn scan_chunk(&self, chunk: &PathChunk, excludes: &[String]) -> ScanFuture<'static> {
+        Box::pin(scan_paths(self.binary_path.clone(), chunk.paths.clone(), excludes.to_vec()))
+    }
+}
 
-        let response: serde_json::Value = serde_json::from_slice(&output.stdout)?;
-        let mut vulnerabilities = Vec::new();
+async fn scan_paths(binary_path: PathBuf, paths: Vec<PathBuf>, excludes: Vec<String>) -> Result<ScanResult> {
+    let mut command = Command::new(binary_path);
+    command.arg("-r").args(&paths).args(["-f", "json"]);
+    if !excludes.is_empty() {
+        command.arg("-x").arg(excludes.join(","));
+    }
+    Ok(match chunks::run_json(command, "bandit").await? {
+        Ok(response) => ScanResult::Success(parse_results(&response)),
+        Err(error) => ScanResult::Error(error),
+    })
+}
 
-        if let Some(results) = response.get("results") {
-            for result in results.as_array().unwrap_or(&vec![]) {
-                let vuln = Vulnerability {
-                    id: result
-                        .get("test_id")
-                        .map(|v| v.as_str().unwrap_or_default().to_string())
-                        .unwrap_or_default(),
-                    title: result
-                        .get("issue_text")
-                        .map(|v| v.as_str().unwrap_or_default().to_string())
-                        .unwrap_or_default(),
-                    description: result
-                        .get("issue_confidence")
-                        .map(|v| v.as_str().unwrap_or_default().to_string())
-                        .unwrap_or_default(),
-                    severity: map_severity(
-                        result
-                            .get("issue_severity")
-                            .map(|v| v.as_str().unwrap_or_default())
-                            .unwrap_or(""),
-                    ),
-                    category: "python-code".to_string(),
-                    file_path: result
-                        .get("filename")
-                        .map(|v| v.as_str().unwrap_or_default().to_string())
-                        .unwrap_or_default(),
-                    line_number: result
-                        .get("line_number")
-                        .and_then(|v| v.as_u64())
-                        .map(|v| v as usize),
-                    column_number: None,
-                    code_snippet: None,
-                    suggested_fix: None,
-                    references: vec![],
-                    scanner: "bandit".to_string(),
-                };
-                vulnerabilities.push(vuln);
-            }
-        }
+/// The findings in bandit's `-f json` output.
+pub fn parse_results(response: &serde_json::Value) -> Vec<Vulnerability> {
+    let mut vulnerabilities = Vec::new();
 
-        Ok(ScanResult::Success(vulnerabilities))
+    if let Some(results) = response.get("results") {
+        for result in results.as_array().unwrap_or(&vec![]) {
+            let vuln = Vulnerability {
+                id: result
+                    .get("test_id")
+                    .map(|v| v.as_str().unwrap_or_default().to_string())
+                    .unwrap_or_default(),
+                title: result
+                    .get("issue_text")
+                    .map(|v| v.as_str().unwrap_or_default().to_string())
+                    .unwrap_or_default(),
+                description: result
+                    .get("issue_confidence")
+                    .map(|v| v.as_str().unwrap_or_default().to_string())
+                    .unwrap_or_default(),
+                severity: map_severity(
+                    result
+                        .get("issue_severity")
+                        .map(|v| v.as_str().unwrap_or_default())
+                        .unwrap_or(""),
+                ),
+                category: "python-code".to_string(),
+                file_path: result
+                    .get("filename")
+                    .map(|v| v.as_str().unwrap_or_default().to_string())
+                    .unwrap_or_default(),
+                line_number: result
+                    .get("line_number")
+                    .and_then(|v| v.as_u64())
+                    .map(|v| v as usize),
+                column_number: None,
+                code_snippet: None,
+                suggested_fix: None,
+                references: vec![],
+                scanner: "bandit".to_string(),
+                remediation: None,
+                suppression: None,
+            };
+            vulnerabilities.push(vuln);
+        }
     }
+
+    vulnerabilities
 }
 
 fn map_severity(severity: &str) -> Severity {
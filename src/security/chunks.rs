@@ -0,0 +1,163 @@
+//! Splitting a scan target into chunks scanned side by side. semgrep and
+//! bandit only print their JSON report once they're done, so a tree scanned
+//! whole shows nothing until the end; scanned a top-level directory at a
+//! time, each part's findings come in as it finishes.
+
+use anyhow::{anyhow, Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+/// Chunks of one scanner scanned at a time.
+pub const CHUNK_CONCURRENCY: usize = 4;
+
+/// The name of the chunk of files at the top of a directory.
+pub const TOP_LEVEL_CHUNK: &str = "(top level)";
+
+/// Part of a scan target, scanned in one run of a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathChunk {
+    /// The top-level directory's name, `TOP_LEVEL_CHUNK`, or the file's
+    /// name for a target that's a file
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+}
+
+/// What a scan leaves out: directories and files named in
+/// `security.excluded_paths`, and paths matching the request's exclude
+/// patterns, gitignore style.
+pub struct Exclusions {
+    names: Vec<String>,
+    patterns: Vec<String>,
+    matcher: Gitignore,
+}
+
+impl Exclusions {
+    pub fn new(root: &Path, names: &[String], patterns: &[String]) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid exclude pattern {:?}", pattern))?;
+        }
+        Ok(Self {
+            names: names.to_vec(),
+            patterns: patterns.to_vec(),
+            matcher: builder.build().context("Invalid exclude patterns")?,
+        })
+    }
+
+    /// Whether `path`, under the root, is left out of the scan.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let named = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| self.names.iter().any(|excluded| excluded == name));
+        named || self.matcher.matched(path, is_dir).is_ignore()
+    }
+
+    /// The names and patterns, for the tools to leave out what's nested
+    /// deeper than the chunks.
+    pub fn tool_patterns(&self) -> Vec<String> {
+        self.names.iter().chain(&self.patterns).cloned().collect()
+    }
+}
+
+/// `root` split into a chunk per top-level directory and one for the files
+/// at the top, without what `exclusions` leaves out. A link is only kept
+/// when it leads somewhere inside `root` no other chunk covers, so nothing
+/// is scanned twice; links out of `root` are left alone, as the tools leave
+/// them when walking it. A `root` that's a file is its own chunk.
+pub fn chunk_paths(root: &Path, exclusions: &Exclusions) -> Result<Vec<PathChunk>> {
+    if !root.is_dir() {
+        return Ok(vec![PathChunk {
+            name: root.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            paths: vec![root.to_path_buf()],
+        }]);
+    }
+    let canonical_root = root.canonicalize().with_context(|| format!("Failed to resolve {}", root.display()))?;
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(root).with_context(|| format!("Failed to read {}", root.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        let Ok(resolved) = path.canonicalize() else {
+            // A broken link
+            continue;
+        };
+        let is_dir = resolved.is_dir();
+        if exclusions.is_excluded(&path, is_dir) || !resolved.starts_with(&canonical_root) || resolved == canonical_root {
+            continue;
+        }
+        let is_link = entry.file_type()?.is_symlink();
+        entries.push((is_link, path, resolved, is_dir));
+    }
+    // Real paths first, so they're the ones kept over links to them
+    entries.sort();
+
+    let mut kept: Vec<PathBuf> = Vec::new();
+    let mut directories = Vec::new();
+    let mut files = Vec::new();
+    for (_, path, resolved, is_dir) in entries {
+        if kept.iter().any(|other| resolved.starts_with(other) || other.starts_with(&resolved)) {
+            continue;
+        }
+        kept.push(resolved);
+        match is_dir {
+            true => directories.push(path),
+            false => files.push(path),
+        }
+    }
+
+    let mut chunks: Vec<PathChunk> = directories
+        .into_iter()
+        .map(|path| PathChunk {
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            paths: vec![path],
+        })
+        .collect();
+    if !files.is_empty() {
+        chunks.push(PathChunk {
+            name: TOP_LEVEL_CHUNK.to_string(),
+            paths: files,
+        });
+    }
+    Ok(chunks)
+}
+
+/// Runs `command`, reading its output as it comes so a big report never
+/// fills the pipe, and returns the JSON report it printed, or the last line
+/// it wrote to stderr when it didn't print one. The process is killed if the
+/// scan is dropped, as when it times out.
+pub async fn run_json(mut command: Command, tool: &str) -> Result<Result<Value, String>> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run {}", tool))?;
+    let mut stdout = child.stdout.take().ok_or_else(|| anyhow!("{} has no stdout", tool))?;
+    let mut stderr = child.stderr.take().ok_or_else(|| anyhow!("{} has no stderr", tool))?;
+    let (mut report, mut errors) = (Vec::new(), Vec::new());
+    let (read_report, read_errors) = tokio::join!(stdout.read_to_end(&mut report), stderr.read_to_end(&mut errors));
+    read_report.with_context(|| format!("Failed to read {}'s report", tool))?;
+    read_errors.with_context(|| format!("Failed to read {}'s errors", tool))?;
+    let status = child.wait().await?;
+
+    // Both tools exit non-zero when they find something, with the report
+    // printed all the same
+    match serde_json::from_slice::<Value>(&report) {
+        Ok(report) if report.get("results").is_some() => Ok(Ok(report)),
+        _ => {
+            let errors = String::from_utf8_lossy(&errors);
+            let error = errors.lines().rev().find(|line| !line.trim().is_empty());
+            Ok(Err(match error {
+                Some(error) => error.trim().to_string(),
+                None => format!("{} failed ({})", tool, status),
+            }))
+        }
+    }
+}
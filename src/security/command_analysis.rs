@@ -0,0 +1,175 @@
+use super::secrets;
+use super::Severity;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// A check of a command line, by regexes over it. Commands are checked as
+/// written, quotes and all, so a pattern inside a string still counts:
+/// these are hints to look again, not a parser's verdict.
+pub struct CommandRule {
+    /// What `security.disabled_command_rules` names it by
+    pub id: &'static str,
+    pub severity: Severity,
+    pub title: &'static str,
+    pub explanation: &'static str,
+    /// Any of these matching is a finding
+    pub patterns: &'static [&'static str],
+}
+
+/// Shells and interpreters a download shouldn't be piped into: a shell
+/// with any arguments, others only when reading the script from stdin, as
+/// `| python3 -m json.tool` is harmless.
+const INTERPRETER: &str = r"(?:sudo\s+(?:-\S+\s+)*)?(?:env\s+)?(?:(?:ba|z|k|da|fi|c|tc)?sh\b|(?:python[0-9.]*|perl|ruby|node|php|pwsh|powershell)(?:\s+-)?\s*(?:$|[;&|)])|iex\b)";
+
+pub const COMMAND_RULES: &[CommandRule] = &[
+    CommandRule {
+        id: "delete-root",
+        severity: Severity::Critical,
+        title: "Deletes everything under / or your home directory",
+        explanation: "rm -rf on / or ~ removes the system or all your files, and there's no undo.",
+        patterns: &[
+            r"\brm\s+(?:-\S+\s+)*-(?:[a-zA-Z]*r[a-zA-Z]*f|[a-zA-Z]*f[a-zA-Z]*r)[a-zA-Z]*\s+(?:-\S+\s+)*(?:/|/\*|~|~/|~/\*|\$HOME|\$HOME/|\$HOME/\*)(?:\s|;|&|\||$)",
+            r"\brm\s+(?:-\S+\s+)*--recursive\s+(?:-\S+\s+)*(?:/|/\*|~|~/|\$HOME)(?:\s|;|&|\||$)",
+        ],
+    },
+    CommandRule {
+        id: "pipe-to-shell",
+        severity: Severity::High,
+        title: "Runs a script straight from the internet",
+        explanation: "Whatever the server sends is run with your permissions, without a chance to read it first, and it can differ from what others got. Download it, read it, then run it.",
+        patterns: &[
+            // curl … | sh, wget -qO- … | sudo bash
+            r"\b(?:curl|wget|fetch|iwr|Invoke-WebRequest|irm|Invoke-RestMethod)\b[^|;&]*\|\s*(?:INTERPRETER)",
+            // bash <(curl …), sh -c "$(curl …)"
+            r"\b(?:INTERPRETER)\s+(?:-\S+\s+)*<\(\s*(?:curl|wget)\b",
+            r#"\b(?:INTERPRETER)\s+-c\s+["']?\$\(\s*(?:curl|wget)\b"#,
+            r"\b(?:eval|source|\.)\s+.*\$\(\s*(?:curl|wget)\b",
+        ],
+    },
+    CommandRule {
+        id: "base64-eval",
+        severity: Severity::High,
+        title: "Runs hidden, base64-encoded code",
+        explanation: "Decoding base64 into a shell hides what actually runs; obfuscation like this is common in malicious snippets. Decode it and read it first.",
+        patterns: &[
+            r"\bbase64\s+(?:-\S+\s+)*(?:-d|-D|--decode|-[a-zA-Z]*d)\b[^;&]*\|\s*(?:INTERPRETER)",
+            r"\b(?:eval|exec)\b[^;&]*\bbase64\s+(?:-\S+\s+)*(?:-d|-D|--decode)\b",
+            r"(?i)\b(?:powershell|pwsh)\b.*\s-(?:e|enc|encodedcommand)\s+[A-Za-z0-9+/=]{16,}",
+        ],
+    },
+    CommandRule {
+        id: "system-path-write",
+        severity: Severity::High,
+        title: "Writes to system files or a disk",
+        explanation: "Overwriting files under /etc, /usr, /boot and the like, or a disk device, can break the system or open it up. Make sure you meant to change them.",
+        patterns: &[
+            r"(?:>>?|\btee\s+(?:-\S+\s+)*)\s*/(?:etc|usr|bin|sbin|boot|lib|lib32|lib64|sys|proc|System|Library)/",
+            r"\bdd\b[^;&|]*\bof=/dev/(?:sd|hd|vd|xvd|nvme|disk|rdisk|mmcblk)",
+            r">\s*/dev/(?:sd|hd|vd|xvd|nvme|disk|rdisk|mmcblk)",
+            r"\bmkfs(?:\.\w+)?\s+(?:-\S+\s+)*/dev/",
+        ],
+    },
+    CommandRule {
+        id: "chmod-777",
+        severity: Severity::Medium,
+        title: "Makes files writable by everyone",
+        explanation: "chmod 777 lets any user or process on the machine change or replace the files. Grant only what's needed, like 755 for programs or 644 for files.",
+        patterns: &[r"\bchmod\s+(?:-\S+\s+)*(?:0?777|a\+rwx|ugo\+rwx|a=rwx|ugo=rwx)(?:\s|$)"],
+    },
+    CommandRule {
+        id: "tls-disabled",
+        severity: Severity::Medium,
+        title: "Turns off TLS certificate checks",
+        explanation: "Without certificate checks, anyone on the network path can impersonate the server and change what's downloaded or steal what's sent.",
+        patterns: &[
+            r"\bcurl\b[^|;&]*\s(?:--insecure|-[a-zA-Z]*k[a-zA-Z]*)(?:\s|$)",
+            r"\bwget\b[^|;&]*\s--no-check-certificate\b",
+            r"(?i)\bhttp\.sslVerify\s*=?\s*false\b",
+            r"\bGIT_SSL_NO_VERIFY=(?:1|true)\b",
+            r"\bNODE_TLS_REJECT_UNAUTHORIZED=0\b",
+            r"\b(?:npm|yarn|pnpm)\s+config\s+set\s+strict-ssl\s+false\b",
+            r"\bpip3?\b[^|;&]*\s--trusted-host\b",
+            r"\bPYTHONHTTPSVERIFY=0\b",
+            r"(?i)-SkipCertificateCheck\b",
+        ],
+    },
+    CommandRule {
+        id: "credential-echo",
+        severity: Severity::Medium,
+        title: "Prints credentials",
+        explanation: "Printed secrets end up in the scrollback, session recordings and shared blocks, and anything the output is piped to sees them.",
+        patterns: &[
+            r"\b(?:echo|printf|print|cat|env|printenv)\b[^|;&]*\$\{?[A-Za-z0-9_]*(?i:token|secret|passw(?:or)?d|api_?key|access_?key|private_?key|credentials?)[A-Za-z0-9_]*\}?",
+            r"\bprintenv\s+[A-Za-z0-9_]*(?i:token|secret|passw(?:or)?d|api_?key|access_?key)",
+            r"\b(?:cat|less|more|head|tail|bat)\s+(?:-\S+\s+)*(?:~|\$HOME)/(?:\.aws/credentials|\.ssh/id_[a-z0-9]+(?:\s|$)|\.netrc|\.npmrc|\.pypirc|\.docker/config\.json|\.kube/config)",
+        ],
+    },
+];
+
+fn rule_regexes() -> &'static [Vec<Regex>] {
+    static REGEXES: OnceLock<Vec<Vec<Regex>>> = OnceLock::new();
+    REGEXES.get_or_init(|| {
+        COMMAND_RULES
+            .iter()
+            .map(|rule| {
+                rule.patterns
+                    .iter()
+                    .map(|pattern| {
+                        // `$` ends a line too, for pasted scripts
+                        let pattern = format!("(?m){}", pattern.replace("INTERPRETER", INTERPRETER));
+                        Regex::new(&pattern).unwrap_or_else(|e| panic!("invalid {} pattern: {}", rule.id, e))
+                    })
+                    .collect()
+            })
+            .collect()
+    })
+}
+
+/// Something risky in a command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandFinding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub title: &'static str,
+    pub explanation: &'static str,
+    /// The part of the command that matched
+    pub matched: String,
+}
+
+/// The risky things `command` does, by `COMMAND_RULES`, most severe first;
+/// plus a low-severity note when it has a credential written into it.
+pub fn analyze_command(command: &str) -> Vec<CommandFinding> {
+    let mut findings: Vec<CommandFinding> = COMMAND_RULES
+        .iter()
+        .zip(rule_regexes())
+        .filter_map(|(rule, regexes)| {
+            let matched = regexes.iter().find_map(|regex| regex.find(command))?;
+            Some(CommandFinding {
+                rule: rule.id,
+                severity: rule.severity.clone(),
+                title: rule.title,
+                explanation: rule.explanation,
+                matched: matched.as_str().trim().to_string(),
+            })
+        })
+        .collect();
+    if secrets::redact_secrets(command) != command {
+        findings.push(CommandFinding {
+            rule: SECRET_IN_COMMAND,
+            severity: Severity::Low,
+            title: "Has a credential written in it",
+            explanation: "The command line is kept in the shell history and the audit log; pass secrets through the environment or a file instead.",
+            matched: String::new(),
+        });
+    }
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity.rank()));
+    findings
+}
+
+/// The id of the note about credentials in the command line.
+pub const SECRET_IN_COMMAND: &str = "secret-in-command";
+
+/// Every rule id, for settings: `COMMAND_RULES` and the credential note.
+pub fn rule_ids() -> impl Iterator<Item = &'static str> {
+    COMMAND_RULES.iter().map(|rule| rule.id).chain(std::iter::once(SECRET_IN_COMMAND))
+}
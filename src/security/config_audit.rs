@@ -0,0 +1,377 @@
+use super::{ScanResult, SecurityConfig, Severity, Vulnerability};
+use anyhow::Result;
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// The kinds of files `CONFIG_RULES` check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFile {
+    /// `Dockerfile`, `Dockerfile.*`, `*.dockerfile` and `Containerfile`
+    Dockerfile,
+    /// `docker-compose*.yml` and `compose.*.yml`
+    Compose,
+    /// YAML under `.github/workflows`
+    Workflow,
+}
+
+impl ConfigFile {
+    /// What kind of file `path` is, by its name.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        let yaml = name.ends_with(".yml") || name.ends_with(".yaml");
+        if name == "dockerfile" || name.starts_with("dockerfile.") || name.ends_with(".dockerfile") || name == "containerfile" {
+            Some(ConfigFile::Dockerfile)
+        } else if yaml && (name.starts_with("docker-compose") || name.starts_with("compose.")) {
+            Some(ConfigFile::Compose)
+        } else if yaml && path.parent().is_some_and(|dir| dir.ends_with(".github/workflows")) {
+            Some(ConfigFile::Workflow)
+        } else {
+            None
+        }
+    }
+}
+
+/// How a rule looks at a file, line by line. Comment lines are skipped.
+pub enum Check {
+    /// Each line matching `pattern` but not `unless`, in files where some
+    /// line matches `requires`
+    Line {
+        pattern: &'static str,
+        unless: Option<&'static str>,
+        requires: Option<&'static str>,
+    },
+    /// The last line of the final build stage matching `pattern`, when it
+    /// also matches `bad`; or the stage's `FROM` line, when none does
+    FinalStage { pattern: &'static str, bad: &'static str },
+}
+
+/// A built-in check of a Dockerfile, compose file or workflow. Adding one
+/// is a new entry here and a case in `tests/config_audit.rs`.
+pub struct ConfigRule {
+    /// Also the finding's id
+    pub id: &'static str,
+    pub file: ConfigFile,
+    pub severity: Severity,
+    pub title: &'static str,
+    pub explanation: &'static str,
+    pub fix: &'static str,
+    pub check: Check,
+}
+
+/// First-party actions, local actions, Docker images and actions pinned to
+/// a full commit SHA.
+const PINNED_ACTION: &str = r#"uses:\s*["']?(?:actions/|github/|\./|docker://)|@[0-9a-fA-F]{40}\b"#;
+
+pub const CONFIG_RULES: &[ConfigRule] = &[
+    ConfigRule {
+        id: "dockerfile-root-user",
+        file: ConfigFile::Dockerfile,
+        severity: Severity::Medium,
+        title: "Container runs as root",
+        explanation: "Without a USER instruction, or with USER root last, the container's processes run as root, so a compromised process has root inside the container and an easier path out of it.",
+        fix: "Create an unprivileged user and switch to it at the end of the final stage: RUN useradd --system app, then USER app",
+        check: Check::FinalStage {
+            pattern: r"(?i)^\s*USER\s",
+            bad: r"(?i)^\s*USER\s+(?:root|0)(?::\S*)?\s*$",
+        },
+    },
+    ConfigRule {
+        id: "dockerfile-remote-add",
+        file: ConfigFile::Dockerfile,
+        severity: Severity::Medium,
+        title: "ADD downloads a remote URL",
+        explanation: "ADD fetches the URL at build time without checking what it got, so a changed or compromised download goes straight into the image.",
+        fix: "Download with RUN curl -fsSLO <url> and check it with sha256sum -c, or pin it with ADD --checksum=sha256:<digest>",
+        check: Check::Line {
+            pattern: r"(?i)^\s*ADD\s+(?:--\S+\s+)*https?://",
+            unless: Some(r"--checksum="),
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "dockerfile-latest-tag",
+        file: ConfigFile::Dockerfile,
+        severity: Severity::Low,
+        title: "Base image uses the latest tag",
+        explanation: "latest points at whatever was pushed last, so builds aren't reproducible and can pick up breaking or malicious changes unnoticed.",
+        fix: "Pin the base image to a version tag, or to a digest like image:1.2.3@sha256:<digest>",
+        check: Check::Line {
+            pattern: r"(?i)^\s*FROM\s+(?:--\S+\s+)*\S+:latest(?:\s|$)",
+            unless: None,
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "dockerfile-secret-env",
+        file: ConfigFile::Dockerfile,
+        severity: Severity::High,
+        title: "Secret passed in ENV or ARG",
+        explanation: "ENV values are baked into the image and ARG values are kept in its history; anyone who can pull the image can read them with docker history or docker inspect.",
+        fix: "Use a build secret (RUN --mount=type=secret,id=token) at build time, and pass secrets at run time through the environment or a secret store",
+        check: Check::Line {
+            pattern: r"(?i)^\s*(?:ENV|ARG)\s+[A-Z0-9_]*(?:PASSWORD|PASSWD|SECRET|TOKEN|API_?KEY|ACCESS_?KEY|PRIVATE_?KEY|CREDENTIALS?)[A-Z0-9_]*\b",
+            unless: None,
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "dockerfile-no-healthcheck",
+        file: ConfigFile::Dockerfile,
+        severity: Severity::Low,
+        title: "Image has no HEALTHCHECK",
+        explanation: "Without a health check, Docker and orchestrators only know whether the process is running, not whether it still works, so a hung service keeps receiving traffic.",
+        fix: "Add a HEALTHCHECK to the final stage, e.g. HEALTHCHECK CMD curl -fs http://localhost:8080/health || exit 1",
+        check: Check::FinalStage {
+            pattern: r"(?i)^\s*HEALTHCHECK\s",
+            bad: r"(?i)^\s*HEALTHCHECK\s+NONE\b",
+        },
+    },
+    ConfigRule {
+        id: "compose-privileged",
+        file: ConfigFile::Compose,
+        severity: Severity::High,
+        title: "Service runs privileged",
+        explanation: "A privileged container gets every capability and access to the host's devices; escaping to the host from it is trivial.",
+        fix: "Remove privileged: true and grant only the capabilities the service needs with cap_add",
+        check: Check::Line {
+            pattern: r#"^\s*privileged:\s*["']?true\b"#,
+            unless: None,
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "compose-host-network",
+        file: ConfigFile::Compose,
+        severity: Severity::Medium,
+        title: "Service uses the host network",
+        explanation: "With the host network the service shares the host's network stack: every port it opens is open on the host and it can reach services bound to localhost.",
+        fix: "Remove network_mode: host and publish the ports the service needs with ports:",
+        check: Check::Line {
+            pattern: r#"^\s*network_mode:\s*["']?host\b"#,
+            unless: None,
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "compose-docker-socket",
+        file: ConfigFile::Compose,
+        severity: Severity::High,
+        title: "Docker socket mounted into a container",
+        explanation: "Whoever controls the Docker socket can start a privileged container with the host's filesystem mounted, which is root on the host.",
+        fix: "Don't mount /var/run/docker.sock; if the service must talk to Docker, put a filtering proxy like docker-socket-proxy in between, read-only",
+        check: Check::Line {
+            pattern: r"/var/run/docker\.sock",
+            unless: None,
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "workflow-pr-target-checkout",
+        file: ConfigFile::Workflow,
+        severity: Severity::High,
+        title: "Checkout in a pull_request_target workflow",
+        explanation: "pull_request_target runs with the repository's secrets and a write token; checking out and building the pull request's code there lets anyone opening a pull request run code with them.",
+        fix: "Use the pull_request trigger for building contributions, or don't check out the pull request's head in this workflow",
+        check: Check::Line {
+            pattern: r#"^\s*-?\s*uses:\s*["']?actions/checkout@"#,
+            unless: None,
+            requires: Some(r"\bpull_request_target\b"),
+        },
+    },
+    ConfigRule {
+        id: "workflow-unpinned-action",
+        file: ConfigFile::Workflow,
+        severity: Severity::Medium,
+        title: "Third-party action not pinned to a commit",
+        explanation: "Tags and branches can be moved, so whoever controls the action can change the code your workflow runs, with its secrets and token.",
+        fix: "Pin the action to a full commit SHA, keeping the version in a comment: uses: owner/action@<40-character sha> # v1.2.3",
+        check: Check::Line {
+            pattern: r#"^\s*-?\s*uses:\s*["']?[\w.-]+/[\w./-]+@"#,
+            unless: Some(PINNED_ACTION),
+            requires: None,
+        },
+    },
+    ConfigRule {
+        id: "workflow-secret-echo",
+        file: ConfigFile::Workflow,
+        severity: Severity::High,
+        title: "Secret printed in a run step",
+        explanation: "Logs only mask a secret's exact value; printed, encoded or split up it appears in the workflow log for anyone who can read it.",
+        fix: "Pass the secret to the step through env: and use it without printing it",
+        check: Check::Line {
+            pattern: r"\b(?:echo|printf|cat)\b.*\$\{\{\s*secrets\.",
+            unless: None,
+            requires: None,
+        },
+    },
+];
+
+/// `Check` with its patterns compiled.
+enum CompiledCheck {
+    Line {
+        pattern: Regex,
+        unless: Option<Regex>,
+        requires: Option<Regex>,
+    },
+    FinalStage {
+        pattern: Regex,
+        bad: Regex,
+    },
+}
+
+fn compiled_checks() -> &'static [CompiledCheck] {
+    static CHECKS: OnceLock<Vec<CompiledCheck>> = OnceLock::new();
+    CHECKS.get_or_init(|| {
+        CONFIG_RULES
+            .iter()
+            .map(|rule| {
+                let compile =
+                    |pattern: &str| Regex::new(pattern).unwrap_or_else(|e| panic!("invalid {} pattern: {}", rule.id, e));
+                match &rule.check {
+                    Check::Line { pattern, unless, requires } => CompiledCheck::Line {
+                        pattern: compile(pattern),
+                        unless: unless.map(compile),
+                        requires: requires.map(compile),
+                    },
+                    Check::FinalStage { pattern, bad } => CompiledCheck::FinalStage {
+                        pattern: compile(pattern),
+                        bad: compile(bad),
+                    },
+                }
+            })
+            .collect()
+    })
+}
+
+fn from_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?i)^\s*FROM\s").unwrap())
+}
+
+/// Checks Dockerfiles, compose files and GitHub Actions workflows against
+/// `CONFIG_RULES`, without any external tool. Honours `.gitignore`,
+/// `security.excluded_paths` and `security.max_file_size_mb`.
+pub struct ConfigAuditScanner {
+    excluded_paths: Vec<String>,
+    max_file_size: u64,
+}
+
+impl ConfigAuditScanner {
+    pub fn new(config: &SecurityConfig) -> Self {
+        Self {
+            excluded_paths: config.excluded_paths.clone(),
+            max_file_size: config.max_file_size_mb * 1024 * 1024,
+        }
+    }
+
+    pub async fn scan(&self, path: &Path) -> Result<ScanResult> {
+        let scanner = Self {
+            excluded_paths: self.excluded_paths.clone(),
+            max_file_size: self.max_file_size,
+        };
+        let path = path.to_path_buf();
+        let vulnerabilities = tokio::task::spawn_blocking(move || scanner.scan_path(&path)).await?;
+        Ok(ScanResult::Success(vulnerabilities))
+    }
+
+    /// Scans a file, or every config file under a directory.
+    pub fn scan_path(&self, root: &Path) -> Vec<Vulnerability> {
+        let excluded_paths = self.excluded_paths.clone();
+        // .github is hidden, so hidden files can't be skipped
+        let walker = WalkBuilder::new(root)
+            .hidden(false)
+            .max_filesize(Some(self.max_file_size))
+            .filter_entry(move |entry| {
+                !entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| excluded_paths.iter().any(|excluded| excluded == name))
+            })
+            .build();
+
+        let mut vulnerabilities = Vec::new();
+        for entry in walker.flatten() {
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let path = entry.path();
+            let Some(kind) = ConfigFile::detect(path) else {
+                continue;
+            };
+            match std::fs::read_to_string(path) {
+                Ok(content) => vulnerabilities.extend(scan_content(path, kind, &content)),
+                Err(e) => log::debug!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        vulnerabilities
+    }
+}
+
+/// The findings of the rules for `kind` in `content`, read from `path`.
+pub fn scan_content(path: &Path, kind: ConfigFile, content: &str) -> Vec<Vulnerability> {
+    // (line number, line), without comments
+    let lines: Vec<(usize, &str)> = content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim_start().starts_with('#'))
+        .collect();
+    let final_stage = match kind {
+        ConfigFile::Dockerfile => lines.iter().rposition(|(_, line)| from_regex().is_match(line)),
+        _ => None,
+    };
+
+    let mut vulnerabilities = Vec::new();
+    for (rule, check) in CONFIG_RULES.iter().zip(compiled_checks()) {
+        if rule.file != kind {
+            continue;
+        }
+        match check {
+            CompiledCheck::Line { pattern, unless, requires } => {
+                if requires
+                    .as_ref()
+                    .is_some_and(|requires| !lines.iter().any(|(_, line)| requires.is_match(line)))
+                {
+                    continue;
+                }
+                for (number, line) in &lines {
+                    if pattern.is_match(line) && !unless.as_ref().is_some_and(|unless| unless.is_match(line)) {
+                        vulnerabilities.push(vulnerability(rule, path, *number, line));
+                    }
+                }
+            }
+            CompiledCheck::FinalStage { pattern, bad } => {
+                let stage = &lines[final_stage.unwrap_or(0)..];
+                match stage.iter().rev().find(|(_, line)| pattern.is_match(line)) {
+                    Some((number, line)) if bad.is_match(line) => {
+                        vulnerabilities.push(vulnerability(rule, path, *number, line));
+                    }
+                    Some(_) => {}
+                    None => {
+                        let (number, line) = stage.first().copied().unwrap_or((1, ""));
+                        vulnerabilities.push(vulnerability(rule, path, number, line));
+                    }
+                }
+            }
+        }
+    }
+    vulnerabilities
+}
+
+fn vulnerability(rule: &ConfigRule, path: &Path, line: usize, snippet: &str) -> Vulnerability {
+    let mut vulnerability = Vulnerability::new(
+        rule.title.to_string(),
+        rule.explanation.to_string(),
+        rule.severity.clone(),
+        "config".to_string(),
+        path.display().to_string(),
+        "config-audit".to_string(),
+    )
+    .with_location(line, None)
+    .with_code_snippet(snippet.trim().to_string())
+    .with_fix(rule.fix.to_string());
+    vulnerability.id = rule.id.to_string();
+    vulnerability
+}
@@ -0,0 +1,285 @@
+use super::{ScanResult, SecurityConfig, Severity, Vulnerability};
+use anyhow::{anyhow, bail, Context, Result};
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Files with a NUL byte in this many leading bytes are treated as binary
+/// and skipped.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Languages a rule can be limited to, and the file extensions (or, for
+/// names without a dot, file names) they cover.
+pub const LANGUAGES: &[(&str, &[&str])] = &[
+    ("python", &["py", "pyw"]),
+    ("javascript", &["js", "mjs", "cjs", "jsx"]),
+    ("typescript", &["ts", "mts", "cts", "tsx"]),
+    ("rust", &["rs"]),
+    ("go", &["go"]),
+    ("java", &["java"]),
+    ("kotlin", &["kt", "kts"]),
+    ("ruby", &["rb"]),
+    ("php", &["php"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hh", "hxx"]),
+    ("csharp", &["cs"]),
+    ("swift", &["swift"]),
+    ("shell", &["sh", "bash", "zsh"]),
+    ("yaml", &["yml", "yaml"]),
+    ("terraform", &["tf"]),
+    ("dockerfile", &["Dockerfile"]),
+];
+
+/// The default rules file, `security_rules.toml` next to the config.
+pub fn default_rules_path() -> PathBuf {
+    crate::config::config_dir().join("security_rules.toml")
+}
+
+/// A grep-style check, as written in a rules file:
+///
+/// ```toml
+/// [[rules]]
+/// id = "no-eval"
+/// pattern = '\beval\('
+/// severity = "high"
+/// message = "eval() runs arbitrary code"
+/// category = "injection"
+/// languages = ["python", "javascript"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomRule {
+    pub id: String,
+    /// A regex, matched against whole files so it may span lines
+    pub pattern: String,
+    /// `critical`, `high`, `medium`, `low` or `info`
+    pub severity: String,
+    pub message: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// Names from `LANGUAGES`; every file when empty
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub fix: Option<String>,
+}
+
+fn default_category() -> String {
+    "custom".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<CustomRule>,
+}
+
+/// A rule ready to run.
+#[derive(Debug)]
+struct CompiledRule {
+    rule: CustomRule,
+    regex: Regex,
+    severity: Severity,
+    /// Extensions and file names from the rule's languages
+    files: Vec<&'static str>,
+}
+
+impl CompiledRule {
+    fn new(rule: CustomRule) -> Result<Self> {
+        let regex = Regex::new(&rule.pattern).with_context(|| format!("Rule \"{}\": invalid pattern", rule.id))?;
+        let severity = parse_severity(&rule.severity).ok_or_else(|| {
+            anyhow!(
+                "Rule \"{}\": unknown severity \"{}\"; use critical, high, medium, low or info",
+                rule.id,
+                rule.severity
+            )
+        })?;
+        let mut files = Vec::new();
+        for language in &rule.languages {
+            let (_, extensions) = LANGUAGES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(language))
+                .ok_or_else(|| {
+                    let known: Vec<_> = LANGUAGES.iter().map(|(name, _)| *name).collect();
+                    anyhow!("Rule \"{}\": unknown language \"{}\"; known languages are {}", rule.id, language, known.join(", "))
+                })?;
+            files.extend_from_slice(extensions);
+        }
+        Ok(Self {
+            rule,
+            regex,
+            severity,
+            files,
+        })
+    }
+
+    fn applies_to(&self, path: &Path) -> bool {
+        if self.files.is_empty() {
+            return true;
+        }
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        self.files.iter().any(|file| *file == extension || *file == name)
+    }
+}
+
+fn parse_severity(severity: &str) -> Option<Severity> {
+    match severity.to_lowercase().as_str() {
+        "critical" => Some(Severity::Critical),
+        "high" => Some(Severity::High),
+        "medium" => Some(Severity::Medium),
+        "low" => Some(Severity::Low),
+        "info" => Some(Severity::Info),
+        _ => None,
+    }
+}
+
+/// Runs user-defined regex rules over every file under a path, reporting a
+/// `Vulnerability` per rule and matching line. Honours `.gitignore`,
+/// `security.excluded_paths` and `security.max_file_size_mb`.
+pub struct CustomRuleScanner {
+    rules: Arc<Vec<CompiledRule>>,
+    excluded_paths: Vec<String>,
+    max_file_size: u64,
+}
+
+impl CustomRuleScanner {
+    /// Compiles `rules`, failing on the first invalid one.
+    pub fn new(rules: Vec<CustomRule>, config: &SecurityConfig) -> Result<Self> {
+        let mut ids = std::collections::HashSet::new();
+        let mut compiled = Vec::new();
+        for rule in rules {
+            if !ids.insert(rule.id.clone()) {
+                bail!("Rule \"{}\" is defined twice", rule.id);
+            }
+            compiled.push(CompiledRule::new(rule)?);
+        }
+        Ok(Self {
+            rules: Arc::new(compiled),
+            excluded_paths: config.excluded_paths.clone(),
+            max_file_size: config.max_file_size_mb * 1024 * 1024,
+        })
+    }
+
+    /// Parses a rules file's contents.
+    pub fn parse(content: &str, config: &SecurityConfig) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content).context("Invalid rules file")?;
+        Self::new(file.rules, config)
+    }
+
+    pub fn load(path: &Path, config: &SecurityConfig) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content, config).with_context(|| format!("In {}", path.display()))
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub async fn scan(&self, path: &Path) -> Result<ScanResult> {
+        let scanner = Self {
+            rules: self.rules.clone(),
+            excluded_paths: self.excluded_paths.clone(),
+            max_file_size: self.max_file_size,
+        };
+        let path = path.to_path_buf();
+        let vulnerabilities = tokio::task::spawn_blocking(move || scanner.scan_path(&path)).await??;
+        Ok(ScanResult::Success(vulnerabilities))
+    }
+
+    /// Scans a file, or every file under a directory.
+    pub fn scan_path(&self, root: &Path) -> Result<Vec<Vulnerability>> {
+        if self.rules.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let excluded_paths = self.excluded_paths.clone();
+        let walker = WalkBuilder::new(root)
+            .max_filesize(Some(self.max_file_size))
+            .filter_entry(move |entry| {
+                !entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| excluded_paths.iter().any(|excluded| excluded == name))
+            })
+            .build();
+
+        let mut vulnerabilities = Vec::new();
+        for entry in walker {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let path = entry.path();
+            if !self.rules.iter().any(|rule| rule.applies_to(path)) {
+                continue;
+            }
+            match read_text(path) {
+                Ok(Some(content)) => vulnerabilities.extend(self.scan_content(path, &content)),
+                Ok(None) => {}
+                Err(e) => log::debug!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        Ok(vulnerabilities)
+    }
+
+    /// Matches the rules that apply to `path` against its `content`: one
+    /// finding per rule and line, at the line and column the match starts.
+    pub fn scan_content(&self, path: &Path, content: &str) -> Vec<Vulnerability> {
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        let mut vulnerabilities = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule.applies_to(path)) {
+            let mut last_line = None;
+            for found in rule.regex.find_iter(content) {
+                let line = line_starts.partition_point(|start| *start <= found.start());
+                if last_line == Some(line) {
+                    continue;
+                }
+                last_line = Some(line);
+
+                let line_start = line_starts[line - 1];
+                let line_end = line_starts.get(line).map(|start| start - 1).unwrap_or(content.len());
+                let column = content[line_start..found.start()].chars().count() + 1;
+                vulnerabilities.push(rule.vulnerability(path, line, column, content[line_start..line_end].trim()));
+            }
+        }
+        vulnerabilities
+    }
+}
+
+impl CompiledRule {
+    fn vulnerability(&self, path: &Path, line: usize, column: usize, snippet: &str) -> Vulnerability {
+        let mut vulnerability = Vulnerability::new(
+            self.rule.message.clone(),
+            format!("Matched custom rule \"{}\"", self.rule.id),
+            self.severity.clone(),
+            self.rule.category.clone(),
+            path.display().to_string(),
+            "custom-rules".to_string(),
+        )
+        .with_location(line, Some(column))
+        .with_code_snippet(snippet.to_string());
+        vulnerability.id = self.rule.id.clone();
+        if let Some(fix) = &self.rule.fix {
+            vulnerability = vulnerability.with_fix(fix.clone());
+        }
+        vulnerability
+    }
+}
+
+/// A file's text, or `None` when it's binary.
+fn read_text(path: &Path) -> Result<Option<String>> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?.read_to_end(&mut bytes)?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
@@ -0,0 +1,371 @@
+use super::{SecurityReport, Vulnerability};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The schema, one migration per version; `PRAGMA user_version` is the
+/// number applied. Only ever append.
+const MIGRATIONS: &[&str] = &[
+    // 1: findings by fingerprint
+    "CREATE TABLE findings (
+        fingerprint TEXT PRIMARY KEY,
+        target TEXT NOT NULL,
+        scanner TEXT NOT NULL,
+        rule_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        severity TEXT NOT NULL,
+        file_path TEXT NOT NULL,
+        line INTEGER,
+        status TEXT NOT NULL,
+        suppressed_by_comment INTEGER NOT NULL DEFAULT 0,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        fixed_at TEXT,
+        assignee TEXT,
+        note TEXT
+    );
+    CREATE INDEX findings_target ON findings (target, status);",
+    // 2: the scans, for the risk trend
+    "CREATE TABLE scans (
+        scan_id TEXT PRIMARY KEY,
+        target TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        scan_type TEXT NOT NULL,
+        risk_score INTEGER NOT NULL,
+        findings INTEGER NOT NULL
+    );
+    CREATE INDEX scans_target ON scans (target, timestamp);",
+];
+
+/// The schema version this build writes.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// The default store, `findings.db` in the data directory.
+pub fn default_db_path() -> PathBuf {
+    crate::config::data_dir().join("findings.db")
+}
+
+/// Where a tracked finding stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FindingStatus {
+    Open,
+    /// Seen and accepted for now, still reported
+    Acknowledged,
+    /// Gone from a complete scan, or marked fixed
+    Fixed,
+    /// Not a problem, by an ignore comment or by hand
+    Suppressed,
+}
+
+impl FindingStatus {
+    pub const ALL: [FindingStatus; 4] = [
+        FindingStatus::Open,
+        FindingStatus::Acknowledged,
+        FindingStatus::Fixed,
+        FindingStatus::Suppressed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FindingStatus::Open => "open",
+            FindingStatus::Acknowledged => "acknowledged",
+            FindingStatus::Fixed => "fixed",
+            FindingStatus::Suppressed => "suppressed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|known| known.as_str() == status)
+    }
+}
+
+/// A finding as the store remembers it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedFinding {
+    pub fingerprint: String,
+    /// The scanned path or image, as `SecurityReport::target_name`
+    pub target: String,
+    pub scanner: String,
+    pub rule_id: String,
+    pub title: String,
+    pub severity: String,
+    pub file_path: String,
+    pub line: Option<usize>,
+    pub status: FindingStatus,
+    /// Suppressed by an ignore comment rather than by hand, so it reopens
+    /// when the comment goes
+    pub suppressed_by_comment: bool,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub fixed_at: Option<DateTime<Utc>>,
+    pub assignee: Option<String>,
+    pub note: Option<String>,
+}
+
+/// What recording a scan changed, by fingerprint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanChanges {
+    /// Never seen before
+    pub new: Vec<String>,
+    /// Marked fixed before, found again
+    pub reopened: Vec<String>,
+    /// Missing from the scan, so now fixed
+    pub fixed: Vec<String>,
+}
+
+/// Identifies a finding across scans: its target, scanner, rule, file
+/// relative to the scanned path, and code (or line when there's no
+/// snippet). Random ids, which some scanners make up, are left out.
+pub fn fingerprint(report: &SecurityReport, vulnerability: &Vulnerability) -> String {
+    let rule = match uuid::Uuid::parse_str(&vulnerability.id) {
+        Ok(_) => "",
+        Err(_) => vulnerability.id.as_str(),
+    };
+    let file = Path::new(&vulnerability.file_path);
+    let file = file.strip_prefix(&report.path).unwrap_or(file).to_string_lossy();
+    let snippet = vulnerability.code_snippet.as_deref().unwrap_or_default().split_whitespace().collect::<Vec<_>>();
+    let location = match snippet.is_empty() {
+        true => vulnerability.line_number.map(|line| line.to_string()).unwrap_or_default(),
+        false => snippet.join(" "),
+    };
+    let key = [report.target_name().as_str(), &vulnerability.scanner, rule, &vulnerability.title, &file, &location].join("\0");
+    Sha256::digest(key.as_bytes())[..16].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn timestamp(value: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+impl TrackedFinding {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let status: String = row.get("status")?;
+        Ok(Self {
+            fingerprint: row.get("fingerprint")?,
+            target: row.get("target")?,
+            scanner: row.get("scanner")?,
+            rule_id: row.get("rule_id")?,
+            title: row.get("title")?,
+            severity: row.get("severity")?,
+            file_path: row.get("file_path")?,
+            line: row.get::<_, Option<i64>>("line")?.map(|line| line as usize),
+            status: FindingStatus::parse(&status).unwrap_or(FindingStatus::Open),
+            suppressed_by_comment: row.get("suppressed_by_comment")?,
+            first_seen: timestamp(&row.get::<_, String>("first_seen")?)?,
+            last_seen: timestamp(&row.get::<_, String>("last_seen")?)?,
+            fixed_at: row.get::<_, Option<String>>("fixed_at")?.as_deref().map(timestamp).transpose()?,
+            assignee: row.get("assignee")?,
+            note: row.get("note")?,
+        })
+    }
+}
+
+/// Findings remembered across scans and sessions, in SQLite.
+pub struct FindingsStore {
+    conn: Connection,
+}
+
+impl FindingsStore {
+    /// Opens the store at `path`, creating it or bringing its schema up to
+    /// date.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        Self::with_connection(conn).with_context(|| format!("In {}", path.display()))
+    }
+
+    /// A store that lives as long as it does.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    pub fn schema_version(&self) -> Result<u32> {
+        schema_version(&self.conn)
+    }
+
+    /// Adds `report`'s findings and updates the known ones: found again,
+    /// fixed ones reopen; silenced by an ignore comment, they're
+    /// suppressed. After a complete full scan (no scanner failed), the
+    /// target's findings it didn't find are marked fixed.
+    pub fn record_scan(&mut self, report: &SecurityReport) -> Result<ScanChanges> {
+        let target = report.target_name();
+        let now = report.timestamp.to_rfc3339();
+        let tx = self.conn.transaction()?;
+        let mut changes = ScanChanges::default();
+        let mut seen = HashSet::new();
+
+        let found = report.vulnerabilities.iter().map(|v| (v, false));
+        for (vulnerability, by_comment) in found.chain(report.suppressed.iter().map(|v| (v, true))) {
+            let fingerprint = fingerprint(report, vulnerability);
+            if !seen.insert(fingerprint.clone()) {
+                continue;
+            }
+            let known: Option<(String, bool)> = tx
+                .query_row(
+                    "SELECT status, suppressed_by_comment FROM findings WHERE fingerprint = ?1",
+                    [&fingerprint],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let (status, suppressed_by_comment) = match known {
+                None => {
+                    changes.new.push(fingerprint.clone());
+                    match by_comment {
+                        true => (FindingStatus::Suppressed, true),
+                        false => (FindingStatus::Open, false),
+                    }
+                }
+                Some((status, was_by_comment)) => match (FindingStatus::parse(&status), by_comment) {
+                    (_, true) => (FindingStatus::Suppressed, true),
+                    (Some(FindingStatus::Fixed), false) => {
+                        changes.reopened.push(fingerprint.clone());
+                        (FindingStatus::Open, false)
+                    }
+                    // The ignore comment is gone
+                    (Some(FindingStatus::Suppressed), false) if was_by_comment => (FindingStatus::Open, false),
+                    (status, false) => (status.unwrap_or(FindingStatus::Open), false),
+                },
+            };
+            tx.execute(
+                "INSERT INTO findings (fingerprint, target, scanner, rule_id, title, severity, file_path, line,
+                     status, suppressed_by_comment, first_seen, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)
+                 ON CONFLICT (fingerprint) DO UPDATE SET
+                     severity = excluded.severity, file_path = excluded.file_path, line = excluded.line,
+                     status = excluded.status, suppressed_by_comment = excluded.suppressed_by_comment,
+                     last_seen = excluded.last_seen, fixed_at = NULL",
+                params![
+                    fingerprint,
+                    target,
+                    vulnerability.scanner,
+                    vulnerability.id,
+                    vulnerability.title,
+                    format!("{:?}", vulnerability.severity),
+                    vulnerability.file_path,
+                    vulnerability.line_number.map(|line| line as i64),
+                    status.as_str(),
+                    suppressed_by_comment,
+                    now,
+                ],
+            )?;
+        }
+
+        if report.scan_type == "Full" && report.scanner_errors.is_empty() {
+            let known: Vec<String> = tx
+                .prepare("SELECT fingerprint FROM findings WHERE target = ?1 AND status != 'fixed'")?
+                .query_map([&target], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            for fingerprint in known.into_iter().filter(|fingerprint| !seen.contains(fingerprint)) {
+                tx.execute(
+                    "UPDATE findings SET status = 'fixed', suppressed_by_comment = 0, fixed_at = ?2 WHERE fingerprint = ?1",
+                    params![fingerprint, now],
+                )?;
+                changes.fixed.push(fingerprint);
+            }
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO scans (scan_id, target, timestamp, scan_type, risk_score, findings)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                report.scan_id,
+                target,
+                now,
+                report.scan_type,
+                report.summary.risk_score(),
+                report.summary.total_vulnerabilities as i64
+            ],
+        )?;
+        tx.commit()?;
+        Ok(changes)
+    }
+
+    /// The risk score of the last scan of `target` recorded.
+    pub fn last_risk_score(&self, target: &str) -> Result<Option<u32>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT risk_score FROM scans WHERE target = ?1 ORDER BY timestamp DESC LIMIT 1",
+                [target],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Sets a finding's status by hand. Marking it fixed records when;
+    /// anything else clears that.
+    pub fn set_status(&self, fingerprint: &str, status: FindingStatus) -> Result<()> {
+        let fixed_at = (status == FindingStatus::Fixed).then(|| Utc::now().to_rfc3339());
+        let updated = self.conn.execute(
+            "UPDATE findings SET status = ?2, suppressed_by_comment = 0, fixed_at = ?3 WHERE fingerprint = ?1",
+            params![fingerprint, status.as_str(), fixed_at],
+        )?;
+        match updated {
+            0 => Err(anyhow!("No finding {}", fingerprint)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets who's looking at a finding and a note about it; empty values
+    /// clear them.
+    pub fn set_assignment(&self, fingerprint: &str, assignee: &str, note: &str) -> Result<()> {
+        let value = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+        let updated = self.conn.execute(
+            "UPDATE findings SET assignee = ?2, note = ?3 WHERE fingerprint = ?1",
+            params![fingerprint, value(assignee), value(note)],
+        )?;
+        match updated {
+            0 => Err(anyhow!("No finding {}", fingerprint)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get(&self, fingerprint: &str) -> Result<Option<TrackedFinding>> {
+        Ok(self
+            .conn
+            .query_row("SELECT * FROM findings WHERE fingerprint = ?1", [fingerprint], TrackedFinding::from_row)
+            .optional()?)
+    }
+
+    /// The findings of `target`, oldest first.
+    pub fn findings(&self, target: &str) -> Result<Vec<TrackedFinding>> {
+        let mut statement = self.conn.prepare("SELECT * FROM findings WHERE target = ?1 ORDER BY first_seen, rowid")?;
+        let findings = statement.query_map([target], TrackedFinding::from_row)?.collect::<rusqlite::Result<_>>()?;
+        Ok(findings)
+    }
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Applies the migrations `conn` hasn't had, each in a transaction.
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    let version = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        bail!(
+            "The findings database has schema version {}, newer than this version of antraft knows ({})",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)
+            .with_context(|| format!("Failed to migrate the findings database to version {}", index + 1))?;
+        tx.pragma_update(None, "user_version", index as u32 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
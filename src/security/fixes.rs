@@ -0,0 +1,198 @@
+use super::{Remediation, Vulnerability};
+use crate::terminal::diff::{self, OutputDiff};
+use anyhow::{bail, Context, Result};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Something to do about a finding, shown for confirmation first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixAction {
+    /// A package manager command, run in `directory`
+    Command { command: String, directory: PathBuf },
+    Patch(FilePatch),
+}
+
+/// A file's content before and after a fix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+    pub path: PathBuf,
+    pub original: String,
+    pub fixed: String,
+}
+
+impl FilePatch {
+    pub fn diff(&self) -> Result<OutputDiff> {
+        diff::diff_outputs(&self.original, &self.fixed, false)
+    }
+
+    /// Writes the fixed content, unless the file changed since the patch
+    /// was made.
+    pub fn apply(&self) -> Result<()> {
+        let current = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        if current != self.original {
+            bail!("{} changed since the fix was prepared; scan again", self.path.display());
+        }
+        std::fs::write(&self.path, &self.fixed).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// What fixes `vulnerability`, found by a scan of `scan_root`: an upgrade
+/// command for a dependency with a known package manager, or the patch
+/// of a semgrep autofix. `None` when there's nothing concrete to offer.
+pub fn fix_action(vulnerability: &Vulnerability, scan_root: &Path) -> Result<Option<FixAction>> {
+    let Some(remediation) = &vulnerability.remediation else {
+        return Ok(None);
+    };
+    match remediation {
+        Remediation::Upgrade {
+            ecosystem,
+            package,
+            version,
+            fixed_version,
+            source,
+        } => {
+            let source = source.as_ref().map(|source| scan_root.join(source));
+            let command = upgrade_command(
+                ecosystem,
+                package,
+                version.as_deref(),
+                fixed_version.as_deref(),
+                source.as_deref(),
+            );
+            let directory = source
+                .as_deref()
+                .and_then(Path::parent)
+                .map_or_else(|| scan_root.to_path_buf(), Path::to_path_buf);
+            Ok(command.map(|command| FixAction::Command { command, directory }))
+        }
+        Remediation::Replace { start, end, replacement } => {
+            let path = scan_root.join(&vulnerability.file_path);
+            let original =
+                std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let fixed = apply_replacement(&original, *start, *end, replacement)?;
+            Ok(Some(FixAction::Patch(FilePatch { path, original, fixed })))
+        }
+    }
+}
+
+/// `content` with bytes `start..end` replaced.
+pub fn apply_replacement(content: &str, start: usize, end: usize, replacement: &str) -> Result<String> {
+    if start > end || end > content.len() || !content.is_char_boundary(start) || !content.is_char_boundary(end) {
+        bail!("The fix is for a different version of the file; scan again");
+    }
+    Ok(format!("{}{}{}", &content[..start], replacement, &content[end..]))
+}
+
+/// The command upgrading `package` to `fixed_version`, or as far as its
+/// constraints allow without one, with the package manager its `source`
+/// lockfile belongs to. `None` for ecosystems without one we know, and for
+/// names or versions with characters a package manager wouldn't use, since
+/// they come from scanner output and end up in a shell.
+pub fn upgrade_command(
+    ecosystem: &str,
+    package: &str,
+    version: Option<&str>,
+    fixed_version: Option<&str>,
+    source: Option<&Path>,
+) -> Option<String> {
+    let safe = |word: &str| {
+        word.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '@')
+            && word.chars().all(|c| c.is_ascii_alphanumeric() || "@/._-+:~".contains(c))
+    };
+    if !safe(package) || !version.is_none_or(safe) || !fixed_version.is_none_or(safe) {
+        return None;
+    }
+    let lockfile = source
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    let command = match ecosystem {
+        "npm" => {
+            let target = fixed_version.map_or_else(|| package.to_string(), |fixed| format!("{}@{}", package, fixed));
+            match lockfile {
+                "yarn.lock" => format!("yarn upgrade {}", target),
+                "pnpm-lock.yaml" => format!("pnpm update {}", target),
+                _ if fixed_version.is_some() => format!("npm install {}", target),
+                _ => format!("npm update {}", package),
+            }
+        }
+        "crates.io" => {
+            // `-p name@version` picks one when several versions are locked
+            let spec = version.map_or_else(|| package.to_string(), |version| format!("{}@{}", package, version));
+            match fixed_version {
+                Some(fixed) => format!("cargo update -p {} --precise {}", spec, fixed),
+                None => format!("cargo update -p {}", spec),
+            }
+        }
+        "PyPI" => match lockfile {
+            "poetry.lock" => format!("poetry update {}", package),
+            "Pipfile.lock" => format!("pipenv update {}", package),
+            "uv.lock" => format!("uv lock --upgrade-package {}", package),
+            _ => match fixed_version {
+                Some(fixed) => format!("pip install --upgrade '{}>={}'", package, fixed),
+                None => format!("pip install --upgrade {}", package),
+            },
+        },
+        // The standard library comes with the toolchain
+        "Go" if package == "stdlib" || package == "toolchain" => return None,
+        "Go" => match fixed_version {
+            Some(fixed) => format!("go get {}@v{}", package, fixed.trim_start_matches('v')),
+            None => format!("go get -u {}", package),
+        },
+        "RubyGems" => format!("bundle update --conservative {}", package),
+        "Packagist" => format!("composer update {} --with-dependencies", package),
+        _ => return None,
+    };
+    Some(command)
+}
+
+/// Compares versions like `4.17.21` and `1.0.0-rc.1` a component at a
+/// time, numerically where both are numbers. A pre-release sorts before
+/// its release.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| {
+        // Build metadata doesn't count
+        let version = version.trim_start_matches('v').split('+').next().unwrap_or_default();
+        let (release, pre) = match version.split_once('-') {
+            Some((release, pre)) => (release.to_string(), Some(pre.to_string())),
+            None => (version.to_string(), None),
+        };
+        (release, pre)
+    };
+    let (a_release, a_pre) = split(a);
+    let (b_release, b_pre) = split(b);
+    // `1.2` is `1.2.0`
+    compare_dotted(&a_release, &b_release, Some("0")).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => compare_dotted(&a, &b, None),
+    })
+}
+
+/// Compares dot-separated components in turn, a missing one as `missing`
+/// or, without one, as less than any.
+fn compare_dotted(a: &str, b: &str, missing: Option<&str>) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(a), Some(b)) => compare_component(a, b),
+            (Some(a), None) => missing.map_or(Ordering::Greater, |missing| compare_component(a, missing)),
+            (None, Some(b)) => missing.map_or(Ordering::Less, |missing| compare_component(missing, b)),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+fn compare_component(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
@@ -0,0 +1,212 @@
+use super::Vulnerability;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Why a finding was left out of a report's findings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suppression {
+    /// The ignore comment on the finding's line, like `nosec B602`
+    pub comment: String,
+    /// What the comment says after the rule ids, if anything
+    pub reason: Option<String>,
+}
+
+/// An ignore comment, as written on a line:
+///
+/// - `# nosec`, `# nosec B602, B607`: bandit's, for its findings
+/// - `// nosemgrep`, `// nosemgrep: rule-id`: semgrep's, for its findings
+/// - `# antraft-ignore: <rule-id>[, <rule-id>…] <reason>`: any scanner's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreComment {
+    /// The scanner whose findings it silences; any for `antraft-ignore`
+    pub scanner: Option<&'static str>,
+    /// The rule ids it names; every rule of `scanner` when empty
+    pub rules: Vec<String>,
+    pub reason: Option<String>,
+    /// The comment from its keyword on
+    pub text: String,
+}
+
+fn comment_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?:#|//|/\*|<!--)\s*((nosec|nosemgrep|nosem|antraft-ignore)\b.*)").unwrap())
+}
+
+fn bandit_rule() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^[Bb]\d+$").unwrap())
+}
+
+/// The comma-separated ids at the start of `text`, and what follows them.
+fn rule_list(text: &str) -> (Vec<&str>, &str) {
+    let mut rules = Vec::new();
+    let mut rest = text.trim_start();
+    loop {
+        let end = rest.find(|c: char| c.is_whitespace() || c == ',').unwrap_or(rest.len());
+        if end == 0 {
+            break;
+        }
+        rules.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after.trim_start(),
+            None => break,
+        }
+    }
+    (rules, rest)
+}
+
+fn reason(text: &str) -> Option<String> {
+    let reason = text.trim().trim_start_matches(['-', ':']).trim();
+    (!reason.is_empty()).then(|| reason.to_string())
+}
+
+impl IgnoreComment {
+    /// The ignore comment in `line`, if it has one.
+    pub fn parse(line: &str) -> Option<Self> {
+        let captures = comment_pattern().captures(line)?;
+        let text = captures[1].trim_end().trim_end_matches("-->").trim_end_matches("*/").trim_end();
+        let keyword = &captures[2];
+        let after = text[keyword.len()..].trim_start();
+        let (listed, after) = match after.strip_prefix(':') {
+            Some(after) => (true, after),
+            None => (false, after),
+        };
+        let (scanner, rules, rest) = match keyword {
+            "nosec" => {
+                // Bandit reads words that aren't test ids as a blanket nosec
+                let (rules, rest) = rule_list(after);
+                match rules.iter().all(|rule| bandit_rule().is_match(rule)) {
+                    true => (Some("bandit"), rules, rest),
+                    false => (Some("bandit"), Vec::new(), after),
+                }
+            }
+            // Semgrep only takes rule ids after a colon
+            "nosemgrep" | "nosem" if !listed => (Some("semgrep"), Vec::new(), after),
+            "nosemgrep" | "nosem" => {
+                let (rules, rest) = rule_list(after);
+                (Some("semgrep"), rules, rest)
+            }
+            _ => {
+                let (rules, rest) = rule_list(after);
+                (None, rules, rest)
+            }
+        };
+        Some(Self {
+            scanner,
+            rules: rules.into_iter().map(str::to_string).collect(),
+            reason: reason(rest),
+            text: text.to_string(),
+        })
+    }
+
+    /// Whether the comment is meant for `vulnerability`'s scanner.
+    pub fn applies_to(&self, vulnerability: &Vulnerability) -> bool {
+        self.scanner.is_none_or(|scanner| scanner == vulnerability.scanner)
+    }
+
+    /// Whether the comment silences `vulnerability`. Rule ids match
+    /// ignoring case, and semgrep's also by their last part, so
+    /// `nosemgrep: eval-detected` covers `python.lang….eval-detected`.
+    pub fn matches(&self, vulnerability: &Vulnerability) -> bool {
+        if !self.applies_to(vulnerability) {
+            return false;
+        }
+        if self.rules.is_empty() {
+            return self.scanner.is_some();
+        }
+        let id = &vulnerability.id;
+        self.rules.iter().any(|rule| {
+            rule.eq_ignore_ascii_case(id) || id.rsplit('.').next().is_some_and(|last| last.eq_ignore_ascii_case(rule))
+        })
+    }
+}
+
+/// A scan's findings after applying the ignore comments on their lines.
+#[derive(Debug, Default)]
+pub struct Applied {
+    pub kept: Vec<Vulnerability>,
+    /// With their `suppression` set
+    pub suppressed: Vec<Vulnerability>,
+    /// Ignore comments on a finding's line that don't name its rule
+    pub warnings: Vec<String>,
+}
+
+/// Reads the lines of the findings under `root` and moves those with an
+/// ignore comment for their rule to `suppressed`. Files are only read up
+/// to the last line with a finding.
+pub fn apply(vulnerabilities: Vec<Vulnerability>, root: &Path) -> Applied {
+    let file = |vulnerability: &Vulnerability| match Path::new(&vulnerability.file_path) {
+        path if path.is_absolute() => path.to_path_buf(),
+        path => root.join(path),
+    };
+    let mut wanted: HashMap<PathBuf, BTreeSet<usize>> = HashMap::new();
+    for vulnerability in &vulnerabilities {
+        if let Some(line) = vulnerability.line_number.filter(|line| *line > 0 && !vulnerability.file_path.is_empty()) {
+            wanted.entry(file(vulnerability)).or_default().insert(line);
+        }
+    }
+    let lines: HashMap<PathBuf, HashMap<usize, String>> =
+        wanted.into_iter().map(|(path, numbers)| (path.clone(), read_lines(&path, &numbers))).collect();
+
+    let mut applied = Applied::default();
+    for mut vulnerability in vulnerabilities {
+        let comment = vulnerability
+            .line_number
+            .and_then(|line| lines.get(&file(&vulnerability))?.get(&line))
+            .and_then(|line| IgnoreComment::parse(line));
+        match comment {
+            Some(comment) if comment.matches(&vulnerability) => {
+                vulnerability.suppression = Some(Suppression {
+                    comment: comment.text,
+                    reason: comment.reason,
+                });
+                applied.suppressed.push(vulnerability);
+            }
+            Some(comment) if comment.applies_to(&vulnerability) => {
+                let warning = format!(
+                    "{}:{}: `{}` doesn't name {}, so it's still reported",
+                    vulnerability.file_path,
+                    vulnerability.line_number.unwrap_or_default(),
+                    comment.text,
+                    if vulnerability.id.is_empty() { "its rule" } else { &vulnerability.id }
+                );
+                if !applied.warnings.contains(&warning) {
+                    applied.warnings.push(warning);
+                }
+                applied.kept.push(vulnerability);
+            }
+            _ => applied.kept.push(vulnerability),
+        }
+    }
+    applied
+}
+
+/// Lines `numbers` (1-based) of `path`, without their line endings.
+fn read_lines(path: &Path, numbers: &BTreeSet<usize>) -> HashMap<usize, String> {
+    let mut lines = HashMap::new();
+    let Some(&last) = numbers.last() else {
+        return lines;
+    };
+    let Ok(file) = std::fs::File::open(path) else {
+        return lines;
+    };
+    let mut reader = BufReader::new(file);
+    let mut buffer = Vec::new();
+    for number in 1..=last {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if numbers.contains(&number) {
+            let line = String::from_utf8_lossy(&buffer);
+            lines.insert(number, line.trim_end_matches(['\n', '\r']).to_string());
+        }
+    }
+    lines
+}
@@ -0,0 +1,695 @@
+use super::{ScanResult, SecurityConfig, Severity, Vulnerability};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Lockfiles the inventory reads, by file name.
+pub const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "poetry.lock",
+    "requirements.txt",
+];
+
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Package registries, by OSV's names for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Ecosystem {
+    #[serde(rename = "crates.io")]
+    CratesIo,
+    #[serde(rename = "npm")]
+    Npm,
+    #[serde(rename = "PyPI")]
+    PyPI,
+}
+
+impl Ecosystem {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ecosystem::CratesIo => "crates.io",
+            Ecosystem::Npm => "npm",
+            Ecosystem::PyPI => "PyPI",
+        }
+    }
+}
+
+/// A package at the version a lockfile pins it to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    /// As the lockfile records it; only npm's do
+    pub license: Option<String>,
+}
+
+impl LockedPackage {
+    fn new(ecosystem: Ecosystem, name: &str, version: &str, license: Option<String>) -> Self {
+        Self {
+            ecosystem,
+            name: name.to_string(),
+            version: version.to_string(),
+            license,
+        }
+    }
+}
+
+/// The packages of a lockfile named in `LOCKFILES`.
+pub fn parse_lockfile(path: &Path) -> Result<Vec<LockedPackage>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Couldn't read {}", path.display()))?;
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let packages = match name {
+        "Cargo.lock" => parse_cargo_lock(&text),
+        "package-lock.json" | "npm-shrinkwrap.json" => parse_package_lock(&text),
+        "poetry.lock" => parse_poetry_lock(&text),
+        "requirements.txt" => Ok(parse_requirements(&text)),
+        _ => Err(anyhow!("Not a lockfile the inventory reads")),
+    };
+    packages.with_context(|| format!("Couldn't parse {}", path.display()))
+}
+
+/// The crates of a `Cargo.lock`, without the workspace's own and other
+/// path dependencies, which have no `source`.
+pub fn parse_cargo_lock(text: &str) -> Result<Vec<LockedPackage>> {
+    let lock: toml::Value = toml::from_str(text)?;
+    Ok(toml_packages(&lock)
+        .filter(|package| package.get("source").is_some())
+        .filter_map(|package| {
+            let name = package.get("name")?.as_str()?;
+            let version = package.get("version")?.as_str()?;
+            Some(LockedPackage::new(Ecosystem::CratesIo, name, version, None))
+        })
+        .collect())
+}
+
+/// The packages of a `poetry.lock`.
+pub fn parse_poetry_lock(text: &str) -> Result<Vec<LockedPackage>> {
+    let lock: toml::Value = toml::from_str(text)?;
+    Ok(toml_packages(&lock)
+        .filter_map(|package| {
+            let name = package.get("name")?.as_str()?;
+            let version = package.get("version")?.as_str()?;
+            Some(LockedPackage::new(Ecosystem::PyPI, name, version, None))
+        })
+        .collect())
+}
+
+fn toml_packages(lock: &toml::Value) -> impl Iterator<Item = &toml::Value> {
+    lock.get("package").and_then(|packages| packages.as_array()).into_iter().flatten()
+}
+
+/// The packages of a `package-lock.json` or `npm-shrinkwrap.json`: the
+/// `packages` of lockfile versions 2 and 3, with the licenses npm records
+/// there, or the nested `dependencies` of version 1.
+pub fn parse_package_lock(text: &str) -> Result<Vec<LockedPackage>> {
+    let lock: serde_json::Value = serde_json::from_str(text)?;
+    let mut packages = Vec::new();
+    if let Some(entries) = lock.get("packages").and_then(|packages| packages.as_object()) {
+        for (path, entry) in entries {
+            // "" is the project itself; links are workspace packages
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            if entry.get("link").and_then(|link| link.as_bool()) == Some(true) {
+                continue;
+            }
+            let Some(version) = entry.get("version").and_then(|version| version.as_str()) else {
+                continue;
+            };
+            packages.push(LockedPackage::new(Ecosystem::Npm, name, version, package_json_license(entry)));
+        }
+    } else if let Some(dependencies) = lock.get("dependencies") {
+        collect_v1_dependencies(dependencies, &mut packages);
+    }
+    Ok(packages)
+}
+
+fn collect_v1_dependencies(dependencies: &serde_json::Value, packages: &mut Vec<LockedPackage>) {
+    for (name, entry) in dependencies.as_object().into_iter().flatten() {
+        if let Some(version) = entry.get("version").and_then(|version| version.as_str()) {
+            // Links and git dependencies have no registry version
+            if !version.starts_with("file:") && !version.contains("://") {
+                packages.push(LockedPackage::new(Ecosystem::Npm, name, version, None));
+            }
+        }
+        if let Some(nested) = entry.get("dependencies") {
+            collect_v1_dependencies(nested, packages);
+        }
+    }
+}
+
+/// The pinned (`==`) packages of a `requirements.txt`; others have no one
+/// version to look up.
+pub fn parse_requirements(text: &str) -> Vec<LockedPackage> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.split(" #").next().unwrap_or_default().trim();
+            if line.starts_with(['#', '-']) {
+                return None;
+            }
+            // Environment markers and `\` continuations with hashes
+            let requirement = line.split([';', '\\']).next()?.trim();
+            let (name, version) = requirement.split_once("==")?;
+            let name = name.split('[').next()?.trim();
+            let version = version.split([',', ' ']).next()?.trim();
+            (!name.is_empty() && !version.is_empty()).then(|| LockedPackage::new(Ecosystem::PyPI, name, version, None))
+        })
+        .collect()
+}
+
+/// The `license` of a `package.json`, or of a lockfile entry: an SPDX
+/// expression, or the older `{ "type": … }` and `licenses` list.
+pub fn package_json_license(package: &serde_json::Value) -> Option<String> {
+    let type_of = |license: &serde_json::Value| -> Option<String> {
+        license
+            .as_str()
+            .or_else(|| license.get("type")?.as_str())
+            .map(str::to_string)
+    };
+    if let Some(license) = package.get("license").and_then(type_of) {
+        return Some(license);
+    }
+    let licenses: Vec<String> = package
+        .get("licenses")?
+        .as_array()?
+        .iter()
+        .filter_map(type_of)
+        .collect();
+    (!licenses.is_empty()).then(|| licenses.join(" OR "))
+}
+
+/// The `package.license` of a crate's `Cargo.toml`.
+pub fn cargo_toml_license(text: &str) -> Option<String> {
+    let manifest: toml::Value = toml::from_str(text).ok()?;
+    Some(manifest.get("package")?.get("license")?.as_str()?.to_string())
+}
+
+/// The license in the `METADATA` of an installed Python package: its
+/// `License-Expression`, a short `License`, or else its license classifier.
+pub fn python_metadata_license(metadata: &str) -> Option<String> {
+    // Headers end at the first blank line; the description follows
+    let headers = metadata.lines().take_while(|line| !line.is_empty());
+    let mut license = None;
+    let mut classifier = None;
+    for line in headers {
+        if let Some(expression) = line.strip_prefix("License-Expression:") {
+            return Some(expression.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("License:") {
+            license = Some(value.trim());
+        } else if let Some(value) = line.strip_prefix("Classifier: License :: ") {
+            classifier.get_or_insert(value.rsplit(" :: ").next().unwrap_or(value).trim());
+        }
+    }
+    // `License` is often the whole license text, or UNKNOWN
+    license
+        .filter(|license| !license.is_empty() && license.len() <= 64 && !license.eq_ignore_ascii_case("UNKNOWN"))
+        .map(str::to_string)
+        .or_else(|| classifier.map(classifier_license))
+}
+
+/// The SPDX id for a trove license classifier, when there's one.
+fn classifier_license(classifier: &str) -> String {
+    let spdx = match classifier {
+        "MIT License" => "MIT",
+        "Apache Software License" => "Apache-2.0",
+        "BSD License" => "BSD-3-Clause",
+        "ISC License (ISCL)" => "ISC",
+        "Mozilla Public License 2.0 (MPL 2.0)" => "MPL-2.0",
+        "GNU General Public License v2 (GPLv2)" => "GPL-2.0",
+        "GNU General Public License v3 (GPLv3)" => "GPL-3.0",
+        "GNU General Public License v3 or later (GPLv3+)" => "GPL-3.0-or-later",
+        "GNU Lesser General Public License v3 (LGPLv3)" => "LGPL-3.0",
+        "GNU Affero General Public License v3" => "AGPL-3.0",
+        "The Unlicense (Unlicense)" => "Unlicense",
+        _ => classifier,
+    };
+    spdx.to_string()
+}
+
+/// The license a registry's JSON for one version of a package gives: the
+/// crates.io API, the npm registry or PyPI's JSON API.
+pub fn registry_license(ecosystem: Ecosystem, response: &serde_json::Value) -> Option<String> {
+    match ecosystem {
+        Ecosystem::CratesIo => Some(response.get("version")?.get("license")?.as_str()?.to_string()),
+        Ecosystem::Npm => package_json_license(response),
+        Ecosystem::PyPI => {
+            let info = response.get("info")?;
+            let text = |key: &str| info.get(key).and_then(|value| value.as_str()).filter(|value| !value.is_empty());
+            if let Some(expression) = text("license_expression") {
+                return Some(expression.to_string());
+            }
+            let classifiers: String = info
+                .get("classifiers")
+                .and_then(|classifiers| classifiers.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|classifier| classifier.as_str())
+                .map(|classifier| format!("Classifier: {}\n", classifier))
+                .collect();
+            // Often the whole license text
+            let license = text("license")
+                .filter(|license| !license.contains('\n'))
+                .map(|license| format!("License: {}\n", license))
+                .unwrap_or_default();
+            python_metadata_license(&format!("{}{}", license, classifiers))
+        }
+    }
+}
+
+/// Licenses not to depend on, like `GPL-3.0` or `AGPL`. An entry matches
+/// licenses it's the start of, so `AGPL` takes in `AGPL-3.0-only` and
+/// `GPL-3.0` takes in `GPL-3.0-or-later`, but not `LGPL-3.0`.
+#[derive(Debug, Clone, Default)]
+pub struct LicenseDenylist {
+    entries: Vec<String>,
+}
+
+impl LicenseDenylist {
+    pub fn new(entries: &[String]) -> Self {
+        Self {
+            entries: entries.iter().map(|entry| entry.trim().to_string()).filter(|entry| !entry.is_empty()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry a package under `license`, an SPDX expression, is denied
+    /// by. A choice (`MIT OR GPL-3.0`) is only denied when every option is;
+    /// a combination (`MIT AND GPL-3.0`) when any part is.
+    pub fn matches(&self, license: &str) -> Option<&str> {
+        let tokens = tokenize(license);
+        let mut parser = ExpressionParser {
+            tokens: &tokens,
+            position: 0,
+            denylist: self,
+        };
+        parser.any_of()
+    }
+
+    fn match_id(&self, id: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| {
+                id.get(..entry.len()).is_some_and(|start| start.eq_ignore_ascii_case(entry))
+                    && matches!(id.as_bytes().get(entry.len()), None | Some(b'-' | b'+' | b'.'))
+            })
+            .map(String::as_str)
+    }
+}
+
+fn tokenize(expression: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in expression.char_indices() {
+        // `/` separates choices in older crates' licenses
+        if c.is_whitespace() || matches!(c, '(' | ')' | '/') {
+            if let Some(start) = start.take() {
+                tokens.push(&expression[start..index]);
+            }
+            match c {
+                '/' => tokens.push("OR"),
+                '(' | ')' => tokens.push(&expression[index..index + 1]),
+                _ => {}
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&expression[start..]);
+    }
+    tokens
+}
+
+/// Evaluates an SPDX expression against a denylist, from its tokens.
+struct ExpressionParser<'t, 'd> {
+    tokens: &'t [&'t str],
+    position: usize,
+    denylist: &'d LicenseDenylist,
+}
+
+impl<'t, 'd> ExpressionParser<'t, 'd> {
+    fn peek(&self) -> Option<&'t str> {
+        self.tokens.get(self.position).copied()
+    }
+
+    /// Alternatives: denied only when all are.
+    fn any_of(&mut self) -> Option<&'d str> {
+        let mut denied = self.all_of();
+        while self.peek().is_some_and(|token| token.eq_ignore_ascii_case("OR")) {
+            self.position += 1;
+            let other = self.all_of();
+            denied = denied.filter(|_| other.is_some());
+        }
+        denied
+    }
+
+    /// Parts that all apply: denied when one is.
+    fn all_of(&mut self) -> Option<&'d str> {
+        let mut denied = self.license();
+        while self.peek().is_some_and(|token| token.eq_ignore_ascii_case("AND")) {
+            self.position += 1;
+            let other = self.license();
+            denied = denied.or(other);
+        }
+        denied
+    }
+
+    fn license(&mut self) -> Option<&'d str> {
+        let token = self.peek()?;
+        self.position += 1;
+        let denied = if token == "(" {
+            let denied = self.any_of();
+            if self.peek() == Some(")") {
+                self.position += 1;
+            }
+            denied
+        } else {
+            self.denylist.match_id(token)
+        };
+        // An exception doesn't change the license it's for
+        if self.peek().is_some_and(|token| token.eq_ignore_ascii_case("WITH")) {
+            self.position += 2;
+        }
+        denied
+    }
+}
+
+/// Where a package's license was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LicenseSource {
+    Lockfile,
+    /// The package's own metadata, vendored or installed
+    Local,
+    Registry,
+}
+
+impl LicenseSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            LicenseSource::Lockfile => "lockfile",
+            LicenseSource::Local => "local",
+            LicenseSource::Registry => "registry",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InventoryEntry {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    /// `None` when neither the lockfile, the local metadata nor (online)
+    /// the registry says
+    pub license: Option<String>,
+    pub license_source: Option<LicenseSource>,
+    /// The lockfile it was first found in
+    pub lockfile: PathBuf,
+    /// The denylist entry its license falls under
+    pub denied_by: Option<String>,
+}
+
+/// The packages a project's lockfiles pin, with their licenses.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyInventory {
+    pub root: PathBuf,
+    pub entries: Vec<InventoryEntry>,
+    /// Lockfiles that couldn't be read
+    pub errors: Vec<String>,
+}
+
+impl DependencyInventory {
+    /// Reads the lockfiles under `root`, outside `config.excluded_paths`,
+    /// resolving licenses from the lockfiles and the packages' vendored or
+    /// installed metadata only, and marks the denied ones.
+    pub fn local(root: &Path, config: &SecurityConfig) -> Self {
+        let mut inventory = Self {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        let mut seen = HashSet::new();
+        for lockfile in find_lockfiles(root, &config.excluded_paths) {
+            let packages = match parse_lockfile(&lockfile) {
+                Ok(packages) => packages,
+                Err(e) => {
+                    inventory.errors.push(format!("{:#}", e));
+                    continue;
+                }
+            };
+            let directory = lockfile.parent().unwrap_or(root);
+            for package in packages {
+                if !seen.insert((package.ecosystem, package.name.clone(), package.version.clone())) {
+                    continue;
+                }
+                let (license, license_source) = match package.license {
+                    Some(license) => (Some(license), Some(LicenseSource::Lockfile)),
+                    None => match local_license(&package, directory) {
+                        Some(license) => (Some(license), Some(LicenseSource::Local)),
+                        None => (None, None),
+                    },
+                };
+                inventory.entries.push(InventoryEntry {
+                    ecosystem: package.ecosystem,
+                    name: package.name,
+                    version: package.version,
+                    license,
+                    license_source,
+                    lockfile: lockfile.clone(),
+                    denied_by: None,
+                });
+            }
+        }
+        inventory.entries.sort_by(|a, b| (a.ecosystem, &a.name, &a.version).cmp(&(b.ecosystem, &b.name, &b.version)));
+        inventory.apply_denylist(&LicenseDenylist::new(&config.license_denylist));
+        inventory
+    }
+
+    /// Like `local`, then with `online`, asks the registries for the
+    /// licenses still unknown.
+    pub async fn build(root: &Path, config: &SecurityConfig, online: bool) -> Result<Self> {
+        let (path, local_config) = (root.to_path_buf(), config.clone());
+        let mut inventory = tokio::task::spawn_blocking(move || Self::local(&path, &local_config)).await?;
+        if online {
+            let client = reqwest::Client::builder()
+                .timeout(REGISTRY_TIMEOUT)
+                // crates.io turns away requests without one
+                .user_agent(concat!("antraft/", env!("CARGO_PKG_VERSION")))
+                .build()?;
+            for entry in inventory.entries.iter_mut().filter(|entry| entry.license.is_none()) {
+                match fetch_registry_license(&client, entry).await {
+                    Ok(Some(license)) => {
+                        entry.license = Some(license);
+                        entry.license_source = Some(LicenseSource::Registry);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::debug!("No license for {} {}: {:#}", entry.name, entry.version, e),
+                }
+            }
+            inventory.apply_denylist(&LicenseDenylist::new(&config.license_denylist));
+        }
+        Ok(inventory)
+    }
+
+    pub fn apply_denylist(&mut self, denylist: &LicenseDenylist) {
+        for entry in &mut self.entries {
+            entry.denied_by = entry
+                .license
+                .as_deref()
+                .and_then(|license| denylist.matches(license))
+                .map(str::to_string);
+        }
+    }
+
+    /// How many packages have each license, most common first; unknown
+    /// ones count as "Unknown".
+    pub fn license_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in &self.entries {
+            *counts.entry(entry.license.as_deref().unwrap_or("Unknown")).or_default() += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().map(|(license, count)| (license.to_string(), count)).collect();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+    }
+
+    pub fn unknown(&self) -> impl Iterator<Item = &InventoryEntry> {
+        self.entries.iter().filter(|entry| entry.license.is_none())
+    }
+
+    /// A medium finding for each package with a denied license.
+    pub fn findings(&self) -> Vec<Vulnerability> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let denied_by = entry.denied_by.as_ref()?;
+                let license = entry.license.as_deref().unwrap_or_default();
+                let lockfile = entry.lockfile.strip_prefix(&self.root).unwrap_or(&entry.lockfile);
+                let mut finding = Vulnerability::new(
+                    format!("{} {} is licensed {}", entry.name, entry.version, license),
+                    format!(
+                        "The {} package {} {} is under {}, which the license denylist rules out ({}).",
+                        entry.ecosystem.name(),
+                        entry.name,
+                        entry.version,
+                        license,
+                        denied_by
+                    ),
+                    Severity::Medium,
+                    "license".to_string(),
+                    lockfile.display().to_string(),
+                    "license-inventory".to_string(),
+                )
+                .with_fix(format!("Replace {} or get its use cleared", entry.name));
+                finding.id = format!("license:{}:{}@{}", entry.ecosystem.name(), entry.name, entry.version);
+                Some(finding)
+            })
+            .collect()
+    }
+
+    /// The inventory as CSV, one package per row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("ecosystem,name,version,license,license_source,lockfile,denied_by\n");
+        for entry in &self.entries {
+            let lockfile = entry.lockfile.strip_prefix(&self.root).unwrap_or(&entry.lockfile);
+            let fields: [&str; 7] = [
+                entry.ecosystem.name(),
+                &entry.name,
+                &entry.version,
+                entry.license.as_deref().unwrap_or_default(),
+                entry.license_source.map(LicenseSource::label).unwrap_or_default(),
+                &lockfile.display().to_string(),
+                entry.denied_by.as_deref().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Reports dependencies under denied licenses as part of security scans,
+/// from local metadata only.
+pub struct LicenseScanner {
+    config: SecurityConfig,
+}
+
+impl LicenseScanner {
+    pub fn new(config: SecurityConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn scan(&self, path: &Path) -> Result<ScanResult> {
+        let (path, config) = (path.to_path_buf(), self.config.clone());
+        let inventory = tokio::task::spawn_blocking(move || DependencyInventory::local(&path, &config)).await?;
+        Ok(ScanResult::Success(inventory.findings()))
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The lockfiles under `root`, skipping directories named in `excluded`.
+pub fn find_lockfiles(root: &Path, excluded: &[String]) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !excluded.iter().any(|name| entry.file_name() == name.as_str())
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| LOCKFILES.iter().any(|name| entry.file_name() == *name))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// The license in the package's own metadata next to the lockfile in
+/// `directory`: `vendor/` or the cargo registry for crates, `node_modules`
+/// for npm, and a virtualenv's `site-packages` for Python.
+fn local_license(package: &LockedPackage, directory: &Path) -> Option<String> {
+    match package.ecosystem {
+        Ecosystem::CratesIo => {
+            let versioned = format!("{}-{}", package.name, package.version);
+            let mut manifests = vec![
+                directory.join("vendor").join(&versioned).join("Cargo.toml"),
+                directory.join("vendor").join(&package.name).join("Cargo.toml"),
+            ];
+            let cargo_home = std::env::var_os("CARGO_HOME")
+                .map(PathBuf::from)
+                .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")));
+            if let Some(registries) = cargo_home.and_then(|home| std::fs::read_dir(home.join("registry/src")).ok()) {
+                manifests.extend(registries.flatten().map(|registry| registry.path().join(&versioned).join("Cargo.toml")));
+            }
+            manifests.iter().find_map(|path| {
+                let text = std::fs::read_to_string(path).ok()?;
+                let manifest: toml::Value = toml::from_str(&text).ok()?;
+                // `vendor/<name>` may be another version
+                let version = manifest.get("package")?.get("version")?.as_str()?;
+                (version == package.version).then(|| cargo_toml_license(&text)).flatten()
+            })
+        }
+        Ecosystem::Npm => {
+            let path = directory.join("node_modules").join(&package.name).join("package.json");
+            let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+            let version = manifest.get("version")?.as_str()?;
+            (version == package.version).then(|| package_json_license(&manifest)).flatten()
+        }
+        Ecosystem::PyPI => {
+            let name = normalize_python_name(&package.name);
+            site_packages(directory).into_iter().find_map(|site_packages| {
+                let entry = std::fs::read_dir(&site_packages).ok()?.flatten().find(|entry| {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    file_name
+                        .strip_suffix(".dist-info")
+                        .and_then(|stem| stem.rsplit_once('-'))
+                        .is_some_and(|(dist, version)| normalize_python_name(dist) == name && version == package.version)
+                })?;
+                python_metadata_license(&std::fs::read_to_string(entry.path().join("METADATA")).ok()?)
+            })
+        }
+    }
+}
+
+/// A Python package name as compared: case, `-`, `_` and `.` don't matter.
+fn normalize_python_name(name: &str) -> String {
+    name.to_lowercase().replace(['-', '.'], "_")
+}
+
+/// The `site-packages` directories of the virtualenvs in `directory`.
+fn site_packages(directory: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for venv in [".venv", "venv", "env"] {
+        let venv = directory.join(venv);
+        // Windows virtualenvs have no Python version in the path
+        found.push(venv.join("Lib").join("site-packages"));
+        if let Ok(versions) = std::fs::read_dir(venv.join("lib")) {
+            found.extend(versions.flatten().map(|version| version.path().join("site-packages")));
+        }
+    }
+    found.retain(|path| path.is_dir());
+    found
+}
+
+async fn fetch_registry_license(client: &reqwest::Client, entry: &InventoryEntry) -> Result<Option<String>> {
+    let url = match entry.ecosystem {
+        Ecosystem::CratesIo => format!("https://crates.io/api/v1/crates/{}/{}", entry.name, entry.version),
+        Ecosystem::Npm => format!("https://registry.npmjs.org/{}/{}", entry.name, entry.version),
+        Ecosystem::PyPI => format!("https://pypi.org/pypi/{}/{}/json", entry.name, entry.version),
+    };
+    let response: serde_json::Value = client.get(&url).send().await?.error_for_status()?.json().await?;
+    Ok(registry_license(entry.ecosystem, &response))
+}
@@ -2,14 +2,28 @@ pub mod scanner;
 pub mod bandit;
 pub mod semgrep;
 pub mod osv;
+pub mod secrets;
+pub mod custom_rules;
+pub mod fixes;
+pub mod command_analysis;
+pub mod inventory;
+pub mod config_audit;
+pub mod trivy;
+pub mod rule_catalog;
+pub mod ignore_comments;
+pub mod findings_db;
+pub mod chunks;
 
-pub use scanner::{SecurityScanner, ScanResult, Vulnerability, Severity};
+pub use scanner::{Remediation, ScanProgress, ScanResult, Scanner, ScannerKind, SecurityScanner, Severity, Vulnerability};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::tools::{self, ExternalTool};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SecurityConfig {
     pub enable_bandit: bool,
     pub enable_semgrep: bool,
@@ -19,6 +33,28 @@ pub struct SecurityConfig {
     pub excluded_paths: Vec<String>,
     pub bandit_config_path: Option<PathBuf>,
     pub semgrep_rules_path: Option<PathBuf>,
+    /// Run the regex rules from `custom_rules_path`
+    pub enable_custom_rules: bool,
+    /// See `custom_rules::CustomRule`; `security_rules.toml` in the config
+    /// directory when unset
+    pub custom_rules_path: Option<PathBuf>,
+    /// Ids of `command_analysis` rules not to check commands from the AI,
+    /// workflows and pastes against
+    pub disabled_command_rules: Vec<String>,
+    /// Licenses dependencies shouldn't be under, reported as medium
+    /// findings; see `inventory::LicenseDenylist`
+    pub license_denylist: Vec<String>,
+    /// Check Dockerfiles, compose files and GitHub Actions workflows; see
+    /// `config_audit::CONFIG_RULES`
+    pub enable_config_audit: bool,
+    /// Scan with trivy when it's installed: dependencies in full and
+    /// dependency scans, and container images
+    pub enable_trivy: bool,
+    /// Remember findings across scans in `findings_db_path`, to tell new
+    /// ones from known ones and mark the ones gone as fixed
+    pub track_findings: bool,
+    /// `findings.db` in the data directory when unset
+    pub findings_db_path: Option<PathBuf>,
 }
 
 impl Default for SecurityConfig {
@@ -41,16 +77,86 @@ impl Default for SecurityConfig {
             ],
             bandit_config_path: None,
             semgrep_rules_path: None,
+            enable_custom_rules: true,
+            custom_rules_path: None,
+            disabled_command_rules: Vec::new(),
+            license_denylist: vec!["GPL-3.0".to_string(), "AGPL".to_string()],
+            enable_config_audit: true,
+            enable_trivy: true,
+            track_findings: true,
+            findings_db_path: None,
         }
     }
 }
 
+impl SecurityConfig {
+    /// What `command_analysis` finds in `command`, without the disabled
+    /// rules.
+    pub fn analyze_command(&self, command: &str) -> Vec<command_analysis::CommandFinding> {
+        let mut findings = command_analysis::analyze_command(command);
+        findings.retain(|finding| !self.disabled_command_rules.iter().any(|rule| rule == finding.rule));
+        findings
+    }
+
+    /// Where the custom rules are read from.
+    pub fn custom_rules_path(&self) -> PathBuf {
+        self.custom_rules_path.clone().unwrap_or_else(custom_rules::default_rules_path)
+    }
+
+    /// Where findings are tracked across scans.
+    pub fn findings_db_path(&self) -> PathBuf {
+        self.findings_db_path.clone().unwrap_or_else(findings_db::default_db_path)
+    }
+
+    /// The external scanners, each with whether this config enables it.
+    pub fn external_scanners(&self) -> [(ExternalTool, bool); 4] {
+        [
+            (tools::BANDIT, self.enable_bandit),
+            (tools::SEMGREP, self.enable_semgrep),
+            (tools::OSV_SCANNER, self.enable_osv),
+            (tools::TRIVY, self.enable_trivy),
+        ]
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SecurityScanRequest {
-    pub path: PathBuf,
+    pub target: ScanTarget,
     pub scan_type: ScanType,
     pub include_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
+    /// Leave out findings silenced by an ignore comment on their line,
+    /// like `# nosec` or `# antraft-ignore: <rule-id>`; see
+    /// `ignore_comments::IgnoreComment`
+    pub honor_ignore_comments: bool,
+}
+
+/// What a scan looks at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanTarget {
+    /// A file or directory, checked by every scanner of the scan type
+    Path(PathBuf),
+    /// An image reference like `nginx:1.25`, checked by the scanners that
+    /// can; the scan type doesn't apply
+    ContainerImage(String),
+}
+
+impl ScanTarget {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ScanTarget::Path(path) => Some(path),
+            ScanTarget::ContainerImage(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ScanTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanTarget::Path(path) => write!(f, "{}", path.display()),
+            ScanTarget::ContainerImage(image) => write!(f, "image {}", image),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -65,11 +171,25 @@ pub enum ScanType {
 pub struct SecurityReport {
     pub scan_id: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// The scanned directory; empty for images
     pub path: PathBuf,
+    /// The scanned container image, for image scans
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
     pub scan_type: String,
     pub vulnerabilities: Vec<Vulnerability>,
     pub summary: ScanSummary,
     pub recommendations: Vec<String>,
+    /// Scanners that failed or timed out, and why; their findings are missing
+    #[serde(default)]
+    pub scanner_errors: Vec<String>,
+    /// Findings silenced by ignore comments, not in `vulnerabilities` or
+    /// the summary
+    #[serde(default)]
+    pub suppressed: Vec<Vulnerability>,
+    /// Ignore comments on a finding's line that don't name its rule
+    #[serde(default)]
+    pub ignore_comment_warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +202,21 @@ pub struct ScanSummary {
     pub info_count: usize,
     pub files_scanned: usize,
     pub scan_duration_ms: u64,
+    /// Findings per category, like `dependency` or `secret`
+    #[serde(default)]
+    pub by_category: HashMap<String, usize>,
+    /// The files with the most findings, most first, and how many they have
+    #[serde(default)]
+    pub top_files: Vec<(String, usize)>,
+    /// The risk score of the last scan of the same target, for the trend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_risk_score: Option<u32>,
+}
+
+impl Default for ScanSummary {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ScanSummary {
@@ -95,6 +230,9 @@ impl ScanSummary {
             info_count: 0,
             files_scanned: 0,
             scan_duration_ms: 0,
+            by_category: HashMap::new(),
+            top_files: Vec::new(),
+            previous_risk_score: None,
         }
     }
 
@@ -127,34 +265,96 @@ impl ScanSummary {
             _ => "Critical".to_string(),
         }
     }
+
+    /// The categories and their counts, most findings first.
+    pub fn categories(&self) -> Vec<(&str, usize)> {
+        let mut categories: Vec<_> = self.by_category.iter().map(|(category, count)| (category.as_str(), *count)).collect();
+        categories.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        categories
+    }
+
+    /// How the risk score changed since the previous scan, like `62 → 48`.
+    pub fn risk_trend(&self) -> Option<String> {
+        let previous = self.previous_risk_score?;
+        Some(format!("{} → {}", previous, self.risk_score()))
+    }
+}
+
+/// Files listed in `ScanSummary::top_files`.
+pub const TOP_FILES: usize = 3;
+
+/// The `limit` files with the most findings and their counts; ties go by
+/// path.
+pub fn top_files(vulnerabilities: &[Vulnerability], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for vulnerability in vulnerabilities.iter().filter(|v| !v.file_path.is_empty()) {
+        *counts.entry(vulnerability.file_path.as_str()).or_default() += 1;
+    }
+    let mut files: Vec<_> = counts.into_iter().map(|(file, count)| (file.to_string(), count)).collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files.truncate(limit);
+    files
 }
 
 impl SecurityReport {
-    pub fn new(path: PathBuf, scan_type: ScanType) -> Self {
+    pub fn new(target: &ScanTarget, scan_type: ScanType) -> Self {
+        let (path, image) = match target {
+            ScanTarget::Path(path) => (path.clone(), None),
+            ScanTarget::ContainerImage(image) => (PathBuf::new(), Some(image.clone())),
+        };
         Self {
             scan_id: uuid::Uuid::new_v4().to_string(),
             timestamp: chrono::Utc::now(),
             path,
+            image,
             scan_type: format!("{:?}", scan_type),
             vulnerabilities: Vec::new(),
             summary: ScanSummary::new(),
             recommendations: Vec::new(),
+            scanner_errors: Vec::new(),
+            suppressed: Vec::new(),
+            ignore_comment_warnings: Vec::new(),
         }
     }
 
+    /// The scanned path, or the image's name.
+    pub fn target_name(&self) -> String {
+        match &self.image {
+            Some(image) => image.clone(),
+            None => self.path.display().to_string(),
+        }
+    }
+
+    /// Records a scanner that didn't finish.
+    pub fn scanner_error(&mut self, error: String) {
+        log::warn!("Security scan: {}", error);
+        self.scanner_errors.push(error);
+    }
+
     pub fn add_vulnerability(&mut self, vulnerability: Vulnerability) {
         self.summary.add_vulnerability(&vulnerability.severity);
+        *self.summary.by_category.entry(vulnerability.category.clone()).or_default() += 1;
         self.vulnerabilities.push(vulnerability);
     }
 
     pub fn finalize(&mut self, files_scanned: usize, duration_ms: u64) {
         self.summary.files_scanned = files_scanned;
         self.summary.scan_duration_ms = duration_ms;
+        self.summary.top_files = top_files(&self.vulnerabilities, TOP_FILES);
+        self.generate_recommendations();
+    }
+
+    /// Compares the report with the previous scan of the same target, for
+    /// the risk trend.
+    pub fn set_previous_risk_score(&mut self, score: u32) {
+        self.summary.previous_risk_score = Some(score);
         self.generate_recommendations();
     }
 
     fn generate_recommendations(&mut self) {
         let mut recommendations = Vec::new();
+        let category = |name: &str| self.summary.by_category.get(name).copied().unwrap_or_default();
+        let findings = |count: usize| format!("{} finding{}", count, if count == 1 { "" } else { "s" });
 
         if self.summary.critical_count > 0 {
             recommendations.push("🚨 Critical vulnerabilities found! Address immediately.".to_string());
@@ -168,31 +368,65 @@ impl SecurityReport {
             recommendations.push("📋 Multiple medium-severity issues. Consider a security review.".to_string());
         }
 
-        if self.vulnerabilities.iter().any(|v| v.category == "dependency") {
-            recommendations.push("📦 Update dependencies to latest secure versions.".to_string());
+        // Where the serious findings are concentrated
+        let serious = |v: &&Vulnerability| matches!(v.severity, Severity::Critical | Severity::High);
+        let serious_total = self.vulnerabilities.iter().filter(serious).count();
+        for (file, _) in &self.summary.top_files {
+            let in_file = self.vulnerabilities.iter().filter(serious).filter(|v| &v.file_path == file).count();
+            if in_file > 0 && serious_total > 1 {
+                recommendations.push(format!(
+                    "🎯 `{}` accounts for {}% of high and critical findings.",
+                    file,
+                    (in_file * 100 + serious_total / 2) / serious_total
+                ));
+            }
+        }
+
+        if category("container") > 0 {
+            recommendations.push(format!("🐳 Rebuild the image on an updated base image ({}).", findings(category("container"))));
+        }
+
+        if category("dependency") > 0 {
+            recommendations.push(format!("📦 Update dependencies to latest secure versions ({}).", findings(category("dependency"))));
         }
 
-        if self.vulnerabilities.iter().any(|v| v.category == "secret") {
-            recommendations.push("🔐 Secrets detected in code. Use environment variables or secret management.".to_string());
+        if category("secret") > 0 {
+            recommendations.push(format!(
+                "🔐 Secrets detected in code ({}). Use environment variables or secret management.",
+                findings(category("secret"))
+            ));
         }
 
-        if self.vulnerabilities.iter().any(|v| v.category == "injection") {
-            recommendations.push("💉 Input validation issues found. Implement proper sanitization.".to_string());
+        if category("injection") > 0 {
+            recommendations.push(format!(
+                "💉 Input validation issues found ({}). Implement proper sanitization.",
+                findings(category("injection"))
+            ));
         }
 
         if recommendations.is_empty() {
             recommendations.push("✅ No significant security issues found. Good job!".to_string());
         }
 
+        if let Some(trend) = self.summary.risk_trend() {
+            let icon = match self.summary.previous_risk_score.cmp(&Some(self.summary.risk_score())) {
+                std::cmp::Ordering::Less => "📈",
+                std::cmp::Ordering::Equal => "➖",
+                std::cmp::Ordering::Greater => "📉",
+            };
+            recommendations.insert(0, format!("{} Risk score {} since the last scan.", icon, trend));
+        }
+
         self.recommendations = recommendations;
     }
 
     pub fn to_markdown(&self) -> String {
         let mut markdown = format!(
-            "# Security Scan Report\n\n**Scan ID:** {}\n**Timestamp:** {}\n**Path:** {}\n**Type:** {}\n\n",
+            "# Security Scan Report\n\n**Scan ID:** {}\n**Timestamp:** {}\n**{}:** {}\n**Type:** {}\n\n",
             self.scan_id,
             self.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
-            self.path.display(),
+            if self.image.is_some() { "Image" } else { "Path" },
+            self.target_name(),
             self.scan_type
         );
 
@@ -200,7 +434,10 @@ impl SecurityReport {
         markdown.push_str("## Summary\n\n");
         markdown.push_str(&format!("- **Total Vulnerabilities:** {}\n", self.summary.total_vulnerabilities));
         markdown.push_str(&format!("- **Risk Level:** {}\n", self.summary.risk_level()));
-        markdown.push_str(&format!("- **Risk Score:** {}\n", self.summary.risk_score()));
+        match self.summary.risk_trend() {
+            Some(trend) => markdown.push_str(&format!("- **Risk Score:** {} (last scan: {})\n", self.summary.risk_score(), trend)),
+            None => markdown.push_str(&format!("- **Risk Score:** {}\n", self.summary.risk_score())),
+        }
         markdown.push_str(&format!("- **Files Scanned:** {}\n", self.summary.files_scanned));
         markdown.push_str(&format!("- **Scan Duration:** {}ms\n\n", self.summary.scan_duration_ms));
 
@@ -223,6 +460,18 @@ impl SecurityReport {
                 markdown.push_str(&format!("- ℹ️ **Info:** {}\n", self.summary.info_count));
             }
             markdown.push('\n');
+
+            markdown.push_str("### By Category\n\n");
+            for (category, count) in self.summary.categories() {
+                markdown.push_str(&format!("- **{}:** {}\n", category, count));
+            }
+            markdown.push('\n');
+
+            markdown.push_str("### Top Files\n\n");
+            for (file, count) in &self.summary.top_files {
+                markdown.push_str(&format!("- `{}`: {}\n", file, count));
+            }
+            markdown.push('\n');
         }
 
         // Recommendations
@@ -256,6 +505,33 @@ impl SecurityReport {
             }
         }
 
+        if !self.suppressed.is_empty() {
+            markdown.push_str("## Silenced by Ignore Comments\n\n");
+            for vuln in &self.suppressed {
+                markdown.push_str(&format!(
+                    "- **{}** ({:?}, {}) at {}:{}",
+                    vuln.title,
+                    vuln.severity,
+                    vuln.id,
+                    vuln.file_path,
+                    vuln.line_number.unwrap_or(0)
+                ));
+                if let Some(suppression) = &vuln.suppression {
+                    markdown.push_str(&format!(": `{}`", suppression.comment));
+                }
+                markdown.push('\n');
+            }
+            markdown.push('\n');
+        }
+
+        if !self.ignore_comment_warnings.is_empty() {
+            markdown.push_str("## Ignore Comment Warnings\n\n");
+            for warning in &self.ignore_comment_warnings {
+                markdown.push_str(&format!("- {}\n", warning));
+            }
+            markdown.push('\n');
+        }
+
         markdown
     }
 }
@@ -1,4 +1,5 @@
-use super::{ScanResult, Severity, Vulnerability};
+use super::{fixes, Remediation, ScanResult, Severity, Vulnerability};
+use crate::tools::{self, ToolDetector};
 use anyhow::Result;
 use std::path::PathBuf;
 use tokio::process::Command;
@@ -8,12 +9,12 @@ pub struct OsvScanner {
 }
 
 impl OsvScanner {
+    /// Runs `osv-scanner` from `PATH`; a scan fails when it isn't installed.
     pub fn new() -> Result<Self> {
-        // For now, assume osv-scanner is available. In a real implementation,
-        // we'd check if the binary exists
-        Ok(Self {
-            binary_path: PathBuf::from("osv-scanner"),
-        })
+        let binary_path = ToolDetector::new()
+            .find(tools::OSV_SCANNER.binary)
+            .unwrap_or_else(|| PathBuf::from(tools::OSV_SCANNER.binary));
+        Ok(Self { binary_path })
     }
 
     pub async fn scan(&self, path: &PathBuf) -> Result<ScanResult> {
@@ -35,44 +36,90 @@ impl OsvScanner {
         }
 
         let response: serde_json::Value = serde_json::from_slice(&output.stdout)?;
-        let mut vulnerabilities = Vec::new();
+        Ok(ScanResult::Success(parse_results(&response)))
+    }
+}
 
-        if let Some(results) = response.get("results") {
-            for result in results.as_array().unwrap_or(&vec![]) {
-                if let Some(packages) = result.get("packages") {
-                    for package in packages.as_array().unwrap_or(&vec![]) {
-                        if let Some(vulnerabilities_list) = package.get("vulnerabilities") {
-                            for vuln_data in vulnerabilities_list.as_array().unwrap_or(&vec![]) {
-                                let vuln = Vulnerability {
-                                    id: vuln_data.get("id").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                                    title: vuln_data.get("summary").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                                    description: vuln_data.get("details").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                                    severity: map_severity(vuln_data.get("severity").and_then(|v| v.as_array()).and_then(|arr| arr.first()).and_then(|s| s.get("score")).map(|v| v.as_str().unwrap_or_default()).unwrap_or("")),
-                                    category: "dependency".to_string(),
-                                    file_path: package.get("package").and_then(|p| p.get("name")).map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                                    line_number: None,
-                                    column_number: None,
-                                    code_snippet: None,
-                                    suggested_fix: Some(format!("Update {} to a secure version", 
-                                        package.get("package").and_then(|p| p.get("name")).map(|v| v.as_str().unwrap_or_default()).unwrap_or("package"))),
-                                    references: vuln_data.get("references")
-                                        .and_then(|refs| refs.as_array())
-                                        .map(|refs| refs.iter()
-                                            .filter_map(|r| r.get("url").and_then(|u| u.as_str()).map(|s| s.to_string()))
-                                            .collect())
-                                        .unwrap_or_default(),
-                                    scanner: "osv".to_string(),
-                                };
-                                vulnerabilities.push(vuln);
-                            }
-                        }
-                    }
-                }
+/// The findings in osv-scanner's `--format=json` output. Each comes with
+/// the upgrade that fixes it, when OSV knows a fixed version.
+pub fn parse_results(response: &serde_json::Value) -> Vec<Vulnerability> {
+    let text = |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+    let mut vulnerabilities = Vec::new();
+    for result in response.get("results").and_then(|v| v.as_array()).into_iter().flatten() {
+        let source = text(result.get("source").and_then(|v| v.get("path"))).map(PathBuf::from);
+        for package in result.get("packages").and_then(|v| v.as_array()).into_iter().flatten() {
+            let info = package.get("package");
+            let name = text(info.and_then(|v| v.get("name"))).unwrap_or_default();
+            let version = text(info.and_then(|v| v.get("version")));
+            let ecosystem = text(info.and_then(|v| v.get("ecosystem"))).unwrap_or_default();
+            for vuln_data in package.get("vulnerabilities").and_then(|v| v.as_array()).into_iter().flatten() {
+                let fixed_version = fixed_version(vuln_data, &name, version.as_deref());
+                let suggested_fix = match &fixed_version {
+                    Some(fixed) => format!("Update {} to >= {}", name, fixed),
+                    None => format!("Update {} to a secure version", if name.is_empty() { "package" } else { &name }),
+                };
+                vulnerabilities.push(Vulnerability {
+                    id: text(vuln_data.get("id")).unwrap_or_default(),
+                    title: text(vuln_data.get("summary")).unwrap_or_default(),
+                    description: text(vuln_data.get("details")).unwrap_or_default(),
+                    severity: map_severity(
+                        vuln_data
+                            .get("severity")
+                            .and_then(|v| v.as_array())
+                            .and_then(|arr| arr.first())
+                            .and_then(|s| s.get("score"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or(""),
+                    ),
+                    category: "dependency".to_string(),
+                    file_path: name.clone(),
+                    line_number: None,
+                    column_number: None,
+                    code_snippet: None,
+                    suggested_fix: Some(suggested_fix),
+                    references: vuln_data
+                        .get("references")
+                        .and_then(|refs| refs.as_array())
+                        .map(|refs| {
+                            refs.iter()
+                                .filter_map(|r| r.get("url").and_then(|u| u.as_str()).map(|s| s.to_string()))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    scanner: "osv".to_string(),
+                    remediation: (!name.is_empty()).then(|| Remediation::Upgrade {
+                        ecosystem: ecosystem.clone(),
+                        package: name.clone(),
+                        version: version.clone(),
+                        fixed_version,
+                        source: source.clone(),
+                    }),
+                    suppression: None,
+                });
             }
         }
-
-        Ok(ScanResult::Success(vulnerabilities))
     }
+    vulnerabilities
+}
+
+/// The lowest version that fixes `vuln_data` for `package` and is newer
+/// than `current`, from the `fixed` events of its affected ranges.
+fn fixed_version(vuln_data: &serde_json::Value, package: &str, current: Option<&str>) -> Option<String> {
+    vuln_data
+        .get("affected")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|affected| {
+            let name = affected.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str());
+            name.is_none_or(|name| name == package)
+        })
+        .flat_map(|affected| affected.get("ranges").and_then(|v| v.as_array()).into_iter().flatten())
+        .flat_map(|range| range.get("events").and_then(|v| v.as_array()).into_iter().flatten())
+        .filter_map(|event| event.get("fixed").and_then(|v| v.as_str()))
+        .filter(|fixed| current.is_none_or(|current| fixes::compare_versions(fixed, current).is_gt()))
+        .min_by(|a, b| fixes::compare_versions(a, b))
+        .map(str::to_string)
 }
 
 fn map_severity(severity_str: &str) -> Severity {
@@ -0,0 +1,136 @@
+use super::Vulnerability;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// The catalog shipped with the app, `assets/rule_catalog.toml`.
+const BUILTIN_CATALOG: &str = include_str!("../../assets/rule_catalog.toml");
+
+/// What a scanner rule or advisory is about, as written in the catalog:
+///
+/// ```toml
+/// [[rules]]
+/// id = "B602"
+/// title = "subprocess with shell=True"
+/// cwe = "CWE-78"
+/// url = "https://bandit.readthedocs.io/en/latest/plugins/b602_subprocess_popen_with_shell_equals_true.html"
+/// explanation = "With shell=True the command goes through the shell…"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RuleInfo {
+    /// The rule id, or for `prefixes` entries the start of advisory ids,
+    /// like `CVE-`
+    pub id: String,
+    pub title: String,
+    pub explanation: String,
+    #[serde(default)]
+    pub cwe: Option<String>,
+    /// Documentation; `{id}` stands for the finding's id
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl RuleInfo {
+    /// The CWE's page on cwe.mitre.org.
+    pub fn cwe_url(&self) -> Option<String> {
+        let number = self.cwe.as_deref()?.strip_prefix("CWE-")?;
+        Some(format!("https://cwe.mitre.org/data/definitions/{}.html", number))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    #[serde(default)]
+    rules: Vec<RuleInfo>,
+    #[serde(default)]
+    prefixes: Vec<RuleInfo>,
+}
+
+/// Explanations, CWEs and documentation links for the rule ids scanners
+/// report: bandit's B-codes, semgrep registry ids, and advisory ids by
+/// their prefix (`CVE-`, `GHSA-`, `RUSTSEC-`…).
+#[derive(Debug, Clone, Default)]
+pub struct RuleCatalog {
+    rules: Vec<RuleInfo>,
+    /// Longest first, so `GHSA-` wins over a shorter match
+    prefixes: Vec<RuleInfo>,
+}
+
+/// What's known about a finding's rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleDetails {
+    /// The catalog's entry, when it knows the rule
+    pub info: Option<RuleInfo>,
+    /// Documentation links: the scanner's own first, as they're about the
+    /// finding itself, then the catalog's
+    pub links: Vec<String>,
+}
+
+impl RuleCatalog {
+    /// The catalog shipped with the app.
+    pub fn builtin() -> &'static RuleCatalog {
+        static CATALOG: OnceLock<RuleCatalog> = OnceLock::new();
+        CATALOG.get_or_init(|| RuleCatalog::from_toml(BUILTIN_CATALOG).expect("the bundled rule catalog is valid"))
+    }
+
+    /// Reads a catalog; every id is listed once, and CWEs look like
+    /// `CWE-78`.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: CatalogFile = toml::from_str(content).context("Invalid rule catalog")?;
+        let mut seen = HashSet::new();
+        for entry in file.rules.iter().chain(&file.prefixes) {
+            if entry.id.trim().is_empty() {
+                bail!("Rule catalog: \"{}\" has no id", entry.title);
+            }
+            if !seen.insert(entry.id.to_ascii_lowercase()) {
+                bail!("Rule catalog: {} is listed twice", entry.id);
+            }
+            if let Some(cwe) = &entry.cwe {
+                let number = cwe.strip_prefix("CWE-").unwrap_or_default();
+                if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+                    bail!("Rule catalog: {} has CWE \"{}\"; write it like CWE-78", entry.id, cwe);
+                }
+            }
+        }
+        let mut prefixes = file.prefixes;
+        prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.id.len()));
+        Ok(Self {
+            rules: file.rules,
+            prefixes,
+        })
+    }
+
+    /// The entry for `id`: the rule with that id, ignoring case, or else
+    /// the longest prefix it starts with. `{id}` in the URL is filled in.
+    pub fn lookup(&self, id: &str) -> Option<RuleInfo> {
+        let id = id.trim();
+        if id.is_empty() {
+            return None;
+        }
+        let entry = self
+            .rules
+            .iter()
+            .find(|rule| rule.id.eq_ignore_ascii_case(id))
+            .or_else(|| self.prefixes.iter().find(|prefix| id.starts_with(&prefix.id)))?;
+        let mut info = entry.clone();
+        info.url = info.url.map(|url| url.replace("{id}", id));
+        Some(info)
+    }
+
+    /// The catalog's entry for `vulnerability`'s rule, with the references
+    /// the scanner gave.
+    pub fn details(&self, vulnerability: &Vulnerability) -> RuleDetails {
+        let info = self.lookup(&vulnerability.id);
+        let mut links: Vec<String> = Vec::new();
+        let catalog_url = info.as_ref().and_then(|info| info.url.as_ref());
+        for link in vulnerability.references.iter().chain(catalog_url) {
+            let link = link.trim();
+            let known = links.iter().any(|known| known.trim_end_matches('/') == link.trim_end_matches('/'));
+            if !link.is_empty() && !known {
+                links.push(link.to_string());
+            }
+        }
+        RuleDetails { info, links }
+    }
+}
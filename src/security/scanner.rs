@@ -1,15 +1,24 @@
-use super::{SecurityConfig, SecurityReport, SecurityScanRequest, ScanType};
+use super::{SecurityConfig, SecurityReport, SecurityScanRequest, ScanTarget, ScanType};
 use super::bandit::BanditScanner;
 use super::semgrep::SemgrepScanner;
 use super::osv::OsvScanner;
+use super::custom_rules::CustomRuleScanner;
+use super::inventory::LicenseScanner;
+use super::config_audit::ConfigAuditScanner;
+use super::trivy::{self, TrivyScanner};
+use super::ignore_comments::{self, Suppression};
+use super::chunks::{self, Exclusions, PathChunk};
 use anyhow::{anyhow, Result};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::time::Instant;
+use tokio::task::JoinSet;
 use tokio::time::{timeout, Duration};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Severity {
     Critical,
     High,
@@ -18,6 +27,19 @@ pub enum Severity {
     Info,
 }
 
+impl Severity {
+    /// Higher for more severe, for sorting and thresholds.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Severity::Critical => 4,
+            Severity::High => 3,
+            Severity::Medium => 2,
+            Severity::Low => 1,
+            Severity::Info => 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vulnerability {
     pub id: String,
@@ -32,6 +54,36 @@ pub struct Vulnerability {
     pub suggested_fix: Option<String>,
     pub references: Vec<String>,
     pub scanner: String,
+    /// How to fix it, when the scanner says; see `fixes::fix_action`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<Remediation>,
+    /// Set when an ignore comment on its line silenced it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppression: Option<Suppression>,
+}
+
+/// A fix a scanner worked out, concrete enough to apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Remediation {
+    /// Upgrading a dependency, as OSV reports it
+    Upgrade {
+        /// OSV's name for it, like `npm` or `crates.io`
+        ecosystem: String,
+        package: String,
+        /// The version in use
+        version: Option<String>,
+        /// The lowest version past `version` with the vulnerability fixed
+        fixed_version: Option<String>,
+        /// The lockfile or manifest the package was found in
+        source: Option<PathBuf>,
+    },
+    /// Replacing bytes `start..end` of the finding's file, as semgrep's
+    /// autofix does
+    Replace {
+        start: usize,
+        end: usize,
+        replacement: String,
+    },
 }
 
 impl Vulnerability {
@@ -56,6 +108,8 @@ impl Vulnerability {
             suggested_fix: None,
             references: Vec::new(),
             scanner,
+            remediation: None,
+            suppression: None,
         }
     }
 
@@ -79,6 +133,11 @@ impl Vulnerability {
         self.references = refs;
         self
     }
+
+    pub fn with_remediation(mut self, remediation: Remediation) -> Self {
+        self.remediation = Some(remediation);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -88,301 +147,404 @@ pub enum ScanResult {
     Timeout,
 }
 
+/// A scanner in flight.
+pub type ScanFuture<'a> = Pin<Box<dyn Future<Output = Result<ScanResult>> + Send + 'a>>;
+
+/// What a scanner looks at, which decides the scan types it runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScannerKind {
+    Code,
+    Dependencies,
+}
+
+/// One tool `SecurityScanner` runs, like Bandit or OSV-Scanner.
+pub trait Scanner: Send + Sync {
+    /// The name reported by `get_available_scanners`
+    fn name(&self) -> &str;
+
+    fn kind(&self) -> ScannerKind;
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a>;
+
+    /// A faster scan for `ScanType::Quick`, or `None` to sit quick scans out.
+    fn quick_scan<'a>(&'a self, _path: &'a Path) -> Option<ScanFuture<'a>> {
+        None
+    }
+
+    /// A scan of a container image, or `None` for scanners that can't.
+    fn scan_image<'a>(&'a self, _image: &'a str) -> Option<ScanFuture<'a>> {
+        None
+    }
+
+    /// Whether directories are scanned a chunk at a time with `scan_chunk`;
+    /// see `chunks`.
+    fn scans_chunks(&self) -> bool {
+        false
+    }
+
+    /// A scan of `chunk` without `excludes`, quick for `ScanType::Quick`, or
+    /// `None` to sit that scan out.
+    fn scan_chunk(&self, _chunk: &PathChunk, _excludes: &[String], _quick: bool) -> Option<ScanFuture<'static>> {
+        None
+    }
+}
+
+/// Findings a scan reports before it's done: those of one chunk of the
+/// target, or of a scanner that scans it whole.
+#[derive(Debug, Clone)]
+pub struct ScanProgress {
+    pub scanner: String,
+    /// The chunk's name, or empty for a scanner that scans the target whole;
+    /// see `chunks::PathChunk`
+    pub chunk: String,
+    /// The scanner's chunks done so far, this one included, and in all
+    pub done: usize,
+    pub total: usize,
+    /// Without those silenced by ignore comments, when they're honored
+    pub findings: Vec<Vulnerability>,
+}
+
+impl Scanner for BanditScanner {
+    fn name(&self) -> &str {
+        "bandit"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Code
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(async move { BanditScanner::scan(self, &path.to_path_buf()).await })
+    }
+
+    fn scans_chunks(&self) -> bool {
+        true
+    }
+
+    fn scan_chunk(&self, chunk: &PathChunk, excludes: &[String], quick: bool) -> Option<ScanFuture<'static>> {
+        (!quick).then(|| BanditScanner::scan_chunk(self, chunk, excludes))
+    }
+}
+
+impl Scanner for SemgrepScanner {
+    fn name(&self) -> &str {
+        "semgrep"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Code
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(async move { SemgrepScanner::scan(self, &path.to_path_buf()).await })
+    }
+
+    fn quick_scan<'a>(&'a self, path: &'a Path) -> Option<ScanFuture<'a>> {
+        Some(Box::pin(async move { SemgrepScanner::quick_scan(self, &path.to_path_buf()).await }))
+    }
+
+    fn scans_chunks(&self) -> bool {
+        true
+    }
+
+    fn scan_chunk(&self, chunk: &PathChunk, excludes: &[String], quick: bool) -> Option<ScanFuture<'static>> {
+        Some(SemgrepScanner::scan_chunk(self, chunk, excludes, quick))
+    }
+}
+
+impl Scanner for OsvScanner {
+    fn name(&self) -> &str {
+        "osv"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Dependencies
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(async move { OsvScanner::scan(self, &path.to_path_buf()).await })
+    }
+
+    // Fast, and dependencies are the most critical part of a quick scan
+    fn quick_scan<'a>(&'a self, path: &'a Path) -> Option<ScanFuture<'a>> {
+        Some(Scanner::scan(self, path))
+    }
+}
+
+impl Scanner for LicenseScanner {
+    fn name(&self) -> &str {
+        "license-inventory"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Dependencies
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(LicenseScanner::scan(self, path))
+    }
+
+    fn quick_scan<'a>(&'a self, path: &'a Path) -> Option<ScanFuture<'a>> {
+        Some(Scanner::scan(self, path))
+    }
+}
+
+impl Scanner for CustomRuleScanner {
+    fn name(&self) -> &str {
+        "custom-rules"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Code
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(CustomRuleScanner::scan(self, path))
+    }
+
+    fn quick_scan<'a>(&'a self, path: &'a Path) -> Option<ScanFuture<'a>> {
+        Some(Scanner::scan(self, path))
+    }
+}
+
+impl Scanner for TrivyScanner {
+    fn name(&self) -> &str {
+        "trivy"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Dependencies
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(TrivyScanner::scan_filesystem(self, path))
+    }
+
+    fn scan_image<'a>(&'a self, image: &'a str) -> Option<ScanFuture<'a>> {
+        Some(Box::pin(TrivyScanner::scan_image(self, image)))
+    }
+}
+
+impl Scanner for ConfigAuditScanner {
+    fn name(&self) -> &str {
+        "config-audit"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Code
+    }
+
+    fn scan<'a>(&'a self, path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(ConfigAuditScanner::scan(self, path))
+    }
+}
+
 pub struct SecurityScanner {
     config: SecurityConfig,
-    bandit_scanner: Option<BanditScanner>,
-    semgrep_scanner: Option<SemgrepScanner>,
-    osv_scanner: Option<OsvScanner>,
+    scanners: Vec<Box<dyn Scanner>>,
 }
 
 impl SecurityScanner {
+    /// A scanner running the tools enabled in `config`.
     pub fn new(config: SecurityConfig) -> Result<Self> {
-        let bandit_scanner = if config.enable_bandit {
+        let mut scanners: Vec<Box<dyn Scanner>> = Vec::new();
+
+        if config.enable_bandit {
             match BanditScanner::new() {
-                Ok(scanner) => Some(scanner),
-                Err(e) => {
-                    warn!("Failed to initialize Bandit scanner: {}", e);
-                    None
-                }
+                Ok(scanner) => scanners.push(Box::new(scanner)),
+                Err(e) => warn!("Failed to initialize Bandit scanner: {}", e),
             }
-        } else {
-            None
-        };
+        }
 
-        let semgrep_scanner = if config.enable_semgrep {
+        if config.enable_semgrep {
             match SemgrepScanner::new() {
-                Ok(scanner) => Some(scanner),
-                Err(e) => {
-                    warn!("Failed to initialize Semgrep scanner: {}", e);
-                    None
-                }
+                Ok(scanner) => scanners.push(Box::new(scanner)),
+                Err(e) => warn!("Failed to initialize Semgrep scanner: {}", e),
             }
-        } else {
-            None
-        };
+        }
 
-        let osv_scanner = if config.enable_osv {
+        if config.enable_osv {
             match OsvScanner::new() {
-                Ok(scanner) => Some(scanner),
-                Err(e) => {
-                    warn!("Failed to initialize OSV scanner: {}", e);
-                    None
-                }
+                Ok(scanner) => scanners.push(Box::new(scanner)),
+                Err(e) => warn!("Failed to initialize OSV scanner: {}", e),
             }
-        } else {
-            None
-        };
-
-        Ok(Self {
-            config,
-            bandit_scanner,
-            semgrep_scanner,
-            osv_scanner,
-        })
-    }
+        }
 
-    pub async fn scan(&self, request: SecurityScanRequest) -> Result<SecurityReport> {
-        let start_time = Instant::now();
-        info!("Starting security scan of: {}", request.path.display());
+        if config.enable_trivy {
+            match TrivyScanner::new() {
+                Ok(scanner) => scanners.push(Box::new(scanner)),
+                Err(e) => debug!("Not scanning with trivy: {}", e),
+            }
+        }
 
-        let mut report = SecurityReport::new(request.path.clone(), request.scan_type.clone());
-        let mut files_scanned = 0;
+        if !config.license_denylist.is_empty() {
+            scanners.push(Box::new(LicenseScanner::new(config.clone())));
+        }
 
-        // Validate path exists
-        if !request.path.exists() {
-            return Err(anyhow!("Path does not exist: {}", request.path.display()));
+        if config.enable_config_audit {
+            scanners.push(Box::new(ConfigAuditScanner::new(&config)));
         }
 
-        // Run scans based on type and configuration
-        match request.scan_type {
-            ScanType::Full => {
-                files_scanned += self.run_all_scanners(&request, &mut report).await?;
-            }
-            ScanType::Quick => {
-                files_scanned += self.run_quick_scan(&request, &mut report).await?;
-            }
-            ScanType::CodeOnly => {
-                files_scanned += self.run_code_scanners(&request, &mut report).await?;
-            }
-            ScanType::DependenciesOnly => {
-                files_scanned += self.run_dependency_scanners(&request, &mut report).await?;
+        let rules_path = config.custom_rules_path();
+        if config.enable_custom_rules && rules_path.exists() {
+            match CustomRuleScanner::load(&rules_path, &config) {
+                Ok(scanner) => {
+                    info!("Loaded {} custom security rules", scanner.rule_count());
+                    scanners.push(Box::new(scanner));
+                }
+                Err(e) => warn!("Failed to load custom security rules: {:#}", e),
             }
         }
 
-        let duration_ms = start_time.elapsed().as_millis() as u64;
-        report.finalize(files_scanned, duration_ms);
+        Ok(Self::with_scanners(config, scanners))
+    }
 
-        info!(
-            "Security scan completed in {}ms. Found {} vulnerabilities.",
-            duration_ms, report.summary.total_vulnerabilities
-        );
+    /// A scanner running exactly `scanners`, whatever `config` enables.
+    pub fn with_scanners(config: SecurityConfig, scanners: Vec<Box<dyn Scanner>>) -> Self {
+        Self { config, scanners }
+    }
 
-        Ok(report)
+    pub async fn scan(&self, request: SecurityScanRequest) -> Result<SecurityReport> {
+        self.scan_with_progress(request, None).await
     }
 
-    async fn run_all_scanners(
+    /// `scan`, sending the findings of each chunk of the target, and of each
+    /// scanner that scans it whole, to `progress` as they come; the report
+    /// has them all.
+    pub async fn scan_with_progress(
         &self,
-        request: &SecurityScanRequest,
-        report: &mut SecurityReport,
-    ) -> Result<usize> {
-        let mut total_files = 0;
-
-        // Run Bandit for Python files
-        if let Some(bandit) = &self.bandit_scanner {
-            match timeout(
-                Duration::from_secs(self.config.scan_timeout_seconds),
-                bandit.scan(&request.path),
-            ).await {
-                Ok(Ok(result)) => {
-                    match result {
-                        ScanResult::Success(vulns) => {
-                            for vuln in vulns {
-                                report.add_vulnerability(vuln);
-                            }
-                            total_files += 1;
-                        }
-                        ScanResult::Error(e) => {
-                            warn!("Bandit scan error: {}", e);
-                        }
-                        ScanResult::Timeout => {
-                            warn!("Bandit scan timed out");
-                        }
-                    }
-                }
-                Ok(Err(e)) => warn!("Bandit scan failed: {}", e),
-                Err(_) => warn!("Bandit scan timed out"),
-            }
-        }
+        request: SecurityScanRequest,
+        progress: Option<crossbeam_channel::Sender<ScanProgress>>,
+    ) -> Result<SecurityReport> {
+        let start_time = Instant::now();
+        info!("Starting security scan of: {}", request.target);
 
-        // Run Semgrep for multiple languages
-        if let Some(semgrep) = &self.semgrep_scanner {
-            match timeout(
-                Duration::from_secs(self.config.scan_timeout_seconds),
-                semgrep.scan(&request.path),
-            ).await {
-                Ok(Ok(result)) => {
-                    match result {
-                        ScanResult::Success(vulns) => {
-                            for vuln in vulns {
-                                report.add_vulnerability(vuln);
-                            }
-                            total_files += 1;
-                        }
-                        ScanResult::Error(e) => {
-                            warn!("Semgrep scan error: {}", e);
-                        }
-                        ScanResult::Timeout => {
-                            warn!("Semgrep scan timed out");
-                        }
-                    }
-                }
-                Ok(Err(e)) => warn!("Semgrep scan failed: {}", e),
-                Err(_) => warn!("Semgrep scan timed out"),
-            }
-        }
+        let mut report = SecurityReport::new(&request.target, request.scan_type.clone());
+        let mut files_scanned = 0;
+        let mut scanners_run = 0;
 
-        // Run OSV for dependency vulnerabilities
-        if let Some(osv) = &self.osv_scanner {
-            match timeout(
-                Duration::from_secs(self.config.scan_timeout_seconds),
-                osv.scan(&request.path),
-            ).await {
-                Ok(Ok(result)) => {
-                    match result {
-                        ScanResult::Success(vulns) => {
-                            for vuln in vulns {
-                                report.add_vulnerability(vuln);
-                            }
-                            total_files += 1;
-                        }
-                        ScanResult::Error(e) => {
-                            warn!("OSV scan error: {}", e);
-                        }
-                        ScanResult::Timeout => {
-                            warn!("OSV scan timed out");
-                        }
-                    }
-                }
-                Ok(Err(e)) => warn!("OSV scan failed: {}", e),
-                Err(_) => warn!("OSV scan timed out"),
+        match &request.target {
+            // Validate path exists
+            ScanTarget::Path(path) if !path.exists() => {
+                return Err(anyhow!("Path does not exist: {}", path.display()));
+            }
+            ScanTarget::ContainerImage(image) if image.trim().is_empty() => {
+                return Err(anyhow!("No image to scan"));
             }
+            _ => {}
         }
 
-        Ok(total_files)
-    }
+        let root = match request.target.path() {
+            Some(path) if request.honor_ignore_comments => Some(match path.is_dir() {
+                true => path.to_path_buf(),
+                false => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            }),
+            _ => None,
+        };
+        let mut gathered = Gathered {
+            root,
+            progress,
+            kept: Vec::new(),
+            suppressed: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let chunked = match &request.target {
+            ScanTarget::Path(path) if self.scanners.iter().any(|scanner| scanner.scans_chunks()) => {
+                let exclusions = Exclusions::new(path, &self.config.excluded_paths, &request.exclude_patterns)?;
+                Some((chunks::chunk_paths(path, &exclusions)?, exclusions.tool_patterns()))
+            }
+            _ => None,
+        };
+        let scan_timeout = Duration::from_secs(self.config.scan_timeout_seconds);
 
-    async fn run_quick_scan(
-        &self,
-        request: &SecurityScanRequest,
-        report: &mut SecurityReport,
-    ) -> Result<usize> {
-        // Quick scan prioritizes speed - run only essential checks
-        let mut total_files = 0;
-
-        // Run OSV first (fastest, most critical for dependencies)
-        if let Some(osv) = &self.osv_scanner {
-            if let Ok(result) = osv.scan(&request.path).await {
-                if let ScanResult::Success(vulns) = result {
-                    for vuln in vulns {
-                        report.add_vulnerability(vuln);
-                    }
-                    total_files += 1;
+        // Run scans based on type and configuration
+        for scanner in &self.scanners {
+            let name = scanner.name();
+            let covered = matches!(
+                (&request.scan_type, scanner.kind()),
+                (ScanType::Full | ScanType::Quick, _)
+                    | (ScanType::CodeOnly, ScannerKind::Code)
+                    | (ScanType::DependenciesOnly, ScannerKind::Dependencies)
+            );
+
+            if let (Some((path_chunks, excludes)), true) = (&chunked, scanner.scans_chunks()) {
+                let quick = matches!(request.scan_type, ScanType::Quick);
+                let scans: Option<Vec<_>> = path_chunks
+                    .iter()
+                    .map(|chunk| scanner.scan_chunk(chunk, excludes, quick).map(|scan| (chunk.name.clone(), scan)))
+                    .collect();
+                let Some(scans) = scans.filter(|_| covered) else {
+                    continue;
+                };
+                scanners_run += 1;
+
+                let mut succeeded = 0;
+                let total = scans.len();
+                let run = run_chunks(name, scans, &mut gathered, &mut report, &mut succeeded);
+                let scanned = timeout(scan_timeout, run).await;
+                match scanned {
+                    Ok(result) => result?,
+                    Err(_) => report.scanner_error(format!("{} timed out", name)),
+                }
+                if succeeded > 0 || total == 0 {
+                    files_scanned += 1;
                 }
+                continue;
             }
-        }
 
-        // Run basic Semgrep rules
-        if let Some(semgrep) = &self.semgrep_scanner {
-            if let Ok(result) = semgrep.quick_scan(&request.path).await {
-                if let ScanResult::Success(vulns) = result {
-                    for vuln in vulns {
-                        report.add_vulnerability(vuln);
-                    }
-                    total_files += 1;
+            let scan = match (&request.target, &request.scan_type) {
+                (ScanTarget::ContainerImage(image), _) => scanner.scan_image(image.trim()),
+                (ScanTarget::Path(path), ScanType::Quick) => scanner.quick_scan(path),
+                (ScanTarget::Path(path), _) if covered => Some(scanner.scan(path)),
+                _ => None,
+            };
+            let Some(scan) = scan else {
+                continue;
+            };
+            scanners_run += 1;
+
+            match timeout(scan_timeout, scan).await {
+                Ok(Ok(ScanResult::Success(vulns))) => {
+                    gathered.add(name, "", 1, 1, vulns).await?;
+                    files_scanned += 1;
                 }
+                Ok(Ok(ScanResult::Error(e))) => report.scanner_error(format!("{}: {}", name, e)),
+                Ok(Ok(ScanResult::Timeout)) | Err(_) => report.scanner_error(format!("{} timed out", name)),
+                Ok(Err(e)) => report.scanner_error(format!("{}: {}", name, e)),
             }
         }
 
-        Ok(total_files)
-    }
-
-    async fn run_code_scanners(
-        &self,
-        request: &SecurityScanRequest,
-        report: &mut SecurityReport,
-    ) -> Result<usize> {
-        let mut total_files = 0;
-
-        // Run Bandit for Python
-        if let Some(bandit) = &self.bandit_scanner {
-            if let Ok(result) = bandit.scan(&request.path).await {
-                if let ScanResult::Success(vulns) = result {
-                    for vuln in vulns {
-                        report.add_vulnerability(vuln);
-                    }
-                    total_files += 1;
-                }
-            }
+        if matches!(request.target, ScanTarget::ContainerImage(_)) && scanners_run == 0 {
+            let reason = match self.config.enable_trivy {
+                true => trivy::install_hint(),
+                false => "security.enable_trivy is off".to_string(),
+            };
+            return Err(anyhow!("Can't scan container images: {}", reason));
         }
 
-        // Run Semgrep for multiple languages
-        if let Some(semgrep) = &self.semgrep_scanner {
-            if let Ok(result) = semgrep.scan(&request.path).await {
-                if let ScanResult::Success(vulns) = result {
-                    for vuln in vulns {
-                        report.add_vulnerability(vuln);
-                    }
-                    total_files += 1;
-                }
-            }
+        report.suppressed = gathered.suppressed;
+        report.ignore_comment_warnings = gathered.warnings;
+        for vuln in gathered.kept {
+            report.add_vulnerability(vuln);
         }
 
-        Ok(total_files)
-    }
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        report.finalize(files_scanned, duration_ms);
 
-    async fn run_dependency_scanners(
-        &self,
-        request: &SecurityScanRequest,
-        report: &mut SecurityReport,
-    ) -> Result<usize> {
-        let mut total_files = 0;
-
-        // Run OSV for dependency vulnerabilities
-        if let Some(osv) = &self.osv_scanner {
-            if let Ok(result) = osv.scan(&request.path).await {
-                if let ScanResult::Success(vulns) = result {
-                    for vuln in vulns {
-                        report.add_vulnerability(vuln);
-                    }
-                    total_files += 1;
-                }
-            }
-        }
+        info!(
+            "Security scan completed in {}ms. Found {} vulnerabilities.",
+            duration_ms, report.summary.total_vulnerabilities
+        );
 
-        Ok(total_files)
+        Ok(report)
     }
 
     pub fn is_scanner_available(&self, scanner_name: &str) -> bool {
-        match scanner_name {
-            "bandit" => self.bandit_scanner.is_some(),
-            "semgrep" => self.semgrep_scanner.is_some(),
-            "osv" => self.osv_scanner.is_some(),
-            _ => false,
-        }
+        self.scanners.iter().any(|scanner| scanner.name() == scanner_name)
     }
 
     pub fn get_available_scanners(&self) -> Vec<String> {
-        let mut scanners = Vec::new();
-        if self.bandit_scanner.is_some() {
-            scanners.push("bandit".to_string());
-        }
-        if self.semgrep_scanner.is_some() {
-            scanners.push("semgrep".to_string());
-        }
-        if self.osv_scanner.is_some() {
-            scanners.push("osv".to_string());
-        }
-        scanners
+        self.scanners.iter().map(|scanner| scanner.name().to_string()).collect()
     }
 
     pub fn update_config(&mut self, config: SecurityConfig) {
@@ -441,3 +603,83 @@ impl SecurityScanner {
         }
     }
 }
+
+/// The findings of a scan so far, without those ignore comments silence,
+/// which are set aside; each batch is sent on to `progress` as it comes.
+struct Gathered {
+    /// Where the findings' files are, when ignore comments are honored
+    root: Option<PathBuf>,
+    progress: Option<crossbeam_channel::Sender<ScanProgress>>,
+    kept: Vec<Vulnerability>,
+    suppressed: Vec<Vulnerability>,
+    warnings: Vec<String>,
+}
+
+impl Gathered {
+    async fn add(&mut self, scanner: &str, chunk: &str, done: usize, total: usize, found: Vec<Vulnerability>) -> Result<()> {
+        let found = match &self.root {
+            Some(root) => {
+                let root = root.clone();
+                let applied = tokio::task::spawn_blocking(move || ignore_comments::apply(found, &root)).await?;
+                for warning in &applied.warnings {
+                    warn!("Security scan: {}", warning);
+                }
+                self.suppressed.extend(applied.suppressed);
+                self.warnings.extend(applied.warnings);
+                applied.kept
+            }
+            None => found,
+        };
+        if let Some(progress) = &self.progress {
+            let _ = progress.send(ScanProgress {
+                scanner: scanner.to_string(),
+                chunk: chunk.to_string(),
+                done,
+                total,
+                findings: found.clone(),
+            });
+        }
+        self.kept.extend(found);
+        Ok(())
+    }
+}
+
+/// Runs a scanner's chunk `scans`, `chunks::CHUNK_CONCURRENCY` at a time,
+/// gathering each one's findings as it finishes. Failed chunks are reported
+/// and the rest still scanned; `succeeded` counts the others.
+async fn run_chunks(
+    name: &str,
+    scans: Vec<(String, ScanFuture<'static>)>,
+    gathered: &mut Gathered,
+    report: &mut SecurityReport,
+    succeeded: &mut usize,
+) -> Result<()> {
+    let total = scans.len();
+    let mut pending = scans.into_iter();
+    // Dropped on a timeout, which kills the tools still running
+    let mut running = JoinSet::new();
+    let mut done = 0;
+    loop {
+        while running.len() < chunks::CHUNK_CONCURRENCY {
+            let Some((chunk, scan)) = pending.next() else {
+                break;
+            };
+            running.spawn(async move { (chunk, scan.await) });
+        }
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        let (chunk, result) = joined?;
+        done += 1;
+        match result {
+            Ok(ScanResult::Success(vulns)) => {
+                *succeeded += 1;
+                gathered.add(name, &chunk, done, total, vulns).await?;
+            }
+            Ok(ScanResult::Error(e)) => report.scanner_error(format!("{} ({}): {}", name, chunk, e)),
+            Ok(ScanResult::Timeout) => report.scanner_error(format!("{} ({}) timed out", name, chunk)),
+            Err(e) => report.scanner_error(format!("{} ({}): {:#}", name, chunk, e)),
+        }
+    }
+    Ok(())
+}
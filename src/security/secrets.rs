@@ -0,0 +1,102 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Whether log messages go through `redact_secrets`; see `set_redact_logs`.
+static REDACT_LOGS: AtomicBool = AtomicBool::new(true);
+
+/// Where secrets are redacted besides AI prompts, which `ai.redaction`
+/// covers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Redact secrets from log output
+    pub redact_logs: bool,
+    /// Redact secrets from commands written to the audit log
+    pub redact_audit_log: bool,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            redact_logs: true,
+            redact_audit_log: true,
+        }
+    }
+}
+
+/// Patterns for strings that look like credentials. Each pattern either
+/// matches the secret itself, or has a `secret` group for the part to replace
+/// (so `API_KEY=abc` becomes `API_KEY=[REDACTED]`).
+pub const SECRET_PATTERNS: &[(&str, &str)] = &[
+    ("private key", r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----"),
+    ("aws access key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    ("github token", r"\bgh[pousr]_[A-Za-z0-9]{36,}\b"),
+    ("github fine-grained token", r"\bgithub_pat_[A-Za-z0-9_]{22,}\b"),
+    ("slack token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}\b"),
+    ("google api key", r"\bAIza[0-9A-Za-z_\-]{35}\b"),
+    ("openai key", r"\bsk-[A-Za-z0-9_\-]{20,}\b"),
+    ("jwt", r"\beyJ[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+\b"),
+    ("bearer token", r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9_\-\.=]{16,})"),
+    ("url credentials", r"[a-zA-Z][a-zA-Z0-9+.\-]*://[^\s:/@]+:(?P<secret>[^\s@/]+)@"),
+    (
+        "assignment",
+        r#"(?i)\b[A-Z0-9_\-]*(?:password|passwd|pwd|secret|token|api[_\-]?key|access[_\-]?key)[A-Z0-9_\-]*\s*[=:]\s*["']?(?P<secret>[^\s"']{4,})"#,
+    ),
+];
+
+fn secret_regexes() -> &'static [Regex] {
+    static REGEXES: OnceLock<Vec<Regex>> = OnceLock::new();
+    REGEXES.get_or_init(|| {
+        SECRET_PATTERNS
+            .iter()
+            .map(|(name, pattern)| {
+                Regex::new(pattern).unwrap_or_else(|e| panic!("invalid {} pattern: {}", name, e))
+            })
+            .collect()
+    })
+}
+
+/// Replaces anything that looks like a credential with `[REDACTED]`.
+pub fn redact_secrets(text: &str) -> String {
+    let mut redacted = text.to_string();
+
+    for regex in secret_regexes() {
+        if !regex.is_match(&redacted) {
+            continue;
+        }
+        redacted = regex
+            .replace_all(&redacted, |caps: &regex::Captures| match caps.name("secret") {
+                Some(secret) => {
+                    let whole = caps.get(0).unwrap();
+                    let start = secret.start() - whole.start();
+                    let end = secret.end() - whole.start();
+                    let text = whole.as_str();
+                    format!("{}{}{}", &text[..start], REDACTED, &text[end..])
+                }
+                None => REDACTED.to_string(),
+            })
+            .into_owned();
+    }
+
+    redacted
+}
+
+/// Turns log redaction on or off for the whole process.
+pub fn set_redact_logs(enabled: bool) {
+    REDACT_LOGS.store(enabled, Ordering::Relaxed);
+}
+
+/// A log message as it should be written, with secrets redacted unless
+/// log redaction is off.
+pub fn redact_log_message(message: &str) -> Cow<'_, str> {
+    if REDACT_LOGS.load(Ordering::Relaxed) {
+        Cow::Owned(redact_secrets(message))
+    } else {
+        Cow::Borrowed(message)
+    }
+}
@@ -1,4 +1,7 @@
-use super::{ScanResult, Severity, Vulnerability};
+use super::chunks::{self, PathChunk};
+use super::scanner::ScanFuture;
+use super::{Remediation, ScanResult, Severity, Vulnerability};
+use crate::tools::{self, ToolDetector};
 use anyhow::Result;
 use std::path::PathBuf;
 use tokio::process::Command;
@@ -8,94 +11,83 @@ pub struct SemgrepScanner {
 }
 
 impl SemgrepScanner {
+    /// Runs `semgrep` from `PATH`; a scan fails when it isn't installed.
     pub fn new() -> Result<Self> {
-        // For now, assume semgrep is available. In a real implementation,
-        // we'd check if the binary exists
-        Ok(Self {
-            binary_path: PathBuf::from("semgrep"),
-        })
+        let binary_path = ToolDetector::new()
+            .find(tools::SEMGREP.binary)
+            .unwrap_or_else(|| PathBuf::from(tools::SEMGREP.binary));
+        Ok(Self { binary_path })
     }
 
     pub async fn scan(&self, path: &PathBuf) -> Result<ScanResult> {
-        let output = Command::new(&self.binary_path)
-            .args(&[
-                "--config=auto", 
-                "--json", 
-                &path.display().to_string()
-            ])
-            .output()
-            .await?;
-
-        if !output.status.success() {
-            return Ok(ScanResult::Error("Semgrep scan failed".to_string()));
-        }
-
-        let response: serde_json::Value = serde_json::from_slice(&output.stdout)?;
-        let mut vulnerabilities = Vec::new();
-
-        if let Some(results) = response.get("results") {
-            for result in results.as_array().unwrap_or(&vec![]) {
-                let vuln = Vulnerability {
-                    id: result.get("check_id").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    title: result.get("message").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    description: result.get("message").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    severity: map_severity(result.get("severity").map(|v| v.as_str().unwrap_or_default()).unwrap_or("")),
-                    category: "code-quality".to_string(),
-                    file_path: result.get("path").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    line_number: result.get("start").and_then(|v| v.get("line")).and_then(|v| v.as_u64()).map(|v| v as usize),
-                    column_number: result.get("start").and_then(|v| v.get("col")).and_then(|v| v.as_u64()).map(|v| v as usize),
-                    code_snippet: result.get("extra").and_then(|v| v.get("lines")).map(|v| v.as_str().unwrap_or_default().to_string()),
-                    suggested_fix: None,
-                    references: vec![],
-                    scanner: "semgrep".to_string(),
-                };
-                vulnerabilities.push(vuln);
-            }
-        }
-
-        Ok(ScanResult::Success(vulnerabilities))
+        scan_paths(self.binary_path.clone(), vec![path.clone()], Vec::new(), false).await
     }
 
     pub async fn quick_scan(&self, path: &PathBuf) -> Result<ScanResult> {
-        let output = Command::new(&self.binary_path)
-            .args(&[
-                "--config=p/security-audit",
-                "--json",
-                "--severity=HIGH",
-                &path.display().to_string()
-            ])
-            .output()
-            .await?;
+        scan_paths(self.binary_path.clone(), vec![path.clone()], Vec::new(), true).await
+    }
 
-        if !output.status.success() {
-            return Ok(ScanResult::Error("Semgrep quick scan failed".to_string()));
-        }
+    /// `scan`, or `quick_scan` when `quick`, of `chunk` without `excludes`,
+    /// owning what it needs so chunks can run side by side.
+    pub fn scan_chunk(&self, chunk: &PathChunk, excludes: &[String], quick: bool) -> ScanFuture<'static> {
+        Box::pin(scan_paths(self.binary_path.clone(), chunk.paths.clone(), excludes.to_vec(), quick))
+    }
+}
 
-        let response: serde_json::Value = serde_json::from_slice(&output.stdout)?;
-        let mut vulnerabilities = Vec::new();
+async fn scan_paths(binary_path: PathBuf, paths: Vec<PathBuf>, excludes: Vec<String>, quick: bool) -> Result<ScanResult> {
+    let mut command = Command::new(binary_path);
+    match quick {
+        true => command.args(["--config=p/security-audit", "--json", "--severity=HIGH"]),
+        false => command.args(["--config=auto", "--json"]),
+    };
+    for exclude in &excludes {
+        command.arg(format!("--exclude={}", exclude));
+    }
+    command.args(&paths);
 
-        if let Some(results) = response.get("results") {
-            for result in results.as_array().unwrap_or(&vec![]) {
-                let vuln = Vulnerability {
-                    id: result.get("check_id").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    title: result.get("message").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    description: result.get("message").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    severity: map_severity(result.get("severity").map(|v| v.as_str().unwrap_or_default()).unwrap_or("")),
-                    category: "security".to_string(),
-                    file_path: result.get("path").map(|v| v.as_str().unwrap_or_default().to_string()).unwrap_or_default(),
-                    line_number: result.get("start").and_then(|v| v.get("line")).and_then(|v| v.as_u64()).map(|v| v as usize),
-                    column_number: result.get("start").and_then(|v| v.get("col")).and_then(|v| v.as_u64()).map(|v| v as usize),
-                    code_snippet: result.get("extra").and_then(|v| v.get("lines")).map(|v| v.as_str().unwrap_or_default().to_string()),
-                    suggested_fix: None,
-                    references: vec![],
-                    scanner: "semgrep".to_string(),
-                };
-                vulnerabilities.push(vuln);
-            }
-        }
+    let category = if quick { "security" } else { "code-quality" };
+    Ok(match chunks::run_json(command, "semgrep").await? {
+        Ok(response) => ScanResult::Success(parse_results(&response, category)),
+        Err(error) => ScanResult::Error(error),
+    })
+}
 
-        Ok(ScanResult::Success(vulnerabilities))
+/// The findings in semgrep's `--json` output, filed under `category`.
+/// Autofixes come with the offsets they replace.
+pub fn parse_results(response: &serde_json::Value, category: &str) -> Vec<Vulnerability> {
+    let mut vulnerabilities = Vec::new();
+    for result in response.get("results").and_then(|v| v.as_array()).into_iter().flatten() {
+        let text = |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let offset = |key: &str| result.get(key).and_then(|v| v.get("offset")).and_then(|v| v.as_u64());
+        let extra = result.get("extra");
+        // semgrep puts the message and severity under `extra`
+        let field = |key: &str| extra.and_then(|v| v.get(key)).or_else(|| result.get(key));
+        let remediation = match (extra.and_then(|v| v.get("fix")).and_then(|v| v.as_str()), offset("start"), offset("end")) {
+            (Some(fix), Some(start), Some(end)) => Some(Remediation::Replace {
+                start: start as usize,
+                end: end as usize,
+                replacement: fix.to_string(),
+            }),
+            _ => None,
+        };
+        vulnerabilities.push(Vulnerability {
+            id: text(result.get("check_id")),
+            title: text(field("message")),
+            description: text(field("message")),
+            severity: map_severity(field("severity").and_then(|v| v.as_str()).unwrap_or("")),
+            category: category.to_string(),
+            file_path: text(result.get("path")),
+            line_number: result.get("start").and_then(|v| v.get("line")).and_then(|v| v.as_u64()).map(|v| v as usize),
+            column_number: result.get("start").and_then(|v| v.get("col")).and_then(|v| v.as_u64()).map(|v| v as usize),
+            code_snippet: extra.and_then(|v| v.get("lines")).and_then(|v| v.as_str()).map(str::to_string),
+            suggested_fix: remediation.as_ref().map(|_| "Apply semgrep's autofix".to_string()),
+            references: vec![],
+            scanner: "semgrep".to_string(),
+            remediation,
+            suppression: None,
+        });
     }
+    vulnerabilities
 }
 
 fn map_severity(severity: &str) -> Severity {
@@ -0,0 +1,170 @@
+use super::{fixes, Remediation, ScanResult, Severity, Vulnerability};
+use crate::terminal::quick_fix;
+use crate::tools::{self, ToolDetector};
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+const INSTALL_DOCS: &str = "https://aquasecurity.github.io/trivy/latest/getting-started/installation/";
+
+/// How to install trivy here: the package manager command when one of the
+/// installed ones has it, and the install docs.
+pub fn install_hint() -> String {
+    let commands = quick_fix::install_commands("trivy", &quick_fix::local_package_managers());
+    match commands.first() {
+        Some((_, command)) => format!("trivy isn't installed; install it with `{}` or see {}", command, INSTALL_DOCS),
+        None => format!("trivy isn't installed; see {}", INSTALL_DOCS),
+    }
+}
+
+/// Runs Aqua Security's `trivy` on a directory's dependencies, or on a
+/// container image.
+pub struct TrivyScanner {
+    binary_path: PathBuf,
+}
+
+impl TrivyScanner {
+    /// Finds `trivy` on `PATH`, failing with `install_hint` when it isn't.
+    pub fn new() -> Result<Self> {
+        let binary_path = ToolDetector::new().find(tools::TRIVY.binary).ok_or_else(|| anyhow!(install_hint()))?;
+        Ok(Self { binary_path })
+    }
+
+    /// `trivy fs`: the vulnerable packages in the lockfiles under `path`.
+    pub async fn scan_filesystem(&self, path: &Path) -> Result<ScanResult> {
+        let output = self.run(&[OsStr::new("fs"), path.as_os_str()]).await?;
+        Ok(match output {
+            Ok(report) => ScanResult::Success(parse_results(&report, Some(path))),
+            Err(error) => ScanResult::Error(error),
+        })
+    }
+
+    /// `trivy image`: the vulnerable OS and language packages in `image`,
+    /// pulled first when it isn't local.
+    pub async fn scan_image(&self, image: &str) -> Result<ScanResult> {
+        let output = self.run(&[OsStr::new("image"), OsStr::new(image)]).await?;
+        Ok(match output {
+            Ok(report) => ScanResult::Success(parse_results(&report, None)),
+            Err(error) => ScanResult::Error(error),
+        })
+    }
+
+    /// The JSON report of `trivy <args>`, or what trivy said went wrong.
+    async fn run(&self, args: &[&OsStr]) -> Result<Result<Value, String>> {
+        let output = Command::new(&self.binary_path)
+            .args(args)
+            .args(["--scanners", "vuln", "--format", "json", "--quiet"])
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("Failed to run trivy")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("trivy failed");
+            return Ok(Err(error.trim().to_string()));
+        }
+        let report = serde_json::from_slice(&output.stdout).context("trivy printed invalid JSON")?;
+        Ok(Ok(report))
+    }
+}
+
+/// The findings in a trivy `--format json` report. Language packages found
+/// in lockfiles under `root` (in `fs` mode) come with the upgrade that
+/// fixes them.
+pub fn parse_results(report: &Value, root: Option<&Path>) -> Vec<Vulnerability> {
+    let text = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let mut vulnerabilities = Vec::new();
+    for result in report.get("Results").and_then(Value::as_array).into_iter().flatten() {
+        let target = text(result, "Target");
+        let class = text(result, "Class");
+        let ecosystem = ecosystem(&text(result, "Type"));
+        for finding in result.get("Vulnerabilities").and_then(Value::as_array).into_iter().flatten() {
+            let id = text(finding, "VulnerabilityID");
+            let package = text(finding, "PkgName");
+            let installed = text(finding, "InstalledVersion");
+            let fixed_version = fixed_version(&text(finding, "FixedVersion"), &installed);
+
+            let title = match finding.get("Title").and_then(Value::as_str) {
+                Some(title) => format!("{}: {}", id, title),
+                None => format!("{} in {}", id, package),
+            };
+            let suggested_fix = match &fixed_version {
+                Some(fixed) => format!("Upgrade {} from {} to {}", package, installed, fixed),
+                None => format!("No fixed version of {} yet (installed: {})", package, installed),
+            };
+            let mut references: Vec<String> = finding
+                .get("PrimaryURL")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .into_iter()
+                .collect();
+            for reference in finding.get("References").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(url) = reference.as_str().filter(|url| !references.iter().any(|known| known == url)) {
+                    references.push(url.to_string());
+                }
+            }
+
+            let mut vulnerability = Vulnerability::new(
+                title,
+                text(finding, "Description"),
+                map_severity(&text(finding, "Severity")),
+                if class == "os-pkgs" { "container" } else { "dependency" }.to_string(),
+                target.clone(),
+                "trivy".to_string(),
+            )
+            .with_fix(suggested_fix)
+            .with_references(references);
+            vulnerability.id = id;
+            if let (Some(root), Some(ecosystem), "lang-pkgs") = (root, ecosystem, class.as_str()) {
+                vulnerability = vulnerability.with_remediation(Remediation::Upgrade {
+                    ecosystem: ecosystem.to_string(),
+                    package,
+                    version: Some(installed).filter(|version| !version.is_empty()),
+                    fixed_version,
+                    source: Some(root.join(&target)),
+                });
+            }
+            vulnerabilities.push(vulnerability);
+        }
+    }
+    vulnerabilities
+}
+
+/// OSV's name for the ecosystem of a trivy result `Type`, as `Remediation`
+/// uses.
+fn ecosystem(trivy_type: &str) -> Option<&'static str> {
+    match trivy_type {
+        "npm" | "yarn" | "pnpm" | "node-pkg" => Some("npm"),
+        "cargo" | "rustbinary" => Some("crates.io"),
+        "pip" | "pipenv" | "poetry" | "uv" | "python-pkg" => Some("PyPI"),
+        "gomod" | "gobinary" => Some("Go"),
+        "bundler" | "gemspec" => Some("RubyGems"),
+        "composer" => Some("Packagist"),
+        _ => None,
+    }
+}
+
+/// The lowest of trivy's comma-separated fixed versions past `installed`.
+fn fixed_version(fixed: &str, installed: &str) -> Option<String> {
+    fixed
+        .split(',')
+        .map(str::trim)
+        .filter(|fixed| !fixed.is_empty())
+        .filter(|fixed| installed.is_empty() || fixes::compare_versions(fixed, installed).is_gt())
+        .min_by(|a, b| fixes::compare_versions(a, b))
+        .map(str::to_string)
+}
+
+fn map_severity(severity: &str) -> Severity {
+    match severity {
+        "CRITICAL" => Severity::Critical,
+        "HIGH" => Severity::High,
+        "MEDIUM" => Severity::Medium,
+        "LOW" => Severity::Low,
+        _ => Severity::Info,
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Maximum number of alias substitutions for a single command, as a backstop
+/// in case cycle detection is ever bypassed.
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AliasCommand {
+    /// `alias` with no arguments: list all aliases
+    List,
+    /// `alias name`: show a single alias
+    Show(String),
+    /// `alias name=value`
+    Define { name: String, value: String },
+    /// `unalias name`
+    Remove(String),
+}
+
+/// Expands the first token of `command` against `aliases`, repeatedly, so
+/// that aliases may refer to other aliases.
+///
+/// An alias whose value starts with its own name (`ls=ls --color`) stops
+/// expanding there, like bash does. Any other revisit of an alias is a cycle
+/// and returns an error.
+pub fn expand_aliases(command: &str, aliases: &HashMap<String, String>) -> Result<String> {
+    let mut current = command.trim_start().to_string();
+    let mut chain: Vec<String> = Vec::new();
+
+    for _ in 0..MAX_EXPANSION_DEPTH {
+        let (first, rest) = split_first_token(&current);
+        let Some(value) = aliases.get(first) else {
+            return Ok(current);
+        };
+
+        if chain.iter().any(|name| name == first) {
+            return Err(anyhow!(
+                "Alias cycle detected while expanding '{}': {} -> {}",
+                command.trim(),
+                chain.join(" -> "),
+                first
+            ));
+        }
+        chain.push(first.to_string());
+
+        let expanded = format!("{}{}", value.trim(), rest);
+        let self_referencing = split_first_token(value.trim()).0 == first;
+        current = expanded;
+
+        if self_referencing {
+            return Ok(current);
+        }
+    }
+
+    Err(anyhow!(
+        "Alias expansion for '{}' exceeded {} levels",
+        command.trim(),
+        MAX_EXPANSION_DEPTH
+    ))
+}
+
+/// Parses the `alias` / `unalias` builtins. Returns `None` for any other command.
+pub fn parse_alias_command(command: &str) -> Option<Result<AliasCommand>> {
+    let trimmed = command.trim();
+    let (first, rest) = split_first_token(trimmed);
+    let rest = rest.trim();
+
+    match first {
+        "alias" if rest.is_empty() => Some(Ok(AliasCommand::List)),
+        "alias" => match rest.split_once('=') {
+            Some((name, value)) => {
+                let name = name.trim();
+                if !is_valid_alias_name(name) {
+                    return Some(Err(anyhow!("Invalid alias name: '{}'", name)));
+                }
+                let value = strip_quotes(value.trim());
+                if value.is_empty() {
+                    return Some(Err(anyhow!("Alias '{}' needs a value", name)));
+                }
+                Some(Ok(AliasCommand::Define {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                }))
+            }
+            None => Some(Ok(AliasCommand::Show(rest.to_string()))),
+        },
+        "unalias" if rest.is_empty() => Some(Err(anyhow!("usage: unalias <name>"))),
+        "unalias" => Some(Ok(AliasCommand::Remove(rest.to_string()))),
+        _ => None,
+    }
+}
+
+pub fn format_alias(name: &str, value: &str) -> String {
+    format!("alias {}='{}'", name, value)
+}
+
+fn split_first_token(command: &str) -> (&str, &str) {
+    match command.find(char::is_whitespace) {
+        Some(index) => command.split_at(index),
+        None => (command, ""),
+    }
+}
+
+fn is_valid_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn strip_quotes(value: &str) -> &str {
+    for quote in ['\'', '"'] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
@@ -0,0 +1,253 @@
+use crate::security::secrets::redact_secrets;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// `prev_hash` of the first record.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Record every executed command in an append-only log
+    pub enabled: bool,
+    /// Defaults to `audit.jsonl` in the data directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl AuditConfig {
+    pub fn log_path(&self) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| crate::config::data_dir().join("audit.jsonl"))
+    }
+}
+
+/// Who asked for a command to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandOrigin {
+    User,
+    /// Run by an AI tool call, or built from AI output
+    Ai,
+    /// `terminal.startup_commands` or a project's `on_enter`
+    Startup,
+}
+
+/// One executed command, as a line of the audit log. Each record carries the
+/// hash of the one before it, so editing or deleting a line breaks the chain
+/// from there on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub command: String,
+    pub working_directory: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    pub ai_originated: bool,
+    pub prev_hash: String,
+    /// SHA-256 of this record with `hash` empty
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> String {
+        let unsigned = AuditRecord {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let json = serde_json::to_string(&unsigned).unwrap_or_default();
+        Sha256::digest(json.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// What to record about a finished command.
+pub struct AuditEntry {
+    pub command: String,
+    pub working_directory: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    pub origin: CommandOrigin,
+}
+
+/// Appends `AuditRecord`s as JSON lines. Records are only ever appended.
+pub struct AuditLog {
+    path: PathBuf,
+    /// (next seq, hash of the last record)
+    chain: Mutex<(u64, String)>,
+    /// Replace secrets in commands with `[REDACTED]` before writing them
+    redact_secrets: bool,
+}
+
+impl AuditLog {
+    /// Opens the log at `path`, continuing its hash chain.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let mut chain = (0, GENESIS_HASH.to_string());
+        if path.exists() {
+            if let Some(last) = read_records(&path)?.pop() {
+                chain = (last.seq + 1, last.hash);
+            }
+        } else if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        Ok(Self {
+            path,
+            chain: Mutex::new(chain),
+            redact_secrets: false,
+        })
+    }
+
+    /// Redacts secrets from recorded commands. The hash covers the redacted
+    /// command, so the chain still verifies.
+    pub fn with_secret_redaction(mut self, redact_secrets: bool) -> Self {
+        self.redact_secrets = redact_secrets;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, entry: AuditEntry) -> Result<()> {
+        let mut chain = self.chain.lock().unwrap();
+        let mut record = AuditRecord {
+            seq: chain.0,
+            timestamp: Utc::now(),
+            command: if self.redact_secrets { redact_secrets(&entry.command) } else { entry.command },
+            working_directory: entry.working_directory,
+            exit_code: entry.exit_code,
+            duration_ms: entry.duration_ms,
+            ai_originated: entry.origin == CommandOrigin::Ai,
+            prev_hash: chain.1.clone(),
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+
+        let mut file = open_append(&self.path)
+            .with_context(|| format!("Failed to open audit log {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        *chain = (record.seq + 1, record.hash);
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn open_append(path: &Path) -> std::io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new().create(true).append(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+pub fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    let file = File::open(path).with_context(|| format!("Failed to open audit log {}", path.display()))?;
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: not an audit record", path.display(), index + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Checks the hash chain. The error names the first record that was
+/// changed, or that follows a removed one.
+pub fn verify(records: &[AuditRecord]) -> Result<()> {
+    let mut prev_hash = GENESIS_HASH;
+    for (expected_seq, record) in (0u64..).zip(records) {
+        if record.seq != expected_seq || record.prev_hash != prev_hash {
+            bail!("Audit log chain is broken at record {}: a record before it was removed or changed", record.seq);
+        }
+        if record.compute_hash() != record.hash {
+            bail!("Audit record {} was modified", record.seq);
+        }
+        prev_hash = &record.hash;
+    }
+    Ok(())
+}
+
+/// Which records to return from the audit log.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// Substring of the command, case-insensitive
+    pub text: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub working_directory: Option<String>,
+    pub ai_originated: Option<bool>,
+    /// `Some(true)` for failed commands only, `Some(false)` for successful
+    pub failed: Option<bool>,
+}
+
+impl AuditFilter {
+    /// Parses a query like `ai failed since:2024-05-01 cwd:~/src docker`:
+    /// `ai`/`user`, `failed`/`ok`, `since:`/`until:` dates, `cwd:` prefix,
+    /// and any other words as text the command must contain.
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut filter = Self::default();
+        let mut text = Vec::new();
+        for word in query.split_whitespace() {
+            match word.split_once(':') {
+                Some(("since", date)) => filter.since = Some(parse_date(date)?),
+                Some(("until", date)) => filter.until = Some(parse_date(date)? + chrono::Duration::days(1)),
+                Some(("cwd", dir)) => filter.working_directory = Some(dir.to_string()),
+                _ => match word {
+                    "ai" => filter.ai_originated = Some(true),
+                    "user" => filter.ai_originated = Some(false),
+                    "failed" => filter.failed = Some(true),
+                    "ok" => filter.failed = Some(false),
+                    _ => text.push(word),
+                },
+            }
+        }
+        if !text.is_empty() {
+            filter.text = Some(text.join(" ").to_lowercase());
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        let failed = record.exit_code != Some(0);
+        self.text
+            .as_ref()
+            .is_none_or(|text| record.command.to_lowercase().contains(text))
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+            && self
+                .working_directory
+                .as_ref()
+                .is_none_or(|dir| record.working_directory.starts_with(&expand_home(dir)))
+            && self.ai_originated.is_none_or(|ai| record.ai_originated == ai)
+            && self.failed.is_none_or(|wanted| failed == wanted)
+    }
+}
+
+fn parse_date(date: &str) -> Result<DateTime<Utc>> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date \"{}\", expected YYYY-MM-DD", date))
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+fn expand_home(dir: &str) -> String {
+    match (dir.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}{}", home.display(), rest),
+        _ => dir.to_string(),
+    }
+}
@@ -3,6 +3,32 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// Well-known keys of `Block::metadata`. Other keys are free-form, e.g.
+/// annotations added by the UI.
+pub mod metadata_keys {
+    pub const COMMAND: &str = "command";
+    pub const WORKING_DIRECTORY: &str = "working_directory";
+    pub const SHELL: &str = "shell";
+    pub const USER: &str = "user";
+    pub const HOSTNAME: &str = "hostname";
+    pub const EXIT_CODE: &str = "exit_code";
+    pub const DURATION: &str = "duration";
+    pub const AI_EXPLANATION: &str = "ai_explanation";
+    /// "startup" for commands run when the session started or entered a project
+    pub const ORIGIN: &str = "origin";
+    /// The id of the pipeline a command ran as a step of
+    pub const PIPELINE: &str = "pipeline";
+    /// The highest CPU of a command and its processes, sampled while it ran
+    pub const PEAK_CPU: &str = "peak_cpu";
+    pub const PEAK_MEMORY: &str = "peak_memory";
+    /// An estimate from the samples
+    pub const CPU_TIME: &str = "cpu_time";
+    /// The time a command was given to run, e.g. `30s`; see `timeout`
+    pub const TIMEOUT: &str = "timeout";
+    /// Set, to the timeout, when the command was killed for running past it
+    pub const TIMED_OUT: &str = "timed_out";
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BlockType {
     Command,
@@ -72,12 +98,22 @@ impl Block {
         self.metadata.get(key)
     }
 
+    /// Records where the block was produced: shell, working directory, user
+    /// and hostname.
+    pub fn set_environment_metadata(&mut self, shell: &str, working_directory: &str) {
+        self.metadata.extend(environment_metadata(shell, working_directory));
+    }
+
     pub fn set_execution_time(&mut self, duration_ms: u64) {
         self.execution_time = Some(duration_ms);
+        if let Some(duration) = self.formatted_execution_time() {
+            self.set_metadata(metadata_keys::DURATION.to_string(), duration);
+        }
     }
 
     pub fn set_exit_code(&mut self, code: i32) {
         self.exit_code = Some(code);
+        self.set_metadata(metadata_keys::EXIT_CODE.to_string(), code.to_string());
     }
 
     pub fn toggle_collapsed(&mut self) {
@@ -99,18 +135,46 @@ impl Block {
     }
 
     pub fn formatted_execution_time(&self) -> Option<String> {
-        self.execution_time.map(|ms| {
-            if ms < 1000 {
-                format!("{}ms", ms)
-            } else if ms < 60000 {
-                format!("{:.1}s", ms as f64 / 1000.0)
-            } else {
-                format!("{}m {:02}s", ms / 60000, (ms % 60000) / 1000)
-            }
-        })
+        self.execution_time.map(format_duration)
+    }
+}
+
+pub fn format_duration(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else if ms < 60000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", ms / 60000, (ms % 60000) / 1000)
     }
 }
 
+/// Metadata describing the environment a command runs in.
+pub fn environment_metadata(shell: &str, working_directory: &str) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    metadata.insert(metadata_keys::SHELL.to_string(), shell.to_string());
+    metadata.insert(metadata_keys::WORKING_DIRECTORY.to_string(), working_directory.to_string());
+    if let Some(user) = ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+    {
+        metadata.insert(metadata_keys::USER.to_string(), user);
+    }
+    if let Some(hostname) = hostname() {
+        metadata.insert(metadata_keys::HOSTNAME.to_string(), hostname);
+    }
+    metadata
+}
+
+fn hostname() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
 #[derive(Debug, Clone)]
 pub struct CommandBlock {
     pub command_block: Block,
@@ -121,9 +185,12 @@ pub struct CommandBlock {
 }
 
 impl CommandBlock {
-    pub fn new(command: String, working_directory: String) -> Self {
+    pub fn new(command: String, working_directory: String, shell: &str) -> Self {
+        let mut command_block = Block::command(command.clone());
+        command_block.set_metadata(metadata_keys::COMMAND.to_string(), command);
+        command_block.set_environment_metadata(shell, &working_directory);
         Self {
-            command_block: Block::command(command),
+            command_block,
             output_blocks: Vec::new(),
             start_time: Utc::now(),
             end_time: None,
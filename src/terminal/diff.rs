@@ -0,0 +1,235 @@
+use crate::git::diff::DiffLineKind;
+use anyhow::{bail, Result};
+use similar::{Algorithm, ChangeTag, DiffTag, TextDiff};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Largest output compared. Bigger ones take too long to diff and to show.
+pub const MAX_COMPARE_BYTES: usize = 1024 * 1024;
+
+/// Unchanged lines shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// After this long the diff settles for a coarser result.
+const DIFF_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Part of a line, `changed` when it differs from the line it was paired with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    pub segments: Vec<Segment>,
+}
+
+impl OutputLine {
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|segment| segment.text.as_str()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputHunk {
+    /// The `@@ -1,4 +1,5 @@` line
+    pub header: String,
+    pub lines: Vec<OutputLine>,
+}
+
+/// The differences between two commands' outputs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputDiff {
+    pub hunks: Vec<OutputHunk>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl OutputDiff {
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// The diff as a unified patch between files named `old_label` and
+    /// `new_label`.
+    pub fn to_patch(&self, old_label: &str, new_label: &str) -> String {
+        let mut patch = format!("--- {}\n+++ {}\n", old_label, new_label);
+        for hunk in &self.hunks {
+            patch.push_str(&hunk.header);
+            patch.push('\n');
+            for line in &hunk.lines {
+                patch.push(match line.kind {
+                    DiffLineKind::Context => ' ',
+                    DiffLineKind::Added => '+',
+                    DiffLineKind::Removed => '-',
+                });
+                patch.push_str(&line.text());
+                patch.push('\n');
+            }
+        }
+        patch
+    }
+}
+
+/// Diffs two outputs line by line, with the changed words of changed lines
+/// marked. With `ignore_whitespace`, lines differing only in spacing count
+/// as unchanged. Outputs over `MAX_COMPARE_BYTES` are refused.
+pub fn diff_outputs(old: &str, new: &str, ignore_whitespace: bool) -> Result<OutputDiff> {
+    for (side, text) in [("earlier", old), ("later", new)] {
+        if text.len() > MAX_COMPARE_BYTES {
+            bail!(
+                "The {} output is {}; only outputs up to {} can be compared",
+                side,
+                format_size(text.len()),
+                format_size(MAX_COMPARE_BYTES)
+            );
+        }
+    }
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let key = |line: &&str| match ignore_whitespace {
+        true => line.split_whitespace().collect::<Vec<_>>().join(" "),
+        false => line.to_string(),
+    };
+    let old_keys: Vec<String> = old_lines.iter().map(key).collect();
+    let new_keys: Vec<String> = new_lines.iter().map(key).collect();
+    let deadline = Instant::now() + DIFF_TIMEOUT;
+    let ops = similar::capture_diff_slices_deadline(Algorithm::Myers, &old_keys, &new_keys, Some(deadline));
+
+    let mut diff = OutputDiff::default();
+    for group in similar::group_diff_ops(ops, CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let header = format!(
+            "@@ -{} +{} @@",
+            hunk_range(first.old_range().start..last.old_range().end),
+            hunk_range(first.new_range().start..last.new_range().end)
+        );
+        let mut lines = Vec::new();
+        for op in &group {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            let removed = |index: usize, segments| OutputLine {
+                kind: DiffLineKind::Removed,
+                old_line: Some(index as u32 + 1),
+                new_line: None,
+                segments,
+            };
+            let added = |index: usize, segments| OutputLine {
+                kind: DiffLineKind::Added,
+                old_line: None,
+                new_line: Some(index as u32 + 1),
+                segments,
+            };
+            match tag {
+                DiffTag::Equal => {
+                    lines.extend(old_range.zip(new_range).map(|(old_index, new_index)| OutputLine {
+                        kind: DiffLineKind::Context,
+                        old_line: Some(old_index as u32 + 1),
+                        new_line: Some(new_index as u32 + 1),
+                        segments: whole(new_lines[new_index]),
+                    }));
+                }
+                DiffTag::Delete => lines.extend(old_range.map(|index| removed(index, whole(old_lines[index])))),
+                DiffTag::Insert => lines.extend(new_range.map(|index| added(index, whole(new_lines[index])))),
+                DiffTag::Replace => {
+                    // Lines are paired in order to mark what changed within them
+                    let mut old_segments = Vec::new();
+                    let mut new_segments = Vec::new();
+                    for (offset, old_index) in old_range.clone().enumerate() {
+                        let old_line = old_lines[old_index];
+                        match new_range.clone().nth(offset) {
+                            Some(new_index) => {
+                                let (old_changes, new_changes) = inline_changes(old_line, new_lines[new_index]);
+                                old_segments.push(old_changes);
+                                new_segments.push(new_changes);
+                            }
+                            None => old_segments.push(whole(old_line)),
+                        }
+                    }
+                    for new_index in new_range.clone().skip(old_segments.len()) {
+                        new_segments.push(whole(new_lines[new_index]));
+                    }
+                    lines.extend(old_range.zip(old_segments).map(|(index, segments)| removed(index, segments)));
+                    lines.extend(new_range.zip(new_segments).map(|(index, segments)| added(index, segments)));
+                }
+            }
+        }
+        diff.added += lines.iter().filter(|line| line.kind == DiffLineKind::Added).count();
+        diff.removed += lines.iter().filter(|line| line.kind == DiffLineKind::Removed).count();
+        diff.hunks.push(OutputHunk { header, lines });
+    }
+    Ok(diff)
+}
+
+/// A range of lines as a hunk header gives it: `start,count`, with the
+/// count left out when it's 1.
+fn hunk_range(range: Range<usize>) -> String {
+    match range.len() {
+        // An empty range names the line before it
+        0 => format!("{},0", range.start),
+        1 => (range.start + 1).to_string(),
+        len => format!("{},{}", range.start + 1, len),
+    }
+}
+
+fn whole(line: &str) -> Vec<Segment> {
+    vec![Segment {
+        text: line.to_string(),
+        changed: false,
+    }]
+}
+
+/// The segments of a changed line and its replacement, with the words that
+/// differ marked. Lines with nothing in common are left unmarked, since
+/// marking all of them says nothing.
+fn inline_changes(old: &str, new: &str) -> (Vec<Segment>, Vec<Segment>) {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .timeout(DIFF_TIMEOUT)
+        .diff_words(old, new);
+    let mut old_segments = Vec::new();
+    let mut new_segments = Vec::new();
+    let mut shared = false;
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        match change.tag() {
+            ChangeTag::Equal => {
+                shared |= !value.trim().is_empty();
+                push_segment(&mut old_segments, value, false);
+                push_segment(&mut new_segments, value, false);
+            }
+            ChangeTag::Delete => push_segment(&mut old_segments, value, true),
+            ChangeTag::Insert => push_segment(&mut new_segments, value, true),
+        }
+    }
+    match shared {
+        true => (old_segments, new_segments),
+        false => (whole(old), whole(new)),
+    }
+}
+
+/// Appends `text`, merging it into the last segment when that is marked
+/// the same way.
+fn push_segment(segments: &mut Vec<Segment>, text: &str, changed: bool) {
+    match segments.last_mut() {
+        Some(last) if last.changed == changed => last.text.push_str(text),
+        _ => segments.push(Segment {
+            text: text.to_string(),
+            changed,
+        }),
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        bytes if bytes >= 1024 * 1024 => format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)),
+        bytes if bytes >= 1024 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        bytes => format!("{} bytes", bytes),
+    }
+}
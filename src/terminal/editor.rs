@@ -0,0 +1,227 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Editors that need a terminal to draw in.
+const TERMINAL_EDITORS: &[&str] = &[
+    "vi", "vim", "nvim", "nano", "pico", "emacs", "micro", "hx", "helix", "kak", "joe", "ne", "mg", "ed",
+];
+
+/// Opens files in the user's editor: `terminal.editor_command` if set, then
+/// `$VISUAL`/`$EDITOR`, then the platform's default.
+pub struct EditorIntegration {
+    configured: Option<String>,
+}
+
+/// Where the editor command came from, for error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorSource {
+    Config,
+    Env(&'static str),
+    PlatformDefault,
+}
+
+impl fmt::Display for EditorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config => write!(f, "terminal.editor_command"),
+            Self::Env(name) => write!(f, "${}", name),
+            Self::PlatformDefault => write!(f, "the platform default"),
+        }
+    }
+}
+
+impl EditorIntegration {
+    pub fn new(editor_command: Option<String>) -> Self {
+        Self {
+            configured: editor_command.filter(|command| !command.trim().is_empty()),
+        }
+    }
+
+    /// The editor command template and where it came from.
+    pub fn command(&self) -> (String, EditorSource) {
+        if let Some(command) = &self.configured {
+            return (command.clone(), EditorSource::Config);
+        }
+        for name in ["VISUAL", "EDITOR"] {
+            if let Ok(command) = std::env::var(name) {
+                if !command.trim().is_empty() {
+                    return (command, EditorSource::Env(name));
+                }
+            }
+        }
+        (platform_default().to_string(), EditorSource::PlatformDefault)
+    }
+
+    /// Opens `path` at `line`/`column` in a detached process, so the editor
+    /// stays open when ANTRAFT exits.
+    pub fn open(&self, path: &Path, line: Option<u32>, column: Option<u32>) -> Result<()> {
+        let (template, source) = self.command();
+        let args = expand_command(&template, path, line, column)
+            .with_context(|| format!("Invalid editor command \"{}\" (from {})", template, source))?;
+        let program = &args[0];
+
+        if is_terminal_editor(program) {
+            bail!(
+                "{} (from {}) runs in a terminal, and ANTRAFT can't host interactive programs yet. \
+                 Set `terminal.editor_command` to a graphical editor, e.g. \"code --goto {{file}}:{{line}}:{{col}}\"",
+                program,
+                source
+            );
+        }
+
+        let mut command = Command::new(program);
+        command.args(&args[1..]);
+        spawn_detached(&mut command)
+            .with_context(|| format!("Couldn't start editor `{}` (from {})", program, source))
+    }
+}
+
+/// Splits an editor command template into arguments and fills in `{file}`,
+/// `{line}` and `{col}`. Placeholders are substituted after splitting, so a
+/// path with spaces stays one argument. Templates without `{file}` get the
+/// file appended in the way the editor understands, like `+12 file` for
+/// vim-style editors.
+pub fn expand_command(template: &str, path: &Path, line: Option<u32>, column: Option<u32>) -> Result<Vec<String>> {
+    let words = split_words(template)?;
+    let Some(program) = words.first() else {
+        bail!("the command is empty");
+    };
+
+    let file = path.to_string_lossy();
+    let line_text = line.unwrap_or(1).to_string();
+    let column_text = column.unwrap_or(1).to_string();
+    let substitute = |word: &str| {
+        word.replace("{file}", &file)
+            .replace("{line}", &line_text)
+            .replace("{col}", &column_text)
+    };
+
+    if words.iter().any(|word| word.contains("{file}")) {
+        return Ok(words.iter().map(|word| substitute(word)).collect());
+    }
+
+    let mut args = words.clone();
+    let name = program_name(program);
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            args.push("--goto".to_string());
+            args.push(substitute("{file}:{line}:{col}"));
+        }
+        "subl" | "zed" | "hx" | "helix" => args.push(substitute("{file}:{line}:{col}")),
+        "idea" | "pycharm" | "webstorm" | "clion" | "goland" | "rustrover" => {
+            args.push("--line".to_string());
+            args.push(line_text.clone());
+            args.push(file.to_string());
+        }
+        _ if line.is_some() && accepts_plus_line(&name) => {
+            args.push(format!("+{}", line_text));
+            args.push(file.to_string());
+        }
+        _ => args.push(file.to_string()),
+    }
+    Ok(args)
+}
+
+/// Whether `program` needs a terminal to run.
+pub fn is_terminal_editor(program: &str) -> bool {
+    TERMINAL_EDITORS.contains(&program_name(program).as_str())
+}
+
+/// Starts `command` without tying it to ANTRAFT: no inherited stdio and its
+/// own process group, so it outlives the app.
+pub fn spawn_detached(command: &mut Command) -> Result<()> {
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    let mut child = command.spawn().map_err(|e| anyhow!(e))?;
+    // Reap it when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+fn platform_default() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open -t {file}"
+    } else if cfg!(windows) {
+        "notepad {file}"
+    } else {
+        "xdg-open {file}"
+    }
+}
+
+/// `/usr/bin/nvim` -> `nvim`, `C:\...\Code.exe` -> `code`
+fn program_name(program: &str) -> String {
+    Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn accepts_plus_line(name: &str) -> bool {
+    TERMINAL_EDITORS.contains(&name) || matches!(name, "gvim" | "mvim" | "gedit" | "kate" | "emacsclient")
+}
+
+/// Splits on whitespace, honouring single and double quotes. A backslash
+/// only escapes a quote, whitespace or another backslash, so Windows paths
+/// can be written as they are.
+fn split_words(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', q) if q != Some('\'') => match chars.peek() {
+                Some(&next) if next == '"' || next == '\'' || next == '\\' || next.is_whitespace() => {
+                    word.push(next);
+                    chars.next();
+                    in_word = true;
+                }
+                _ => {
+                    word.push(c);
+                    in_word = true;
+                }
+            },
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (c, _) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        bail!("unclosed {} quote", q);
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
@@ -1,41 +1,122 @@
+use super::alias::{self, AliasCommand};
+use super::audit::{AuditEntry, AuditLog, CommandOrigin};
+use super::editor::EditorIntegration;
+use super::block::metadata_keys;
+use super::pipeline::Pipeline;
+use super::project;
+use super::prompts;
+use super::remote::{self, RemoteInfo};
+use super::shell::{self, ShellKind};
+use super::pty::VteProcessor;
+use super::timeout;
 use super::{
     Block, CommandBlock, PtyManager, TerminalConfig, TerminalEvent, TerminalEventSender,
     TerminalSession,
 };
 use anyhow::{anyhow, Result};
-use log::{debug, error, info};
-use std::collections::HashMap;
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+/// Output of a command run with `TerminalEngine::run_sandboxed`.
+#[derive(Debug, Clone)]
+pub struct SandboxedOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+/// Environment variables passed through to sandboxed commands.
+const SANDBOX_ENV: &[&str] = &["PATH", "HOME", "USER", "LANG", "LC_ALL", "TERM", "SYSTEMROOT"];
+
+/// Commands `handle_builtin_command` runs itself.
+pub const BUILTIN_COMMANDS: &[&str] = &["alias", "unalias", "clear", "exit", "quit", "cd", "open", "pwd"];
+
+/// Stdin of the running commands, by block id, for answering prompts.
+type StdinHandles = Arc<Mutex<HashMap<Uuid, ChildStdin>>>;
+
+/// The commands whose processes are still running, by block id.
+type RunningChildren = Arc<Mutex<HashMap<Uuid, RunningChild>>>;
+
+struct RunningChild {
+    command: String,
+    pid: Option<u32>,
+    /// Kills the command; taken when sent
+    kill: Option<oneshot::Sender<()>>,
+}
+
+/// A command still running, as listed before shutting down.
+#[derive(Debug, Clone)]
+pub struct RunningCommand {
+    pub id: Uuid,
+    pub command: String,
+    pub pid: Option<u32>,
+}
+
 pub struct TerminalEngine {
-    config: TerminalConfig,
+    config: std::sync::RwLock<TerminalConfig>,
     sessions: Arc<RwLock<HashMap<Uuid, TerminalSession>>>,
     active_session_id: Arc<RwLock<Option<Uuid>>>,
     event_sender: TerminalEventSender,
     pty_manager: Arc<PtyManager>,
     is_running: Arc<AtomicBool>,
+    aliases: Arc<RwLock<HashMap<String, String>>>,
+    audit_log: Option<Arc<AuditLog>>,
+    stdin_handles: StdinHandles,
+    children: RunningChildren,
 }
 
 impl TerminalEngine {
     pub fn new(config: TerminalConfig, event_sender: TerminalEventSender) -> Result<Self> {
         let pty_manager = Arc::new(PtyManager::new()?);
+        let aliases = Arc::new(RwLock::new(config.aliases.clone()));
 
         Ok(Self {
-            config,
+            config: std::sync::RwLock::new(config),
             sessions: Arc::new(RwLock::new(HashMap::new())),
             active_session_id: Arc::new(RwLock::new(None)),
             event_sender,
             pty_manager,
             is_running: Arc::new(AtomicBool::new(true)),
+            aliases,
+            audit_log: None,
+            stdin_handles: Arc::new(Mutex::new(HashMap::new())),
+            children: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 
+    /// Applies changed settings to the commands started from now on, and
+    /// replaces the aliases with the config's.
+    pub async fn update_config(&self, config: TerminalConfig) {
+        *self.aliases.write().await = config.aliases.clone();
+        *self.config.write().unwrap() = config;
+    }
+
+    fn config(&self) -> TerminalConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Records every command this engine runs in `audit_log`.
+    pub fn with_audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    fn audit(&self, entry: AuditEntry) {
+        if let Some(audit_log) = &self.audit_log {
+            record_audit_entry(audit_log, entry);
+        }
+    }
+
     pub async fn create_session(&self) -> Result<Uuid> {
         let session = TerminalSession::new();
         let session_id = session.id;
@@ -78,26 +159,240 @@ impl TerminalEngine {
         }
     }
 
-    pub async fn execute_command(&self, command: String) -> Result<Uuid> {
-        let session_id = match *self.active_session_id.read().await {
-            Some(id) => id,
-            None => self.create_session().await?,
+    /// Starts a session in `directory` and makes it the active one; the
+    /// others stay where they are. Returns the session and its directory.
+    pub async fn open_session_in(&self, directory: &Path) -> Result<(Uuid, String)> {
+        let session_id = self.create_session().await?;
+        self.switch_session(session_id).await?;
+        let directory = self.change_directory(directory).await?;
+        Ok((session_id, directory))
+    }
+
+    pub async fn execute_command(&self, input: String) -> Result<Uuid> {
+        self.execute_command_from(input, CommandOrigin::User).await
+    }
+
+    /// Like `execute_command`, noting in the audit log who asked for it.
+    pub async fn execute_command_from(&self, input: String, origin: CommandOrigin) -> Result<Uuid> {
+        self.start_command(input, origin, None, None).await.map(|(command_id, _)| command_id)
+    }
+
+    /// Like `execute_command_from`, but runs in `working_directory` rather
+    /// than the active session's, which stays where it is. Remote sessions
+    /// run in their own directory regardless.
+    pub async fn execute_command_in(
+        &self,
+        input: String,
+        working_directory: String,
+        origin: CommandOrigin,
+    ) -> Result<Uuid> {
+        self.start_command(input, origin, Some(working_directory), None)
+            .await
+            .map(|(command_id, _)| command_id)
+    }
+
+    /// Runs startup commands one after another, each once the one before
+    /// has finished, whether or not it succeeded. Their blocks are marked
+    /// with `metadata_keys::ORIGIN`.
+    pub async fn run_init_commands(&self, commands: Vec<String>) {
+        for command in commands {
+            let mut block = match self.handle_builtin_command(&command).await {
+                Some(Ok(block)) => block,
+                Some(Err(e)) => Block::error(e.to_string()),
+                None => {
+                    match self.start_command(command.clone(), CommandOrigin::Startup, None, None).await {
+                        Ok((_, finished)) => {
+                            let _ = finished.await;
+                        }
+                        Err(e) => error!("Startup command '{}' failed: {}", command, e),
+                    }
+                    continue;
+                }
+            };
+            let working_directory = self.current_directory().await;
+            block.set_metadata(metadata_keys::COMMAND.to_string(), command);
+            block.set_metadata(metadata_keys::ORIGIN.to_string(), "startup".to_string());
+            block.set_environment_metadata(&self.config().shell, &working_directory);
+            let _ = self.event_sender.send(TerminalEvent::NewBlock { block });
+        }
+    }
+
+    /// Runs `commands` one after another in the active session, as one
+    /// pipeline whose steps report their status with
+    /// `TerminalEvent::PipelineUpdated`. Each step still gets its own block,
+    /// marked with `metadata_keys::PIPELINE`, and builtins like `cd` move the
+    /// session for the steps after them. With `stop_on_error`, the steps
+    /// after a failing one are skipped. Returns the pipeline once it's done.
+    pub async fn run_sequence(&self, commands: Vec<String>, stop_on_error: bool) -> Result<Pipeline> {
+        if commands.is_empty() {
+            return Err(anyhow!("No commands to run"));
+        }
+        let mut pipeline = Pipeline::new(commands, stop_on_error);
+        let _ = self.event_sender.send(TerminalEvent::PipelineUpdated {
+            pipeline: pipeline.clone(),
+        });
+
+        while let Some(index) = pipeline.next_step() {
+            // Shutting down kills the running step; the rest shouldn't start
+            if !self.is_running.load(Ordering::Relaxed) {
+                pipeline.skip_remaining();
+                break;
+            }
+            let command = pipeline.steps[index].command.clone();
+            // Builtins, and commands that couldn't be started, are done
+            // with a block of their own
+            let started = match self.handle_builtin_command(&command).await {
+                Some(Ok(block)) => Err((block, 0)),
+                Some(Err(e)) => Err((Block::error(e.to_string()), 1)),
+                None => self
+                    .start_command(command.clone(), CommandOrigin::User, None, Some(pipeline.id))
+                    .await
+                    .map_err(|e| (Block::error(e.to_string()), -1)),
+            };
+            let exit_code = match started {
+                Ok((command_id, finished)) => {
+                    pipeline.start_step(index, command_id);
+                    let _ = self.event_sender.send(TerminalEvent::PipelineUpdated {
+                        pipeline: pipeline.clone(),
+                    });
+                    finished.await.unwrap_or(-1)
+                }
+                Err((mut block, exit_code)) => {
+                    let working_directory = self.current_directory().await;
+                    block.set_metadata(metadata_keys::COMMAND.to_string(), command);
+                    block.set_metadata(metadata_keys::PIPELINE.to_string(), pipeline.id.to_string());
+                    block.set_environment_metadata(&self.config().shell, &working_directory);
+                    pipeline.start_step(index, block.id);
+                    let _ = self.event_sender.send(TerminalEvent::NewBlock { block });
+                    exit_code
+                }
+            };
+            pipeline.finish_step(index, exit_code);
+            let _ = self.event_sender.send(TerminalEvent::PipelineUpdated {
+                pipeline: pipeline.clone(),
+            });
+        }
+        Ok(pipeline)
+    }
+
+    /// Sets the environment of the active session's project, replacing the
+    /// previous project's.
+    pub async fn set_project_env(&self, env: BTreeMap<String, String>) -> Result<()> {
+        let session_id = self.active_or_new_session().await?;
+        if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
+            session.project_env = env;
+        }
+        Ok(())
+    }
+
+    /// The host the active session runs its commands on, if any.
+    pub async fn active_remote(&self) -> Option<RemoteInfo> {
+        let active_id = (*self.active_session_id.read().await)?;
+        self.sessions.read().await.get(&active_id)?.remote.clone()
+    }
+
+    /// Runs the active session's commands on `target` (`host` or
+    /// `user@host`) from now on, once it can be reached.
+    pub async fn connect_remote(&self, target: &str) -> Result<RemoteInfo> {
+        let remote = RemoteInfo::connect(target).await?;
+        self.set_remote(Some(remote.clone())).await?;
+        Ok(remote)
+    }
+
+    /// Tries the active session's host again after a dropped connection.
+    pub async fn reconnect_remote(&self) -> Result<RemoteInfo> {
+        let mut remote = self
+            .active_remote()
+            .await
+            .ok_or_else(|| anyhow!("Not in a remote session"))?;
+        remote.reconnect().await?;
+        self.set_remote(Some(remote.clone())).await?;
+        Ok(remote)
+    }
+
+    /// Replaces the active session's remote and tells the UI.
+    async fn set_remote(&self, remote: Option<RemoteInfo>) -> Result<()> {
+        let session_id = self.active_or_new_session().await?;
+        if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
+            session.remote = remote.clone();
+        }
+        let _ = self.event_sender.send(TerminalEvent::RemoteChanged { remote });
+        Ok(())
+    }
+
+    async fn active_or_new_session(&self) -> Result<Uuid> {
+        // Copied out first: `create_session` needs the lock
+        let active_id = *self.active_session_id.read().await;
+        match active_id {
+            Some(id) => Ok(id),
+            None => self.create_session().await,
+        }
+    }
+
+    /// Starts a command, in `directory` or the session's current directory,
+    /// as a step of `pipeline` when given, returning its block id and a
+    /// handle that completes with its exit code when it has finished.
+    async fn start_command(
+        &self,
+        input: String,
+        origin: CommandOrigin,
+        directory: Option<String>,
+        pipeline: Option<Uuid>,
+    ) -> Result<(Uuid, JoinHandle<i32>)> {
+        let (own_timeout, typed) = timeout::split_timeout(&input)?;
+        let timeout = timeout::effective_timeout(own_timeout, self.config().timeout);
+        let command = {
+            let aliases = self.aliases.read().await;
+            alias::expand_aliases(typed, &aliases)?
         };
 
-        let working_directory = {
+        let session_id = self.active_or_new_session().await?;
+
+        let (local_directory, environment, remote) = {
             let sessions = self.sessions.read().await;
-            sessions
-                .get(&session_id)
-                .map(|s| s.current_directory.clone())
-                .unwrap_or_else(|| {
+            match sessions.get(&session_id) {
+                Some(session) => (
+                    session.current_directory.clone(),
+                    project::merged_env(&session.environment, &session.project_env),
+                    session.remote.clone(),
+                ),
+                None => (
                     std::env::current_dir()
                         .unwrap_or_default()
                         .to_string_lossy()
-                        .to_string()
-                })
+                        .to_string(),
+                    HashMap::new(),
+                    None,
+                ),
+            }
+        };
+        let local_directory = directory.unwrap_or(local_directory);
+        // Remote commands are shown with the remote directory, and run by a
+        // local `ssh`
+        let (working_directory, command_line) = match &remote {
+            Some(remote) => (
+                format!("{}:{}", remote.label(), remote.directory),
+                remote.wrap_command(&command, &self.config().shell),
+            ),
+            None => (local_directory.clone(), command.clone()),
         };
 
-        let command_block = CommandBlock::new(command.clone(), working_directory.clone());
+        let mut command_block = CommandBlock::new(command.clone(), working_directory.clone(), &self.config().shell);
+        if origin == CommandOrigin::Startup {
+            command_block
+                .command_block
+                .set_metadata(metadata_keys::ORIGIN.to_string(), "startup".to_string());
+        }
+        if let Some(pipeline) = pipeline {
+            command_block
+                .command_block
+                .set_metadata(metadata_keys::PIPELINE.to_string(), pipeline.to_string());
+        }
+        if let Some(timeout) = timeout {
+            command_block
+                .command_block
+                .set_metadata(metadata_keys::TIMEOUT.to_string(), timeout::format_timeout(timeout));
+        }
         let command_id = command_block.command_block.id;
 
         // Add command block to session
@@ -112,99 +407,199 @@ impl TerminalEngine {
         let _ = self.event_sender.send(TerminalEvent::CommandStarted {
             id: command_id,
             command: command.clone(),
+            input,
+            working_directory: working_directory.clone(),
+            metadata: command_block.command_block.metadata.clone(),
         });
 
         // Execute the command asynchronously
         let event_sender = self.event_sender.clone();
         let sessions = self.sessions.clone();
-        let shell = self.config.shell.clone();
+        let config = self.config();
+        let shell = config.shell;
+        let login_shell = config.login_shell;
+        let audit_log = self.audit_log.clone();
+        let stdin_handles = config.detect_prompts.then(|| self.stdin_handles.clone());
+        let children = self.children.clone();
+        let is_running = self.is_running.clone();
 
-        tokio::spawn(async move {
+        let finished = tokio::spawn(async move {
+            let started = std::time::Instant::now();
             let result = Self::run_command_async(
-                command,
-                working_directory,
+                command_line,
+                local_directory,
+                environment,
                 shell,
+                login_shell,
                 command_id,
+                timeout,
                 event_sender.clone(),
-                sessions,
+                sessions.clone(),
                 session_id,
+                stdin_handles,
+                children,
+                is_running,
             )
             .await;
 
-            if let Err(e) = result {
+            if let (Some(remote), Ok(exit_code)) = (&remote, &result) {
+                let connected = *exit_code != remote::SSH_CONNECTION_ERROR;
+                if connected != remote.connected {
+                    set_remote_connected(&sessions, session_id, connected, &event_sender).await;
+                }
+            }
+
+            if let Some(audit_log) = audit_log {
+                record_audit_entry(
+                    &audit_log,
+                    AuditEntry {
+                        command,
+                        working_directory,
+                        exit_code: result.as_ref().ok().copied(),
+                        duration_ms: Some(started.elapsed().as_millis() as u64),
+                        origin,
+                    },
+                );
+            }
+
+            if let Err(e) = &result {
                 error!("Command execution failed: {}", e);
+                let _ = event_sender.send(TerminalEvent::CommandOutput {
+                    id: command_id,
+                    output: format!("{}\n", e),
+                    is_stderr: true,
+                    hyperlinks: Vec::new(),
+                });
+                let _ = event_sender.send(TerminalEvent::CommandFinished {
+                    id: command_id,
+                    exit_code: -1,
+                });
                 let _ = event_sender.send(TerminalEvent::Error {
                     message: format!("Command execution failed: {}", e),
                 });
             }
+            result.unwrap_or(-1)
         });
 
-        Ok(command_id)
+        Ok((command_id, finished))
     }
 
+    /// Runs `command` with the shell, streaming its output as events. With
+    /// `stdin_handles`, its stdin is piped and kept there until it exits, and
+    /// its output is watched for prompts. The process is tracked in
+    /// `children` so shutting down can kill it, as is running past `timeout`.
+    #[allow(clippy::too_many_arguments)]
     async fn run_command_async(
         command: String,
         working_directory: String,
+        environment: HashMap<String, String>,
         shell: String,
+        login_shell: bool,
         command_id: Uuid,
+        timeout: Option<Duration>,
         event_sender: TerminalEventSender,
-        _sessions: Arc<RwLock<HashMap<Uuid, TerminalSession>>>,
-        _session_id: Uuid,
-    ) -> Result<()> {
+        sessions: Arc<RwLock<HashMap<Uuid, TerminalSession>>>,
+        session_id: Uuid,
+        stdin_handles: Option<StdinHandles>,
+        children: RunningChildren,
+        is_running: Arc<AtomicBool>,
+    ) -> Result<i32> {
         debug!("Executing command: {} in {}", command, working_directory);
 
-        let mut child = if cfg!(windows) {
-            Command::new(&shell)
-                .args(&["-Command", &command])
-                .current_dir(&working_directory)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?
-        } else {
-            Command::new(&shell)
-                .args(&["-c", &command])
-                .current_dir(&working_directory)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?
-        };
+        let mut child = Command::new(&shell);
+        let login = login_shell && shell::supports_login(&shell);
+        ShellKind::detect(&shell).command_args(&command, login).apply(&mut child);
+        if stdin_handles.is_some() {
+            child.stdin(Stdio::piped());
+        }
+        // Its own process group, so killing it takes whatever it started too
+        #[cfg(unix)]
+        child.process_group(0);
+        let mut child = child
+            .kill_on_drop(true)
+            .current_dir(&working_directory)
+            .envs(environment)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
 
-        // Handle stdout
+        let detect_prompts = stdin_handles.is_some();
+        if let (Some(stdin_handles), Some(stdin)) = (&stdin_handles, child.stdin.take()) {
+            stdin_handles.lock().await.insert(command_id, stdin);
+        }
         if let Some(stdout) = child.stdout.take() {
-            let event_sender_stdout = event_sender.clone();
-            tokio::spawn(async move {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    let _ = event_sender_stdout.send(TerminalEvent::CommandOutput {
-                        id: command_id,
-                        output: format!("{}\n", line),
-                        is_stderr: false,
-                    });
-                }
-            });
+            tokio::spawn(stream_output(stdout, command_id, false, event_sender.clone(), detect_prompts));
         }
-
-        // Handle stderr
         if let Some(stderr) = child.stderr.take() {
-            let event_sender_stderr = event_sender.clone();
-            tokio::spawn(async move {
-                let reader = BufReader::new(stderr);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    let _ = event_sender_stderr.send(TerminalEvent::CommandOutput {
-                        id: command_id,
-                        output: format!("{}\n", line),
-                        is_stderr: true,
-                    });
-                }
-            });
+            tokio::spawn(stream_output(stderr, command_id, true, event_sender.clone(), detect_prompts));
+        }
+
+        let (kill_tx, kill_rx) = oneshot::channel();
+        {
+            let mut children = children.lock().await;
+            // Started while the engine was shutting down, too late to be killed
+            // with the rest
+            if !is_running.load(Ordering::Relaxed) {
+                kill_command(&mut child);
+            }
+            children.insert(
+                command_id,
+                RunningChild {
+                    command: command.clone(),
+                    pid: child.id(),
+                    kill: Some(kill_tx),
+                },
+            );
         }
 
         // Wait for command to finish
-        let exit_status = child.wait().await?;
-        let exit_code = exit_status.code().unwrap_or(-1);
+        let deadline = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        let mut timed_out = false;
+        let exit_status = tokio::select! {
+            status = child.wait() => status,
+            _ = kill_rx => {
+                kill_command(&mut child);
+                child.wait().await
+            }
+            _ = deadline => {
+                timed_out = true;
+                kill_command(&mut child);
+                child.wait().await
+            }
+        };
+        children.lock().await.remove(&command_id);
+        if let Some(stdin_handles) = &stdin_handles {
+            stdin_handles.lock().await.remove(&command_id);
+        }
+        let exit_code = match timed_out {
+            true => timeout::TIMED_OUT_EXIT_CODE,
+            false => exit_status?.code().unwrap_or(-1),
+        };
+
+        // Record the result on the session's block, for exports
+        if let Some(block) = sessions
+            .write()
+            .await
+            .get_mut(&session_id)
+            .and_then(|session| session.blocks.iter_mut().rev().find(|b| b.id == command_id))
+        {
+            block.set_exit_code(exit_code);
+            let elapsed = chrono::Utc::now() - block.timestamp;
+            block.set_execution_time(elapsed.num_milliseconds().max(0) as u64);
+            if let Some(timeout) = timeout.filter(|_| timed_out) {
+                block.set_metadata(metadata_keys::TIMED_OUT.to_string(), timeout::format_timeout(timeout));
+            }
+        }
 
+        if let Some(timeout) = timeout.filter(|_| timed_out) {
+            warn!("Command timed out after {}: {}", timeout::format_timeout(timeout), command);
+            let _ = event_sender.send(TerminalEvent::CommandTimedOut { id: command_id, timeout });
+        }
         // Send command finished event
         let _ = event_sender.send(TerminalEvent::CommandFinished {
             id: command_id,
@@ -212,9 +607,82 @@ impl TerminalEngine {
         });
 
         debug!("Command finished with exit code: {}", exit_code);
+        Ok(exit_code)
+    }
+
+    /// Runs `argv` directly, without a shell, with stdin closed, a minimal
+    /// environment and a timeout. Nothing is shown in the terminal. Callers
+    /// decide what is safe to run; see `ai::tools`. The audit log records
+    /// these as AI-originated.
+    pub async fn run_sandboxed(
+        &self,
+        argv: &[String],
+        working_directory: &Path,
+        timeout: Duration,
+    ) -> Result<SandboxedOutput> {
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| anyhow!("No command given"))?;
+        debug!("Running sandboxed command: {:?} in {}", argv, working_directory.display());
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(working_directory)
+            .env_clear()
+            .envs(SANDBOX_ENV.iter().filter_map(|var| std::env::var(var).ok().map(|value| (var, value))))
+            .env("PAGER", "cat")
+            .env("GIT_PAGER", "cat")
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let started = std::time::Instant::now();
+        let audit = |exit_code| AuditEntry {
+            command: argv.join(" "),
+            working_directory: working_directory.to_string_lossy().to_string(),
+            exit_code,
+            duration_ms: Some(started.elapsed().as_millis() as u64),
+            origin: CommandOrigin::Ai,
+        };
+
+        let child = command.spawn().map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => output?,
+            Err(_) => {
+                self.audit(audit(None));
+                return Err(anyhow!("{} timed out after {}s", program, timeout.as_secs()));
+            }
+        };
+        self.audit(audit(output.status.code()));
+
+        Ok(SandboxedOutput {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+        })
+    }
+
+    /// Writes `input` and a newline to a running command's stdin, to answer
+    /// a prompt. The input may be a password, so it's never logged.
+    pub async fn send_input(&self, command_id: Uuid, input: &str) -> Result<()> {
+        let mut stdin_handles = self.stdin_handles.lock().await;
+        let stdin = stdin_handles
+            .get_mut(&command_id)
+            .ok_or_else(|| anyhow!("The command isn't reading input"))?;
+        stdin.write_all(input.as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+        stdin.flush().await?;
         Ok(())
     }
 
+    /// Closes a running command's stdin, so it sees end of input.
+    pub async fn close_input(&self, command_id: Uuid) {
+        self.stdin_handles.lock().await.remove(&command_id);
+    }
+
     pub async fn handle_command_output(
         &self,
         command_id: Uuid,
@@ -277,6 +745,51 @@ impl TerminalEngine {
         }
     }
 
+    /// The commands whose processes haven't exited yet.
+    pub async fn running_commands(&self) -> Vec<RunningCommand> {
+        self.children
+            .lock()
+            .await
+            .iter()
+            .map(|(id, child)| RunningCommand {
+                id: *id,
+                command: child.command.clone(),
+                pid: child.pid,
+            })
+            .collect()
+    }
+
+    /// Kills every running command, along with the processes it started, and
+    /// waits up to `timeout` for them to be reaped, then shuts down. Returns
+    /// how many were killed.
+    pub async fn shutdown_and_reap(&self, timeout: Duration) -> usize {
+        let killed = {
+            let mut children = self.children.lock().await;
+            self.is_running.store(false, Ordering::Relaxed);
+            children
+                .values_mut()
+                .filter_map(|child| child.kill.take()?.send(()).ok())
+                .count()
+        };
+        if killed > 0 {
+            info!("Killing {} running command{}", killed, if killed == 1 { "" } else { "s" });
+        }
+
+        let reaped = tokio::time::timeout(timeout, async {
+            while !self.children.lock().await.is_empty() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await;
+        if reaped.is_err() {
+            warn!("Running commands didn't exit within {}s", timeout.as_secs());
+        }
+
+        self.stdin_handles.lock().await.clear();
+        self.shutdown().await;
+        killed
+    }
+
     pub async fn shutdown(&self) {
         info!("Shutting down terminal engine");
         self.is_running.store(false, Ordering::Relaxed);
@@ -290,8 +803,94 @@ impl TerminalEngine {
         self.is_running.load(Ordering::Relaxed)
     }
 
+    /// The active session's local working directory. Each session keeps
+    /// its own; the process's never changes.
+    pub async fn current_directory(&self) -> String {
+        let active_id = *self.active_session_id.read().await;
+        let sessions = self.sessions.read().await;
+        match active_id.and_then(|id| sessions.get(&id)) {
+            Some(session) => session.current_directory.clone(),
+            None => std::env::current_dir()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+
     // Built-in commands
+    /// Changes the active session's working directory, resolving `path`
+    /// against the current one, and returns the new directory. Later
+    /// commands of the session run there; other sessions stay where they are.
+    pub async fn change_directory(&self, path: &Path) -> Result<String> {
+        let session_id = self.active_or_new_session().await?;
+        let new_dir = {
+            let mut sessions = self.sessions.write().await;
+            let session = sessions
+                .get_mut(&session_id)
+                .ok_or_else(|| anyhow!("Session not found: {}", session_id))?;
+            let target = resolve_directory(Path::new(&session.current_directory), path);
+            if !target.is_dir() {
+                let reason = match target.exists() {
+                    true => "Not a directory",
+                    false => "No such file or directory",
+                };
+                return Err(anyhow!("Failed to change directory: {}: {}", path.display(), reason));
+            }
+            session.current_directory = target.to_string_lossy().to_string();
+            session.current_directory.clone()
+        };
+
+        let _ = self.event_sender.send(TerminalEvent::DirectoryChanged {
+            directory: new_dir.clone(),
+        });
+        Ok(new_dir)
+    }
+
+    async fn change_remote_directory(&self, mut remote: RemoteInfo, path: &str) -> Result<Block> {
+        remote.directory = remote.change_directory(path).await?;
+        let block = Block::system(format!("Changed directory to: {}:{}", remote.label(), remote.directory));
+        self.set_remote(Some(remote)).await?;
+        Ok(block)
+    }
+
+    /// The `open <path>[:line[:col]]` builtin, with `path` relative to the
+    /// session's directory.
+    async fn open_in_editor(&self, target: &str) -> Result<Block> {
+        let target = target.trim_matches(|c| c == '"' || c == '\'');
+        let directory = self.current_directory().await;
+        let target = Path::new(&directory).join(target).to_string_lossy().to_string();
+        let (path, line, column) = split_file_position(&target);
+        if !path.is_file() {
+            return Err(anyhow!("open: {}: No such file", path.display()));
+        }
+
+        EditorIntegration::new(self.config().editor_command.clone()).open(path, line, column)?;
+        Ok(Block::system(format!("Opened {} in the editor", path.display())))
+    }
+
     pub async fn handle_builtin_command(&self, command: &str) -> Option<Result<Block>> {
+        // A script of several lines is the shell's to run, even when it
+        // starts with `cd` or `alias`
+        if command.trim().contains('\n') {
+            return None;
+        }
+        if let Some(parsed) = alias::parse_alias_command(command) {
+            return Some(match parsed {
+                Ok(alias_command) => Ok(self.handle_alias_command(alias_command).await),
+                Err(e) => Err(e),
+            });
+        }
+        // `alias proj='cd ~/p'` has to move the session like `cd` does
+        let command = {
+            let aliases = self.aliases.read().await;
+            match alias::expand_aliases(command, &aliases) {
+                Ok(command) => command,
+                Err(e) => return Some(Err(e)),
+            }
+        };
+
+        let remote = self.active_remote().await;
+        let shell_kind = ShellKind::detect(&self.config().shell);
         match command.trim() {
             "clear" => {
                 if let Some(session) = self.get_active_session().await {
@@ -301,43 +900,261 @@ impl TerminalEngine {
                 }
                 Some(Ok(Block::system("Screen cleared".to_string())))
             }
+            "exit" | "quit" if remote.is_some() => {
+                let label = remote.map(|remote| remote.label()).unwrap_or_default();
+                Some(
+                    self.set_remote(None)
+                        .await
+                        .map(|_| Block::system(format!("Disconnected from {}", label))),
+                )
+            }
             "exit" | "quit" => {
                 self.shutdown().await;
                 Some(Ok(Block::system("Goodbye!".to_string())))
             }
-            cmd if cmd.starts_with("cd ") => {
+            cmd if cmd.starts_with("cd ") && remote.is_some() => {
                 let path = cmd.strip_prefix("cd ").unwrap().trim();
-                match std::env::set_current_dir(path) {
-                    Ok(_) => {
-                        let new_dir = std::env::current_dir()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string();
-
-                        // Update session directory
-                        if let Some(active_id) = *self.active_session_id.read().await {
-                            let mut sessions = self.sessions.write().await;
-                            if let Some(session) = sessions.get_mut(&active_id) {
-                                session.current_directory = new_dir.clone();
-                            }
-                        }
-
-                        Some(Ok(Block::system(format!(
-                            "Changed directory to: {}",
-                            new_dir
-                        ))))
-                    }
-                    Err(e) => Some(Err(anyhow!("Failed to change directory: {}", e))),
+                Some(self.change_remote_directory(remote?, path).await)
+            }
+            cmd if remote.is_none() && shell_kind.cd_arguments(cmd).is_some() => {
+                let arguments = shell_kind.cd_arguments(cmd)?;
+                // A bare `cd` in cmd.exe prints the directory
+                let Some(path) = shell_kind.cd_target(arguments, |name| std::env::var(name).ok()) else {
+                    return Some(Ok(Block::output(self.current_directory().await)));
+                };
+                match self.change_directory(Path::new(&path)).await {
+                    Ok(new_dir) => Some(Ok(Block::system(format!(
+                        "Changed directory to: {}",
+                        new_dir
+                    )))),
+                    Err(e) => Some(Err(e)),
+                }
+            }
+            cmd if cmd.starts_with("open ") => {
+                let target = cmd.strip_prefix("open ").unwrap().trim();
+                // Flags mean the system `open` (e.g. `open -a Safari`)
+                if target.starts_with('-') {
+                    return None;
                 }
+                Some(self.open_in_editor(target).await)
             }
-            "pwd" => {
-                let current_dir = std::env::current_dir()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-                Some(Ok(Block::output(current_dir)))
+            "pwd" if remote.is_some() => Some(Ok(Block::output(remote?.directory))),
+            cmd if remote.is_none() && remote_target(cmd).is_some() => {
+                let target = remote_target(cmd)?;
+                Some(
+                    self.connect_remote(target)
+                        .await
+                        .map(|remote| Block::system(format!("Connected to {} in {}", remote.label(), remote.directory))),
+                )
             }
+            "pwd" => Some(Ok(Block::output(self.current_directory().await))),
             _ => None,
         }
     }
+
+    pub async fn get_aliases(&self) -> HashMap<String, String> {
+        self.aliases.read().await.clone()
+    }
+
+    async fn handle_alias_command(&self, alias_command: AliasCommand) -> Block {
+        let mut aliases = self.aliases.write().await;
+
+        match alias_command {
+            AliasCommand::List => {
+                if aliases.is_empty() {
+                    return Block::output("No aliases defined".to_string());
+                }
+                let mut names: Vec<_> = aliases.keys().collect();
+                names.sort();
+                let listing = names
+                    .into_iter()
+                    .map(|name| alias::format_alias(name, &aliases[name]))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Block::output(listing)
+            }
+            AliasCommand::Show(name) => match aliases.get(&name) {
+                Some(value) => Block::output(alias::format_alias(&name, value)),
+                None => Block::error(format!("alias: {}: not found", name)),
+            },
+            AliasCommand::Define { name, value } => {
+                aliases.insert(name.clone(), value.clone());
+                let _ = self.event_sender.send(TerminalEvent::AliasesChanged {
+                    aliases: aliases.clone(),
+                });
+                Block::system(format!("Defined {}", alias::format_alias(&name, &value)))
+            }
+            AliasCommand::Remove(name) => {
+                if aliases.remove(&name).is_none() {
+                    return Block::error(format!("unalias: {}: not found", name));
+                }
+                let _ = self.event_sender.send(TerminalEvent::AliasesChanged {
+                    aliases: aliases.clone(),
+                });
+                Block::system(format!("Removed alias '{}'", name))
+            }
+        }
+    }
+}
+
+/// `src/main.rs:12:5` -> (`src/main.rs`, 12, 5). A file whose name really
+/// ends in `:12` is left alone.
+/// The host of a bare `ssh <host>`, which starts a remote session rather
+/// than running `ssh`. Anything more, like options or a command, is run as
+/// typed.
+fn remote_target(command: &str) -> Option<&str> {
+    let mut words = command.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("ssh"), Some(target), None) if !target.starts_with('-') => Some(target),
+        _ => None,
+    }
+}
+
+/// Marks the session's host reached or lost, after a command.
+async fn set_remote_connected(
+    sessions: &RwLock<HashMap<Uuid, TerminalSession>>,
+    session_id: Uuid,
+    connected: bool,
+    event_sender: &TerminalEventSender,
+) {
+    let remote = sessions
+        .write()
+        .await
+        .get_mut(&session_id)
+        .and_then(|session| session.remote.as_mut())
+        .map(|remote| {
+            remote.connected = connected;
+            remote.clone()
+        });
+    if remote.is_some() {
+        let _ = event_sender.send(TerminalEvent::RemoteChanged { remote });
+    }
+}
+
+/// `path` resolved against `current` and with `.` and `..` worked out
+/// without following symlinks, the way shells track their directory.
+fn resolve_directory(current: &Path, path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in current.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+fn split_file_position(target: &str) -> (&Path, Option<u32>, Option<u32>) {
+    let whole = Path::new(target);
+    if whole.exists() {
+        return (whole, None, None);
+    }
+
+    let mut rest = target;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+        match rest.rsplit_once(':') {
+            Some((head, tail)) if !tail.is_empty() && tail.chars().all(|c| c.is_ascii_digit()) => {
+                numbers.push(tail.parse().ok());
+                rest = head;
+            }
+            _ => break,
+        }
+    }
+    match numbers.as_slice() {
+        [line] => (Path::new(rest), *line, None),
+        [column, line] => (Path::new(rest), *line, *column),
+        _ => (whole, None, None),
+    }
+}
+
+/// Forwards a command's output as it arrives rather than a line at a time,
+/// so prompts that don't end in a newline are shown. Escape sequences are
+/// stripped, keeping OSC 8 hyperlinks. With `detect_prompts`, an unfinished
+/// last line that looks like a prompt is reported with
+/// `TerminalEvent::InputRequested`.
+async fn stream_output(
+    mut reader: impl AsyncRead + Unpin,
+    command_id: Uuid,
+    is_stderr: bool,
+    event_sender: TerminalEventSender,
+    detect_prompts: bool,
+) {
+    let mut buffer = [0u8; 4096];
+    let mut processor = VteProcessor::new();
+    let mut partial_line = String::new();
+    let mut finished = false;
+    while !finished {
+        let (output, hyperlinks) = match reader.read(&mut buffer).await {
+            // A sequence the output ended in the middle of still shows up
+            Ok(0) => {
+                finished = true;
+                processor.finish_to_text()
+            }
+            // The processor keeps UTF-8 sequences and escapes split across
+            // reads, and turns bytes that aren't UTF-8 into U+FFFD
+            Ok(read) => processor.process_to_text(&buffer[..read]),
+            Err(e) => {
+                debug!("Stopped reading command output: {}", e);
+                break;
+            }
+        };
+        if let Some(title) = processor.take_title() {
+            let _ = event_sender.send(TerminalEvent::TitleChanged { id: command_id, title });
+        }
+        if output.is_empty() {
+            continue;
+        }
+
+        match output.rfind('\n') {
+            Some(newline) => partial_line = output[newline + 1..].to_string(),
+            None => partial_line.push_str(&output),
+        }
+        // Progress bars redraw the line with a carriage return
+        if let Some(carriage_return) = partial_line.rfind('\r') {
+            partial_line.drain(..=carriage_return);
+        }
+        let _ = event_sender.send(TerminalEvent::CommandOutput {
+            id: command_id,
+            output,
+            is_stderr,
+            hyperlinks,
+        });
+
+        if !detect_prompts {
+            continue;
+        }
+        if let Some(prompt) = prompts::detect_prompt(&partial_line) {
+            let _ = event_sender.send(TerminalEvent::InputRequested {
+                id: command_id,
+                prompt: prompt.text,
+                secret: prompt.secret,
+            });
+        }
+    }
+}
+
+/// Kills a command started by `run_command_async`. On Unix that's its
+/// whole process group, so background jobs and pipelines go with it.
+fn kill_command(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: `kill` has no memory effects; the group is the one
+        // `run_command_async` created for this command
+        if unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) } == 0 {
+            return;
+        }
+    }
+    if let Err(e) = child.start_kill() {
+        debug!("Failed to kill command: {}", e);
+    }
+}
+
+fn record_audit_entry(audit_log: &AuditLog, entry: AuditEntry) {
+    if let Err(e) = audit_log.record(entry) {
+        error!("Failed to write audit log {}: {:#}", audit_log.path().display(), e);
+    }
 }
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A starred command, run as it is with one click. Unlike a workflow it has
+/// no placeholders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub command: String,
+    /// Shown instead of the command when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub added: DateTime<Utc>,
+}
+
+impl Favorite {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            label: None,
+            added: Utc::now(),
+        }
+    }
+
+    /// The label, or the command when there's none.
+    pub fn title(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.command)
+    }
+}
+
+pub fn default_favorites_path() -> PathBuf {
+    crate::config::config_dir().join("favorites.json")
+}
+
+/// The user's favorite commands, in the order they were starred, kept in a
+/// JSON file. Commands are compared with surrounding whitespace trimmed.
+#[derive(Debug, Clone)]
+pub struct Favorites {
+    path: PathBuf,
+    favorites: Vec<Favorite>,
+}
+
+impl Favorites {
+    /// No favorites, saved to `path`.
+    pub fn empty(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            favorites: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut favorites = Self::empty(path);
+        if path.exists() {
+            let content =
+                std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            favorites.favorites =
+                serde_json::from_str(&content).with_context(|| format!("Invalid favorites in {}", path.display()))?;
+        }
+        Ok(favorites)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.favorites)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    pub fn all(&self) -> &[Favorite] {
+        &self.favorites
+    }
+
+    pub fn len(&self) -> usize {
+        self.favorites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.favorites.is_empty()
+    }
+
+    pub fn get(&self, command: &str) -> Option<&Favorite> {
+        self.favorites.iter().find(|favorite| favorite.command == command.trim())
+    }
+
+    pub fn contains(&self, command: &str) -> bool {
+        self.get(command).is_some()
+    }
+
+    /// Stars `command`; returns false when it's empty or already a favorite.
+    pub fn add(&mut self, command: &str) -> bool {
+        let command = command.trim();
+        if command.is_empty() || self.contains(command) {
+            return false;
+        }
+        self.favorites.push(Favorite::new(command));
+        true
+    }
+
+    pub fn remove(&mut self, command: &str) -> Option<Favorite> {
+        let index = self.favorites.iter().position(|favorite| favorite.command == command.trim())?;
+        Some(self.favorites.remove(index))
+    }
+
+    /// Stars or unstars `command`. Returns whether it's a favorite now.
+    pub fn toggle(&mut self, command: &str) -> bool {
+        match self.remove(command) {
+            Some(_) => false,
+            None => self.add(command),
+        }
+    }
+
+    /// Sets or, with a blank `label`, clears a favorite's label.
+    pub fn set_label(&mut self, command: &str, label: &str) {
+        let label = label.trim();
+        if let Some(favorite) = self.favorites.iter_mut().find(|favorite| favorite.command == command.trim()) {
+            favorite.label = (!label.is_empty()).then(|| label.to_string());
+        }
+    }
+
+    /// Favorites whose label or command fuzzy-match `query`, best first;
+    /// all of them in order for an empty query.
+    pub fn matches(&self, query: &str) -> Vec<&Favorite> {
+        let query = query.trim();
+        if query.is_empty() {
+            return self.favorites.iter().collect();
+        }
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<(&Favorite, i64)> = self
+            .favorites
+            .iter()
+            .filter_map(|favorite| {
+                let label = favorite.label.as_deref().and_then(|label| matcher.fuzzy_match(label, query));
+                let command = matcher.fuzzy_match(&favorite.command, query);
+                label.max(command).map(|score| (favorite, score))
+            })
+            .collect();
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(favorite, _)| favorite).collect()
+    }
+}
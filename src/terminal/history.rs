@@ -1,15 +1,22 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     pub command: String,
     pub timestamp: DateTime<Utc>,
+    /// Where the command ran, as its block shows it (`host:dir` for remote
+    /// sessions); empty when unknown, as for imported shell history
     pub working_directory: String,
     pub exit_code: Option<i32>,
     pub execution_time: Option<u64>, // milliseconds
+    /// Times the command was run again straight after this entry; history
+    /// keeps those runs as one entry
+    #[serde(default)]
+    pub repeats: u32,
 }
 
 impl HistoryEntry {
@@ -20,6 +27,7 @@ impl HistoryEntry {
             working_directory,
             exit_code: None,
             execution_time: None,
+            repeats: 0,
         }
     }
 
@@ -28,6 +36,11 @@ impl HistoryEntry {
         self.execution_time = Some(execution_time);
     }
 
+    /// How many times the command ran, counting repeats.
+    pub fn runs(&self) -> u32 {
+        self.repeats + 1
+    }
+
     pub fn is_success(&self) -> bool {
         matches!(self.exit_code, Some(0))
     }
@@ -53,9 +66,15 @@ impl CommandHistory {
     }
 
     pub fn add_entry(&mut self, entry: HistoryEntry) {
-        // Don't add duplicate consecutive entries
-        if let Some(last) = self.entries.back() {
+        // Don't add duplicate consecutive entries, but count them, keeping
+        // the latest run's time and result so frecency sees it as recent
+        if let Some(last) = self.entries.back_mut() {
             if last.command == entry.command {
+                last.repeats += 1;
+                last.timestamp = entry.timestamp;
+                last.exit_code = entry.exit_code;
+                last.execution_time = entry.execution_time;
+                self.current_index = None;
                 return;
             }
         }
@@ -175,10 +194,13 @@ impl CommandHistory {
             .iter()
             .map(|entry| {
                 format!(
-                    "{} {} ({}){}\n",
+                    "{} {}{}{}\n",
                     entry.formatted_timestamp(),
                     entry.command,
-                    entry.working_directory,
+                    match entry.working_directory.as_str() {
+                        "" => String::new(),
+                        directory => format!(" ({})", directory),
+                    },
                     match entry.exit_code {
                         Some(0) => " ✓".to_string(),
                         Some(code) => format!(" ✗({})", code),
@@ -192,51 +214,329 @@ impl CommandHistory {
         Ok(())
     }
 
+    /// Attaches an exit code and duration to the most recent entry for `command`.
+    pub fn record_result(&mut self, command: &str, exit_code: i32, execution_time: u64) -> bool {
+        match self.entries.iter_mut().rev().find(|entry| entry.command == command) {
+            Some(entry) => {
+                entry.set_result(exit_code, execution_time);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains_command(&self, command: &str) -> bool {
+        self.entries.iter().any(|entry| entry.command == command)
+    }
+
+    /// Adds `entries` that aren't already in the history, returning how many
+    /// were added. Running the same import twice adds nothing the second time.
+    pub fn import_entries(&mut self, entries: Vec<HistoryEntry>) -> usize {
+        let mut known: HashSet<String> =
+            self.entries.iter().map(|entry| entry.command.clone()).collect();
+        let mut imported = 0;
+
+        for entry in entries {
+            if known.insert(entry.command.clone()) {
+                self.entries.push_back(entry);
+                imported += 1;
+            }
+        }
+
+        while self.entries.len() > self.max_entries {
+            self.entries.pop_front();
+        }
+        self.current_index = None;
+
+        imported
+    }
+
     pub fn import_from_shell_history(&mut self, shell: &str) -> Result<usize> {
-        let history_file = match shell {
-            "bash" => {
-                dirs::home_dir()
-                    .map(|home| home.join(".bash_history"))
-                    .and_then(|path| if path.exists() { Some(path) } else { None })
+        let entries = read_shell_history(shell)?;
+        Ok(self.import_entries(entries))
+    }
+
+    pub fn load(path: &Path, max_entries: usize) -> Result<Self> {
+        let mut history = Self::new(max_entries);
+        if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            let entries: Vec<HistoryEntry> = serde_json::from_str(&content)?;
+            history.entries = entries.into_iter().collect();
+            while history.entries.len() > history.max_entries {
+                history.entries.pop_front();
             }
-            "zsh" => {
-                dirs::home_dir()
-                    .map(|home| home.join(".zsh_history"))
-                    .and_then(|path| if path.exists() { Some(path) } else { None })
+        }
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(&self.entries)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+pub fn default_history_path() -> PathBuf {
+    crate::config::data_dir().join("history.json")
+}
+
+/// Shells whose history files we know how to import.
+pub const IMPORTABLE_SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+pub fn shell_history_path(shell: &str) -> Option<PathBuf> {
+    let path = match shell {
+        "bash" => dirs::home_dir()?.join(".bash_history"),
+        "zsh" => std::env::var_os("HISTFILE")
+            .map(PathBuf::from)
+            .filter(|path| path.ends_with(".zsh_history"))
+            .or_else(|| dirs::home_dir().map(|home| home.join(".zsh_history")))?,
+        "fish" => std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))?
+            .join("fish")
+            .join("fish_history"),
+        "powershell" | "pwsh" => {
+            return default_psreadline_history_path()
+                .filter(|path| path.exists())
+                .or_else(psreadline_history_save_path);
+        }
+        _ => return None,
+    };
+
+    path.exists().then_some(path)
+}
+
+/// Where PSReadLine keeps history unless `HistorySavePath` was changed.
+fn default_psreadline_history_path() -> Option<PathBuf> {
+    let dir = if cfg!(windows) {
+        dirs::data_dir()?.join("Microsoft").join("Windows").join("PowerShell")
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))?
+            .join("powershell")
+    };
+    Some(dir.join("PSReadLine").join("ConsoleHost_history.txt"))
+}
+
+/// Asks PowerShell for `(Get-PSReadLineOption).HistorySavePath`, for setups
+/// that moved the history file.
+fn psreadline_history_save_path() -> Option<PathBuf> {
+    let powershell = ["pwsh", "powershell"]
+        .into_iter()
+        .find_map(|name| which::which(name).ok())?;
+    let output = std::process::Command::new(powershell)
+        .args([
+            "-NoLogo",
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "(Get-PSReadLineOption).HistorySavePath",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    (!path.as_os_str().is_empty() && path.exists()).then_some(path)
+}
+
+/// Reads and parses a shell's history file. Returns an empty list when the
+/// shell has no history file.
+pub fn read_shell_history(shell: &str) -> Result<Vec<HistoryEntry>> {
+    let Some(history_path) = shell_history_path(shell) else {
+        return Ok(Vec::new());
+    };
+
+    // History files regularly contain non-UTF8 bytes (pasted binary, old locales)
+    let bytes = std::fs::read(history_path)?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    // Shells don't record where commands ran; left empty rather than
+    // guessed, so directory-aware ranking ignores them
+    Ok(parse_shell_history(shell, &content)
+        .into_iter()
+        .map(|parsed| {
+            let mut entry = HistoryEntry::new(parsed.command, String::new());
+            if let Some(timestamp) = parsed.timestamp {
+                entry.timestamp = timestamp;
             }
-            "fish" => {
-                dirs::config_dir()
-                    .map(|config| config.join("fish").join("fish_history"))
-                    .and_then(|path| if path.exists() { Some(path) } else { None })
+            entry
+        })
+        .collect())
+}
+
+/// A command read from a shell's history file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellHistoryCommand {
+    pub command: String,
+    /// When the command ran, if the history file records it
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl From<String> for ShellHistoryCommand {
+    fn from(command: String) -> Self {
+        Self {
+            command,
+            timestamp: None,
+        }
+    }
+}
+
+pub fn parse_shell_history(shell: &str, content: &str) -> Vec<ShellHistoryCommand> {
+    let commands = match shell {
+        "zsh" => return parse_zsh_history(content),
+        "fish" => parse_fish_history(content),
+        "powershell" | "pwsh" => parse_powershell_history(content),
+        _ => parse_bash_history(content),
+    };
+    commands.into_iter().map(ShellHistoryCommand::from).collect()
+}
+
+fn parse_bash_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        // `#1700000000` lines are HISTTIMEFORMAT timestamps, not commands
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses both plain zsh history and the extended format
+/// (`: <timestamp>:<elapsed>;<command>`), joining `\`-continued lines.
+/// Extended entries keep their timestamp.
+fn parse_zsh_history(content: &str) -> Vec<ShellHistoryCommand> {
+    let mut commands = Vec::new();
+    let mut pending: Option<(String, Option<DateTime<Utc>>)> = None;
+
+    let mut push = |command: &str, timestamp| {
+        let command = command.trim();
+        if !command.is_empty() {
+            commands.push(ShellHistoryCommand {
+                command: command.to_string(),
+                timestamp,
+            });
+        }
+    };
+
+    for line in content.lines() {
+        let (line, timestamp) = match pending.take() {
+            Some((mut previous, timestamp)) => {
+                previous.push('\n');
+                previous.push_str(line);
+                (previous, timestamp)
             }
-            "pwsh" | "powershell" => {
-                // PowerShell history is more complex, skip for now
-                return Ok(0);
+            None => {
+                let (command, timestamp) = parse_zsh_extended_line(line);
+                (command.to_string(), timestamp)
             }
-            _ => None,
         };
 
-        if let Some(history_path) = history_file {
-            let content = std::fs::read_to_string(history_path)?;
-            let mut imported = 0;
-
-            for line in content.lines() {
-                if !line.trim().is_empty() && !line.starts_with('#') {
-                    let entry = HistoryEntry::new(
-                        line.trim().to_string(),
-                        std::env::current_dir()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string(),
-                    );
-                    self.add_entry(entry);
-                    imported += 1;
-                }
+        if let Some(continued) = line.strip_suffix('\\') {
+            pending = Some((continued.to_string(), timestamp));
+            continue;
+        }
+        push(&line, timestamp);
+    }
+
+    if let Some((command, timestamp)) = pending {
+        push(&command, timestamp);
+    }
+
+    commands
+}
+
+/// Splits `: 1700000000:0;git status` into the command and its start time.
+/// Lines that aren't in the extended format are returned as they are.
+fn parse_zsh_extended_line(line: &str) -> (&str, Option<DateTime<Utc>>) {
+    let Some((meta, command)) = line.strip_prefix(": ").and_then(|rest| rest.split_once(';')) else {
+        return (line, None);
+    };
+    let Some((start, elapsed)) = meta.split_once(':') else {
+        return (line, None);
+    };
+    let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if !is_number(start) || !is_number(elapsed) {
+        return (line, None);
+    }
+
+    let timestamp = start
+        .parse()
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0));
+    (command, timestamp)
+}
+
+/// fish stores history as YAML-ish records:
+/// ```text
+/// - cmd: git status
+///   when: 1700000000
+/// ```
+fn parse_fish_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(|command| unescape_fish(command).trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// fish writes newlines in a command as `\n` and backslashes as `\\`.
+/// Decoded in one pass, so `\\n` stays a backslash followed by `n`.
+fn unescape_fish(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
             }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
 
-            Ok(imported)
-        } else {
-            Ok(0)
+/// PSReadLine writes one command per line; the lines of a multi-line command
+/// all but the last end with a backtick.
+fn parse_powershell_history(content: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut pending = String::new();
+
+    for line in content.lines() {
+        match line.strip_suffix('`') {
+            Some(continued) => {
+                pending.push_str(continued);
+                pending.push('\n');
+            }
+            None => {
+                pending.push_str(line);
+                let command = pending.trim();
+                if !command.is_empty() {
+                    commands.push(command.to_string());
+                }
+                pending.clear();
+            }
         }
     }
+
+    let command = pending.trim();
+    if !command.is_empty() {
+        commands.push(command.to_string());
+    }
+
+    commands
 }
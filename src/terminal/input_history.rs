@@ -0,0 +1,64 @@
+//! Recalling earlier commands into the input with Up and Down, keeping
+//! what was being typed to come back to.
+
+use std::collections::VecDeque;
+
+/// Where Up and Down have got to in the commands run, newest first.
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    /// The recalled command's index
+    position: Option<usize>,
+    /// The input before the first Up, which Down past the newest command
+    /// brings back
+    draft: Option<String>,
+}
+
+impl InputHistory {
+    /// The command before the one recalled, or the newest one when nothing
+    /// is recalled yet, in which case `input` is kept as the draft. `None`
+    /// at the oldest.
+    pub fn older(&mut self, commands: &VecDeque<String>, input: &str) -> Option<String> {
+        let next = self.position.map_or(0, |position| position + 1);
+        let command = commands.get(next)?;
+        if self.position.is_none() {
+            self.draft = Some(input.to_string());
+        }
+        self.position = Some(next);
+        Some(command.clone())
+    }
+
+    /// The command after the one recalled, or the draft after the newest.
+    /// `None` when nothing is recalled.
+    pub fn newer(&mut self, commands: &VecDeque<String>) -> Option<String> {
+        match self.position? {
+            0 => {
+                self.position = None;
+                Some(self.draft.take().unwrap_or_default())
+            }
+            position => {
+                self.position = Some(position - 1);
+                commands.get(position - 1).cloned()
+            }
+        }
+    }
+
+    pub fn is_recalling(&self) -> bool {
+        self.position.is_some()
+    }
+
+    /// Starts over from the newest command, as one was run.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Whether the character at `cursor` is on the first line of `text`, so Up
+/// recalls an older command rather than moving up a line.
+pub fn on_first_line(text: &str, cursor: usize) -> bool {
+    !text.chars().take(cursor).any(|c| c == '\n')
+}
+
+/// Whether the character at `cursor` is on the last line of `text`.
+pub fn on_last_line(text: &str, cursor: usize) -> bool {
+    !text.chars().skip(cursor).any(|c| c == '\n')
+}
@@ -0,0 +1,156 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+static NULL: Value = Value::Null;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// `.name` or `."some key"` or `["some key"]`
+    Key(String),
+    /// `[2]`, or from the end with `[-1]`
+    Index(i64),
+    /// `[]`, every element or value
+    Iterate,
+}
+
+/// A jq-style path like `.items[0].metadata.name` or `.items[].name`.
+/// Only lookups are supported: no pipes, functions or slices.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JsonPath {
+    pub segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// Parses a path. `.` alone is the whole value. Keys that aren't plain
+    /// words, like `app.kubernetes.io/name`, need quotes: `."app.kubernetes.io/name"`
+    /// or `["app.kubernetes.io/name"]`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let chars: Vec<char> = text.trim().chars().collect();
+        if chars.first() != Some(&'.') {
+            bail!("A path starts with '.'");
+        }
+        let mut segments = Vec::new();
+        let mut position = 0;
+        while position < chars.len() {
+            match chars[position] {
+                '.' => {
+                    position += 1;
+                    match chars.get(position) {
+                        // `.[0]` is the same as `[0]`
+                        Some('[') => {}
+                        Some('"') => {
+                            let (key, next) = quoted(&chars, position)?;
+                            segments.push(PathSegment::Key(key));
+                            position = next;
+                        }
+                        Some(&c) if is_word_char(c) => {
+                            let end = chars[position..]
+                                .iter()
+                                .position(|&c| !is_word_char(c))
+                                .map_or(chars.len(), |length| position + length);
+                            segments.push(PathSegment::Key(chars[position..end].iter().collect()));
+                            position = end;
+                        }
+                        None if segments.is_empty() => {}
+                        Some(&c) => bail!("Unexpected '{}' after '.' at {}", c, position + 1),
+                        None => bail!("The path ends with '.'"),
+                    }
+                }
+                '[' => {
+                    position += 1;
+                    let Some(close) = chars[position..].iter().position(|&c| c == ']') else {
+                        bail!("Missing ']' for the '[' at {}", position);
+                    };
+                    let inside: String = chars[position..position + close].iter().collect();
+                    let inside = inside.trim();
+                    segments.push(if inside.is_empty() {
+                        PathSegment::Iterate
+                    } else if inside.starts_with('"') {
+                        let (key, next) = quoted(&inside.chars().collect::<Vec<_>>(), 0)?;
+                        if next != inside.chars().count() {
+                            bail!("Unexpected text after the key in [{}]", inside);
+                        }
+                        PathSegment::Key(key)
+                    } else {
+                        match inside.parse::<i64>() {
+                            Ok(index) => PathSegment::Index(index),
+                            Err(_) => bail!("[{}] isn't an index; quote keys: [\"{}\"]", inside, inside),
+                        }
+                    });
+                    position += close + 1;
+                }
+                c => bail!("Unexpected '{}' at {}", c, position + 1),
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    /// What the path picks out of `value`. Like jq, missing keys and
+    /// indexes past the end give `null`, and `[]` gives one result per
+    /// element, so there can be any number of results.
+    pub fn evaluate<'a>(&self, value: &'a Value) -> Result<Vec<&'a Value>> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in current {
+                match (segment, value) {
+                    (PathSegment::Key(key), Value::Object(map)) => next.push(map.get(key).unwrap_or(&NULL)),
+                    (PathSegment::Index(index), Value::Array(items)) => {
+                        let position = match *index < 0 {
+                            true => items.len().checked_sub(index.unsigned_abs() as usize),
+                            false => Some(*index as usize),
+                        };
+                        next.push(position.and_then(|position| items.get(position)).unwrap_or(&NULL));
+                    }
+                    (PathSegment::Key(_) | PathSegment::Index(_), Value::Null) => next.push(&NULL),
+                    (PathSegment::Iterate, Value::Array(items)) => next.extend(items),
+                    (PathSegment::Iterate, Value::Object(map)) => next.extend(map.values()),
+                    (PathSegment::Key(key), other) => {
+                        bail!("Cannot index {} with \"{}\"", type_name(other), key)
+                    }
+                    (PathSegment::Index(_), other) => bail!("Cannot index {} with a number", type_name(other)),
+                    (PathSegment::Iterate, other) => bail!("Cannot iterate over {}", type_name(other)),
+                }
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// The string starting with the quote at `start`, with `\"` and `\\`
+/// unescaped, and the position after its closing quote.
+fn quoted(chars: &[char], start: usize) -> Result<(String, usize)> {
+    let mut key = String::new();
+    let mut position = start + 1;
+    while let Some(&c) = chars.get(position) {
+        match c {
+            '"' => return Ok((key, position + 1)),
+            '\\' => {
+                position += 1;
+                match chars.get(position) {
+                    Some(&escaped) => key.push(escaped),
+                    None => break,
+                }
+            }
+            c => key.push(c),
+        }
+        position += 1;
+    }
+    bail!("Missing closing '\"' for the key at {}", start + 1)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,192 @@
+use regex::Regex;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Something clickable in a line of command output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Byte range of the link text within the line
+    pub range: Range<usize>,
+    pub target: LinkTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    Url(String),
+    File {
+        /// Resolved against the block's working directory
+        path: PathBuf,
+        line: Option<u32>,
+        column: Option<u32>,
+    },
+}
+
+/// Schemes of OSC 8 hyperlinks that are opened; others are shown as text.
+const HYPERLINK_SCHEMES: &[&str] = &["http", "https", "ftp", "mailto"];
+
+/// Where an OSC 8 hyperlink's URI points. `file://` URIs become paths so
+/// they can be revealed or edited like detected paths; other URIs are only
+/// linked for the schemes in `HYPERLINK_SCHEMES`.
+pub fn hyperlink_target(uri: &str) -> Option<LinkTarget> {
+    let (scheme, rest) = uri.split_once(':')?;
+    let scheme = scheme.to_ascii_lowercase();
+    if scheme == "file" {
+        // file://host/path; the host is usually empty or this machine
+        let path = rest.strip_prefix("//")?;
+        let path = &path[path.find('/')?..];
+        // file:///C:/Users/... on Windows
+        let path = match path.as_bytes() {
+            [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => &path[1..],
+            _ => path,
+        };
+        return Some(LinkTarget::File {
+            path: PathBuf::from(percent_decode(path)),
+            line: None,
+            column: None,
+        });
+    }
+    HYPERLINK_SCHEMES
+        .contains(&scheme.as_str())
+        .then(|| LinkTarget::Url(uri.to_string()))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Finds URLs and existing file paths (optionally with `:line:col`) in one
+/// line of output. Relative paths are resolved against `working_directory`.
+///
+/// Only whole tokens are considered, so `foo.rs` inside `xfoo.rs` or a URL
+/// embedded in a longer word is never linked. Paths are only linked when the
+/// file exists, which keeps hostnames like `example.com:443` out.
+pub fn detect_links(line: &str, working_directory: &Path) -> Vec<Link> {
+    let mut links = Vec::new();
+
+    for token in token_regex().find_iter(line) {
+        // A leading `(` belongs to the surrounding text, as in `(see foo.rs)`
+        let text = token.as_str().trim_start_matches('(');
+        let start = token.end() - text.len();
+        let text = text.trim_end_matches(TRAILING_PUNCTUATION);
+        if text.is_empty() {
+            continue;
+        }
+
+        if text.starts_with("http://") || text.starts_with("https://") {
+            let url = trim_unbalanced_parens(text);
+            if url.len() > "https://".len() {
+                links.push(Link {
+                    range: start..start + url.len(),
+                    target: LinkTarget::Url(url.to_string()),
+                });
+            }
+            continue;
+        }
+
+        let text = text.trim_end_matches(')').trim_end_matches(TRAILING_PUNCTUATION);
+        if let Some((len, target)) = detect_file(text, working_directory) {
+            links.push(Link {
+                range: start..start + len,
+                target,
+            });
+        }
+    }
+
+    links
+}
+
+/// Characters that end a sentence or list rather than a path or URL.
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?'];
+
+/// Runs of characters that can't be split by quotes, brackets or whitespace.
+fn token_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r#"[^\s"'`<>\[\]{}|]+"#).unwrap())
+}
+
+/// A path, then an optional `:line` or `:line:col`. Windows paths may start
+/// with a drive letter and use either slash.
+fn path_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"^(?P<path>(?:[A-Za-z]:[\\/]|~[\\/]|\.{1,2}[\\/]|[\\/])?(?:[\w@+.-]+[\\/])*[\w@+.-]+)(?::(?P<line>\d+)(?::(?P<column>\d+))?)?",
+        )
+        .unwrap()
+    })
+}
+
+/// Returns the length of the linked text and its target.
+fn detect_file(text: &str, working_directory: &Path) -> Option<(usize, LinkTarget)> {
+    let caps = path_regex().captures(text)?;
+    let whole = caps.get(0)?;
+    // `src/main.rs:12:fn main()` from grep is fine; `src/main.rs#L12` isn't
+    // a path
+    let rest = &text[whole.end()..];
+    if !rest.is_empty() && !rest.starts_with(':') {
+        return None;
+    }
+
+    let path = caps.name("path")?;
+    let raw = path.as_str();
+    let looks_like_path = raw.contains(['/', '\\'])
+        || raw
+            .rsplit_once('.')
+            .is_some_and(|(stem, ext)| !stem.is_empty() && ext.chars().any(|c| c.is_ascii_alphabetic()));
+    if !looks_like_path {
+        return None;
+    }
+
+    let resolved = resolve(raw, working_directory)?;
+    if !resolved.is_file() {
+        return None;
+    }
+
+    let number = |name: &str| caps.name(name).and_then(|m| m.as_str().parse().ok());
+    Some((
+        whole.end(),
+        LinkTarget::File {
+            path: resolved,
+            line: number("line"),
+            column: number("column"),
+        },
+    ))
+}
+
+fn resolve(path: &str, working_directory: &Path) -> Option<PathBuf> {
+    if let Some(rest) = path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        return Some(dirs::home_dir()?.join(rest));
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        Some(working_directory.join(path))
+    }
+}
+
+/// Drops a `)` the URL doesn't open, as in `(see https://example.com)`.
+fn trim_unbalanced_parens(url: &str) -> &str {
+    let mut url = url;
+    while url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+        url = &url[..url.len() - 1];
+    }
+    url.trim_end_matches(TRAILING_PUNCTUATION)
+}
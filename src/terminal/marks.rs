@@ -0,0 +1,172 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Blocks pinned at once; pinning another unpins the one pinned longest ago.
+pub const MAX_PINNED_BLOCKS: usize = 20;
+
+/// Pinned blocks in the order they were pinned.
+#[derive(Debug, Clone)]
+pub struct Pins {
+    order: Vec<Uuid>,
+    capacity: usize,
+}
+
+impl Default for Pins {
+    fn default() -> Self {
+        Self::new(MAX_PINNED_BLOCKS)
+    }
+}
+
+impl Pins {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            order: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Pins `id`, returning the block unpinned to make room for it.
+    pub fn pin(&mut self, id: Uuid) -> Option<Uuid> {
+        if self.contains(id) {
+            return None;
+        }
+        self.order.push(id);
+        (self.order.len() > self.capacity).then(|| self.order.remove(0))
+    }
+
+    pub fn unpin(&mut self, id: Uuid) -> bool {
+        let before = self.order.len();
+        self.order.retain(|pinned| *pinned != id);
+        self.order.len() != before
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.order.contains(&id)
+    }
+
+    /// Oldest pin first.
+    pub fn ids(&self) -> &[Uuid] {
+        &self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+/// Blocks marked with a letter, like marks in vim: `m` and a letter marks
+/// the selected block, `'` and the letter jumps back to it. A block can
+/// have several letters; a letter marks one block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct QuickMarks {
+    marks: BTreeMap<char, Uuid>,
+}
+
+impl QuickMarks {
+    pub fn is_mark_letter(letter: char) -> bool {
+        letter.is_ascii_alphabetic()
+    }
+
+    /// Marks `id` with `letter`, returning the block it marked before.
+    pub fn set(&mut self, letter: char, id: Uuid) -> Result<Option<Uuid>> {
+        if !Self::is_mark_letter(letter) {
+            bail!("Marks are letters, not '{}'", letter);
+        }
+        Ok(self.marks.insert(letter, id).filter(|previous| *previous != id))
+    }
+
+    pub fn get(&self, letter: char) -> Option<Uuid> {
+        self.marks.get(&letter).copied()
+    }
+
+    /// The letters marking `id`, in order.
+    pub fn letters_of(&self, id: Uuid) -> Vec<char> {
+        self.marks
+            .iter()
+            .filter(|(_, marked)| **marked == id)
+            .map(|(letter, _)| *letter)
+            .collect()
+    }
+
+    pub fn remove(&mut self, letter: char) -> Option<Uuid> {
+        self.marks.remove(&letter)
+    }
+
+    /// Drops the marks of blocks `keep` says no to, like cleared ones.
+    pub fn retain_blocks(&mut self, mut keep: impl FnMut(Uuid) -> bool) {
+        self.marks.retain(|_, id| keep(*id));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (char, Uuid)> + '_ {
+        self.marks.iter().map(|(letter, id)| (*letter, *id))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+}
+
+/// What a key does in block navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationCommand {
+    Previous,
+    Next,
+    First,
+    Last,
+    TogglePin,
+    /// Deletes the block, until undone
+    Delete,
+    SetMark(char),
+    JumpToMark(char),
+}
+
+/// Turns typed characters into navigation commands: `k`/`j` move, `g`/`G`
+/// go to the first and last block, `p` pins, `d` deletes, and `m` or `'`
+/// followed by a letter set or jump to a mark.
+#[derive(Debug, Clone, Default)]
+pub struct NavigationKeys {
+    /// `m` or `'`, waiting for its letter
+    pending: Option<char>,
+}
+
+impl NavigationKeys {
+    pub fn press(&mut self, key: char) -> Option<NavigationCommand> {
+        if let Some(pending) = self.pending.take() {
+            // Anything but a letter cancels
+            let command = match pending {
+                'm' => NavigationCommand::SetMark(key),
+                _ => NavigationCommand::JumpToMark(key),
+            };
+            return QuickMarks::is_mark_letter(key).then_some(command);
+        }
+        match key {
+            'k' => Some(NavigationCommand::Previous),
+            'j' => Some(NavigationCommand::Next),
+            'g' => Some(NavigationCommand::First),
+            'G' => Some(NavigationCommand::Last),
+            'p' => Some(NavigationCommand::TogglePin),
+            'd' => Some(NavigationCommand::Delete),
+            'm' | '\'' | '`' => {
+                self.pending = Some(key);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// `m` or `'` when waiting for a mark's letter, for showing it.
+    pub fn pending(&self) -> Option<char> {
+        self.pending
+    }
+
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+}
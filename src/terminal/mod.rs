@@ -1,25 +1,107 @@
+pub mod alias;
+pub mod audit;
 pub mod block;
+pub mod diff;
+pub mod editor;
 pub mod engine;
+pub mod favorites;
 pub mod history;
+pub mod input_history;
+pub mod json_path;
+pub mod links;
+pub mod marks;
+pub mod output_classifier;
+pub mod pipeline;
+pub mod process_usage;
+pub mod project;
+pub mod prompts;
 pub mod pty;
+pub mod quick_fix;
+pub mod recording;
+pub mod remote;
+pub mod shell;
+pub mod stats;
+pub mod structured;
+pub mod table;
+pub mod timeout;
+pub mod title;
+pub mod undo;
 
-pub use block::{Block, CommandBlock};
+pub use block::{Block, BlockType, CommandBlock};
 pub use engine::TerminalEngine;
-pub use pty::PtyManager;
+pub use history::{CommandHistory, HistoryEntry};
+pub use pty::{Hyperlink, PtyManager};
 
+use crate::ai::script;
 use anyhow::Result;
+use block::metadata_keys;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TerminalConfig {
     pub shell: String,
+    /// Run commands in a login shell (`-l`), so setup done only in
+    /// `.bash_profile`, `.zprofile` and the like, such as the `PATH` of
+    /// version managers, applies. Ignored for shells without one
+    pub login_shell: bool,
     pub font_size: f32,
     pub theme: String,
     pub max_history: usize,
     pub enable_vi_mode: bool,
+    pub aliases: HashMap<String, String>,
+    /// Command used to open files, e.g. `code --goto {file}:{line}:{col}`;
+    /// `$VISUAL`/`$EDITOR` when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor_command: Option<String>,
+    /// Output longer than this many lines is shown in a scrollable pager;
+    /// 0 always shows it in full
+    pub pager_threshold_lines: usize,
+    /// Watch output for password and confirmation prompts and offer to
+    /// answer them; commands get a piped stdin for this
+    pub detect_prompts: bool,
+    /// Run, in order, when a session starts, before any project `on_enter`
+    /// commands. Each runs in its own shell, so use a project's `[env]` for
+    /// variables that should stick
+    pub startup_commands: Vec<String>,
+    /// Whether the AI chat replaces the terminal or docks beside it
+    pub layout: PanelLayout,
+    /// Fold the progress noise of known tools (cargo, npm, pytest, ...) and
+    /// color their warnings and errors
+    pub fold_output: bool,
+    /// See `output_classifier::ToolRules`; `output_rules.toml` in the config
+    /// directory when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_rules_path: Option<PathBuf>,
+    /// Show the CPU and memory of running commands, and the processes they
+    /// started, in the status bar and on their blocks, and record their
+    /// peaks
+    pub show_process_usage: bool,
+    /// Complete the flags and subcommands of commands without a spec by
+    /// running `<command> --help`, or reading their man page, in the
+    /// background
+    pub help_completions: bool,
+    /// Seconds a command may run before it's killed, unless it sets its own
+    /// with `@timeout=`; see `timeout`. Interactive PTY sessions have none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Deleted blocks, cleared terminals and cleared inputs Ctrl+Z can bring
+    /// back, newest first; 0 turns undo off
+    pub undo_limit: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelLayout {
+    /// The terminal and the AI chat are separate views
+    #[default]
+    Tabbed,
+    /// The AI chat is a resizable panel on the right of the terminal
+    Split,
 }
 
 impl Default for TerminalConfig {
@@ -30,35 +112,96 @@ impl Default for TerminalConfig {
             } else {
                 "bash".to_string()
             },
+            login_shell: false,
             font_size: 14.0,
             theme: "dark".to_string(),
             max_history: 1000,
             enable_vi_mode: false,
+            aliases: HashMap::new(),
+            editor_command: None,
+            pager_threshold_lines: 500,
+            detect_prompts: true,
+            startup_commands: Vec::new(),
+            layout: PanelLayout::default(),
+            fold_output: true,
+            output_rules_path: None,
+            show_process_usage: true,
+            help_completions: true,
+            timeout: None,
+            undo_limit: undo::DEFAULT_UNDO_LIMIT,
         }
     }
 }
 
+impl TerminalConfig {
+    pub fn output_rules_path(&self) -> PathBuf {
+        self.output_rules_path
+            .clone()
+            .unwrap_or_else(output_classifier::default_rules_path)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum TerminalEvent {
     CommandStarted {
         id: Uuid,
         command: String,
+        /// The command as typed, before alias expansion
+        input: String,
+        working_directory: String,
+        /// Shell, user, hostname and so on; see `block::metadata_keys`
+        metadata: HashMap<String, String>,
     },
     CommandOutput {
         id: Uuid,
         output: String,
         is_stderr: bool,
+        /// Ranges are relative to `output`
+        hyperlinks: Vec<Hyperlink>,
+    },
+    /// A running command seems to be waiting for input; see `prompts`
+    InputRequested {
+        id: Uuid,
+        prompt: String,
+        secret: bool,
     },
     CommandFinished {
         id: Uuid,
         exit_code: i32,
     },
+    /// A command ran past its timeout and was killed; `CommandFinished`
+    /// follows with `timeout::TIMED_OUT_EXIT_CODE`
+    CommandTimedOut {
+        id: Uuid,
+        timeout: std::time::Duration,
+    },
     NewBlock {
         block: Block,
     },
     Error {
         message: String,
     },
+    AliasesChanged {
+        aliases: HashMap<String, String>,
+    },
+    /// The active session moved to another local directory
+    DirectoryChanged {
+        directory: String,
+    },
+    /// The active session connected to, moved on, lost or left a remote host
+    RemoteChanged {
+        remote: Option<remote::RemoteInfo>,
+    },
+    /// A running command set the title with OSC 0 or 2; empty to reset it
+    TitleChanged {
+        id: Uuid,
+        title: String,
+    },
+    /// A pipeline started, or one of its steps started or finished; the
+    /// steps' own blocks come as usual
+    PipelineUpdated {
+        pipeline: pipeline::Pipeline,
+    },
 }
 
 pub type TerminalEventSender = mpsc::UnboundedSender<TerminalEvent>;
@@ -70,9 +213,21 @@ pub struct TerminalSession {
     pub blocks: Vec<Block>,
     pub current_directory: String,
     pub environment: HashMap<String, String>,
+    /// From the trusted `.antraft.toml` of the project the session is in;
+    /// overrides `environment`
+    pub project_env: BTreeMap<String, String>,
+    /// Set while the session's commands run on another host; its directory
+    /// is then tracked there, and `current_directory` stays local
+    pub remote: Option<remote::RemoteInfo>,
     pub is_active: bool,
 }
 
+impl Default for TerminalSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TerminalSession {
     pub fn new() -> Self {
         Self {
@@ -83,6 +238,8 @@ impl TerminalSession {
                 .to_string_lossy()
                 .to_string(),
             environment: std::env::vars().collect(),
+            project_env: BTreeMap::new(),
+            remote: None,
             is_active: true,
         }
     }
@@ -98,4 +255,54 @@ impl TerminalSession {
     pub fn get_block_by_id(&self, id: &Uuid) -> Option<&Block> {
         self.blocks.iter().find(|b| &b.id == id)
     }
+
+    /// The session's successful commands as a script for `shell`: a shebang,
+    /// stop-on-error, and each command with a comment saying when it ran.
+    /// `cd`s are replaced by a `cd` wherever the working directory changes,
+    /// so the script runs the same from anywhere.
+    pub fn export_as_script(&self, shell: &str) -> String {
+        let shell = script::script_shell(shell);
+        let mut lines = vec![
+            script::shebang(&shell).to_string(),
+            format!(
+                "# Exported by ANTRAFT on {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+            ),
+        ];
+        match shell.as_str() {
+            "fish" => {}
+            "powershell" => lines.push("$ErrorActionPreference = 'Stop'".to_string()),
+            _ => lines.push("set -e".to_string()),
+        }
+
+        let mut current_directory: Option<&str> = None;
+        for block in self.blocks.iter().filter(|b| {
+            matches!(b.block_type, BlockType::Command) && b.exit_code == Some(0)
+        }) {
+            let command = block.content.trim();
+            if command.is_empty() || script::is_directory_change(command) {
+                continue;
+            }
+
+            lines.push(String::new());
+            lines.push(format!(
+                "# {}",
+                block.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+            ));
+            let directory = block
+                .get_metadata(metadata_keys::WORKING_DIRECTORY)
+                .map(String::as_str)
+                .unwrap_or_default();
+            if !directory.is_empty() && current_directory != Some(directory) {
+                lines.push(format!("cd {}", script::shell_quote(directory, &shell)));
+                current_directory = Some(directory);
+            }
+            lines.push(command.to_string());
+            if shell == "fish" {
+                lines.push("or exit $status".to_string());
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
 }
@@ -0,0 +1,375 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexSet};
+use serde::Deserialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Consecutive noise lines it takes to fold them.
+pub const MIN_FOLDED_LINES: usize = 3;
+
+/// What a line of output is, to its tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineClass {
+    #[default]
+    Normal,
+    /// Progress and chatter, folded away
+    Noise,
+    Warning,
+    Error,
+}
+
+/// Tags the output lines of the commands it handles.
+pub trait OutputClassifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether it knows the output of `command`, as typed.
+    fn handles(&self, command: &str) -> bool;
+
+    fn classify(&self, line: &str) -> LineClass;
+}
+
+/// The default rules file, `output_rules.toml` next to the config.
+pub fn default_rules_path() -> PathBuf {
+    crate::config::config_dir().join("output_rules.toml")
+}
+
+/// Regex rules for a tool's output, as written in a rules file:
+///
+/// ```toml
+/// [[tools]]
+/// name = "make"
+/// command = '^make\b'
+/// noise = ['^make\[\d+\]: (Entering|Leaving) directory']
+/// warning = [': warning:']
+/// error = [': error:', '^make: \*\*\*']
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolRules {
+    pub name: String,
+    /// A regex matched against the command line
+    pub command: String,
+    #[serde(default)]
+    pub noise: Vec<String>,
+    #[serde(default)]
+    pub warning: Vec<String>,
+    #[serde(default)]
+    pub error: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    tools: Vec<ToolRules>,
+}
+
+/// `ToolRules` ready to run. A line matching several kinds of pattern is
+/// an error over a warning, and a warning over noise.
+#[derive(Debug)]
+pub struct RegexClassifier {
+    name: String,
+    command: Regex,
+    noise: RegexSet,
+    warning: RegexSet,
+    error: RegexSet,
+}
+
+impl RegexClassifier {
+    pub fn new(rules: &ToolRules) -> Result<Self> {
+        let context = |kind: &str| format!("Tool \"{}\": invalid {} pattern", rules.name, kind);
+        Ok(Self {
+            name: rules.name.clone(),
+            command: Regex::new(&rules.command).with_context(|| context("command"))?,
+            noise: RegexSet::new(&rules.noise).with_context(|| context("noise"))?,
+            warning: RegexSet::new(&rules.warning).with_context(|| context("warning"))?,
+            error: RegexSet::new(&rules.error).with_context(|| context("error"))?,
+        })
+    }
+}
+
+impl OutputClassifier for RegexClassifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handles(&self, command: &str) -> bool {
+        self.command.is_match(command.trim_start())
+    }
+
+    fn classify(&self, line: &str) -> LineClass {
+        if self.error.is_match(line) {
+            LineClass::Error
+        } else if self.warning.is_match(line) {
+            LineClass::Warning
+        } else if self.noise.is_match(line) {
+            LineClass::Noise
+        } else {
+            LineClass::Normal
+        }
+    }
+}
+
+/// `ToolRules` for a tool known out of the box.
+struct BuiltinTool {
+    name: &'static str,
+    command: &'static str,
+    noise: &'static [&'static str],
+    warning: &'static [&'static str],
+    error: &'static [&'static str],
+}
+
+/// Tools that run others (like `npx jest`) come first.
+const BUILTIN_TOOLS: &[BuiltinTool] = &[
+    BuiltinTool {
+        name: "jest",
+        command: r"^((npx|yarn|pnpm)\s+)?(jest|vitest)\b",
+        noise: &[r"^\s*PASS\s", r"^\s*[✓√]\s", r"^Determining test suites", r"^\s*RUNS\s"],
+        warning: &[r"^\s*console\.warn\b", r"^\s*○\s"],
+        error: &[r"^\s*FAIL\s", r"^\s*[✕×]\s", r"^\s*●\s", r"^Tests:.*\d+ failed"],
+    },
+    BuiltinTool {
+        name: "npm",
+        command: r"^(npm|npx|yarn|pnpm)\b",
+        noise: &[
+            r"^npm (http|timing|sill|verb|notice)\b",
+            r"^\[\d+/\d+\]\s",
+            r"^info\s",
+            r"^Progress: resolved",
+            r"^[⸨(][#░▓█ ]+",
+            r"^\s*[⠁-⣿]\s",
+        ],
+        warning: &[r"^npm WARN\b", r"^warning\s", r"^\s*WARN\s"],
+        error: &[r"^npm (ERR!|error)\b", r"^error\s", r"^\s*ERR_PNPM_"],
+    },
+    BuiltinTool {
+        name: "pip",
+        command: r"^(pip3?|python3?\s+-m\s+pip|uv\s+pip)\b",
+        noise: &[
+            r"^\s*(Collecting|Downloading|Using cached|Obtaining|Requirement already satisfied|Installing build dependencies|Getting requirements to build|Preparing metadata|Building wheels? for|Created wheel for|Stored in directory|Attempting uninstall|Found existing installation|Uninstalling|Successfully uninstalled)\b",
+            r"^\s+[━╸╺|█]",
+        ],
+        warning: &[r"^WARNING:", r"^DEPRECATION:"],
+        error: &[r"^ERROR:", r"^error:"],
+    },
+    BuiltinTool {
+        name: "pytest",
+        command: r"^(pytest|py\.test|python3?\s+-m\s+pytest)\b",
+        noise: &[
+            r"^\S+\.py\s+[.sxX]+\s*(\[\s*\d+%\])?$",
+            r"^[.sxX]+\s*(\[\s*\d+%\])?$",
+            r"^(platform|rootdir|plugins|cachedir|configfile):? ",
+            r"^collect(ing|ed) ",
+        ],
+        warning: &[r"^=+ warnings summary =+$", r"^\S+:\d+: \w*Warning\b"],
+        error: &[
+            r"^\S+\.py\s+[.sxXFE]*[FE][.sxXFE]*\s*(\[\s*\d+%\])?$",
+            r"^(FAILED|ERROR)\s",
+            r"^E\s",
+            r"^=+ .*\b(failed|errors?)\b.* =+$",
+        ],
+    },
+    BuiltinTool {
+        name: "cargo",
+        command: r"^cargo\b",
+        noise: &[
+            r"^\s*(Compiling|Checking|Downloading|Downloaded|Fresh|Updating|Locking|Adding|Blocking|Documenting|Packaging|Verifying|Installing|Unpacking)\s",
+            r"^\s*Building \[",
+            r"^test .* \.\.\. ok$",
+        ],
+        warning: &[r"^warning(\[\S+\])?:"],
+        error: &[
+            r"^error(\[E\d+\])?:",
+            r"^test .* \.\.\. FAILED$",
+            r"^test result: FAILED",
+            r"^thread '.*' panicked at",
+        ],
+    },
+    BuiltinTool {
+        name: "docker build",
+        command: r"^docker\s+((buildx\s+)?build|compose\s+build|image\s+build)\b",
+        noise: &[
+            r"^#\d+ ",
+            r"^Sending build context",
+            r"^ ---> ",
+            r"^Removing intermediate container",
+            r"^[0-9a-f]{12}: (Pulling|Waiting|Downloading|Extracting|Verifying|Download complete|Pull complete|Already exists)",
+        ],
+        warning: &[r"^(#\d+ )?WARN(ING)?\b"],
+        error: &[r"^(#\d+ )?ERROR\b", r"^error:", r"^The command '.*' returned a non-zero code"],
+    },
+];
+
+/// The classifiers to pick from by command: the user's rules, then the
+/// built-in ones.
+#[derive(Clone)]
+pub struct OutputClassifiers {
+    classifiers: Vec<Arc<dyn OutputClassifier>>,
+}
+
+impl Default for OutputClassifiers {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl OutputClassifiers {
+    pub fn builtin() -> Self {
+        let classifiers = BUILTIN_TOOLS
+            .iter()
+            .filter_map(|tool| {
+                let patterns = |patterns: &[&str]| patterns.iter().map(|pattern| pattern.to_string()).collect();
+                let rules = ToolRules {
+                    name: tool.name.to_string(),
+                    command: tool.command.to_string(),
+                    noise: patterns(tool.noise),
+                    warning: patterns(tool.warning),
+                    error: patterns(tool.error),
+                };
+                RegexClassifier::new(&rules)
+                    .map_err(|e| log::error!("Built-in output rules: {:#}", e))
+                    .ok()
+            })
+            .map(|classifier| Arc::new(classifier) as Arc<dyn OutputClassifier>)
+            .collect();
+        Self { classifiers }
+    }
+
+    /// The built-in classifiers, after the tools in the rules file at `path`
+    /// if there is one.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut classifiers = Self::builtin();
+        if !path.exists() {
+            return Ok(classifiers);
+        }
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let user = Self::parse(&content).with_context(|| format!("In {}", path.display()))?;
+        classifiers.classifiers.splice(0..0, user);
+        Ok(classifiers)
+    }
+
+    fn parse(content: &str) -> Result<Vec<Arc<dyn OutputClassifier>>> {
+        let file: RulesFile = toml::from_str(content)?;
+        file.tools
+            .iter()
+            .map(|rules| Ok(Arc::new(RegexClassifier::new(rules)?) as Arc<dyn OutputClassifier>))
+            .collect()
+    }
+
+    /// Adds a classifier, tried before those already there.
+    pub fn register(&mut self, classifier: Arc<dyn OutputClassifier>) {
+        self.classifiers.insert(0, classifier);
+    }
+
+    pub fn for_command(&self, command: &str) -> Option<Arc<dyn OutputClassifier>> {
+        self.classifiers
+            .iter()
+            .find(|classifier| classifier.handles(command))
+            .cloned()
+    }
+}
+
+/// A command's output lines, classified as they stream in. Lines are
+/// numbered as in the output; only complete lines are classified until the
+/// command finishes.
+#[derive(Clone)]
+pub struct ClassifiedOutput {
+    classifier: Arc<dyn OutputClassifier>,
+    classes: Vec<LineClass>,
+    /// Byte offset in the output up to which lines have been classified
+    scanned: usize,
+    /// Runs of consecutive noise lines
+    noise_runs: Vec<Range<usize>>,
+    errors: usize,
+    warnings: usize,
+}
+
+impl std::fmt::Debug for ClassifiedOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClassifiedOutput")
+            .field("classifier", &self.classifier.name())
+            .field("lines", &self.classes.len())
+            .field("errors", &self.errors)
+            .field("warnings", &self.warnings)
+            .finish()
+    }
+}
+
+impl ClassifiedOutput {
+    pub fn new(classifier: Arc<dyn OutputClassifier>) -> Self {
+        Self {
+            classifier,
+            classes: Vec::new(),
+            scanned: 0,
+            noise_runs: Vec::new(),
+            errors: 0,
+            warnings: 0,
+        }
+    }
+
+    pub fn classifier_name(&self) -> &str {
+        self.classifier.name()
+    }
+
+    /// Classifies the complete lines appended since the last call, and the
+    /// trailing partial line once the command has `finished`.
+    pub fn update(&mut self, output: &str, finished: bool) {
+        if self.scanned > output.len() || !output.is_char_boundary(self.scanned) {
+            *self = Self::new(self.classifier.clone());
+        }
+        let end = if finished {
+            output.len()
+        } else {
+            match output[self.scanned..].rfind('\n') {
+                Some(newline) => self.scanned + newline + 1,
+                None => return,
+            }
+        };
+        for line in output[self.scanned..end].split_inclusive('\n') {
+            let class = self.classifier.classify(line.trim_end_matches(['\n', '\r']));
+            self.push(class);
+        }
+        self.scanned = end;
+    }
+
+    fn push(&mut self, class: LineClass) {
+        let line = self.classes.len();
+        self.classes.push(class);
+        match class {
+            LineClass::Noise => match self.noise_runs.last_mut() {
+                Some(run) if run.end == line => run.end += 1,
+                _ => self.noise_runs.push(line..line + 1),
+            },
+            LineClass::Warning => self.warnings += 1,
+            LineClass::Error => self.errors += 1,
+            LineClass::Normal => {}
+        }
+    }
+
+    /// Forgets the classes from `offset` on, after the output was rewritten
+    /// from there.
+    pub fn rewind(&mut self, offset: usize) {
+        if offset < self.scanned {
+            *self = Self::new(self.classifier.clone());
+        }
+    }
+
+    /// The class of `line`; `Normal` until it's classified.
+    pub fn class(&self, line: usize) -> LineClass {
+        self.classes.get(line).copied().unwrap_or_default()
+    }
+
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.warnings
+    }
+
+    /// Runs of at least `MIN_FOLDED_LINES` noise lines, in order.
+    pub fn folds(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.noise_runs.iter().filter(|run| run.len() >= MIN_FOLDED_LINES).cloned()
+    }
+}
@@ -0,0 +1,94 @@
+use uuid::Uuid;
+
+/// Where a step of a pipeline is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Finished(i32),
+    /// Not run, as an earlier step failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineStep {
+    pub command: String,
+    pub status: StepStatus,
+    /// The block the step runs in, once it has started
+    pub block_id: Option<Uuid>,
+}
+
+/// Commands run one after another as one logical block, each in its own
+/// block too; see `TerminalEngine::run_sequence`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub id: Uuid,
+    pub steps: Vec<PipelineStep>,
+    /// Skip the remaining steps once one fails
+    pub stop_on_error: bool,
+}
+
+impl Pipeline {
+    pub fn new(commands: Vec<String>, stop_on_error: bool) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            steps: commands
+                .into_iter()
+                .map(|command| PipelineStep {
+                    command,
+                    status: StepStatus::Pending,
+                    block_id: None,
+                })
+                .collect(),
+            stop_on_error,
+        }
+    }
+
+    /// The index of the next step to run, if any is left.
+    pub fn next_step(&self) -> Option<usize> {
+        self.steps.iter().position(|step| step.status == StepStatus::Pending)
+    }
+
+    pub fn start_step(&mut self, index: usize, block_id: Uuid) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Running;
+        step.block_id = Some(block_id);
+    }
+
+    /// Records a step's exit code, skipping the rest when it failed and the
+    /// pipeline stops on errors.
+    pub fn finish_step(&mut self, index: usize, exit_code: i32) {
+        self.steps[index].status = StepStatus::Finished(exit_code);
+        if exit_code != 0 && self.stop_on_error {
+            self.skip_remaining();
+        }
+    }
+
+    /// Marks the steps that haven't started as skipped.
+    pub fn skip_remaining(&mut self) {
+        for step in &mut self.steps {
+            if step.status == StepStatus::Pending {
+                step.status = StepStatus::Skipped;
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| matches!(step.status, StepStatus::Finished(_) | StepStatus::Skipped))
+    }
+
+    /// The exit code of the first step that failed, or 0 when all
+    /// succeeded; `None` while steps are left to run.
+    pub fn exit_code(&self) -> Option<i32> {
+        if !self.is_finished() {
+            return None;
+        }
+        let failed = self.steps.iter().find_map(|step| match step.status {
+            StepStatus::Finished(code) if code != 0 => Some(code),
+            _ => None,
+        });
+        Some(failed.unwrap_or(0))
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+use sysinfo::{ProcessRefreshKind, System};
+
+/// A process at one sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub parent: u32,
+    pub cpu_percent: f32,
+    /// Resident memory
+    pub memory_bytes: u64,
+}
+
+/// The CPU and memory of a group of processes together.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProcessUsage {
+    /// Can be over 100 with several cores busy
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub processes: usize,
+}
+
+/// The usage of `roots` and every process started from them, however deep.
+/// Roots that have already exited count for nothing.
+pub fn usage_of(samples: &[ProcessSample], roots: &[u32]) -> ProcessUsage {
+    let by_pid: HashMap<u32, &ProcessSample> = samples.iter().map(|sample| (sample.pid, sample)).collect();
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for sample in samples {
+        children.entry(sample.parent).or_default().push(sample.pid);
+    }
+
+    let mut usage = ProcessUsage::default();
+    let mut seen = HashSet::new();
+    let mut pending: Vec<u32> = roots.iter().copied().filter(|pid| by_pid.contains_key(pid)).collect();
+    while let Some(pid) = pending.pop() {
+        if !seen.insert(pid) {
+            continue;
+        }
+        if let Some(sample) = by_pid.get(&pid) {
+            usage.cpu_percent += sample.cpu_percent;
+            usage.memory_bytes += sample.memory_bytes;
+            usage.processes += 1;
+        }
+        pending.extend(children.get(&pid).into_iter().flatten());
+    }
+    usage
+}
+
+/// The usage of each of `roots` with its own descendants, by root.
+pub fn usage_by_root(samples: &[ProcessSample], roots: &[u32]) -> HashMap<u32, ProcessUsage> {
+    roots.iter().map(|root| (*root, usage_of(samples, &[*root]))).collect()
+}
+
+/// Samples every process, for `usage_of` and `usage_by_root`. Keep one
+/// between samples: a process's CPU is what it used since the previous
+/// `sample`, so it shows 0 the first time it's seen.
+pub struct ProcessSampler {
+    system: System,
+}
+
+impl Default for ProcessSampler {
+    fn default() -> Self {
+        Self { system: System::new() }
+    }
+}
+
+impl ProcessSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every process running now. Samples closer together than
+    /// `sysinfo::MINIMUM_CPU_UPDATE_INTERVAL` give unreliable CPU figures.
+    pub fn sample(&mut self) -> Result<Vec<ProcessSample>> {
+        if !sysinfo::IS_SUPPORTED_SYSTEM {
+            bail!("Process usage isn't available on this system");
+        }
+        self.system
+            .refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
+        Ok(self
+            .system
+            .processes()
+            .values()
+            .map(|process| ProcessSample {
+                pid: process.pid().as_u32(),
+                parent: process.parent().map_or(0, |parent| parent.as_u32()),
+                cpu_percent: process.cpu_usage(),
+                memory_bytes: process.memory(),
+            })
+            .collect())
+    }
+}
+
+/// A command's usage over its run: the latest sample while it runs, and the
+/// peaks it reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageRecord {
+    /// `None` before the first sample, and once its processes have exited
+    pub current: Option<ProcessUsage>,
+    pub peak_cpu_percent: f32,
+    pub peak_memory_bytes: u64,
+    /// CPU time summed from the samples, so an estimate
+    pub cpu_seconds: f64,
+    last_sample: Option<Instant>,
+}
+
+impl UsageRecord {
+    /// Adds a sample taken `at`. An empty one, from processes that exited
+    /// since the last, clears `current` but keeps the peaks.
+    pub fn record(&mut self, usage: ProcessUsage, at: Instant) {
+        if usage.processes == 0 {
+            self.current = None;
+            return;
+        }
+        if let Some(last) = self.last_sample {
+            self.cpu_seconds += f64::from(usage.cpu_percent) / 100.0 * at.saturating_duration_since(last).as_secs_f64();
+        }
+        self.last_sample = Some(at);
+        self.current = Some(usage);
+        self.peak_cpu_percent = self.peak_cpu_percent.max(usage.cpu_percent);
+        self.peak_memory_bytes = self.peak_memory_bytes.max(usage.memory_bytes);
+    }
+
+    /// Whether any sample had processes in it.
+    pub fn is_empty(&self) -> bool {
+        self.last_sample.is_none()
+    }
+}
+
+/// `CPU 320% · 1.2 GB`.
+pub fn format_usage(usage: &ProcessUsage) -> String {
+    format!("CPU {:.0}% · {}", usage.cpu_percent, format_memory(usage.memory_bytes))
+}
+
+/// `820 KB`, `312 MB` or `1.4 GB`.
+pub fn format_memory(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    const GB: u64 = 1024 * MB;
+    match bytes {
+        bytes if bytes >= GB => format!("{:.1} GB", bytes as f64 / GB as f64),
+        bytes if bytes >= MB => format!("{} MB", bytes / MB),
+        bytes => format!("{} KB", bytes / KB),
+    }
+}
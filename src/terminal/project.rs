@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// The project-local file read from the session's directory or the nearest
+/// directory above it.
+pub const PROJECT_FILE: &str = ".antraft.toml";
+
+/// Settings from a project's `.antraft.toml`.
+///
+/// ```toml
+/// on_enter = ["nvm use", "git fetch --quiet"]
+///
+/// [env]
+/// VIRTUAL_ENV = ".venv"
+/// RUST_LOG = "debug"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Run, in order, when a session starts or `cd`s into the project
+    pub on_enter: Vec<String>,
+    /// Set for every command run while in the project
+    pub env: BTreeMap<String, String>,
+}
+
+/// A project file found for a directory, with its raw content so trust can
+/// be checked against exactly what was read.
+#[derive(Debug, Clone)]
+pub struct ProjectFile {
+    pub path: PathBuf,
+    pub content: String,
+    pub config: ProjectConfig,
+}
+
+impl ProjectFile {
+    /// The `.antraft.toml` in `dir` or the nearest directory above it.
+    pub fn find(dir: &Path) -> Result<Option<Self>> {
+        let Some(path) = dir.ancestors().map(|d| d.join(PROJECT_FILE)).find(|p| p.is_file()) else {
+            return Ok(None);
+        };
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let config = toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+        Ok(Some(Self { path, content, config }))
+    }
+}
+
+/// The commands to run when a session starts: `terminal.startup_commands`
+/// first, then the project's `on_enter`.
+pub fn init_commands(startup_commands: &[String], project: Option<&ProjectConfig>) -> Vec<String> {
+    startup_commands
+        .iter()
+        .chain(project.into_iter().flat_map(|project| &project.on_enter))
+        .map(|command| command.trim())
+        .filter(|command| !command.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The environment for a command: the session's, overridden by the project's.
+pub fn merged_env(session: &HashMap<String, String>, project: &BTreeMap<String, String>) -> HashMap<String, String> {
+    let mut env = session.clone();
+    env.extend(project.iter().map(|(key, value)| (key.clone(), value.clone())));
+    env
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trust {
+    Allowed,
+    Denied,
+    /// Never seen, or changed since it was allowed or denied
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrustDecision {
+    /// SHA-256 of the file's content when the decision was made
+    hash: String,
+    allowed: bool,
+}
+
+/// Which project files the user has allowed to run commands, remembered by
+/// content hash like direnv: editing an allowed file asks again.
+#[derive(Debug)]
+pub struct TrustStore {
+    path: PathBuf,
+    decisions: BTreeMap<PathBuf, TrustDecision>,
+}
+
+impl TrustStore {
+    /// A store with no decisions, saved to `path`.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            decisions: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let decisions = if path.exists() {
+            let content =
+                std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&content).with_context(|| format!("Invalid trust store {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            decisions,
+        })
+    }
+
+    pub fn check(&self, file: &ProjectFile) -> Trust {
+        match self.decisions.get(&file.path) {
+            Some(decision) if decision.hash == content_hash(&file.content) => match decision.allowed {
+                true => Trust::Allowed,
+                false => Trust::Denied,
+            },
+            _ => Trust::Unknown,
+        }
+    }
+
+    /// The settings of `file` if it's allowed as it is now; untrusted and
+    /// changed files contribute no commands or environment.
+    pub fn allowed<'a>(&self, file: Option<&'a ProjectFile>) -> Option<&'a ProjectConfig> {
+        file.filter(|file| self.check(file) == Trust::Allowed).map(|file| &file.config)
+    }
+
+    /// Remembers the decision for the file as it is now, and saves the store.
+    pub fn decide(&mut self, file: &ProjectFile, allowed: bool) -> Result<()> {
+        self.decisions.insert(
+            file.path.clone(),
+            TrustDecision {
+                hash: content_hash(&file.content),
+                allowed,
+            },
+        );
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.decisions)?)
+            .with_context(|| format!("Failed to write trust store {}", self.path.display()))
+    }
+}
+
+pub fn default_trust_store_path() -> PathBuf {
+    crate::config::data_dir().join("trusted_projects.json")
+}
+
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
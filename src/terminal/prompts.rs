@@ -0,0 +1,55 @@
+use regex::{Regex, RegexBuilder};
+use std::sync::OnceLock;
+
+/// Prompts are short; a longer partial line is just output without a newline.
+const MAX_PROMPT_LEN: usize = 200;
+
+/// A prompt a command seems to be waiting on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedPrompt {
+    pub text: String,
+    /// Asks for a password or passphrase, so the answer must not be shown
+    pub secret: bool,
+}
+
+/// Password and passphrase prompts, like `[sudo] password for ana: ` or
+/// `Enter passphrase for key '/home/ana/.ssh/id_ed25519': `.
+fn secret_prompt() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        RegexBuilder::new(r"\b(password|passphrase|passcode|pin|one-time code|otp)\b[^\n]*:\s*$")
+            .case_insensitive(true)
+            .build()
+            .unwrap()
+    })
+}
+
+/// Questions, like `Continue? [Y/n]`, `(yes/no/[fingerprint])?` or
+/// `Are you sure you want to continue?`, and `Username for '...': `.
+fn question_prompt() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        RegexBuilder::new(
+            r"(\[y(es)?/n(o)?\]|\(y(es)?/n(o)?[^)]*\))\s*[:?]?\s*$|^\s*are you sure\b.*[?:]\s*$|\b(continue|proceed|overwrite|replace)\b[^\n]*\?\s*$|^\s*(enter\s+)?(username|user name|login|email)\b[^\n]*:\s*$",
+        )
+        .case_insensitive(true)
+        .build()
+        .unwrap()
+    })
+}
+
+/// Checks the last, unfinished line of a command's output for a prompt.
+/// Only call this once the command has stopped writing, since a prompt is
+/// text that isn't followed by a newline.
+pub fn detect_prompt(partial_line: &str) -> Option<DetectedPrompt> {
+    let text = partial_line.trim();
+    if text.is_empty() || text.len() > MAX_PROMPT_LEN {
+        return None;
+    }
+
+    let secret = secret_prompt().is_match(text);
+    (secret || question_prompt().is_match(text)).then(|| DetectedPrompt {
+        text: text.to_string(),
+        secret,
+    })
+}
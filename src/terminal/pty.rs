@@ -1,22 +1,31 @@
+use super::shell::ShellKind;
 use anyhow::Result;
 use log::{debug, error};
 use portable_pty::{CommandBuilder, PtyPair, PtySize, PtySystem};
 use std::io::{Read, Write};
-use std::sync::Arc;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
 pub struct PtyManager {
-    pty_system: Box<dyn PtySystem>,
+    // Wrapped in a mutex so the manager can be shared across tokio tasks
+    pty_system: Mutex<Box<dyn PtySystem + Send>>,
 }
 
 impl PtyManager {
     pub fn new() -> Result<Self> {
         let pty_system = portable_pty::native_pty_system();
-        Ok(Self { pty_system })
+        Ok(Self {
+            pty_system: Mutex::new(pty_system),
+        })
     }
 
     pub fn create_pty(&self, rows: u16, cols: u16, shell: &str) -> Result<PtySession> {
-        let pty_pair = self.pty_system.openpty(PtySize {
+        let pty_system = self
+            .pty_system
+            .lock()
+            .map_err(|_| anyhow::anyhow!("PTY system lock poisoned"))?;
+        let pty_pair = pty_system.openpty(PtySize {
             rows,
             cols,
             pixel_width: 0,
@@ -24,14 +33,7 @@ impl PtyManager {
         })?;
 
         let mut cmd = CommandBuilder::new(shell);
-        
-        if cfg!(windows) {
-            // For Windows PowerShell
-            cmd.args(&["-NoLogo", "-NoExit"]);
-        } else {
-            // For Unix shells
-            cmd.args(&["-i"]); // Interactive mode
-        }
+        cmd.args(ShellKind::detect(shell).interactive_args());
 
         let child = pty_pair.slave.spawn_command(cmd)?;
         
@@ -103,39 +105,239 @@ impl Drop for PtySession {
     }
 }
 
+/// Decodes output that may not be UTF-8 a read at a time, the way
+/// `String::from_utf8_lossy` would: each invalid byte becomes U+FFFD and
+/// a sequence cut off at the end of a read waits for the next one. The
+/// VTE parser on its own drops invalid bytes, and the byte after them.
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    /// The start of a sequence the last read ended in the middle of
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+        let mut text = String::with_capacity(input.len());
+        let mut rest = input.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            self.pending = invalid.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    /// What's left at the end of the output: U+FFFD for a sequence that
+    /// never finished.
+    pub fn finish(&mut self) -> String {
+        match std::mem::take(&mut self.pending).is_empty() {
+            true => String::new(),
+            false => char::REPLACEMENT_CHARACTER.to_string(),
+        }
+    }
+}
+
 // VTE (Virtual Terminal Emulator) parser for handling terminal escape sequences
 pub struct VteProcessor {
     parser: vte::Parser,
+    decoder: Utf8Decoder,
     performer: VtePerformer,
+    /// The last title set with OSC 0 or 2 in `process_to_text`, until taken
+    title: Option<String>,
+    /// Inside an OSC sequence, and whether the last byte was ESC, so a
+    /// sequence missing its terminator can be cut off; see `advance`
+    in_osc: bool,
+    after_escape: bool,
+}
+
+impl Default for VteProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VteProcessor {
     pub fn new() -> Self {
         Self {
             parser: vte::Parser::new(),
+            decoder: Utf8Decoder::default(),
             performer: VtePerformer::new(),
+            title: None,
+            in_osc: false,
+            after_escape: false,
         }
     }
 
+    /// The title the output set with OSC 0 or 2 since the last call, if any.
+    pub fn take_title(&mut self) -> Option<String> {
+        self.title.take()
+    }
+
     pub fn process_bytes(&mut self, bytes: &[u8]) -> Vec<TerminalAction> {
+        let text = self.decoder.decode(bytes);
+        self.advance(&text)
+    }
+
+    fn advance(&mut self, text: &str) -> Vec<TerminalAction> {
         self.performer.clear_actions();
         
-        for byte in bytes {
-            self.parser.advance(&mut self.performer, *byte);
+        for &byte in text.as_bytes() {
+            // An OSC sequence never spans lines. Without this, one that lost
+            // its terminator would swallow the rest of the output
+            if self.in_osc && byte == b'\n' {
+                self.performer.discard_osc = true;
+                self.parser.advance(&mut self.performer, 0x07);
+                self.performer.discard_osc = false;
+            }
+            self.in_osc = match byte {
+                0x07 | 0x18 | 0x1a | b'\n' => false,
+                // ESC ends the sequence, as ST or by starting another
+                0x1b => false,
+                b']' if self.after_escape => true,
+                _ => self.in_osc,
+            };
+            self.after_escape = byte == 0x1b;
+            self.parser.advance(&mut self.performer, byte);
         }
+        // Don't hold back the text of a link that's still open
+        self.performer.flush_hyperlink();
         
         self.performer.take_actions()
     }
+
+    /// Processes `bytes` into plain text for a block: escape sequences are
+    /// dropped, except OSC 8 hyperlinks, which are returned with the range
+    /// of their text. Titles are kept for `take_title`.
+    pub fn process_to_text(&mut self, bytes: &[u8]) -> (String, Vec<Hyperlink>) {
+        let actions = self.process_bytes(bytes);
+        self.actions_to_text(actions)
+    }
+
+    /// Text for a sequence the output ended in the middle of, once it has.
+    pub fn finish_to_text(&mut self) -> (String, Vec<Hyperlink>) {
+        let text = self.decoder.finish();
+        let actions = self.advance(&text);
+        self.actions_to_text(actions)
+    }
+
+    fn actions_to_text(&mut self, actions: Vec<TerminalAction>) -> (String, Vec<Hyperlink>) {
+        let mut text = String::new();
+        let mut hyperlinks = Vec::new();
+        for action in actions {
+            match action {
+                TerminalAction::Print(c) => text.push(c),
+                TerminalAction::LineFeed => {
+                    if text.ends_with('\r') {
+                        text.pop();
+                    }
+                    text.push('\n');
+                }
+                TerminalAction::CarriageReturn => text.push('\r'),
+                TerminalAction::Tab => text.push('\t'),
+                TerminalAction::Backspace if !text.ends_with('\n') => {
+                    text.pop();
+                }
+                TerminalAction::Hyperlink { text: link_text, uri } => {
+                    let start = text.len();
+                    text.push_str(&link_text);
+                    hyperlinks.push(Hyperlink {
+                        range: start..text.len(),
+                        uri,
+                    });
+                }
+                TerminalAction::SetTitle(title) => self.title = Some(title),
+                _ => {}
+            }
+        }
+        (text, hyperlinks)
+    }
+}
+
+/// Appends text from `VteProcessor::process_to_text` to a block's output
+/// the way a terminal shows it: a carriage return not followed by a newline
+/// goes back to the start of the line, and what comes next replaces the
+/// line, so progress bars redrawn with `\r` update in place. A `\r` at the
+/// end of `output` waits there until the next text shows what it was for.
+///
+/// Returns the offset `output` was rewritten from when a line was replaced.
+pub fn append_output(output: &mut String, text: &str) -> Option<usize> {
+    if !text.contains('\r') && !output.ends_with('\r') {
+        output.push_str(text);
+        return None;
+    }
+    let mut rewritten: Option<usize> = None;
+    for c in text.chars() {
+        match c {
+            '\r' => {
+                if !output.ends_with('\r') {
+                    output.push('\r');
+                }
+            }
+            '\n' => {
+                if output.ends_with('\r') {
+                    output.pop();
+                }
+                output.push('\n');
+            }
+            c => {
+                if output.ends_with('\r') {
+                    output.pop();
+                    let line_start = output.rfind('\n').map_or(0, |newline| newline + 1);
+                    output.truncate(line_start);
+                    rewritten = Some(rewritten.map_or(line_start, |offset| offset.min(line_start)));
+                }
+                output.push(c);
+            }
+        }
+    }
+    rewritten
 }
 
 struct VtePerformer {
     actions: Vec<TerminalAction>,
+    /// The URI and text so far of an OSC 8 hyperlink that hasn't been closed
+    hyperlink: Option<(String, String)>,
+    /// Ignore the OSC sequence being ended, which was cut off
+    discard_osc: bool,
 }
 
 impl VtePerformer {
     fn new() -> Self {
         Self {
             actions: Vec::new(),
+            hyperlink: None,
+            discard_osc: false,
+        }
+    }
+
+    /// Emits the open hyperlink's text so far, keeping it open.
+    fn flush_hyperlink(&mut self) {
+        if let Some((uri, text)) = &mut self.hyperlink {
+            if !text.is_empty() {
+                self.actions.push(TerminalAction::Hyperlink {
+                    text: std::mem::take(text),
+                    uri: uri.clone(),
+                });
+            }
         }
     }
 
@@ -148,6 +350,14 @@ impl VtePerformer {
     }
 }
 
+/// An OSC 8 hyperlink in processed output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    /// Byte range of the link's text
+    pub range: Range<usize>,
+    pub uri: String,
+}
+
 #[derive(Debug, Clone)]
 pub enum TerminalAction {
     Print(char),
@@ -164,14 +374,23 @@ pub enum TerminalAction {
     SetItalic(bool),
     SetUnderline(bool),
     Reset,
+    /// Text wrapped in an OSC 8 hyperlink
+    Hyperlink { text: String, uri: String },
+    /// OSC 0 or 2: the window title
+    SetTitle(String),
 }
 
 impl vte::Perform for VtePerformer {
     fn print(&mut self, c: char) {
-        self.actions.push(TerminalAction::Print(c));
+        match &mut self.hyperlink {
+            Some((_, text)) => text.push(c),
+            None => self.actions.push(TerminalAction::Print(c)),
+        }
     }
 
     fn execute(&mut self, byte: u8) {
+        // Keep the link's text in order with the control character
+        self.flush_hyperlink();
         match byte {
             b'\n' => self.actions.push(TerminalAction::LineFeed),
             b'\r' => self.actions.push(TerminalAction::CarriageReturn),
@@ -193,8 +412,33 @@ impl vte::Perform for VtePerformer {
         // End of DCS sequence
     }
 
-    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if self.discard_osc {
+            return;
+        }
         // Handle OSC (Operating System Command) sequences
+        // OSC 8 ; params ; URI opens a hyperlink, and an empty URI closes it.
+        // The URI may itself contain semicolons.
+        let joined = |parts: &[&[u8]]| {
+            parts
+                .iter()
+                .map(|part| String::from_utf8_lossy(part))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        match params {
+            [b"8", _, uri @ ..] => {
+                self.flush_hyperlink();
+                let uri = joined(uri);
+                self.hyperlink = (!uri.is_empty()).then(|| (uri, String::new()));
+            }
+            // OSC 0 sets the icon name and title, OSC 2 just the title
+            [b"0" | b"2", title @ ..] => {
+                let title: String = joined(title).chars().filter(|c| !c.is_control()).collect();
+                self.actions.push(TerminalAction::SetTitle(title));
+            }
+            _ => {}
+        }
     }
 
     fn csi_dispatch(&mut self, params: &vte::Params, _intermediates: &[u8], _ignore: bool, c: char) {
@@ -0,0 +1,426 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Exit status shells give a command they couldn't find.
+pub const COMMAND_NOT_FOUND_EXIT: i32 = 127;
+
+/// Suggestions shown under a failed block at most.
+pub const MAX_QUICK_FIXES: usize = 3;
+
+/// Why a command failed, when a quick fix can help.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// The shell couldn't find `program`. It's the command's own program
+    /// when `typed`, or one a script it ran needed otherwise.
+    ProgramNotFound { program: String, typed: bool },
+    /// `program` exists but doesn't know `subcommand`, like `git sttus`.
+    UnknownSubcommand { program: String, subcommand: String },
+}
+
+/// Shells' messages for a program they couldn't find; the first group is
+/// the program.
+fn not_found_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // zsh: `zsh: command not found: foo`
+            r"command not found: (\S+)",
+            // bash: `bash: foo: command not found`, `./x.sh: line 3: foo: command not found`
+            r"([^\s:]+): command not found",
+            // fish: `fish: Unknown command: foo`
+            r"^fish: Unknown command:? '?([^\s']+)'?",
+            // sh, dash and busybox: `sh: 1: foo: not found`
+            r"^[\w./-]+: (?:line )?(?:\d+: )?([^\s:]+): not found\s*$",
+            // cmd and PowerShell: `'foo' is not recognized as…`
+            r"'([^']+)' is not recognized as",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(&format!("(?m){}", pattern)).unwrap())
+        .collect()
+    })
+}
+
+/// Messages of tools that don't know a subcommand: git, cargo, docker,
+/// kubectl, npm…
+fn unknown_subcommand() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)is not a \S+ command|no such (sub)?command|unknown (sub)?command|unrecognized subcommand")
+            .unwrap()
+    })
+}
+
+/// The words of `command` with their byte ranges, skipping `sudo` and
+/// leading `NAME=value` assignments.
+fn command_words(command: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut rest = command;
+    let mut offset = 0;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        let end = rest[start..].find(char::is_whitespace).map_or(rest.len(), |end| start + end);
+        words.push((offset + start, &rest[start..end]));
+        offset += end;
+        rest = &command[offset..];
+    }
+    let skipped = words
+        .iter()
+        .take_while(|(_, word)| *word == "sudo" || (word.contains('=') && !word.starts_with('=')))
+        .count();
+    words.split_off(skipped)
+}
+
+/// `command` with its `index`th word (as counted by `command_words`)
+/// replaced.
+fn replace_word(command: &str, index: usize, replacement: &str) -> Option<String> {
+    let (start, word) = *command_words(command).get(index)?;
+    Some(format!("{}{}{}", &command[..start], replacement, &command[start + word.len()..]))
+}
+
+/// Works out from a failed command's exit code and output whether it
+/// failed in a way a quick fix can help with.
+pub fn diagnose(command: &str, exit_code: i32, output: &str) -> Option<Failure> {
+    if exit_code == 0 {
+        return None;
+    }
+    let words = command_words(command);
+    let program = words.first()?.1;
+    let missing = not_found_patterns()
+        .iter()
+        .find_map(|pattern| pattern.captures(output))
+        .map(|captures| captures[1].to_string());
+    match missing {
+        Some(missing) => {
+            let typed = missing == program;
+            return Some(Failure::ProgramNotFound { program: missing, typed });
+        }
+        None if exit_code == COMMAND_NOT_FOUND_EXIT => {
+            return Some(Failure::ProgramNotFound {
+                program: program.to_string(),
+                typed: true,
+            });
+        }
+        None => {}
+    }
+    let subcommand = words.get(1)?.1;
+    (!subcommand.starts_with('-') && unknown_subcommand().is_match(output)).then(|| Failure::UnknownSubcommand {
+        program: program.to_string(),
+        subcommand: subcommand.to_string(),
+    })
+}
+
+/// Edits turning `a` into `b`, counting a swap of neighbouring characters
+/// as one edit (optimal string alignment distance).
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in rows[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Edits a word of this length may be off by and still be a typo.
+fn max_typo_distance(word: &str) -> usize {
+    match word.chars().count() {
+        0 | 1 => 0,
+        2..=4 => 1,
+        _ => 2,
+    }
+}
+
+/// Candidates `word` is likely a typo of, closest first; ties keep the
+/// order of `candidates`.
+pub fn closest_matches<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>, limit: usize) -> Vec<&'a str> {
+    let max_distance = max_typo_distance(word);
+    let mut seen = HashSet::new();
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != word && seen.insert(*candidate))
+        .filter_map(|candidate| {
+            let distance = edit_distance(word, candidate);
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    matches.sort_by_key(|(distance, _)| *distance);
+    matches.into_iter().take(limit).map(|(_, candidate)| candidate).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackageManager {
+    Brew,
+    Apt,
+    Dnf,
+    Pacman,
+    Zypper,
+    Apk,
+    Winget,
+    Scoop,
+    Choco,
+    Cargo,
+    Npm,
+    Pip,
+}
+
+impl PackageManager {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Brew => "brew",
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Apk => "apk",
+            PackageManager::Winget => "winget",
+            PackageManager::Scoop => "scoop",
+            PackageManager::Choco => "choco",
+            PackageManager::Cargo => "cargo",
+            PackageManager::Npm => "npm",
+            PackageManager::Pip => "pip",
+        }
+    }
+
+    /// The program whose presence means the manager is installed.
+    fn program(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt-get",
+            other => other.name(),
+        }
+    }
+
+    pub fn install_command(&self, package: &str) -> String {
+        match self {
+            PackageManager::Brew => format!("brew install {}", package),
+            PackageManager::Apt => format!("sudo apt install {}", package),
+            PackageManager::Dnf => format!("sudo dnf install {}", package),
+            PackageManager::Pacman => format!("sudo pacman -S {}", package),
+            PackageManager::Zypper => format!("sudo zypper install {}", package),
+            PackageManager::Apk => format!("sudo apk add {}", package),
+            PackageManager::Winget => format!("winget install {}", package),
+            PackageManager::Scoop => format!("scoop install {}", package),
+            PackageManager::Choco => format!("choco install {}", package),
+            PackageManager::Cargo => format!("cargo install {}", package),
+            PackageManager::Npm => format!("npm install -g {}", package),
+            PackageManager::Pip => format!("pip install {}", package),
+        }
+    }
+}
+
+/// The system package managers of `os` (as in `std::env::consts::OS`)
+/// that `is_installed`, preferred first, then the language ones. macOS and
+/// Windows fall back to Homebrew and winget when none is installed.
+pub fn detect_package_managers(os: &str, is_installed: impl Fn(&str) -> bool) -> Vec<PackageManager> {
+    use PackageManager::*;
+    let (system, fallback): (&[PackageManager], Option<PackageManager>) = match os {
+        "macos" => (&[Brew], Some(Brew)),
+        "windows" => (&[Winget, Scoop, Choco], Some(Winget)),
+        "linux" => (&[Apt, Dnf, Pacman, Zypper, Apk, Brew], None),
+        _ => (&[Brew], None),
+    };
+    let mut managers: Vec<PackageManager> = system
+        .iter()
+        .copied()
+        .filter(|manager| is_installed(manager.program()))
+        .collect();
+    if managers.is_empty() {
+        managers.extend(fallback);
+    }
+    managers.extend([Cargo, Npm, Pip].into_iter().filter(|manager| is_installed(manager.program())));
+    managers
+}
+
+/// The package managers installed on this machine.
+pub fn local_package_managers() -> Vec<PackageManager> {
+    detect_package_managers(std::env::consts::OS, |program| which::which(program).is_ok())
+}
+
+/// Well-known programs and the package providing each, per package manager.
+const PACKAGES: &[(&str, &[(PackageManager, &str)])] = {
+    use PackageManager::*;
+    &[
+        ("rg", &[(Brew, "ripgrep"), (Apt, "ripgrep"), (Dnf, "ripgrep"), (Pacman, "ripgrep"), (Zypper, "ripgrep"), (Apk, "ripgrep"), (Winget, "BurntSushi.ripgrep.MSVC"), (Scoop, "ripgrep"), (Choco, "ripgrep"), (Cargo, "ripgrep")]),
+        ("fd", &[(Brew, "fd"), (Apt, "fd-find"), (Dnf, "fd-find"), (Pacman, "fd"), (Zypper, "fd"), (Apk, "fd"), (Winget, "sharkdp.fd"), (Scoop, "fd"), (Choco, "fd"), (Cargo, "fd-find")]),
+        ("bat", &[(Brew, "bat"), (Apt, "bat"), (Dnf, "bat"), (Pacman, "bat"), (Zypper, "bat"), (Apk, "bat"), (Winget, "sharkdp.bat"), (Scoop, "bat"), (Choco, "bat"), (Cargo, "bat")]),
+        ("eza", &[(Brew, "eza"), (Apt, "eza"), (Dnf, "eza"), (Pacman, "eza"), (Winget, "eza-community.eza"), (Scoop, "eza"), (Cargo, "eza")]),
+        ("delta", &[(Brew, "git-delta"), (Apt, "git-delta"), (Dnf, "git-delta"), (Pacman, "git-delta"), (Winget, "dandavison.delta"), (Scoop, "delta"), (Cargo, "git-delta")]),
+        ("fzf", &[(Brew, "fzf"), (Apt, "fzf"), (Dnf, "fzf"), (Pacman, "fzf"), (Zypper, "fzf"), (Apk, "fzf"), (Winget, "junegunn.fzf"), (Scoop, "fzf"), (Choco, "fzf")]),
+        ("jq", &[(Brew, "jq"), (Apt, "jq"), (Dnf, "jq"), (Pacman, "jq"), (Zypper, "jq"), (Apk, "jq"), (Winget, "jqlang.jq"), (Scoop, "jq"), (Choco, "jq")]),
+        ("yq", &[(Brew, "yq"), (Pacman, "go-yq"), (Apk, "yq"), (Winget, "MikeFarah.yq"), (Scoop, "yq"), (Choco, "yq")]),
+        ("htop", &[(Brew, "htop"), (Apt, "htop"), (Dnf, "htop"), (Pacman, "htop"), (Zypper, "htop"), (Apk, "htop")]),
+        ("tree", &[(Brew, "tree"), (Apt, "tree"), (Dnf, "tree"), (Pacman, "tree"), (Zypper, "tree"), (Apk, "tree"), (Scoop, "tree")]),
+        ("tmux", &[(Brew, "tmux"), (Apt, "tmux"), (Dnf, "tmux"), (Pacman, "tmux"), (Zypper, "tmux"), (Apk, "tmux")]),
+        ("wget", &[(Brew, "wget"), (Apt, "wget"), (Dnf, "wget"), (Pacman, "wget"), (Zypper, "wget"), (Apk, "wget"), (Winget, "JernejSimoncic.Wget"), (Scoop, "wget"), (Choco, "wget")]),
+        ("curl", &[(Brew, "curl"), (Apt, "curl"), (Dnf, "curl"), (Pacman, "curl"), (Zypper, "curl"), (Apk, "curl"), (Winget, "cURL.cURL"), (Scoop, "curl"), (Choco, "curl")]),
+        ("http", &[(Brew, "httpie"), (Apt, "httpie"), (Dnf, "httpie"), (Pacman, "httpie"), (Winget, "HTTPie.HTTPie"), (Scoop, "httpie"), (Pip, "httpie")]),
+        ("git", &[(Brew, "git"), (Apt, "git"), (Dnf, "git"), (Pacman, "git"), (Zypper, "git"), (Apk, "git"), (Winget, "Git.Git"), (Scoop, "git"), (Choco, "git")]),
+        ("gh", &[(Brew, "gh"), (Apt, "gh"), (Dnf, "gh"), (Pacman, "github-cli"), (Zypper, "gh"), (Apk, "github-cli"), (Winget, "GitHub.cli"), (Scoop, "gh"), (Choco, "gh")]),
+        ("make", &[(Brew, "make"), (Apt, "make"), (Dnf, "make"), (Pacman, "make"), (Zypper, "make"), (Apk, "make"), (Scoop, "make"), (Choco, "make")]),
+        ("gcc", &[(Brew, "gcc"), (Apt, "build-essential"), (Dnf, "gcc"), (Pacman, "gcc"), (Zypper, "gcc"), (Apk, "build-base")]),
+        ("node", &[(Brew, "node"), (Apt, "nodejs"), (Dnf, "nodejs"), (Pacman, "nodejs"), (Zypper, "nodejs"), (Apk, "nodejs"), (Winget, "OpenJS.NodeJS"), (Scoop, "nodejs"), (Choco, "nodejs")]),
+        ("npm", &[(Brew, "node"), (Apt, "npm"), (Dnf, "npm"), (Pacman, "npm"), (Zypper, "npm"), (Apk, "npm"), (Winget, "OpenJS.NodeJS"), (Scoop, "nodejs"), (Choco, "nodejs")]),
+        ("yarn", &[(Brew, "yarn"), (Npm, "yarn")]),
+        ("pnpm", &[(Brew, "pnpm"), (Winget, "pnpm.pnpm"), (Npm, "pnpm")]),
+        ("python3", &[(Brew, "python"), (Apt, "python3"), (Dnf, "python3"), (Pacman, "python"), (Zypper, "python3"), (Apk, "python3"), (Winget, "Python.Python.3.12"), (Scoop, "python"), (Choco, "python")]),
+        ("pip3", &[(Brew, "python"), (Apt, "python3-pip"), (Dnf, "python3-pip"), (Pacman, "python-pip"), (Zypper, "python3-pip"), (Apk, "py3-pip")]),
+        ("cargo", &[(Brew, "rust"), (Apt, "cargo"), (Dnf, "cargo"), (Pacman, "rust"), (Zypper, "cargo"), (Apk, "cargo"), (Winget, "Rustlang.Rustup"), (Scoop, "rustup"), (Choco, "rustup.install")]),
+        ("go", &[(Brew, "go"), (Apt, "golang-go"), (Dnf, "golang"), (Pacman, "go"), (Zypper, "go"), (Apk, "go"), (Winget, "GoLang.Go"), (Scoop, "go"), (Choco, "golang")]),
+        ("docker", &[(Brew, "docker"), (Apt, "docker.io"), (Dnf, "moby-engine"), (Pacman, "docker"), (Zypper, "docker"), (Apk, "docker"), (Winget, "Docker.DockerDesktop"), (Choco, "docker-desktop")]),
+        ("kubectl", &[(Brew, "kubectl"), (Dnf, "kubernetes-client"), (Pacman, "kubectl"), (Zypper, "kubernetes-client"), (Apk, "kubectl"), (Winget, "Kubernetes.kubectl"), (Scoop, "kubectl"), (Choco, "kubernetes-cli")]),
+        ("nvim", &[(Brew, "neovim"), (Apt, "neovim"), (Dnf, "neovim"), (Pacman, "neovim"), (Zypper, "neovim"), (Apk, "neovim"), (Winget, "Neovim.Neovim"), (Scoop, "neovim"), (Choco, "neovim")]),
+        ("tldr", &[(Brew, "tlrc"), (Apt, "tldr"), (Dnf, "tldr"), (Pacman, "tealdeer"), (Cargo, "tlrc"), (Npm, "tldr"), (Pip, "tldr")]),
+        ("dig", &[(Brew, "bind"), (Apt, "dnsutils"), (Dnf, "bind-utils"), (Pacman, "bind"), (Zypper, "bind-utils"), (Apk, "bind-tools")]),
+        ("netstat", &[(Apt, "net-tools"), (Dnf, "net-tools"), (Pacman, "net-tools"), (Zypper, "net-tools"), (Apk, "net-tools")]),
+        ("ifconfig", &[(Apt, "net-tools"), (Dnf, "net-tools"), (Pacman, "net-tools"), (Zypper, "net-tools"), (Apk, "net-tools")]),
+        ("trivy", &[(Brew, "trivy"), (Pacman, "trivy"), (Apk, "trivy"), (Scoop, "trivy"), (Choco, "trivy")]),
+        ("unzip", &[(Brew, "unzip"), (Apt, "unzip"), (Dnf, "unzip"), (Pacman, "unzip"), (Zypper, "unzip"), (Apk, "unzip"), (Scoop, "unzip")]),
+    ]
+};
+
+/// Commands installing `program` with `managers`, in their order.
+pub fn install_commands(program: &str, managers: &[PackageManager]) -> Vec<(PackageManager, String)> {
+    let Some((_, packages)) = PACKAGES.iter().find(|(name, _)| *name == program) else {
+        return Vec::new();
+    };
+    managers
+        .iter()
+        .filter_map(|manager| {
+            packages
+                .iter()
+                .find(|(packaged, _)| packaged == manager)
+                .map(|(_, package)| (*manager, manager.install_command(package)))
+        })
+        .collect()
+}
+
+/// The names of the files in the directories on `PATH`, without Windows'
+/// executable extensions.
+pub fn path_programs() -> Vec<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut programs = HashSet::new();
+    for dir in std::env::split_paths(&path) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let program = match cfg!(windows) {
+                true => Path::new(&name).file_stem().map_or(name.clone(), |stem| stem.to_string_lossy().to_string()),
+                false => name,
+            };
+            programs.insert(program);
+        }
+    }
+    programs.into_iter().collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickFixKind {
+    /// The command with a misspelled program or subcommand corrected
+    Correction,
+    Install(PackageManager),
+}
+
+/// A command that may fix a failed one, to put in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickFix {
+    pub kind: QuickFixKind,
+    pub command: String,
+}
+
+impl QuickFix {
+    pub fn description(&self) -> String {
+        match self.kind {
+            QuickFixKind::Correction => "Did you mean this?".to_string(),
+            QuickFixKind::Install(manager) => format!("Install it with {}", manager.name()),
+        }
+    }
+}
+
+/// What quick fixes are picked from.
+#[derive(Debug, Clone, Default)]
+pub struct QuickFixSources {
+    /// Commands that succeeded before, most recent first
+    pub history: Vec<String>,
+    /// Commands autocomplete knows, like `ls` or `git status`
+    pub known_commands: Vec<String>,
+    /// The terminal's own builtins and the user's aliases, which no shell
+    /// or `PATH` knows
+    pub builtins: Vec<String>,
+    /// Programs installed where the command ran
+    pub programs: Vec<String>,
+    /// Package managers to suggest installing missing programs with; empty
+    /// when they aren't known, as in a remote session
+    pub package_managers: Vec<PackageManager>,
+}
+
+/// Up to `MAX_QUICK_FIXES` commands that may fix `command`, which failed
+/// with `failure`.
+pub fn suggest(command: &str, failure: &Failure, sources: &QuickFixSources) -> Vec<QuickFix> {
+    let all_commands = || sources.history.iter().chain(&sources.known_commands);
+    let corrections: Vec<String> = match failure {
+        // Nothing to correct in a path, or when a script needed the program
+        Failure::ProgramNotFound { program, typed } if !typed || program.contains(['/', '\\']) => Vec::new(),
+        Failure::ProgramNotFound { program, .. } => {
+            let candidates = all_commands()
+                .filter_map(|known| known.split_whitespace().next())
+                .chain(sources.builtins.iter().map(String::as_str))
+                .chain(sources.programs.iter().map(String::as_str));
+            closest_matches(program, candidates, MAX_QUICK_FIXES)
+                .into_iter()
+                .filter_map(|program| replace_word(command, 0, program))
+                .collect()
+        }
+        Failure::UnknownSubcommand { program, subcommand } => {
+            let candidates = all_commands().filter_map(|known| {
+                let words = command_words(known);
+                match words.as_slice() {
+                    [(_, known_program), (_, known_subcommand), ..] if known_program == program => {
+                        Some(*known_subcommand)
+                    }
+                    _ => None,
+                }
+            });
+            closest_matches(subcommand, candidates, MAX_QUICK_FIXES)
+                .into_iter()
+                .filter_map(|subcommand| replace_word(command, 1, subcommand))
+                .collect()
+        }
+    };
+    let installs: Vec<QuickFix> = match failure {
+        Failure::ProgramNotFound { program, .. } => install_commands(program, &sources.package_managers)
+            .into_iter()
+            .map(|(manager, command)| QuickFix {
+                kind: QuickFixKind::Install(manager),
+                command,
+            })
+            .collect(),
+        Failure::UnknownSubcommand { .. } => Vec::new(),
+    };
+    let corrections = corrections.into_iter().map(|command| QuickFix {
+        kind: QuickFixKind::Correction,
+        command,
+    });
+    // A program we know how to install was most likely typed right
+    let fixes: Vec<QuickFix> = match installs.is_empty() {
+        true => corrections.collect(),
+        false => installs.into_iter().chain(corrections).collect(),
+    };
+    fixes.into_iter().take(MAX_QUICK_FIXES).collect()
+}
@@ -0,0 +1,491 @@
+use super::block::metadata_keys;
+use super::marks::QuickMarks;
+use super::{Block, Hyperlink, TerminalEvent};
+use crate::ai::redaction::Redactor;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Version of the JSON timeline format; newer files are refused.
+pub const TIMELINE_VERSION: u32 = 1;
+
+const MIN_CAST_WIDTH: usize = 80;
+const MAX_CAST_WIDTH: usize = 240;
+const CAST_HEIGHT: usize = 30;
+
+/// Something that happened in a recorded session.
+#[derive(Debug, Clone)]
+pub enum SessionEvent {
+    Terminal(TerminalEvent),
+    /// A finished message in the AI chat, `role` as the chat shows it
+    AiMessage { role: String, content: String },
+}
+
+/// An event and when it happened, in seconds since recording started.
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    pub time: f64,
+    pub event: SessionEvent,
+}
+
+impl RecordedEvent {
+    pub fn terminal_event(&self) -> Option<&TerminalEvent> {
+        match &self.event {
+            SessionEvent::Terminal(event) => Some(event),
+            SessionEvent::AiMessage { .. } => None,
+        }
+    }
+}
+
+/// The engine events and AI chat messages of a session between "Start
+/// recording" and "Stop recording". Commands aren't re-run for export or
+/// replay; their events are played back as they were.
+#[derive(Debug, Clone)]
+pub struct SessionRecording {
+    pub started_at: DateTime<Utc>,
+    started: Instant,
+    pub shell: String,
+    pub events: Vec<RecordedEvent>,
+    /// Set when the recording is stopped
+    pub duration: Option<f64>,
+    /// Blocks pinned while recording; exports mark them
+    pinned: BTreeSet<Uuid>,
+    /// Quick marks set while recording
+    marks: QuickMarks,
+}
+
+/// The asciicast v2 header line; see
+/// https://docs.asciinema.org/manual/asciicast/v2/
+#[derive(Serialize)]
+struct CastHeader {
+    version: u8,
+    width: usize,
+    height: usize,
+    timestamp: i64,
+    duration: f64,
+    title: String,
+    env: BTreeMap<&'static str, String>,
+}
+
+impl SessionRecording {
+    pub fn start(shell: &str) -> Self {
+        Self {
+            started_at: Utc::now(),
+            started: Instant::now(),
+            shell: shell.to_string(),
+            events: Vec::new(),
+            duration: None,
+            pinned: BTreeSet::new(),
+            marks: QuickMarks::default(),
+        }
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.duration.unwrap_or_else(|| self.started.elapsed().as_secs_f64())
+    }
+
+    /// Keeps the events that show up in the terminal. Prompts are left out,
+    /// and answers to them are never sent as events.
+    pub fn record(&mut self, event: &TerminalEvent) {
+        if self.duration.is_some() {
+            return;
+        }
+        if let TerminalEvent::CommandStarted { .. }
+        | TerminalEvent::CommandOutput { .. }
+        | TerminalEvent::CommandFinished { .. }
+        | TerminalEvent::NewBlock { .. } = event
+        {
+            self.push(SessionEvent::Terminal(event.clone()));
+        }
+    }
+
+    /// Keeps a message of the AI chat once it's complete.
+    pub fn record_ai_message(&mut self, role: &str, content: &str) {
+        if self.duration.is_some() || content.is_empty() {
+            return;
+        }
+        self.push(SessionEvent::AiMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        });
+    }
+
+    fn push(&mut self, event: SessionEvent) {
+        self.events.push(RecordedEvent {
+            time: self.started.elapsed().as_secs_f64(),
+            event,
+        });
+    }
+
+    pub fn set_pinned(&mut self, id: Uuid, pinned: bool) {
+        if self.duration.is_some() {
+            return;
+        }
+        if pinned {
+            self.pinned.insert(id);
+        } else {
+            self.pinned.remove(&id);
+        }
+    }
+
+    pub fn is_pinned(&self, id: Uuid) -> bool {
+        self.pinned.contains(&id)
+    }
+
+    pub fn set_mark(&mut self, letter: char, id: Uuid) {
+        if self.duration.is_none() {
+            let _ = self.marks.set(letter, id);
+        }
+    }
+
+    pub fn marks(&self) -> &QuickMarks {
+        &self.marks
+    }
+
+    /// The command of a pinned block an event starts.
+    fn pinned_command<'a>(&self, event: &'a TerminalEvent) -> Option<&'a str> {
+        let (id, command) = match event {
+            TerminalEvent::CommandStarted { id, input, .. } => (*id, Some(input.as_str())),
+            TerminalEvent::NewBlock { block } => (block.id, block.get_metadata(metadata_keys::COMMAND).map(String::as_str)),
+            _ => return None,
+        };
+        command.filter(|_| self.is_pinned(id))
+    }
+
+    pub fn stop(&mut self) {
+        self.duration = Some(self.elapsed());
+    }
+
+    /// The recording as an asciicast v2 file: a JSON header line, then one
+    /// `[time, "o", data]` line per chunk of terminal output. Commands are
+    /// shown after a `$` prompt, and pinned ones get a `[time, "m", label]`
+    /// marker. With `redactor`, secrets are replaced by placeholders.
+    pub fn to_asciicast(&self, redactor: Option<&Redactor>) -> String {
+        let redact = |text: String| match redactor {
+            Some(redactor) => redactor.redact(&text).text,
+            None => text,
+        };
+        let frames: Vec<(f64, String)> = self
+            .events
+            .iter()
+            .filter_map(|recorded| Some((recorded.time, redact(terminal_text(recorded.terminal_event()?)?))))
+            .collect();
+        let markers: Vec<(f64, String)> = self
+            .events
+            .iter()
+            .filter_map(|recorded| {
+                let command = self.pinned_command(recorded.terminal_event()?)?;
+                Some((recorded.time, redact(format!("📌 {}", command))))
+            })
+            .collect();
+
+        let width = frames
+            .iter()
+            .flat_map(|(_, text)| text.split("\r\n"))
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .clamp(MIN_CAST_WIDTH, MAX_CAST_WIDTH);
+        let header = CastHeader {
+            version: 2,
+            width,
+            height: CAST_HEIGHT,
+            timestamp: self.started_at.timestamp(),
+            duration: round_time(self.elapsed()),
+            title: format!("ANTRAFT session {}", self.started_at.format("%Y-%m-%d %H:%M")),
+            env: BTreeMap::from([("SHELL", self.shell.clone()), ("TERM", "xterm-256color".to_string())]),
+        };
+
+        let mut cast = serde_json::to_string(&header).unwrap_or_default();
+        cast.push('\n');
+        let mut markers = markers.into_iter().peekable();
+        for (time, text) in frames {
+            while let Some((marker_time, label)) = markers.next_if(|(marker_time, _)| *marker_time <= time) {
+                cast.push_str(&json!([round_time(marker_time), "m", label]).to_string());
+                cast.push('\n');
+            }
+            cast.push_str(&json!([round_time(time), "o", text]).to_string());
+            cast.push('\n');
+        }
+        cast
+    }
+
+    /// The commands and their output as plain text, pinned commands marked
+    /// with 📌.
+    pub fn to_transcript(&self, redactor: Option<&Redactor>) -> String {
+        let transcript: String = self
+            .events
+            .iter()
+            .filter_map(|recorded| {
+                let event = recorded.terminal_event()?;
+                let text = strip_escapes(&terminal_text(event)?.replace("\r\n", "\n"));
+                Some(match self.pinned_command(event) {
+                    Some(_) => format!("📌 {}", text),
+                    None => text,
+                })
+            })
+            .collect();
+        match redactor {
+            Some(redactor) => redactor.redact(&transcript).text,
+            None => transcript,
+        }
+    }
+
+    /// The recording as a JSON timeline: every recorded event, AI messages
+    /// included, that `from_timeline` reads back for replay. With
+    /// `redactor`, secrets are replaced by placeholders.
+    pub fn to_timeline(&self, redactor: Option<&Redactor>) -> Result<String> {
+        let redact = |text: &str| match redactor {
+            Some(redactor) => redactor.redact(text).text,
+            None => text.to_string(),
+        };
+        let events = self
+            .events
+            .iter()
+            .filter_map(|recorded| {
+                let event = match &recorded.event {
+                    SessionEvent::Terminal(TerminalEvent::CommandStarted {
+                        id,
+                        command,
+                        input,
+                        working_directory,
+                        metadata,
+                    }) => TimelineEvent::CommandStarted {
+                        id: *id,
+                        command: redact(command),
+                        input: redact(input),
+                        working_directory: working_directory.clone(),
+                        metadata: metadata.clone(),
+                    },
+                    SessionEvent::Terminal(TerminalEvent::CommandOutput { id, output, is_stderr, hyperlinks }) => {
+                        // Redaction moves text around, so links only survive unredacted output
+                        let redacted = redact(output);
+                        let hyperlinks = match redacted == *output {
+                            true => hyperlinks.iter().map(TimelineHyperlink::from).collect(),
+                            false => Vec::new(),
+                        };
+                        TimelineEvent::CommandOutput {
+                            id: *id,
+                            output: redacted,
+                            is_stderr: *is_stderr,
+                            hyperlinks,
+                        }
+                    }
+                    SessionEvent::Terminal(TerminalEvent::CommandFinished { id, exit_code }) => {
+                        TimelineEvent::CommandFinished {
+                            id: *id,
+                            exit_code: *exit_code,
+                        }
+                    }
+                    SessionEvent::Terminal(TerminalEvent::NewBlock { block }) => {
+                        let mut block = block.clone();
+                        block.content = redact(&block.content);
+                        TimelineEvent::NewBlock { block }
+                    }
+                    SessionEvent::Terminal(_) => return None,
+                    SessionEvent::AiMessage { role, content } => TimelineEvent::AiMessage {
+                        role: role.clone(),
+                        content: redact(content),
+                    },
+                };
+                Some(TimelineEntry {
+                    time: round_time(recorded.time),
+                    event,
+                })
+            })
+            .collect();
+        let timeline = Timeline {
+            version: TIMELINE_VERSION,
+            started_at: self.started_at,
+            shell: self.shell.clone(),
+            duration: round_time(self.elapsed()),
+            pinned: self.pinned.iter().copied().collect(),
+            marks: self.marks.clone(),
+            events,
+        };
+        Ok(serde_json::to_string_pretty(&timeline)?)
+    }
+
+    /// Reads a timeline written by `to_timeline`, as a stopped recording.
+    pub fn from_timeline(json: &str) -> Result<Self> {
+        let timeline: Timeline = serde_json::from_str(json).context("Not a session timeline")?;
+        if timeline.version > TIMELINE_VERSION {
+            bail!(
+                "Timeline version {} is newer than this version of ANTRAFT supports ({})",
+                timeline.version,
+                TIMELINE_VERSION
+            );
+        }
+        let events = timeline
+            .events
+            .into_iter()
+            .map(|entry| RecordedEvent {
+                time: entry.time,
+                event: entry.event.into(),
+            })
+            .collect();
+        Ok(Self {
+            started_at: timeline.started_at,
+            started: Instant::now(),
+            shell: timeline.shell,
+            events,
+            duration: Some(timeline.duration),
+            pinned: timeline.pinned.into_iter().collect(),
+            marks: timeline.marks,
+        })
+    }
+}
+
+/// A recording as written by `to_timeline`.
+#[derive(Serialize, Deserialize)]
+struct Timeline {
+    version: u32,
+    started_at: DateTime<Utc>,
+    shell: String,
+    duration: f64,
+    #[serde(default)]
+    pinned: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "QuickMarks::is_empty")]
+    marks: QuickMarks,
+    events: Vec<TimelineEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimelineEntry {
+    time: f64,
+    #[serde(flatten)]
+    event: TimelineEvent,
+}
+
+/// The recorded events, as they're written to a timeline.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TimelineEvent {
+    CommandStarted {
+        id: Uuid,
+        command: String,
+        input: String,
+        working_directory: String,
+        #[serde(default)]
+        metadata: HashMap<String, String>,
+    },
+    CommandOutput {
+        id: Uuid,
+        output: String,
+        #[serde(default)]
+        is_stderr: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        hyperlinks: Vec<TimelineHyperlink>,
+    },
+    CommandFinished {
+        id: Uuid,
+        exit_code: i32,
+    },
+    NewBlock {
+        block: Block,
+    },
+    AiMessage {
+        role: String,
+        content: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimelineHyperlink {
+    start: usize,
+    end: usize,
+    uri: String,
+}
+
+impl From<&Hyperlink> for TimelineHyperlink {
+    fn from(link: &Hyperlink) -> Self {
+        Self {
+            start: link.range.start,
+            end: link.range.end,
+            uri: link.uri.clone(),
+        }
+    }
+}
+
+impl From<TimelineEvent> for SessionEvent {
+    fn from(event: TimelineEvent) -> Self {
+        let event = match event {
+            TimelineEvent::CommandStarted { id, command, input, working_directory, metadata } => {
+                TerminalEvent::CommandStarted { id, command, input, working_directory, metadata }
+            }
+            TimelineEvent::CommandOutput { id, output, is_stderr, hyperlinks } => TerminalEvent::CommandOutput {
+                id,
+                hyperlinks: hyperlinks
+                    .into_iter()
+                    // Out-of-range links would break slicing the output
+                    .filter(|link| link.start <= link.end && output.get(link.start..link.end).is_some())
+                    .map(|link| Hyperlink {
+                        range: link.start..link.end,
+                        uri: link.uri,
+                    })
+                    .collect(),
+                output,
+                is_stderr,
+            },
+            TimelineEvent::CommandFinished { id, exit_code } => TerminalEvent::CommandFinished { id, exit_code },
+            TimelineEvent::NewBlock { block } => TerminalEvent::NewBlock { block },
+            TimelineEvent::AiMessage { role, content } => return SessionEvent::AiMessage { role, content },
+        };
+        SessionEvent::Terminal(event)
+    }
+}
+
+/// What an event put on the terminal, with `\r\n` line endings as a
+/// terminal player expects.
+fn terminal_text(event: &TerminalEvent) -> Option<String> {
+    let text = match event {
+        TerminalEvent::CommandStarted { input, .. } => format!("\x1b[32m$\x1b[0m {}\n", input),
+        TerminalEvent::CommandOutput { output, .. } => output.clone(),
+        TerminalEvent::CommandFinished { exit_code, .. } if *exit_code != 0 => {
+            format!("\x1b[31m[exit {}]\x1b[0m\n", exit_code)
+        }
+        TerminalEvent::NewBlock { block } => {
+            let mut text = match block.get_metadata(metadata_keys::COMMAND) {
+                Some(command) => format!("\x1b[32m$\x1b[0m {}\n", command),
+                None => String::new(),
+            };
+            if !block.content.is_empty() {
+                text.push_str(&block.content);
+                if !block.content.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+            text
+        }
+        _ => return None,
+    };
+    (!text.is_empty()).then(|| text.replace('\n', "\r\n"))
+}
+
+/// Drops the SGR color codes `terminal_text` adds.
+fn strip_escapes(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // ESC [ ... m
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// asciinema writes times with microsecond precision.
+fn round_time(seconds: f64) -> f64 {
+    (seconds * 1_000_000.0).round() / 1_000_000.0
+}
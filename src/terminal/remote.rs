@@ -0,0 +1,288 @@
+use crate::ai::script::{script_shell, shell_quote};
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Exit code `ssh` uses for its own errors, like a refused or dropped
+/// connection.
+pub const SSH_CONNECTION_ERROR: i32 = 255;
+
+/// Seconds `ssh` waits for a host before giving up.
+const CONNECT_TIMEOUT_SECS: u32 = 10;
+
+/// How long a probe like `pwd` may take, connecting included.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wait before the second try at a lost host, doubled after each failure.
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(2);
+
+/// The longest wait between tries at a lost host.
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// The host a session runs its commands on.
+///
+/// Commands are run one at a time with `ssh <host> 'cd <directory> && ...'`,
+/// non-interactively, so the host must accept a key or agent: password
+/// prompts can't be answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteInfo {
+    /// As given to `ssh`: an alias from `~/.ssh/config` or a host name
+    pub host: String,
+    pub user: Option<String>,
+    /// The working directory on the host
+    pub directory: String,
+    /// False once `ssh` failed to reach the host, until a command gets
+    /// through again
+    pub connected: bool,
+}
+
+/// When to try a lost host again on its own: right away, then after
+/// waiting twice as long as before following each failure, up to a minute.
+#[derive(Debug, Clone, Default)]
+pub struct ReconnectBackoff {
+    failures: u32,
+    next_attempt: Option<Instant>,
+}
+
+impl ReconnectBackoff {
+    /// The wait after `failures` tries in a row failed.
+    pub fn delay(failures: u32) -> Duration {
+        match failures {
+            0 => Duration::ZERO,
+            n => RECONNECT_INITIAL_DELAY
+                .saturating_mul(2u32.saturating_pow(n - 1))
+                .min(RECONNECT_MAX_DELAY),
+        }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Whether the next try is due at `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next_attempt.is_none_or(|at| now >= at)
+    }
+
+    /// How long until the next try, `None` once it's due.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.next_attempt
+            .map(|at| at.saturating_duration_since(now))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Records a failed try at `now`, pushing the next one back.
+    pub fn failed(&mut self, now: Instant) {
+        self.failures = self.failures.saturating_add(1);
+        self.next_attempt = Some(now + Self::delay(self.failures));
+    }
+
+    /// Starts over once the host is reached again.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// An entry of a remote directory listing.
+#[derive(Debug, Clone)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub is_directory: bool,
+}
+
+impl RemoteInfo {
+    /// Connects to `target` (`host` or `user@host`), starting in the remote
+    /// home directory.
+    pub async fn connect(target: &str) -> Result<Self> {
+        let (user, host) = match target.split_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host.to_string()),
+            None => (None, target.to_string()),
+        };
+        if host.is_empty() || host.starts_with('-') {
+            bail!("ssh: '{}' isn't a host", target);
+        }
+
+        let mut remote = Self {
+            host,
+            user,
+            directory: String::new(),
+            connected: false,
+        };
+        remote.directory = remote.probe("pwd").await?.trim().to_string();
+        remote.connected = true;
+        Ok(remote)
+    }
+
+    /// `user@host`, or just the host.
+    pub fn label(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    fn ssh_args(&self) -> Vec<String> {
+        vec![
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            format!("ConnectTimeout={}", CONNECT_TIMEOUT_SECS),
+            self.label(),
+        ]
+    }
+
+    /// The command line that runs `command` on the host, in the session's
+    /// remote directory, for the local `shell` to run.
+    pub fn wrap_command(&self, command: &str, shell: &str) -> String {
+        let script = format!("cd {} && {}", shell_quote(&self.directory, "sh"), command);
+        let shell = script_shell(shell);
+        let mut words = vec!["ssh".to_string()];
+        words.extend(self.ssh_args().iter().map(|arg| shell_quote(arg, &shell)));
+        words.push(shell_quote(&script, &shell));
+        words.join(" ")
+    }
+
+    /// Runs `script` on the host without a terminal, returning its stdout.
+    pub async fn probe(&self, script: &str) -> Result<String> {
+        let output = Command::new("ssh")
+            .args(self.ssh_args())
+            .arg(script)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(PROBE_TIMEOUT, output)
+            .await
+            .map_err(|_| anyhow!("ssh {}: timed out", self.label()))?
+            .context("Failed to run ssh")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            match stderr.trim().trim_start_matches("ssh: ") {
+                "" => bail!("ssh {}: exited with {}", self.label(), output.status),
+                message => bail!("ssh {}: {}", self.label(), message),
+            }
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Checks the host can be reached again, from the same directory.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let script = format!("cd {} && pwd", shell_quote(&self.directory, "sh"));
+        self.directory = self.probe(&script).await?.trim().to_string();
+        self.connected = true;
+        Ok(())
+    }
+
+    /// Resolves `cd <path>` from the remote directory, as typed, returning
+    /// the new directory.
+    pub async fn change_directory(&self, path: &str) -> Result<String> {
+        let script = format!("cd {} && cd {} && pwd", shell_quote(&self.directory, "sh"), path);
+        Ok(self.probe(&script).await?.trim().to_string())
+    }
+
+    /// Lists `directory` on the host, directories first.
+    pub async fn list_directory(&self, directory: &str) -> Result<Vec<RemoteEntry>> {
+        let script = format!("cd {} && ls -1Ap", shell_quote(directory, "sh"));
+        let mut entries: Vec<RemoteEntry> = self
+            .probe(&script)
+            .await?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.strip_suffix('/') {
+                Some(name) => RemoteEntry {
+                    name: name.to_string(),
+                    is_directory: true,
+                },
+                None => RemoteEntry {
+                    name: line.to_string(),
+                    is_directory: false,
+                },
+            })
+            .collect();
+        entries.sort_by(|a, b| b.is_directory.cmp(&a.is_directory).then_with(|| a.name.cmp(&b.name)));
+        Ok(entries)
+    }
+}
+
+/// A `Host` from `~/.ssh/config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    /// What to pass to `ssh`
+    pub alias: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+}
+
+impl SshHost {
+    /// `user@hostname` as configured, for showing next to the alias.
+    pub fn destination(&self) -> Option<String> {
+        let hostname = self.hostname.as_ref()?;
+        Some(match &self.user {
+            Some(user) => format!("{}@{}", user, hostname),
+            None => hostname.clone(),
+        })
+    }
+}
+
+pub fn default_ssh_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// The hosts in the user's `~/.ssh/config`, empty if there is none.
+pub fn load_ssh_hosts() -> Vec<SshHost> {
+    default_ssh_config_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|content| parse_ssh_config(&content))
+        .unwrap_or_default()
+}
+
+/// The named hosts in an ssh config, skipping patterns like `*.internal`.
+/// `Include` isn't followed.
+pub fn parse_ssh_config(content: &str) -> Vec<SshHost> {
+    let mut hosts: Vec<SshHost> = Vec::new();
+    // Hosts of the block being read, by index in `hosts`
+    let mut block = 0..0;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((keyword, value)) => (keyword, value.trim_start_matches(|c: char| c.is_whitespace() || c == '=').trim()),
+            None => (line, ""),
+        };
+
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                let start = hosts.len();
+                hosts.extend(
+                    value
+                        .split_whitespace()
+                        .filter(|alias| !alias.contains(['*', '?', '!']))
+                        .map(|alias| SshHost {
+                            alias: alias.to_string(),
+                            hostname: None,
+                            user: None,
+                        }),
+                );
+                block = start..hosts.len();
+            }
+            "match" => block = hosts.len()..hosts.len(),
+            "hostname" => {
+                for host in &mut hosts[block.clone()] {
+                    host.hostname.get_or_insert_with(|| value.to_string());
+                }
+            }
+            "user" => {
+                for host in &mut hosts[block.clone()] {
+                    host.user.get_or_insert_with(|| value.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    hosts
+}
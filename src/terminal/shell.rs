@@ -0,0 +1,237 @@
+use base64::Engine as _;
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+/// The family of the configured shell, which decides how a command is
+/// handed to it and how it spells `cd` and variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    /// sh, bash, zsh, fish, and Git Bash's `bash.exe` on Windows
+    Posix,
+    /// `pwsh` and Windows PowerShell
+    PowerShell,
+    /// `cmd.exe`
+    Cmd,
+    /// `wsl.exe`, running commands in the default Linux distribution
+    Wsl,
+}
+
+/// The arguments running a command with a shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellArgs {
+    /// Passed as separate arguments, quoted by the platform as usual
+    Args(Vec<String>),
+    /// Appended to the command line as it is. `cmd.exe` doesn't split its
+    /// command line the way other programs do, so it can't be quoted for it.
+    Raw(String),
+}
+
+impl ShellArgs {
+    pub fn apply(self, command: &mut tokio::process::Command) {
+        match self {
+            ShellArgs::Args(args) => {
+                command.args(args);
+            }
+            #[cfg(windows)]
+            ShellArgs::Raw(line) => {
+                command.raw_arg(line);
+            }
+            // There's no `cmd.exe` to take it elsewhere
+            #[cfg(not(windows))]
+            ShellArgs::Raw(line) => {
+                command.arg(line);
+            }
+        }
+    }
+}
+
+fn posix_variable() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\$(?:\{(\w+)\}|(\w+))").unwrap())
+}
+
+fn powershell_variable() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?i)\$(?:\{env:(\w+)\}|env:(\w+))").unwrap())
+}
+
+fn cmd_variable() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"%([^%\s=]+)%").unwrap())
+}
+
+impl ShellKind {
+    /// Works out the kind from the shell's program name, with or without a
+    /// directory and `.exe`: `/bin/zsh`, `pwsh`, `C:\Windows\System32\cmd.exe`.
+    /// Anything unknown is taken for a POSIX shell.
+    pub fn detect(shell: &str) -> Self {
+        match program_name(shell).as_str() {
+            "pwsh" | "pwsh-preview" | "powershell" | "powershell_ise" => ShellKind::PowerShell,
+            "cmd" => ShellKind::Cmd,
+            "wsl" => ShellKind::Wsl,
+            _ => ShellKind::Posix,
+        }
+    }
+
+    /// The arguments running `command` and exiting, in a login shell with
+    /// `login` (see `supports_login`).
+    ///
+    /// PowerShell gets it base64-encoded, since the way it rejoins and
+    /// parses `-Command` arguments mangles embedded quotes. `cmd.exe` gets
+    /// `/S /C "command"`, with which it strips just the outer quotes.
+    pub fn command_args(&self, command: &str, login: bool) -> ShellArgs {
+        let args = |args: &[&str]| ShellArgs::Args(args.iter().map(|arg| arg.to_string()).collect());
+        match (self, login) {
+            (ShellKind::Posix, false) => args(&["-c", command]),
+            (ShellKind::Posix, true) => args(&["-l", "-c", command]),
+            (ShellKind::PowerShell, login) => {
+                let encoded = encode_powershell_command(command);
+                // `-Login` has to come first
+                match login {
+                    true => args(&["-Login", "-NoLogo", "-EncodedCommand", &encoded]),
+                    false => args(&["-NoLogo", "-EncodedCommand", &encoded]),
+                }
+            }
+            // cmd.exe has no login profile to read
+            (ShellKind::Cmd, _) => ShellArgs::Raw(format!("/D /S /C \"{}\"", command)),
+            (ShellKind::Wsl, false) => args(&["-e", "sh", "-c", command]),
+            (ShellKind::Wsl, true) => args(&["-e", "sh", "-l", "-c", command]),
+        }
+    }
+
+    /// The arguments starting the shell for a person to type into.
+    pub fn interactive_args(&self) -> Vec<&'static str> {
+        match self {
+            ShellKind::Posix => vec!["-i"],
+            ShellKind::PowerShell => vec!["-NoLogo", "-NoExit"],
+            ShellKind::Cmd => vec!["/K"],
+            ShellKind::Wsl => Vec::new(),
+        }
+    }
+
+    /// How the shell refers to the environment variable `name`.
+    pub fn env_var(&self, name: &str) -> String {
+        match self {
+            ShellKind::Posix | ShellKind::Wsl => format!("${}", name),
+            ShellKind::PowerShell => format!("$env:{}", name),
+            ShellKind::Cmd => format!("%{}%", name),
+        }
+    }
+
+    /// Expands the environment variables in `text` the way the shell
+    /// would, looking them up with `lookup`. `cmd.exe` leaves undefined
+    /// variables as they are; the others expand them to nothing.
+    pub fn expand_env(&self, text: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+        let pattern = match self {
+            ShellKind::Posix | ShellKind::Wsl => posix_variable(),
+            ShellKind::PowerShell => powershell_variable(),
+            ShellKind::Cmd => cmd_variable(),
+        };
+        pattern
+            .replace_all(text, |captures: &Captures| {
+                let name = captures.get(1).or_else(|| captures.get(2)).map_or("", |name| name.as_str());
+                match lookup(name) {
+                    Some(value) => value,
+                    None if *self == ShellKind::Cmd => captures[0].to_string(),
+                    None => String::new(),
+                }
+            })
+            .into_owned()
+    }
+
+    /// The arguments of `command` when it changes directory, empty for a
+    /// bare `cd`. Besides `cd`, `cmd.exe` has `chdir` and `cd..`, and
+    /// PowerShell `Set-Location`, `sl` and `chdir`, in any case.
+    pub fn cd_arguments<'a>(&self, command: &'a str) -> Option<&'a str> {
+        let command = command.trim();
+        // `cd..` and `cd\`, without a space
+        if *self == ShellKind::Cmd
+            && command.get(..2).is_some_and(|cd| cd.eq_ignore_ascii_case("cd"))
+            && command[2..].starts_with(['.', '\\'])
+        {
+            return Some(&command[2..]);
+        }
+        let (word, arguments) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let word = word.to_lowercase();
+        let is_cd = match self {
+            ShellKind::Posix | ShellKind::Wsl => word == "cd",
+            ShellKind::PowerShell => ["cd", "chdir", "sl", "set-location"].contains(&word.as_str()),
+            ShellKind::Cmd => word == "cd" || word == "chdir",
+        };
+        is_cd.then(|| arguments.trim())
+    }
+
+    /// The directory `cd` with `arguments` goes to: unquoted, with
+    /// variables expanded and, outside `cmd.exe`, a leading `~` for home.
+    /// A bare `cd` goes home, except in `cmd.exe`, where it only prints the
+    /// current directory and `None` is returned.
+    pub fn cd_target(&self, arguments: &str, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+        let home = || lookup("HOME").or_else(|| lookup("USERPROFILE"));
+        let mut arguments = arguments.trim();
+        if *self == ShellKind::Cmd {
+            // `/d` also changes drive, which changing directory here does anyway
+            if let Some(rest) = arguments.strip_prefix("/d ").or_else(|| arguments.strip_prefix("/D ")) {
+                arguments = rest.trim_start();
+            }
+        }
+        if arguments.is_empty() {
+            return match self {
+                ShellKind::Cmd => None,
+                _ => home(),
+            };
+        }
+
+        let single_quoted = *self != ShellKind::Cmd && is_quoted(arguments, '\'');
+        let unquoted = match single_quoted || is_quoted(arguments, '"') {
+            true => &arguments[1..arguments.len() - 1],
+            false => arguments,
+        };
+        // Nothing is expanded in single quotes
+        if single_quoted {
+            return Some(unquoted.to_string());
+        }
+        let expanded = self.expand_env(unquoted, &lookup);
+        if *self != ShellKind::Cmd && !is_quoted(arguments, '"') {
+            if let Some(rest) = expanded.strip_prefix('~').filter(|rest| rest.is_empty() || rest.starts_with(['/', '\\'])) {
+                if let Some(home) = home() {
+                    return Some(format!("{}{}", home, rest));
+                }
+            }
+        }
+        Some(expanded)
+    }
+}
+
+/// Whether `shell` can run a command as a login shell, reading
+/// `.bash_profile`, `.zprofile`, `config.fish`'s login sections and the
+/// like. `csh` and `tcsh` only log in with `-l` as their sole argument,
+/// Windows PowerShell has no `-Login` (and reads its profile anyway), and
+/// `cmd.exe` has no login profile.
+pub fn supports_login(shell: &str) -> bool {
+    let name = program_name(shell);
+    match ShellKind::detect(shell) {
+        ShellKind::Posix => !matches!(name.as_str(), "csh" | "tcsh"),
+        ShellKind::PowerShell => name != "powershell" && name != "powershell_ise",
+        ShellKind::Cmd => false,
+        ShellKind::Wsl => true,
+    }
+}
+
+/// `shell`'s program name, lower case and without a directory or `.exe`.
+pub(crate) fn program_name(shell: &str) -> String {
+    let program = shell.trim().rsplit(['/', '\\']).next().unwrap_or_default().to_lowercase();
+    match program.strip_suffix(".exe") {
+        Some(name) => name.to_string(),
+        None => program,
+    }
+}
+
+fn is_quoted(text: &str, quote: char) -> bool {
+    text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote)
+}
+
+/// `command` as `-EncodedCommand` takes it: UTF-16LE, base64-encoded.
+pub fn encode_powershell_command(command: &str) -> String {
+    let bytes: Vec<u8> = command.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
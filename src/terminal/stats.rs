@@ -0,0 +1,148 @@
+use super::history::HistoryEntry;
+use chrono::{DateTime, Duration, Local, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const TOP_COMMANDS: usize = 20;
+const SLOWEST_COMMANDS: usize = 10;
+const DAILY_WINDOW_DAYS: i64 = 30;
+
+/// Tools whose first argument is a subcommand worth tracking separately,
+/// so `git push` and `git status` don't collapse into one `git` bucket.
+const SUBCOMMAND_TOOLS: &[&str] = &[
+    "git", "cargo", "npm", "yarn", "pnpm", "docker", "kubectl", "go", "pip", "brew", "apt",
+    "systemctl",
+];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub count: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub total_duration_ms: u64,
+    pub timed_runs: usize,
+}
+
+impl CommandStats {
+    pub fn success_ratio(&self) -> Option<f32> {
+        let finished = self.successes + self.failures;
+        (finished > 0).then(|| self.successes as f32 / finished as f32)
+    }
+
+    pub fn average_duration_ms(&self) -> Option<u64> {
+        (self.timed_runs > 0).then(|| self.total_duration_ms / self.timed_runs as u64)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageStats {
+    pub total_commands: usize,
+    /// Most frequently used commands, most used first
+    pub top_commands: Vec<CommandStats>,
+    /// Commands with the highest average duration, slowest first
+    pub slowest_commands: Vec<CommandStats>,
+    /// Commands run per local hour of day (index 0 = midnight)
+    pub by_hour: [usize; 24],
+    /// Commands per day for the last 30 days, oldest first
+    pub per_day: Vec<(NaiveDate, usize)>,
+    pub generated_at: DateTime<Utc>,
+}
+
+pub fn aggregate<'a>(entries: impl IntoIterator<Item = &'a HistoryEntry>) -> UsageStats {
+    aggregate_at(entries, Utc::now())
+}
+
+/// Single pass over `entries`; `now` anchors the 30-day window.
+pub fn aggregate_at<'a>(
+    entries: impl IntoIterator<Item = &'a HistoryEntry>,
+    now: DateTime<Utc>,
+) -> UsageStats {
+    let today = now.with_timezone(&Local).date_naive();
+    let window_start = today - Duration::days(DAILY_WINDOW_DAYS - 1);
+
+    let mut per_command: HashMap<String, CommandStats> = HashMap::new();
+    let mut by_hour = [0usize; 24];
+    let mut per_day: HashMap<NaiveDate, usize> = HashMap::new();
+    let mut total_commands = 0;
+
+    for entry in entries {
+        total_commands += 1;
+
+        let key = command_key(&entry.command);
+        let stats = per_command.entry(key.clone()).or_insert_with(|| CommandStats {
+            command: key,
+            ..Default::default()
+        });
+        stats.count += 1;
+        match entry.exit_code {
+            Some(0) => stats.successes += 1,
+            Some(_) => stats.failures += 1,
+            None => {}
+        }
+        if let Some(duration) = entry.execution_time {
+            stats.total_duration_ms += duration;
+            stats.timed_runs += 1;
+        }
+
+        let local = entry.timestamp.with_timezone(&Local);
+        by_hour[local.hour() as usize] += 1;
+        let day = local.date_naive();
+        if day >= window_start && day <= today {
+            *per_day.entry(day).or_insert(0) += 1;
+        }
+    }
+
+    let mut all: Vec<CommandStats> = per_command.into_values().collect();
+
+    all.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.command.cmp(&b.command)));
+    let top_commands = all.iter().take(TOP_COMMANDS).cloned().collect();
+
+    let mut slowest: Vec<CommandStats> = all
+        .into_iter()
+        .filter(|stats| stats.timed_runs > 0)
+        .collect();
+    slowest.sort_by(|a, b| {
+        b.average_duration_ms()
+            .cmp(&a.average_duration_ms())
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    slowest.truncate(SLOWEST_COMMANDS);
+
+    let per_day = (0..DAILY_WINDOW_DAYS)
+        .map(|offset| {
+            let day = window_start + Duration::days(offset);
+            (day, per_day.get(&day).copied().unwrap_or(0))
+        })
+        .collect();
+
+    UsageStats {
+        total_commands,
+        top_commands,
+        slowest_commands: slowest,
+        by_hour,
+        per_day,
+        generated_at: now,
+    }
+}
+
+/// The bucket a command line is counted under: the program name, plus the
+/// subcommand for tools in `SUBCOMMAND_TOOLS`.
+pub fn command_key(command: &str) -> String {
+    let mut tokens = command
+        .split_whitespace()
+        .skip_while(|token| *token == "sudo" || token.contains('='));
+
+    let Some(program) = tokens.next() else {
+        return String::new();
+    };
+    let program = program.rsplit(['/', '\\']).next().unwrap_or(program);
+
+    if SUBCOMMAND_TOOLS.contains(&program) {
+        if let Some(subcommand) = tokens.find(|token| !token.starts_with('-')) {
+            return format!("{} {}", program, subcommand);
+        }
+    }
+
+    program.to_string()
+}
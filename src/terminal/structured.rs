@@ -0,0 +1,196 @@
+use super::table::{Table, MAX_TABLE_LINES};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Largest output parsed for a structured view; bigger output stays text.
+pub const MAX_STRUCTURED_BYTES: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Yaml,
+}
+
+impl StructuredFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+        }
+    }
+}
+
+/// The format output might be in, judged from how it starts: `{` or `[`
+/// for JSON and `---` for YAML. Output over `MAX_STRUCTURED_BYTES` isn't
+/// considered, so nothing big is parsed for nothing.
+pub fn detect_format(output: &str) -> Option<StructuredFormat> {
+    if output.len() > MAX_STRUCTURED_BYTES {
+        return None;
+    }
+    let start = output.trim_start();
+    if start.starts_with(['{', '[']) {
+        Some(StructuredFormat::Json)
+    } else if start.starts_with("---") {
+        Some(StructuredFormat::Yaml)
+    } else {
+        None
+    }
+}
+
+/// Parses output that `detect_format` picked. A stream of several JSON
+/// values, as `jq` prints them, or of several YAML documents becomes an
+/// array of them. Output that doesn't parse in full, or that is only a
+/// scalar, gives `None`.
+pub fn parse_structured(output: &str) -> Option<(StructuredFormat, Value)> {
+    let format = detect_format(output)?;
+    let mut values = match format {
+        StructuredFormat::Json => serde_json::Deserializer::from_str(output)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?,
+        StructuredFormat::Yaml => serde_yaml::Deserializer::from_str(output)
+            .map(Value::deserialize)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?
+            .into_iter()
+            // A trailing `---` makes an empty document
+            .filter(|value| !value.is_null())
+            .collect(),
+    };
+    let value = match values.len() {
+        0 => return None,
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    };
+    matches!(value, Value::Object(_) | Value::Array(_)).then_some((format, value))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Key,
+    String,
+    Number,
+    /// `true`, `false` and `null`
+    Literal,
+    /// Brackets, commas, colons and indentation
+    Punctuation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// `value` as indented JSON split into lines of tokens, for coloring. The
+/// lines joined with newlines are what `serde_json::to_string_pretty` gives.
+pub fn pretty_lines(value: &Value) -> Vec<Vec<Token>> {
+    let mut lines = vec![Vec::new()];
+    write_value(&mut lines, value, 0);
+    lines
+}
+
+fn push(lines: &mut [Vec<Token>], kind: TokenKind, text: impl Into<String>) {
+    if let Some(line) = lines.last_mut() {
+        line.push(Token { kind, text: text.into() });
+    }
+}
+
+fn write_value(lines: &mut Vec<Vec<Token>>, value: &Value, depth: usize) {
+    match value {
+        Value::Null | Value::Bool(_) => push(lines, TokenKind::Literal, value.to_string()),
+        Value::Number(_) => push(lines, TokenKind::Number, value.to_string()),
+        Value::String(_) => push(lines, TokenKind::String, value.to_string()),
+        Value::Array(items) if items.is_empty() => push(lines, TokenKind::Punctuation, "[]"),
+        Value::Object(map) if map.is_empty() => push(lines, TokenKind::Punctuation, "{}"),
+        Value::Array(items) => {
+            push(lines, TokenKind::Punctuation, "[");
+            for (index, item) in items.iter().enumerate() {
+                lines.push(vec![Token {
+                    kind: TokenKind::Punctuation,
+                    text: "  ".repeat(depth + 1),
+                }]);
+                write_value(lines, item, depth + 1);
+                if index + 1 < items.len() {
+                    push(lines, TokenKind::Punctuation, ",");
+                }
+            }
+            lines.push(vec![Token {
+                kind: TokenKind::Punctuation,
+                text: format!("{}]", "  ".repeat(depth)),
+            }]);
+        }
+        Value::Object(map) => {
+            push(lines, TokenKind::Punctuation, "{");
+            for (index, (key, item)) in map.iter().enumerate() {
+                lines.push(vec![Token {
+                    kind: TokenKind::Punctuation,
+                    text: "  ".repeat(depth + 1),
+                }]);
+                push(lines, TokenKind::Key, Value::String(key.clone()).to_string());
+                push(lines, TokenKind::Punctuation, ": ");
+                write_value(lines, item, depth + 1);
+                if index + 1 < map.len() {
+                    push(lines, TokenKind::Punctuation, ",");
+                }
+            }
+            lines.push(vec![Token {
+                kind: TokenKind::Punctuation,
+                text: format!("{}}}", "  ".repeat(depth)),
+            }]);
+        }
+    }
+}
+
+/// Values as indented JSON, one after another like `jq` prints them.
+pub fn to_pretty_json(values: &[&Value]) -> String {
+    values
+        .iter()
+        .map(|value| serde_json::to_string_pretty(value).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// An array of objects whose values are all scalars as a table, with a
+/// column per key in the order the keys first appear. Missing keys and
+/// `null`s are empty cells. `None` for anything else, or for more rows than
+/// `MAX_TABLE_LINES`.
+pub fn flat_table(value: &Value) -> Option<Table> {
+    let items = value.as_array()?;
+    if items.is_empty() || items.len() > MAX_TABLE_LINES {
+        return None;
+    }
+    let mut headers: Vec<String> = Vec::new();
+    for item in items {
+        for (key, value) in item.as_object()? {
+            if matches!(value, Value::Array(_) | Value::Object(_)) {
+                return None;
+            }
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+    }
+    if headers.is_empty() {
+        return None;
+    }
+    let rows = items
+        .iter()
+        .filter_map(Value::as_object)
+        .map(|item| {
+            headers
+                .iter()
+                .map(|key| match item.get(key) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(text)) => text.clone(),
+                    Some(other) => other.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    Some(Table {
+        headers,
+        has_header: true,
+        rows,
+    })
+}
@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+
+/// Lines of output looked at for a table at most; longer output stays text.
+pub const MAX_TABLE_LINES: usize = 2000;
+
+/// Whitespace-aligned output split into columns, like that of `ls -l`, `ps`,
+/// `df` or `kubectl get`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    /// The header row's cells, or `Column 1`, `Column 2`… when the output
+    /// has none
+    pub headers: Vec<String>,
+    pub has_header: bool,
+    /// Every row has a cell per column, empty where the output had nothing
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Row indexes ordered by `column`, comparing numbers and sizes like
+    /// `12K` by value; rows that compare equal keep their order.
+    pub fn sorted_rows(&self, column: usize, descending: bool) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        let cell = |row: usize| self.rows[row].get(column).map_or("", String::as_str);
+        order.sort_by(|&a, &b| {
+            let ordering = compare_cells(cell(a), cell(b));
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        order
+    }
+}
+
+/// A cell's value as a number: plain numbers, percentages and sizes with
+/// a `K`/`M`/`G`/`T` suffix, binary or not.
+fn numeric_value(cell: &str) -> Option<f64> {
+    let cell = cell.trim().trim_end_matches('%');
+    let cell = cell.strip_suffix("iB").or_else(|| cell.strip_suffix('B')).unwrap_or(cell);
+    let cell = cell.strip_suffix('i').unwrap_or(cell);
+    let (number, scale) = match cell.char_indices().last()? {
+        (index, unit) if unit.is_ascii_alphabetic() => {
+            let power = "KMGTP".find(unit.to_ascii_uppercase())? as i32 + 1;
+            (&cell[..index], 1024f64.powi(power))
+        }
+        _ => (cell, 1.0),
+    };
+    number.parse::<f64>().ok().filter(|value| value.is_finite()).map(|value| value * scale)
+}
+
+/// Numbers by value before text, text case-insensitively.
+pub fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (numeric_value(a), numeric_value(b)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b)),
+    }
+}
+
+/// Expands tabs to the next multiple of eight, as a terminal shows them.
+fn expand_tabs(line: &str) -> Vec<char> {
+    let mut chars = Vec::with_capacity(line.len());
+    for c in line.chars() {
+        if c == '\t' {
+            chars.resize((chars.len() / 8 + 1) * 8, ' ');
+        } else {
+            chars.push(c);
+        }
+    }
+    chars
+}
+
+fn cell(line: &[char], start: usize, end: usize) -> String {
+    let end = end.min(line.len());
+    if start >= end {
+        return String::new();
+    }
+    line[start..end].iter().collect::<String>().trim().to_string()
+}
+
+/// A header cell: it has letters and isn't a path or a flag.
+fn is_header_word(word: &str) -> bool {
+    word.chars().any(char::is_alphabetic) && !word.starts_with(['/', '-', '.', '~'])
+}
+
+fn is_upper_case(word: &str) -> bool {
+    word.chars().filter(|c| c.is_alphabetic()).all(char::is_uppercase)
+}
+
+/// Splits `output` into columns at the positions that are blank on every
+/// line. The first line is taken for a header when its words all look like
+/// column names and either they're upper case, as `ps` and `kubectl` print
+/// them, or the rows below don't look like that. Cells spilling past their
+/// column, like the arguments of a command in `ps`, stay in it. Returns
+/// `None` when the output isn't at least two rows of two columns.
+pub fn parse_table(output: &str) -> Option<Table> {
+    let mut lines: Vec<Vec<char>> = Vec::new();
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if lines.len() == MAX_TABLE_LINES {
+            return None;
+        }
+        lines.push(expand_tabs(line.trim_end()));
+    }
+    // `ls -l` starts with the blocks used
+    if lines.first().is_some_and(|line| {
+        let line: String = line.iter().collect();
+        line.strip_prefix("total ").is_some_and(|size| numeric_value(size).is_some())
+    }) {
+        lines.remove(0);
+    }
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let width = lines.iter().map(Vec::len).max()?;
+    let mut blank = vec![true; width];
+    for line in &lines {
+        for (position, c) in line.iter().enumerate() {
+            if !c.is_whitespace() {
+                blank[position] = false;
+            }
+        }
+    }
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let mut start = None;
+    for (position, &is_blank) in blank.iter().chain([&true]).enumerate() {
+        match (start, is_blank) {
+            (None, false) => start = Some(position),
+            (Some(from), true) => {
+                spans.push((from, position));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if spans.len() < 2 {
+        return None;
+    }
+
+    let words = |line: &[char]| -> Vec<String> { spans.iter().map(|&(start, end)| cell(line, start, end)).collect() };
+    let first = words(&lines[0]);
+    let looks_like_header = |cells: &[String]| {
+        cells.iter().filter(|cell| !cell.is_empty()).all(|cell| cell.split_whitespace().all(is_header_word))
+    };
+    let has_header = !first[0].is_empty()
+        && looks_like_header(&first)
+        && (first.iter().all(|cell| is_upper_case(cell))
+            || !lines[1..].iter().all(|line| looks_like_header(&words(line))));
+
+    // Join spans into columns. With a header, spans without a header cell
+    // or without a cell in any row (`Mounted on`) belong to the one before.
+    // Without, the last column is text with spaces, like the file names of
+    // `ls -l`, for as long as it's empty on some rows
+    let data = if has_header { &lines[1..] } else { &lines[..] };
+    let mut columns: Vec<(usize, usize)> = Vec::new();
+    for (index, &span) in spans.iter().enumerate() {
+        let starts_column = !has_header
+            || !first[index].is_empty() && data.iter().any(|line| !cell(line, span.0, span.1).is_empty());
+        match columns.last_mut() {
+            Some(column) if !starts_column => column.1 = span.1,
+            _ => columns.push(span),
+        }
+    }
+    while !has_header && columns.len() > 2 {
+        let (start, end) = columns[columns.len() - 1];
+        if data.iter().all(|line| !cell(line, start, end).is_empty()) {
+            break;
+        }
+        columns.pop();
+        columns.last_mut().unwrap().1 = end;
+    }
+    if columns.len() < 2 {
+        return None;
+    }
+
+    let mut rows: Vec<Vec<String>> = lines
+        .iter()
+        .map(|line| columns.iter().map(|&(start, end)| cell(line, start, end)).collect())
+        .collect();
+    // Rows carry on the first column; a row without it is wrapped text
+    if rows.iter().any(|row| row[0].is_empty()) {
+        return None;
+    }
+    // Without a header to say otherwise, spaces before the last column are
+    // prose that happens to line up
+    let last = columns.len() - 1;
+    if !has_header && rows.iter().any(|row| row[..last].iter().any(|cell| cell.contains(' '))) {
+        return None;
+    }
+    let headers = match has_header {
+        true => rows.remove(0),
+        false => (1..=columns.len()).map(|number| format!("Column {}", number)).collect(),
+    };
+    if rows.is_empty() {
+        return None;
+    }
+    Some(Table {
+        headers,
+        has_header,
+        rows,
+    })
+}
@@ -0,0 +1,95 @@
+//! Time limits on commands: `TerminalConfig::timeout` for every command, or
+//! `@timeout=30s` before one. A command still running at its deadline is
+//! killed, with whatever it started.
+
+use anyhow::{anyhow, bail, Result};
+use std::time::Duration;
+
+/// The prefix giving a command its own timeout.
+pub const PREFIX: &str = "@timeout=";
+
+/// The exit code of a command that timed out, as coreutils' `timeout` uses.
+pub const TIMED_OUT_EXIT_CODE: i32 = 124;
+
+/// Splits `@timeout=<duration>` off the start of `input`, returning the
+/// timeout and the command after it. `@timeout=0` or `@timeout=off` gives
+/// `Some(Duration::ZERO)`, running without the default timeout. Input
+/// without the prefix is returned as it is.
+pub fn split_timeout(input: &str) -> Result<(Option<Duration>, &str)> {
+    let trimmed = input.trim_start();
+    let Some(rest) = trimmed.strip_prefix(PREFIX) else {
+        return Ok((None, input));
+    };
+    let (value, command) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let command = command.trim_start();
+    if command.is_empty() {
+        bail!("No command after {}{}", PREFIX, value);
+    }
+    let timeout = match value {
+        "off" | "none" => Duration::ZERO,
+        value => parse_duration(value)?,
+    };
+    Ok((Some(timeout), command))
+}
+
+/// `command` with the prefix giving it `timeout`, zero for none.
+pub fn with_timeout(command: &str, timeout: Duration) -> String {
+    let value = match timeout.is_zero() {
+        true => "off".to_string(),
+        false => format_timeout(timeout).replace(' ', ""),
+    };
+    format!("{}{} {}", PREFIX, value, command)
+}
+
+/// The timeout a command runs with: its own, else the default in seconds.
+/// `None` when neither is set or it's zero.
+pub fn effective_timeout(own: Option<Duration>, default_secs: Option<u64>) -> Option<Duration> {
+    own.or(default_secs.map(Duration::from_secs))
+        .filter(|timeout| !timeout.is_zero())
+}
+
+/// Parses `500ms`, `30s`, `5m`, `1h` or `1m 30s`. A bare number is
+/// seconds. Durations too long to represent are invalid.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let invalid = || anyhow!("Invalid timeout '{}': use e.g. 30s, 5m or 1h", text);
+    let compact: String = text.split_whitespace().collect();
+    if compact.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = compact.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = compact.as_str();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let amount: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit_end = rest[digits..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |end| digits + end);
+        let part = match &rest[digits..unit_end] {
+            "ms" => Duration::from_millis(amount),
+            "s" => Duration::from_secs(amount),
+            "m" => Duration::from_secs(amount.checked_mul(60).ok_or_else(invalid)?),
+            "h" => Duration::from_secs(amount.checked_mul(3600).ok_or_else(invalid)?),
+            _ => return Err(invalid()),
+        };
+        total = total.checked_add(part).ok_or_else(invalid)?;
+        rest = &rest[unit_end..];
+    }
+    Ok(total)
+}
+
+/// `500ms`, `30s`, `5m`, `1h` or `1m 30s`, as `parse_duration` reads.
+pub fn format_timeout(timeout: Duration) -> String {
+    let secs = timeout.as_secs();
+    if timeout.subsec_millis() != 0 {
+        return format!("{}ms", timeout.as_millis());
+    }
+    match secs {
+        secs if secs >= 3600 && secs % 3600 == 0 => format!("{}h", secs / 3600),
+        secs if secs >= 60 && secs % 60 == 0 => format!("{}m", secs / 60),
+        secs if secs >= 60 => format!("{}m {}s", secs / 60, secs % 60),
+        secs => format!("{}s", secs),
+    }
+}
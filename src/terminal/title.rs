@@ -0,0 +1,89 @@
+use std::path::Path;
+
+/// Longest tab label; longer titles are cut with "…".
+pub const MAX_TAB_LABEL_CHARS: usize = 24;
+
+/// Longest command shown in a title.
+const MAX_COMMAND_CHARS: usize = 60;
+
+/// What a window or tab title describes: the command running in the
+/// session, or where the session is when nothing runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionTitle {
+    Running {
+        command: String,
+        directory: String,
+        /// Set by the command with OSC 0 or 2; replaces the computed title
+        program_title: Option<String>,
+    },
+    Idle {
+        directory: String,
+        branch: Option<String>,
+        /// The host of a remote session
+        remote: Option<String>,
+    },
+}
+
+impl SessionTitle {
+    /// `⏳ npm test — ~/proj` while a command runs, `proj — ⎇ main` when
+    /// idle. Directories under `home` are shown from `~`.
+    pub fn window_title(&self, home: Option<&Path>) -> String {
+        match self {
+            SessionTitle::Running {
+                program_title: Some(title), ..
+            } if !title.trim().is_empty() => title.trim().to_string(),
+            SessionTitle::Running { command, directory, .. } => {
+                let command = command.lines().next().unwrap_or_default().trim();
+                format!("⏳ {} — {}", truncate(command, MAX_COMMAND_CHARS), shorten_home(directory, home))
+            }
+            SessionTitle::Idle { directory, branch, remote } => {
+                let mut title = directory_name(directory, home);
+                if let Some(remote) = remote {
+                    title = format!("🌐 {} — {}", remote, title);
+                }
+                if let Some(branch) = branch {
+                    title.push_str(&format!(" — ⎇ {}", branch));
+                }
+                title
+            }
+        }
+    }
+
+    /// The window title, cut to fit a tab.
+    pub fn tab_label(&self, home: Option<&Path>) -> String {
+        truncate(&self.window_title(home), MAX_TAB_LABEL_CHARS)
+    }
+}
+
+/// `directory` with a leading `home` replaced by `~`.
+pub fn shorten_home(directory: &str, home: Option<&Path>) -> String {
+    let Some(relative) = home.and_then(|home| Path::new(directory).strip_prefix(home).ok()) else {
+        return directory.to_string();
+    };
+    match relative.as_os_str().is_empty() {
+        true => "~".to_string(),
+        false => format!("~/{}", relative.display()),
+    }
+}
+
+/// The last component of `directory`, `~` for `home` itself.
+fn directory_name(directory: &str, home: Option<&Path>) -> String {
+    let shortened = shorten_home(directory, home);
+    if shortened == "~" {
+        return shortened;
+    }
+    Path::new(directory)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(shortened)
+}
+
+/// `text` cut to `max_chars` characters, ending in "…" when cut.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
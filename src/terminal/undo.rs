@@ -0,0 +1,90 @@
+//! Undoing destructive actions on the terminal: deleting blocks, clearing
+//! them all and clearing the command input. Generic over the block type so
+//! the UI's blocks, with their views, come back as they were.
+
+use std::collections::VecDeque;
+
+/// Entries kept when `TerminalConfig::undo_limit` isn't set.
+pub const DEFAULT_UNDO_LIMIT: usize = 20;
+
+/// Something that can be undone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UndoEntry<B> {
+    /// Blocks deleted one at a time or by clearing the terminal, each with
+    /// the index it had, in order
+    RemovedBlocks { blocks: Vec<(usize, B)>, cleared: bool },
+    /// The command input's text before it was cleared
+    ClearedInput { text: String },
+}
+
+/// The most recent destructive actions, newest last. The oldest are dropped
+/// past the limit.
+#[derive(Debug, Clone)]
+pub struct UndoStack<B> {
+    entries: VecDeque<UndoEntry<B>>,
+    limit: usize,
+}
+
+impl<B> UndoStack<B> {
+    /// A stack keeping up to `limit` entries; 0 keeps none.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.entries.len() > limit {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn push(&mut self, entry: UndoEntry<B>) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.entries.len() == self.limit {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// The newest entry, to undo.
+    pub fn pop(&mut self) -> Option<UndoEntry<B>> {
+        self.entries.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Removes the blocks `remove` picks, returning them with the indices they
+/// had, for `restore_blocks`.
+pub fn remove_blocks<B>(blocks: &mut Vec<B>, mut remove: impl FnMut(&B) -> bool) -> Vec<(usize, B)> {
+    let mut removed = Vec::new();
+    let mut kept = Vec::with_capacity(blocks.len());
+    for (index, block) in blocks.drain(..).enumerate() {
+        match remove(&block) {
+            true => removed.push((index, block)),
+            false => kept.push(block),
+        }
+    }
+    *blocks = kept;
+    removed
+}
+
+/// Puts removed blocks back where they were, ahead of blocks added since.
+/// Past the end, when there are fewer blocks now, they go at the end.
+pub fn restore_blocks<B>(blocks: &mut Vec<B>, removed: Vec<(usize, B)>) {
+    for (index, block) in removed {
+        let index = index.min(blocks.len());
+        blocks.insert(index, block);
+    }
+}
@@ -0,0 +1,176 @@
+//! Finding the external programs ANTRAFT runs, the shells and the security
+//! scanners, on `PATH`. The scanners find their binaries here, and
+//! `antraft doctor` reports what it finds the same way.
+
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a program gets to print its version.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A program looked for on `PATH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExternalTool {
+    pub name: &'static str,
+    pub binary: &'static str,
+    /// Arguments that make it print its version; empty for programs, like
+    /// `sh`, that can't be asked
+    pub version_args: &'static [&'static str],
+}
+
+pub const BANDIT: ExternalTool = ExternalTool {
+    name: "Bandit",
+    binary: "bandit",
+    version_args: &["--version"],
+};
+
+pub const SEMGREP: ExternalTool = ExternalTool {
+    name: "Semgrep",
+    binary: "semgrep",
+    version_args: &["--version"],
+};
+
+pub const OSV_SCANNER: ExternalTool = ExternalTool {
+    name: "OSV-Scanner",
+    binary: "osv-scanner",
+    version_args: &["--version"],
+};
+
+pub const TRIVY: ExternalTool = ExternalTool {
+    name: "Trivy",
+    binary: "trivy",
+    version_args: &["--version"],
+};
+
+/// The shells sessions can run, by the names `terminal.shell` takes.
+pub const SHELLS: &[ExternalTool] = &[
+    ExternalTool {
+        name: "bash",
+        binary: "bash",
+        version_args: &["--version"],
+    },
+    ExternalTool {
+        name: "zsh",
+        binary: "zsh",
+        version_args: &["--version"],
+    },
+    ExternalTool {
+        name: "fish",
+        binary: "fish",
+        version_args: &["--version"],
+    },
+    ExternalTool {
+        name: "sh",
+        binary: "sh",
+        version_args: &[],
+    },
+    ExternalTool {
+        name: "pwsh",
+        binary: "pwsh",
+        version_args: &["-NoLogo", "-NoProfile", "-Command", "$PSVersionTable.PSVersion.ToString()"],
+    },
+    ExternalTool {
+        name: "powershell",
+        binary: "powershell",
+        version_args: &["-NoLogo", "-NoProfile", "-Command", "$PSVersionTable.PSVersion.ToString()"],
+    },
+    ExternalTool {
+        name: "cmd",
+        binary: "cmd",
+        version_args: &[],
+    },
+];
+
+/// Where a program was found, and the version it reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedTool {
+    pub path: PathBuf,
+    /// The first line it printed when asked; `None` when it can't be asked,
+    /// fails or takes too long
+    pub version: Option<String>,
+}
+
+/// Looks for programs on a search path, `PATH` unless given another.
+#[derive(Debug, Clone)]
+pub struct ToolDetector {
+    search_path: Option<OsString>,
+}
+
+impl Default for ToolDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolDetector {
+    /// Searches `PATH`.
+    pub fn new() -> Self {
+        Self {
+            search_path: std::env::var_os("PATH"),
+        }
+    }
+
+    /// Searches `search_path`, in `PATH`'s format, instead.
+    pub fn with_search_path(search_path: impl Into<OsString>) -> Self {
+        Self {
+            search_path: Some(search_path.into()),
+        }
+    }
+
+    /// The program `binary` would run, if it's installed.
+    pub fn find(&self, binary: &str) -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        which::which_in(binary, self.search_path.as_ref(), cwd).ok()
+    }
+
+    /// Finds `tool` and asks it for its version.
+    pub fn detect(&self, tool: &ExternalTool) -> Option<DetectedTool> {
+        let path = self.find(tool.binary)?;
+        let version = match tool.version_args.is_empty() {
+            true => None,
+            false => version(&path, tool.version_args),
+        };
+        Some(DetectedTool { path, version })
+    }
+}
+
+/// Runs `path` with `args` and returns the first line it printed, on stdout
+/// or, for tools like older Bandits, stderr. Killed after `VERSION_TIMEOUT`.
+fn version(path: &Path, args: &[&str]) -> Option<String> {
+    let mut child = Command::new(path)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait().ok()? {
+            Some(status) => break status,
+            None if started.elapsed() >= VERSION_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            None => std::thread::sleep(Duration::from_millis(20)),
+        }
+    };
+    if !status.success() {
+        return None;
+    }
+
+    let mut output = String::new();
+    child.stdout.take()?.read_to_string(&mut output).ok()?;
+    if output.trim().is_empty() {
+        child.stderr.take()?.read_to_string(&mut output).ok()?;
+    }
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,236 @@
+//! The theme, with its high-contrast variant, and the widgets that follow
+//! the accessibility settings: still indicators when motion is reduced,
+//! and icon buttons that screen readers can name.
+
+use crate::autocomplete::highlight::HighlightClass;
+use crate::config::AccessibilityConfig;
+use eframe::egui::{self, Color32, Stroke};
+
+/// The colors of the terminal, the chat and the welcome screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub background: Color32,
+    pub text: Color32,
+    /// Descriptions and other secondary text
+    pub muted: Color32,
+    /// The `❯` of the input and the `>` before block commands
+    pub prompt: Color32,
+    /// "You:" in the chat
+    pub user: Color32,
+    /// "AI:" in the chat
+    pub assistant: Color32,
+    /// A command waiting for input
+    pub warning: Color32,
+    /// Failed commands' exit codes
+    pub error: Color32,
+    /// The block being navigated, and keyboard focus
+    pub accent: Color32,
+    /// The block just jumped to
+    pub flash: Color32,
+    pub card: Color32,
+    pub card_hovered: Color32,
+    pub card_border: Color32,
+    pub syntax: SyntaxColors,
+}
+
+/// The colors of commands' bash syntax, in the input and in blocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyntaxColors {
+    pub command: Color32,
+    pub keyword: Color32,
+    pub string: Color32,
+    pub variable: Color32,
+    pub flag: Color32,
+    pub comment: Color32,
+    pub number: Color32,
+    pub operator: Color32,
+}
+
+impl SyntaxColors {
+    pub fn color(&self, class: HighlightClass) -> Color32 {
+        match class {
+            HighlightClass::Command => self.command,
+            HighlightClass::Keyword => self.keyword,
+            HighlightClass::String => self.string,
+            HighlightClass::Variable => self.variable,
+            HighlightClass::Flag => self.flag,
+            HighlightClass::Comment => self.comment,
+            HighlightClass::Number => self.number,
+            HighlightClass::Operator => self.operator,
+        }
+    }
+}
+
+impl Palette {
+    pub const STANDARD: Palette = Palette {
+        background: Color32::from_rgb(16, 16, 20),
+        text: Color32::from_gray(180),
+        muted: Color32::GRAY,
+        prompt: Color32::from_rgb(100, 200, 100),
+        user: Color32::from_rgb(100, 150, 255),
+        assistant: Color32::from_rgb(100, 255, 150),
+        warning: Color32::from_rgb(230, 180, 80),
+        error: Color32::from_rgb(220, 100, 100),
+        accent: Color32::from_rgb(100, 150, 255),
+        flash: Color32::from_rgb(230, 200, 80),
+        card: Color32::from_rgb(30, 30, 35),
+        card_hovered: Color32::from_rgb(40, 40, 45),
+        card_border: Color32::from_rgb(60, 60, 65),
+        syntax: SyntaxColors {
+            command: Color32::from_rgb(120, 170, 255),
+            keyword: Color32::from_rgb(200, 120, 220),
+            string: Color32::from_rgb(150, 200, 110),
+            variable: Color32::from_rgb(230, 180, 80),
+            flag: Color32::from_rgb(110, 190, 210),
+            comment: Color32::GRAY,
+            number: Color32::from_rgb(210, 150, 100),
+            operator: Color32::from_gray(170),
+        },
+    };
+
+    pub const HIGH_CONTRAST: Palette = Palette {
+        background: Color32::BLACK,
+        text: Color32::WHITE,
+        muted: Color32::from_gray(220),
+        prompt: Color32::from_rgb(80, 255, 80),
+        user: Color32::from_rgb(140, 200, 255),
+        assistant: Color32::from_rgb(80, 255, 200),
+        warning: Color32::from_rgb(255, 210, 0),
+        error: Color32::from_rgb(255, 130, 130),
+        accent: Color32::from_rgb(255, 255, 0),
+        flash: Color32::from_rgb(255, 255, 0),
+        card: Color32::BLACK,
+        card_hovered: Color32::from_gray(45),
+        card_border: Color32::WHITE,
+        syntax: SyntaxColors {
+            command: Color32::from_rgb(140, 200, 255),
+            keyword: Color32::from_rgb(255, 150, 255),
+            string: Color32::from_rgb(180, 255, 120),
+            variable: Color32::from_rgb(255, 210, 0),
+            flag: Color32::from_rgb(120, 220, 255),
+            comment: Color32::from_gray(200),
+            number: Color32::from_rgb(255, 180, 120),
+            operator: Color32::WHITE,
+        },
+    };
+
+    pub fn for_settings(settings: &AccessibilityConfig) -> Self {
+        match settings.high_contrast {
+            true => Self::HIGH_CONTRAST,
+            false => Self::STANDARD,
+        }
+    }
+
+    /// The colors text and indicators are drawn in, with their names.
+    pub fn foregrounds(&self) -> [(&'static str, Color32); 9] {
+        [
+            ("text", self.text),
+            ("muted", self.muted),
+            ("prompt", self.prompt),
+            ("user", self.user),
+            ("assistant", self.assistant),
+            ("warning", self.warning),
+            ("error", self.error),
+            ("accent", self.accent),
+            ("flash", self.flash),
+        ]
+    }
+}
+
+fn relative_luminance(color: Color32) -> f32 {
+    let channel = |value: u8| {
+        let value = value as f32 / 255.0;
+        match value <= 0.03928 {
+            true => value / 12.92,
+            false => ((value + 0.055) / 1.055).powf(2.4),
+        }
+    };
+    0.2126 * channel(color.r()) + 0.7152 * channel(color.g()) + 0.0722 * channel(color.b())
+}
+
+/// The WCAG contrast ratio of two colors, from 1 to 21.
+pub fn contrast_ratio(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn reduced_motion_id() -> egui::Id {
+    egui::Id::new("accessibility_reduced_motion")
+}
+
+/// Whether animations should be left out, as `apply_style` last set.
+pub fn reduced_motion(ctx: &egui::Context) -> bool {
+    ctx.data(|data| data.get_temp(reduced_motion_id())).unwrap_or(false)
+}
+
+/// Sets the dark theme, or its high-contrast variant, with text no smaller
+/// than the floor, and animations off when motion is reduced.
+pub fn apply_style(ctx: &egui::Context, settings: &AccessibilityConfig) {
+    let palette = Palette::for_settings(settings);
+    let mut style = egui::Style {
+        visuals: egui::Visuals::dark(),
+        ..Default::default()
+    };
+    let visuals = &mut style.visuals;
+    visuals.window_fill = palette.background;
+    visuals.panel_fill = palette.background;
+    visuals.extreme_bg_color = Color32::from_rgb(12, 12, 15);
+    visuals.faint_bg_color = Color32::from_rgb(20, 20, 24);
+    if settings.high_contrast {
+        visuals.override_text_color = Some(palette.text);
+        visuals.extreme_bg_color = Color32::BLACK;
+        visuals.faint_bg_color = Color32::from_gray(24);
+        visuals.window_stroke = Stroke::new(1.0, palette.card_border);
+        visuals.hyperlink_color = palette.user;
+        visuals.selection.bg_fill = Color32::from_rgb(0, 60, 150);
+        visuals.selection.stroke = Stroke::new(2.0, palette.accent);
+        let widgets = &mut visuals.widgets;
+        for state in [&mut widgets.noninteractive, &mut widgets.inactive, &mut widgets.hovered, &mut widgets.open] {
+            state.fg_stroke = Stroke::new(1.5, palette.text);
+            state.bg_stroke = Stroke::new(1.0, Color32::from_gray(200));
+        }
+        widgets.inactive.weak_bg_fill = Color32::from_gray(30);
+        widgets.hovered.weak_bg_fill = Color32::from_gray(60);
+        // Focused widgets are drawn active, so this is the focus ring
+        widgets.active.fg_stroke = Stroke::new(2.0, palette.text);
+        widgets.active.bg_stroke = Stroke::new(2.5, palette.accent);
+        widgets.active.weak_bg_fill = Color32::from_gray(60);
+    }
+    if settings.reduced_motion {
+        style.animation_time = 0.0;
+    }
+    if settings.min_font_size > 0.0 {
+        for font in style.text_styles.values_mut() {
+            font.size = font.size.max(settings.min_font_size);
+        }
+    }
+    ctx.set_style(style);
+    ctx.data_mut(|data| data.insert_temp(reduced_motion_id(), settings.reduced_motion));
+}
+
+/// A spinner, or a still ⏳ when motion is reduced.
+pub fn spinner(ui: &mut egui::Ui) -> egui::Response {
+    if !reduced_motion(ui.ctx()) {
+        return ui.spinner();
+    }
+    let response = ui.label("⏳").on_hover_text("Working…");
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, "Working"));
+    response
+}
+
+/// A small button showing `icon`, with `label` as its tooltip and as its
+/// name for screen readers.
+pub fn icon_button(ui: &mut egui::Ui, icon: &str, label: &str) -> egui::Response {
+    let response = ui.small_button(icon).on_hover_text(label);
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, label));
+    response
+}
+
+/// Outlines a widget that paints itself while it has keyboard focus.
+pub fn focus_ring(ui: &egui::Ui, response: &egui::Response, rounding: f32) {
+    if response.has_focus() {
+        let stroke = ui.visuals().selection.stroke;
+        ui.painter()
+            .rect_stroke(response.rect.expand(2.0), rounding, Stroke::new(stroke.width.max(2.0), stroke.color));
+    }
+}
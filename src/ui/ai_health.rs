@@ -0,0 +1,111 @@
+use super::AnTraftApp;
+use crate::ai::GeminiClient;
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// How often a working setup is checked again.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Failed checks are retried sooner, so a fixed network shows up quickly.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Health {
+    Unknown,
+    Checking,
+    Healthy,
+    Failing(String),
+}
+
+/// Whether the configured API key and model work, checked in the background
+/// on startup, whenever either changes, and every few minutes.
+pub struct AiHealth {
+    health: Health,
+    /// The (API key, model) the last check was for
+    checked: Option<(String, String)>,
+    checked_at: Option<Instant>,
+    check_rx: Option<crossbeam_channel::Receiver<Result<(), String>>>,
+}
+
+impl Default for AiHealth {
+    fn default() -> Self {
+        Self {
+            health: Health::Unknown,
+            checked: None,
+            checked_at: None,
+            check_rx: None,
+        }
+    }
+}
+
+impl AiHealth {
+    fn is_due(&self, key: &(String, String)) -> bool {
+        let interval = match self.health {
+            Health::Failing(_) => RETRY_INTERVAL,
+            _ => RECHECK_INTERVAL,
+        };
+        self.checked.as_ref() != Some(key) || self.checked_at.is_none_or(|at| at.elapsed() >= interval)
+    }
+}
+
+impl AnTraftApp {
+    fn check_ai_health(&mut self) {
+        let config = self.config.ai.clone();
+        self.ai_health.checked = Some((config.api_key.clone(), config.model.clone()));
+        self.ai_health.checked_at = Some(Instant::now());
+        self.ai_health.health = Health::Checking;
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.ai_health.check_rx = Some(rx);
+        // Checked with the settings as they are now, rather than through the
+        // agent, which picks up changes asynchronously
+        self.spawn_reported("The AI health check", async move {
+            let result = GeminiClient::new(config).check_health().await;
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    /// Starts a check when one is due and collects its result.
+    pub(super) fn poll_ai_health(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.ai_health.check_rx {
+            match rx.try_recv() {
+                Ok(Ok(())) => self.ai_health.health = Health::Healthy,
+                Ok(Err(e)) => {
+                    log::warn!("AI health check failed: {}", e);
+                    self.ai_health.health = Health::Failing(e);
+                }
+                Err(crossbeam_channel::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(Duration::from_millis(250));
+                    return;
+                }
+                Err(crossbeam_channel::TryRecvError::Disconnected) => self.ai_health.health = Health::Unknown,
+            }
+            self.ai_health.check_rx = None;
+        }
+
+        let key = (self.config.ai.api_key.clone(), self.config.ai.model.clone());
+        if self.ai_health.is_due(&key) {
+            self.check_ai_health();
+        } else {
+            ctx.request_repaint_after(RETRY_INTERVAL);
+        }
+    }
+
+    /// The model name with a dot showing whether the AI is reachable.
+    /// Clicking it checks again.
+    pub(super) fn render_ai_health(&mut self, ui: &mut egui::Ui) {
+        let (color, hover) = match &self.ai_health.health {
+            Health::Unknown => (egui::Color32::GRAY, "Not checked yet".to_string()),
+            Health::Checking => (egui::Color32::from_rgb(220, 180, 80), "Checking…".to_string()),
+            Health::Healthy => (egui::Color32::from_rgb(100, 200, 100), "AI backend reachable".to_string()),
+            Health::Failing(e) => (egui::Color32::from_rgb(220, 100, 100), e.clone()),
+        };
+        let text = egui::RichText::new(format!("● {}", self.config.ai.model));
+        let response = ui
+            .add(egui::Button::new(text.color(color)).frame(false))
+            .on_hover_text(format!("{}\nClick to check again", hover));
+        if response.clicked() && self.ai_health.check_rx.is_none() {
+            self.check_ai_health();
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use super::{accessibility, AnTraftApp};
+use crate::ai::tools::{self, ToolStep};
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// Role of chat entries showing a tool the AI ran.
+pub const TOOL_ROLE: &str = "Tool";
+
+/// Role of the warning above a reply whose request included content that
+/// looked like a prompt injection.
+pub const INJECTION_ROLE: &str = "Warning";
+
+/// Show "(still waiting...)" when nothing has arrived for this long.
+const STILL_WAITING_AFTER: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone)]
+pub enum AiStreamEvent {
+    Chunk(String),
+    ToolStep(ToolStep),
+    /// What `injection::detect_injection` found in the content sent
+    InjectionSuspected(Vec<String>),
+    Finished(Result<(), String>),
+}
+
+/// An AI response that is still streaming into `ai_messages[index]`.
+pub struct PendingAiMessage {
+    pub index: usize,
+    /// Place in the AI request queue
+    pub ticket: u64,
+    started: Instant,
+    last_chunk: Option<Instant>,
+    chars: usize,
+    task: tokio::task::JoinHandle<()>,
+    events: crossbeam_channel::Receiver<AiStreamEvent>,
+}
+
+impl AnTraftApp {
+    /// Streams the reply to `message` into a new AI message.
+    pub(super) fn start_ai_stream(&mut self, message: String) {
+        if let Some(pending) = self.pending_ai_message.take() {
+            pending.task.abort();
+        }
+
+        self.ai_messages.push(("AI".to_string(), String::new()));
+        let index = self.ai_messages.len() - 1;
+
+        let queue_ticket = self.ai_queue.enqueue();
+        let ticket = queue_ticket.id();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let ai_agent = self.ai_agent.clone();
+        let task = if self.config.ai.enable_tools && !self.config.ai.api_key.is_empty() {
+            // Tool replies are parsed whole, so the answer arrives in one chunk
+            let engine = self.terminal_engine.clone();
+            let cwd = self.working_directory.clone();
+            self.spawn_reported("The AI chat", async move {
+                let step_tx = tx.clone();
+                let result = ai_agent
+                    .read()
+                    .await
+                    .chat_with_tools(
+                        queue_ticket,
+                        &message,
+                        |call| {
+                            let engine = engine.clone();
+                            let cwd = cwd.clone();
+                            async move { tools::execute_tool(&engine, call, &cwd).await }
+                        },
+                        move |step| {
+                            let _ = step_tx.send(AiStreamEvent::ToolStep(step.clone()));
+                        },
+                    )
+                    .await;
+                let result = result.map(|response| {
+                    if !response.injection_signals.is_empty() {
+                        let _ = tx.send(AiStreamEvent::InjectionSuspected(response.injection_signals));
+                    }
+                    let _ = tx.send(AiStreamEvent::Chunk(response.content));
+                });
+                let _ = tx.send(AiStreamEvent::Finished(result.map_err(|e| e.to_string())));
+            })
+        } else {
+            self.spawn_reported("The AI chat", async move {
+                let chunk_tx = tx.clone();
+                let result = ai_agent
+                    .read()
+                    .await
+                    .chat_stream(queue_ticket, &message, move |chunk| {
+                        let _ = chunk_tx.send(AiStreamEvent::Chunk(chunk.to_string()));
+                    })
+                    .await;
+                let result = result.map(|response| {
+                    if !response.injection_signals.is_empty() {
+                        let _ = tx.send(AiStreamEvent::InjectionSuspected(response.injection_signals));
+                    }
+                });
+                let _ = tx.send(AiStreamEvent::Finished(result.map_err(|e| e.to_string())));
+            })
+        };
+
+        self.pending_ai_message = Some(PendingAiMessage {
+            index,
+            ticket,
+            started: Instant::now(),
+            last_chunk: None,
+            chars: 0,
+            task,
+            events: rx,
+        });
+    }
+
+    pub(super) fn poll_ai_stream(&mut self, ctx: &egui::Context) {
+        let Some(pending) = self.pending_ai_message.as_mut() else {
+            return;
+        };
+
+        let mut finished = None;
+        while let Ok(event) = pending.events.try_recv() {
+            match event {
+                AiStreamEvent::Chunk(chunk) => {
+                    pending.last_chunk = Some(Instant::now());
+                    pending.chars += chunk.chars().count();
+                    if let Some((_, message)) = self.ai_messages.get_mut(pending.index) {
+                        message.push_str(&chunk);
+                    }
+                }
+                AiStreamEvent::ToolStep(step) => {
+                    // Steps go above the reply they lead to
+                    pending.last_chunk = Some(Instant::now());
+                    let status = if step.success { "🔧" } else { "⚠" };
+                    let message = format!("{} {}\n{}", status, step.title, step.output);
+                    self.session_recorder.record_ai_message(TOOL_ROLE, &message);
+                    self.ai_messages.insert(pending.index, (TOOL_ROLE.to_string(), message));
+                    if let Some(summary) = self.summary_message.as_mut().filter(|i| **i >= pending.index) {
+                        *summary += 1;
+                    }
+                    pending.index += 1;
+                }
+                AiStreamEvent::InjectionSuspected(signals) => {
+                    // Above the reply, like the steps
+                    let message = injection_warning(&signals);
+                    self.session_recorder.record_ai_message(INJECTION_ROLE, &message);
+                    self.ai_messages.insert(pending.index, (INJECTION_ROLE.to_string(), message));
+                    if let Some(summary) = self.summary_message.as_mut().filter(|i| **i >= pending.index) {
+                        *summary += 1;
+                    }
+                    pending.index += 1;
+                }
+                AiStreamEvent::Finished(result) => {
+                    finished = Some(result);
+                    break;
+                }
+            }
+        }
+
+        match finished {
+            Some(result) => {
+                if let Err(e) = result {
+                    self.notifications.sender().error("AI request failed", &e);
+                    if let Some((_, message)) = self.ai_messages.get_mut(pending.index) {
+                        if !message.is_empty() {
+                            message.push_str("\n\n");
+                        }
+                        message.push_str(&format!("Sorry, I encountered an error: {}", e));
+                    }
+                }
+                if let Some((role, message)) = self.ai_messages.get(pending.index) {
+                    self.session_recorder.record_ai_message(role, message);
+                }
+                self.pending_ai_message = None;
+            }
+            // Keep the indicator animating
+            None => ctx.request_repaint_after(Duration::from_millis(100)),
+        }
+    }
+
+    /// Puts the prompt injection warning for the reply at `index` above it,
+    /// moving the entries that follow down.
+    pub(super) fn insert_injection_warning(&mut self, index: usize, signals: &[String]) {
+        let message = injection_warning(signals);
+        self.session_recorder.record_ai_message(INJECTION_ROLE, &message);
+        self.ai_messages.insert(index, (INJECTION_ROLE.to_string(), message));
+        if let Some(pending) = self.pending_ai_message.as_mut().filter(|p| p.index >= index) {
+            pending.index += 1;
+        }
+        if let Some(summary) = self.summary_message.as_mut().filter(|i| **i >= index) {
+            *summary += 1;
+        }
+    }
+
+    /// Stops the streaming response, keeping whatever has arrived so far.
+    pub(super) fn stop_ai_stream(&mut self) {
+        let Some(pending) = self.pending_ai_message.take() else {
+            return;
+        };
+        pending.task.abort();
+        if let Some((_, message)) = self.ai_messages.get_mut(pending.index) {
+            if !message.is_empty() {
+                message.push_str("\n\n");
+            }
+            message.push_str("(stopped)");
+        }
+        if let Some((role, message)) = self.ai_messages.get(pending.index) {
+            self.session_recorder.record_ai_message(role, message);
+        }
+    }
+}
+
+/// The animated indicator shown under a streaming message. Returns true if
+/// the user asked to stop.
+pub fn render_stream_indicator(
+    ui: &mut egui::Ui,
+    pending: &PendingAiMessage,
+    requests_ahead: Option<usize>,
+) -> bool {
+    let now = Instant::now();
+    let since_activity = now.duration_since(pending.last_chunk.unwrap_or(pending.started));
+    let dots = ".".repeat(1 + (now.duration_since(pending.started).as_millis() / 400 % 3) as usize);
+
+    let mut stop = false;
+    ui.horizontal(|ui| {
+        accessibility::spinner(ui);
+        let status = if let Some(ahead) = requests_ahead {
+            match ahead {
+                0 => "⏳ Queued".to_string(),
+                1 => "⏳ Queued, 1 request ahead".to_string(),
+                n => format!("⏳ Queued, {} requests ahead", n),
+            }
+        } else if pending.last_chunk.is_none() {
+            format!("🤔 Thinking{}", dots)
+        } else {
+            format!("Receiving{} {} chars", dots, pending.chars)
+        };
+        ui.weak(status);
+        if requests_ahead.is_none() && since_activity >= STILL_WAITING_AFTER {
+            ui.weak("(still waiting...)");
+        }
+        if ui.small_button("⏹ Stop").clicked() {
+            stop = true;
+        }
+    });
+    stop
+}
+
+/// A tool step in the chat, collapsed to its title line.
+pub fn render_tool_step(ui: &mut egui::Ui, index: usize, message: &str) {
+    let (title, output) = message.split_once('\n').unwrap_or((message, ""));
+    egui::CollapsingHeader::new(egui::RichText::new(title).monospace().weak())
+        .id_source(("tool_step", index))
+        .default_open(false)
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                ui.monospace(if output.is_empty() { "(no output)" } else { output });
+            });
+        });
+}
+
+fn injection_warning(signals: &[String]) -> String {
+    format!("Possible prompt injection in included content: {}", signals.join(", "))
+}
+
+/// The warning above a reply that may have been steered by instructions in
+/// the output or files its request included.
+pub fn render_injection_warning(ui: &mut egui::Ui, color: egui::Color32, message: &str) {
+    ui.colored_label(color, format!("⚠ {}", message)).on_hover_text(
+        "Text in the output, files or code sent with this request reads like instructions to the AI. \
+         Check the reply below before acting on it.",
+    );
+}
@@ -0,0 +1,94 @@
+use super::{accessibility, AnTraftApp, TerminalBlock};
+use crate::terminal::audit::CommandOrigin;
+use crate::terminal::engine::BUILTIN_COMMANDS;
+use crate::terminal::title;
+use eframe::egui;
+use std::path::Path;
+
+/// Running an old block's command again, or going where it ran.
+pub enum DirectoryAction {
+    /// Run `block`'s command in `directory`, or the current directory
+    Rerun {
+        block: uuid::Uuid,
+        command: String,
+        directory: Option<String>,
+    },
+    ChangeDirectory(String),
+}
+
+/// Whether a block ran in a local directory other than `current`, that
+/// commands can still run in. Remote blocks' `host:dir` never is one.
+pub fn ran_elsewhere(block: &TerminalBlock, current: &str) -> bool {
+    !block.working_directory.is_empty()
+        && block.working_directory != current
+        && Path::new(&block.working_directory).is_dir()
+}
+
+/// The directory a block ran in, for its header, when it isn't the
+/// current one.
+pub fn render_directory(ui: &mut egui::Ui, block: &TerminalBlock, current: &str, home: Option<&Path>) {
+    if block.working_directory.is_empty() || block.working_directory == current {
+        return;
+    }
+    ui.weak(format!("📁 {}", title::shorten_home(&block.working_directory, home)))
+        .on_hover_text(format!("Ran in {}", block.working_directory));
+}
+
+/// The ↻ button of a finished block. It runs the command again straight
+/// away, or, when the block ran in another directory, asks where.
+pub fn render_rerun(ui: &mut egui::Ui, block: &TerminalBlock, current: &str, home: Option<&Path>) -> Option<DirectoryAction> {
+    let rerun = |directory| DirectoryAction::Rerun {
+        block: block.id,
+        command: block.input.clone(),
+        directory,
+    };
+    // Builtins like `cd` act on the current directory only
+    if is_builtin(&block.input) || !ran_elsewhere(block, current) {
+        return accessibility::icon_button(ui, "↻", "Run again").clicked().then(|| rerun(None));
+    }
+
+    let mut action = None;
+    ui.menu_button("↻", |ui| {
+        if ui.button(format!("Run here ({})", title::shorten_home(current, home))).clicked() {
+            action = Some(rerun(None));
+            ui.close_menu();
+        }
+        let original = title::shorten_home(&block.working_directory, home);
+        if ui.button(format!("Run in original directory ({})", original)).clicked() {
+            action = Some(rerun(Some(block.working_directory.clone())));
+            ui.close_menu();
+        }
+    })
+    .response
+    .on_hover_text("Run again");
+    action
+}
+
+fn is_builtin(command: &str) -> bool {
+    command
+        .split_whitespace()
+        .next()
+        .is_some_and(|word| BUILTIN_COMMANDS.contains(&word))
+}
+
+impl AnTraftApp {
+    pub(super) fn handle_directory_action(&mut self, action: DirectoryAction) {
+        match action {
+            DirectoryAction::Rerun { block, command, directory } => {
+                // Offered a diff against `block` if its output changes.
+                // Builtins make blocks of their own, never compared.
+                if !is_builtin(&command) {
+                    self.pending_reruns.push((command.clone(), block));
+                }
+                self.run_command(command, CommandOrigin::User, directory);
+            }
+            DirectoryAction::ChangeDirectory(directory) => {
+                let command = match directory.contains(char::is_whitespace) {
+                    true => format!("cd \"{}\"", directory),
+                    false => format!("cd {}", directory),
+                };
+                self.run_command(command, CommandOrigin::User, None);
+            }
+        }
+    }
+}
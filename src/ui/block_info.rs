@@ -0,0 +1,151 @@
+use super::{accessibility, block_directory, AnTraftApp, TerminalBlock};
+use crate::ai::AiRequest;
+use crate::terminal::block::metadata_keys;
+use eframe::egui;
+
+/// Metadata to attach to a terminal block once some background work, such
+/// as an AI explanation, finishes.
+#[derive(Debug, Clone)]
+pub struct BlockAnnotation {
+    pub block_id: uuid::Uuid,
+    pub key: String,
+    pub value: String,
+}
+
+/// Keys shown first in the inspector, in this order. Anything else follows
+/// alphabetically.
+const KEY_ORDER: &[(&str, &str)] = &[
+    (metadata_keys::COMMAND, "Command"),
+    (metadata_keys::WORKING_DIRECTORY, "Directory"),
+    (metadata_keys::SHELL, "Shell"),
+    (metadata_keys::USER, "User"),
+    (metadata_keys::HOSTNAME, "Host"),
+    (metadata_keys::EXIT_CODE, "Exit code"),
+    (metadata_keys::DURATION, "Duration"),
+    (metadata_keys::PEAK_CPU, "Peak CPU"),
+    (metadata_keys::PEAK_MEMORY, "Peak memory"),
+    (metadata_keys::CPU_TIME, "CPU time"),
+];
+
+/// What the user asked for from a block's info popover.
+pub enum BlockInfoAction {
+    Explain,
+    SaveAsWorkflow,
+    /// `cd` to where the block ran
+    ChangeDirectory,
+    /// Diff its output against another block's
+    Compare,
+}
+
+impl AnTraftApp {
+    /// Asks the AI to explain a block's command and stores the answer in the
+    /// block's metadata.
+    pub(super) fn explain_block(&mut self, block_id: uuid::Uuid) {
+        let Some(block) = self.terminal_output.iter_mut().find(|b| b.id == block_id) else {
+            return;
+        };
+        if block.command.is_empty() {
+            return;
+        }
+        block
+            .metadata
+            .insert(metadata_keys::AI_EXPLANATION.to_string(), "Explaining…".to_string());
+
+        let command = block.command.clone();
+        let ai_agent = self.ai_agent.clone();
+        let tx = self.block_annotation_tx.clone();
+        self.spawn_reported("Explaining the command", async move {
+            let value = match ai_agent
+                .read()
+                .await
+                .process_request(AiRequest::ExplainCommand { command })
+                .await
+            {
+                Ok(response) => response.content,
+                Err(e) => format!("Explanation failed: {}", e),
+            };
+            let _ = tx.send(BlockAnnotation {
+                block_id,
+                key: metadata_keys::AI_EXPLANATION.to_string(),
+                value,
+            });
+        });
+    }
+
+    pub(super) fn handle_block_annotation(&mut self, annotation: BlockAnnotation) {
+        if let Some(block) = self.terminal_output.iter_mut().find(|b| b.id == annotation.block_id) {
+            block.metadata.insert(annotation.key, annotation.value);
+        }
+    }
+}
+
+/// The ℹ button on a terminal block and the metadata popover it opens.
+pub fn render_block_info(ui: &mut egui::Ui, block: &TerminalBlock, current_directory: &str) -> Option<BlockInfoAction> {
+    let response = accessibility::icon_button(ui, "ℹ", "Block info");
+    let popup_id = ui.make_persistent_id(("block_info", block.id));
+    if response.clicked() {
+        ui.memory_mut(|memory| memory.toggle_popup(popup_id));
+    }
+
+    let mut action = None;
+    egui::popup::popup_below_widget(ui, popup_id, &response, |ui| {
+        ui.set_min_width(320.0);
+
+        let mut rows: Vec<(String, &String)> = KEY_ORDER
+            .iter()
+            .filter_map(|(key, label)| block.metadata.get(*key).map(|value| (label.to_string(), value)))
+            .collect();
+        let mut extra: Vec<(&String, &String)> = block
+            .metadata
+            .iter()
+            .filter(|(key, _)| {
+                key.as_str() != metadata_keys::AI_EXPLANATION
+                    && !KEY_ORDER.iter().any(|(known, _)| known == key)
+            })
+            .collect();
+        extra.sort();
+        rows.extend(extra.into_iter().map(|(key, value)| (key.clone(), value)));
+
+        egui::Grid::new(("block_info_grid", block.id))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.weak("Started");
+                ui.label(block.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string());
+                ui.end_row();
+                for (label, value) in rows {
+                    ui.weak(label);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+        match block.metadata.get(metadata_keys::AI_EXPLANATION) {
+            Some(explanation) => {
+                ui.weak("AI explanation");
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.label(explanation);
+                });
+            }
+            None => {
+                if !block.command.is_empty() && ui.button("🤖 Explain this command").clicked() {
+                    action = Some(BlockInfoAction::Explain);
+                }
+            }
+        }
+        if !block.input.is_empty() && ui.button("⚡ Save as workflow").clicked() {
+            action = Some(BlockInfoAction::SaveAsWorkflow);
+        }
+        if !block.is_running && !block.command.is_empty() && ui.button("⇄ Compare with…").clicked() {
+            action = Some(BlockInfoAction::Compare);
+        }
+        if block_directory::ran_elsewhere(block, current_directory)
+            && ui.button("📂 cd to this block's directory").clicked()
+        {
+            action = Some(BlockInfoAction::ChangeDirectory);
+        }
+    });
+
+    action
+}
@@ -0,0 +1,122 @@
+use super::{accessibility, AnTraftApp};
+use crate::terminal::{Block, TerminalEvent};
+use eframe::egui;
+use uuid::Uuid;
+
+/// An answer being typed for a running command: to a prompt spotted in its
+/// output, or free-form input the user chose to send.
+#[derive(Debug, Clone, Default)]
+pub struct BlockInput {
+    /// Empty when the user opened the input themselves
+    pub prompt: String,
+    /// Masked, and never echoed into the output or kept in history
+    pub secret: bool,
+    answer: String,
+    focus: bool,
+}
+
+impl BlockInput {
+    pub fn new(prompt: String, secret: bool) -> Self {
+        Self {
+            prompt,
+            secret,
+            answer: String::new(),
+            focus: true,
+        }
+    }
+
+    pub fn has_answer(&self) -> bool {
+        !self.answer.is_empty()
+    }
+
+    /// What the block header says the command is waiting for.
+    pub fn status(&self) -> &'static str {
+        match (self.secret, self.prompt.is_empty()) {
+            (true, _) => "🔑 Waiting for password",
+            (false, false) => "❓ Waiting for input",
+            (false, true) => "⌨ Input",
+        }
+    }
+}
+
+pub enum BlockInputAction {
+    Open(Uuid),
+    Send { id: Uuid, answer: String, secret: bool },
+    /// Close the command's stdin, so it sees end of input
+    Close(Uuid),
+    Dismiss(Uuid),
+}
+
+/// The input row under a running block's output.
+pub fn render_block_input(ui: &mut egui::Ui, id: Uuid, input: &mut BlockInput) -> Option<BlockInputAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if !input.prompt.is_empty() {
+            ui.label(egui::RichText::new(&input.prompt).strong());
+        }
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut input.answer)
+                .id_source(("block_input", id))
+                .password(input.secret)
+                .desired_width(220.0)
+                .hint_text(if input.secret { "Password" } else { "Answer" }),
+        );
+        if std::mem::take(&mut input.focus) {
+            response.request_focus();
+        }
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if submitted || ui.button("Send").clicked() {
+            action = Some(BlockInputAction::Send {
+                id,
+                answer: std::mem::take(&mut input.answer),
+                secret: input.secret,
+            });
+        }
+        if ui.small_button("EOF").on_hover_text("Close the command's input").clicked() {
+            action = Some(BlockInputAction::Close(id));
+        }
+        if accessibility::icon_button(ui, "✕", "Hide").clicked() {
+            action = Some(BlockInputAction::Dismiss(id));
+        }
+    });
+    action
+}
+
+impl AnTraftApp {
+    pub(super) fn handle_block_input_action(&mut self, action: BlockInputAction) {
+        let id = match &action {
+            BlockInputAction::Open(id)
+            | BlockInputAction::Send { id, .. }
+            | BlockInputAction::Close(id)
+            | BlockInputAction::Dismiss(id) => *id,
+        };
+        let Some(block) = self.terminal_output.iter_mut().rev().find(|b| b.id == id) else {
+            return;
+        };
+        block.stdin_prompt = None;
+
+        let engine = self.terminal_engine.clone();
+        let event_sender = self.terminal_event_tx.clone();
+        match action {
+            BlockInputAction::Send { answer, secret, .. } => {
+                // The command doesn't echo input from a pipe, so show what
+                // was typed, except for passwords
+                if !secret {
+                    block.output.push_str(&answer);
+                }
+                block.output.push('\n');
+                self.runtime_handle.spawn(async move {
+                    if let Err(e) = engine.send_input(id, &answer).await {
+                        let block = Block::error(format!("Couldn't send input: {}", e));
+                        let _ = event_sender.send(TerminalEvent::NewBlock { block });
+                    }
+                });
+            }
+            BlockInputAction::Close(_) => {
+                self.runtime_handle.spawn(async move { engine.close_input(id).await });
+            }
+            BlockInputAction::Open(_) => block.stdin_prompt = Some(BlockInput::new(String::new(), false)),
+            BlockInputAction::Dismiss(_) => {}
+        }
+    }
+}
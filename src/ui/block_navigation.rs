@@ -0,0 +1,131 @@
+use super::{AnTraftApp, UIMode};
+use crate::terminal::marks::{NavigationCommand, NavigationKeys};
+use eframe::egui;
+use uuid::Uuid;
+
+/// Moving between blocks with the keyboard, entered with Cmd/Ctrl+↑ from
+/// the input. The input gives up focus meanwhile, so typed letters are
+/// commands; see `NavigationKeys`.
+pub struct BlockNavigation {
+    pub selected: Uuid,
+    pub keys: NavigationKeys,
+}
+
+impl AnTraftApp {
+    /// Enters, drives and leaves block navigation; called every frame.
+    pub(super) fn handle_block_navigation(&mut self, ctx: &egui::Context) {
+        if self.current_mode != UIMode::Terminal {
+            self.block_navigation = None;
+            return;
+        }
+        let Some(navigation) = &mut self.block_navigation else {
+            let last = self.terminal_output.iter().rev().find(|block| !block.command.is_empty()).map(|block| block.id);
+            if let Some(last) = last {
+                if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::ArrowUp)) {
+                    self.block_navigation = Some(BlockNavigation {
+                        selected: last,
+                        keys: NavigationKeys::default(),
+                    });
+                    self.scroll_to_block = Some(last);
+                    ctx.memory_mut(|memory| {
+                        if let Some(focused) = memory.focused() {
+                            memory.surrender_focus(focused);
+                        }
+                    });
+                }
+            }
+            return;
+        };
+
+        let leave = ctx.input_mut(|i| {
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) || i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)
+        });
+        if leave {
+            // A pending `m` or `'` is cancelled first
+            if navigation.keys.pending().is_some() {
+                navigation.keys.cancel();
+            } else {
+                self.block_navigation = None;
+            }
+            return;
+        }
+
+        let mut commands = Vec::new();
+        ctx.input_mut(|i| {
+            for (key, command) in [
+                (egui::Key::ArrowUp, NavigationCommand::Previous),
+                (egui::Key::ArrowDown, NavigationCommand::Next),
+                (egui::Key::Home, NavigationCommand::First),
+                (egui::Key::End, NavigationCommand::Last),
+                (egui::Key::Delete, NavigationCommand::Delete),
+            ] {
+                if i.consume_key(egui::Modifiers::NONE, key) {
+                    commands.push(command);
+                }
+            }
+            for event in &i.events {
+                if let egui::Event::Text(text) = event {
+                    commands.extend(text.chars().filter_map(|c| navigation.keys.press(c)));
+                }
+            }
+        });
+        for command in commands {
+            self.run_navigation_command(command);
+        }
+    }
+
+    fn run_navigation_command(&mut self, command: NavigationCommand) {
+        let Some(selected) = self.block_navigation.as_ref().map(|navigation| navigation.selected) else {
+            return;
+        };
+        let blocks: Vec<Uuid> = self
+            .terminal_output
+            .iter()
+            .filter(|block| !block.command.is_empty())
+            .map(|block| block.id)
+            .collect();
+        let index = blocks.iter().position(|id| *id == selected);
+        let target = match command {
+            NavigationCommand::Previous => index.map_or(blocks.last(), |index| blocks.get(index.saturating_sub(1))),
+            NavigationCommand::Next => index.map_or(blocks.last(), |index| blocks.get(index + 1).or(blocks.last())),
+            NavigationCommand::First => blocks.first(),
+            NavigationCommand::Last => blocks.last(),
+            NavigationCommand::Delete => {
+                // The selection moves on to the next block, or the one before
+                let neighbor = index.and_then(|index| blocks.get(index + 1).or(blocks.get(index.wrapping_sub(1))));
+                if let (Some(&neighbor), Some(navigation)) = (neighbor, &mut self.block_navigation) {
+                    navigation.selected = neighbor;
+                }
+                self.delete_block(selected);
+                return;
+            }
+            NavigationCommand::TogglePin => {
+                let pinned = self.pins.contains(selected);
+                self.set_block_pinned(selected, !pinned);
+                return;
+            }
+            NavigationCommand::SetMark(letter) => {
+                if self.quick_marks.set(letter, selected).is_ok() {
+                    self.session_recorder.set_mark(letter, selected);
+                }
+                return;
+            }
+            NavigationCommand::JumpToMark(letter) => {
+                match self.quick_marks.get(letter) {
+                    Some(id) => {
+                        if let Some(navigation) = &mut self.block_navigation {
+                            navigation.selected = id;
+                        }
+                        self.jump_to_block(id);
+                    }
+                    None => self.notifications.sender().info(format!("No block is marked '{}", letter)),
+                }
+                return;
+            }
+        };
+        if let (Some(&target), Some(navigation)) = (target, &mut self.block_navigation) {
+            navigation.selected = target;
+            self.scroll_to_block = Some(target);
+        }
+    }
+}
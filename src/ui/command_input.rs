@@ -0,0 +1,210 @@
+//! The command input: one line until Shift+Enter or a paste adds more,
+//! then up to `MAX_ROWS` rows with line numbers. Up and Down recall
+//! earlier commands. Both it and blocks' commands are highlighted as bash
+//! when the shell is one.
+
+use super::accessibility::{Palette, SyntaxColors};
+use super::AnTraftApp;
+use crate::autocomplete::highlight::{self, SyntaxHighlighter};
+use super::TerminalBlock;
+use crate::terminal::block::metadata_keys;
+use crate::terminal::input_history::{on_first_line, on_last_line};
+use crate::terminal::timeout::{self, format_timeout};
+use eframe::egui::{self, text::LayoutJob};
+use std::time::Duration;
+
+/// The input grows to this many rows, then scrolls.
+const MAX_ROWS: usize = 10;
+
+/// Room left beside the input for the timeout and Run buttons.
+const RUN_BUTTON_WIDTH: f32 = 110.0;
+
+/// Timeouts offered for the next command, in seconds.
+const TIMEOUT_CHOICES: [u64; 6] = [10, 30, 60, 300, 900, 3600];
+
+impl AnTraftApp {
+    /// Up and Down recall earlier commands while the input has focus and
+    /// the completions don't use them: from anywhere in one line, and from
+    /// the first or last line of several. They're taken from the input
+    /// when they do.
+    pub(super) fn handle_history_keys(&mut self, ui: &mut egui::Ui) {
+        let input_id = Self::command_input_id();
+        if self.completion.is_open() || !ui.memory(|memory| memory.has_focus(input_id)) {
+            return;
+        }
+        let (up, down) = ui.input(|i| {
+            let plain = i.modifiers.is_none();
+            (plain && i.key_pressed(egui::Key::ArrowUp), plain && i.key_pressed(egui::Key::ArrowDown))
+        });
+        if !up && !down {
+            return;
+        }
+        let cursor = egui::TextEdit::load_state(ui.ctx(), input_id)
+            .and_then(|state| state.cursor.char_range())
+            .map_or(self.command_input.chars().count(), |range| range.primary.index);
+
+        let (key, recalled) = if up && on_first_line(&self.command_input, cursor) {
+            (egui::Key::ArrowUp, self.input_history.older(&self.command_history, &self.command_input))
+        } else if down && on_last_line(&self.command_input, cursor) {
+            (egui::Key::ArrowDown, self.input_history.newer(&self.command_history))
+        } else {
+            return;
+        };
+        if let Some(input) = recalled {
+            ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key));
+            self.set_command_input(ui.ctx(), input);
+        }
+    }
+
+    /// The input itself, with a gutter of line numbers once it has more
+    /// than one. Returns its response, and whether Enter without Shift was
+    /// pressed in it to run the command.
+    pub(super) fn render_command_input(&mut self, ui: &mut egui::Ui) -> (egui::Response, bool) {
+        let input_id = Self::command_input_id();
+        let submitted = ui.memory(|memory| memory.has_focus(input_id))
+            && ui.input_mut(|i| !i.modifiers.shift && i.consume_key(egui::Modifiers::NONE, egui::Key::Enter));
+
+        let lines = self.command_input.split('\n').count();
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let width = (ui.available_width() - RUN_BUTTON_WIDTH).max(200.0);
+        let colors = Palette::for_settings(&self.config.accessibility).syntax;
+        let mut highlighter =
+            highlight::highlights_as_bash(&self.config.terminal.shell).then_some(&mut self.syntax_highlighter);
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let font = egui::TextStyle::Monospace.resolve(ui.style());
+            let mut job = highlighted_job(highlighter.as_deref_mut(), &colors, text, font, ui.visuals().text_color());
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+        let response = egui::ScrollArea::vertical()
+            .id_source("command_input_scroll")
+            .max_height(row_height * MAX_ROWS as f32 + 4.0)
+            .show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    if lines > 1 {
+                        let numbers: Vec<String> = (1..=lines).map(|line| line.to_string()).collect();
+                        ui.vertical(|ui| {
+                            // Level with the text inside the input's margin
+                            ui.add_space(2.0);
+                            ui.label(egui::RichText::new(numbers.join("\n")).monospace().weak());
+                        });
+                    }
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.command_input)
+                            .id(input_id)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_rows(1)
+                            .desired_width(width)
+                            // Enter runs the command
+                            .return_key(egui::KeyboardShortcut::new(egui::Modifiers::SHIFT, egui::Key::Enter))
+                            .hint_text("Type a command, or ! for workflows · Shift+Enter for a new line")
+                            // Tab cycles the completions instead of moving on
+                            .lock_focus(self.completion.is_open())
+                            .layouter(&mut layouter),
+                    )
+                })
+                .inner
+            })
+            .inner;
+        (response, submitted)
+    }
+}
+
+impl AnTraftApp {
+    /// The ⏱ menu beside Run, giving the next command a timeout other than
+    /// the default. It applies to that command only.
+    pub(super) fn render_timeout_menu(&mut self, ui: &mut egui::Ui) {
+        let label = match self.next_timeout {
+            Some(timeout) if timeout.is_zero() => "⏱ none".to_string(),
+            Some(timeout) => format!("⏱ {}", format_timeout(timeout)),
+            None => "⏱".to_string(),
+        };
+        let default = self.config.terminal.timeout.filter(|secs| *secs > 0).map(Duration::from_secs);
+        ui.menu_button(label, |ui| {
+            let default_label = match default {
+                Some(timeout) => format!("Default ({})", format_timeout(timeout)),
+                None => "Default (none)".to_string(),
+            };
+            let mut choice = |ui: &mut egui::Ui, timeout: Option<Duration>, label: String| {
+                if ui.radio(self.next_timeout == timeout, label).clicked() {
+                    self.next_timeout = timeout;
+                    ui.close_menu();
+                }
+            };
+            choice(ui, None, default_label);
+            if default.is_some() {
+                choice(ui, Some(Duration::ZERO), "None".to_string());
+            }
+            for secs in TIMEOUT_CHOICES {
+                let timeout = Duration::from_secs(secs);
+                choice(ui, Some(timeout), format_timeout(timeout));
+            }
+        })
+        .response
+        .on_hover_text("Timeout for the next command; or type @timeout=30s before it");
+    }
+
+    /// `command` with the timeout picked for it, if any, which is then
+    /// cleared. A command with its own `@timeout=` keeps it.
+    pub(super) fn apply_next_timeout(&mut self, command: String) -> String {
+        match self.next_timeout.take() {
+            Some(next) if !command.starts_with(timeout::PREFIX) => timeout::with_timeout(&command, next),
+            _ => command,
+        }
+    }
+}
+
+/// The time left before a running block's command is killed, if it has a
+/// timeout.
+pub(super) fn render_countdown(ui: &mut egui::Ui, block: &TerminalBlock) {
+    let Some(timeout) = block.metadata.get(metadata_keys::TIMEOUT).and_then(|t| timeout::parse_duration(t).ok()) else {
+        return;
+    };
+    let elapsed = (chrono::Utc::now() - block.timestamp).to_std().unwrap_or_default();
+    let left = Duration::from_secs(timeout.saturating_sub(elapsed).as_secs());
+    ui.small(format!("⏱ {} left", format_timeout(left)))
+        .on_hover_text(format!("Killed if still running after {}", format_timeout(timeout)));
+    ui.ctx().request_repaint_after(Duration::from_secs(1));
+}
+
+/// A block's command, keeping the lines of a multi-line one, highlighted
+/// when there's a `highlighter` for the shell.
+pub(super) fn render_command_text(
+    ui: &mut egui::Ui,
+    command: &str,
+    highlighter: Option<&mut SyntaxHighlighter>,
+    colors: &SyntaxColors,
+) {
+    let style = match command.contains('\n') {
+        true => egui::TextStyle::Monospace,
+        false => egui::TextStyle::Body,
+    };
+    let font = style.resolve(ui.style());
+    ui.label(highlighted_job(highlighter, colors, command, font, ui.visuals().text_color()));
+}
+
+/// `text` laid out in `font`, its highlighted runs in their colors and the
+/// rest in `plain`. All plain without a `highlighter`.
+fn highlighted_job(
+    highlighter: Option<&mut SyntaxHighlighter>,
+    colors: &SyntaxColors,
+    text: &str,
+    font: egui::FontId,
+    plain: egui::Color32,
+) -> LayoutJob {
+    let format = |color| egui::TextFormat::simple(font.clone(), color);
+    let spans = highlighter.map(|highlighter| highlighter.highlight(text)).unwrap_or_default();
+    let mut job = LayoutJob::default();
+    let mut end = 0;
+    for span in spans {
+        if span.range.start > end {
+            job.append(&text[end..span.range.start], 0.0, format(plain));
+        }
+        job.append(&text[span.range.clone()], 0.0, format(colors.color(span.class)));
+        end = span.range.end;
+    }
+    if end < text.len() || text.is_empty() {
+        job.append(&text[end..], 0.0, format(plain));
+    }
+    job
+}
@@ -0,0 +1,275 @@
+use super::conversation_file::ConversationFileAction;
+use super::{AnTraftApp, UIMode};
+use crate::security::ScanType;
+use eframe::egui;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteAction {
+    ShowWelcome,
+    ShowTerminal,
+    ShowAiAgent,
+    ToggleFileExplorer,
+    ToggleSplitLayout,
+    ResetLayout,
+    ClearTerminal,
+    Undo,
+    SearchInFiles,
+    RunSecurityScan,
+    ScanContainerImage,
+    ShowDependencyInventory,
+    RunWorkflow,
+    RunPipeline,
+    ShowFavorites,
+    SwitchWorkspace,
+    OpenFolder,
+    NewRemoteSession,
+    OpenSettings,
+    ImportShellHistory,
+    ShowUsageStats,
+    SummarizeSession,
+    GenerateScript,
+    ExportSessionScript,
+    RecordStart,
+    RecordStop,
+    ReplayRecording,
+    GenerateCommitMessage,
+    ExportConversation,
+    ImportConversation,
+    ShowLogs,
+    /// Panics, to try crash reports; see `HIDDEN`
+    DebugCrash,
+}
+
+impl PaletteAction {
+    pub const ALL: &'static [PaletteAction] = &[
+        PaletteAction::ShowWelcome,
+        PaletteAction::ShowTerminal,
+        PaletteAction::ShowAiAgent,
+        PaletteAction::ToggleFileExplorer,
+        PaletteAction::ToggleSplitLayout,
+        PaletteAction::ResetLayout,
+        PaletteAction::ClearTerminal,
+        PaletteAction::Undo,
+        PaletteAction::SearchInFiles,
+        PaletteAction::RunSecurityScan,
+        PaletteAction::ScanContainerImage,
+        PaletteAction::ShowDependencyInventory,
+        PaletteAction::RunWorkflow,
+        PaletteAction::RunPipeline,
+        PaletteAction::ShowFavorites,
+        PaletteAction::SwitchWorkspace,
+        PaletteAction::OpenFolder,
+        PaletteAction::NewRemoteSession,
+        PaletteAction::OpenSettings,
+        PaletteAction::ImportShellHistory,
+        PaletteAction::ShowUsageStats,
+        PaletteAction::SummarizeSession,
+        PaletteAction::GenerateScript,
+        PaletteAction::ExportSessionScript,
+        PaletteAction::RecordStart,
+        PaletteAction::RecordStop,
+        PaletteAction::ReplayRecording,
+        PaletteAction::GenerateCommitMessage,
+        PaletteAction::ExportConversation,
+        PaletteAction::ImportConversation,
+        PaletteAction::ShowLogs,
+    ];
+
+    /// Found by searching for them in debug builds, and never listed.
+    pub const HIDDEN: &'static [PaletteAction] = &[PaletteAction::DebugCrash];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaletteAction::ShowWelcome => "🏠 Go to Welcome",
+            PaletteAction::ShowTerminal => "🖥 Go to Terminal",
+            PaletteAction::ShowAiAgent => "🤖 Go to AI Agent",
+            PaletteAction::ToggleFileExplorer => "📁 Toggle File Explorer",
+            PaletteAction::ToggleSplitLayout => "◫ Toggle Split Layout",
+            PaletteAction::ResetLayout => "↺ Reset Layout",
+            PaletteAction::ClearTerminal => "🧹 Clear Terminal",
+            PaletteAction::Undo => "↶ Undo Delete or Clear",
+            PaletteAction::SearchInFiles => "🔎 Search in Files",
+            PaletteAction::RunSecurityScan => "🛡 Scan Working Directory for Vulnerabilities",
+            PaletteAction::ScanContainerImage => "🐳 Scan Container Image…",
+            PaletteAction::ShowDependencyInventory => "📦 Dependency License Inventory",
+            PaletteAction::RunWorkflow => "⚡ Run Workflow…",
+            PaletteAction::RunPipeline => "⛓ Run Commands as Pipeline…",
+            PaletteAction::ShowFavorites => "★ Favorite Commands…",
+            PaletteAction::SwitchWorkspace => "🗂 Switch Workspace…",
+            PaletteAction::OpenFolder => "📂 Open Folder…",
+            PaletteAction::NewRemoteSession => "🌐 New Remote Session…",
+            PaletteAction::OpenSettings => "⚙ Open Settings",
+            PaletteAction::ImportShellHistory => "⬇ Import Shell History",
+            PaletteAction::ShowUsageStats => "📊 Show Usage Statistics",
+            PaletteAction::SummarizeSession => "📝 Summarize This Session",
+            PaletteAction::GenerateScript => "📜 Generate Script from Selection",
+            PaletteAction::ExportSessionScript => "💾 Export Session as Script",
+            PaletteAction::RecordStart => "⏺ Start Recording Session",
+            PaletteAction::RecordStop => "⏹ Stop Recording Session",
+            PaletteAction::ReplayRecording => "▶ Replay Recorded Session…",
+            PaletteAction::GenerateCommitMessage => "✨ Generate Commit Message",
+            PaletteAction::ExportConversation => "💾 Export Conversation…",
+            PaletteAction::ImportConversation => "📂 Import Conversation…",
+            PaletteAction::ShowLogs => "🗒 Show Logs",
+            PaletteAction::DebugCrash => "💥 Debug: Crash the App",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Actions matching the current query, best match first.
+    fn matches(&self) -> Vec<PaletteAction> {
+        if self.query.is_empty() {
+            return PaletteAction::ALL.to_vec();
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let hidden = if cfg!(debug_assertions) { PaletteAction::HIDDEN } else { &[] };
+        let mut scored: Vec<(PaletteAction, i64)> = PaletteAction::ALL
+            .iter()
+            .chain(hidden)
+            .filter_map(|action| {
+                matcher
+                    .fuzzy_match(action.label(), &self.query)
+                    .map(|score| (*action, score))
+            })
+            .collect();
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(action, _)| action).collect()
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn render_command_palette(&mut self, ctx: &egui::Context) {
+        if !self.command_palette.open {
+            return;
+        }
+
+        let matches = self.command_palette.matches();
+        if matches.is_empty() {
+            self.command_palette.selected = 0;
+        } else {
+            self.command_palette.selected = self.command_palette.selected.min(matches.len() - 1);
+        }
+
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.command_palette.open = false;
+            return;
+        }
+        if up {
+            self.command_palette.selected = self.command_palette.selected.saturating_sub(1);
+        }
+        if down && self.command_palette.selected + 1 < matches.len() {
+            self.command_palette.selected += 1;
+        }
+
+        let mut chosen = if enter {
+            matches.get(self.command_palette.selected).copied()
+        } else {
+            None
+        };
+
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.command_palette.query)
+                        .hint_text("Type a command…")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.command_palette.selected = 0;
+                }
+                ui.separator();
+
+                if matches.is_empty() {
+                    ui.weak("No matching commands");
+                }
+                for (index, action) in matches.iter().enumerate() {
+                    let selected = index == self.command_palette.selected;
+                    if ui.selectable_label(selected, action.label()).clicked() {
+                        chosen = Some(*action);
+                    }
+                }
+            });
+
+        if let Some(action) = chosen {
+            self.command_palette.open = false;
+            self.run_palette_action(ctx, action);
+        }
+    }
+
+    fn run_palette_action(&mut self, ctx: &egui::Context, action: PaletteAction) {
+        match action {
+            PaletteAction::ShowWelcome => self.current_mode = UIMode::Welcome,
+            PaletteAction::ShowTerminal => self.current_mode = UIMode::Terminal,
+            PaletteAction::ShowAiAgent => self.current_mode = UIMode::AiAgent,
+            PaletteAction::ToggleFileExplorer => {
+                self.show_file_explorer = !self.show_file_explorer;
+                if self.show_file_explorer {
+                    self.current_mode = UIMode::Terminal;
+                }
+            }
+            PaletteAction::ToggleSplitLayout => self.toggle_split_layout(),
+            PaletteAction::ResetLayout => self.layout.request_reset(),
+            PaletteAction::ClearTerminal => self.clear_terminal(),
+            PaletteAction::Undo => self.undo(ctx),
+            PaletteAction::SearchInFiles => self.content_search.toggle(),
+            PaletteAction::RunSecurityScan => self.start_security_scan(ScanType::Full),
+            PaletteAction::ScanContainerImage => self.open_image_scan(),
+            PaletteAction::ShowDependencyInventory => self.open_dependency_inventory(),
+            PaletteAction::RunWorkflow => self.open_workflow_picker(),
+            PaletteAction::RunPipeline => self.open_pipeline_dialog(),
+            PaletteAction::ShowFavorites => self.open_favorites(),
+            PaletteAction::SwitchWorkspace => self.open_workspace_switcher(),
+            PaletteAction::OpenFolder => self.pick_workspace_folder(),
+            PaletteAction::NewRemoteSession => self.open_remote_picker(),
+            PaletteAction::OpenSettings => self.show_settings = true,
+            PaletteAction::ImportShellHistory => self.scan_shell_histories(),
+            PaletteAction::ShowUsageStats => self.open_usage_stats(),
+            PaletteAction::SummarizeSession => self.summarize_session(),
+            PaletteAction::GenerateScript => self.generate_script_from_selection(),
+            PaletteAction::ExportSessionScript => self.export_session_script(),
+            PaletteAction::RecordStart => self.start_session_recording(),
+            PaletteAction::RecordStop => self.stop_session_recording(),
+            PaletteAction::ReplayRecording => self.open_recording_dialog(),
+            PaletteAction::GenerateCommitMessage => self.generate_commit_message(),
+            PaletteAction::ExportConversation => {
+                self.open_conversation_dialog(ConversationFileAction::Export)
+            }
+            PaletteAction::ImportConversation => {
+                self.open_conversation_dialog(ConversationFileAction::Import)
+            }
+            PaletteAction::ShowLogs => self.open_log_view(),
+            PaletteAction::DebugCrash => self.crash_deliberately(),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::security_findings::severity_color;
+use super::AnTraftApp;
+use crate::security::command_analysis::CommandFinding;
+use crate::security::Severity;
+use crate::terminal::audit::CommandOrigin;
+use eframe::egui;
+
+/// Where the command in the input came from, when it wasn't typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertedFrom {
+    Ai,
+    Workflow,
+    /// A paste of several lines
+    Paste,
+}
+
+impl InsertedFrom {
+    fn label(self) -> &'static str {
+        match self {
+            InsertedFrom::Ai => "the AI",
+            InsertedFrom::Workflow => "a workflow",
+            InsertedFrom::Paste => "a paste",
+        }
+    }
+}
+
+/// What `security::command_analysis` found in a command that was put in the
+/// input rather than typed. Findings of medium severity or worse hold the
+/// command back until "Run anyway".
+#[derive(Default)]
+pub struct CommandReview {
+    source: Option<InsertedFrom>,
+    /// The input the findings are for
+    reviewed: Option<String>,
+    findings: Vec<CommandFinding>,
+    /// Running was stopped for the findings
+    held: bool,
+}
+
+impl CommandReview {
+    /// Checks the command again, as the rules changed.
+    pub fn recheck(&mut self) {
+        self.reviewed = None;
+    }
+
+    fn blocking(&self) -> bool {
+        self.findings.iter().any(|finding| finding.severity.rank() >= Severity::Medium.rank())
+    }
+}
+
+impl AnTraftApp {
+    /// Notes that the input was filled from `source`, to check it before
+    /// it runs.
+    pub(super) fn mark_inserted(&mut self, source: InsertedFrom) {
+        self.command_review = CommandReview {
+            source: Some(source),
+            ..Default::default()
+        };
+        self.review_command_input();
+    }
+
+    /// Whether this frame pastes more than one line into the focused input.
+    pub(super) fn multiline_paste(ui: &egui::Ui, input_id: egui::Id) -> bool {
+        ui.memory(|memory| memory.has_focus(input_id))
+            && ui.input(|i| {
+                i.events
+                    .iter()
+                    .any(|event| matches!(event, egui::Event::Paste(text) if text.trim_end().contains('\n')))
+            })
+    }
+
+    /// Checks the input again when it changed since the last check; edits
+    /// to an inserted command are checked too.
+    pub(super) fn review_command_input(&mut self) {
+        let review = &mut self.command_review;
+        if review.source.is_none() {
+            return;
+        }
+        if self.command_input.trim().is_empty() {
+            *review = CommandReview::default();
+            return;
+        }
+        if review.reviewed.as_deref() == Some(self.command_input.as_str()) {
+            return;
+        }
+        review.findings = self.config.security.analyze_command(&self.command_input);
+        review.reviewed = Some(self.command_input.clone());
+        review.held = false;
+    }
+
+    /// Whether running the input has to wait for the user to look at its
+    /// findings. Holds it back, showing "Run anyway", when it does.
+    pub(super) fn hold_for_review(&mut self) -> bool {
+        self.review_command_input();
+        let review = &mut self.command_review;
+        if review.blocking() {
+            review.held = true;
+        }
+        review.held
+    }
+
+    /// The findings for an inserted command, above the input.
+    pub(super) fn render_command_review(&mut self, ui: &mut egui::Ui) {
+        let review = &self.command_review;
+        let Some(source) = review.source else {
+            return;
+        };
+        if review.findings.is_empty() {
+            return;
+        }
+        let mut run_anyway = false;
+        let mut edit = false;
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.label(format!("🛡 Check this command from {} before running it:", source.label()));
+            for finding in &review.findings {
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(severity_color(&finding.severity), format!("{:?}", finding.severity));
+                    ui.strong(finding.title);
+                    if !finding.matched.is_empty() {
+                        ui.code(&finding.matched);
+                    }
+                });
+                ui.add(egui::Label::new(egui::RichText::new(finding.explanation).small()).wrap(true));
+            }
+            if review.held {
+                ui.horizontal(|ui| {
+                    run_anyway = ui.button("⚠ Run anyway").clicked();
+                    edit = ui.button("Edit").clicked();
+                });
+            }
+        });
+
+        if run_anyway {
+            let origin = match source {
+                InsertedFrom::Ai => CommandOrigin::Ai,
+                InsertedFrom::Workflow | InsertedFrom::Paste => CommandOrigin::User,
+            };
+            self.command_review = CommandReview::default();
+            self.submit_command_from(origin);
+        } else if edit {
+            self.command_review.held = false;
+            ui.memory_mut(|memory| memory.request_focus(Self::command_input_id()));
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use super::command_review::InsertedFrom;
+use super::{accessibility, AnTraftApp, UIMode};
+use crate::ai::intent::{self, InputIntent};
+use crate::ai::AiRequest;
+use crate::terminal::audit::CommandOrigin;
+use crate::terminal::engine::BUILTIN_COMMANDS;
+use eframe::egui;
+
+/// A command generated from a description typed on the welcome screen,
+/// shown for confirmation before it runs.
+pub struct CommandSuggestion {
+    /// What the user typed
+    pub request: String,
+    pub command: String,
+    pub explanation: String,
+    pub status: Option<String>,
+    response_rx: Option<crossbeam_channel::Receiver<Result<(String, String), String>>>,
+}
+
+impl CommandSuggestion {
+    fn poll(&mut self) {
+        let Some(rx) = &self.response_rx else {
+            return;
+        };
+        let Ok(result) = rx.try_recv() else {
+            return;
+        };
+        self.response_rx = None;
+
+        match result {
+            Ok((command, explanation)) => {
+                self.command = command;
+                self.explanation = explanation;
+            }
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    fn is_generating(&self) -> bool {
+        self.response_rx.is_some()
+    }
+}
+
+impl AnTraftApp {
+    /// Runs the welcome screen input, sends it to the AI panel, or generates
+    /// a command from it, depending on what it looks like.
+    pub(super) fn submit_welcome_input(&mut self) {
+        let input = self.command_input.trim().to_string();
+        if input.is_empty() {
+            return;
+        }
+        if input.starts_with("ai ") || input.starts_with("ask ") {
+            self.ai_input = self.command_input.clone();
+            self.current_mode = UIMode::AiAgent;
+            return;
+        }
+
+        let intent = if self.config.ai.detect_natural_language {
+            intent::classify_input(&input, |word| self.is_known_command(word))
+        } else {
+            InputIntent::Command
+        };
+        match intent {
+            InputIntent::Command => {
+                self.current_mode = UIMode::Terminal;
+                self.submit_command();
+            }
+            InputIntent::NaturalLanguage => {
+                self.command_input.clear();
+                self.suggest_command(input);
+            }
+        }
+    }
+
+    fn is_known_command(&self, word: &str) -> bool {
+        BUILTIN_COMMANDS.contains(&word)
+            || self.config.terminal.aliases.contains_key(word)
+            || which::which(word).is_ok()
+    }
+
+    fn suggest_command(&mut self, request: String) {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.command_suggestion = Some(CommandSuggestion {
+            request: request.clone(),
+            command: String::new(),
+            explanation: String::new(),
+            status: None,
+            response_rx: Some(rx),
+        });
+
+        let ai_agent = self.ai_agent.clone();
+        self.spawn_reported("The command suggestion", async move {
+            let result = ai_agent
+                .read()
+                .await
+                .process_request(AiRequest::GenerateCommand { description: request })
+                .await
+                .map_err(|e| format!("Couldn't generate a command: {}", e))
+                .and_then(|response| match response.code_snippets.first() {
+                    Some(snippet) => Ok((snippet.code.trim().to_string(), response.content)),
+                    None => Err("The AI didn't suggest a command.".to_string()),
+                });
+            let _ = tx.send(result);
+        });
+    }
+
+    /// The generated command, editable, with run/cancel choices.
+    pub(super) fn render_command_suggestion(&mut self, ui: &mut egui::Ui) {
+        let Some(suggestion) = self.command_suggestion.as_mut() else {
+            return;
+        };
+        suggestion.poll();
+
+        let mut run = false;
+        let mut run_as_typed = false;
+        let mut ask_ai = false;
+        let mut cancel = false;
+        ui.group(|ui| {
+            ui.set_max_width(650.0);
+            ui.horizontal(|ui| {
+                ui.label("✨");
+                ui.weak(&suggestion.request);
+            });
+            if suggestion.is_generating() {
+                ui.horizontal(|ui| {
+                    accessibility::spinner(ui);
+                    ui.label("Generating a command…");
+                });
+            } else if let Some(status) = &suggestion.status {
+                ui.colored_label(egui::Color32::from_rgb(220, 100, 100), status);
+            } else {
+                ui.add(
+                    egui::TextEdit::multiline(&mut suggestion.command)
+                        .code_editor()
+                        .desired_rows(1)
+                        .desired_width(f32::INFINITY),
+                );
+                if !suggestion.explanation.is_empty() {
+                    egui::CollapsingHeader::new("Explanation")
+                        .id_source("command_suggestion_explanation")
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                                ui.label(&suggestion.explanation);
+                            });
+                        });
+                }
+            }
+            ui.horizontal(|ui| {
+                let ready = !suggestion.is_generating() && !suggestion.command.trim().is_empty();
+                if ui.add_enabled(ready, egui::Button::new("⚡ Run")).clicked() {
+                    run = true;
+                }
+                if ui
+                    .button("Run as typed")
+                    .on_hover_text("It was a command after all")
+                    .clicked()
+                {
+                    run_as_typed = true;
+                }
+                if ui.button("🤖 Ask AI").clicked() {
+                    ask_ai = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+        if run || run_as_typed {
+            if let Some(suggestion) = self.command_suggestion.take() {
+                self.current_mode = UIMode::Terminal;
+                if run {
+                    self.command_input = suggestion.command.trim().to_string();
+                    self.mark_inserted(InsertedFrom::Ai);
+                    self.submit_command_from(CommandOrigin::Ai);
+                } else {
+                    self.command_input = suggestion.request;
+                    self.submit_command();
+                }
+            }
+        } else if ask_ai {
+            if let Some(suggestion) = self.command_suggestion.take() {
+                self.ai_input = suggestion.request;
+                self.current_mode = UIMode::AiAgent;
+            }
+        } else if cancel {
+            self.command_suggestion = None;
+        }
+    }
+}
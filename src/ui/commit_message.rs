@@ -0,0 +1,184 @@
+use super::{accessibility, AnTraftApp, UIMode};
+use crate::ai::commit_message::commit_command;
+use crate::ai::AiRequest;
+use crate::git;
+use crate::terminal::audit::CommandOrigin;
+use eframe::egui;
+
+const NO_STAGED_CHANGES: &str =
+    "No staged changes. Stage the files you want to commit with `git add` first.";
+
+pub struct CommitMessageDialog {
+    pub message: String,
+    pub editing: bool,
+    pub status: Option<String>,
+    response_rx: Option<crossbeam_channel::Receiver<Result<String, String>>>,
+}
+
+impl CommitMessageDialog {
+    fn poll(&mut self) {
+        let Some(rx) = &self.response_rx else {
+            return;
+        };
+        let Ok(result) = rx.try_recv() else {
+            return;
+        };
+        self.response_rx = None;
+
+        match result {
+            Ok(message) => {
+                self.message = message;
+                self.editing = false;
+                self.status = None;
+            }
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    fn is_generating(&self) -> bool {
+        self.response_rx.is_some()
+    }
+}
+
+impl AnTraftApp {
+    /// Opens the commit message dialog and generates a message for the
+    /// staged diff of the current directory.
+    pub(super) fn generate_commit_message(&mut self) {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        // Regenerating asks again rather than repeating the cached message
+        let regenerating = self.commit_dialog.is_some();
+        match self.commit_dialog.as_mut() {
+            Some(dialog) => {
+                dialog.status = None;
+                dialog.response_rx = Some(rx);
+            }
+            None => {
+                self.commit_dialog = Some(CommitMessageDialog {
+                    message: String::new(),
+                    editing: false,
+                    status: None,
+                    response_rx: Some(rx),
+                });
+            }
+        }
+
+        let cwd = self.working_directory.clone();
+        let convention = self.config.ai.commit_convention;
+        let ai_agent = self.ai_agent.clone();
+        self.spawn_reported("Generating the commit message", async move {
+            let diff = tokio::task::spawn_blocking(move || git::staged_diff(&cwd))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|diff| diff);
+            let diff = match diff {
+                Ok(diff) => diff,
+                Err(e) => {
+                    let _ = tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            if diff.trim().is_empty() {
+                let _ = tx.send(Err(NO_STAGED_CHANGES.to_string()));
+                return;
+            }
+
+            let request = AiRequest::CommitMessage {
+                diff: git::truncate_diff(&diff),
+                convention,
+            };
+            let ai_agent = ai_agent.read().await;
+            let response = match regenerating {
+                true => ai_agent.process_request_fresh(request).await,
+                false => ai_agent.process_request(request).await,
+            };
+            let result = response
+                .map(|response| response.content)
+                .map_err(|e| format!("Commit message generation failed: {}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Runs `git commit` with `message` as a regular terminal command.
+    fn commit_with_message(&mut self, message: &str) {
+        let Some(command) = commit_command(message, &self.config.terminal.shell) else {
+            return;
+        };
+        self.command_input = command;
+        self.submit_command_from(CommandOrigin::Ai);
+        self.current_mode = UIMode::Terminal;
+    }
+
+    pub(super) fn render_commit_message_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.commit_dialog.as_mut() else {
+            return;
+        };
+        dialog.poll();
+        if dialog.is_generating() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+
+        let mut open = true;
+        let mut regenerate = false;
+        let mut commit = None;
+        egui::Window::new("✨ Commit Message")
+            .open(&mut open)
+            .default_size([560.0, 320.0])
+            .show(ctx, |ui| {
+                if dialog.is_generating() {
+                    ui.horizontal(|ui| {
+                        accessibility::spinner(ui);
+                        ui.label("Writing commit message…");
+                    });
+                }
+                if let Some(status) = &dialog.status {
+                    ui.colored_label(egui::Color32::from_rgb(220, 100, 100), status);
+                }
+                if dialog.message.is_empty() {
+                    return;
+                }
+
+                ui.separator();
+                if dialog.editing {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut dialog.message)
+                            .code_editor()
+                            .desired_rows(8)
+                            .desired_width(f32::INFINITY),
+                    );
+                } else {
+                    egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        ui.monospace(&dialog.message);
+                    });
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let edit_label = if dialog.editing { "✔ Done" } else { "✏ Edit" };
+                    if ui.button(edit_label).clicked() {
+                        dialog.editing = !dialog.editing;
+                    }
+                    if ui
+                        .add_enabled(!dialog.is_generating(), egui::Button::new("🔄 Regenerate"))
+                        .clicked()
+                    {
+                        regenerate = true;
+                    }
+                    if ui
+                        .add_enabled(!dialog.is_generating(), egui::Button::new("✅ Commit with this message"))
+                        .clicked()
+                    {
+                        commit = Some(dialog.message.clone());
+                    }
+                });
+            });
+
+        if !open {
+            self.commit_dialog = None;
+        } else if regenerate {
+            self.generate_commit_message();
+        } else if let Some(message) = commit {
+            self.commit_dialog = None;
+            self.commit_with_message(&message);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use super::{AnTraftApp, Subsystem};
+use crate::autocomplete::completion::{self, CompletionKey, KeyOutcome};
+use crate::autocomplete::AutocompleteContext;
+use eframe::egui;
+use eframe::egui::text::{CCursor, CCursorRange};
+
+/// The list never grows taller than this; it scrolls instead.
+const LIST_HEIGHT: f32 = 180.0;
+
+const DOCS_WIDTH: f32 = 260.0;
+
+/// The keys the popup takes from the input while it's open.
+const KEYS: [(egui::Key, CompletionKey); 5] = [
+    (egui::Key::Tab, CompletionKey::Tab),
+    (egui::Key::ArrowDown, CompletionKey::Down),
+    (egui::Key::ArrowUp, CompletionKey::Up),
+    (egui::Key::Enter, CompletionKey::Enter),
+    (egui::Key::Escape, CompletionKey::Escape),
+];
+
+impl AnTraftApp {
+    /// The id of the command input, for its focus and cursor.
+    pub(super) fn command_input_id() -> egui::Id {
+        egui::Id::new("command_input")
+    }
+
+    /// Asks the providers again once the input changed. Not for `!`, which
+    /// has the workflow picker, or while a workflow is being filled in.
+    pub(super) fn refresh_completions(&mut self) {
+        if !self.completion.is_stale(&self.command_input) {
+            return;
+        }
+        let input = self.command_input.clone();
+        if input.trim().is_empty() || input.starts_with('!') || self.workflow_form.is_some() {
+            self.completion.update(&input, Vec::new());
+            return;
+        }
+        // Held for writing while specs load; the next frame tries again
+        let Ok(engine) = self.autocomplete_engine.try_read() else {
+            return;
+        };
+        let context = AutocompleteContext::new(
+            self.working_directory.to_string_lossy().to_string(),
+            self.config.terminal.shell.clone(),
+        )
+        .with_git_repository(self.git_status.context.is_some())
+        .with_recent_commands(self.command_history.iter().take(20).cloned().collect())
+        .with_remote(self.remote.is_remote());
+        let items = engine.get_suggestions(&input, &context);
+        drop(engine);
+        self.completion.update(&input, items);
+    }
+
+    /// Feeds Tab, Shift+Tab, the arrows, Enter and Escape to the popup
+    /// while the input has focus, taking the ones it uses away from the
+    /// input. Returns whether the selection moved.
+    pub(super) fn handle_completion_keys(&mut self, ui: &mut egui::Ui) -> bool {
+        let input_id = Self::command_input_id();
+        if !self.completion.is_open() || !ui.memory(|memory| memory.has_focus(input_id)) {
+            return false;
+        }
+        let mut moved = false;
+        for (key, completion_key) in KEYS {
+            let Some(shift) = ui.input(|i| i.key_pressed(key).then_some(i.modifiers.shift)) else {
+                continue;
+            };
+            let completion_key = match completion_key {
+                CompletionKey::Tab if shift => CompletionKey::ShiftTab,
+                other => other,
+            };
+            match self.completion.key(completion_key) {
+                KeyOutcome::Ignored => continue,
+                KeyOutcome::Handled => moved |= completion_key != CompletionKey::Escape,
+                KeyOutcome::Accepted(input) => self.set_command_input(ui.ctx(), input),
+            }
+            ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key));
+        }
+        moved
+    }
+
+    /// Replaces the input, with the cursor at its end.
+    pub(super) fn set_command_input(&mut self, ctx: &egui::Context, input: String) {
+        let input_id = Self::command_input_id();
+        if let Some(mut state) = egui::TextEdit::load_state(ctx, input_id) {
+            let end = CCursor::new(input.chars().count());
+            state.cursor.set_char_range(Some(CCursorRange::one(end)));
+            state.store(ctx, input_id);
+        }
+        self.command_input = input;
+    }
+
+    /// The completions above the input, each with its icon, and the docs
+    /// of the selected one beside them.
+    pub(super) fn render_completion_popup(&mut self, ui: &mut egui::Ui, scroll_to_selected: bool) {
+        if !self.completion.is_open() {
+            return;
+        }
+        let pointer_moved = ui.input(|i| i.pointer.delta() != egui::Vec2::ZERO);
+        let mut hovered = None;
+        let mut accepted = None;
+        ui.group(|ui| {
+            ui.horizontal_top(|ui| {
+                let list_width = (ui.available_width() - DOCS_WIDTH).max(200.0);
+                ui.vertical(|ui| {
+                    ui.set_width(list_width);
+                    egui::ScrollArea::vertical()
+                        .id_source("completion_list")
+                        .max_height(LIST_HEIGHT)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            for (index, item) in self.completion.items().iter().enumerate() {
+                                let selected = self.completion.selected() == Some(index);
+                                let label = format!("{} {}", completion::item_icon(item), item.text);
+                                let response = ui.selectable_label(selected, label);
+                                if response.clicked() {
+                                    accepted = Some(index);
+                                } else if response.hovered() && pointer_moved {
+                                    hovered = Some(index);
+                                }
+                                if selected && scroll_to_selected {
+                                    response.scroll_to_me(None);
+                                }
+                            }
+                        });
+                    let hint = "Tab/Shift+Tab to choose · Enter to insert · Esc to close";
+                    match self.is_ready(Subsystem::History) {
+                        true => ui.weak(hint),
+                        false => ui.weak(format!("{} · indexing history…", hint)),
+                    };
+                });
+                ui.separator();
+                ui.vertical(|ui| {
+                    ui.set_width(DOCS_WIDTH);
+                    self.render_completion_docs(ui);
+                });
+            });
+        });
+
+        if let Some(index) = hovered {
+            self.completion.select(index);
+        }
+        if let Some(input) = accepted.and_then(|index| self.completion.accept(index)) {
+            self.set_command_input(ui.ctx(), input);
+            ui.memory_mut(|memory| memory.request_focus(Self::command_input_id()));
+        }
+    }
+
+    fn render_completion_docs(&self, ui: &mut egui::Ui) {
+        let Some(item) = self.completion.selected_item() else {
+            ui.weak("Select a suggestion to see what it does");
+            return;
+        };
+        ui.strong(&item.text);
+        if !item.description.is_empty() {
+            ui.add(egui::Label::new(&item.description).wrap(true));
+        }
+        if let Some(snippet) = &item.snippet {
+            ui.add_space(4.0);
+            ui.label("Usage:");
+            ui.add(egui::Label::new(egui::RichText::new(snippet).monospace()).wrap(true));
+        }
+        ui.add_space(4.0);
+        ui.weak("Inserts:");
+        let inserted = completion::apply_completion(self.completion.query(), &item.insert_text);
+        ui.add(egui::Label::new(egui::RichText::new(inserted).monospace()).wrap(true));
+    }
+}
@@ -0,0 +1,63 @@
+use super::{accessibility, AnTraftApp, UIMode};
+use eframe::egui;
+
+impl AnTraftApp {
+    /// "🐳" in the status bar: the running containers, each with a shell
+    /// and a log follower. Listed when the menu opens.
+    pub(super) fn render_containers_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(docker) = self.docker.clone() else {
+            return;
+        };
+        let cached = docker.cached();
+        let label = match cached.containers.len() {
+            0 => "🐳".to_string(),
+            running => format!("🐳 {}", running),
+        };
+
+        let mut command = None;
+        ui.menu_button(label, |ui| {
+            let snapshot = docker.snapshot();
+            if docker.is_refreshing() {
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+            }
+            ui.strong("Containers");
+            if let Some(error) = &snapshot.error {
+                ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+            } else if snapshot.containers.is_empty() {
+                if docker.is_refreshing() {
+                    accessibility::spinner(ui);
+                } else {
+                    ui.weak("No running containers");
+                }
+            }
+            for container in &snapshot.containers {
+                ui.separator();
+                ui.label(&container.name)
+                    .on_hover_text(format!("{} · {}", container.id, container.status));
+                ui.weak(&container.image);
+                ui.horizontal(|ui| {
+                    // No terminal to attach, so the shell reads the block's
+                    // input instead of a tty
+                    if ui
+                        .small_button("Open shell")
+                        .on_hover_text("Send commands with the block's ⌨ input")
+                        .clicked()
+                    {
+                        command = Some(format!("docker exec -i {} sh", container.name));
+                        ui.close_menu();
+                    }
+                    if ui.small_button("Follow logs").clicked() {
+                        command = Some(format!("docker logs -f --tail 200 {}", container.name));
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
+
+        if let Some(command) = command {
+            self.current_mode = UIMode::Terminal;
+            self.command_input = command;
+            self.submit_command();
+        }
+    }
+}
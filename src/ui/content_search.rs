@@ -0,0 +1,302 @@
+use super::{accessibility, AnTraftApp};
+use crate::file_explorer::search::{
+    self, ContentSearchEvent, ContentSearchOptions, FileMatches, LineMatch, SearchBackend,
+};
+use crate::file_explorer::CancellationToken;
+use crate::terminal::Block;
+use eframe::egui;
+use std::path::PathBuf;
+
+const MAX_MATCHES_PER_FILE: usize = 100;
+
+/// The search stops once this many files have matches.
+const MAX_RESULT_FILES: usize = 500;
+
+/// The "Search in Files" panel (Ctrl+Shift+F).
+#[derive(Default)]
+pub struct ContentSearch {
+    pub open: bool,
+    query: String,
+    case_sensitive: bool,
+    regex: bool,
+    whole_word: bool,
+    /// Comma-separated globs
+    include: String,
+    exclude: String,
+    root: PathBuf,
+    results: Vec<FileMatches>,
+    backend: Option<SearchBackend>,
+    /// Ok for a summary, Err for a failure
+    status: Option<Result<String, String>>,
+    running: Option<(CancellationToken, crossbeam_channel::Receiver<ContentSearchEvent>)>,
+    focus_query: bool,
+}
+
+impl ContentSearch {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus_query = self.open;
+    }
+
+    fn cancel(&mut self) {
+        if let Some((token, _)) = &self.running {
+            token.cancel();
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.results.iter().map(|file| file.matches.len()).sum()
+    }
+}
+
+enum SearchResultAction {
+    Preview(PathBuf, usize),
+    Edit(PathBuf, usize, usize),
+}
+
+impl AnTraftApp {
+    fn start_content_search(&mut self) {
+        let search = &mut self.content_search;
+        search.cancel();
+
+        let split = |globs: &str| -> Vec<String> {
+            globs
+                .split(',')
+                .map(str::trim)
+                .filter(|glob| !glob.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let options = ContentSearchOptions {
+            query: search.query.clone(),
+            case_sensitive: search.case_sensitive,
+            regex: search.regex,
+            whole_word: search.whole_word,
+            include: split(&search.include),
+            exclude: split(&search.exclude),
+            excluded_paths: self.config.security.excluded_paths.clone(),
+            max_matches_per_file: MAX_MATCHES_PER_FILE,
+            max_file_size: self.config.security.max_file_size_mb * 1024 * 1024,
+        };
+        if let Err(e) = options.pattern() {
+            search.status = Some(Err(e.to_string()));
+            return;
+        }
+
+        let root = self.working_directory.clone();
+        let token = CancellationToken::new();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        search.results.clear();
+        search.backend = None;
+        search.status = None;
+        search.root = root.clone();
+        search.running = Some((token.clone(), rx));
+
+        self.spawn_blocking_reported("Searching in files", move || {
+            search::search_contents(&root, &options, &token, &tx);
+        });
+    }
+
+    fn poll_content_search(&mut self, ctx: &egui::Context) {
+        let search = &mut self.content_search;
+        let Some((token, rx)) = &search.running else {
+            return;
+        };
+
+        let mut finished = None;
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                ContentSearchEvent::Started(backend) => search.backend = Some(backend),
+                ContentSearchEvent::File(file) => {
+                    search.results.push(file);
+                    if search.results.len() >= MAX_RESULT_FILES {
+                        token.cancel();
+                    }
+                }
+                ContentSearchEvent::Finished => finished = Some(Ok(String::new())),
+                ContentSearchEvent::Cancelled if search.results.len() >= MAX_RESULT_FILES => {
+                    finished = Some(Ok(format!("Stopped after {} files", MAX_RESULT_FILES)))
+                }
+                ContentSearchEvent::Cancelled => finished = Some(Ok("Cancelled".to_string())),
+                ContentSearchEvent::Failed(e) => finished = Some(Err(e)),
+            }
+        }
+
+        match finished {
+            Some(status) => {
+                search.running = None;
+                search.status = Some(status.map(|note| {
+                    let summary = format!("{} matches in {} files", search.match_count(), search.results.len());
+                    if note.is_empty() {
+                        summary
+                    } else {
+                        format!("{} · {}", summary, note)
+                    }
+                }));
+            }
+            None => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+        }
+    }
+
+    pub(super) fn render_content_search(&mut self, ctx: &egui::Context) {
+        if !self.content_search.open {
+            return;
+        }
+        self.poll_content_search(ctx);
+
+        let mut open = true;
+        let mut submit = false;
+        let mut action = None;
+        let search = &mut self.content_search;
+        egui::Window::new("🔎 Search in Files")
+            .open(&mut open)
+            .default_size([560.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut search.query)
+                            .desired_width(300.0)
+                            .hint_text("Search"),
+                    );
+                    if std::mem::take(&mut search.focus_query) {
+                        response.request_focus();
+                    }
+                    submit |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    ui.toggle_value(&mut search.case_sensitive, "Aa").on_hover_text("Match case");
+                    ui.toggle_value(&mut search.whole_word, "ab").on_hover_text("Whole word");
+                    ui.toggle_value(&mut search.regex, ".*").on_hover_text("Regular expression");
+                });
+                egui::Grid::new("content_search_globs").num_columns(2).show(ui, |ui| {
+                    ui.label("Include");
+                    submit |= ui
+                        .add(egui::TextEdit::singleline(&mut search.include).hint_text("*.rs, src/**"))
+                        .lost_focus()
+                        && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    ui.end_row();
+                    ui.label("Exclude");
+                    submit |= ui
+                        .add(egui::TextEdit::singleline(&mut search.exclude).hint_text("*.min.js, tests/**"))
+                        .lost_focus()
+                        && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    if search.running.is_some() {
+                        accessibility::spinner(ui);
+                        ui.small(format!("{} files so far", search.results.len()));
+                        if ui.small_button("Cancel").clicked() {
+                            search.cancel();
+                        }
+                    } else if ui.add_enabled(!search.query.is_empty(), egui::Button::new("Search")).clicked() {
+                        submit = true;
+                    }
+                    match &search.status {
+                        Some(Ok(summary)) => {
+                            ui.small(summary);
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(egui::Color32::from_rgb(220, 100, 100), e);
+                        }
+                        None => {}
+                    }
+                    if let Some(backend) = search.backend {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.weak(match backend {
+                                SearchBackend::Ripgrep => "ripgrep",
+                                SearchBackend::Builtin => "built-in",
+                            });
+                        });
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                    for file in &search.results {
+                        let relative = file.path.strip_prefix(&search.root).unwrap_or(&file.path);
+                        let count = if file.truncated {
+                            format!("{}+", file.matches.len())
+                        } else {
+                            file.matches.len().to_string()
+                        };
+                        egui::CollapsingHeader::new(format!("{}  ({})", relative.display(), count))
+                            .id_source(&file.path)
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for line_match in &file.matches {
+                                    if let Some(clicked) = render_line_match(ui, file, line_match) {
+                                        action = Some(clicked);
+                                    }
+                                }
+                            });
+                    }
+                });
+            });
+
+        if !open {
+            self.content_search.cancel();
+            self.content_search.open = false;
+        }
+        if submit && !self.content_search.query.is_empty() {
+            self.start_content_search();
+        }
+        match action {
+            Some(SearchResultAction::Preview(path, line)) => self.open_file_preview_at(path, line),
+            Some(SearchResultAction::Edit(path, line, column)) => {
+                if let Err(e) = self.open_in_editor(&path, Some(line as u32), Some(column as u32)) {
+                    self.terminal_output
+                        .push(super::TerminalBlock::from_builtin(Block::error(e)));
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// One matching line, with the matches highlighted. Clicking it previews
+/// the file at that line; ↗ opens it in the editor.
+fn render_line_match(ui: &mut egui::Ui, file: &FileMatches, line_match: &LineMatch) -> Option<SearchResultAction> {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let highlighted = egui::TextFormat {
+        background: egui::Color32::from_rgb(110, 90, 20),
+        color: egui::Color32::WHITE,
+        ..egui::TextFormat::simple(font, ui.visuals().text_color())
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    let line = line_match.line.trim_end();
+    let indent = line.len() - line.trim_start().len();
+    let mut position = indent;
+    for range in &line_match.ranges {
+        let start = range.start.max(position).min(line.len());
+        let end = range.end.min(line.len());
+        if start >= end {
+            continue;
+        }
+        job.append(&line[position..start], 0.0, plain.clone());
+        job.append(&line[start..end], 0.0, highlighted.clone());
+        position = end;
+    }
+    job.append(&line[position..], 0.0, plain);
+
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.weak(format!("{:>5}", line_match.line_number));
+        if ui
+            .add(egui::Label::new(job).sense(egui::Sense::click()).truncate(true))
+            .on_hover_text("Open in preview")
+            .clicked()
+        {
+            action = Some(SearchResultAction::Preview(file.path.clone(), line_match.line_number));
+        }
+        if accessibility::icon_button(ui, "↗", "Open in editor").clicked() {
+            let column = line_match
+                .ranges
+                .first()
+                .map_or(1, |range| line_match.line[..range.start].chars().count() + 1);
+            action = Some(SearchResultAction::Edit(file.path.clone(), line_match.line_number, column));
+        }
+    });
+    action
+}
@@ -0,0 +1,207 @@
+use super::{accessibility, AnTraftApp};
+use crate::ai::chat::{ChatMessage, MessageRole};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversationFileAction {
+    Export,
+    Import,
+}
+
+enum ConversationFileResult {
+    Exported(PathBuf),
+    Imported(Vec<ChatMessage>),
+}
+
+/// The "Export conversation…" / "Import conversation…" dialog.
+pub struct ConversationFileDialog {
+    action: ConversationFileAction,
+    path: String,
+    /// Ok for a success message, Err for a failure
+    status: Option<Result<String, String>>,
+    result_rx: Option<crossbeam_channel::Receiver<Result<ConversationFileResult, String>>>,
+}
+
+impl ConversationFileDialog {
+    fn is_busy(&self) -> bool {
+        self.result_rx.is_some()
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn open_conversation_dialog(&mut self, action: ConversationFileAction) {
+        let path = match action {
+            ConversationFileAction::Export => self
+                .working_directory
+                .join(format!("conversation-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S")))
+                .to_string_lossy()
+                .to_string(),
+            ConversationFileAction::Import => String::new(),
+        };
+        self.conversation_dialog = Some(ConversationFileDialog {
+            action,
+            path,
+            status: None,
+            result_rx: None,
+        });
+    }
+
+    /// Writes the active conversation to `path`, as markdown for `.md` files
+    /// and JSON otherwise.
+    fn export_conversation(&mut self, path: PathBuf) {
+        let ai_agent = self.ai_agent.clone();
+        self.start_conversation_file_task(async move {
+            let agent = ai_agent.read().await;
+            let markdown = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+            let content = if markdown {
+                agent.export_chat_to_markdown().await
+            } else {
+                agent
+                    .export_chat_to_json()
+                    .await
+                    .transpose()
+                    .map_err(|e| e.to_string())?
+            };
+            let content = content.ok_or_else(|| "There is no conversation to export yet".to_string())?;
+            write_file(&path, &content)?;
+            Ok(ConversationFileResult::Exported(path))
+        });
+    }
+
+    /// Loads a JSON conversation as a new, active session.
+    fn import_conversation(&mut self, path: PathBuf) {
+        let ai_agent = self.ai_agent.clone();
+        self.start_conversation_file_task(async move {
+            let json = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+            let messages = ai_agent
+                .read()
+                .await
+                .import_chat_from_json(&json)
+                .await
+                .map_err(|e| format!("Couldn't import {}: {:#}", path.display(), e))?;
+            Ok(ConversationFileResult::Imported(messages))
+        });
+    }
+
+    fn start_conversation_file_task(
+        &mut self,
+        task: impl std::future::Future<Output = Result<ConversationFileResult, String>> + Send + 'static,
+    ) {
+        let Some(dialog) = self.conversation_dialog.as_mut() else {
+            return;
+        };
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        dialog.result_rx = Some(rx);
+        dialog.status = None;
+        self.runtime_handle.spawn(async move {
+            let _ = tx.send(task.await);
+        });
+    }
+
+    fn poll_conversation_dialog(&mut self) {
+        let Some(dialog) = self.conversation_dialog.as_mut() else {
+            return;
+        };
+        let Some(Ok(result)) = dialog.result_rx.as_ref().map(|rx| rx.try_recv()) else {
+            return;
+        };
+        dialog.result_rx = None;
+
+        dialog.status = Some(match result {
+            Ok(ConversationFileResult::Exported(path)) => Ok(format!("Exported to {}", path.display())),
+            Ok(ConversationFileResult::Imported(messages)) => {
+                let count = messages.len();
+                self.ai_messages = messages
+                    .into_iter()
+                    .map(|message| (transcript_role(&message.role).to_string(), message.content))
+                    .collect();
+                Ok(format!("Imported {} messages", count))
+            }
+            Err(e) => Err(e),
+        });
+    }
+
+    pub(super) fn render_conversation_dialog(&mut self, ctx: &egui::Context) {
+        self.poll_conversation_dialog();
+        let Some(dialog) = self.conversation_dialog.as_mut() else {
+            return;
+        };
+        if dialog.is_busy() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        let (title, button, hint) = match dialog.action {
+            ConversationFileAction::Export => (
+                "💾 Export Conversation",
+                "Export",
+                "Save as .json to import it later, or .md to share it",
+            ),
+            ConversationFileAction::Import => (
+                "📂 Import Conversation",
+                "Import",
+                "A .json file exported from ANTRAFT; it opens as a new conversation",
+            ),
+        };
+
+        let mut open = true;
+        let mut run = None;
+        egui::Window::new(title)
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.small(hint);
+                ui.horizontal(|ui| {
+                    ui.label("File");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut dialog.path)
+                            .desired_width(320.0)
+                            .hint_text("/path/to/conversation.json"),
+                    );
+                    let ready = !dialog.is_busy() && !dialog.path.trim().is_empty();
+                    if ui.add_enabled(ready, egui::Button::new(button)).clicked() {
+                        run = Some(PathBuf::from(dialog.path.trim()));
+                    }
+                    if dialog.is_busy() {
+                        accessibility::spinner(ui);
+                    }
+                });
+                match &dialog.status {
+                    Some(Ok(message)) => {
+                        ui.small(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(egui::Color32::from_rgb(220, 100, 100), message);
+                    }
+                    None => {}
+                }
+            });
+
+        let action = dialog.action;
+        if !open {
+            self.conversation_dialog = None;
+        } else if let Some(path) = run {
+            match action {
+                ConversationFileAction::Export => self.export_conversation(path),
+                ConversationFileAction::Import => self.import_conversation(path),
+            }
+        }
+    }
+}
+
+/// The sender label `ai_messages` uses for a chat role.
+fn transcript_role(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "You",
+        MessageRole::Assistant => "AI",
+        MessageRole::System => "System",
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+}
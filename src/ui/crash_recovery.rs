@@ -0,0 +1,204 @@
+//! Getting over crashes: the session kept up to date for the panic hook to
+//! save, the dialog offering it back on the next launch, and background
+//! tasks whose panics become notifications instead of vanishing.
+
+use super::{AnTraftApp, TerminalBlock, UIMode};
+use crate::crash::{self, CrashMarker, SessionSnapshot};
+use crate::terminal::block::{metadata_keys, Block, BlockType};
+use crate::terminal::editor::EditorIntegration;
+use anyhow::anyhow;
+use eframe::egui;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// How often the session the panic hook saves is brought up to date.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The most recent blocks kept in the snapshot.
+const SNAPSHOT_BLOCKS: usize = 200;
+
+/// The end of each block's output kept in the snapshot.
+const SNAPSHOT_OUTPUT_BYTES: usize = 64 * 1024;
+
+pub struct CrashRecovery {
+    dir: PathBuf,
+    snapshot: Arc<Mutex<SessionSnapshot>>,
+    snapshot_at: Option<Instant>,
+    /// The last run's crash, until its dialog is closed
+    marker: Option<CrashMarker>,
+}
+
+impl CrashRecovery {
+    /// Picks up the crash marker the last run left, if it crashed.
+    pub fn load() -> Self {
+        let dir = crate::config::data_dir();
+        Self {
+            marker: crash::take_crash_marker(&dir),
+            dir,
+            snapshot: Arc::default(),
+            snapshot_at: None,
+        }
+    }
+}
+
+impl AnTraftApp {
+    /// Has a crash save the command history and the session.
+    pub(super) fn register_crash_flushes(&self) {
+        let history = self.history.clone();
+        let history_path = self.history_path.clone();
+        crash::on_crash("command history", move || match history.try_read() {
+            Ok(history) => history.save(&history_path),
+            Err(_) => Err(anyhow!("it was being changed")),
+        });
+
+        let snapshot = self.crash_recovery.snapshot.clone();
+        let session_path = crash::session_path(&self.crash_recovery.dir);
+        crash::on_crash("session", move || {
+            let snapshot = snapshot.try_lock().map_err(|_| anyhow!("it was being updated"))?;
+            crash::save_session(&session_path, &snapshot)
+        });
+    }
+
+    /// Brings the snapshot a crash would save up to date, every
+    /// `SNAPSHOT_INTERVAL`.
+    pub(super) fn refresh_crash_snapshot(&mut self) {
+        let recovery = &mut self.crash_recovery;
+        if recovery.snapshot_at.is_some_and(|at| at.elapsed() < SNAPSHOT_INTERVAL) {
+            return;
+        }
+        recovery.snapshot_at = Some(Instant::now());
+
+        let start = self.terminal_output.len().saturating_sub(SNAPSHOT_BLOCKS);
+        let blocks = self.terminal_output[start..].iter().map(snapshot_block).collect();
+        let snapshot = SessionSnapshot {
+            working_directory: self.working_directory.to_string_lossy().to_string(),
+            command_input: self.command_input.clone(),
+            blocks,
+            chat: self.ai_messages.clone(),
+        };
+        if let Ok(mut saved) = recovery.snapshot.lock() {
+            *saved = snapshot;
+        }
+    }
+
+    /// After a crash, offers the saved session back and the report.
+    pub(super) fn render_crash_dialog(&mut self, ctx: &egui::Context) {
+        let Some(marker) = self.crash_recovery.marker.clone() else {
+            return;
+        };
+        let mut close = false;
+        let mut restore = false;
+        egui::Window::new("ANTRAFT quit unexpectedly")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "The last session crashed at {}.",
+                    marker.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+                ));
+                ui.small(format!("The crash report is in {}", marker.report.display()));
+                ui.horizontal(|ui| {
+                    if marker.session.is_some() && ui.button("Restore session").clicked() {
+                        restore = true;
+                    }
+                    if ui.button("Open crash report").clicked() {
+                        let editor = EditorIntegration::new(self.config.terminal.editor_command.clone());
+                        if let Err(e) = editor.open(&marker.report, None, None) {
+                            self.notifications.sender().error("Couldn't open the crash report", format!("{:#}", e));
+                        }
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if restore {
+            if let Some(path) = &marker.session {
+                self.restore_crashed_session(path);
+            }
+        }
+        if restore || close {
+            if let Some(path) = &marker.session {
+                let _ = std::fs::remove_file(path);
+            }
+            self.crash_recovery.marker = None;
+        }
+    }
+
+    fn restore_crashed_session(&mut self, path: &std::path::Path) {
+        let session = match crash::load_session(path) {
+            Ok(session) => session,
+            Err(e) => {
+                self.notifications.sender().error("Couldn't restore the session", format!("{:#}", e));
+                return;
+            }
+        };
+        let count = session.blocks.len();
+        let blocks: Vec<TerminalBlock> = session.blocks.into_iter().map(TerminalBlock::from_builtin).collect();
+        self.terminal_output.splice(0..0, blocks);
+        self.ai_messages.splice(0..0, session.chat);
+        if self.command_input.is_empty() {
+            self.command_input = session.command_input;
+        }
+        self.current_mode = UIMode::Terminal;
+        self.notifications.sender().info(format!(
+            "Restored {} block{} from {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            session.working_directory
+        ));
+    }
+
+    /// Spawns `future`, with a panic in it reported as an error notification
+    /// saying `what` crashed.
+    pub(super) fn spawn_reported<F>(&self, what: &'static str, future: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let notifier = self.notifications.sender();
+        self.runtime_handle.spawn(async move {
+            if let Err(message) = crash::catch_panic(future).await {
+                notifier.error(format!("{} crashed", what), message);
+            }
+        })
+    }
+
+    /// `spawn_reported` for blocking work.
+    pub(super) fn spawn_blocking_reported<F>(&self, what: &'static str, work: F) -> JoinHandle<()>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let notifier = self.notifications.sender();
+        self.runtime_handle.spawn_blocking(move || {
+            if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(work)) {
+                notifier.error(format!("{} crashed", what), crash::panic_message(payload.as_ref()));
+            }
+        })
+    }
+
+    /// The hidden "Debug: Crash" palette action, for trying crash reports.
+    pub(super) fn crash_deliberately(&self) -> ! {
+        panic!("Deliberate crash from the command palette");
+    }
+}
+
+/// A block as the snapshot keeps it, with the end of its output.
+fn snapshot_block(block: &TerminalBlock) -> Block {
+    let mut start = block.output.len().saturating_sub(SNAPSHOT_OUTPUT_BYTES);
+    while !block.output.is_char_boundary(start) {
+        start += 1;
+    }
+    let mut snapshot = Block::new(BlockType::Output, block.output[start..].to_string());
+    snapshot.id = block.id;
+    snapshot.timestamp = block.timestamp;
+    snapshot.exit_code = block.exit_code;
+    snapshot.metadata = block.metadata.clone();
+    snapshot.set_metadata(metadata_keys::COMMAND.to_string(), block.command.clone());
+    snapshot.set_metadata(metadata_keys::WORKING_DIRECTORY.to_string(), block.working_directory.clone());
+    snapshot
+}
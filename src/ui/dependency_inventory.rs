@@ -0,0 +1,235 @@
+use super::{accessibility, AnTraftApp};
+use crate::security::inventory::{DependencyInventory, InventoryEntry};
+use anyhow::Result;
+use eframe::egui;
+use std::path::PathBuf;
+
+const DENIED_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 200, 90);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Ecosystem,
+    Name,
+    Version,
+    License,
+    Source,
+}
+
+impl Column {
+    const ALL: [Column; 5] = [Column::Ecosystem, Column::Name, Column::Version, Column::License, Column::Source];
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Ecosystem => "Ecosystem",
+            Column::Name => "Package",
+            Column::Version => "Version",
+            Column::License => "License",
+            Column::Source => "Found in",
+        }
+    }
+
+    fn key(self, entry: &InventoryEntry) -> String {
+        match self {
+            Column::Ecosystem => entry.ecosystem.name().to_string(),
+            Column::Name => entry.name.to_lowercase(),
+            Column::Version => entry.version.clone(),
+            // Unknown sorts last
+            Column::License => entry.license.clone().unwrap_or_else(|| "\u{10FFFF}".to_string()),
+            Column::Source => entry.license_source.map(|source| source.label()).unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// The "Inventory" tab of the security panel: the project's dependencies
+/// with their licenses.
+pub struct InventoryView {
+    inventory: Option<DependencyInventory>,
+    inventory_rx: Option<crossbeam_channel::Receiver<Result<DependencyInventory, String>>>,
+    /// Ask the registries for licenses not found locally
+    online: bool,
+    sort: (Column, bool),
+    /// Indexes into the entries, in the sorted order
+    order: Vec<usize>,
+    status: Option<String>,
+}
+
+impl Default for InventoryView {
+    fn default() -> Self {
+        Self {
+            inventory: None,
+            inventory_rx: None,
+            online: false,
+            sort: (Column::Name, false),
+            order: Vec::new(),
+            status: None,
+        }
+    }
+}
+
+impl InventoryView {
+    fn poll(&mut self) {
+        let Some(result) = self.inventory_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.inventory_rx = None;
+        match result {
+            Ok(inventory) => {
+                self.status = None;
+                self.inventory = Some(inventory);
+                self.sort();
+            }
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    fn sort_by(&mut self, column: Column) {
+        let descending = self.sort == (column, false);
+        self.sort = (column, descending);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        let Some(inventory) = &self.inventory else {
+            return;
+        };
+        let (column, descending) = self.sort;
+        self.order = (0..inventory.entries.len()).collect();
+        self.order.sort_by_cached_key(|&index| column.key(&inventory.entries[index]));
+        if descending {
+            self.order.reverse();
+        }
+    }
+}
+
+impl AnTraftApp {
+    fn build_inventory(&mut self) {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let view = &mut self.security_findings.inventory;
+        view.inventory_rx = Some(rx);
+        view.status = None;
+        let online = view.online;
+        let root = self.working_directory.clone();
+        let config = self.config.security.clone();
+        self.spawn_reported("The dependency inventory", async move {
+            let result = DependencyInventory::build(&root, &config, online)
+                .await
+                .map_err(|e| format!("Couldn't build the inventory: {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Writes the inventory as CSV to the data directory.
+    fn export_inventory(&self) -> Result<PathBuf> {
+        let Some(inventory) = &self.security_findings.inventory.inventory else {
+            anyhow::bail!("No inventory built yet");
+        };
+        let dir = crate::config::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("license-inventory-{}.csv", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+        std::fs::write(&path, inventory.to_csv())?;
+        Ok(path)
+    }
+
+    pub(super) fn render_inventory_tab(&mut self, ui: &mut egui::Ui) {
+        let view = &mut self.security_findings.inventory;
+        view.poll();
+        let building = view.inventory_rx.is_some();
+        if building {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+        }
+
+        let mut build = false;
+        let mut export = false;
+        ui.horizontal(|ui| {
+            let label = if view.inventory.is_some() { "🔄 Rebuild" } else { "📦 Build inventory" };
+            build = ui.add_enabled(!building, egui::Button::new(label)).clicked();
+            ui.checkbox(&mut view.online, "Look up unknown licenses online")
+                .on_hover_text("Ask crates.io, npm and PyPI for licenses the lockfiles and installed packages don't give");
+            export = ui
+                .add_enabled(view.inventory.is_some(), egui::Button::new("Export CSV"))
+                .clicked();
+            if building {
+                accessibility::spinner(ui);
+            }
+        });
+        if let Some(status) = &view.status {
+            ui.small(status);
+        }
+
+        let mut sort_by = None;
+        match &view.inventory {
+            None => {
+                ui.label("Lists the packages pinned by Cargo.lock, package-lock.json, poetry.lock and requirements.txt files in the working directory, with their licenses.");
+            }
+            Some(inventory) => {
+                let denied = inventory.entries.iter().filter(|entry| entry.denied_by.is_some()).count();
+                ui.label(format!(
+                    "{} packages · {} with an unknown license · {} denied",
+                    inventory.entries.len(),
+                    inventory.unknown().count(),
+                    denied
+                ));
+                for error in &inventory.errors {
+                    ui.colored_label(egui::Color32::from_rgb(220, 160, 80), format!("⚠ {}", error));
+                }
+                ui.horizontal_wrapped(|ui| {
+                    for (license, count) in inventory.license_counts() {
+                        ui.weak(format!("{} × {}", count, license));
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                    egui::Grid::new("license_inventory").striped(true).show(ui, |ui| {
+                        for column in Column::ALL {
+                            let arrow = match view.sort {
+                                (sorted, false) if sorted == column => " ⏶",
+                                (sorted, true) if sorted == column => " ⏷",
+                                _ => "",
+                            };
+                            let text = egui::RichText::new(format!("{}{}", column.header(), arrow)).strong();
+                            if ui.add(egui::Button::new(text).frame(false)).on_hover_text("Sort").clicked() {
+                                sort_by = Some(column);
+                            }
+                        }
+                        ui.end_row();
+                        for &index in &view.order {
+                            let entry = &inventory.entries[index];
+                            ui.label(entry.ecosystem.name());
+                            ui.monospace(&entry.name);
+                            ui.monospace(&entry.version);
+                            match (&entry.license, &entry.denied_by) {
+                                (Some(license), Some(denied_by)) => {
+                                    ui.colored_label(DENIED_COLOR, format!("⚠ {}", license))
+                                        .on_hover_text(format!("Denied by `{}` in security.license_denylist", denied_by));
+                                }
+                                (Some(license), None) => {
+                                    ui.label(license);
+                                }
+                                (None, _) => {
+                                    ui.weak("Unknown");
+                                }
+                            }
+                            ui.weak(entry.license_source.map(|source| source.label()).unwrap_or_default());
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+        }
+
+        if let Some(column) = sort_by {
+            view.sort_by(column);
+        }
+        if build {
+            self.build_inventory();
+        }
+        if export {
+            let status = match self.export_inventory() {
+                Ok(path) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Export failed: {}", e),
+            };
+            self.security_findings.inventory.status = Some(status);
+        }
+    }
+}
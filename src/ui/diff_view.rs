@@ -0,0 +1,198 @@
+use super::output_pager::PAGER_VISIBLE_LINES;
+use crate::git::diff::{self, Diff, DiffLine, DiffLineKind};
+use eframe::egui;
+
+pub(super) const ADDED_TEXT: egui::Color32 = egui::Color32::from_rgb(150, 220, 150);
+pub(super) const ADDED_BACKGROUND: egui::Color32 = egui::Color32::from_rgb(28, 52, 32);
+pub(super) const REMOVED_TEXT: egui::Color32 = egui::Color32::from_rgb(230, 140, 140);
+pub(super) const REMOVED_BACKGROUND: egui::Color32 = egui::Color32::from_rgb(64, 28, 28);
+pub(super) const HUNK_HEADER: egui::Color32 = egui::Color32::from_rgb(100, 160, 220);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffMode {
+    #[default]
+    Unified,
+    SideBySide,
+    /// The output as it came
+    Plain,
+}
+
+#[derive(Debug, Clone)]
+enum Row {
+    /// Commit headers and the like, before the first file
+    Preamble(String),
+    File(String),
+    Note(String),
+    Hunk(String),
+    Line(DiffLine),
+    Pair(Option<DiffLine>, Option<DiffLine>),
+}
+
+/// A diff-producing command's output, parsed once it has finished and shown
+/// colored, unified or side by side. Output that doesn't parse as a unified
+/// diff is left to the pager.
+#[derive(Debug, Clone, Default)]
+pub struct DiffView {
+    pub mode: DiffMode,
+    unified: Vec<Row>,
+    side_by_side: Vec<Row>,
+    checked: bool,
+}
+
+impl DiffView {
+    /// Parses the output of a finished diff command, once.
+    pub fn update(&mut self, command: &str, output: &str, is_running: bool) {
+        if self.checked || is_running {
+            return;
+        }
+        self.checked = true;
+        if !is_diff_command(command) {
+            return;
+        }
+        if let Some(diff) = diff::parse_unified_diff(output) {
+            self.unified = rows(&diff, false);
+            self.side_by_side = rows(&diff, true);
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.unified.is_empty()
+    }
+
+    /// Whether the output should be shown as a diff.
+    pub fn is_active(&self) -> bool {
+        self.is_available() && self.mode != DiffMode::Plain
+    }
+}
+
+/// `git diff`, `git show`, `git log -p`, `git stash show -p` and `diff`.
+fn is_diff_command(command: &str) -> bool {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let has = |flags: &[&str]| words.iter().any(|word| flags.contains(word));
+    match words.as_slice() {
+        ["git", rest @ ..] => match rest.iter().find(|word| !word.starts_with('-')) {
+            Some(&"diff") | Some(&"show") => true,
+            Some(&"log") | Some(&"stash") => has(&["-p", "--patch"]),
+            _ => false,
+        },
+        ["diff", ..] => has(&["-u", "-ru", "-ur", "-Nru", "--unified"]),
+        _ => false,
+    }
+}
+
+fn rows(diff: &Diff, side_by_side: bool) -> Vec<Row> {
+    let mut rows: Vec<Row> = diff.preamble.iter().cloned().map(Row::Preamble).collect();
+    for file in &diff.files {
+        rows.push(Row::File(match &file.old_path {
+            Some(old_path) => format!("{} → {}", old_path, file.path),
+            None => file.path.clone(),
+        }));
+        rows.extend(file.notes.iter().cloned().map(Row::Note));
+        for hunk in &file.hunks {
+            rows.push(Row::Hunk(hunk.header.clone()));
+            if side_by_side {
+                rows.extend(
+                    diff::side_by_side(hunk)
+                        .into_iter()
+                        .map(|(old, new)| Row::Pair(old.cloned(), new.cloned())),
+                );
+            } else {
+                rows.extend(hunk.lines.iter().cloned().map(Row::Line));
+            }
+        }
+    }
+    rows
+}
+
+/// Unified / Side by side / Plain, for the block header.
+pub fn render_mode_toggle(ui: &mut egui::Ui, view: &mut DiffView) {
+    ui.selectable_value(&mut view.mode, DiffMode::Unified, "Unified")
+        .on_hover_text("Show the diff colored, one column");
+    ui.selectable_value(&mut view.mode, DiffMode::SideBySide, "Split")
+        .on_hover_text("Show old and new side by side");
+    ui.selectable_value(&mut view.mode, DiffMode::Plain, "Plain")
+        .on_hover_text("Show the output as it came");
+}
+
+pub fn render_diff(ui: &mut egui::Ui, block_id: uuid::Uuid, view: &DiffView) {
+    let rows = match view.mode {
+        DiffMode::SideBySide => &view.side_by_side,
+        _ => &view.unified,
+    };
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let spacing = ui.spacing().item_spacing.y;
+    egui::ScrollArea::both()
+        .id_source(("diff_rows", block_id, view.mode == DiffMode::SideBySide))
+        .max_height((row_height + spacing) * PAGER_VISIBLE_LINES as f32)
+        .auto_shrink([false, true])
+        .show_rows(ui, row_height, rows.len(), |ui, range| {
+            let half = (ui.available_width() / 2.0).max(120.0);
+            for row in &rows[range] {
+                match row {
+                    Row::Preamble(text) => {
+                        ui.add(egui::Label::new(egui::RichText::new(text).monospace().weak()).wrap(false));
+                    }
+                    Row::File(path) => {
+                        ui.add(egui::Label::new(egui::RichText::new(path).monospace().strong()).wrap(false));
+                    }
+                    Row::Note(text) => {
+                        ui.add(egui::Label::new(egui::RichText::new(text).monospace().italics().weak()).wrap(false));
+                    }
+                    Row::Hunk(header) => {
+                        ui.add(egui::Label::new(egui::RichText::new(header).monospace().color(HUNK_HEADER)).wrap(false));
+                    }
+                    Row::Line(line) => {
+                        ui.horizontal(|ui| {
+                            ui.weak(egui::RichText::new(line_number(line.old_line)).monospace());
+                            ui.weak(egui::RichText::new(line_number(line.new_line)).monospace());
+                            ui.add(egui::Label::new(line_text(line, true)).wrap(false));
+                        });
+                    }
+                    Row::Pair(old, new) => {
+                        ui.horizontal(|ui| {
+                            for (line, number) in [(old, old.as_ref().and_then(|l| l.old_line)), (new, new.as_ref().and_then(|l| l.new_line))] {
+                                ui.allocate_ui_with_layout(
+                                    egui::vec2(half - spacing, row_height),
+                                    egui::Layout::left_to_right(egui::Align::Center),
+                                    |ui| {
+                                        ui.set_min_width(half - spacing);
+                                        ui.weak(egui::RichText::new(line_number(number)).monospace());
+                                        if let Some(line) = line {
+                                            ui.add(egui::Label::new(line_text(line, false)).truncate(true));
+                                        }
+                                    },
+                                );
+                            }
+                        });
+                    }
+                }
+            }
+        });
+}
+
+fn line_number(number: Option<u32>) -> String {
+    match number {
+        Some(number) => format!("{:>4}", number),
+        None => "    ".to_string(),
+    }
+}
+
+fn line_text(line: &DiffLine, with_marker: bool) -> egui::RichText {
+    let (marker, text, background) = match line.kind {
+        DiffLineKind::Added => ('+', Some(ADDED_TEXT), Some(ADDED_BACKGROUND)),
+        DiffLineKind::Removed => ('-', Some(REMOVED_TEXT), Some(REMOVED_BACKGROUND)),
+        DiffLineKind::Context => (' ', None, None),
+    };
+    let content = match with_marker {
+        true => format!("{}{}", marker, line.text),
+        false => line.text.clone(),
+    };
+    let mut rich = egui::RichText::new(content).monospace();
+    if let Some(color) = text {
+        rich = rich.color(color);
+    }
+    if let Some(color) = background {
+        rich = rich.background_color(color);
+    }
+    rich
+}
@@ -0,0 +1,180 @@
+use super::{accessibility, AnTraftApp, TerminalBlock};
+use crate::file_explorer::{CancellationToken, FileNode, FileType, TreeLoadEvent};
+use crate::terminal::Block;
+use eframe::egui;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+/// A directory tree being built in the background.
+pub struct TreeLoad {
+    token: CancellationToken,
+    events: mpsc::UnboundedReceiver<TreeLoadEvent>,
+    discovered: usize,
+}
+
+impl TreeLoad {
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+impl AnTraftApp {
+    fn start_tree_load(&mut self) {
+        let Ok(explorer) = self.file_explorer.try_read() else {
+            return;
+        };
+        let loader = explorer.tree_loader();
+        drop(explorer);
+
+        let token = CancellationToken::new();
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.tree_load = Some(TreeLoad {
+            token: token.clone(),
+            events: rx,
+            discovered: 0,
+        });
+        self.tree_load_cancelled = false;
+        self.file_tree_loaded = true;
+
+        let file_explorer = self.file_explorer.clone();
+        self.spawn_blocking_reported("Loading the file tree", move || {
+            let event = match loader.load(&token, Some(&tx)) {
+                Ok(Some(root)) => {
+                    file_explorer.blocking_write().set_root_node(root);
+                    TreeLoadEvent::Finished
+                }
+                Ok(None) => TreeLoadEvent::Cancelled,
+                Err(e) => TreeLoadEvent::Failed(e.to_string()),
+            };
+            let _ = tx.send(event);
+        });
+    }
+
+    fn poll_tree_load(&mut self) {
+        let Some(load) = self.tree_load.as_mut() else {
+            return;
+        };
+
+        while let Ok(event) = load.events.try_recv() {
+            match event {
+                TreeLoadEvent::Progress { discovered } => load.discovered = discovered,
+                TreeLoadEvent::Finished => {
+                    self.tree_load = None;
+                    return;
+                }
+                TreeLoadEvent::Cancelled => {
+                    self.tree_load = None;
+                    self.tree_load_cancelled = true;
+                    return;
+                }
+                TreeLoadEvent::Failed(message) => {
+                    self.notifications.sender().error("Couldn't load the file tree", message);
+                    self.tree_load = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn render_file_explorer(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("📁 Files");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .add_enabled(self.tree_load.is_none(), egui::Button::new("⟳").small())
+                    .on_hover_text("Refresh")
+                    .clicked()
+                {
+                    self.file_tree_loaded = false;
+                }
+            });
+        });
+        ui.separator();
+        self.render_remote_files(ui);
+
+        if !self.file_tree_loaded && self.tree_load.is_none() {
+            self.start_tree_load();
+        }
+        self.poll_tree_load();
+
+        if let Some(load) = &self.tree_load {
+            ui.horizontal(|ui| {
+                accessibility::spinner(ui);
+                ui.label(format!("{} files discovered…", load.discovered));
+                if ui.small_button("Cancel").clicked() {
+                    load.cancel();
+                }
+            });
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+            ui.separator();
+        } else if self.tree_load_cancelled {
+            ui.weak("Loading cancelled. Press ⟳ to try again.");
+            ui.separator();
+        }
+
+        let mut clicked: Option<(PathBuf, FileType)> = None;
+        let mut edit: Option<PathBuf> = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let Ok(explorer) = self.file_explorer.try_read() else {
+                accessibility::spinner(ui);
+                return;
+            };
+            match explorer.get_root_node() {
+                Some(root) => {
+                    if let Some(children) = &root.children {
+                        for child in children {
+                            render_file_node(ui, child, &mut clicked, &mut edit);
+                        }
+                    }
+                }
+                None => {
+                    ui.label("No files loaded");
+                }
+            }
+        });
+
+        if let Some((path, file_type)) = clicked {
+            self.open_file_preview(path, &file_type);
+        }
+        if let Some(path) = edit {
+            if let Err(e) = self.open_in_editor(&path, None, None) {
+                self.terminal_output.push(TerminalBlock::from_builtin(Block::error(e)));
+            }
+        }
+    }
+}
+
+fn render_file_node(
+    ui: &mut egui::Ui,
+    node: &FileNode,
+    clicked: &mut Option<(PathBuf, FileType)>,
+    edit: &mut Option<PathBuf>,
+) {
+    if node.is_directory {
+        egui::CollapsingHeader::new(format!("{} {}", node.icon(), node.name))
+            .id_source(&node.path)
+            .show(ui, |ui| {
+                if node.is_git_ignored {
+                    ui.weak("(ignored)");
+                }
+                if let Some(children) = &node.children {
+                    for child in children {
+                        render_file_node(ui, child, clicked, edit);
+                    }
+                }
+            });
+    } else {
+        let response = ui
+            .selectable_label(false, format!("{} {}", node.icon(), node.name))
+            .on_hover_text(format!("{} · {}", node.formatted_size(), node.formatted_modified()));
+        if response.clicked() {
+            *clicked = Some((node.path.clone(), node.file_type.clone()));
+        }
+        response.context_menu(|ui| {
+            if ui.button("📝 Open in editor").clicked() {
+                *edit = Some(node.path.clone());
+                ui.close_menu();
+            }
+        });
+    }
+}
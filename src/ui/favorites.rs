@@ -0,0 +1,233 @@
+use super::{accessibility, AnTraftApp, UIMode};
+use eframe::egui;
+
+/// Recent commands offered for starring below the favorites.
+const RECENT_COMMANDS: usize = 10;
+
+const STAR_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 190, 60);
+
+/// The ★ palette: favorite commands to run with one click, and recent
+/// commands to star.
+#[derive(Debug, Default)]
+pub struct FavoritesPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+    /// Distinct commands from history, newest first, as of opening
+    recent: Vec<String>,
+    /// The favorite whose label is being edited, and the label so far
+    editing: Option<(String, String)>,
+}
+
+enum FavoriteAction {
+    Run(String),
+    Toggle(String),
+    EditLabel(String),
+    CancelEdit,
+    SaveLabel(String, String),
+}
+
+/// A ☆/★ button starring or unstarring a command; returns whether it was
+/// clicked.
+pub fn render_star(ui: &mut egui::Ui, starred: bool) -> bool {
+    let (text, hover) = match starred {
+        true => (egui::RichText::new("★").color(STAR_COLOR), "Remove from favorites"),
+        false => (egui::RichText::new("☆"), "Add to favorites"),
+    };
+    ui.add(egui::Button::new(text).frame(false)).on_hover_text(hover).clicked()
+}
+
+impl AnTraftApp {
+    pub(super) fn open_favorites(&mut self) {
+        let palette = &mut self.favorites_palette;
+        palette.open = true;
+        palette.query.clear();
+        palette.selected = 0;
+        palette.editing = None;
+        palette.recent.clear();
+        // History is only locked briefly by writers; skip the recent list
+        // rather than wait
+        if let Ok(history) = self.history.try_read() {
+            for entry in history.get_all_entries().iter().rev() {
+                // Extra, for those filtered out as favorites or by the query
+                if palette.recent.len() == RECENT_COMMANDS * 3 {
+                    break;
+                }
+                if !palette.recent.contains(&entry.command) {
+                    palette.recent.push(entry.command.clone());
+                }
+            }
+        }
+    }
+
+    /// Stars `command`, or unstars it if it's a favorite, and saves the
+    /// favorites.
+    pub(super) fn toggle_favorite(&mut self, command: &str) {
+        self.favorites.toggle(command);
+        self.save_favorites();
+    }
+
+    fn save_favorites(&self) {
+        if let Err(e) = self.favorites.save() {
+            self.notifications.sender().error("Couldn't save favorites", format!("{:#}", e));
+        }
+    }
+
+    fn run_favorite(&mut self, command: String) {
+        self.favorites_palette.open = false;
+        self.current_mode = UIMode::Terminal;
+        self.command_input = command;
+        self.submit_command();
+    }
+
+    pub(super) fn render_favorites_palette(&mut self, ctx: &egui::Context) {
+        if !self.favorites_palette.open {
+            return;
+        }
+
+        let palette = &mut self.favorites_palette;
+        let matches: Vec<String> = self
+            .favorites
+            .matches(&palette.query)
+            .into_iter()
+            .map(|favorite| favorite.command.clone())
+            .collect();
+        palette.selected = palette.selected.min(matches.len().saturating_sub(1));
+
+        let mut action = None;
+        if palette.editing.is_none() {
+            let (up, down, enter, escape) = ctx.input(|i| {
+                (
+                    i.key_pressed(egui::Key::ArrowUp),
+                    i.key_pressed(egui::Key::ArrowDown),
+                    i.key_pressed(egui::Key::Enter),
+                    i.key_pressed(egui::Key::Escape),
+                )
+            });
+            if escape {
+                palette.open = false;
+                return;
+            }
+            if up {
+                palette.selected = palette.selected.saturating_sub(1);
+            }
+            if down && palette.selected + 1 < matches.len() {
+                palette.selected += 1;
+            }
+            if enter {
+                action = matches.get(palette.selected).cloned().map(FavoriteAction::Run);
+            }
+        }
+
+        let mut open = true;
+        egui::Window::new("★ Favorites")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([480.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut palette.query)
+                        .hint_text("Find a favorite…")
+                        .desired_width(f32::INFINITY),
+                );
+                if palette.editing.is_none() {
+                    response.request_focus();
+                }
+                if response.changed() {
+                    palette.selected = 0;
+                }
+                ui.separator();
+
+                if self.favorites.is_empty() {
+                    ui.weak("No favorites yet. Star a command with ☆ on its block or below.");
+                } else if matches.is_empty() {
+                    ui.weak("No matching favorites");
+                }
+                egui::ScrollArea::vertical().max_height(280.0).show(ui, |ui| {
+                    for (index, command) in matches.iter().enumerate() {
+                        let Some(favorite) = self.favorites.get(command) else {
+                            continue;
+                        };
+                        ui.horizontal(|ui| {
+                            if render_star(ui, true) {
+                                action = Some(FavoriteAction::Toggle(command.clone()));
+                            }
+                            if let Some((editing, label)) = palette.editing.as_mut().filter(|(c, _)| c == command) {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(label)
+                                        .hint_text("Label")
+                                        .desired_width(200.0),
+                                );
+                                response.request_focus();
+                                if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                                    action = Some(FavoriteAction::CancelEdit);
+                                } else if ui.small_button("Save").clicked()
+                                    || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                                {
+                                    action = Some(FavoriteAction::SaveLabel(editing.clone(), label.clone()));
+                                }
+                                ui.weak(command);
+                                return;
+                            }
+                            let selected = index == palette.selected;
+                            let title = ui.selectable_label(selected, favorite.title()).on_hover_text("Run");
+                            if title.clicked() {
+                                action = Some(FavoriteAction::Run(command.clone()));
+                            }
+                            if favorite.label.is_some() {
+                                ui.label(egui::RichText::new(command).monospace().weak());
+                            }
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if accessibility::icon_button(ui, "✏", "Edit label").clicked() {
+                                    action = Some(FavoriteAction::EditLabel(command.clone()));
+                                }
+                            });
+                        });
+                    }
+                });
+
+                let query = palette.query.to_lowercase();
+                let recent: Vec<&String> = palette
+                    .recent
+                    .iter()
+                    .filter(|command| !self.favorites.contains(command))
+                    .filter(|command| command.to_lowercase().contains(&query))
+                    .take(RECENT_COMMANDS)
+                    .collect();
+                if !recent.is_empty() {
+                    ui.separator();
+                    ui.weak("Recent commands");
+                    for command in recent {
+                        ui.horizontal(|ui| {
+                            if render_star(ui, false) {
+                                action = Some(FavoriteAction::Toggle(command.clone()));
+                            }
+                            ui.label(egui::RichText::new(command).monospace());
+                        });
+                    }
+                }
+            });
+        if !open {
+            self.favorites_palette.open = false;
+        }
+
+        match action {
+            Some(FavoriteAction::Run(command)) => self.run_favorite(command),
+            Some(FavoriteAction::Toggle(command)) => self.toggle_favorite(&command),
+            Some(FavoriteAction::EditLabel(command)) => {
+                self.favorites_palette.editing = self.favorites.get(&command).map(|favorite| {
+                    (favorite.command.clone(), favorite.label.clone().unwrap_or_default())
+                });
+            }
+            Some(FavoriteAction::CancelEdit) => self.favorites_palette.editing = None,
+            Some(FavoriteAction::SaveLabel(command, label)) => {
+                self.favorites_palette.editing = None;
+                self.favorites.set_label(&command, &label);
+                self.save_favorites();
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use super::{accessibility, AnTraftApp};
+use crate::ai::{AiRequest, AiResponse};
+use crate::file_explorer::{FileNode, FileType};
+use eframe::egui;
+use eframe::egui::text::{CCursor, CCursorRange};
+use std::path::PathBuf;
+
+/// Files larger than this are truncated in the preview.
+const MAX_PREVIEW_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct PreviewSelection {
+    pub text: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+pub struct FilePreview {
+    pub path: PathBuf,
+    pub content: String,
+    pub language: Option<String>,
+    pub truncated: bool,
+    pub selection: Option<PreviewSelection>,
+    pub explanation: Option<String>,
+    /// Line to scroll to and select on the next frame, 1-based
+    pub goto_line: Option<usize>,
+    explanation_rx: Option<crossbeam_channel::Receiver<String>>,
+}
+
+impl FilePreview {
+    pub fn open(path: PathBuf, file_type: &FileType) -> std::io::Result<Self> {
+        let bytes = std::fs::read(&path)?;
+        let truncated = bytes.len() > MAX_PREVIEW_BYTES;
+        let content = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_PREVIEW_BYTES)]).to_string();
+
+        let language = match file_type {
+            FileType::SourceCode(language) => Some(language.clone()),
+            _ => None,
+        };
+
+        Ok(Self {
+            path,
+            content,
+            language,
+            truncated,
+            selection: None,
+            explanation: None,
+            goto_line: None,
+            explanation_rx: None,
+        })
+    }
+
+    pub fn is_explaining(&self) -> bool {
+        self.explanation_rx.is_some()
+    }
+
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+
+    fn poll_explanation(&mut self) {
+        if let Some(rx) = &self.explanation_rx {
+            if let Ok(explanation) = rx.try_recv() {
+                self.explanation = Some(explanation);
+                self.explanation_rx = None;
+            }
+        }
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn open_file_preview(&mut self, path: PathBuf, file_type: &FileType) {
+        match FilePreview::open(path.clone(), file_type) {
+            Ok(preview) => self.file_preview = Some(preview),
+            Err(e) => log::error!("Failed to open {} for preview: {}", path.display(), e),
+        }
+    }
+
+    /// Opens the preview with `line` selected and scrolled into view.
+    pub(super) fn open_file_preview_at(&mut self, path: PathBuf, line: usize) {
+        let file_type = match FileNode::new(path.clone()) {
+            Ok(node) => node.file_type,
+            Err(e) => {
+                log::error!("Failed to open {} for preview: {}", path.display(), e);
+                return;
+            }
+        };
+        self.open_file_preview(path, &file_type);
+        if let Some(preview) = self.file_preview.as_mut() {
+            preview.goto_line = Some(line);
+        }
+    }
+
+    /// Sends the current preview selection to the AI agent and shows the answer
+    /// inline in the preview window.
+    fn explain_preview_selection(&mut self) {
+        let Some(preview) = self.file_preview.as_mut() else {
+            return;
+        };
+        let Some(selection) = preview.selection.clone() else {
+            return;
+        };
+
+        let language = preview.language.clone().unwrap_or_else(|| "text".to_string());
+        let message = format!(
+            "Explain what this {} code from {} (lines {}-{}) does:\n\n```{}\n{}\n```",
+            language,
+            preview.path.display(),
+            selection.start_line,
+            selection.end_line,
+            language,
+            selection.text
+        );
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        preview.explanation = None;
+        preview.explanation_rx = Some(rx);
+
+        let ai_agent = self.ai_agent.clone();
+        self.runtime_handle.spawn(async move {
+            let response: anyhow::Result<AiResponse> = ai_agent
+                .read()
+                .await
+                .process_request(AiRequest::Chat { message })
+                .await;
+            let text = match response {
+                Ok(response) => response.content,
+                Err(e) => format!("Sorry, I encountered an error: {}", e),
+            };
+            let _ = tx.send(text);
+        });
+    }
+
+    pub(super) fn render_file_preview(&mut self, ctx: &egui::Context) {
+        let Some(preview) = self.file_preview.as_mut() else {
+            return;
+        };
+        preview.poll_explanation();
+        if preview.is_explaining() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+
+        let mut open = true;
+        let mut explain_clicked = false;
+        let title = format!("📄 {}", preview.file_name());
+
+        egui::Window::new(title)
+            .id(egui::Id::new("file_preview"))
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.small(preview.path.display().to_string());
+                    if let Some(language) = &preview.language {
+                        ui.small(format!("· {}", language));
+                    }
+                    if preview.truncated {
+                        ui.small("· truncated");
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    let has_selection = preview.selection.is_some();
+                    let button = ui.add_enabled(
+                        has_selection && !preview.is_explaining(),
+                        egui::Button::new("🤖 Explain with AI"),
+                    );
+                    if button.clicked() {
+                        explain_clicked = true;
+                    }
+                    match &preview.selection {
+                        Some(selection) => {
+                            ui.small(format!("lines {}-{} selected", selection.start_line, selection.end_line));
+                        }
+                        None => {
+                            ui.small("Select some code to explain it");
+                        }
+                    }
+                });
+                ui.separator();
+
+                if preview.is_explaining() || preview.explanation.is_some() {
+                    egui::TopBottomPanel::bottom("file_preview_explanation")
+                        .resizable(true)
+                        .default_height(160.0)
+                        .show_inside(ui, |ui| {
+                            egui::ScrollArea::vertical().show(ui, |ui| {
+                                match &preview.explanation {
+                                    Some(explanation) => {
+                                        ui.label(explanation);
+                                    }
+                                    None => {
+                                        ui.horizontal(|ui| {
+                                            accessibility::spinner(ui);
+                                            ui.label("Explaining selection…");
+                                        });
+                                    }
+                                }
+                            });
+                        });
+                }
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    // A `&str` buffer keeps the text read-only while still allowing selection
+                    let mut text = preview.content.as_str();
+                    let output = egui::TextEdit::multiline(&mut text)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .show(ui);
+
+                    if let Some(line) = preview.goto_line.take() {
+                        let mut lines = preview.content.split_inclusive('\n');
+                        let start: usize = lines.by_ref().take(line.saturating_sub(1)).map(|l| l.chars().count()).sum();
+                        let len = lines.next().map_or(0, |l| l.trim_end_matches('\n').chars().count());
+                        let mut state = output.state.clone();
+                        state
+                            .cursor
+                            .set_char_range(Some(CCursorRange::two(CCursor::new(start), CCursor::new(start + len))));
+                        state.store(ui.ctx(), output.response.id);
+                        let rect = output
+                            .galley
+                            .pos_from_ccursor(CCursor::new(start))
+                            .translate(output.galley_pos.to_vec2());
+                        ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                    }
+
+                    if let Some(cursor_range) = output.cursor_range {
+                        let range = cursor_range.as_sorted_char_range();
+                        preview.selection = if range.is_empty() {
+                            None
+                        } else {
+                            let [start, end] = cursor_range.sorted_cursors();
+                            Some(PreviewSelection {
+                                text: preview.content.chars().skip(range.start).take(range.len()).collect(),
+                                start_line: start.pcursor.paragraph + 1,
+                                end_line: end.pcursor.paragraph + 1,
+                            })
+                        };
+                    }
+                });
+            });
+
+        if explain_clicked {
+            self.explain_preview_selection();
+        }
+        if !open {
+            self.file_preview = None;
+        }
+    }
+}
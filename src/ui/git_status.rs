@@ -0,0 +1,81 @@
+use super::AnTraftApp;
+use crate::git::GitContext;
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// How often the git context is re-read while the app is idle.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Git state of the current directory, shown in the status bar.
+#[derive(Default)]
+pub struct GitStatus {
+    pub context: Option<GitContext>,
+    last_refresh: Option<Instant>,
+    refresh_rx: Option<crossbeam_channel::Receiver<Option<GitContext>>>,
+}
+
+impl AnTraftApp {
+    /// Re-reads the git context in the background.
+    pub(super) fn refresh_git_status(&mut self) {
+        if self.git_status.refresh_rx.is_some() {
+            return;
+        }
+        let cwd = self.working_directory.clone();
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.git_status.refresh_rx = Some(rx);
+        self.git_status.last_refresh = Some(Instant::now());
+        self.runtime_handle.spawn_blocking(move || {
+            let _ = tx.send(GitContext::detect(&cwd));
+        });
+    }
+
+    pub(super) fn poll_git_status(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.git_status.refresh_rx {
+            if let Ok(context) = rx.try_recv() {
+                let branch = |context: &Option<GitContext>| context.as_ref().and_then(|c| c.branch.clone());
+                if branch(&context) != branch(&self.git_status.context) {
+                    self.window_title.mark_stale();
+                }
+                self.git_status.context = context;
+                self.git_status.refresh_rx = None;
+            }
+        }
+
+        let due = self
+            .git_status
+            .last_refresh
+            .is_none_or(|last| last.elapsed() >= REFRESH_INTERVAL);
+        if due {
+            self.refresh_git_status();
+        }
+        ctx.request_repaint_after(REFRESH_INTERVAL);
+    }
+
+    /// Branch and staged-changes indicator for the status bar.
+    pub(super) fn render_git_status(&mut self, ui: &mut egui::Ui) {
+        let Some(context) = self.git_status.context.clone() else {
+            return;
+        };
+
+        if context.has_staged_changes
+            && ui
+                .small_button("✨ Generate commit message")
+                .on_hover_text("Write a commit message for the staged changes")
+                .clicked()
+        {
+            self.generate_commit_message();
+        }
+
+        let mut label = format!("⎇ {}", context.branch.as_deref().unwrap_or("(no branch)"));
+        if context.has_staged_changes {
+            label.push_str(" ●");
+        }
+        let hover = match (context.has_staged_changes, context.has_unstaged_changes) {
+            (true, _) => "Staged changes",
+            (false, true) => "Unstaged changes",
+            (false, false) => "Clean working tree",
+        };
+        ui.small(label).on_hover_text(hover);
+    }
+}
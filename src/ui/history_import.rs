@@ -0,0 +1,206 @@
+use super::AnTraftApp;
+use crate::terminal::history::{self, HistoryEntry, IMPORTABLE_SHELLS};
+use eframe::egui;
+use log::{error, info};
+
+/// Entries are fed into the history and autocomplete index in chunks so the
+/// progress bar has something to report on large history files.
+const IMPORT_CHUNK_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Default)]
+pub enum HistoryImportState {
+    #[default]
+    Idle,
+    Scanning,
+    Offer {
+        shells: Vec<(String, usize)>,
+    },
+    Running {
+        done: usize,
+        total: usize,
+    },
+    Finished {
+        imported: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum HistoryImportEvent {
+    Scanned(Vec<(String, usize)>),
+    Progress { done: usize, total: usize },
+    Finished { imported: usize },
+    Failed(String),
+}
+
+impl AnTraftApp {
+    /// Counts importable commands per shell in the background and, if there are
+    /// any, offers the import.
+    pub(super) fn scan_shell_histories(&mut self) {
+        self.history_import = HistoryImportState::Scanning;
+        let sender = self.history_import_tx.clone();
+
+        self.runtime_handle.spawn_blocking(move || {
+            let shells = IMPORTABLE_SHELLS
+                .iter()
+                .filter_map(|shell| match history::read_shell_history(shell) {
+                    Ok(entries) if !entries.is_empty() => Some((shell.to_string(), entries.len())),
+                    Ok(_) => None,
+                    Err(e) => {
+                        error!("Failed to read {} history: {}", shell, e);
+                        None
+                    }
+                })
+                .collect();
+            let _ = sender.send(HistoryImportEvent::Scanned(shells));
+        });
+    }
+
+    pub(super) fn start_history_import(&mut self, shells: Vec<String>) {
+        self.history_import = HistoryImportState::Running { done: 0, total: 0 };
+
+        let sender = self.history_import_tx.clone();
+        let history = self.history.clone();
+        let history_path = self.history_path.clone();
+        let autocomplete_engine = self.autocomplete_engine.clone();
+
+        self.runtime_handle.spawn(async move {
+            let mut entries: Vec<HistoryEntry> = Vec::new();
+            for shell in &shells {
+                match history::read_shell_history(shell) {
+                    Ok(mut shell_entries) => entries.append(&mut shell_entries),
+                    Err(e) => {
+                        let _ = sender.send(HistoryImportEvent::Failed(format!(
+                            "Failed to read {} history: {}",
+                            shell, e
+                        )));
+                        return;
+                    }
+                }
+            }
+
+            let total = entries.len();
+            let mut done = 0;
+            let mut imported = 0;
+            let _ = sender.send(HistoryImportEvent::Progress { done, total });
+
+            while !entries.is_empty() {
+                let chunk: Vec<HistoryEntry> =
+                    entries.drain(..IMPORT_CHUNK_SIZE.min(entries.len())).collect();
+                done += chunk.len();
+
+                {
+                    let mut engine = autocomplete_engine.write().await;
+                    for entry in &chunk {
+                        engine.add_to_history(entry.command.clone(), entry.runs(), entry.timestamp);
+                    }
+                }
+                imported += history.write().await.import_entries(chunk);
+
+                let _ = sender.send(HistoryImportEvent::Progress { done, total });
+                tokio::task::yield_now().await;
+            }
+
+            if let Err(e) = history.read().await.save(&history_path) {
+                let _ = sender.send(HistoryImportEvent::Failed(format!(
+                    "Imported history could not be saved: {}",
+                    e
+                )));
+                return;
+            }
+
+            info!("Imported {} commands from shell history", imported);
+            let _ = sender.send(HistoryImportEvent::Finished { imported });
+        });
+    }
+
+    /// Records that the first-run offer was answered so it isn't shown again.
+    fn dismiss_history_import(&mut self) {
+        self.history_import = HistoryImportState::Idle;
+        let history = self.history.clone();
+        let history_path = self.history_path.clone();
+        self.runtime_handle.spawn(async move {
+            if let Err(e) = history.read().await.save(&history_path) {
+                error!("Failed to save history: {}", e);
+            }
+        });
+    }
+
+    pub(super) fn handle_history_import_event(&mut self, event: HistoryImportEvent) {
+        self.history_import = match event {
+            HistoryImportEvent::Scanned(shells) if shells.is_empty() => {
+                self.dismiss_history_import();
+                HistoryImportState::Finished { imported: 0 }
+            }
+            HistoryImportEvent::Scanned(shells) => HistoryImportState::Offer { shells },
+            HistoryImportEvent::Progress { done, total } => {
+                HistoryImportState::Running { done, total }
+            }
+            HistoryImportEvent::Finished { imported } => HistoryImportState::Finished { imported },
+            HistoryImportEvent::Failed(message) => {
+                error!("{}", message);
+                HistoryImportState::Idle
+            }
+        };
+    }
+
+    pub(super) fn render_history_import(&mut self, ctx: &egui::Context) {
+        match self.history_import.clone() {
+            HistoryImportState::Offer { shells } => {
+                let total: usize = shells.iter().map(|(_, count)| count).sum();
+                let names = shells
+                    .iter()
+                    .map(|(shell, _)| shell.as_str())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                egui::Window::new("Import shell history")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        ui.label(format!("Import {} commands from your {} history?", total, names));
+                        ui.small("They'll show up in history search and autocomplete.");
+                        ui.add_space(8.0);
+                        ui.horizontal(|ui| {
+                            if ui.button("Import").clicked() {
+                                let shells = shells.into_iter().map(|(shell, _)| shell).collect();
+                                self.start_history_import(shells);
+                            }
+                            if ui.button("Not now").clicked() {
+                                self.dismiss_history_import();
+                            }
+                        });
+                    });
+            }
+            HistoryImportState::Running { done, total } => {
+                egui::Window::new("Importing shell history")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        let progress = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                        ui.add(
+                            egui::ProgressBar::new(progress)
+                                .text(format!("{} / {} commands", done, total))
+                                .animate(true),
+                        );
+                    });
+                ctx.request_repaint();
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn history_import_status(&self) -> Option<String> {
+        match &self.history_import {
+            HistoryImportState::Scanning => Some("Looking for shell history…".to_string()),
+            HistoryImportState::Running { done, total } => {
+                Some(format!("Importing… {} / {}", done, total))
+            }
+            HistoryImportState::Finished { imported } => {
+                Some(format!("Last import added {} new commands", imported))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::{AnTraftApp, UIMode};
+use crate::ai::script::{script_shell, shell_quote};
+use crate::kubectl::Query;
+use eframe::egui;
+
+impl AnTraftApp {
+    /// "☸ context/namespace" in the status bar, with the contexts and
+    /// namespaces to switch to. Hidden until kubectl names a current context.
+    pub(super) fn render_kube_menu(&mut self, ui: &mut egui::Ui) {
+        if self.remote.is_remote() {
+            return;
+        }
+        let Some(kubectl) = self.kubectl.clone() else {
+            return;
+        };
+        let Some(context) = kubectl.get_one(Query::CurrentContext) else {
+            return;
+        };
+        let namespace = kubectl
+            .get_one(Query::CurrentNamespace)
+            .unwrap_or_else(|| "default".to_string());
+
+        let shell = script_shell(&self.config.terminal.shell);
+        let mut command = None;
+        ui.menu_button(format!("☸ {}/{}", context, namespace), |ui| {
+            if kubectl.is_refreshing() {
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+            }
+            ui.strong("Contexts");
+            for other in kubectl.get(Query::Contexts) {
+                if ui.selectable_label(other == context, &other).clicked() {
+                    command = Some(format!("kubectl config use-context {}", shell_quote(&other, &shell)));
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            ui.strong("Namespaces");
+            let namespaces = kubectl.get(Query::Namespaces);
+            if namespaces.is_empty() {
+                ui.weak("Cluster unreachable");
+            }
+            egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                for other in namespaces {
+                    if ui.selectable_label(other == namespace, &other).clicked() {
+                        command = Some(format!(
+                            "kubectl config set-context --current --namespace={}",
+                            shell_quote(&other, &shell)
+                        ));
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+
+        if let Some(command) = command {
+            self.current_mode = UIMode::Terminal;
+            self.command_input = command;
+            self.submit_command();
+        }
+    }
+}
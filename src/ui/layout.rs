@@ -0,0 +1,316 @@
+//! The window's size and position, the side panels' widths and whether
+//! they're open, the zoom and the active view, kept in `ui_state.json`
+//! between launches.
+//!
+//! Window geometry is saved in logical points, multiplied out of the zoom,
+//! so zooming doesn't change the size the window reopens at.
+
+use super::{AnTraftApp, UIMode};
+use anyhow::{Context, Result};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [1200.0, 800.0];
+pub const MIN_WINDOW_SIZE: [f32; 2] = [480.0, 320.0];
+pub const DEFAULT_FILE_EXPLORER_WIDTH: f32 = 240.0;
+pub const DEFAULT_AI_PANEL_WIDTH: f32 = 380.0;
+const PANEL_WIDTHS: RangeInclusive<f32> = 120.0..=1600.0;
+pub const ZOOM_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+/// How far from the monitor's corner a window that didn't fit is moved.
+const WINDOW_MARGIN: f32 = 40.0;
+
+pub fn default_ui_state_path() -> PathBuf {
+    crate::config::config_dir().join("ui_state.json")
+}
+
+/// Which view was showing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveView {
+    #[default]
+    Welcome,
+    Terminal,
+    AiAgent,
+}
+
+/// Where the window was, in logical points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub size: [f32; 2],
+    /// The outer top-left corner; `None` leaves placing it to the OS
+    #[serde(default)]
+    pub position: Option<[f32; 2]>,
+    /// `size` and `position` are then the ones to unmaximize to
+    #[serde(default)]
+    pub maximized: bool,
+    /// The size of the monitor it was on, to tell whether it still is
+    #[serde(default)]
+    pub monitor_size: Option<[f32; 2]>,
+}
+
+impl WindowGeometry {
+    /// The size and position to give a window that opened on a monitor of
+    /// `monitor` size, when the saved ones won't do: it's larger than the
+    /// monitor, or it was saved on a monitor of another size.
+    ///
+    /// egui only knows the size of the monitor the window is on, so a
+    /// different size is taken to mean the monitor it was saved on is
+    /// gone, and a saved position is moved near the corner of this one.
+    pub fn placement_fix(&self, monitor: [f32; 2]) -> Option<WindowGeometry> {
+        if self.maximized || monitor.iter().any(|side| !side.is_finite() || *side <= 0.0) {
+            return None;
+        }
+        let fits = self.size[0] <= monitor[0] && self.size[1] <= monitor[1];
+        let same_monitor = self
+            .monitor_size
+            .is_some_and(|saved| (saved[0] - monitor[0]).abs() < 1.0 && (saved[1] - monitor[1]).abs() < 1.0);
+        if fits && (same_monitor || self.position.is_none()) {
+            return None;
+        }
+
+        let fit = |side: f32, monitor: f32, min: f32| side.min(monitor - 2.0 * WINDOW_MARGIN).max(min);
+        Some(WindowGeometry {
+            size: [
+                fit(self.size[0], monitor[0], MIN_WINDOW_SIZE[0]),
+                fit(self.size[1], monitor[1], MIN_WINDOW_SIZE[1]),
+            ],
+            position: self.position.map(|_| [WINDOW_MARGIN, WINDOW_MARGIN]),
+            maximized: false,
+            monitor_size: Some(monitor),
+        })
+    }
+}
+
+/// The layout saved in `ui_state.json`. Fields missing from an older file
+/// get their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    /// `None` until the window has been seen
+    pub window: Option<WindowGeometry>,
+    pub file_explorer_open: bool,
+    pub file_explorer_width: f32,
+    pub ai_panel_width: f32,
+    pub zoom: f32,
+    pub view: ActiveView,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            window: None,
+            file_explorer_open: false,
+            file_explorer_width: DEFAULT_FILE_EXPLORER_WIDTH,
+            ai_panel_width: DEFAULT_AI_PANEL_WIDTH,
+            zoom: 1.0,
+            view: ActiveView::Welcome,
+        }
+    }
+}
+
+impl UiState {
+    /// The saved layout, or the default one when there's none yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let state: UiState =
+            serde_json::from_str(&content).with_context(|| format!("Invalid layout in {}", path.display()))?;
+        Ok(state.sanitized())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Replaces values no window could have, like a zero zoom or a
+    /// negative width, and keeps the rest in range.
+    pub fn sanitized(mut self) -> Self {
+        let defaults = UiState::default();
+        let clamp = |value: f32, range: &RangeInclusive<f32>, default: f32| match value.is_finite() {
+            true => value.clamp(*range.start(), *range.end()),
+            false => default,
+        };
+        self.zoom = clamp(self.zoom, &ZOOM_RANGE, defaults.zoom);
+        self.file_explorer_width = clamp(self.file_explorer_width, &PANEL_WIDTHS, defaults.file_explorer_width);
+        self.ai_panel_width = clamp(self.ai_panel_width, &PANEL_WIDTHS, defaults.ai_panel_width);
+        let finite = |values: &[f32; 2]| values.iter().all(|value| value.is_finite());
+        self.window = self.window.filter(|window| finite(&window.size)).map(|mut window| {
+            window.size = [window.size[0].max(MIN_WINDOW_SIZE[0]), window.size[1].max(MIN_WINDOW_SIZE[1])];
+            window.monitor_size = window.monitor_size.filter(|monitor| finite(monitor) && monitor[0] > 0.0 && monitor[1] > 0.0);
+            if let Some(monitor) = window.monitor_size {
+                window.size = [window.size[0].min(monitor[0]), window.size[1].min(monitor[1])];
+            }
+            window.position = window.position.filter(finite);
+            window
+        });
+        self
+    }
+
+    /// The window to open: the saved size and position, or the defaults.
+    pub fn viewport(&self) -> egui::ViewportBuilder {
+        let mut viewport = egui::ViewportBuilder::default().with_min_inner_size(MIN_WINDOW_SIZE);
+        match &self.window {
+            Some(window) => {
+                viewport = viewport.with_inner_size(window.size).with_maximized(window.maximized);
+                if let Some(position) = window.position {
+                    viewport = viewport.with_position(position);
+                }
+            }
+            None => viewport = viewport.with_inner_size(DEFAULT_WINDOW_SIZE),
+        }
+        viewport
+    }
+}
+
+/// What the layout still has to do to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LayoutChange {
+    Restore,
+    Reset,
+}
+
+/// The layout being tracked, and saved on quit.
+pub(super) struct Layout {
+    path: PathBuf,
+    pub(super) state: UiState,
+    pending: Option<LayoutChange>,
+    /// The saved window, until it's been checked against the monitor it
+    /// opened on, which egui may only know a few frames in
+    restored_window: Option<WindowGeometry>,
+    /// Set for the frame the panels are given their saved or default
+    /// widths; egui remembers the widths they're dragged to otherwise
+    force_widths: bool,
+}
+
+impl Layout {
+    /// The layout saved at `path`, or the default one when it can't be read.
+    pub(super) fn load(path: &Path) -> Self {
+        let state = UiState::load(path).unwrap_or_else(|e| {
+            log::warn!("Ignoring the saved layout: {:#}", e);
+            UiState::default()
+        });
+        Self {
+            path: path.to_path_buf(),
+            restored_window: state.window,
+            state,
+            pending: Some(LayoutChange::Restore),
+            force_widths: false,
+        }
+    }
+
+    /// The panels' widths and the window go back to their defaults on the
+    /// next frame.
+    pub(super) fn request_reset(&mut self) {
+        self.pending = Some(LayoutChange::Reset);
+    }
+
+    /// A side panel's width this frame: `width` as it is unless it's being
+    /// restored or reset.
+    pub(super) fn panel(&self, panel: egui::SidePanel, width: f32) -> egui::SidePanel {
+        match self.force_widths {
+            true => panel.exact_width(width),
+            false => panel.default_width(width),
+        }
+    }
+}
+
+impl AnTraftApp {
+    /// Opens the view and the panels the layout was saved with.
+    pub(super) fn restore_layout(&mut self) {
+        self.show_file_explorer = self.layout.state.file_explorer_open;
+        self.current_mode = match self.layout.state.view {
+            ActiveView::Welcome => UIMode::Welcome,
+            ActiveView::Terminal => UIMode::Terminal,
+            ActiveView::AiAgent => UIMode::AiAgent,
+        };
+    }
+
+    /// The window to open, as it was when the app last closed.
+    pub fn viewport(&self) -> egui::ViewportBuilder {
+        self.layout.state.viewport()
+    }
+
+    /// Called every frame: applies a restored or reset layout, and keeps
+    /// track of the window and the zoom for saving.
+    pub(super) fn update_layout(&mut self, ctx: &egui::Context) {
+        self.layout.force_widths = false;
+        match self.layout.pending.take() {
+            Some(LayoutChange::Restore) => {
+                ctx.set_zoom_factor(self.layout.state.zoom);
+                self.layout.force_widths = true;
+            }
+            Some(LayoutChange::Reset) => {
+                let view = self.layout.state.view;
+                let explorer_open = self.layout.state.file_explorer_open;
+                self.layout.state = UiState { view, file_explorer_open: explorer_open, ..UiState::default() };
+                ctx.set_zoom_factor(1.0);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(false));
+                let zoom = ctx.zoom_factor();
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::Vec2::from(DEFAULT_WINDOW_SIZE) / zoom));
+                self.layout.force_widths = true;
+                self.layout.restored_window = None;
+            }
+            None => {}
+        }
+
+        let zoom = ctx.zoom_factor();
+        let viewport = ctx.input(|i| i.viewport().clone());
+        let monitor = viewport.monitor_size.map(|size| [size.x * zoom, size.y * zoom]);
+        if let Some(monitor) = monitor {
+            let fix = self.layout.restored_window.take().and_then(|window| window.placement_fix(monitor));
+            if let Some(fix) = fix {
+                log::info!("The saved window doesn't fit this monitor; moving it");
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::Vec2::from(fix.size) / zoom));
+                if let Some(position) = fix.position {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::Pos2::from(position) / zoom));
+                }
+                self.layout.state.window = Some(fix);
+                return;
+            }
+        }
+
+        let state = &mut self.layout.state;
+        state.zoom = zoom;
+        state.file_explorer_open = self.show_file_explorer;
+        state.view = match self.current_mode {
+            UIMode::Welcome => ActiveView::Welcome,
+            UIMode::Terminal => ActiveView::Terminal,
+            UIMode::AiAgent => ActiveView::AiAgent,
+        };
+        // A minimized or fullscreen window isn't the one to reopen
+        if viewport.minimized == Some(true) || viewport.fullscreen == Some(true) {
+            return;
+        }
+        let maximized = viewport.maximized == Some(true);
+        let window = state.window.get_or_insert(WindowGeometry {
+            size: DEFAULT_WINDOW_SIZE,
+            position: None,
+            maximized,
+            monitor_size: None,
+        });
+        window.maximized = maximized;
+        if !maximized {
+            if let Some(inner) = viewport.inner_rect {
+                window.size = [inner.width() * zoom, inner.height() * zoom];
+            }
+            if let Some(outer) = viewport.outer_rect {
+                window.position = Some([outer.min.x * zoom, outer.min.y * zoom]);
+                window.monitor_size = monitor;
+            }
+        }
+    }
+
+    pub(super) fn save_layout(&self) {
+        if let Err(e) = self.layout.state.save(&self.layout.path) {
+            log::error!("Failed to save the layout: {:#}", e);
+        }
+    }
+}
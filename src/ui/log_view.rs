@@ -0,0 +1,240 @@
+//! The Logs view: the records `logging` keeps in memory, filtered by level,
+//! module and text, following new ones as they come. The buffer is only
+//! read, and its records formatted, while the view is open.
+
+use super::accessibility::Palette;
+use super::AnTraftApp;
+use crate::logging::{self, LogEntry};
+use eframe::egui;
+use log::{Level, LevelFilter};
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+const LEVELS: [Level; 5] = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+
+/// How often an open view looks for new records.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct LogView {
+    pub open: bool,
+    /// Levels shown, in the order of `LEVELS`; the most verbose one shown
+    /// is the level logged at
+    levels: [bool; 5],
+    module: String,
+    search: String,
+    follow: bool,
+    /// Selected records, by number
+    selected: BTreeSet<u64>,
+    entries: Vec<LogEntry>,
+    /// The buffer's `next_seq` when `entries` was read
+    seen: Option<u64>,
+    /// Indices into `entries` of the records the filters let through
+    shown: Vec<usize>,
+    export_path: String,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            levels: LEVELS.map(|level| level <= log::max_level()),
+            module: String::new(),
+            search: String::new(),
+            follow: true,
+            selected: BTreeSet::new(),
+            entries: Vec::new(),
+            seen: None,
+            shown: Vec::new(),
+            export_path: String::new(),
+        }
+    }
+}
+
+impl LogView {
+    /// Reads the buffer again if it has new records, or `force`d after the
+    /// filters change.
+    fn refresh(&mut self, force: bool) {
+        let Some(buffer) = logging::buffer() else {
+            return;
+        };
+        let next_seq = buffer.next_seq();
+        if self.seen != Some(next_seq) {
+            self.entries = buffer.entries();
+            self.seen = Some(next_seq);
+        } else if !force {
+            return;
+        }
+        let module = self.module.trim().to_lowercase();
+        let search = self.search.trim().to_lowercase();
+        let levels = self.levels;
+        self.shown = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| levels[level_index(entry.level)])
+            .filter(|(_, entry)| module.is_empty() || entry.target.to_lowercase().contains(&module))
+            .filter(|(_, entry)| search.is_empty() || entry.message.to_lowercase().contains(&search))
+            .map(|(index, _)| index)
+            .collect();
+        let kept: BTreeSet<u64> = self.entries.iter().map(|entry| entry.seq).collect();
+        self.selected.retain(|seq| kept.contains(seq));
+    }
+
+    /// Logs at the most verbose level shown, or only errors when none is.
+    fn apply_level(&self) {
+        let level = LEVELS
+            .iter()
+            .zip(self.levels)
+            .filter(|(_, shown)| *shown)
+            .map(|(level, _)| level.to_level_filter())
+            .max()
+            .unwrap_or(LevelFilter::Error);
+        logging::set_level(level);
+    }
+
+    fn shown_lines(&self, only_selected: bool) -> Vec<String> {
+        self.shown
+            .iter()
+            .map(|index| &self.entries[*index])
+            .filter(|entry| !only_selected || self.selected.contains(&entry.seq))
+            .map(LogEntry::format)
+            .collect()
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn open_log_view(&mut self) {
+        let view = &mut self.log_view;
+        view.open = true;
+        view.seen = None;
+        if view.export_path.is_empty() {
+            view.export_path = self
+                .working_directory
+                .join(format!("antraft-{}.log", chrono::Local::now().format("%Y%m%d-%H%M%S")))
+                .to_string_lossy()
+                .to_string();
+        }
+    }
+
+    pub(super) fn render_log_view(&mut self, ctx: &egui::Context) {
+        if !self.log_view.open {
+            return;
+        }
+        self.log_view.refresh(false);
+        ctx.request_repaint_after(REFRESH_INTERVAL);
+
+        let palette = Palette::for_settings(&self.config.accessibility);
+        let mut open = true;
+        let mut filters_changed = false;
+        let mut level_changed = false;
+        let mut export = false;
+        egui::Window::new("Logs")
+            .open(&mut open)
+            .default_size([760.0, 420.0])
+            .show(ctx, |ui| {
+                let view = &mut self.log_view;
+                ui.horizontal_wrapped(|ui| {
+                    for (index, level) in LEVELS.iter().enumerate() {
+                        let count = view.entries.iter().filter(|entry| entry.level == *level).count();
+                        let label = egui::RichText::new(format!("{} {}", level, count)).color(level_color(&palette, *level));
+                        if ui
+                            .selectable_label(view.levels[index], label)
+                            .on_hover_text(format!("Show {} records; the most verbose level shown is the one logged", level))
+                            .clicked()
+                        {
+                            view.levels[index] = !view.levels[index];
+                            level_changed = true;
+                        }
+                    }
+                    ui.separator();
+                    filters_changed |= ui
+                        .add(egui::TextEdit::singleline(&mut view.module).hint_text("Module").desired_width(140.0))
+                        .changed();
+                    filters_changed |= ui
+                        .add(egui::TextEdit::singleline(&mut view.search).hint_text("Search").desired_width(180.0))
+                        .changed();
+                    ui.checkbox(&mut view.follow, "Follow");
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!view.selected.is_empty(), egui::Button::new("Copy selected"))
+                        .clicked()
+                    {
+                        let lines = view.shown_lines(true);
+                        ui.output_mut(|o| o.copied_text = lines.join("\n"));
+                    }
+                    if ui.button("Clear selection").clicked() {
+                        view.selected.clear();
+                    }
+                    ui.separator();
+                    ui.add(egui::TextEdit::singleline(&mut view.export_path).desired_width(260.0));
+                    export = ui.button("Export shown").clicked();
+                    ui.weak(format!("{} of {} records", view.shown.len(), view.entries.len()));
+                });
+                ui.separator();
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(view.follow)
+                    .show_rows(ui, row_height, view.shown.len(), |ui, rows| {
+                        for row in rows {
+                            let entry = &view.entries[view.shown[row]];
+                            let selected = view.selected.contains(&entry.seq);
+                            let text = egui::RichText::new(entry.format())
+                                .monospace()
+                                .color(level_color(&palette, entry.level));
+                            if ui.selectable_label(selected, text).clicked() {
+                                match selected {
+                                    true => view.selected.remove(&entry.seq),
+                                    false => view.selected.insert(entry.seq),
+                                };
+                            }
+                        }
+                    });
+            });
+
+        if level_changed {
+            self.log_view.apply_level();
+        }
+        if level_changed || filters_changed {
+            self.log_view.refresh(true);
+        }
+        if export {
+            self.export_log();
+        }
+        if !open {
+            self.log_view.open = false;
+            self.log_view.entries = Vec::new();
+            self.log_view.shown = Vec::new();
+        }
+    }
+
+    fn export_log(&mut self) {
+        let path = std::path::PathBuf::from(self.log_view.export_path.trim());
+        let lines = self.log_view.shown_lines(false);
+        match std::fs::write(&path, lines.join("\n") + "\n") {
+            Ok(()) => self.notifications.sender().success(
+                "Exported the log",
+                format!("{} records to {}", lines.len(), path.display()),
+            ),
+            Err(e) => self
+                .notifications
+                .sender()
+                .error(format!("Couldn't write {}", path.display()), e),
+        }
+    }
+}
+
+fn level_index(level: Level) -> usize {
+    LEVELS.iter().position(|other| *other == level).unwrap_or(0)
+}
+
+fn level_color(palette: &Palette, level: Level) -> egui::Color32 {
+    match level {
+        Level::Error => palette.error,
+        Level::Warn => palette.warning,
+        Level::Info => palette.text,
+        Level::Debug | Level::Trace => palette.muted,
+    }
+}
@@ -1,62 +1,441 @@
-use crate::ai::{AiAgent, AiConfig, AiRequest, AiResponse};
-use crate::autocomplete::{AutocompleteContext, AutocompleteEngine};
+use crate::ai::queue::RequestQueue;
+use crate::ai::templates::{self, PromptTemplateStore};
+use crate::ai::{AiAgent, AiRequest, AiResponse};
+use crate::autocomplete::completion::CompletionState;
+use command_review::{CommandReview, InsertedFrom};
+use crate::autocomplete::learning::{self, ArgumentPatternProvider, LearnedCommands};
+use crate::autocomplete::highlight::highlights_as_bash;
+use crate::autocomplete::{help, AutocompleteContext, AutocompleteEngine, HelpIngestor, SyntaxHighlighter, WorkflowProvider};
+use crate::docker::DockerCache;
 use crate::file_explorer::FileExplorer;
-use crate::security::{ScanType, SecurityConfig, SecurityScanRequest, SecurityScanner};
-use crate::terminal::{TerminalEngine, TerminalEventSender};
+use crate::kubectl::{self, KubectlCache};
+use crate::security::{ScanTarget, ScanType, SecurityScanRequest, SecurityScanner};
+use crate::terminal::audit::{AuditLog, CommandOrigin};
+use crate::terminal::block::{format_duration, metadata_keys};
+use crate::terminal::favorites::{default_favorites_path, Favorites};
+use crate::workspaces::{default_workspaces_path, RecentWorkspaces};
+use crate::terminal::history::{self, HistoryEntry};
+use crate::terminal::input_history::InputHistory;
+use crate::terminal::marks::{Pins, QuickMarks};
+use crate::terminal::quick_fix::QuickFix;
+use crate::terminal::timeout as command_timeout;
+use crate::terminal::undo::UndoStack;
+use crate::terminal::output_classifier::OutputClassifiers;
+use crate::terminal::pipeline::Pipeline;
+use crate::terminal::process_usage::{self, UsageRecord};
+use crate::terminal::{
+    alias, pty, Block, CommandHistory, PanelLayout, TerminalEngine, TerminalEvent, TerminalEventReceiver,
+    TerminalEventSender,
+};
+use crate::workflows::{self as workflow_store, WorkflowStore};
 use anyhow::Result;
 use crossbeam_channel;
 use eframe::egui;
-use log::info;
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use log::{error, info};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::runtime::Handle;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Config {
-    pub ai: AiConfig,
-    pub security: SecurityConfig,
-    pub terminal: crate::terminal::TerminalConfig,
-}
+pub mod accessibility;
+mod ai_health;
+mod ai_stream;
+mod block_directory;
+mod block_info;
+mod block_navigation;
+mod block_input;
+mod command_input;
+mod command_palette;
+mod command_review;
+mod command_suggestion;
+mod commit_message;
+mod completion_popup;
+mod containers;
+mod crash_recovery;
+mod content_search;
+mod dependency_inventory;
+mod diff_view;
+mod conversation_file;
+mod explorer_panel;
+mod file_preview;
+mod git_status;
+mod kubernetes;
+mod log_view;
+pub mod layout;
+mod favorites;
+mod history_import;
+mod model_selector;
+mod notifications;
+mod output_compare;
+mod output_links;
+mod output_filter;
+mod output_folding;
+mod output_pager;
+mod pinned_blocks;
+mod pipeline;
+mod profiles;
+mod quick_fixes;
+mod project_init;
+mod prompt_templates;
+mod remote;
+mod script_preview;
+mod security_findings;
+mod session_recording;
+mod session_summary;
+mod settings;
+mod shutdown;
+mod startup;
+mod stats_view;
+mod status_bar;
+mod structured_view;
+mod table_view;
+mod undo;
+mod workflows;
+mod window_title;
+mod workspaces;
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            ai: AiConfig::default(),
-            security: SecurityConfig::default(),
-            terminal: crate::terminal::TerminalConfig::default(),
-        }
-    }
-}
+use ai_stream::{PendingAiMessage, INJECTION_ROLE, TOOL_ROLE};
+use block_directory::DirectoryAction;
+use block_info::{BlockAnnotation, BlockInfoAction};
+use block_navigation::BlockNavigation;
+use command_palette::CommandPalette;
+use command_suggestion::CommandSuggestion;
+use content_search::ContentSearch;
+use crash_recovery::CrashRecovery;
+use session_recording::SessionRecorder;
+use commit_message::CommitMessageDialog;
+use conversation_file::{ConversationFileAction, ConversationFileDialog};
+use explorer_panel::TreeLoad;
+use file_preview::FilePreview;
+use git_status::GitStatus;
+use ai_health::AiHealth;
+use model_selector::ModelCatalog;
+use notifications::NotificationCenter;
+use output_links::OutputLinks;
+use output_filter::OutputFilter;
+use output_folding::OutputFolding;
+use diff_view::DiffView;
+use output_pager::OutputPager;
+use pipeline::PipelineDialog;
+use block_input::{BlockInput, BlockInputAction};
+use stats_view::StatsView;
+use status_bar::ProcessUsageMonitor;
+use structured_view::StructuredView;
+use table_view::TableView;
+use window_title::WindowTitle;
+use layout::Layout;
+use log_view::LogView;
+
+use favorites::FavoritesPalette;
+use workspaces::Workspaces;
+use quick_fixes::BlockQuickFixes;
+use history_import::{HistoryImportEvent, HistoryImportState};
+use project_init::ProjectInit;
+use prompt_templates::{PendingTemplate, TemplateEditor};
+use remote::RemoteState;
+use output_compare::OutputCompare;
+use security_findings::SecurityFindings;
+use script_preview::ScriptPreview;
+use accessibility::Palette;
+use shutdown::Shutdown;
+use startup::Startup;
+use workflows::{WorkflowEditor, WorkflowForm};
+use session_summary::{SummaryUpdate, SUMMARY_ROLE};
+
+pub use crate::config::Config;
+pub use startup::Subsystem;
 
 pub struct AnTraftApp {
     config: Config,
     terminal_engine: Arc<TerminalEngine>,
     ai_agent: Arc<RwLock<AiAgent>>,
+    ai_queue: Arc<RequestQueue>,
+    /// Secrets redacted from AI prompts so far
+    ai_redactions: Arc<AtomicUsize>,
     file_explorer: Arc<RwLock<FileExplorer>>,
     autocomplete_engine: Arc<RwLock<AutocompleteEngine>>,
+    /// Command lines and arguments learned from finished commands, shared
+    /// with the autocomplete engine's `ArgumentPatternProvider`
+    learned_commands: Arc<std::sync::RwLock<LearnedCommands>>,
+    /// Reloads completion specs when their directory changes, while kept
+    _completion_spec_watcher: Option<notify::RecommendedWatcher>,
     security_scanner: Arc<SecurityScanner>,
+    /// `None` when `terminal.fold_output` is off
+    output_classifiers: Option<OutputClassifiers>,
     terminal_event_tx: TerminalEventSender,
+    terminal_event_rx: TerminalEventReceiver,
     pub response_sender: crossbeam_channel::Sender<AiResponse>,
     pub response_receiver: crossbeam_channel::Receiver<AiResponse>,
     // UI State
     current_mode: UIMode,
     command_input: String,
     command_history: VecDeque<String>,
+    /// Up and Down's place in `command_history`
+    input_history: InputHistory,
+    syntax_highlighter: SyntaxHighlighter,
+    /// Picked from the ⏱ menu for the next command; see `timeout`
+    next_timeout: Option<std::time::Duration>,
+    /// Deleted blocks, cleared terminals and cleared inputs, for Ctrl+Z
+    undo: UndoStack<TerminalBlock>,
     terminal_output: Vec<TerminalBlock>,
     ai_input: String,
     ai_messages: Vec<(String, String)>, // (role, message)
     runtime_handle: Handle,
+    history: Arc<RwLock<CommandHistory>>,
+    history_path: PathBuf,
+    history_import: HistoryImportState,
+    history_import_tx: crossbeam_channel::Sender<HistoryImportEvent>,
+    history_import_rx: crossbeam_channel::Receiver<HistoryImportEvent>,
+    show_settings: bool,
+    show_file_explorer: bool,
+    file_tree_loaded: bool,
+    tree_load: Option<TreeLoad>,
+    tree_load_cancelled: bool,
+    file_preview: Option<FilePreview>,
+    command_palette: CommandPalette,
+    favorites: Favorites,
+    favorites_palette: FavoritesPalette,
+    workspaces: Workspaces,
+    content_search: ContentSearch,
+    session_recorder: SessionRecorder,
+    stats_view: StatsView,
+    summary_tx: crossbeam_channel::Sender<SummaryUpdate>,
+    summary_rx: crossbeam_channel::Receiver<SummaryUpdate>,
+    /// Index in `ai_messages` of the summary being generated
+    summary_message: Option<usize>,
+    selected_blocks: HashSet<uuid::Uuid>,
+    /// A block to scroll into view on the next frame
+    scroll_to_block: Option<uuid::Uuid>,
+    /// The block jumped to last, highlighted for a moment
+    flash_block: Option<(uuid::Uuid, std::time::Instant)>,
+    pins: Pins,
+    quick_marks: QuickMarks,
+    block_navigation: Option<BlockNavigation>,
+    script_preview: Option<ScriptPreview>,
+    output_compare: Option<OutputCompare>,
+    security_findings: SecurityFindings,
+    /// Commands re-run from a block, with the block, until they start
+    pending_reruns: Vec<(String, uuid::Uuid)>,
+    prompt_templates: PromptTemplateStore,
+    template_picker_index: usize,
+    pending_template: Option<PendingTemplate>,
+    template_editor: Option<TemplateEditor>,
+    workflows: Arc<std::sync::RwLock<WorkflowStore>>,
+    /// Suggests workflows for the `!` picker; the autocomplete engine has one too
+    workflow_provider: WorkflowProvider,
+    workflow_picker_index: usize,
+    completion: CompletionState,
+    /// Findings for a command from the AI, a workflow or a paste
+    command_review: CommandReview,
+    workflow_form: Option<WorkflowForm>,
+    workflow_editor: Option<WorkflowEditor>,
+    project_init: ProjectInit,
+    remote: RemoteState,
+    /// The terminal session's local directory, which `cd` changes. The
+    /// process's own never does.
+    working_directory: PathBuf,
+    window_title: WindowTitle,
+    command_suggestion: Option<CommandSuggestion>,
+    pending_ai_message: Option<PendingAiMessage>,
+    git_status: GitStatus,
+    process_usage: ProcessUsageMonitor,
+    /// Containers and images for completions and the status bar; `None`
+    /// without the docker CLI
+    docker: Option<Arc<DockerCache>>,
+    /// Contexts, namespaces and resources; `None` without kubectl
+    kubectl: Option<Arc<KubectlCache>>,
+    /// Flags of binaries without a spec, from their `--help`
+    help_ingestor: Arc<HelpIngestor>,
+    commit_dialog: Option<CommitMessageDialog>,
+    pipeline_dialog: Option<PipelineDialog>,
+    block_annotation_tx: crossbeam_channel::Sender<BlockAnnotation>,
+    block_annotation_rx: crossbeam_channel::Receiver<BlockAnnotation>,
+    quick_fix_tx: crossbeam_channel::Sender<BlockQuickFixes>,
+    quick_fix_rx: crossbeam_channel::Receiver<BlockQuickFixes>,
+    model_catalog: ModelCatalog,
+    ai_health: AiHealth,
+    conversation_dialog: Option<ConversationFileDialog>,
+    shutdown: Shutdown,
+    crash_recovery: CrashRecovery,
+    log_view: LogView,
+    notifications: NotificationCenter,
+    startup: Startup,
+    layout: Layout,
 }
 
 #[derive(Debug, Clone)]
 pub struct TerminalBlock {
     pub id: uuid::Uuid,
     pub command: String,
+    /// The command as the user typed it, which is what history records
+    pub input: String,
+    pub working_directory: String,
     pub output: String,
     pub is_running: bool,
+    pub exit_code: Option<i32>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// See `terminal::block::metadata_keys`
+    pub metadata: HashMap<String, String>,
+    links: OutputLinks,
+    pager: OutputPager,
+    filter: OutputFilter,
+    diff: DiffView,
+    table: TableView,
+    structured: StructuredView,
+    folding: OutputFolding,
+    /// Input for a running command, when it's waiting for some
+    stdin_prompt: Option<BlockInput>,
+    /// Listed in the pinned section, and kept when the terminal is cleared
+    pinned: bool,
+    /// Corrected or install commands, when it failed for a missing program
+    quick_fixes: Vec<QuickFix>,
+    /// The block this one re-ran, while their outputs may differ
+    rerun_of: Option<uuid::Uuid>,
+    /// Set on the block of a pipeline, whose steps have blocks of their own
+    pipeline: Option<Pipeline>,
+    /// CPU and memory while it runs, sampled by `poll_process_usage`
+    usage: UsageRecord,
+}
+
+impl TerminalBlock {
+    fn from_builtin(block: Block) -> Self {
+        Self {
+            id: block.id,
+            command: block.get_metadata(metadata_keys::COMMAND).cloned().unwrap_or_default(),
+            input: block.get_metadata(metadata_keys::COMMAND).cloned().unwrap_or_default(),
+            working_directory: block.get_metadata(metadata_keys::WORKING_DIRECTORY).cloned().unwrap_or_default(),
+            output: block.content,
+            is_running: false,
+            exit_code: block.exit_code,
+            timestamp: block.timestamp,
+            metadata: block.metadata,
+            links: OutputLinks::default(),
+            pager: OutputPager::default(),
+            filter: OutputFilter::default(),
+            diff: DiffView::default(),
+            table: TableView::default(),
+            structured: StructuredView::default(),
+            folding: OutputFolding::default(),
+            stdin_prompt: None,
+            pinned: false,
+            quick_fixes: Vec::new(),
+            rerun_of: None,
+            pipeline: None,
+            usage: UsageRecord::default(),
+        }
+    }
+}
+
+/// Updates `blocks` for an event from the engine. The terminal and the
+/// session replay both build their blocks this way.
+fn apply_terminal_event(blocks: &mut Vec<TerminalBlock>, event: TerminalEvent) {
+    match event {
+        TerminalEvent::CommandStarted { id, command, input, working_directory, metadata } => {
+            blocks.push(TerminalBlock {
+                id,
+                command,
+                input,
+                working_directory,
+                output: String::new(),
+                is_running: true,
+                exit_code: None,
+                timestamp: chrono::Utc::now(),
+                metadata,
+                links: OutputLinks::default(),
+                pager: OutputPager::default(),
+                filter: OutputFilter::default(),
+                diff: DiffView::default(),
+                table: TableView::default(),
+                structured: StructuredView::default(),
+                folding: OutputFolding::default(),
+                stdin_prompt: None,
+                pinned: false,
+                quick_fixes: Vec::new(),
+                rerun_of: None,
+                pipeline: None,
+                usage: UsageRecord::default(),
+            });
+        }
+        TerminalEvent::CommandOutput { id, output, hyperlinks, .. } => {
+            if let Some(block) = blocks.iter_mut().rev().find(|b| b.id == id) {
+                // A pending carriage return is dropped before a newline
+                let offset = block.output.len() - usize::from(block.output.ends_with('\r'));
+                match pty::append_output(&mut block.output, &output) {
+                    // A progress line was redrawn; links in it would point at
+                    // replaced text
+                    Some(rewritten) => {
+                        block.links.rewind(rewritten);
+                        block.pager.rewind(rewritten);
+                        block.folding.rewind(rewritten);
+                    }
+                    None => block.links.add_hyperlinks(offset, hyperlinks),
+                }
+            }
+        }
+        TerminalEvent::InputRequested { id, prompt, secret } => {
+            if let Some(block) = blocks.iter_mut().rev().find(|b| b.id == id) {
+                // Keep a half-typed answer
+                if block.stdin_prompt.as_ref().is_none_or(|input| !input.has_answer()) {
+                    block.stdin_prompt = Some(BlockInput::new(prompt, secret));
+                }
+            }
+        }
+        TerminalEvent::CommandFinished { id, exit_code } => {
+            if let Some(block) = blocks.iter_mut().rev().find(|b| b.id == id) {
+                block.is_running = false;
+                block.stdin_prompt = None;
+                block.exit_code = Some(exit_code);
+                let duration = block_duration_ms(block);
+                block.metadata.insert(metadata_keys::EXIT_CODE.to_string(), exit_code.to_string());
+                block.metadata.insert(metadata_keys::DURATION.to_string(), format_duration(duration));
+                block.usage.current = None;
+                if !block.usage.is_empty() {
+                    let usage = &block.usage;
+                    block.metadata.insert(metadata_keys::PEAK_CPU.to_string(), format!("{:.0}%", usage.peak_cpu_percent));
+                    block
+                        .metadata
+                        .insert(metadata_keys::PEAK_MEMORY.to_string(), process_usage::format_memory(usage.peak_memory_bytes));
+                    block
+                        .metadata
+                        .insert(metadata_keys::CPU_TIME.to_string(), format_duration((usage.cpu_seconds * 1000.0) as u64));
+                }
+            }
+        }
+        TerminalEvent::CommandTimedOut { id, timeout } => {
+            if let Some(block) = blocks.iter_mut().rev().find(|b| b.id == id) {
+                block
+                    .metadata
+                    .insert(metadata_keys::TIMED_OUT.to_string(), command_timeout::format_timeout(timeout));
+            }
+        }
+        TerminalEvent::NewBlock { block } => blocks.push(TerminalBlock::from_builtin(block)),
+        TerminalEvent::PipelineUpdated { pipeline } => {
+            let index = match blocks.iter().rposition(|b| b.id == pipeline.id) {
+                Some(index) => index,
+                None => {
+                    let mut block = Block::system(String::new());
+                    block.id = pipeline.id;
+                    blocks.push(TerminalBlock::from_builtin(block));
+                    blocks.len() - 1
+                }
+            };
+            let block = &mut blocks[index];
+            block.is_running = !pipeline.is_finished();
+            block.exit_code = pipeline.exit_code();
+            block.pipeline = Some(pipeline);
+        }
+        TerminalEvent::Error { .. }
+        | TerminalEvent::AliasesChanged { .. }
+        | TerminalEvent::RemoteChanged { .. }
+        | TerminalEvent::DirectoryChanged { .. }
+        | TerminalEvent::TitleChanged { .. } => {}
+    }
+}
+
+fn block_duration_ms(block: &TerminalBlock) -> u64 {
+    (chrono::Utc::now() - block.timestamp).num_milliseconds().max(0) as u64
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -68,49 +447,203 @@ enum UIMode {
 
 
 impl AnTraftApp {
+    /// Makes the app with placeholders for everything slow to load, which
+    /// `start_background_init` then loads off the UI thread, so the first
+    /// frame doesn't wait on the disk. Doesn't run any external binaries.
     pub async fn new(config: Config) -> Result<Self> {
-        let (terminal_event_tx, _terminal_event_rx) = tokio::sync::mpsc::unbounded_channel();
+        let started = std::time::Instant::now();
+        let (terminal_event_tx, terminal_event_rx) = tokio::sync::mpsc::unbounded_channel();
+        let notifications = NotificationCenter::default();
+        let notifier = notifications.sender();
 
-        let terminal_engine =
-            TerminalEngine::new(config.terminal.clone(), terminal_event_tx.clone())?;
-        let ai_agent = Arc::new(RwLock::new(AiAgent::new(config.ai.clone())));
-        let file_explorer = Arc::new(RwLock::new(FileExplorer::new(std::env::current_dir()?)?));
-        let autocomplete_engine = Arc::new(RwLock::new(AutocompleteEngine::new()));
-        let security_scanner = Arc::new(SecurityScanner::new(config.security.clone())?);
+        let audit_log = if config.audit.enabled {
+            match AuditLog::open(config.audit.log_path()) {
+                Ok(audit_log) => Some(Arc::new(audit_log.with_secret_redaction(config.privacy.redact_audit_log))),
+                Err(e) => {
+                    error!("Failed to open audit log: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let terminal_engine = TerminalEngine::new(config.terminal.clone(), terminal_event_tx.clone())?
+            .with_audit_log(audit_log);
+        let ai_agent = AiAgent::new(config.ai.clone()).with_shell(config.terminal.shell.clone());
+        let ai_queue = ai_agent.queue();
+        let ai_redactions = ai_agent.redactions();
+        let ai_agent = Arc::new(RwLock::new(ai_agent));
+        let file_explorer = Arc::new(RwLock::new(FileExplorer::unloaded(std::env::current_dir()?)));
+        let workflows_dir = workflow_store::default_workflows_dir();
+        let workflows = WorkflowStore::load(&workflows_dir).unwrap_or_else(|e| {
+            error!("Failed to load workflows: {}", e);
+            WorkflowStore::builtin(&workflows_dir)
+        });
+        let workflows = Arc::new(std::sync::RwLock::new(workflows));
+        let mut autocomplete_engine = AutocompleteEngine::new();
+        autocomplete_engine.add_provider(Box::new(WorkflowProvider::new(workflows.clone())));
+        let learned_path = learning::default_learned_path();
+        let learned_commands = LearnedCommands::load(&learned_path).unwrap_or_else(|e| {
+            notifier.warning("Couldn't load the commands autocomplete learned", format!("{:#}", e));
+            LearnedCommands::empty(&learned_path)
+        });
+        let learned_commands = Arc::new(std::sync::RwLock::new(learned_commands));
+        autocomplete_engine.add_provider(Box::new(ArgumentPatternProvider::new(learned_commands.clone())));
+        let help_ingestor = HelpIngestor::new(Handle::current(), help::default_help_cache_dir());
+        help_ingestor.set_enabled(config.terminal.help_completions);
+        autocomplete_engine.set_help_ingestor(help_ingestor.clone());
+        let autocomplete_engine = Arc::new(RwLock::new(autocomplete_engine));
+        // Until the installed scanners are known
+        let security_scanner = Arc::new(SecurityScanner::with_scanners(config.security.clone(), Vec::new()));
+        let output_classifiers = config.terminal.fold_output.then(|| {
+            OutputClassifiers::load(&config.terminal.output_rules_path()).unwrap_or_else(|e| {
+                notifier.warning("Couldn't load your output rules", format!("{:#}", e));
+                OutputClassifiers::builtin()
+            })
+        });
 
         let (response_sender, response_receiver) = crossbeam_channel::unbounded();
 
         let runtime_handle = Handle::current();
 
-        let app = AnTraftApp {
+        let history_path = history::default_history_path();
+        let command_history = CommandHistory::new(config.terminal.max_history);
+        let undo = UndoStack::new(config.terminal.undo_limit);
+        let favorites_path = default_favorites_path();
+        let favorites = Favorites::load(&favorites_path).unwrap_or_else(|e| {
+            notifier.warning("Couldn't load your favorite commands", format!("{:#}", e));
+            Favorites::empty(&favorites_path)
+        });
+        let workspaces_path = default_workspaces_path();
+        let max_workspaces = config.workspaces.max_recent;
+        let recent_workspaces = RecentWorkspaces::load(&workspaces_path, max_workspaces, &config.workspaces.recent)
+            .unwrap_or_else(|e| {
+                notifier.warning("Couldn't load your recent workspaces", format!("{:#}", e));
+                RecentWorkspaces::new(&workspaces_path, max_workspaces)
+            });
+        let (history_import_tx, history_import_rx) = crossbeam_channel::unbounded();
+        let (summary_tx, summary_rx) = crossbeam_channel::unbounded();
+        let (block_annotation_tx, block_annotation_rx) = crossbeam_channel::unbounded();
+        let (quick_fix_tx, quick_fix_rx) = crossbeam_channel::unbounded();
+
+        let templates_dir = templates::default_templates_dir();
+        let prompt_templates = PromptTemplateStore::load(&templates_dir).unwrap_or_else(|e| {
+            error!("Failed to load prompt templates: {}", e);
+            PromptTemplateStore::builtin(&templates_dir)
+        });
+
+        let mut app = AnTraftApp {
             config,
             terminal_engine: Arc::new(terminal_engine),
             ai_agent,
+            ai_queue,
+            ai_redactions,
             file_explorer,
             autocomplete_engine,
+            learned_commands,
+            _completion_spec_watcher: None,
             security_scanner,
+            output_classifiers,
             terminal_event_tx,
+            terminal_event_rx,
             response_sender,
             response_receiver,
             // Initialize UI state
             current_mode: UIMode::Welcome,
             command_input: String::new(),
             command_history: VecDeque::new(),
+            input_history: InputHistory::default(),
+            syntax_highlighter: SyntaxHighlighter::new(),
+            next_timeout: None,
+            undo,
             terminal_output: Vec::new(),
             ai_input: String::new(),
             ai_messages: Vec::new(),
             runtime_handle,
+            history: Arc::new(RwLock::new(command_history)),
+            history_path,
+            history_import: HistoryImportState::default(),
+            history_import_tx,
+            history_import_rx,
+            show_settings: false,
+            show_file_explorer: false,
+            file_tree_loaded: false,
+            tree_load: None,
+            tree_load_cancelled: false,
+            file_preview: None,
+            command_palette: CommandPalette::default(),
+            favorites,
+            favorites_palette: FavoritesPalette::default(),
+            workspaces: Workspaces::new(recent_workspaces),
+            content_search: ContentSearch::default(),
+            session_recorder: SessionRecorder::default(),
+            stats_view: StatsView::default(),
+            summary_tx,
+            summary_rx,
+            summary_message: None,
+            selected_blocks: HashSet::new(),
+            scroll_to_block: None,
+            flash_block: None,
+            pins: Pins::default(),
+            quick_marks: QuickMarks::default(),
+            block_navigation: None,
+            script_preview: None,
+            output_compare: None,
+            security_findings: SecurityFindings::default(),
+            pending_reruns: Vec::new(),
+            prompt_templates,
+            template_picker_index: 0,
+            pending_template: None,
+            template_editor: None,
+            workflow_provider: WorkflowProvider::new(workflows.clone()),
+            workflows,
+            workflow_picker_index: 0,
+            completion: CompletionState::default(),
+            command_review: CommandReview::default(),
+            workflow_form: None,
+            workflow_editor: None,
+            project_init: ProjectInit::load(),
+            remote: RemoteState::default(),
+            working_directory: std::env::current_dir().unwrap_or_default(),
+            window_title: WindowTitle::default(),
+            command_suggestion: None,
+            pending_ai_message: None,
+            git_status: GitStatus::default(),
+            process_usage: ProcessUsageMonitor::default(),
+            docker: None,
+            kubectl: None,
+            help_ingestor,
+            commit_dialog: None,
+            pipeline_dialog: None,
+            block_annotation_tx,
+            block_annotation_rx,
+            quick_fix_tx,
+            quick_fix_rx,
+            model_catalog: ModelCatalog::default(),
+            ai_health: AiHealth::default(),
+            conversation_dialog: None,
+            shutdown: Shutdown::default(),
+            crash_recovery: CrashRecovery::load(),
+            log_view: LogView::default(),
+            notifications,
+            startup: Startup::new(started),
+            layout: Layout::load(&layout::default_ui_state_path()),
         };
+        app.restore_layout();
+        app.import_legacy_workspaces();
+        app.register_crash_flushes();
+        app.start_background_init();
 
         Ok(app)
     }
 
     pub async fn run_security_scan(&self, path: String, scan_type: ScanType) -> Result<()> {
         let request = SecurityScanRequest {
-            path: path.into(),
+            target: ScanTarget::Path(path.into()),
             scan_type,
             include_patterns: vec![],
             exclude_patterns: vec![],
+            honor_ignore_comments: true,
         };
 
         let report = self.security_scanner.scan(request).await?;
@@ -159,41 +692,95 @@ impl AnTraftApp {
 
     // UI helpers (not trait methods)
     pub fn render_ai_panel(&mut self, ui: &mut egui::Ui) {
-        ui.heading("🤖 AI Assistant");
+        let palette = Palette::for_settings(&self.config.accessibility);
+        ui.horizontal(|ui| {
+            ui.heading("🤖 AI Assistant");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("📂 Import…").on_hover_text("Import a conversation from a JSON file").clicked() {
+                    self.open_conversation_dialog(ConversationFileAction::Import);
+                }
+                if ui.small_button("💾 Export…").on_hover_text("Export this conversation as JSON or markdown").clicked() {
+                    self.open_conversation_dialog(ConversationFileAction::Export);
+                }
+            });
+        });
         ui.separator();
         
         // Chat history
+        let mut stop_stream = false;
         egui::ScrollArea::vertical()
             .stick_to_bottom(true)
             .show(ui, |ui| {
-                for (role, message) in &self.ai_messages {
+                for (index, (role, message)) in self.ai_messages.iter().enumerate() {
+                    if role == TOOL_ROLE {
+                        ai_stream::render_tool_step(ui, index, message);
+                        continue;
+                    }
+                    if role == INJECTION_ROLE {
+                        ai_stream::render_injection_warning(ui, palette.warning, message);
+                        continue;
+                    }
                     ui.group(|ui| {
-                        let color = if role == "You" {
-                            egui::Color32::from_rgb(100, 150, 255)
-                        } else {
-                            egui::Color32::from_rgb(100, 255, 150)
-                        };
+                        let color = if role == "You" { palette.user } else { palette.assistant };
                         ui.colored_label(color, format!("{}: ", role));
-                        ui.label(message);
+                        if !message.is_empty() {
+                            ui.label(message);
+                        }
+                        if let Some(pending) = self.pending_ai_message.as_ref().filter(|p| p.index == index) {
+                            let requests_ahead = self.ai_queue.requests_ahead(pending.ticket);
+                            stop_stream |= ai_stream::render_stream_indicator(ui, pending, requests_ahead);
+                        }
+                        if role == SUMMARY_ROLE && ui.small_button("📋 Copy as Markdown").clicked() {
+                            ui.output_mut(|o| o.copied_text = message.clone());
+                        }
                     });
                     ui.add_space(5.0);
                 }
             });
+        if stop_stream {
+            self.stop_ai_stream();
+        }
         
         ui.separator();
         
+        self.render_pending_template(ui);
+
+        // Typing `/` opens the prompt template picker
+        let template_matches = self.template_matches();
+        if let Some(matches) = &template_matches {
+            let (up, down) = ui.input(|i| (i.key_pressed(egui::Key::ArrowUp), i.key_pressed(egui::Key::ArrowDown)));
+            if up {
+                self.template_picker_index = self.template_picker_index.saturating_sub(1);
+            }
+            if down {
+                self.template_picker_index += 1;
+            }
+            self.template_picker_index = self.template_picker_index.min(matches.len().saturating_sub(1));
+            self.render_template_picker(ui, matches);
+        }
+
         // Input area
         ui.horizontal(|ui| {
-            let response = ui.text_edit_singleline(&mut self.ai_input);
-            
-            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                if !self.ai_input.is_empty() {
-                    self.send_ai_message();
-                }
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.ai_input)
+                    .hint_text("Ask anything, or type / for prompt templates"),
+            );
+            // Side by side with the terminal, typing here keeps the focus
+            if response.has_focus() {
+                self.current_mode = UIMode::AiAgent;
             }
-            
-            if ui.button("Send").clicked() && !self.ai_input.is_empty() {
-                self.send_ai_message();
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if submitted || ui.button("Send").clicked() {
+                match &template_matches {
+                    Some(matches) => {
+                        if let Some(template) = matches.get(self.template_picker_index).cloned() {
+                            self.apply_template(template);
+                        }
+                    }
+                    None if !self.ai_input.is_empty() => self.send_ai_message(),
+                    None => {}
+                }
             }
         });
         
@@ -202,202 +789,636 @@ impl AnTraftApp {
     }
 
     pub fn render_terminal(&mut self, ui: &mut egui::Ui) {
+        let palette = Palette::for_settings(&self.config.accessibility);
+        let answering = self
+            .terminal_output
+            .iter()
+            .any(|block| block.is_running && block.stdin_prompt.is_some());
+        let working_directory = self.working_directory.to_string_lossy().to_string();
+        let highlight = highlights_as_bash(&self.config.terminal.shell);
         // Warp-like terminal interface
+        for block in &mut self.terminal_output {
+            // Builtins' blocks have no directory; their paths are the session's
+            let directory = match block.working_directory.is_empty() {
+                true => working_directory.as_str(),
+                false => block.working_directory.as_str(),
+            };
+            block.links.update(&block.output, directory, !block.is_running);
+            block.pager.update(&block.output);
+            block.diff.update(&block.command, &block.output, block.is_running);
+            block.table.update(&block.output, block.is_running);
+            block.structured.update(&block.output, block.is_running, &self.runtime_handle);
+            block
+                .folding
+                .update(self.output_classifiers.as_ref(), &block.command, &block.output, !block.is_running);
+        }
+
+        let flash = self.flash(ui.ctx());
+        let navigating = self.block_navigation.as_ref().map(|navigation| navigation.selected);
+        if self.terminal_output.iter().any(|block| block.structured.is_parsing()) {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
         ui.vertical(|ui| {
+            self.render_remote_banner(ui);
+            self.render_block_toolbar(ui);
             // Terminal output area (scrollable)
             egui::ScrollArea::vertical()
                 .stick_to_bottom(true)
                 .show(ui, |ui| {
                     // Show command history and outputs
-                    for block in &self.terminal_output {
-                        ui.group(|ui| {
+                    let mut explain = None;
+                    let mut save_workflow = None;
+                    let mut link_action = None;
+                    let pager_threshold = self.config.terminal.pager_threshold_lines;
+                    let detect_prompts = self.config.terminal.detect_prompts;
+                    let mut input_action = None;
+                    let mut pin_toggle = None;
+                    let mut delete = None;
+                    let mut favorite_toggle = None;
+                    let mut quick_fix_action = None;
+                    let mut directory_action = None;
+                    let mut compare = None;
+                    let mut jump_to_step = None;
+                    let current_directory = self.current_directory();
+                    let home = dirs::home_dir();
+                    for block in &mut self.terminal_output {
+                        let group = ui.group(|ui| {
                             ui.horizontal(|ui| {
-                                ui.colored_label(egui::Color32::from_rgb(100, 200, 100), ">");
-                                ui.label(&block.command);
+                                if !block.is_running && !block.command.is_empty() {
+                                    let mut selected = self.selected_blocks.contains(&block.id);
+                                    if ui.checkbox(&mut selected, "").changed() {
+                                        if selected {
+                                            self.selected_blocks.insert(block.id);
+                                        } else {
+                                            self.selected_blocks.remove(&block.id);
+                                        }
+                                    }
+                                }
+                                match &block.pipeline {
+                                    Some(pipeline) => pipeline::render_header(ui, pipeline),
+                                    None => {
+                                        ui.colored_label(palette.prompt, ">");
+                                        command_input::render_command_text(
+                                            ui,
+                                            &block.command,
+                                            highlight.then_some(&mut self.syntax_highlighter),
+                                            &palette.syntax,
+                                        );
+                                    }
+                                }
+                                for letter in self.quick_marks.letters_of(block.id) {
+                                    ui.weak(format!("'{}", letter)).on_hover_text(format!("Marked; ' then {} jumps here", letter));
+                                }
+                                if block.metadata.get(metadata_keys::ORIGIN).is_some_and(|origin| origin == "startup") {
+                                    ui.weak("startup").on_hover_text("Run when the session started or entered a project");
+                                }
+                                if block.is_running {
+                                    accessibility::spinner(ui);
+                                    match &block.stdin_prompt {
+                                        Some(input) => {
+                                            ui.colored_label(palette.warning, input.status());
+                                        }
+                                        None if detect_prompts
+                                            && accessibility::icon_button(ui, "⌨", "Send input").clicked() =>
+                                        {
+                                            input_action = Some(BlockInputAction::Open(block.id));
+                                        }
+                                        None => {}
+                                    }
+                                } else if let Some(timeout) = block.metadata.get(metadata_keys::TIMED_OUT) {
+                                    ui.colored_label(palette.warning, format!("timed out after {}", timeout));
+                                } else if let Some(code) = block.exit_code.filter(|c| *c != 0) {
+                                    ui.colored_label(palette.error, format!("exit {}", code));
+                                }
                                 if block.is_running {
-                                    ui.spinner();
+                                    command_input::render_countdown(ui, block);
+                                }
+                                status_bar::render_block_usage(ui, block);
+                                block_directory::render_directory(ui, block, &current_directory, home.as_deref());
+                                output_folding::render_header(ui, &mut block.folding);
+                                if !block.is_running && !block.input.is_empty() {
+                                    if let Some(action) =
+                                        block_directory::render_rerun(ui, block, &current_directory, home.as_deref())
+                                    {
+                                        directory_action = Some(action);
+                                    }
+                                    if block.rerun_of.is_some() && output_compare::render_rerun_diff(ui) {
+                                        compare = Some((block.id, block.rerun_of));
+                                    }
+                                }
+                                if !block.input.is_empty()
+                                    && favorites::render_star(ui, self.favorites.contains(&block.input))
+                                {
+                                    favorite_toggle = Some(block.input.clone());
+                                }
+                                if !block.command.is_empty()
+                                    && ui
+                                        .selectable_label(block.pinned, "📌")
+                                        .on_hover_text(if block.pinned { "Unpin" } else { "Pin to the top" })
+                                        .clicked()
+                                {
+                                    pin_toggle = Some((block.id, !block.pinned));
+                                }
+                                if undo::render_delete(ui, block) {
+                                    delete = Some(block.id);
+                                }
+                                if !block.output.is_empty()
+                                    && ui
+                                        .selectable_label(block.filter.open, "Filter")
+                                        .on_hover_text("Show only the lines matching a filter")
+                                        .clicked()
+                                {
+                                    block.filter.toggle();
+                                }
+                                if block.diff.is_available() {
+                                    ui.separator();
+                                    diff_view::render_mode_toggle(ui, &mut block.diff);
+                                } else if block.structured.is_available() {
+                                    ui.separator();
+                                    structured_view::render_mode_toggle(ui, &mut block.structured);
+                                } else if block.table.is_available() {
+                                    table_view::render_toggle(ui, &mut block.table);
+                                }
+                                if !block.metadata.is_empty() {
+                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                        match block_info::render_block_info(ui, block, &current_directory) {
+                                            Some(BlockInfoAction::Explain) => explain = Some(block.id),
+                                            Some(BlockInfoAction::SaveAsWorkflow) => save_workflow = Some(block.input.clone()),
+                                            Some(BlockInfoAction::ChangeDirectory) => {
+                                                directory_action =
+                                                    Some(DirectoryAction::ChangeDirectory(block.working_directory.clone()));
+                                            }
+                                            Some(BlockInfoAction::Compare) => compare = Some((block.id, None)),
+                                            None => {}
+                                        }
+                                    });
                                 }
                             });
+                            if let Some(pipeline) = &block.pipeline {
+                                ui.separator();
+                                if let Some(step_block) = pipeline::render_steps(ui, pipeline) {
+                                    jump_to_step = Some(step_block);
+                                }
+                            }
                             if !block.output.is_empty() {
                                 ui.separator();
-                                ui.label(&block.output);
+                                if block.filter.open {
+                                    output_filter::render_filter_bar(ui, block.id, &block.output, &block.pager, &mut block.filter);
+                                }
+                                if block.filter.is_active() {
+                                    output_filter::render_filtered_output(
+                                        ui,
+                                        block.id,
+                                        &block.output,
+                                        &block.pager,
+                                        &block.filter,
+                                        block.is_running,
+                                    );
+                                } else if block.diff.is_active() {
+                                    diff_view::render_diff(ui, block.id, &block.diff);
+                                } else if block.structured.is_active() {
+                                    structured_view::render_structured(ui, block.id, &mut block.structured);
+                                } else if block.table.is_active() && !block.structured.is_available() {
+                                    table_view::render_table(ui, block.id, &mut block.table);
+                                } else if block.folding.is_active() {
+                                    if let Some(action) = output_folding::render_folded_output(ui, block, pager_threshold) {
+                                        link_action = Some(action);
+                                    }
+                                } else if let Some(action) = output_pager::render_block_output(
+                                    ui,
+                                    block.id,
+                                    &block.output,
+                                    &block.links,
+                                    &mut block.pager,
+                                    pager_threshold,
+                                    block.is_running,
+                                ) {
+                                    link_action = Some(action);
+                                }
+                            }
+                            if let (true, Some(input)) = (block.is_running, &mut block.stdin_prompt) {
+                                if let Some(action) = block_input::render_block_input(ui, block.id, input) {
+                                    input_action = Some(action);
+                                }
+                            }
+                            if !block.is_running && !block.quick_fixes.is_empty() {
+                                if let Some(action) = quick_fixes::render_quick_fixes(ui, block) {
+                                    quick_fix_action = Some(action);
+                                }
                             }
                         });
+                        let rect = group.response.rect;
+                        if block.pinned {
+                            let stripe = egui::Rect::from_min_max(rect.left_top(), egui::pos2(rect.left() + 3.0, rect.bottom()));
+                            ui.painter().rect_filled(stripe, 2.0, pinned_blocks::PIN_COLOR);
+                        }
+                        if let Some((_, strength)) = flash.filter(|(id, _)| *id == block.id) {
+                            let [r, g, b, _] = palette.flash.to_array();
+                            let color = egui::Color32::from_rgba_unmultiplied(r, g, b, (strength * 60.0) as u8);
+                            ui.painter().rect_filled(rect, 4.0, color);
+                        }
+                        if navigating == Some(block.id) {
+                            ui.painter().rect_stroke(rect, 4.0, egui::Stroke::new(2.0, palette.accent));
+                        }
+                        if self.scroll_to_block == Some(block.id) {
+                            group.response.scroll_to_me(Some(egui::Align::TOP));
+                            self.scroll_to_block = None;
+                        }
                         ui.add_space(5.0);
                     }
+                    if let Some((block_id, pinned)) = pin_toggle {
+                        self.set_block_pinned(block_id, pinned);
+                    }
+                    if let Some(block_id) = delete {
+                        self.delete_block(block_id);
+                    }
+                    if let Some(command) = favorite_toggle {
+                        self.toggle_favorite(&command);
+                    }
+                    if let Some(block_id) = explain {
+                        self.explain_block(block_id);
+                    }
+                    if let Some(command) = save_workflow {
+                        self.save_as_workflow(command);
+                    }
+                    if let Some(action) = link_action {
+                        self.handle_link_action(action);
+                    }
+                    if let Some(action) = input_action {
+                        self.handle_block_input_action(action);
+                    }
+                    if let Some(action) = quick_fix_action {
+                        self.handle_quick_fix_action(action);
+                    }
+                    if let Some(action) = directory_action {
+                        self.handle_directory_action(action);
+                    }
+                    if let Some((block_id, other)) = compare {
+                        self.open_output_compare(block_id, other);
+                    }
+                    if let Some(block_id) = jump_to_step {
+                        self.scroll_to_block = Some(block_id);
+                    }
                 });
 
             ui.separator();
+
+            if !self.selected_blocks.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} selected", self.selected_blocks.len()));
+                    if ui.button("📜 Generate script from selection").clicked() {
+                        self.generate_script_from_selection();
+                    }
+                    if ui.small_button("Clear").clicked() {
+                        self.selected_blocks.clear();
+                    }
+                });
+                ui.separator();
+            }
             
+            self.render_workflow_form(ui);
+
+            // Typing `!` opens the workflow picker
+            let workflow_matches = self.workflow_matches();
+            if let Some(matches) = &workflow_matches {
+                let (up, down) = ui.input(|i| (i.key_pressed(egui::Key::ArrowUp), i.key_pressed(egui::Key::ArrowDown)));
+                if up {
+                    self.workflow_picker_index = self.workflow_picker_index.saturating_sub(1);
+                }
+                if down {
+                    self.workflow_picker_index += 1;
+                }
+                self.workflow_picker_index = self.workflow_picker_index.min(matches.len().saturating_sub(1));
+                self.render_workflow_picker(ui, matches);
+            }
+
+            self.refresh_completions();
+            let completion_moved = self.handle_completion_keys(ui);
+            if workflow_matches.is_none() {
+                self.handle_history_keys(ui);
+                self.handle_clear_input_key(ui);
+                self.render_completion_popup(ui, completion_moved);
+            }
+            self.review_command_input();
+            self.render_command_review(ui);
+
             // Command input area at bottom (like Warp)
-            ui.horizontal(|ui| {
-                ui.colored_label(egui::Color32::from_rgb(100, 200, 100), "❯");
-                let response = ui.text_edit_singleline(&mut self.command_input);
-                
-                // Auto-focus the input field
-                response.request_focus();
+            ui.horizontal_top(|ui| {
+                ui.colored_label(palette.prompt, "❯");
+                let pasted = Self::multiline_paste(ui, Self::command_input_id());
+                let (response, submitted) = self.render_command_input(ui);
+                if response.changed() {
+                    ui.ctx().request_repaint();
+                }
+                if pasted {
+                    self.mark_inserted(InsertedFrom::Paste);
+                }
                 
-                // Handle Enter key to execute command
-                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                    if !self.command_input.is_empty() {
-                        self.execute_command_sync();
-                    }
+                // Auto-focus the input field, unless a command is waiting for
+                // input, a workflow is being filled in or, side by side, the
+                // chat is being typed in
+                if response.has_focus() {
+                    self.current_mode = UIMode::Terminal;
+                } else if !answering
+                    && self.workflow_form.is_none()
+                    && self.block_navigation.is_none()
+                    && self.current_mode == UIMode::Terminal
+                {
+                    response.request_focus();
                 }
                 
-                if ui.button("⚡ Run").clicked() && !self.command_input.is_empty() {
-                    self.execute_command_sync();
+                self.render_timeout_menu(ui);
+                // Enter runs the command, or picks the workflow
+                if submitted || ui.button("⚡ Run").clicked() {
+                    match &workflow_matches {
+                        Some(matches) => {
+                            if let Some(item) = matches.get(self.workflow_picker_index) {
+                                let name = item.text.trim_start_matches('!').to_string();
+                                self.open_workflow(&name);
+                            }
+                        }
+                        None if !self.command_input.is_empty() => self.submit_command(),
+                        None => {}
+                    }
                 }
             });
         });
     }
 
-    fn execute_command_sync(&mut self) {
+    fn submit_command(&mut self) {
+        self.submit_command_from(CommandOrigin::User);
+    }
+
+    fn submit_command_from(&mut self, origin: CommandOrigin) {
         let command = self.command_input.trim().to_string();
-        if command.is_empty() {
+        if command.is_empty() || self.hold_for_review() {
             return;
         }
+        self.command_review = CommandReview::default();
+        self.command_input.clear();
+        self.input_history.reset();
+        let command = self.apply_next_timeout(command);
+        self.run_command(command, origin, None);
+    }
 
+    /// Runs `command` as if it was typed, in `directory` when given (see
+    /// `TerminalEngine::execute_command_in`) or the current directory.
+    fn run_command(&mut self, command: String, origin: CommandOrigin, directory: Option<String>) {
         // Add command to history
         self.command_history.push_front(command.clone());
-        
-        // Create terminal block
-        let block_id = uuid::Uuid::new_v4();
-        let mut block = TerminalBlock {
-            id: block_id,
-            command: command.clone(),
-            output: String::new(),
-            is_running: true,
-            timestamp: chrono::Utc::now(),
-        };
-        
-        // Execute command and capture output
-        let output = if cfg!(target_os = "windows") {
-            std::process::Command::new("cmd")
-                .args(["/C", &command])
-                .output()
-        } else {
-            std::process::Command::new("sh")
-                .arg("-c")
-                .arg(&command)
-                .output()
-        };
 
-        match output {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                let combined_output = if !stderr.is_empty() {
-                    format!("{}\n{}", stdout, stderr)
-                } else {
-                    stdout.to_string()
-                };
-                
-                block.output = combined_output;
-                block.is_running = false;
-            }
-            Err(e) => {
-                block.output = format!("Error executing command: {}", e);
-                block.is_running = false;
-            }
+        let expanded = alias::expand_aliases(&command, &self.config.terminal.aliases);
+        if expanded.is_ok_and(|expanded| expanded.trim() == "clear") {
+            self.clear_terminal();
         }
-        
-        self.terminal_output.push(block);
-        self.command_input.clear();
-    }
 
-    pub fn render_file_explorer(&mut self, ui: &mut egui::Ui) {
-        ui.heading("File Explorer");
-        // Add your file explorer UI code here
-    }
+        let working_directory = directory.clone().unwrap_or_else(|| self.current_directory());
+        self.record_history(command.clone(), working_directory.clone());
+
+        // Builtins come back as a `NewBlock` event; everything else is run by the
+        // engine, which reports the (alias-expanded) command via `CommandStarted`.
+        // Builtins act on the current directory, so elsewhere the shell runs them
+        let shell = self.config.terminal.shell.clone();
+        let engine = self.terminal_engine.clone();
+        let event_sender = self.terminal_event_tx.clone();
+        self.runtime_handle.spawn(async move {
+            let builtin = match directory {
+                Some(_) => None,
+                None => engine.handle_builtin_command(&command).await,
+            };
+            let block = match builtin {
+                Some(Ok(block)) => Some(block),
+                Some(Err(e)) => Some(Block::error(e.to_string())),
+                None => {
+                    let started = match directory {
+                        Some(directory) => engine.execute_command_in(command.clone(), directory, origin).await,
+                        None => engine.execute_command_from(command.clone(), origin).await,
+                    };
+                    started.err().map(|e| Block::error(e.to_string()))
+                }
+            };
 
-    pub fn render_security_panel(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Security Panel");
-        // Add your security panel UI code here
+            if let Some(mut block) = block {
+                block.set_metadata(metadata_keys::COMMAND.to_string(), command);
+                block.set_environment_metadata(&shell, &working_directory);
+                let _ = event_sender.send(TerminalEvent::NewBlock { block });
+            }
+        });
     }
 
-    pub fn send_ai_message(&mut self) {
-        if self.ai_input.is_empty() {
-            return;
+    /// The directory commands run in now: the process's, or `host:dir`
+    /// in a remote session, the way blocks record it.
+    fn current_directory(&self) -> String {
+        match &self.remote.info {
+            Some(remote) => format!("{}:{}", remote.label(), remote.directory),
+            None => self.working_directory.to_string_lossy().to_string(),
         }
+    }
 
-        let message = self.ai_input.clone();
-        self.ai_messages.push(("You".to_string(), message.clone()));
-        self.ai_input.clear();
+    fn record_history(&self, command: String, working_directory: String) {
+        let history = self.history.clone();
+        let history_path = self.history_path.clone();
+        let autocomplete_engine = self.autocomplete_engine.clone();
+        let notifier = self.notifications.sender();
 
-        // Add a placeholder for the AI response that will be updated
-        self.ai_messages.push(("AI".to_string(), "🤔 Thinking...".to_string()));
+        self.runtime_handle.spawn(async move {
+            autocomplete_engine
+                .write()
+                .await
+                .add_to_history(command.clone(), 1, chrono::Utc::now());
+            let mut history = history.write().await;
+            history.add_entry(HistoryEntry::new(command, working_directory));
+            if let Err(e) = history.save(&history_path) {
+                notifier.error("Couldn't save command history", e);
+            }
+        });
+    }
 
-        // Process the message with the AI agent asynchronously
-        let ai_agent = self.ai_agent.clone();
-        let runtime_handle = self.runtime_handle.clone();
-        let response_sender = self.response_sender.clone();
-        let _ai_message_index = self.ai_messages.len() - 1;
+    fn record_history_result(&self, command: String, exit_code: i32, execution_time: u64) {
+        let history = self.history.clone();
+        let history_path = self.history_path.clone();
+        let autocomplete_engine = self.autocomplete_engine.clone();
+        let learned_commands = self.learned_commands.clone();
+        let notifier = self.notifications.sender();
 
-        runtime_handle.spawn(async move {
-            // Create an AI request based on the user's message
-            let ai_request = AiRequest::Chat { message: message.clone() };
-            
-            // Process the request with the AI agent
-            match ai_agent.read().await.process_request(ai_request).await {
-                Ok(ai_response) => {
-                    // Send the response back to the UI thread
-                    let _ = response_sender.send(ai_response);
+        self.runtime_handle.spawn(async move {
+            autocomplete_engine.write().await.record_result(&command, exit_code);
+            {
+                let mut learned = learned_commands.write().unwrap();
+                learned.record(&command, exit_code, chrono::Utc::now());
+                if let Err(e) = learned.save() {
+                    log::warn!("{:#}", e);
                 }
-                Err(e) => {
-                    // Send error response
-                    let error_response = AiResponse {
-                        content: format!("Sorry, I encountered an error: {}", e),
-                        confidence: 0.0,
-                        suggestions: vec![],
-                        code_snippets: vec![],
-                    };
-                    let _ = response_sender.send(error_response);
+            }
+            let mut history = history.write().await;
+            if history.record_result(&command, exit_code, execution_time) {
+                if let Err(e) = history.save(&history_path) {
+                    notifier.error("Couldn't save command history", e);
                 }
             }
         });
     }
 
-    pub fn execute_command(&mut self) {
-        if self.command_input.is_empty() {
-            return;
+    /// Saves the config, telling the user when it can't be.
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            self.notifications.sender().error("Couldn't save settings", format!("{:#}", e));
         }
+    }
 
-        let command = self.command_input.clone();
-        self.command_history.push_front(command.clone());
-
-        // Create a new terminal block
-        let block = TerminalBlock {
-            id: uuid::Uuid::new_v4(),
-            command: command.clone(),
-            output: String::new(),
-            is_running: true,
-            timestamp: chrono::Utc::now(),
+    fn handle_terminal_event(&mut self, event: TerminalEvent) {
+        self.session_recorder.record(&event);
+        self.window_title.handle_event(&event);
+        let rerun = match &event {
+            TerminalEvent::CommandStarted { id, input, .. } => self
+                .pending_reruns
+                .iter()
+                .position(|(command, _)| command == input)
+                .map(|index| (*id, self.pending_reruns.remove(index).1)),
+            _ => None,
+        };
+        let finished = match &event {
+            TerminalEvent::CommandFinished { id, .. } => Some(*id),
+            _ => None,
         };
+        match &event {
+            TerminalEvent::CommandFinished { id, exit_code } => {
+                if let Some(block) = self.terminal_output.iter().rev().find(|b| b.id == *id) {
+                    self.record_history_result(block.input.clone(), *exit_code, block_duration_ms(block));
+                    self.suggest_quick_fixes(block.id, *exit_code);
+                    if let Some(cache) = &self.kubectl {
+                        if kubectl::invalidates_cache(&block.command) {
+                            cache.invalidate();
+                        }
+                    }
+                }
+                if !self.remote.is_remote() {
+                    self.refresh_git_status();
+                }
+            }
+            // Builtins like `cd` may have moved into another repository; a
+            // remote `cd` leaves the local directory alone
+            TerminalEvent::NewBlock { .. } if !self.remote.is_remote() => {
+                self.refresh_git_status();
+                self.check_project();
+            }
+            TerminalEvent::CommandTimedOut { id, timeout } => {
+                let command = self.terminal_output.iter().rev().find(|b| b.id == *id).map(|b| b.command.clone());
+                self.notifications.sender().warning(
+                    format!("Timed out after {}", command_timeout::format_timeout(*timeout)),
+                    command.unwrap_or_default(),
+                );
+            }
+            TerminalEvent::RemoteChanged { remote } => self.remote.set_info(remote.clone()),
+            TerminalEvent::DirectoryChanged { directory } => {
+                self.working_directory = PathBuf::from(directory);
+                let ai_agent = self.ai_agent.clone();
+                let directory = directory.clone();
+                self.runtime_handle.spawn(async move {
+                    ai_agent.write().await.set_working_directory(directory);
+                });
+            }
+            TerminalEvent::Error { message } => {
+                self.notifications.sender().error("Command failed", message);
+            }
+            TerminalEvent::AliasesChanged { aliases } => {
+                self.config.terminal.aliases = aliases.clone();
+                self.save_config();
+            }
+            _ => {}
+        }
+        apply_terminal_event(&mut self.terminal_output, event);
 
-        self.terminal_output.push(block.clone());
-        self.command_input.clear();
+        if let Some((id, original)) = rerun {
+            if let Some(block) = self.terminal_output.iter_mut().rev().find(|b| b.id == id) {
+                block.rerun_of = Some(original);
+            }
+        }
+        // The diff is only offered when the re-run's output changed
+        if let Some(id) = finished {
+            let blocks = &self.terminal_output;
+            let unchanged = blocks.iter().rev().find(|b| b.id == id).and_then(|block| {
+                let original = blocks.iter().find(|b| Some(b.id) == block.rerun_of)?;
+                Some(original.output == block.output)
+            });
+            if unchanged != Some(false) {
+                if let Some(block) = self.terminal_output.iter_mut().rev().find(|b| b.id == id) {
+                    block.rerun_of = None;
+                }
+            }
+        }
+    }
 
-        // Execute the command
-        let runtime_handle = self.runtime_handle.clone();
-        let block_id = block.id;
-        let mut output_blocks = self.terminal_output.clone();
+    pub fn send_ai_message(&mut self) {
+        if self.ai_input.is_empty() {
+            return;
+        }
 
-        runtime_handle.spawn(async move {
-            // Simulate command execution
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let message = self.ai_input.clone();
+        self.ai_messages.push(("You".to_string(), message.clone()));
+        self.session_recorder.record_ai_message("You", &message);
+        self.ai_input.clear();
 
-            // Update the block with output
-            if let Some(block) = output_blocks.iter_mut().find(|b| b.id == block_id) {
-                block.output = format!("Executed: {}", command);
-                block.is_running = false;
-            }
-        });
+        // The reply streams into a new AI message as chunks arrive
+        self.start_ai_stream(message);
+    }
+
+    /// Runs the command in the input, the same way pressing Enter does.
+    pub fn execute_command(&mut self) {
+        self.submit_command();
     }
 
+    /// Scans the working directory in the background, reporting the
+    /// result as a notification.
     pub fn start_security_scan(&mut self, scan_type: ScanType) {
+        self.start_scan(ScanTarget::Path(self.working_directory.clone()), scan_type);
+    }
+
+    /// Scans `target` in the background, reporting the result as a
+    /// notification.
+    pub fn start_scan(&mut self, target: ScanTarget, scan_type: ScanType) {
+        if !self.is_ready(Subsystem::SecurityScanners) {
+            self.notifications
+                .sender()
+                .info("Still looking for the installed security scanners, try again in a moment");
+            return;
+        }
         info!("Starting {:?} security scan", scan_type);
-        // TODO: Implement actual security scan
+        let request = SecurityScanRequest {
+            target,
+            scan_type,
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            honor_ignore_comments: !self.security_findings.include_ignored,
+        };
+        let scanner = self.security_scanner.clone();
+        let notifier = self.notifications.sender();
+        let (report_tx, progress_tx) = self.security_findings.expect_report();
+        notifier.info(format!("Scanning {}…", request.target));
+        self.spawn_reported("The security scan", async move {
+            let report = match scanner.scan_with_progress(request, Some(progress_tx)).await {
+                Ok(report) => report,
+                Err(e) => return notifier.error("Security scan failed", format!("{:#}", e)),
+            };
+            for error in &report.scanner_errors {
+                notifier.warning("A security scanner didn't finish", error);
+            }
+            let summary = &report.summary;
+            notifier.success(
+                "Security scan finished",
+                format!(
+                    "{} issue{}: {} critical, {} high, {} medium, {} low",
+                    summary.total_vulnerabilities,
+                    if summary.total_vulnerabilities == 1 { "" } else { "s" },
+                    summary.critical_count,
+                    summary.high_count,
+                    summary.medium_count,
+                    summary.low_count
+                ),
+            );
+            let _ = report_tx.send(report);
+        });
     }
 
     fn render_welcome_screen(&mut self, ctx: &egui::Context) {
@@ -406,8 +1427,14 @@ impl AnTraftApp {
                 ui.add_space(50.0);
                 
                 // Welcome heading
-                ui.heading("Hello, Shaik!");
+                ui.heading(format!("Hello, {}!", self.config.display_name()));
                 ui.label("Get started with one of these suggestions");
+                if !self.startup_finished() {
+                    ui.horizontal(|ui| {
+                        accessibility::spinner(ui);
+                        ui.weak(format!("Loading {}…", self.startup_pending()));
+                    });
+                }
                 ui.add_space(30.0);
                 
                 // Action cards in a grid
@@ -443,6 +1470,8 @@ impl AnTraftApp {
                         self.current_mode = UIMode::AiAgent;
                     }
                 });
+
+                self.render_recent_workspaces(ui);
             });
             
             // Bottom command input
@@ -452,20 +1481,18 @@ impl AnTraftApp {
                     ui.add_space(50.0);
                     ui.label("❯");
                     let response = ui.add_sized([600.0, 25.0], egui::TextEdit::singleline(&mut self.command_input)
-                        .hint_text("code, ask, build, or run commands"));
+                        .hint_text("run a command, describe one, or ask"));
                     
                     if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                        if !self.command_input.is_empty() {
-                            if self.command_input.starts_with("ai ") || self.command_input.starts_with("ask ") {
-                                self.ai_input = self.command_input.clone();
-                                self.current_mode = UIMode::AiAgent;
-                            } else {
-                                self.current_mode = UIMode::Terminal;
-                                self.execute_command_sync();
-                            }
-                        }
+                        self.submit_welcome_input();
                     }
                 });
+
+                if self.command_suggestion.is_some() {
+                    ui.add_space(8.0);
+                    self.render_command_suggestion(ui);
+                    ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+                }
                 
                 // Mode selector
                 ui.horizontal(|ui| {
@@ -476,7 +1503,17 @@ impl AnTraftApp {
                     if ui.selectable_label(self.current_mode == UIMode::AiAgent, "🤖 AI Agent").clicked() {
                         self.current_mode = UIMode::AiAgent;
                     }
-                    ui.label("auto (claude-3.5-sonnet) ⚙");
+                    self.render_model_selector(ui, "welcome_model_selector");
+                    if ui
+                        .checkbox(&mut self.config.ai.detect_natural_language, "✨ Detect natural language")
+                        .on_hover_text("Generate a command from sentences like \"list all rust files modified today\" instead of running them")
+                        .changed()
+                    {
+                        self.save_config();
+                    }
+                    if accessibility::icon_button(ui, "⚙", "Settings").clicked() {
+                        self.show_settings = !self.show_settings;
+                    }
                 });
             });
         });
@@ -484,30 +1521,34 @@ impl AnTraftApp {
     
     fn render_action_card(&mut self, ui: &mut egui::Ui, icon: &str, title: &str, description: &str) -> bool {
         let mut clicked = false;
+        let palette = Palette::for_settings(&self.config.accessibility);
         
         ui.allocate_ui_with_layout([180.0, 120.0].into(), egui::Layout::top_down(egui::Align::Center), |ui| {
             let rect = ui.available_rect_before_wrap();
+            // Focusable, so Tab reaches it and Enter or Space clicks it
             let response = ui.allocate_response(rect.size(), egui::Sense::click());
+            response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, format!("{}: {}", title, description)));
             
-            if response.hovered() {
+            if response.hovered() || response.has_focus() {
                 ui.painter().rect_filled(
                     rect,
                     egui::Rounding::same(8.0),
-                    egui::Color32::from_rgb(40, 40, 45)
+                    palette.card_hovered
                 );
             } else {
                 ui.painter().rect_filled(
                     rect,
                     egui::Rounding::same(8.0),
-                    egui::Color32::from_rgb(30, 30, 35)
+                    palette.card
                 );
             }
             
             ui.painter().rect_stroke(
                 rect,
                 egui::Rounding::same(8.0),
-                egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 65))
+                egui::Stroke::new(1.0, palette.card_border)
             );
+            accessibility::focus_ring(ui, &response, 8.0);
             
             ui.vertical_centered(|ui| {
                 ui.add_space(15.0);
@@ -515,7 +1556,7 @@ impl AnTraftApp {
                 ui.add_space(8.0);
                 ui.label(egui::RichText::new(title).strong());
                 ui.add_space(5.0);
-                ui.label(egui::RichText::new(description).small().color(egui::Color32::GRAY));
+                ui.label(egui::RichText::new(description).small().color(palette.muted));
             });
             
             if response.clicked() {
@@ -527,10 +1568,49 @@ impl AnTraftApp {
     }
     
     fn render_terminal_mode(&mut self, ctx: &egui::Context) {
+        self.render_mode_panel(ctx);
+
+        if self.show_file_explorer {
+            let panel = egui::SidePanel::left("file_explorer_panel").resizable(true);
+            let response = self
+                .layout
+                .panel(panel, self.layout.state.file_explorer_width)
+                .show(ctx, |ui| {
+                    self.render_file_explorer(ui);
+                });
+            self.layout.state.file_explorer_width = response.response.rect.width();
+        }
+
+        if self.config.terminal.layout == PanelLayout::Split {
+            let panel = egui::SidePanel::right("ai_panel").resizable(true).min_width(260.0);
+            let response = self
+                .layout
+                .panel(panel, self.layout.state.ai_panel_width)
+                .show(ctx, |ui| {
+                    self.render_ai_panel(ui);
+                });
+            self.layout.state.ai_panel_width = response.response.rect.width();
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.render_terminal(ui);
         });
-        
+    }
+
+    /// Switches between the terminal and the chat as separate views and the
+    /// two side by side, and saves the choice.
+    fn toggle_split_layout(&mut self) {
+        self.config.terminal.layout = match self.config.terminal.layout {
+            PanelLayout::Tabbed => PanelLayout::Split,
+            PanelLayout::Split => PanelLayout::Tabbed,
+        };
+        if self.current_mode == UIMode::Welcome {
+            self.current_mode = UIMode::Terminal;
+        }
+        self.save_config();
+    }
+    
+    fn render_mode_panel(&mut self, ctx: &egui::Context) {
         // Bottom panel for mode switching
         egui::TopBottomPanel::bottom("mode_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -543,57 +1623,159 @@ impl AnTraftApp {
                 if ui.selectable_label(self.current_mode == UIMode::AiAgent, "🤖 AI Agent").clicked() {
                     self.current_mode = UIMode::AiAgent;
                 }
+                ui.separator();
+                if ui
+                    .selectable_label(self.config.terminal.layout == PanelLayout::Split, "◫ Split")
+                    .on_hover_text("Show the AI chat beside the terminal")
+                    .clicked()
+                {
+                    self.toggle_split_layout();
+                }
+                if ui.selectable_label(self.show_file_explorer, "📁 Files").clicked() {
+                    self.show_file_explorer = !self.show_file_explorer;
+                    if self.show_file_explorer {
+                        self.current_mode = UIMode::Terminal;
+                    }
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.selectable_label(self.show_settings, "⚙ Settings").clicked() {
+                        self.show_settings = !self.show_settings;
+                    }
+                    self.render_profile_menu(ui);
+                    self.render_notification_bell(ui);
+                    self.render_ai_health(ui);
+                    if let Some(status) = self.history_import_status() {
+                        ui.small(status);
+                    }
+                    if !self.startup_finished() {
+                        ui.small(format!("⏳ {}…", self.startup_pending()))
+                            .on_hover_text("Loading in the background; completions fill in as it finishes");
+                    }
+                    self.render_recording_indicator(ui);
+                    self.render_remote_indicator(ui);
+                    self.render_containers_menu(ui);
+                    self.render_kube_menu(ui);
+                    self.render_git_status(ui);
+                    self.render_process_usage(ui);
+                    self.render_session_status(ui);
+                });
             });
         });
     }
-    
+
     fn render_ai_mode(&mut self, ctx: &egui::Context) {
+        if self.config.terminal.layout == PanelLayout::Split {
+            // The chat is docked beside the terminal
+            return self.render_terminal_mode(ctx);
+        }
+        self.render_mode_panel(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.render_ai_panel(ui);
         });
-        
-        // Bottom panel for mode switching
-        egui::TopBottomPanel::bottom("mode_panel").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if ui.selectable_label(self.current_mode == UIMode::Welcome, "🏠 Welcome").clicked() {
-                    self.current_mode = UIMode::Welcome;
-                }
-                if ui.selectable_label(self.current_mode == UIMode::Terminal, "🖥 Terminal").clicked() {
-                    self.current_mode = UIMode::Terminal;
-                }
-                if ui.selectable_label(self.current_mode == UIMode::AiAgent, "🤖 AI Agent").clicked() {
-                    self.current_mode = UIMode::AiAgent;
-                }
-            });
-        });
     }
 }
 
 impl eframe::App for AnTraftApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for AI responses and update the UI accordingly
+        if self.time_to_first_frame().is_none() {
+            self.on_first_frame();
+        }
+        self.handle_close_request(ctx);
+        self.update_layout(ctx);
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::P)) {
+            self.command_palette.toggle();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::F)) {
+            self.content_search.toggle();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::B)) {
+            self.open_favorites();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Comma)) {
+            self.show_settings = !self.show_settings;
+        }
+
+        self.handle_undo_keys(ctx);
+        self.handle_block_navigation(ctx);
+
+        while let Ok(event) = self.terminal_event_rx.try_recv() {
+            self.handle_terminal_event(event);
+        }
+        while let Ok(event) = self.history_import_rx.try_recv() {
+            self.handle_history_import_event(event);
+        }
+        while let Ok(annotation) = self.block_annotation_rx.try_recv() {
+            self.handle_block_annotation(annotation);
+        }
+        while let Ok(update) = self.quick_fix_rx.try_recv() {
+            self.handle_quick_fixes(update);
+        }
+        while let Ok(update) = self.summary_rx.try_recv() {
+            self.handle_summary_update(update);
+        }
+        self.poll_startup();
+        if !self.startup_finished() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        if self.summary_message.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+        if self.terminal_output.iter().any(|b| b.is_running) {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        // Chat replies stream in; other AI responses arrive whole
+        self.poll_ai_stream(ctx);
+        self.poll_git_status(ctx);
+        self.poll_process_usage(ctx);
+        self.poll_security_report();
+        self.poll_model_catalog(ctx);
+        self.poll_ai_health(ctx);
+        self.poll_notifications(ctx);
+        self.poll_workspaces();
+        self.refresh_crash_snapshot();
         while let Ok(ai_response) = self.response_receiver.try_recv() {
-            // Find the last AI message (which should be the "Thinking..." placeholder)
-            if let Some((role, message)) = self.ai_messages.last_mut() {
-                if role == "AI" && message.contains("🤔 Thinking...") {
-                    *message = ai_response.content;
-                }
+            if !ai_response.injection_signals.is_empty() {
+                self.insert_injection_warning(self.ai_messages.len(), &ai_response.injection_signals);
             }
+            self.ai_messages.push(("AI".to_string(), ai_response.content));
         }
 
-        // Dark theme similar to Warp
-        let mut style = (*ctx.style()).clone();
-        style.visuals.dark_mode = true;
-        style.visuals.window_fill = egui::Color32::from_rgb(16, 16, 20);
-        style.visuals.panel_fill = egui::Color32::from_rgb(16, 16, 20);
-        style.visuals.extreme_bg_color = egui::Color32::from_rgb(12, 12, 15);
-        style.visuals.faint_bg_color = egui::Color32::from_rgb(20, 20, 24);
-        ctx.set_style(style);
+        self.update_window_title(ctx);
+
+        // Dark theme similar to Warp, or its high-contrast variant
+        accessibility::apply_style(ctx, &self.config.accessibility);
 
         match self.current_mode {
             UIMode::Welcome => self.render_welcome_screen(ctx),
             UIMode::Terminal => self.render_terminal_mode(ctx),
             UIMode::AiAgent => self.render_ai_mode(ctx),
         }
+
+        self.render_content_search(ctx);
+        self.render_session_recording(ctx);
+        self.render_project_trust(ctx);
+        self.render_remote_picker(ctx);
+        self.render_file_preview(ctx);
+        self.render_usage_stats(ctx);
+        self.render_script_preview(ctx);
+        self.render_output_compare(ctx);
+        self.render_security_findings(ctx);
+        self.render_commit_message_dialog(ctx);
+        self.render_pipeline_dialog(ctx);
+        self.render_conversation_dialog(ctx);
+        self.render_history_import(ctx);
+        self.render_log_view(ctx);
+        if self.show_settings {
+            self.render_settings(ctx);
+        }
+        self.render_command_palette(ctx);
+        self.render_favorites_palette(ctx);
+        self.render_workspace_switcher(ctx);
+        self.render_notifications(ctx);
+        self.render_crash_dialog(ctx);
+        self.render_shutdown(ctx);
     }
 }
@@ -0,0 +1,173 @@
+use super::{accessibility, AnTraftApp};
+use crate::ai::ModelInfo;
+use eframe::egui;
+
+/// Models offered by the provider, fetched in the background the first time
+/// a model picker is shown.
+#[derive(Default)]
+pub struct ModelCatalog {
+    models: Option<Vec<ModelInfo>>,
+    error: Option<String>,
+    fetch_rx: Option<crossbeam_channel::Receiver<Result<Vec<ModelInfo>, String>>>,
+    /// Text of the manual model field while it's being edited
+    draft: Option<String>,
+}
+
+impl ModelCatalog {
+    fn model(&self, name: &str) -> Option<&ModelInfo> {
+        self.models.as_ref()?.iter().find(|model| model.name == name)
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn fetch_models(&mut self) {
+        if self.model_catalog.fetch_rx.is_some() {
+            return;
+        }
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.model_catalog.fetch_rx = Some(rx);
+        self.model_catalog.error = None;
+        let ai_agent = self.ai_agent.clone();
+        self.spawn_reported("Listing the models", async move {
+            let result = ai_agent.read().await.list_models().await;
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    pub(super) fn poll_model_catalog(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.model_catalog.fetch_rx else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(Ok(models)) => {
+                self.model_catalog.models = Some(models);
+                self.model_catalog.fetch_rx = None;
+            }
+            Ok(Err(e)) => {
+                self.notifications.sender().warning("Couldn't list the AI models", &e);
+                self.model_catalog.error = Some(e);
+                self.model_catalog.fetch_rx = None;
+            }
+            Err(_) => ctx.request_repaint_after(std::time::Duration::from_millis(200)),
+        }
+    }
+
+    /// Switches the AI to `model` and saves the choice.
+    fn select_model(&mut self, model: String) {
+        if model == self.config.ai.model {
+            return;
+        }
+        self.config.ai.model = model.clone();
+        self.save_config();
+
+        let ai_agent = self.ai_agent.clone();
+        self.runtime_handle.spawn(async move {
+            ai_agent.write().await.set_model(&model);
+        });
+    }
+
+    /// A dropdown of the provider's models, or a text field for the model id
+    /// when they can't be listed.
+    pub(super) fn render_model_selector(&mut self, ui: &mut egui::Ui, id_source: &str) {
+        let never_fetched = self.model_catalog.models.is_none()
+            && self.model_catalog.error.is_none()
+            && self.model_catalog.fetch_rx.is_none();
+        if never_fetched && !self.config.ai.api_key.is_empty() {
+            self.fetch_models();
+        }
+
+        let current = self.config.ai.model.clone();
+        let mut selected = current.clone();
+
+        match self.model_catalog.models.as_ref().filter(|models| !models.is_empty()) {
+            Some(models) => {
+                egui::ComboBox::from_id_source(id_source)
+                    .selected_text(&current)
+                    .show_ui(ui, |ui| {
+                        for model in models {
+                            let mut label = model.display_name.clone();
+                            if let Some(limit) = model.input_token_limit {
+                                label.push_str(&format!("  ·  {} in", format_token_count(limit)));
+                            }
+                            ui.selectable_value(&mut selected, model.name.clone(), label)
+                                .on_hover_text(&model.name);
+                        }
+                    });
+            }
+            None => {
+                let draft = self.model_catalog.draft.get_or_insert_with(|| current.clone());
+                let response = ui.add(
+                    egui::TextEdit::singleline(draft)
+                        .desired_width(160.0)
+                        .hint_text("model id"),
+                );
+                if response.lost_focus() {
+                    if let Some(draft) = self.model_catalog.draft.take() {
+                        let draft = draft.trim();
+                        if !draft.is_empty() {
+                            selected = draft.to_string();
+                        }
+                    }
+                }
+
+                if self.model_catalog.fetch_rx.is_some() {
+                    accessibility::spinner(ui);
+                } else if !self.config.ai.api_key.is_empty() {
+                    let hover = match &self.model_catalog.error {
+                        Some(e) => format!("Couldn't list models: {}\nClick to retry", e),
+                        None => "List the provider's models".to_string(),
+                    };
+                    if accessibility::icon_button(ui, "↻", &hover).clicked() {
+                        self.fetch_models();
+                    }
+                }
+            }
+        }
+
+        if let Some(warning) = self.max_tokens_warning() {
+            ui.colored_label(egui::Color32::YELLOW, "⚠").on_hover_text(warning);
+        }
+
+        if selected != current {
+            self.select_model(selected);
+        }
+    }
+
+    /// Token limits of the selected model, for the settings window.
+    pub(super) fn render_model_limits(&self, ui: &mut egui::Ui) {
+        if let Some(model) = self.model_catalog.model(&self.config.ai.model) {
+            let limit = |limit: Option<u32>| limit.map(format_token_count).unwrap_or_else(|| "?".to_string());
+            ui.small(format!(
+                "Input limit: {} tokens · Output limit: {} tokens",
+                limit(model.input_token_limit),
+                limit(model.output_token_limit)
+            ));
+        }
+        if let Some(warning) = self.max_tokens_warning() {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning));
+        }
+    }
+
+    fn max_tokens_warning(&self) -> Option<String> {
+        let model = self.model_catalog.model(&self.config.ai.model)?;
+        let limit = model.output_token_limit?;
+        (self.config.ai.max_tokens > limit).then(|| {
+            format!(
+                "max_tokens ({}) is more than {} can output ({})",
+                self.config.ai.max_tokens, model.name, limit
+            )
+        })
+    }
+}
+
+/// `1048576` -> `1M`, `8192` -> `8k`
+fn format_token_count(tokens: u32) -> String {
+    if tokens >= 1_000_000 {
+        format!("{}M", tokens / 1_000_000)
+    } else if tokens >= 1_000 {
+        format!("{}k", tokens / 1_000)
+    } else {
+        tokens.to_string()
+    }
+}
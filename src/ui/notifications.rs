@@ -0,0 +1,136 @@
+use super::{accessibility, AnTraftApp};
+use crate::notifications::{Notification, NotificationLevel, NotificationSender, Notifications};
+use eframe::egui;
+use std::time::Instant;
+
+/// Toasts shown at once; older ones wait in the history.
+const MAX_TOASTS: usize = 4;
+
+/// Notifications as toasts in the bottom-right corner, and their history in
+/// a drawer opened from the status bar.
+#[derive(Default)]
+pub struct NotificationCenter {
+    pub notifications: Notifications,
+    show_history: bool,
+}
+
+impl NotificationCenter {
+    pub fn sender(&self) -> NotificationSender {
+        self.notifications.sender()
+    }
+}
+
+fn level_style(level: NotificationLevel) -> (&'static str, egui::Color32) {
+    match level {
+        NotificationLevel::Info => ("ℹ", egui::Color32::from_rgb(100, 150, 255)),
+        NotificationLevel::Success => ("✔", egui::Color32::from_rgb(100, 200, 100)),
+        NotificationLevel::Warning => ("⚠", egui::Color32::from_rgb(230, 180, 60)),
+        NotificationLevel::Error => ("✖", egui::Color32::from_rgb(230, 90, 90)),
+    }
+}
+
+/// A notification's icon, title and detail. Returns whether ✖ was clicked.
+fn render_notification(ui: &mut egui::Ui, notification: &Notification, dismissible: bool) -> bool {
+    let (icon, color) = level_style(notification.level);
+    let mut dismissed = false;
+    ui.horizontal(|ui| {
+        ui.colored_label(color, icon);
+        ui.strong(&notification.title);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if dismissible && accessibility::icon_button(ui, "✖", "Dismiss").clicked() {
+                dismissed = true;
+            }
+            ui.weak(notification.timestamp.format("%H:%M:%S").to_string());
+        });
+    });
+    if let Some(detail) = &notification.detail {
+        ui.label(egui::RichText::new(detail).small());
+    }
+    dismissed
+}
+
+impl AnTraftApp {
+    /// Drains and expires notifications; called every frame.
+    pub(super) fn poll_notifications(&mut self, ctx: &egui::Context) {
+        let notifications = &mut self.notifications.notifications;
+        notifications.drain();
+        let now = Instant::now();
+        notifications.expire(now);
+        if let Some(next) = notifications.next_expiry(now) {
+            ctx.request_repaint_after(next);
+        }
+    }
+
+    pub(super) fn render_notifications(&mut self, ctx: &egui::Context) {
+        let mut dismissed = Vec::new();
+        let toasts: Vec<&Notification> = self.notifications.notifications.toasts().rev().take(MAX_TOASTS).collect();
+        if !toasts.is_empty() {
+            egui::Area::new(egui::Id::new("notification_toasts"))
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -40.0))
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    ui.set_max_width(360.0);
+                    // Newest at the bottom, nearest the corner
+                    for notification in toasts.into_iter().rev() {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.set_width(340.0);
+                            if render_notification(ui, notification, true) {
+                                dismissed.push(notification.id);
+                            }
+                        });
+                    }
+                });
+        }
+        for id in dismissed {
+            self.notifications.notifications.dismiss(id);
+        }
+
+        let mut open = self.notifications.show_history;
+        let mut clear = false;
+        egui::Window::new("Notifications")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                let history = self.notifications.notifications.history();
+                if history.is_empty() {
+                    ui.weak("Nothing yet");
+                    return;
+                }
+                if ui.small_button("Clear").clicked() {
+                    clear = true;
+                }
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for notification in history.iter().rev() {
+                        render_notification(ui, notification, false);
+                        ui.separator();
+                    }
+                });
+            });
+        self.notifications.show_history = open;
+        if clear {
+            self.notifications.notifications.clear();
+        }
+    }
+
+    /// The 🔔 status-bar button opening the history, with the number of
+    /// errors still on screen.
+    pub(super) fn render_notification_bell(&mut self, ui: &mut egui::Ui) {
+        let errors = self
+            .notifications
+            .notifications
+            .toasts()
+            .filter(|n| n.level == NotificationLevel::Error)
+            .count();
+        let label = match errors {
+            0 => "🔔".to_string(),
+            errors => format!("🔔 {}", errors),
+        };
+        if ui
+            .selectable_label(self.notifications.show_history, label)
+            .on_hover_text("Notifications")
+            .clicked()
+        {
+            self.notifications.show_history = !self.notifications.show_history;
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use super::diff_view::{ADDED_BACKGROUND, ADDED_TEXT, HUNK_HEADER, REMOVED_BACKGROUND, REMOVED_TEXT};
+use super::{AnTraftApp, TerminalBlock};
+use crate::git::diff::DiffLineKind;
+use crate::terminal::diff::{self, OutputDiff, OutputLine};
+use eframe::egui;
+use uuid::Uuid;
+
+/// Behind the words that changed within a changed line
+const ADDED_HIGHLIGHT: egui::Color32 = egui::Color32::from_rgb(40, 100, 52);
+const REMOVED_HIGHLIGHT: egui::Color32 = egui::Color32::from_rgb(120, 40, 40);
+
+/// One block's output compared with another's, in a window. The earlier
+/// block is the old side.
+pub struct OutputCompare {
+    /// The block "Compare with…" was picked on
+    block: Uuid,
+    other: Option<Uuid>,
+    ignore_whitespace: bool,
+    /// The diff for `computed_for`, or why there isn't one
+    result: Option<Result<OutputDiff, String>>,
+    computed_for: Option<(Uuid, bool)>,
+    status: Option<String>,
+}
+
+/// The last finished run of `block_id`'s command before it.
+pub fn previous_run(blocks: &[TerminalBlock], block_id: Uuid) -> Option<Uuid> {
+    let index = blocks.iter().position(|block| block.id == block_id)?;
+    let input = &blocks[index].input;
+    blocks[..index]
+        .iter()
+        .rev()
+        .find(|block| !block.is_running && !input.is_empty() && &block.input == input)
+        .map(|block| block.id)
+}
+
+/// The command and start time of a block, to tell runs apart.
+fn block_label(block: &TerminalBlock) -> String {
+    format!(
+        "{} ({})",
+        block.command,
+        block.timestamp.with_timezone(&chrono::Local).format("%H:%M:%S")
+    )
+}
+
+/// The chip on a re-run block whose output differs from the run before.
+pub fn render_rerun_diff(ui: &mut egui::Ui) -> bool {
+    ui.small_button("⇄ Diff against previous run")
+        .on_hover_text("The output changed since the run before; compare them")
+        .clicked()
+}
+
+impl AnTraftApp {
+    /// Opens the comparison window for `block_id`, against `other` or else
+    /// the previous run of the same command.
+    pub(super) fn open_output_compare(&mut self, block_id: Uuid, other: Option<Uuid>) {
+        self.output_compare = Some(OutputCompare {
+            block: block_id,
+            other: other.or_else(|| previous_run(&self.terminal_output, block_id)),
+            ignore_whitespace: false,
+            result: None,
+            computed_for: None,
+            status: None,
+        });
+    }
+
+    pub(super) fn render_output_compare(&mut self, ctx: &egui::Context) {
+        let Some(compare) = self.output_compare.as_mut() else {
+            return;
+        };
+        let blocks = &self.terminal_output;
+        let Some(index) = blocks.iter().position(|block| block.id == compare.block) else {
+            // Cleared from the terminal
+            self.output_compare = None;
+            return;
+        };
+        let other_index = compare.other.and_then(|other| blocks.iter().position(|block| block.id == other));
+        // The earlier block is the old side
+        let sides = other_index.map(|other| match other < index {
+            true => (&blocks[other], &blocks[index]),
+            false => (&blocks[index], &blocks[other]),
+        });
+        if let Some((old, new)) = sides {
+            let key = (compare.other.unwrap_or_default(), compare.ignore_whitespace);
+            if compare.computed_for != Some(key) {
+                compare.computed_for = Some(key);
+                compare.result = Some(
+                    diff::diff_outputs(&old.output, &new.output, compare.ignore_whitespace)
+                        .map_err(|e| e.to_string()),
+                );
+            }
+        }
+
+        let mut open = true;
+        egui::Window::new("⇄ Compare Outputs")
+            .open(&mut open)
+            .default_size([720.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}  with", block_label(&blocks[index])));
+                    let selected = other_index.map_or_else(|| "Pick a block…".to_string(), |other| block_label(&blocks[other]));
+                    egui::ComboBox::from_id_source("compare_with")
+                        .selected_text(selected)
+                        .width(280.0)
+                        .show_ui(ui, |ui| {
+                            for block in blocks.iter().rev() {
+                                if block.id != compare.block && !block.is_running && !block.command.is_empty() {
+                                    ui.selectable_value(&mut compare.other, Some(block.id), block_label(block));
+                                }
+                            }
+                        });
+                });
+                let Some((old, new)) = sides else {
+                    ui.weak("Pick a block to compare with.");
+                    return;
+                };
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut compare.ignore_whitespace, "Ignore whitespace")
+                        .on_hover_text("Treat lines differing only in spacing as unchanged");
+                    if let Some(Ok(diff)) = &compare.result {
+                        ui.colored_label(ADDED_TEXT, format!("+{}", diff.added));
+                        ui.colored_label(REMOVED_TEXT, format!("−{}", diff.removed));
+                        if ui
+                            .add_enabled(!diff.is_empty(), egui::Button::new("📋 Copy as patch"))
+                            .clicked()
+                        {
+                            let patch = diff.to_patch(&block_label(old), &block_label(new));
+                            ui.output_mut(|o| o.copied_text = patch);
+                            compare.status = Some("Copied to clipboard".to_string());
+                        }
+                    }
+                });
+                if let Some(status) = &compare.status {
+                    ui.small(status);
+                }
+                ui.separator();
+
+                match &compare.result {
+                    Some(Ok(diff)) if diff.is_empty() => {
+                        ui.weak(match compare.ignore_whitespace {
+                            true => "The outputs are the same, apart from whitespace.",
+                            false => "The outputs are the same.",
+                        });
+                    }
+                    Some(Ok(diff)) => render_diff(ui, diff),
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::from_rgb(220, 100, 100), e);
+                    }
+                    None => {}
+                }
+            });
+
+        if !open {
+            self.output_compare = None;
+        }
+    }
+}
+
+pub(super) fn render_diff(ui: &mut egui::Ui, diff: &OutputDiff) {
+    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+        for hunk in &diff.hunks {
+            ui.add(egui::Label::new(egui::RichText::new(&hunk.header).monospace().color(HUNK_HEADER)).wrap(false));
+            for line in &hunk.lines {
+                ui.add(egui::Label::new(line_job(ui, line)).wrap(false));
+            }
+        }
+    });
+}
+
+fn line_job(ui: &egui::Ui, line: &OutputLine) -> egui::text::LayoutJob {
+    let (marker, color, background, highlight) = match line.kind {
+        DiffLineKind::Added => ('+', ADDED_TEXT, ADDED_BACKGROUND, ADDED_HIGHLIGHT),
+        DiffLineKind::Removed => ('-', REMOVED_TEXT, REMOVED_BACKGROUND, REMOVED_HIGHLIGHT),
+        DiffLineKind::Context => (' ', ui.visuals().text_color(), egui::Color32::TRANSPARENT, egui::Color32::TRANSPARENT),
+    };
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let format = |background| egui::TextFormat {
+        background,
+        ..egui::TextFormat::simple(font.clone(), color)
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    let number = line.new_line.or(line.old_line).map_or_else(String::new, |number| number.to_string());
+    job.append(&format!("{:>5} ", number), 0.0, egui::TextFormat::simple(font.clone(), ui.visuals().weak_text_color()));
+    job.append(&marker.to_string(), 0.0, format(background));
+    for segment in &line.segments {
+        job.append(&segment.text, 0.0, format(if segment.changed { highlight } else { background }));
+    }
+    job
+}
@@ -0,0 +1,177 @@
+use super::accessibility;
+use super::output_pager::{self, OutputPager, PAGER_VISIBLE_LINES};
+use eframe::egui;
+use regex::{Regex, RegexBuilder};
+
+/// Filter-as-you-type over a block's output. Unlike find in the pager, it
+/// narrows the view to the matching lines; the output itself is untouched.
+#[derive(Debug, Clone, Default)]
+pub struct OutputFilter {
+    /// The filter row is showing
+    pub open: bool,
+    query: String,
+    /// Treat the query as a regular expression
+    regex: bool,
+    /// Dim the lines that don't match instead of hiding them
+    dim: bool,
+    /// Lines that match `query`
+    matches: Vec<usize>,
+    /// Lines checked against `query` so far
+    filtered_lines: usize,
+    /// Set when the query isn't a valid regex
+    error: Option<String>,
+    focus: bool,
+}
+
+impl OutputFilter {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus = self.open;
+    }
+
+    /// Whether the output should be shown filtered.
+    pub fn is_active(&self) -> bool {
+        self.open && !self.query.is_empty()
+    }
+
+    fn pattern(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        // Case-insensitive unless the query has capitals, like `less -i`
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.query.chars().any(char::is_uppercase))
+            .build()
+    }
+
+    fn reset(&mut self) {
+        self.matches.clear();
+        self.filtered_lines = 0;
+    }
+
+    /// Checks the lines added since the last call. The last line is checked
+    /// again, since it may have been incomplete.
+    fn update_matches(&mut self, output: &str, pager: &OutputPager, pattern: &Regex) {
+        let line_count = pager.line_count(output);
+        if self.filtered_lines > line_count {
+            self.reset();
+        }
+        let from = self.filtered_lines.saturating_sub(1);
+        self.matches.retain(|&line| line < from);
+        for line in from..line_count {
+            if pattern.is_match(&output[pager.line_range(output, line)]) {
+                self.matches.push(line);
+            }
+        }
+        self.filtered_lines = line_count;
+    }
+}
+
+/// The filter row under a block's header.
+pub fn render_filter_bar(ui: &mut egui::Ui, block_id: uuid::Uuid, output: &str, pager: &OutputPager, filter: &mut OutputFilter) {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut filter.query)
+                .id_source(("output_filter", block_id))
+                .desired_width(200.0)
+                .hint_text("Filter lines"),
+        );
+        if std::mem::take(&mut filter.focus) {
+            response.request_focus();
+        }
+        changed |= response.changed();
+        if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            filter.query.clear();
+            filter.open = false;
+            changed = true;
+        }
+        if ui.selectable_label(filter.regex, ".*").on_hover_text("Regular expression").clicked() {
+            filter.regex = !filter.regex;
+            changed = true;
+        }
+        ui.selectable_value(&mut filter.dim, false, "Hide")
+            .on_hover_text("Show only the matching lines");
+        ui.selectable_value(&mut filter.dim, true, "Dim")
+            .on_hover_text("Show all lines, dimming those that don't match");
+
+        if let Some(error) = &filter.error {
+            ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+        } else if !filter.query.is_empty() {
+            ui.weak(format!("{} of {} lines", filter.matches.len(), pager.line_count(output)));
+        }
+        if accessibility::icon_button(ui, "✕", "Close the filter").clicked() {
+            filter.open = false;
+        }
+    });
+
+    if changed {
+        filter.reset();
+    }
+    if filter.query.is_empty() {
+        filter.error = None;
+        filter.reset();
+        return;
+    }
+    match filter.pattern() {
+        Ok(pattern) => {
+            filter.error = None;
+            filter.update_matches(output, pager, &pattern);
+        }
+        Err(_) => {
+            filter.error = Some("Invalid regex".to_string());
+            filter.reset();
+        }
+    }
+}
+
+/// Shows only the lines matching the filter, or all lines with the others
+/// dimmed, with the matches highlighted.
+pub fn render_filtered_output(
+    ui: &mut egui::Ui,
+    block_id: uuid::Uuid,
+    output: &str,
+    pager: &OutputPager,
+    filter: &OutputFilter,
+    is_running: bool,
+) {
+    let Ok(pattern) = filter.pattern() else {
+        return;
+    };
+    let row_count = if filter.dim {
+        pager.line_count(output)
+    } else {
+        filter.matches.len()
+    };
+    if row_count == 0 {
+        ui.weak("No matching lines");
+        return;
+    }
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    let spacing = ui.spacing().item_spacing.y;
+    let number_width = pager.line_count(output).to_string().len();
+    egui::ScrollArea::both()
+        .id_source(("output_filter_rows", block_id))
+        .max_height((row_height + spacing) * PAGER_VISIBLE_LINES as f32)
+        .auto_shrink([false, true])
+        .stick_to_bottom(is_running)
+        .show_rows(ui, row_height, row_count, |ui, rows| {
+            for row in rows {
+                let line = if filter.dim { row } else { filter.matches[row] };
+                let text = output[pager.line_range(output, line)].trim_end_matches(['\n', '\r']);
+                let matched = !filter.dim || filter.matches.binary_search(&line).is_ok();
+                ui.horizontal(|ui| {
+                    ui.weak(format!("{:>width$}", line + 1, width = number_width));
+                    if matched {
+                        let job = output_pager::highlight_line(ui, text, &pattern, false);
+                        ui.add(egui::Label::new(job).wrap(false));
+                    } else {
+                        ui.add(egui::Label::new(egui::RichText::new(text).weak()).wrap(false));
+                    }
+                });
+            }
+        });
+}
@@ -0,0 +1,214 @@
+use super::output_links::{self, LinkAction};
+use super::output_pager::PAGER_VISIBLE_LINES;
+use super::TerminalBlock;
+use crate::terminal::output_classifier::{ClassifiedOutput, LineClass, OutputClassifiers};
+use eframe::egui;
+use std::collections::HashSet;
+use std::ops::Range;
+
+const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 100, 100);
+const WARNING_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 180, 60);
+
+/// A block's output as classified by its tool's `OutputClassifier`: runs of
+/// progress noise fold into one row, and warnings and errors are colored.
+#[derive(Debug, Clone)]
+pub struct OutputFolding {
+    /// Looked for a classifier for the command already
+    checked: bool,
+    output: Option<ClassifiedOutput>,
+    /// Shown folded, rather than as plain output
+    pub enabled: bool,
+    /// First lines of the folds the user opened
+    opened: HashSet<usize>,
+}
+
+impl Default for OutputFolding {
+    fn default() -> Self {
+        Self {
+            checked: false,
+            output: None,
+            enabled: true,
+            opened: HashSet::new(),
+        }
+    }
+}
+
+impl OutputFolding {
+    /// Classifies the lines appended since the last call, once a classifier
+    /// was found for `command`.
+    pub fn update(&mut self, classifiers: Option<&OutputClassifiers>, command: &str, output: &str, finished: bool) {
+        if !self.checked {
+            self.checked = true;
+            self.output = classifiers
+                .and_then(|classifiers| classifiers.for_command(command))
+                .map(ClassifiedOutput::new);
+        }
+        if let Some(classified) = &mut self.output {
+            classified.update(output, finished);
+        }
+    }
+
+    /// Forgets what was classified from `offset` on, after the output was
+    /// rewritten from there.
+    pub fn rewind(&mut self, offset: usize) {
+        if let Some(classified) = &mut self.output {
+            classified.rewind(offset);
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.output.is_some()
+    }
+}
+
+/// The error and warning counts and the fold toggle, for the block header.
+pub fn render_header(ui: &mut egui::Ui, folding: &mut OutputFolding) {
+    let Some(classified) = &folding.output else {
+        return;
+    };
+    if classified.errors() > 0 {
+        ui.colored_label(ERROR_COLOR, format!("✖ {}", classified.errors()))
+            .on_hover_text(format!("{} error lines", classified.errors()));
+    }
+    if classified.warnings() > 0 {
+        ui.colored_label(WARNING_COLOR, format!("⚠ {}", classified.warnings()))
+            .on_hover_text(format!("{} warning lines", classified.warnings()));
+    }
+    let hover = format!("Fold {}'s progress output and highlight its problems", classified.classifier_name());
+    if ui.selectable_label(folding.enabled, "Fold").on_hover_text(hover).clicked() {
+        folding.enabled = !folding.enabled;
+    }
+}
+
+enum Row {
+    Line(usize),
+    Fold(Range<usize>),
+}
+
+/// The rows of the folded view: lines, with each closed fold as one row.
+struct FoldedRows {
+    folds: Vec<Range<usize>>,
+    line_count: usize,
+}
+
+impl FoldedRows {
+    fn new(folding: &OutputFolding, classified: &ClassifiedOutput, line_count: usize) -> Self {
+        let folds = classified
+            .folds()
+            .filter(|fold| fold.end <= line_count && !folding.opened.contains(&fold.start))
+            .collect();
+        Self { folds, line_count }
+    }
+
+    fn len(&self) -> usize {
+        let folded: usize = self.folds.iter().map(|fold| fold.len() - 1).sum();
+        self.line_count - folded
+    }
+
+    fn row(&self, row: usize) -> Row {
+        let mut line = row;
+        for fold in &self.folds {
+            if line < fold.start {
+                break;
+            }
+            if line == fold.start {
+                return Row::Fold(fold.clone());
+            }
+            line += fold.len() - 1;
+        }
+        Row::Line(line)
+    }
+}
+
+/// Shows the output with noise folded, scrolling it like the pager when
+/// there are more rows than `threshold`.
+pub fn render_folded_output(ui: &mut egui::Ui, block: &mut TerminalBlock, threshold: usize) -> Option<LinkAction> {
+    let TerminalBlock {
+        id: block_id,
+        output,
+        links,
+        pager,
+        folding,
+        is_running,
+        ..
+    } = block;
+    let classified = folding.output.as_ref()?;
+    let line_count = pager.line_count(output);
+    let rows = FoldedRows::new(folding, classified, line_count);
+    let folds = classified.folds().count();
+
+    let mut open = None;
+    let mut close_all = false;
+    if folds > 0 {
+        ui.horizontal(|ui| {
+            ui.weak(format!("{} lines", line_count));
+            if !folding.opened.is_empty() && ui.small_button("Fold all").clicked() {
+                close_all = true;
+            }
+            if !rows.folds.is_empty() && ui.small_button("Unfold all").clicked() {
+                open = Some(rows.folds.iter().map(|fold| fold.start).collect::<Vec<_>>());
+            }
+        });
+    }
+
+    let mut action = None;
+    let mut render_row = |ui: &mut egui::Ui, row: Row| match row {
+        Row::Fold(fold) => {
+            let label = egui::RichText::new(format!("· {} progress lines", fold.len())).weak().italics();
+            if ui
+                .add(egui::Label::new(label).sense(egui::Sense::click()))
+                .on_hover_text("Show these lines")
+                .clicked()
+            {
+                open = Some(vec![fold.start]);
+            }
+        }
+        Row::Line(line) => {
+            let range = pager.line_range(output, line);
+            let line_links = links.starting_in(range.clone());
+            if !line_links.is_empty() {
+                ui.horizontal(|ui| {
+                    if let Some(clicked) = output_links::render_linked_line(ui, output, range, line_links) {
+                        action = Some(clicked);
+                    }
+                });
+                return;
+            }
+            let text = egui::RichText::new(output[range].trim_end_matches(['\n', '\r']));
+            let text = match classified.class(line) {
+                LineClass::Error => text.color(ERROR_COLOR),
+                LineClass::Warning => text.color(WARNING_COLOR),
+                LineClass::Noise => text.weak(),
+                LineClass::Normal => text,
+            };
+            ui.add(egui::Label::new(text).wrap(false));
+        }
+    };
+
+    if threshold == 0 || rows.len() <= threshold {
+        for row in 0..rows.len() {
+            render_row(ui, rows.row(row));
+        }
+    } else {
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        let spacing = ui.spacing().item_spacing.y;
+        egui::ScrollArea::both()
+            .id_source(("output_folding", *block_id))
+            .max_height((row_height + spacing) * PAGER_VISIBLE_LINES as f32)
+            .auto_shrink([false, true])
+            .stick_to_bottom(*is_running)
+            .show_rows(ui, row_height, rows.len(), |ui, visible| {
+                for row in visible {
+                    render_row(ui, rows.row(row));
+                }
+            });
+    }
+
+    if close_all {
+        folding.opened.clear();
+    }
+    if let Some(starts) = open {
+        folding.opened.extend(starts);
+    }
+    action
+}
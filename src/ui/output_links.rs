@@ -0,0 +1,278 @@
+use super::{AnTraftApp, TerminalBlock};
+use crate::ai::script;
+use crate::terminal::editor::{self, EditorIntegration};
+use crate::terminal::links::{self, Link, LinkTarget};
+use crate::terminal::{Block, Hyperlink};
+use eframe::egui;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Links in a block's output. Output is scanned a line at a time as it
+/// streams in, and each line only once.
+#[derive(Debug, Clone, Default)]
+pub struct OutputLinks {
+    /// Byte offset in the output up to which lines have been scanned
+    scanned: usize,
+    /// Ranges are relative to the whole output
+    links: Vec<Link>,
+    /// OSC 8 hyperlinks on lines not scanned yet
+    hyperlinks: Vec<Link>,
+}
+
+impl OutputLinks {
+    /// Adds the OSC 8 hyperlinks of output appended at `offset`.
+    pub fn add_hyperlinks(&mut self, offset: usize, hyperlinks: Vec<Hyperlink>) {
+        for hyperlink in hyperlinks {
+            let Some(target) = links::hyperlink_target(&hyperlink.uri) else {
+                continue;
+            };
+            let range = hyperlink.range.start + offset..hyperlink.range.end + offset;
+            // A link whose text arrived in several reads
+            match self.hyperlinks.last_mut() {
+                Some(last) if last.range.end == range.start && last.target == target => last.range.end = range.end,
+                _ => self.hyperlinks.push(Link { range, target }),
+            }
+        }
+    }
+
+    /// Forgets what was found from `offset` on, after the output was
+    /// rewritten from there.
+    pub fn rewind(&mut self, offset: usize) {
+        self.scanned = self.scanned.min(offset);
+        self.links.retain(|link| link.range.end <= offset);
+        self.hyperlinks.retain(|link| link.range.end <= offset);
+    }
+
+    /// Scans the complete lines appended since the last call, and the
+    /// trailing partial line once the command has `finished`.
+    pub fn update(&mut self, output: &str, working_directory: &str, finished: bool) {
+        if self.scanned > output.len() || !output.is_char_boundary(self.scanned) {
+            *self = Self::default();
+        }
+        let end = if finished {
+            output.len()
+        } else {
+            match output[self.scanned..].rfind('\n') {
+                Some(newline) => self.scanned + newline + 1,
+                None => return,
+            }
+        };
+        if end == self.scanned {
+            return;
+        }
+
+        let working_directory = if working_directory.is_empty() {
+            std::env::current_dir().unwrap_or_default()
+        } else {
+            PathBuf::from(working_directory)
+        };
+        let mut offset = self.scanned;
+        for line in output[self.scanned..end].split_inclusive('\n') {
+            let line_end = offset + line.len();
+            let hyperlinks: Vec<Link> = self
+                .hyperlinks
+                .extract_if(.., |link| link.range.start < line_end)
+                .collect();
+            // Explicit hyperlinks win over anything detected in their text
+            let detected: Vec<Link> = links::detect_links(line, &working_directory)
+                .into_iter()
+                .map(|link| Link {
+                    range: link.range.start + offset..link.range.end + offset,
+                    target: link.target,
+                })
+                .filter(|link| {
+                    !hyperlinks
+                        .iter()
+                        .any(|hyperlink| link.range.start < hyperlink.range.end && hyperlink.range.start < link.range.end)
+                })
+                .collect();
+            let first = self.links.len();
+            self.links.extend(detected.into_iter().chain(hyperlinks));
+            self.links[first..].sort_by_key(|link| link.range.start);
+            offset = line_end;
+        }
+        self.scanned = end;
+    }
+
+    /// The links that start within `range`.
+    pub fn starting_in(&self, range: Range<usize>) -> &[Link] {
+        let start = self.links.partition_point(|link| link.range.start < range.start);
+        let end = self.links.partition_point(|link| link.range.start < range.end);
+        &self.links[start..end]
+    }
+}
+
+pub enum LinkAction {
+    OpenUrl(String),
+    Reveal(PathBuf),
+    OpenInEditor {
+        path: PathBuf,
+        line: Option<u32>,
+        column: Option<u32>,
+    },
+    InsertIntoInput(PathBuf),
+}
+
+/// Shows `output` with its links clickable. Lines without links are drawn
+/// as plain text, the way output always was.
+pub fn render_output(ui: &mut egui::Ui, output: &str, links: &OutputLinks) -> Option<LinkAction> {
+    if links.links.is_empty() {
+        ui.label(output);
+        return None;
+    }
+
+    let mut action = None;
+    let mut plain_start = 0;
+    let mut next_link = 0;
+    let mut line_start = 0;
+    for line in output.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        let line_links = links.links[next_link..]
+            .iter()
+            .take_while(|link| link.range.start < line_end)
+            .count();
+
+        if line_links > 0 {
+            if plain_start < line_start {
+                ui.label(output[plain_start..line_start].trim_end_matches('\n'));
+            }
+            let line_links = &links.links[next_link..next_link + line_links];
+            ui.horizontal_wrapped(|ui| {
+                if let Some(clicked) = render_linked_line(ui, output, line_start..line_end, line_links) {
+                    action = Some(clicked);
+                }
+            });
+            next_link += line_links.len();
+            plain_start = line_end;
+        }
+        line_start = line_end;
+    }
+    if plain_start < output.len() {
+        ui.label(output[plain_start..].trim_end_matches('\n'));
+    }
+
+    action
+}
+
+/// Draws one line of output, `line` being its range, with `links` (those
+/// that start on it) clickable. Meant for a horizontal layout.
+pub fn render_linked_line(ui: &mut egui::Ui, output: &str, line: Range<usize>, links: &[Link]) -> Option<LinkAction> {
+    let mut action = None;
+    ui.spacing_mut().item_spacing.x = 0.0;
+    let mut position = line.start;
+    for link in links {
+        if position < link.range.start {
+            ui.label(&output[position..link.range.start]);
+        }
+        if let Some(clicked) = render_link(ui, &output[link.range.clone()], &link.target) {
+            action = Some(clicked);
+        }
+        position = link.range.end;
+    }
+    let rest = output[position..line.end].trim_end_matches('\n');
+    if !rest.is_empty() {
+        ui.label(rest);
+    }
+    action
+}
+
+fn render_link(ui: &mut egui::Ui, text: &str, target: &LinkTarget) -> Option<LinkAction> {
+    match target {
+        LinkTarget::Url(url) => ui
+            .link(text)
+            .on_hover_text(url)
+            .clicked()
+            .then(|| LinkAction::OpenUrl(url.clone())),
+        LinkTarget::File { path, line, column } => {
+            let mut action = None;
+            let label = egui::RichText::new(text)
+                .color(ui.visuals().hyperlink_color)
+                .underline();
+            ui.scope(|ui| {
+                ui.visuals_mut().button_frame = false;
+                ui.menu_button(label, |ui| {
+                    if ui.button("📂 Reveal in explorer").clicked() {
+                        action = Some(LinkAction::Reveal(path.clone()));
+                        ui.close_menu();
+                    }
+                    if ui.button("📝 Open in editor").clicked() {
+                        action = Some(LinkAction::OpenInEditor {
+                            path: path.clone(),
+                            line: *line,
+                            column: *column,
+                        });
+                        ui.close_menu();
+                    }
+                    if ui.button("⌨ Insert into input").clicked() {
+                        action = Some(LinkAction::InsertIntoInput(path.clone()));
+                        ui.close_menu();
+                    }
+                })
+                .response
+                .on_hover_text(path.display().to_string());
+            });
+            action
+        }
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn handle_link_action(&mut self, action: LinkAction) {
+        let result = match action {
+            LinkAction::OpenUrl(url) => open_with_system(&url),
+            LinkAction::Reveal(path) => reveal(&path),
+            LinkAction::OpenInEditor { path, line, column } => self.open_in_editor(&path, line, column),
+            LinkAction::InsertIntoInput(path) => {
+                let shell = script::script_shell(&self.config.terminal.shell);
+                let path = script::shell_quote(&path.to_string_lossy(), &shell);
+                if !self.command_input.is_empty() && !self.command_input.ends_with(' ') {
+                    self.command_input.push(' ');
+                }
+                self.command_input.push_str(&path);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            self.terminal_output.push(TerminalBlock::from_builtin(Block::error(e)));
+        }
+    }
+
+    /// Opens `path` in the configured editor; see `EditorIntegration`.
+    pub(super) fn open_in_editor(&self, path: &Path, line: Option<u32>, column: Option<u32>) -> Result<(), String> {
+        EditorIntegration::new(self.config.terminal.editor_command.clone())
+            .open(path, line, column)
+            .map_err(|e| format!("{:#}", e))
+    }
+}
+
+/// Opens a URL or file with the desktop's default handler.
+fn open_with_system(target: &str) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    spawn(command.arg(target))
+}
+
+/// Shows the file selected in the system file manager where that's
+/// supported, otherwise opens its folder.
+fn reveal(path: &Path) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        spawn(Command::new("open").arg("-R").arg(path))
+    } else if cfg!(windows) {
+        spawn(Command::new("explorer").arg(format!("/select,{}", path.display())))
+    } else {
+        let folder = path.parent().unwrap_or(path);
+        open_with_system(&folder.to_string_lossy())
+    }
+}
+
+fn spawn(command: &mut Command) -> Result<(), String> {
+    editor::spawn_detached(command).map_err(|e| format!("Failed to run {:?}: {}", command.get_program(), e))
+}
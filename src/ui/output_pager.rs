@@ -0,0 +1,254 @@
+use super::accessibility;
+use super::output_links::{self, LinkAction, OutputLinks};
+use eframe::egui;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// Lines of output the pager shows at a time.
+pub(super) const PAGER_VISIBLE_LINES: usize = 20;
+
+/// Line index and view state for a block's output. Output longer than
+/// `terminal.pager_threshold_lines` is shown in a fixed-height pager that only
+/// lays out the lines in view, instead of as one huge label.
+#[derive(Debug, Clone, Default)]
+pub struct OutputPager {
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    /// Byte offset in the output up to which lines have been indexed
+    indexed: usize,
+    /// Showing the whole output instead of the pager
+    expanded: bool,
+    query: String,
+    /// Lines that match `query`
+    hits: Vec<usize>,
+    /// Lines searched for `query` so far
+    searched_lines: usize,
+    /// Index into `hits` of the selected match
+    current_hit: usize,
+    /// Line to scroll to on the next frame
+    scroll_to: Option<usize>,
+}
+
+impl OutputPager {
+    /// Indexes the lines appended since the last call.
+    pub fn update(&mut self, output: &str) {
+        if self.indexed > output.len() || !output.is_char_boundary(self.indexed) || self.line_starts.is_empty() {
+            *self = Self {
+                line_starts: vec![0],
+                expanded: self.expanded,
+                ..Self::default()
+            };
+        }
+        for (offset, _) in output[self.indexed..].match_indices('\n') {
+            self.line_starts.push(self.indexed + offset + 1);
+        }
+        self.indexed = output.len();
+    }
+
+    /// Forgets the lines indexed from `offset` on, after the output was
+    /// rewritten from there.
+    pub fn rewind(&mut self, offset: usize) {
+        self.indexed = self.indexed.min(offset);
+        self.line_starts.retain(|&start| start <= offset);
+    }
+
+    /// Lines in the output, not counting the empty one after a final newline.
+    pub fn line_count(&self, output: &str) -> usize {
+        match self.line_starts.last() {
+            Some(&last) if last == output.len() => self.line_starts.len() - 1,
+            _ => self.line_starts.len(),
+        }
+    }
+
+    pub(super) fn line_range(&self, output: &str, line: usize) -> Range<usize> {
+        let start = self.line_starts[line];
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(output.len());
+        start..end
+    }
+
+    fn pattern(&self) -> Option<Regex> {
+        if self.query.is_empty() {
+            return None;
+        }
+        RegexBuilder::new(&regex::escape(&self.query))
+            .case_insensitive(true)
+            .build()
+            .ok()
+    }
+
+    /// Searches the lines added since the last search. The last line is
+    /// searched again, since it may have been incomplete.
+    fn update_hits(&mut self, output: &str, pattern: &Regex) {
+        let line_count = self.line_count(output);
+        let from = self.searched_lines.saturating_sub(1);
+        self.hits.retain(|&line| line < from);
+        for line in from..line_count {
+            if pattern.is_match(&output[self.line_range(output, line)]) {
+                self.hits.push(line);
+            }
+        }
+        self.searched_lines = line_count;
+    }
+
+    fn select_hit(&mut self, index: usize) {
+        if let Some(&line) = self.hits.get(index) {
+            self.current_hit = index;
+            self.scroll_to = Some(line);
+        }
+    }
+}
+
+/// Shows a block's output: the full text when it's short or expanded, the
+/// pager otherwise.
+pub fn render_block_output(
+    ui: &mut egui::Ui,
+    block_id: uuid::Uuid,
+    output: &str,
+    links: &OutputLinks,
+    pager: &mut OutputPager,
+    threshold: usize,
+    is_running: bool,
+) -> Option<LinkAction> {
+    let line_count = pager.line_count(output);
+    if threshold == 0 || line_count <= threshold {
+        return output_links::render_output(ui, output, links);
+    }
+    if pager.expanded {
+        ui.horizontal(|ui| {
+            ui.weak(format!("{} lines", line_count));
+            if ui.small_button("⤡ Collapse").clicked() {
+                pager.expanded = false;
+            }
+        });
+        return output_links::render_output(ui, output, links);
+    }
+
+    render_pager(ui, block_id, output, links, pager, line_count, is_running)
+}
+
+fn render_pager(
+    ui: &mut egui::Ui,
+    block_id: uuid::Uuid,
+    output: &str,
+    links: &OutputLinks,
+    pager: &mut OutputPager,
+    line_count: usize,
+    is_running: bool,
+) -> Option<LinkAction> {
+    let mut query_changed = false;
+    let mut step: Option<isize> = None;
+    ui.horizontal(|ui| {
+        ui.weak(format!("{} lines", line_count));
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut pager.query)
+                .id_source(("output_pager_query", block_id))
+                .desired_width(160.0)
+                .hint_text("🔍 Find in output"),
+        );
+        query_changed = response.changed();
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            step = Some(if ui.input(|i| i.modifiers.shift) { -1 } else { 1 });
+            response.request_focus();
+        }
+        if !pager.query.is_empty() {
+            if pager.hits.is_empty() {
+                ui.weak("No matches");
+            } else {
+                ui.weak(format!("{}/{}", pager.current_hit + 1, pager.hits.len()));
+            }
+            if accessibility::icon_button(ui, "⏶", "Previous match").clicked() {
+                step = Some(-1);
+            }
+            if accessibility::icon_button(ui, "⏷", "Next match").clicked() {
+                step = Some(1);
+            }
+        }
+        if ui.small_button("⤢ Expand").on_hover_text("Show the full output").clicked() {
+            pager.expanded = true;
+        }
+    });
+
+    let pattern = pager.pattern();
+    if query_changed {
+        pager.hits.clear();
+        pager.searched_lines = 0;
+        pager.current_hit = 0;
+    }
+    match &pattern {
+        Some(pattern) => pager.update_hits(output, pattern),
+        None => pager.hits.clear(),
+    }
+    if query_changed {
+        pager.select_hit(0);
+    }
+    if let (Some(step), false) = (step, pager.hits.is_empty()) {
+        let count = pager.hits.len() as isize;
+        let next = (pager.current_hit as isize + step).rem_euclid(count);
+        pager.select_hit(next as usize);
+    }
+    let current_line = pager.hits.get(pager.current_hit).copied();
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    let spacing = ui.spacing().item_spacing.y;
+    let mut scroll_area = egui::ScrollArea::both()
+        .id_source(("output_pager", block_id))
+        .max_height((row_height + spacing) * PAGER_VISIBLE_LINES as f32)
+        .auto_shrink([false, true])
+        // Follow the output as it streams in, unless searching
+        .stick_to_bottom(is_running && pager.query.is_empty());
+    if let Some(line) = pager.scroll_to.take() {
+        let centered = line.saturating_sub(PAGER_VISIBLE_LINES / 2);
+        scroll_area = scroll_area.vertical_scroll_offset(centered as f32 * (row_height + spacing));
+    }
+
+    let mut action = None;
+    scroll_area.show_rows(ui, row_height, line_count, |ui, rows| {
+        for line in rows {
+            let range = pager.line_range(output, line);
+            let text = output[range.clone()].trim_end_matches(['\n', '\r']);
+            match &pattern {
+                Some(pattern) if pager.hits.binary_search(&line).is_ok() => {
+                    let job = highlight_line(ui, text, pattern, current_line == Some(line));
+                    ui.add(egui::Label::new(job).wrap(false));
+                }
+                _ => {
+                    let line_links = links.starting_in(range.clone());
+                    if line_links.is_empty() {
+                        ui.add(egui::Label::new(text).wrap(false));
+                    } else {
+                        ui.horizontal(|ui| {
+                            if let Some(clicked) = output_links::render_linked_line(ui, output, range, line_links) {
+                                action = Some(clicked);
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    });
+    action
+}
+
+pub(super) fn highlight_line(ui: &egui::Ui, text: &str, pattern: &Regex, current: bool) -> egui::text::LayoutJob {
+    let font = egui::TextStyle::Body.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let highlighted = egui::TextFormat {
+        background: if current {
+            egui::Color32::from_rgb(200, 130, 20)
+        } else {
+            egui::Color32::from_rgb(110, 90, 20)
+        },
+        color: egui::Color32::WHITE,
+        ..egui::TextFormat::simple(font, ui.visuals().text_color())
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut position = 0;
+    for found in pattern.find_iter(text) {
+        job.append(&text[position..found.start()], 0.0, plain.clone());
+        job.append(found.as_str(), 0.0, highlighted.clone());
+        position = found.end();
+    }
+    job.append(&text[position..], 0.0, plain);
+    job
+}
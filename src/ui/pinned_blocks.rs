@@ -0,0 +1,168 @@
+use super::{accessibility, AnTraftApp};
+use crate::notifications::NotificationLevel;
+use crate::terminal::marks::MAX_PINNED_BLOCKS;
+use eframe::egui;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Lines of output previewed for each pinned block.
+const PREVIEW_LINES: usize = 3;
+
+/// How long a block jumped to stays highlighted.
+const FLASH_DURATION: Duration = Duration::from_millis(1200);
+
+/// The stripe down the left of pinned blocks.
+pub(super) const PIN_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 160, 60);
+
+enum PinAction {
+    Jump(Uuid),
+    Unpin(Uuid),
+    RemoveMark(char),
+}
+
+impl AnTraftApp {
+    /// Pins or unpins a block. Past `MAX_PINNED_BLOCKS`, the block pinned
+    /// longest ago is unpinned, with a toast saying so.
+    pub(super) fn set_block_pinned(&mut self, id: Uuid, pinned: bool) {
+        if !self.terminal_output.iter().any(|block| block.id == id) {
+            return;
+        }
+        let evicted = match pinned {
+            true => self.pins.pin(id),
+            false => {
+                self.pins.unpin(id);
+                None
+            }
+        };
+        for (id, pinned) in [(Some(id), pinned), (evicted, false)] {
+            let Some(block) = id.and_then(|id| self.terminal_output.iter_mut().find(|block| block.id == id)) else {
+                continue;
+            };
+            block.pinned = pinned;
+            self.session_recorder.set_pinned(block.id, pinned);
+        }
+        if let Some(block) = evicted.and_then(|id| self.terminal_output.iter().find(|block| block.id == id)) {
+            self.notifications.sender().send(
+                NotificationLevel::Info,
+                format!("Unpinned {}", block.command),
+                Some(format!("Up to {} blocks can be pinned; the oldest pin made room", MAX_PINNED_BLOCKS)),
+            );
+        }
+    }
+
+    /// Scrolls to a block and highlights it for a moment.
+    pub(super) fn jump_to_block(&mut self, id: Uuid) {
+        self.scroll_to_block = Some(id);
+        self.flash_block = Some((id, Instant::now()));
+    }
+
+    /// The block highlighted after a jump and how strongly, from 1 fading
+    /// to 0, or held at 1 when motion is reduced.
+    pub(super) fn flash(&mut self, ctx: &egui::Context) -> Option<(Uuid, f32)> {
+        let (id, since) = self.flash_block?;
+        let elapsed = since.elapsed();
+        if elapsed >= FLASH_DURATION {
+            self.flash_block = None;
+            return None;
+        }
+        if accessibility::reduced_motion(ctx) {
+            // Held still, then gone
+            ctx.request_repaint_after(FLASH_DURATION - elapsed);
+            return Some((id, 1.0));
+        }
+        ctx.request_repaint();
+        Some((id, 1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32()))
+    }
+
+    /// The Pinned and Marks dropdowns above the scrollback, shown once a
+    /// block is pinned or marked, and the keys of block navigation while
+    /// it's on.
+    pub(super) fn render_block_toolbar(&mut self, ui: &mut egui::Ui) {
+        let navigating = self.block_navigation.is_some();
+        if self.pins.is_empty() && self.quick_marks.is_empty() && !navigating {
+            return;
+        }
+
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if !self.pins.is_empty() {
+                ui.menu_button(format!("📌 Pinned ({})", self.pins.len()), |ui| {
+                    ui.set_min_width(320.0);
+                    egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                        for block in self.terminal_output.iter().filter(|block| block.pinned) {
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .link(&block.command)
+                                    .on_hover_text(format!("Jump to it\n{}", block.working_directory))
+                                    .clicked()
+                                {
+                                    action = Some(PinAction::Jump(block.id));
+                                    ui.close_menu();
+                                }
+                                ui.weak(block.timestamp.with_timezone(&chrono::Local).format("%H:%M:%S").to_string());
+                                if let Some(code) = block.exit_code.filter(|c| *c != 0) {
+                                    ui.colored_label(egui::Color32::from_rgb(220, 100, 100), format!("exit {}", code));
+                                }
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if accessibility::icon_button(ui, "✖", "Unpin").clicked() {
+                                        action = Some(PinAction::Unpin(block.id));
+                                    }
+                                    if !block.output.is_empty() && accessibility::icon_button(ui, "📋", "Copy output").clicked() {
+                                        ui.output_mut(|o| o.copied_text = block.output.clone());
+                                    }
+                                });
+                            });
+                            let preview: Vec<&str> = block.output.lines().take(PREVIEW_LINES).collect();
+                            if !preview.is_empty() {
+                                ui.weak(egui::RichText::new(preview.join("\n")).monospace());
+                            }
+                            ui.separator();
+                        }
+                    });
+                });
+            }
+            if !self.quick_marks.is_empty() {
+                ui.menu_button(format!("🔖 Marks ({})", self.quick_marks.iter().count()), |ui| {
+                    for (letter, id) in self.quick_marks.iter() {
+                        let Some(block) = self.terminal_output.iter().find(|block| block.id == id) else {
+                            continue;
+                        };
+                        ui.horizontal(|ui| {
+                            ui.monospace(format!("'{}", letter));
+                            if ui.link(&block.command).on_hover_text("Jump to it").clicked() {
+                                action = Some(PinAction::Jump(id));
+                                ui.close_menu();
+                            }
+                            if accessibility::icon_button(ui, "✖", "Remove the mark").clicked() {
+                                action = Some(PinAction::RemoveMark(letter));
+                            }
+                        });
+                    }
+                });
+            }
+            if let Some(navigation) = &self.block_navigation {
+                let hint = match navigation.keys.pending() {
+                    Some('m') => "Type a letter to mark the block with",
+                    Some(_) => "Type the letter of the mark to jump to",
+                    None => "↑↓ or j/k move · p pin · m+letter mark · '+letter jump · Esc done",
+                };
+                ui.weak(hint);
+            }
+        });
+        ui.separator();
+
+        match action {
+            Some(PinAction::Jump(id)) => {
+                if let Some(navigation) = &mut self.block_navigation {
+                    navigation.selected = id;
+                }
+                self.jump_to_block(id);
+            }
+            Some(PinAction::Unpin(id)) => self.set_block_pinned(id, false),
+            Some(PinAction::RemoveMark(letter)) => {
+                self.quick_marks.remove(letter);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::{accessibility, AnTraftApp, UIMode};
+use crate::terminal::pipeline::{Pipeline, StepStatus};
+use crate::terminal::{Block, TerminalEvent};
+use eframe::egui;
+use uuid::Uuid;
+
+const PASSED_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 200, 100);
+const FAILED_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 100, 100);
+
+/// The window queueing commands to run as a pipeline, one per line.
+pub struct PipelineDialog {
+    commands: String,
+    stop_on_error: bool,
+}
+
+/// The commands of a pipeline: one per non-blank line.
+fn pipeline_commands(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The header of a pipeline block.
+pub fn render_header(ui: &mut egui::Ui, pipeline: &Pipeline) {
+    ui.strong(format!("⛓ Pipeline of {} steps", pipeline.steps.len()));
+    if pipeline.stop_on_error {
+        ui.weak("stops at the first failure");
+    }
+}
+
+/// A pipeline's steps, each with its status; returns the block of the step
+/// that was clicked, to scroll to it.
+pub fn render_steps(ui: &mut egui::Ui, pipeline: &Pipeline) -> Option<Uuid> {
+    let mut clicked = None;
+    for (index, step) in pipeline.steps.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.weak(format!("{}.", index + 1));
+            match step.status {
+                StepStatus::Pending => {
+                    ui.weak("○");
+                }
+                StepStatus::Running => {
+                    accessibility::spinner(ui);
+                }
+                StepStatus::Finished(0) => {
+                    ui.colored_label(PASSED_COLOR, "✔");
+                }
+                StepStatus::Finished(code) => {
+                    ui.colored_label(FAILED_COLOR, format!("✘ exit {}", code));
+                }
+                StepStatus::Skipped => {
+                    ui.weak("⤼ skipped");
+                }
+            }
+            let text = egui::RichText::new(&step.command).monospace();
+            match step.block_id {
+                Some(block_id) => {
+                    let label = egui::Label::new(text).sense(egui::Sense::click());
+                    if ui.add(label).on_hover_text("Go to its output").clicked() {
+                        clicked = Some(block_id);
+                    }
+                }
+                None => {
+                    ui.label(text);
+                }
+            }
+        });
+    }
+    clicked
+}
+
+impl AnTraftApp {
+    /// Opens the pipeline window with the lines of the input as its steps.
+    pub(super) fn open_pipeline_dialog(&mut self) {
+        self.current_mode = UIMode::Terminal;
+        self.pipeline_dialog = Some(PipelineDialog {
+            commands: pipeline_commands(&self.command_input).join("\n"),
+            stop_on_error: true,
+        });
+    }
+
+    /// Runs `commands` in order as one pipeline block; see
+    /// `TerminalEngine::run_sequence`.
+    pub(super) fn run_pipeline(&mut self, commands: Vec<String>, stop_on_error: bool) {
+        let working_directory = self.current_directory();
+        for command in &commands {
+            self.command_history.push_front(command.clone());
+            self.record_history(command.clone(), working_directory.clone());
+        }
+
+        let engine = self.terminal_engine.clone();
+        let event_sender = self.terminal_event_tx.clone();
+        self.runtime_handle.spawn(async move {
+            if let Err(e) = engine.run_sequence(commands, stop_on_error).await {
+                let _ = event_sender.send(TerminalEvent::NewBlock {
+                    block: Block::error(e.to_string()),
+                });
+            }
+        });
+    }
+
+    pub(super) fn render_pipeline_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.pipeline_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut run = None;
+        egui::Window::new("⛓ Run as Pipeline")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label("One command per line, run in order in the current directory:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut dialog.commands)
+                        .code_editor()
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("npm ci\nnpm test\nnpm run build"),
+                );
+                ui.checkbox(&mut dialog.stop_on_error, "Stop at the first failing step");
+                let commands = pipeline_commands(&dialog.commands);
+                ui.horizontal(|ui| {
+                    let label = format!("▶ Run {} steps", commands.len());
+                    if ui.add_enabled(!commands.is_empty(), egui::Button::new(label)).clicked() {
+                        run = Some((commands, dialog.stop_on_error));
+                    }
+                });
+            });
+
+        if let Some((commands, stop_on_error)) = run {
+            self.pipeline_dialog = None;
+            self.run_pipeline(commands, stop_on_error);
+        } else if !open {
+            self.pipeline_dialog = None;
+        }
+    }
+}
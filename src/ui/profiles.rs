@@ -0,0 +1,84 @@
+//! The status bar's profile switcher: switching reloads the config with
+//! another `[profiles.<name>]` applied and hands the new settings to the AI
+//! agent, the terminal engine and the security scanners. Which profile a
+//! session starts with is still `--profile` or the config's `profile`.
+
+use super::AnTraftApp;
+use eframe::egui;
+
+impl AnTraftApp {
+    /// The active profile's name, and a menu to switch to another. Hidden
+    /// when the config has no profiles.
+    pub(super) fn render_profile_menu(&mut self, ui: &mut egui::Ui) {
+        if self.config.profiles.is_empty() {
+            return;
+        }
+        let active = self.config.active_profile.as_ref().map(|active| active.name.clone());
+        let label = match &active {
+            Some(name) => egui::RichText::new(format!("👤 {}", name)).strong(),
+            None => egui::RichText::new("👤 No profile").weak(),
+        };
+        let mut switch = None;
+        ui.menu_button(label, |ui| {
+            ui.strong("Profile");
+            if ui.selectable_label(active.is_none(), "No profile").clicked() {
+                switch = Some(None);
+                ui.close_menu();
+            }
+            for name in self.config.profile_names() {
+                let sections = self.config.profiles[&name].keys().cloned().collect::<Vec<_>>().join(", ");
+                if ui
+                    .selectable_label(active.as_ref() == Some(&name), &name)
+                    .on_hover_text(format!("Overrides the {} settings", sections))
+                    .clicked()
+                {
+                    switch = Some(Some(name));
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text("The active profile: settings from its [profiles.<name>] table override the rest of the config");
+
+        if let Some(name) = switch.filter(|name| *name != active) {
+            self.switch_profile(name);
+        }
+    }
+
+    /// Reloads the config with the profile `name`, or none, and applies it
+    /// to everything that reads the AI, security and terminal settings.
+    pub(super) fn switch_profile(&mut self, name: Option<String>) {
+        let config = match self.config.reload_with_profile(name.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                self.notifications
+                    .sender()
+                    .error("Couldn't switch profiles", format!("{:#}", e));
+                return;
+            }
+        };
+        if let Err(e) = config.validate() {
+            self.notifications
+                .sender()
+                .warning("The profile's settings have problems", format!("{:#}", e));
+        }
+
+        self.config = config;
+        let ai_agent = self.ai_agent.clone();
+        let terminal_engine = self.terminal_engine.clone();
+        let ai = self.config.ai.clone();
+        let terminal = self.config.terminal.clone();
+        self.runtime_handle.spawn(async move {
+            ai_agent.write().await.update_config(ai);
+            terminal_engine.update_config(terminal).await;
+        });
+        self.detect_security_scanners();
+        self.undo.set_limit(self.config.terminal.undo_limit);
+        self.help_ingestor.set_enabled(self.config.terminal.help_completions);
+
+        self.notifications.sender().info(match &name {
+            Some(name) => format!("Switched to the {} profile", name),
+            None => "Switched to no profile".to_string(),
+        });
+    }
+}
@@ -0,0 +1,150 @@
+use super::AnTraftApp;
+use crate::terminal::project::{self, ProjectFile, Trust, TrustStore};
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Startup commands and the `.antraft.toml` of the project the session is in.
+pub struct ProjectInit {
+    trust: TrustStore,
+    /// The project file last applied or asked about, `None` outside a project
+    current: Option<PathBuf>,
+    /// A project file waiting for the user to trust it or not
+    prompt: Option<ProjectFile>,
+}
+
+impl ProjectInit {
+    pub fn load() -> Self {
+        let path = project::default_trust_store_path();
+        let trust = TrustStore::load(&path).unwrap_or_else(|e| {
+            log::error!("Failed to load trusted projects: {}", e);
+            TrustStore::new(&path)
+        });
+        Self {
+            trust,
+            current: None,
+            prompt: None,
+        }
+    }
+}
+
+impl AnTraftApp {
+    /// Runs `terminal.startup_commands`, then enters the project in the
+    /// current directory, if any.
+    pub(super) fn start_session_init(&mut self) {
+        let dir = self.working_directory.clone();
+        let project = self.find_project(&dir);
+        self.project_init.current = project.as_ref().map(|file| file.path.clone());
+
+        let allowed = self.project_init.trust.allowed(project.as_ref());
+        let commands = project::init_commands(&self.config.terminal.startup_commands, allowed);
+        let env = allowed.map(|config| config.env.clone()).unwrap_or_default();
+        self.run_init(commands, env);
+
+        if let Some(file) = project.filter(|file| self.project_init.trust.check(file) == Trust::Unknown) {
+            self.project_init.prompt = Some(file);
+        }
+    }
+
+    /// Applies the project of the directory the session is now in, after a
+    /// `cd`. Moving around inside the same project does nothing.
+    pub(super) fn check_project(&mut self) {
+        let dir = self.working_directory.clone();
+        let project = self.find_project(&dir);
+        let path = project.as_ref().map(|file| file.path.clone());
+        if path == self.project_init.current {
+            return;
+        }
+        self.project_init.current = path;
+        self.project_init.prompt = None;
+
+        match project {
+            Some(file) => match self.project_init.trust.check(&file) {
+                Trust::Allowed => self.enter_project(&file),
+                Trust::Denied => self.run_init(Vec::new(), BTreeMap::new()),
+                Trust::Unknown => {
+                    self.run_init(Vec::new(), BTreeMap::new());
+                    self.project_init.prompt = Some(file);
+                }
+            },
+            None => self.run_init(Vec::new(), BTreeMap::new()),
+        }
+    }
+
+    fn find_project(&self, dir: &Path) -> Option<ProjectFile> {
+        ProjectFile::find(dir).unwrap_or_else(|e| {
+            log::error!("{:#}", e);
+            None
+        })
+    }
+
+    fn enter_project(&mut self, file: &ProjectFile) {
+        self.run_init(
+            project::init_commands(&[], Some(&file.config)),
+            file.config.env.clone(),
+        );
+    }
+
+    /// Sets the project environment, then runs `commands` in order.
+    fn run_init(&self, commands: Vec<String>, env: BTreeMap<String, String>) {
+        let engine = self.terminal_engine.clone();
+        self.runtime_handle.spawn(async move {
+            if let Err(e) = engine.set_project_env(env).await {
+                log::error!("Failed to set the project environment: {}", e);
+            }
+            engine.run_init_commands(commands).await;
+        });
+    }
+
+    /// Asks whether to trust a project file seen for the first time, or
+    /// changed since it was last trusted.
+    pub(super) fn render_project_trust(&mut self, ctx: &egui::Context) {
+        let Some(file) = &self.project_init.prompt else {
+            return;
+        };
+
+        let mut decision = None;
+        let mut dismiss = false;
+        egui::Window::new("Trust this project?")
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} wants to set environment variables and run commands in this session:",
+                    file.path.display()
+                ));
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut file.content.as_str())
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+                ui.small("Only allow files you trust. You'll be asked again if the file changes.");
+                ui.horizontal(|ui| {
+                    if ui.button("✔ Allow").clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button("✖ Deny").clicked() {
+                        decision = Some(false);
+                    }
+                    if ui.button("Not now").clicked() {
+                        dismiss = true;
+                    }
+                });
+            });
+
+        if let Some(allowed) = decision {
+            if let Some(file) = self.project_init.prompt.take() {
+                if let Err(e) = self.project_init.trust.decide(&file, allowed) {
+                    log::error!("Failed to save project trust: {:#}", e);
+                }
+                if allowed {
+                    self.enter_project(&file);
+                }
+            }
+        } else if dismiss {
+            self.project_init.prompt = None;
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use super::{accessibility, AnTraftApp};
+use crate::ai::PromptTemplate;
+use eframe::egui;
+use std::collections::HashMap;
+
+/// A template picked in the AI panel that still needs values for some of
+/// its variables.
+pub struct PendingTemplate {
+    pub template: PromptTemplate,
+    pub values: HashMap<String, String>,
+    pub missing: Vec<String>,
+}
+
+/// A template being created or edited in the settings panel.
+pub struct TemplateEditor {
+    /// Name of the template being edited, `None` for a new one
+    pub original_name: Option<String>,
+    pub template: PromptTemplate,
+    pub error: Option<String>,
+}
+
+impl AnTraftApp {
+    /// Templates matching the text after a leading `/` in the AI input, best
+    /// match first. `None` when the input isn't a template query.
+    pub(super) fn template_matches(&self) -> Option<Vec<PromptTemplate>> {
+        self.prompt_templates.search(&self.ai_input)
+    }
+
+    /// Values for the variables the app can fill in on its own.
+    fn template_context(&self) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        values.insert("cwd".to_string(), self.working_directory.to_string_lossy().to_string());
+        if let Some(block) = self.terminal_output.iter().rev().find(|b| !b.output.is_empty()) {
+            values.insert("last_output".to_string(), block.output.clone());
+        }
+        if let Some(selection) = self.file_preview.as_ref().and_then(|p| p.selection.as_ref()) {
+            values.insert("selection".to_string(), selection.text.clone());
+        }
+        // egui can't read the clipboard on demand, so {{clipboard}} is always
+        // asked for inline, where it can be pasted.
+        values
+    }
+
+    pub(super) fn apply_template(&mut self, template: PromptTemplate) {
+        self.ai_input.clear();
+        self.template_picker_index = 0;
+
+        let values = self.template_context();
+        let missing = template.missing_variables(&values);
+        if missing.is_empty() {
+            self.send_template(&template, &values);
+        } else {
+            self.pending_template = Some(PendingTemplate {
+                template,
+                values,
+                missing,
+            });
+        }
+    }
+
+    fn send_template(&mut self, template: &PromptTemplate, values: &HashMap<String, String>) {
+        match template.render(values) {
+            Ok(prompt) => {
+                self.ai_input = prompt;
+                self.send_ai_message();
+            }
+            Err(e) => log::error!("Failed to render template '{}': {}", template.name, e),
+        }
+    }
+
+    /// The `/` template list shown under the AI input.
+    pub(super) fn render_template_picker(&mut self, ui: &mut egui::Ui, matches: &[PromptTemplate]) {
+        let mut chosen = None;
+        ui.group(|ui| {
+            if matches.is_empty() {
+                ui.weak("No matching templates");
+            }
+            for (index, template) in matches.iter().enumerate() {
+                let selected = index == self.template_picker_index;
+                let response = ui
+                    .selectable_label(selected, format!("/{}", template.name))
+                    .on_hover_text(&template.body);
+                if response.clicked() {
+                    chosen = Some(template.clone());
+                }
+                if !template.description.is_empty() {
+                    ui.small(&template.description);
+                }
+            }
+        });
+
+        if let Some(template) = chosen {
+            self.apply_template(template);
+        }
+    }
+
+    /// Inline prompts for template variables that couldn't be filled in.
+    pub(super) fn render_pending_template(&mut self, ui: &mut egui::Ui) {
+        let Some(pending) = self.pending_template.as_mut() else {
+            return;
+        };
+
+        let mut send = false;
+        let mut cancel = false;
+        ui.group(|ui| {
+            ui.label(format!("/{} needs a few values:", pending.template.name));
+            for name in &pending.missing {
+                ui.label(name);
+                let value = pending.values.entry(name.clone()).or_default();
+                ui.add(
+                    egui::TextEdit::multiline(value)
+                        .desired_rows(2)
+                        .desired_width(f32::INFINITY),
+                );
+            }
+            ui.horizontal(|ui| {
+                let ready = pending.template.missing_variables(&pending.values).is_empty();
+                if ui.add_enabled(ready, egui::Button::new("Send")).clicked() {
+                    send = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+        if send {
+            if let Some(pending) = self.pending_template.take() {
+                self.send_template(&pending.template, &pending.values);
+            }
+        } else if cancel {
+            self.pending_template = None;
+        }
+    }
+
+    /// CRUD for prompt templates, shown in the settings window.
+    pub(super) fn render_template_settings(&mut self, ui: &mut egui::Ui) {
+        let mut edit = None;
+        let mut delete = None;
+
+        for template in self.prompt_templates.templates() {
+            ui.horizontal(|ui| {
+                ui.label(format!("/{}", template.name)).on_hover_text(&template.body);
+                if template.builtin {
+                    ui.weak("built-in");
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .add_enabled(!template.builtin, egui::Button::new("🗑").small())
+                        .on_hover_text("Delete")
+                        .clicked()
+                    {
+                        delete = Some(template.name.clone());
+                    }
+                    if accessibility::icon_button(ui, "✏", "Edit").clicked() {
+                        edit = Some(template.clone());
+                    }
+                });
+            });
+        }
+
+        if ui.button("➕ New template").clicked() {
+            self.template_editor = Some(TemplateEditor {
+                original_name: None,
+                template: PromptTemplate::new("", "", ""),
+                error: None,
+            });
+        }
+
+        if let Some(template) = edit {
+            self.template_editor = Some(TemplateEditor {
+                original_name: Some(template.name.clone()),
+                template,
+                error: None,
+            });
+        }
+        if let Some(name) = delete {
+            if let Err(e) = self.prompt_templates.delete(&name) {
+                log::error!("Failed to delete template: {}", e);
+            }
+        }
+
+        self.render_template_editor(ui);
+    }
+
+    fn render_template_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = self.template_editor.as_mut() else {
+            return;
+        };
+
+        let mut save = false;
+        let mut cancel = false;
+        ui.separator();
+        ui.group(|ui| {
+            egui::Grid::new("template_editor").num_columns(2).show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut editor.template.name);
+                ui.end_row();
+                ui.label("Description");
+                ui.text_edit_singleline(&mut editor.template.description);
+                ui.end_row();
+            });
+            ui.label("Prompt");
+            ui.add(
+                egui::TextEdit::multiline(&mut editor.template.body)
+                    .desired_rows(6)
+                    .desired_width(f32::INFINITY),
+            );
+            ui.small("Variables: {{selection}}, {{last_output}}, {{cwd}}, {{clipboard}} or any other name. Write \\{{ for a literal {{.");
+            if let Some(error) = &editor.error {
+                ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    save = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+        if cancel {
+            self.template_editor = None;
+        } else if save {
+            let template = editor.template.clone();
+            let renamed_from = editor
+                .original_name
+                .clone()
+                .filter(|original| *original != template.name);
+
+            match self.prompt_templates.save(template) {
+                Ok(()) => {
+                    if let Some(original) = renamed_from {
+                        // Renaming a built-in just adds a copy under the new name
+                        if self.prompt_templates.templates().iter().any(|t| t.name == original && !t.builtin) {
+                            if let Err(e) = self.prompt_templates.delete(&original) {
+                                log::error!("Failed to remove renamed template: {}", e);
+                            }
+                        }
+                    }
+                    self.template_editor = None;
+                }
+                Err(e) => {
+                    if let Some(editor) = self.template_editor.as_mut() {
+                        editor.error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+    }
+}
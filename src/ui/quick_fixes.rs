@@ -0,0 +1,141 @@
+use super::{AnTraftApp, TerminalBlock, UIMode};
+use crate::terminal::engine::BUILTIN_COMMANDS;
+use crate::terminal::quick_fix::{self, QuickFix, QuickFixKind, QuickFixSources};
+use crate::terminal::PanelLayout;
+use eframe::egui;
+
+/// Bytes read from the end of a failed command's output for the shell's
+/// not-found message.
+const DIAGNOSED_OUTPUT_BYTES: usize = 4096;
+
+/// Output lines sent with a request to the AI to fix a command.
+const AI_FIX_OUTPUT_LINES: usize = 40;
+
+/// Quick fixes found in the background for a failed block.
+#[derive(Debug, Clone)]
+pub struct BlockQuickFixes {
+    pub block_id: uuid::Uuid,
+    pub fixes: Vec<QuickFix>,
+}
+
+pub enum QuickFixAction {
+    /// Put a fixed command in the input
+    Insert(String),
+    AskAi(uuid::Uuid),
+}
+
+fn output_tail(output: &str, bytes: usize) -> &str {
+    let mut start = output.len().saturating_sub(bytes);
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    &output[start..]
+}
+
+impl AnTraftApp {
+    /// Looks for typo corrections and install commands for a block that
+    /// failed because a program or subcommand wasn't found. Works offline,
+    /// from history, autocomplete, builtins, aliases and the programs on
+    /// `PATH`.
+    pub(super) fn suggest_quick_fixes(&self, block_id: uuid::Uuid, exit_code: i32) {
+        let Some(block) = self.terminal_output.iter().rev().find(|b| b.id == block_id) else {
+            return;
+        };
+        let output = output_tail(&block.output, DIAGNOSED_OUTPUT_BYTES);
+        let Some(failure) = quick_fix::diagnose(&block.input, exit_code, output) else {
+            return;
+        };
+
+        let command = block.input.clone();
+        // Local programs and package managers say nothing about a remote host
+        let local = !self.remote.is_remote();
+        let autocomplete_engine = self.autocomplete_engine.clone();
+        let builtins: Vec<String> = BUILTIN_COMMANDS
+            .iter()
+            .map(|builtin| builtin.to_string())
+            .chain(self.config.terminal.aliases.keys().cloned())
+            .collect();
+        let history = self.history.clone();
+        let tx = self.quick_fix_tx.clone();
+        self.spawn_reported("Looking for quick fixes", async move {
+            let known_commands = autocomplete_engine.read().await.known_commands();
+            let history: Vec<String> = history
+                .read()
+                .await
+                .get_all_entries()
+                .iter()
+                .rev()
+                .filter(|entry| entry.is_success())
+                .map(|entry| entry.command.clone())
+                .collect();
+            let fixes = tokio::task::spawn_blocking(move || {
+                let sources = QuickFixSources {
+                    history,
+                    known_commands,
+                    builtins,
+                    programs: if local { quick_fix::path_programs() } else { Vec::new() },
+                    package_managers: if local { quick_fix::local_package_managers() } else { Vec::new() },
+                };
+                quick_fix::suggest(&command, &failure, &sources)
+            })
+            .await
+            .unwrap_or_default();
+            if !fixes.is_empty() {
+                let _ = tx.send(BlockQuickFixes { block_id, fixes });
+            }
+        });
+    }
+
+    pub(super) fn handle_quick_fixes(&mut self, update: BlockQuickFixes) {
+        if let Some(block) = self.terminal_output.iter_mut().find(|b| b.id == update.block_id) {
+            block.quick_fixes = update.fixes;
+        }
+    }
+
+    pub(super) fn handle_quick_fix_action(&mut self, action: QuickFixAction) {
+        match action {
+            QuickFixAction::Insert(command) => self.command_input = command,
+            QuickFixAction::AskAi(block_id) => self.ask_ai_to_fix(block_id),
+        }
+    }
+
+    /// Sends a failed block's command and the end of its output to the AI
+    /// chat, asking how to fix it.
+    fn ask_ai_to_fix(&mut self, block_id: uuid::Uuid) {
+        let Some(block) = self.terminal_output.iter().find(|b| b.id == block_id) else {
+            return;
+        };
+        let lines: Vec<&str> = block.output.lines().collect();
+        let output = lines[lines.len().saturating_sub(AI_FIX_OUTPUT_LINES)..].join("\n");
+        self.ai_input = format!(
+            "This command failed with exit code {}:\n\n```\n{}\n```\n\nOutput:\n\n```\n{}\n```\n\nHow do I fix it?",
+            block.exit_code.unwrap_or_default(),
+            block.input,
+            output.trim_end()
+        );
+        if self.config.terminal.layout != PanelLayout::Split {
+            self.current_mode = UIMode::AiAgent;
+        }
+        self.send_ai_message();
+    }
+}
+
+/// The suggestions under a failed block, as chips inserting the fixed
+/// command into the input, and a button asking the AI instead.
+pub fn render_quick_fixes(ui: &mut egui::Ui, block: &TerminalBlock) -> Option<QuickFixAction> {
+    let mut action = None;
+    ui.horizontal_wrapped(|ui| {
+        let correction = block.quick_fixes.iter().any(|fix| fix.kind == QuickFixKind::Correction);
+        ui.weak(if correction { "💡 Did you mean" } else { "💡 Not installed?" });
+        for fix in &block.quick_fixes {
+            let chip = egui::Button::new(egui::RichText::new(&fix.command).monospace()).rounding(10.0);
+            if ui.add(chip).on_hover_text(format!("{} Click to put it in the input", fix.description())).clicked() {
+                action = Some(QuickFixAction::Insert(fix.command.clone()));
+            }
+        }
+        if ui.small_button("🤖 Fix with AI").on_hover_text("Ask the AI what went wrong").clicked() {
+            action = Some(QuickFixAction::AskAi(block.id));
+        }
+    });
+    action
+}
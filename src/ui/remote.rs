@@ -0,0 +1,300 @@
+use super::{accessibility, AnTraftApp, UIMode};
+use crate::terminal::remote::{self, ReconnectBackoff, RemoteEntry, RemoteInfo, SshHost};
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// Choosing the host of a new remote session from `~/.ssh/config`, or
+/// typing one.
+struct HostPicker {
+    hosts: Vec<SshHost>,
+    filter: String,
+    focus: bool,
+}
+
+impl HostPicker {
+    fn matches(&self) -> Vec<&SshHost> {
+        let filter = self.filter.trim().to_lowercase();
+        self.hosts
+            .iter()
+            .filter(|host| {
+                host.alias.to_lowercase().contains(&filter)
+                    || host.hostname.as_ref().is_some_and(|name| name.to_lowercase().contains(&filter))
+            })
+            .collect()
+    }
+}
+
+/// A directory of the remote host shown in the file explorer, listed when
+/// asked for.
+struct RemoteListing {
+    directory: String,
+    entries: Vec<RemoteEntry>,
+    error: Option<String>,
+    response_rx: Option<crossbeam_channel::Receiver<Result<Vec<RemoteEntry>, String>>>,
+}
+
+/// The active session's remote host, as last reported by the engine, and
+/// the UI around it.
+#[derive(Default)]
+pub struct RemoteState {
+    pub info: Option<RemoteInfo>,
+    picker: Option<HostPicker>,
+    reconnect_rx: Option<crossbeam_channel::Receiver<Result<(), String>>>,
+    reconnect_error: Option<String>,
+    backoff: ReconnectBackoff,
+    listing: Option<RemoteListing>,
+}
+
+impl RemoteState {
+    pub fn set_info(&mut self, info: Option<RemoteInfo>) {
+        if info.as_ref().map(|info| &info.host) != self.info.as_ref().map(|info| &info.host) {
+            self.listing = None;
+            self.backoff.reset();
+        }
+        if info.as_ref().is_some_and(|info| info.connected) {
+            self.reconnect_error = None;
+            self.backoff.reset();
+        }
+        self.info = info;
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.info.is_some()
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn open_remote_picker(&mut self) {
+        self.remote.picker = Some(HostPicker {
+            hosts: remote::load_ssh_hosts(),
+            filter: String::new(),
+            focus: true,
+        });
+    }
+
+    /// Connects the session with the `ssh <host>` builtin, so it shows up
+    /// in the terminal and history like a typed one.
+    fn connect_remote(&mut self, target: &str) {
+        self.current_mode = UIMode::Terminal;
+        self.command_input = format!("ssh {}", target);
+        self.submit_command();
+    }
+
+    pub(super) fn render_remote_picker(&mut self, ctx: &egui::Context) {
+        let Some(picker) = self.remote.picker.as_mut() else {
+            return;
+        };
+
+        let connected_to = self.remote.info.as_ref().map(RemoteInfo::label);
+        let mut target = None;
+        let mut open = true;
+        egui::Window::new("🌐 New Remote Session")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if let Some(label) = &connected_to {
+                    ui.label(format!("Connected to {}. Run `exit` to leave it first.", label));
+                    return;
+                }
+
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut picker.filter)
+                        .hint_text("Host from ~/.ssh/config, or user@host")
+                        .desired_width(f32::INFINITY),
+                );
+                if std::mem::take(&mut picker.focus) {
+                    response.request_focus();
+                }
+                let matches = picker.matches();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    target = match matches.first() {
+                        Some(host) => Some(host.alias.clone()),
+                        None => Some(picker.filter.trim().to_string()).filter(|filter| !filter.is_empty()),
+                    };
+                }
+
+                egui::ScrollArea::vertical().max_height(260.0).show(ui, |ui| {
+                    for host in &matches {
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(false, &host.alias).clicked() {
+                                target = Some(host.alias.clone());
+                            }
+                            if let Some(destination) = host.destination() {
+                                ui.weak(destination);
+                            }
+                        });
+                    }
+                    if picker.hosts.is_empty() {
+                        ui.weak("No hosts in ~/.ssh/config");
+                    }
+                });
+
+                let typed = picker.filter.trim();
+                if !typed.is_empty()
+                    && !matches.iter().any(|host| host.alias == typed)
+                    && ui.button(format!("Connect to {}", typed)).clicked()
+                {
+                    target = Some(typed.to_string());
+                }
+                ui.small("Commands run over ssh without a terminal: the host must accept your key or agent.");
+            });
+
+        if let Some(target) = target {
+            self.remote.picker = None;
+            self.connect_remote(&target);
+        } else if !open {
+            self.remote.picker = None;
+        }
+    }
+
+    /// Retries the session's host after it was lost, backing off while it
+    /// stays unreachable, and offers to reconnect right away.
+    pub(super) fn render_remote_banner(&mut self, ui: &mut egui::Ui) {
+        let now = Instant::now();
+        if let Some(rx) = &self.remote.reconnect_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.remote.reconnect_rx = None;
+                if let Err(e) = result {
+                    self.remote.backoff.failed(now);
+                    self.remote.reconnect_error = Some(e);
+                }
+            } else {
+                ui.ctx().request_repaint_after(Duration::from_millis(200));
+            }
+        }
+
+        let Some(info) = self.remote.info.as_ref().filter(|info| !info.connected) else {
+            return;
+        };
+        let mut reconnect = self.remote.reconnect_rx.is_none() && self.remote.backoff.is_due(now);
+        let retry_in = self.remote.backoff.remaining(now);
+        if let Some(remaining) = retry_in {
+            ui.ctx().request_repaint_after(remaining);
+        }
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                egui::Color32::from_rgb(220, 100, 100),
+                format!("⚠ Lost the connection to {}", info.label()),
+            );
+            if self.remote.reconnect_rx.is_some() {
+                accessibility::spinner(ui);
+            } else if ui.button("⟳ Reconnect").clicked() {
+                reconnect = true;
+            }
+            if let Some(error) = &self.remote.reconnect_error {
+                ui.weak(error);
+            }
+            if let Some(remaining) = retry_in.filter(|_| self.remote.reconnect_rx.is_none()) {
+                ui.weak(format!("Retrying in {:.0}s", remaining.as_secs_f32().ceil()));
+            }
+        });
+        ui.separator();
+
+        if reconnect {
+            let (tx, rx) = crossbeam_channel::bounded(1);
+            self.remote.reconnect_rx = Some(rx);
+            let engine = self.terminal_engine.clone();
+            self.runtime_handle.spawn(async move {
+                let result = engine.reconnect_remote().await.map(|_| ()).map_err(|e| e.to_string());
+                let _ = tx.send(result);
+            });
+        }
+    }
+
+    /// The host in the status bar, while the session is remote.
+    pub(super) fn render_remote_indicator(&mut self, ui: &mut egui::Ui) {
+        let Some(info) = &self.remote.info else {
+            return;
+        };
+        let (text, color) = match info.connected {
+            true => (format!("🌐 {}", info.label()), egui::Color32::from_rgb(100, 180, 220)),
+            false => (format!("🌐 {} (disconnected)", info.label()), egui::Color32::from_rgb(220, 100, 100)),
+        };
+        ui.colored_label(color, text)
+            .on_hover_text(format!("Commands run in {}. Run `exit` to come back.", info.directory));
+    }
+
+    /// The remote directory at the top of the file explorer, listed with
+    /// `ls` over ssh when asked for.
+    pub(super) fn render_remote_files(&mut self, ui: &mut egui::Ui) {
+        let Some(info) = self.remote.info.clone() else {
+            return;
+        };
+
+        let mut list = None;
+        if let Some(listing) = self.remote.listing.as_mut() {
+            if let Some(Ok(result)) = listing.response_rx.as_ref().map(|rx| rx.try_recv()) {
+                listing.response_rx = None;
+                match result {
+                    Ok(entries) => listing.entries = entries,
+                    Err(e) => listing.error = Some(e),
+                }
+            }
+        }
+
+        egui::CollapsingHeader::new(format!("🌐 {}", info.label()))
+            .id_source("remote_files")
+            .default_open(true)
+            .show(ui, |ui| {
+                let Some(listing) = &self.remote.listing else {
+                    if ui.button("List remote files").clicked() {
+                        list = Some(info.directory.clone());
+                    }
+                    return;
+                };
+
+                ui.horizontal(|ui| {
+                    if accessibility::icon_button(ui, "⬆", "Parent directory").clicked() {
+                        list = Some(parent_directory(&listing.directory));
+                    }
+                    if accessibility::icon_button(ui, "⟳", "Refresh").clicked() {
+                        list = Some(listing.directory.clone());
+                    }
+                    ui.weak(&listing.directory);
+                });
+                if listing.response_rx.is_some() {
+                    accessibility::spinner(ui);
+                    ui.ctx().request_repaint_after(Duration::from_millis(200));
+                } else if let Some(error) = &listing.error {
+                    ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+                } else {
+                    for entry in &listing.entries {
+                        if entry.is_directory {
+                            if ui.selectable_label(false, format!("📁 {}", entry.name)).clicked() {
+                                list = Some(format!("{}/{}", listing.directory.trim_end_matches('/'), entry.name));
+                            }
+                        } else {
+                            ui.label(format!("📄 {}", entry.name));
+                        }
+                    }
+                }
+            });
+        ui.separator();
+
+        if let Some(directory) = list {
+            self.list_remote_directory(info, directory);
+        }
+    }
+
+    fn list_remote_directory(&mut self, info: RemoteInfo, directory: String) {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.remote.listing = Some(RemoteListing {
+            directory: directory.clone(),
+            entries: Vec::new(),
+            error: None,
+            response_rx: Some(rx),
+        });
+        self.runtime_handle.spawn(async move {
+            let result = info.list_directory(&directory).await.map_err(|e| e.to_string());
+            let _ = tx.send(result);
+        });
+    }
+}
+
+fn parent_directory(directory: &str) -> String {
+    match directory.trim_end_matches('/').rsplit_once('/') {
+        Some(("", _)) | None => "/".to_string(),
+        Some((parent, _)) => parent.to_string(),
+    }
+}
@@ -0,0 +1,181 @@
+use super::{accessibility, AnTraftApp};
+use crate::ai::script::{self, ScriptCommand};
+use crate::ai::AiRequest;
+use eframe::egui;
+use std::path::PathBuf;
+
+/// A script ready for preview, with any notes about it.
+struct GeneratedScript {
+    script: Option<String>,
+    notes: Option<String>,
+}
+
+pub struct ScriptPreview {
+    pub shell: String,
+    pub script: Option<String>,
+    pub notes: Option<String>,
+    pub save_path: String,
+    pub status: Option<String>,
+    response_rx: Option<crossbeam_channel::Receiver<Result<GeneratedScript, String>>>,
+}
+
+impl ScriptPreview {
+    fn poll(&mut self) {
+        let Some(rx) = &self.response_rx else {
+            return;
+        };
+        let Ok(result) = rx.try_recv() else {
+            return;
+        };
+        self.response_rx = None;
+
+        match result {
+            Ok(generated) => {
+                self.script = generated.script;
+                self.notes = generated.notes;
+            }
+            Err(e) => self.status = Some(format!("Script generation failed: {}", e)),
+        }
+    }
+
+    fn is_generating(&self) -> bool {
+        self.response_rx.is_some()
+    }
+}
+
+impl AnTraftApp {
+    /// Commands of the selected blocks, in the order they were run.
+    fn selected_script_commands(&self) -> Vec<ScriptCommand> {
+        self.terminal_output
+            .iter()
+            .filter(|block| self.selected_blocks.contains(&block.id) && !block.command.is_empty())
+            .map(|block| ScriptCommand::new(block.command.clone(), block.working_directory.clone()))
+            .collect()
+    }
+
+    /// Opens an empty preview whose script arrives on the returned sender.
+    fn open_script_preview(
+        &mut self,
+        file_stem: &str,
+    ) -> (String, crossbeam_channel::Sender<Result<GeneratedScript, String>>) {
+        let shell = script::script_shell(&self.config.terminal.shell);
+        let save_path = self
+            .working_directory
+            .join(format!("{}.{}", file_stem, script::script_extension(&shell)));
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.script_preview = Some(ScriptPreview {
+            shell: shell.clone(),
+            script: None,
+            notes: None,
+            save_path: save_path.to_string_lossy().to_string(),
+            status: None,
+            response_rx: Some(rx),
+        });
+        (shell, tx)
+    }
+
+    pub(super) fn generate_script_from_selection(&mut self) {
+        let commands = self.selected_script_commands();
+        if commands.is_empty() {
+            return;
+        }
+
+        let (shell, tx) = self.open_script_preview("script");
+        let ai_agent = self.ai_agent.clone();
+        self.spawn_reported("Generating the script", async move {
+            let result = ai_agent
+                .read()
+                .await
+                .process_request(AiRequest::GenerateScript { commands, shell })
+                .await
+                .map(|response| GeneratedScript {
+                    script: response.code_snippets.into_iter().next().map(|snippet| snippet.code),
+                    notes: (!response.content.is_empty()).then_some(response.content),
+                })
+                .map_err(|e| e.to_string());
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Exports the session's successful commands, as run, without the AI.
+    pub(super) fn export_session_script(&mut self) {
+        let (shell, tx) = self.open_script_preview("session");
+        let terminal_engine = self.terminal_engine.clone();
+        self.runtime_handle.spawn(async move {
+            let result = match terminal_engine.get_active_session().await {
+                Some(session) => Ok(GeneratedScript {
+                    script: Some(session.export_as_script(&shell)),
+                    notes: Some("Successful commands from this session, in the order they ran.".to_string()),
+                }),
+                None => Err("No commands have been run in this session yet".to_string()),
+            };
+            let _ = tx.send(result);
+        });
+    }
+
+    pub(super) fn render_script_preview(&mut self, ctx: &egui::Context) {
+        let Some(preview) = self.script_preview.as_mut() else {
+            return;
+        };
+        preview.poll();
+        if preview.is_generating() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+
+        let mut open = true;
+        egui::Window::new("📜 Generated Script")
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                let Some(script) = preview.script.as_mut() else {
+                    if preview.is_generating() {
+                        ui.horizontal(|ui| {
+                            accessibility::spinner(ui);
+                            ui.label("Generating script…");
+                        });
+                    }
+                    if let Some(status) = &preview.status {
+                        ui.colored_label(egui::Color32::from_rgb(220, 100, 100), status);
+                    }
+                    return;
+                };
+
+                if let Some(notes) = &preview.notes {
+                    ui.small(notes);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Save as…");
+                    ui.add(egui::TextEdit::singleline(&mut preview.save_path).desired_width(320.0));
+                    if ui.button("💾 Save").clicked() {
+                        let path = PathBuf::from(preview.save_path.trim());
+                        preview.status = Some(match script::write_script(&path, script, &preview.shell) {
+                            Ok(()) => format!("Saved {}", path.display()),
+                            Err(e) => format!("Save failed: {}", e),
+                        });
+                    }
+                    if ui.button("📋 Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = script.clone());
+                        preview.status = Some("Copied to clipboard".to_string());
+                    }
+                });
+                if let Some(status) = &preview.status {
+                    ui.small(status);
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(script)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+            });
+
+        if !open {
+            self.script_preview = None;
+        }
+    }
+}
@@ -0,0 +1,678 @@
+use super::dependency_inventory::InventoryView;
+use super::{accessibility, output_compare, AnTraftApp, UIMode};
+use crate::terminal::PanelLayout;
+use crate::security::fixes::{self, FixAction};
+use crate::security::findings_db::{self, FindingStatus, FindingsStore, ScanChanges, TrackedFinding};
+use crate::security::rule_catalog::RuleCatalog;
+use crate::security::{ScanProgress, ScanTarget, ScanType, SecurityConfig, SecurityReport, Severity, Vulnerability};
+use crate::terminal::audit::CommandOrigin;
+use crate::terminal::diff::OutputDiff;
+use eframe::egui;
+use std::collections::HashMap;
+
+/// The findings of the last security scan, with their fixes offered, and
+/// the dependency inventory.
+#[derive(Default)]
+pub struct SecurityFindings {
+    report: Option<SecurityReport>,
+    report_rx: Option<crossbeam_channel::Receiver<SecurityReport>>,
+    progress_rx: Option<crossbeam_channel::Receiver<ScanProgress>>,
+    /// The findings of the scan in progress so far
+    streamed: Vec<Vulnerability>,
+    /// The chunks each scanner of the scan in progress has done, and has
+    streamed_chunks: Vec<(String, usize, usize)>,
+    open: bool,
+    tab: SecurityTab,
+    pending_fix: Option<PendingFix>,
+    /// The container image to scan, as typed
+    image: String,
+    focus_image: bool,
+    /// The risk score of each target's last scan this session, for the
+    /// trend when findings aren't tracked
+    risk_scores: HashMap<String, u32>,
+    /// The findings database, opened with the first report
+    store: Option<FindingsStore>,
+    store_error: Option<String>,
+    /// The report's findings' fingerprints, by index
+    fingerprints: Vec<String>,
+    /// The tracked findings of the report's target, by fingerprint
+    tracked: HashMap<String, TrackedFinding>,
+    /// What the report changed in the database
+    changes: Option<ScanChanges>,
+    /// Only the findings with this status
+    status_filter: Option<FindingStatus>,
+    /// Assignees and notes being edited, by fingerprint
+    drafts: HashMap<String, (String, String)>,
+    /// Report findings silenced by ignore comments in the next scans too
+    pub(super) include_ignored: bool,
+    pub(super) inventory: InventoryView,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SecurityTab {
+    #[default]
+    Findings,
+    Inventory,
+}
+
+impl SecurityFindings {
+    /// Waits for the report of a scan started now, and for its findings as
+    /// they come before that.
+    pub fn expect_report(
+        &mut self,
+    ) -> (crossbeam_channel::Sender<SecurityReport>, crossbeam_channel::Sender<ScanProgress>) {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let (progress_tx, progress_rx) = crossbeam_channel::unbounded();
+        self.report_rx = Some(rx);
+        self.progress_rx = Some(progress_rx);
+        self.streamed.clear();
+        self.streamed_chunks.clear();
+        (tx, progress_tx)
+    }
+
+    /// Takes in the findings the scan in progress has sent, opening the
+    /// panel on the first ones.
+    fn poll_progress(&mut self) {
+        let Some(rx) = &self.progress_rx else {
+            return;
+        };
+        let had_findings = !self.streamed.is_empty();
+        for progress in rx.try_iter() {
+            match self.streamed_chunks.iter_mut().find(|(scanner, _, _)| *scanner == progress.scanner) {
+                Some(chunks) => chunks.1 = chunks.1.max(progress.done),
+                None => self.streamed_chunks.push((progress.scanner, progress.done, progress.total)),
+            }
+            self.streamed.extend(progress.findings);
+        }
+        if !had_findings && !self.streamed.is_empty() {
+            self.open = true;
+            self.tab = SecurityTab::Findings;
+        }
+    }
+
+    /// The findings database, opened on first use.
+    fn store(&mut self, config: &SecurityConfig) -> Option<&mut FindingsStore> {
+        if self.store.is_none() && self.store_error.is_none() {
+            match FindingsStore::open(&config.findings_db_path()) {
+                Ok(store) => self.store = Some(store),
+                Err(e) => {
+                    log::warn!("{:#}", e);
+                    self.store_error = Some(format!("Findings aren't tracked: {:#}", e));
+                }
+            }
+        }
+        self.store.as_mut()
+    }
+
+    /// Records `report` in the findings database, comparing its risk score
+    /// with the last scan of its target, and loads the target's findings.
+    fn track(&mut self, config: &SecurityConfig, report: &mut SecurityReport) {
+        let Some(store) = self.store(config) else {
+            return;
+        };
+        let recorded = store.last_risk_score(&report.target_name()).and_then(|previous| {
+            if let Some(score) = previous {
+                report.set_previous_risk_score(score);
+            }
+            store.record_scan(report)
+        });
+        match recorded {
+            Ok(changes) => self.changes = Some(changes),
+            Err(e) => self.store_error = Some(format!("Couldn't record the scan: {:#}", e)),
+        }
+        self.reload_tracked(&report.target_name());
+    }
+
+    fn reload_tracked(&mut self, target: &str) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        match store.findings(target) {
+            Ok(findings) => {
+                self.tracked = findings.into_iter().map(|finding| (finding.fingerprint.clone(), finding)).collect();
+            }
+            Err(e) => self.store_error = Some(format!("Couldn't load the tracked findings: {:#}", e)),
+        }
+    }
+
+    /// Sets a tracked finding's status, or its assignee and note.
+    fn update_tracked(&mut self, fingerprint: &str, status: Option<FindingStatus>, assignment: Option<(String, String)>) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let updated = match (status, assignment) {
+            (Some(status), _) => store.set_status(fingerprint, status),
+            (None, Some((assignee, note))) => store.set_assignment(fingerprint, &assignee, &note),
+            (None, None) => return,
+        };
+        if let Err(e) = updated {
+            self.store_error = Some(format!("{:#}", e));
+        }
+        if let Some(target) = self.tracked.get(fingerprint).map(|finding| finding.target.clone()) {
+            self.reload_tracked(&target);
+        }
+    }
+}
+
+/// A fix waiting for the user to confirm it.
+struct PendingFix {
+    /// The finding, by index in the report
+    index: usize,
+    title: String,
+    action: FixAction,
+    /// For patches, what they change
+    diff: Option<Result<OutputDiff, String>>,
+}
+
+/// The catalog's explanation of a finding's rule, with its CWE and
+/// documentation links; for unknown rules, a button asking the AI instead.
+/// True when that was clicked.
+fn render_rule_details(ui: &mut egui::Ui, vulnerability: &Vulnerability) -> bool {
+    let details = RuleCatalog::builtin().details(vulnerability);
+    match &details.info {
+        Some(info) => {
+            ui.strong(&info.title);
+            ui.label(&info.explanation);
+            if let (Some(cwe), Some(url)) = (&info.cwe, info.cwe_url()) {
+                ui.hyperlink_to(cwe, url);
+            }
+        }
+        None => {
+            ui.weak(format!("No explanation of {} is bundled.", vulnerability.id));
+        }
+    }
+    for link in &details.links {
+        ui.hyperlink(link);
+    }
+    details.info.is_none() && ui.small_button("🤖 Ask AI to explain this rule").clicked()
+}
+
+/// How far the scan in progress has got, and what it's found so far, which
+/// stands in for the last report once there's something. True when it does.
+fn render_scan_progress(ui: &mut egui::Ui, findings: &SecurityFindings) -> bool {
+    ui.horizontal_wrapped(|ui| {
+        accessibility::spinner(ui);
+        let chunks: Vec<String> = findings
+            .streamed_chunks
+            .iter()
+            .filter(|(_, _, total)| *total > 1)
+            .map(|(scanner, done, total)| format!("{} {}/{}", scanner, done, total))
+            .collect();
+        ui.label(match chunks.is_empty() {
+            true => "Scanning…".to_string(),
+            false => format!("Scanning… {}", chunks.join(" · ")),
+        });
+        ui.weak(format!(
+            "{} finding{} so far",
+            findings.streamed.len(),
+            if findings.streamed.len() == 1 { "" } else { "s" }
+        ));
+    });
+    if findings.streamed.is_empty() {
+        return false;
+    }
+    ui.separator();
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for vulnerability in &findings.streamed {
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(severity_color(&vulnerability.severity), format!("{:?}", vulnerability.severity));
+                ui.label(&vulnerability.title);
+                let location = match vulnerability.line_number {
+                    Some(line) => format!("{}:{}", vulnerability.file_path, line),
+                    None => vulnerability.file_path.clone(),
+                };
+                ui.weak(format!("{} · {}", location, vulnerability.scanner));
+            });
+        }
+    });
+    true
+}
+
+pub(super) fn severity_color(severity: &Severity) -> egui::Color32 {
+    match severity {
+        Severity::Critical => egui::Color32::from_rgb(230, 80, 80),
+        Severity::High => egui::Color32::from_rgb(230, 140, 70),
+        Severity::Medium => egui::Color32::from_rgb(220, 200, 90),
+        Severity::Low => egui::Color32::from_rgb(120, 170, 220),
+        Severity::Info => egui::Color32::GRAY,
+    }
+}
+
+impl AnTraftApp {
+    /// Collects the report of a finished scan, opening the findings when
+    /// there are any.
+    pub(super) fn poll_security_report(&mut self) {
+        let findings = &mut self.security_findings;
+        findings.poll_progress();
+        let mut report = match findings.report_rx.as_ref().map(|rx| rx.try_recv()) {
+            Some(Ok(report)) => report,
+            // The scan failed, and said so in a notification
+            Some(Err(crossbeam_channel::TryRecvError::Disconnected)) => {
+                findings.report_rx = None;
+                findings.progress_rx = None;
+                return;
+            }
+            _ => return,
+        };
+        findings.report_rx = None;
+        findings.progress_rx = None;
+        findings.streamed.clear();
+        findings.streamed_chunks.clear();
+        findings.changes = None;
+        findings.tracked.clear();
+        findings.drafts.clear();
+        if self.config.security.track_findings {
+            findings.track(&self.config.security, &mut report);
+        } else if let Some(previous) = findings.risk_scores.get(&report.target_name()) {
+            report.set_previous_risk_score(*previous);
+        }
+        findings.risk_scores.insert(report.target_name(), report.summary.risk_score());
+        findings.fingerprints = report.vulnerabilities.iter().map(|v| findings_db::fingerprint(&report, v)).collect();
+        findings.open = !report.vulnerabilities.is_empty();
+        findings.tab = SecurityTab::Findings;
+        findings.pending_fix = None;
+        findings.report = Some(report);
+    }
+
+    /// Opens the security panel with the image field focused.
+    pub(super) fn open_image_scan(&mut self) {
+        let findings = &mut self.security_findings;
+        findings.open = true;
+        findings.tab = SecurityTab::Findings;
+        findings.focus_image = true;
+    }
+
+    /// Opens the security panel on the dependency inventory.
+    pub(super) fn open_dependency_inventory(&mut self) {
+        let findings = &mut self.security_findings;
+        findings.open = true;
+        findings.tab = SecurityTab::Inventory;
+    }
+
+    pub(super) fn render_security_findings(&mut self, ctx: &egui::Context) {
+        let mut open = self.security_findings.open;
+        egui::Window::new("🛡 Security findings")
+            .open(&mut open)
+            .default_width(560.0)
+            .default_height(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let tab = &mut self.security_findings.tab;
+                    ui.selectable_value(tab, SecurityTab::Findings, "Findings");
+                    ui.selectable_value(tab, SecurityTab::Inventory, "📦 Inventory");
+                });
+                ui.separator();
+                match self.security_findings.tab {
+                    SecurityTab::Findings => self.render_security_panel(ui),
+                    SecurityTab::Inventory => self.render_inventory_tab(ui),
+                }
+            });
+        self.security_findings.open = open;
+        self.render_fix_confirmation(ctx);
+    }
+
+    /// A field for a container image to scan with trivy.
+    fn render_image_scan(&mut self, ui: &mut egui::Ui) {
+        let findings = &mut self.security_findings;
+        let scanning = findings.report_rx.is_some();
+        let mut scan = false;
+        ui.horizontal(|ui| {
+            ui.label("🐳 Image");
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut findings.image)
+                    .hint_text("nginx:1.25")
+                    .desired_width(220.0),
+            );
+            if std::mem::take(&mut findings.focus_image) {
+                field.request_focus();
+            }
+            let submitted = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let ready = !scanning && !findings.image.trim().is_empty();
+            scan = ui
+                .add_enabled(ready, egui::Button::new("Scan image"))
+                .on_hover_text("Scans the image's OS and language packages with trivy")
+                .clicked()
+                || (submitted && ready);
+            if scanning {
+                accessibility::spinner(ui);
+            }
+        });
+        ui.separator();
+        if scan {
+            let image = self.security_findings.image.trim().to_string();
+            self.start_scan(ScanTarget::ContainerImage(image), ScanType::Full);
+        }
+    }
+
+    /// The last scan's findings, each with a "Fix…" button when there's a
+    /// command or patch for it, and what its rule is about.
+    pub fn render_security_panel(&mut self, ui: &mut egui::Ui) {
+        self.render_image_scan(ui);
+        ui.checkbox(&mut self.security_findings.include_ignored, "Include findings silenced by ignore comments")
+            .on_hover_text("Report findings with `# nosec`, `// nosemgrep` or `# antraft-ignore: <rule-id>` on their line in the next scans");
+        let findings = &mut self.security_findings;
+        if findings.report_rx.is_some() && render_scan_progress(ui, findings) {
+            return;
+        }
+        let Some(report) = &findings.report else {
+            ui.label("Run a security scan from the command palette to see findings here.");
+            return;
+        };
+        ui.label(format!(
+            "{} · {} finding{} · risk {}",
+            report.target_name(),
+            report.vulnerabilities.len(),
+            if report.vulnerabilities.len() == 1 { "" } else { "s" },
+            report.summary.risk_level()
+        ));
+        if let Some(trend) = report.summary.risk_trend() {
+            ui.weak(format!("Risk score {} since the last scan", trend));
+        }
+        if let Some(changes) = &findings.changes {
+            ui.weak(format!(
+                "{} new · {} reopened · {} fixed since the last scan",
+                changes.new.len(),
+                changes.reopened.len(),
+                changes.fixed.len()
+            ));
+        }
+        if !report.summary.by_category.is_empty() {
+            let categories: Vec<String> = report
+                .summary
+                .categories()
+                .iter()
+                .map(|(category, count)| format!("{} {}", category, count))
+                .collect();
+            ui.weak(categories.join(" · "));
+        }
+        if !report.summary.top_files.is_empty() {
+            let files: Vec<String> =
+                report.summary.top_files.iter().map(|(file, count)| format!("{} ({})", file, count)).collect();
+            ui.weak(format!("Most findings: {}", files.join(", ")));
+        }
+        for error in report.scanner_errors.iter().chain(&report.ignore_comment_warnings).chain(&findings.store_error) {
+            ui.colored_label(egui::Color32::from_rgb(220, 160, 80), format!("⚠ {}", error));
+        }
+        if !findings.tracked.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Show");
+                ui.selectable_value(&mut findings.status_filter, None, "all");
+                for status in FindingStatus::ALL {
+                    ui.selectable_value(&mut findings.status_filter, Some(status), status.as_str());
+                }
+            });
+        }
+        ui.separator();
+
+        let mut fix = None;
+        let mut explain = None;
+        let mut status_change = None;
+        let mut assignment = None;
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            for (index, vulnerability) in report.vulnerabilities.iter().enumerate() {
+                let tracked = findings.fingerprints.get(index).and_then(|fingerprint| findings.tracked.get(fingerprint));
+                let status = tracked.map_or(FindingStatus::Open, |tracked| tracked.status);
+                if findings.status_filter.is_some_and(|filter| filter != status) {
+                    continue;
+                }
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        severity_color(&vulnerability.severity),
+                        format!("{:?}", vulnerability.severity),
+                    );
+                    ui.strong(&vulnerability.title);
+                    let new = tracked.zip(findings.changes.as_ref());
+                    if new.is_some_and(|(tracked, changes)| changes.new.contains(&tracked.fingerprint)) {
+                        ui.small("🆕");
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if vulnerability.remediation.is_some() && ui.small_button("🔧 Fix…").clicked() {
+                            fix = Some(index);
+                        }
+                        let Some(tracked) = tracked else {
+                            return;
+                        };
+                        egui::ComboBox::from_id_source(("status", index))
+                            .selected_text(status.as_str())
+                            .show_ui(ui, |ui| {
+                                for option in FindingStatus::ALL {
+                                    if ui.selectable_label(option == status, option.as_str()).clicked() && option != status {
+                                        status_change = Some((tracked.fingerprint.clone(), option));
+                                    }
+                                }
+                            });
+                    });
+                });
+                let location = match vulnerability.line_number {
+                    Some(line) => format!("{}:{}", vulnerability.file_path, line),
+                    None => vulnerability.file_path.clone(),
+                };
+                ui.weak(format!("{} · {} · {}", location, vulnerability.scanner, vulnerability.id));
+                if let Some(suggested_fix) = &vulnerability.suggested_fix {
+                    ui.small(suggested_fix);
+                }
+                if !vulnerability.id.is_empty() {
+                    egui::CollapsingHeader::new("About this rule")
+                        .id_source(("rule", index))
+                        .show(ui, |ui| {
+                            if render_rule_details(ui, vulnerability) {
+                                explain = Some(index);
+                            }
+                        });
+                }
+                if let Some(tracked) = tracked {
+                    let assigned: Vec<&str> = tracked.assignee.iter().chain(&tracked.note).map(String::as_str).collect();
+                    if !assigned.is_empty() {
+                        ui.small(format!("👤 {}", assigned.join(" · ")));
+                    }
+                    egui::CollapsingHeader::new("Assignee and note")
+                        .id_source(("assignment", index))
+                        .show(ui, |ui| {
+                            let (assignee, note) = findings.drafts.entry(tracked.fingerprint.clone()).or_insert_with(|| {
+                                (tracked.assignee.clone().unwrap_or_default(), tracked.note.clone().unwrap_or_default())
+                            });
+                            ui.add(egui::TextEdit::singleline(assignee).hint_text("Assignee"));
+                            ui.add(egui::TextEdit::multiline(note).hint_text("Note").desired_rows(2));
+                            if ui.small_button("Save").clicked() {
+                                assignment = Some((tracked.fingerprint.clone(), assignee.clone(), note.clone()));
+                            }
+                        });
+                }
+                ui.separator();
+            }
+            if findings.status_filter == Some(FindingStatus::Fixed) {
+                let mut fixed: Vec<_> = findings
+                    .tracked
+                    .values()
+                    .filter(|tracked| tracked.status == FindingStatus::Fixed && !findings.fingerprints.contains(&tracked.fingerprint))
+                    .collect();
+                fixed.sort_by_key(|tracked| std::cmp::Reverse(tracked.fixed_at));
+                for tracked in fixed {
+                    ui.horizontal(|ui| {
+                        ui.weak(&tracked.severity);
+                        ui.label(&tracked.title);
+                    });
+                    let line = tracked.line.map(|line| format!(":{}", line)).unwrap_or_default();
+                    let fixed_at = tracked.fixed_at.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+                    ui.weak(format!("{}{} · {} · fixed {}", tracked.file_path, line, tracked.rule_id, fixed_at));
+                    ui.separator();
+                }
+            }
+            if !report.suppressed.is_empty() {
+                egui::CollapsingHeader::new(format!("🔕 {} silenced by ignore comments", report.suppressed.len()))
+                    .id_source("suppressed_findings")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        for vulnerability in &report.suppressed {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(
+                                    severity_color(&vulnerability.severity),
+                                    format!("{:?}", vulnerability.severity),
+                                );
+                                ui.label(&vulnerability.title);
+                            });
+                            let Some(suppression) = &vulnerability.suppression else {
+                                continue;
+                            };
+                            ui.weak(format!(
+                                "{}:{} · {} · `{}`",
+                                vulnerability.file_path,
+                                vulnerability.line_number.unwrap_or_default(),
+                                vulnerability.id,
+                                suppression.comment
+                            ));
+                            if let Some(reason) = &suppression.reason {
+                                ui.small(reason);
+                            }
+                        }
+                    });
+            }
+        });
+
+        if let Some((fingerprint, status)) = status_change {
+            self.security_findings.update_tracked(&fingerprint, Some(status), None);
+        }
+        if let Some((fingerprint, assignee, note)) = assignment {
+            self.security_findings.drafts.remove(&fingerprint);
+            self.security_findings.update_tracked(&fingerprint, None, Some((assignee, note)));
+        }
+        if let Some(index) = fix {
+            self.prepare_fix(index);
+        }
+        if let Some(index) = explain {
+            self.ask_ai_to_explain_rule(index);
+        }
+    }
+
+    /// Sends a finding's rule id and the code it flagged to the AI chat,
+    /// for rules the catalog doesn't know.
+    fn ask_ai_to_explain_rule(&mut self, index: usize) {
+        let Some(vulnerability) = self.security_findings.report.as_ref().and_then(|r| r.vulnerabilities.get(index)) else {
+            return;
+        };
+        let mut prompt = format!(
+            "The {} security scanner reported rule `{}` (\"{}\") in {}.",
+            vulnerability.scanner, vulnerability.id, vulnerability.title, vulnerability.file_path
+        );
+        if let Some(snippet) = vulnerability.code_snippet.as_deref().filter(|snippet| !snippet.trim().is_empty()) {
+            prompt.push_str(&format!(" It flagged this code:\n\n```\n{}\n```\n", snippet.trim_end()));
+        }
+        prompt.push_str("\nWhat does this rule check for, why is it a risk, and how do I fix it?");
+        self.ai_input = prompt;
+        if self.config.terminal.layout != PanelLayout::Split {
+            self.current_mode = UIMode::AiAgent;
+        }
+        self.send_ai_message();
+    }
+
+    fn prepare_fix(&mut self, index: usize) {
+        let Some(report) = &self.security_findings.report else {
+            return;
+        };
+        let vulnerability = &report.vulnerabilities[index];
+        match fixes::fix_action(vulnerability, &report.path) {
+            Ok(Some(action)) => {
+                let diff = match &action {
+                    FixAction::Patch(patch) => Some(patch.diff().map_err(|e| format!("{:#}", e))),
+                    FixAction::Command { .. } => None,
+                };
+                self.security_findings.pending_fix = Some(PendingFix {
+                    index,
+                    title: vulnerability.title.clone(),
+                    action,
+                    diff,
+                });
+            }
+            Ok(None) => self
+                .notifications
+                .sender()
+                .info(format!("There's no command to upgrade {}", vulnerability.file_path)),
+            Err(e) => self.notifications.sender().error("Couldn't prepare the fix", format!("{:#}", e)),
+        }
+    }
+
+    /// Shows the command or diff of the fix being applied, and runs or
+    /// applies it once confirmed.
+    fn render_fix_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &self.security_findings.pending_fix else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("Apply fix?")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(520.0)
+            .default_height(360.0)
+            .show(ctx, |ui| {
+                ui.strong(&pending.title);
+                // Above the diff, which takes the rest of the window
+                ui.horizontal(|ui| {
+                    let label = match pending.action {
+                        FixAction::Command { .. } => "▶ Run",
+                        FixAction::Patch(_) => "✔ Apply",
+                    };
+                    confirmed = ui.button(label).clicked();
+                    cancelled = ui.button("Cancel").clicked();
+                });
+                ui.separator();
+                match &pending.action {
+                    FixAction::Command { command, directory } => {
+                        ui.label(format!("Runs in {}:", directory.display()));
+                        ui.add(egui::Label::new(egui::RichText::new(command).monospace()).wrap(true));
+                    }
+                    FixAction::Patch(patch) => {
+                        ui.label(format!("Changes {}:", patch.path.display()));
+                        match &pending.diff {
+                            Some(Ok(diff)) => output_compare::render_diff(ui, diff),
+                            Some(Err(e)) => {
+                                ui.colored_label(egui::Color32::from_rgb(220, 100, 100), e);
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            });
+
+        if !open || cancelled {
+            self.security_findings.pending_fix = None;
+        } else if confirmed {
+            if let Some(pending) = self.security_findings.pending_fix.take() {
+                self.apply_fix(pending);
+            }
+        }
+    }
+
+    fn apply_fix(&mut self, pending: PendingFix) {
+        match pending.action {
+            FixAction::Command { command, directory } => {
+                self.current_mode = UIMode::Terminal;
+                let directory = directory.to_string_lossy().to_string();
+                self.run_command(command, CommandOrigin::User, Some(directory));
+            }
+            FixAction::Patch(patch) => match patch.apply() {
+                Ok(()) => {
+                    if let Some(report) = &mut self.security_findings.report {
+                        if pending.index < report.vulnerabilities.len() {
+                            let fixed = report.vulnerabilities.remove(pending.index);
+                            if pending.index < self.security_findings.fingerprints.len() {
+                                self.security_findings.fingerprints.remove(pending.index);
+                            }
+                            // Other fixes in the file point at offsets that moved
+                            for vulnerability in &mut report.vulnerabilities {
+                                if vulnerability.file_path == fixed.file_path {
+                                    vulnerability.remediation = None;
+                                }
+                            }
+                        }
+                    }
+                    self.notifications
+                        .sender()
+                        .success("Fix applied", patch.path.display().to_string());
+                }
+                Err(e) => self.notifications.sender().error("Couldn't apply the fix", format!("{:#}", e)),
+            },
+        }
+    }
+}
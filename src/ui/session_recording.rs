@@ -0,0 +1,491 @@
+use super::ai_stream::{self, INJECTION_ROLE, TOOL_ROLE};
+use super::{accessibility, apply_terminal_event, output_pager, AnTraftApp, TerminalBlock};
+use crate::ai::redaction::{RedactionConfig, Redactor};
+use crate::terminal::recording::{SessionEvent, SessionRecording};
+use crate::terminal::TerminalEvent;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::time::Instant;
+
+const REPLAY_SPEEDS: &[f64] = &[1.0, 2.0, 4.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    /// Everything recorded, AI chat included; the only format that can be
+    /// replayed later
+    Timeline,
+    Asciicast,
+    Transcript,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Timeline => "json",
+            ExportFormat::Asciicast => "cast",
+            ExportFormat::Transcript => "txt",
+        }
+    }
+}
+
+/// "Start recording": captures the engine's events and the AI chat while
+/// active, then offers export and replay of what was recorded. Saved
+/// timelines can be opened for replay too.
+pub struct SessionRecorder {
+    active: Option<SessionRecording>,
+    /// The last stopped recording
+    finished: Option<Arc<SessionRecording>>,
+    window_open: bool,
+    format: ExportFormat,
+    path: String,
+    redact: bool,
+    /// Ok for a success message, Err for a failure
+    status: Option<Result<String, String>>,
+    replay: Option<Replay>,
+    /// The "Replay recording…" dialog's path, while it's open
+    open_path: Option<String>,
+    open_error: Option<String>,
+}
+
+impl Default for SessionRecorder {
+    fn default() -> Self {
+        Self {
+            active: None,
+            finished: None,
+            window_open: false,
+            format: ExportFormat::Timeline,
+            path: String::new(),
+            redact: true,
+            status: None,
+            replay: None,
+            open_path: None,
+            open_error: None,
+        }
+    }
+}
+
+impl SessionRecorder {
+    pub fn record(&mut self, event: &TerminalEvent) {
+        if let Some(recording) = &mut self.active {
+            recording.record(event);
+        }
+    }
+
+    pub fn record_ai_message(&mut self, role: &str, content: &str) {
+        if let Some(recording) = &mut self.active {
+            recording.record_ai_message(role, content);
+        }
+    }
+
+    pub fn set_pinned(&mut self, id: uuid::Uuid, pinned: bool) {
+        if let Some(recording) = &mut self.active {
+            recording.set_pinned(id, pinned);
+        }
+    }
+
+    pub fn set_mark(&mut self, letter: char, id: uuid::Uuid) {
+        if let Some(recording) = &mut self.active {
+            recording.set_mark(letter, id);
+        }
+    }
+}
+
+/// What the replay shows, in the order it happened.
+enum ReplayEntry {
+    /// Index into `Replay::blocks`
+    Block(usize),
+    AiMessage { role: String, content: String },
+}
+
+/// Plays a recording back by applying its events to a separate set of
+/// blocks, on the original schedule. AI messages show between the blocks.
+struct Replay {
+    recording: Arc<SessionRecording>,
+    blocks: Vec<TerminalBlock>,
+    entries: Vec<ReplayEntry>,
+    next_event: usize,
+    /// Seconds into the recording
+    position: f64,
+    speed: f64,
+    playing: bool,
+    last_tick: Option<Instant>,
+}
+
+impl Replay {
+    fn new(recording: Arc<SessionRecording>) -> Self {
+        Self {
+            recording,
+            blocks: Vec::new(),
+            entries: Vec::new(),
+            next_event: 0,
+            position: 0.0,
+            speed: 1.0,
+            playing: true,
+            last_tick: None,
+        }
+    }
+
+    fn restart(&mut self) {
+        self.blocks.clear();
+        self.entries.clear();
+        self.next_event = 0;
+        self.position = 0.0;
+        self.playing = true;
+        self.last_tick = None;
+    }
+
+    fn advance(&mut self) {
+        let now = Instant::now();
+        if self.playing {
+            if let Some(last_tick) = self.last_tick {
+                self.position += now.duration_since(last_tick).as_secs_f64() * self.speed;
+            }
+        }
+        self.last_tick = Some(now);
+
+        while let Some(recorded) = self.recording.events.get(self.next_event) {
+            if recorded.time > self.position {
+                break;
+            }
+            match &recorded.event {
+                SessionEvent::Terminal(event) => {
+                    let blocks = self.blocks.len();
+                    apply_terminal_event(&mut self.blocks, event.clone());
+                    self.entries.extend((blocks..self.blocks.len()).map(ReplayEntry::Block));
+                }
+                SessionEvent::AiMessage { role, content } => self.entries.push(ReplayEntry::AiMessage {
+                    role: role.clone(),
+                    content: content.clone(),
+                }),
+            }
+            self.next_event += 1;
+        }
+        let duration = self.recording.elapsed();
+        if self.position >= duration {
+            self.position = duration;
+            self.playing = false;
+        }
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn start_session_recording(&mut self) {
+        if self.session_recorder.active.is_none() {
+            self.session_recorder.active = Some(SessionRecording::start(&self.config.terminal.shell));
+        }
+    }
+
+    /// Stops recording and opens the export window.
+    pub(super) fn stop_session_recording(&mut self) {
+        let recorder = &mut self.session_recorder;
+        let Some(mut recording) = recorder.active.take() else {
+            self.notifications.sender().info("No session is being recorded");
+            return;
+        };
+        recording.stop();
+        recorder.path = default_export_path(&self.working_directory, &recording, recorder.format);
+        recorder.finished = Some(Arc::new(recording));
+        recorder.window_open = true;
+        recorder.status = None;
+    }
+
+    pub(super) fn open_recording_dialog(&mut self) {
+        self.session_recorder.open_path = Some(String::new());
+        self.session_recorder.open_error = None;
+    }
+
+    /// "● REC 0:42" in the mode panel while recording; click to stop.
+    pub(super) fn render_recording_indicator(&mut self, ui: &mut egui::Ui) {
+        let Some(recording) = &self.session_recorder.active else {
+            return;
+        };
+        let elapsed = recording.elapsed() as u64;
+        let label = egui::RichText::new(format!("● REC {}:{:02}", elapsed / 60, elapsed % 60))
+            .color(egui::Color32::from_rgb(230, 80, 80));
+        if ui.selectable_label(false, label).on_hover_text("Stop recording").clicked() {
+            self.stop_session_recording();
+        }
+        ui.ctx().request_repaint_after(std::time::Duration::from_millis(500));
+    }
+
+    pub(super) fn render_session_recording(&mut self, ctx: &egui::Context) {
+        self.render_recording_window(ctx);
+        self.render_open_recording(ctx);
+        self.render_replay(ctx);
+    }
+
+    fn render_open_recording(&mut self, ctx: &egui::Context) {
+        let recorder = &mut self.session_recorder;
+        let Some(path) = recorder.open_path.as_mut() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut submitted = false;
+        egui::Window::new("📂 Replay Recording")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label("A session timeline (.json) exported from a recording:");
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(path)
+                            .hint_text("session-20240101-120000.json")
+                            .desired_width(300.0),
+                    );
+                    let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    let clicked = ui.add_enabled(!path.trim().is_empty(), egui::Button::new("▶ Replay")).clicked();
+                    submitted = (entered || clicked) && !path.trim().is_empty();
+                });
+                if let Some(error) = &recorder.open_error {
+                    ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+                }
+            });
+
+        if submitted {
+            let path = PathBuf::from(path.trim());
+            let recording = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))
+                .and_then(|json| SessionRecording::from_timeline(&json).map_err(|e| format!("{:#}", e)));
+            match recording {
+                Ok(recording) => {
+                    recorder.replay = Some(Replay::new(Arc::new(recording)));
+                    recorder.open_path = None;
+                }
+                Err(e) => recorder.open_error = Some(e),
+            }
+        } else if !open {
+            recorder.open_path = None;
+        }
+    }
+
+    fn render_recording_window(&mut self, ctx: &egui::Context) {
+        let recorder = &mut self.session_recorder;
+        let Some(recording) = recorder.finished.clone() else {
+            return;
+        };
+        if !recorder.window_open {
+            return;
+        }
+
+        let mut open = true;
+        let mut export = false;
+        let mut replay = false;
+        egui::Window::new("⏺ Session Recording")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let commands = recording
+                    .events
+                    .iter()
+                    .filter(|recorded| {
+                        matches!(
+                            recorded.terminal_event(),
+                            Some(TerminalEvent::CommandStarted { .. } | TerminalEvent::NewBlock { .. })
+                        )
+                    })
+                    .count();
+                ui.label(format!(
+                    "{} commands, {:.1}s, recorded {}",
+                    commands,
+                    recording.elapsed(),
+                    recording.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+                ));
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Format");
+                    let before = recorder.format;
+                    ui.radio_value(&mut recorder.format, ExportFormat::Timeline, "Timeline (.json)")
+                        .on_hover_text("Commands, output and AI chat with their timing; can be replayed later");
+                    ui.radio_value(&mut recorder.format, ExportFormat::Asciicast, "asciinema (.cast)");
+                    ui.radio_value(&mut recorder.format, ExportFormat::Transcript, "Transcript (.txt)");
+                    if recorder.format != before {
+                        recorder.path = PathBuf::from(&recorder.path)
+                            .with_extension(recorder.format.extension())
+                            .to_string_lossy()
+                            .to_string();
+                    }
+                });
+                ui.checkbox(&mut recorder.redact, "Redact secrets")
+                    .on_hover_text("Replace API keys, tokens and passwords with placeholders, using the AI redaction patterns");
+                ui.horizontal(|ui| {
+                    ui.label("File");
+                    ui.add(egui::TextEdit::singleline(&mut recorder.path).desired_width(300.0));
+                    if ui
+                        .add_enabled(!recorder.path.trim().is_empty(), egui::Button::new("Export"))
+                        .clicked()
+                    {
+                        export = true;
+                    }
+                });
+                match &recorder.status {
+                    Some(Ok(message)) => {
+                        ui.small(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(egui::Color32::from_rgb(220, 100, 100), message);
+                    }
+                    None => {}
+                }
+                ui.separator();
+                if ui.button("▶ Replay").clicked() {
+                    replay = true;
+                }
+            });
+
+        if export {
+            let redactor = recorder.redact.then(|| {
+                let config = RedactionConfig {
+                    enabled: true,
+                    ..self.config.ai.redaction.clone()
+                };
+                Redactor::new(&config, Arc::new(AtomicUsize::new(0)))
+            });
+            let content = match recorder.format {
+                ExportFormat::Timeline => recording.to_timeline(redactor.as_ref()).map_err(|e| e.to_string()),
+                ExportFormat::Asciicast => Ok(recording.to_asciicast(redactor.as_ref())),
+                ExportFormat::Transcript => Ok(recording.to_transcript(redactor.as_ref())),
+            };
+            let path = PathBuf::from(recorder.path.trim());
+            recorder.status = Some(content.and_then(|content| {
+                std::fs::write(&path, content)
+                    .map(|_| format!("Exported to {}", path.display()))
+                    .map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+            }));
+        }
+        if replay {
+            recorder.replay = Some(Replay::new(recording));
+        }
+        if !open {
+            recorder.window_open = false;
+        }
+    }
+
+    fn render_replay(&mut self, ctx: &egui::Context) {
+        let pager_threshold = self.config.terminal.pager_threshold_lines;
+        let Some(replay) = self.session_recorder.replay.as_mut() else {
+            return;
+        };
+        replay.advance();
+        if replay.playing {
+            ctx.request_repaint_after(std::time::Duration::from_millis(30));
+        }
+        for block in &mut replay.blocks {
+            block.links.update(&block.output, &block.working_directory, !block.is_running);
+            block.pager.update(&block.output);
+        }
+
+        let mut open = true;
+        let mut link_action = None;
+        egui::Window::new("▶ Replay")
+            .open(&mut open)
+            .default_size([640.0, 420.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let finished = replay.next_event == replay.recording.events.len() && !replay.playing;
+                    if finished {
+                        if ui.button("⟲ Replay").clicked() {
+                            replay.restart();
+                        }
+                    } else if ui.button(if replay.playing { "⏸" } else { "▶" }).clicked() {
+                        replay.playing = !replay.playing;
+                    }
+                    if accessibility::icon_button(ui, "⏮", "Restart").clicked() {
+                        replay.restart();
+                    }
+                    for speed in REPLAY_SPEEDS {
+                        ui.selectable_value(&mut replay.speed, *speed, format!("{}x", speed));
+                    }
+                    ui.add(
+                        egui::ProgressBar::new((replay.position / replay.recording.elapsed().max(0.001)) as f32)
+                            .desired_width(160.0)
+                            .text(format!("{:.1}s / {:.1}s", replay.position, replay.recording.elapsed())),
+                    );
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                    for (index, entry) in replay.entries.iter().enumerate() {
+                        let block = match entry {
+                            ReplayEntry::Block(block) => &mut replay.blocks[*block],
+                            ReplayEntry::AiMessage { role, content } if role == TOOL_ROLE => {
+                                ai_stream::render_tool_step(ui, index, content);
+                                continue;
+                            }
+                            ReplayEntry::AiMessage { role, content } if role == INJECTION_ROLE => {
+                                ai_stream::render_injection_warning(ui, egui::Color32::from_rgb(230, 180, 80), content);
+                                continue;
+                            }
+                            ReplayEntry::AiMessage { role, content } => {
+                                ui.group(|ui| {
+                                    let color = if role == "You" {
+                                        egui::Color32::from_rgb(100, 150, 255)
+                                    } else {
+                                        egui::Color32::from_rgb(100, 255, 150)
+                                    };
+                                    ui.colored_label(color, format!("{}: ", role));
+                                    ui.label(content);
+                                });
+                                ui.add_space(5.0);
+                                continue;
+                            }
+                        };
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(egui::Color32::from_rgb(100, 200, 100), ">");
+                                ui.label(&block.command);
+                                if replay.recording.is_pinned(block.id) {
+                                    ui.label("📌");
+                                }
+                                for letter in replay.recording.marks().letters_of(block.id) {
+                                    ui.weak(format!("'{}", letter));
+                                }
+                                if block.is_running {
+                                    accessibility::spinner(ui);
+                                } else if let Some(code) = block.exit_code.filter(|c| *c != 0) {
+                                    ui.colored_label(egui::Color32::from_rgb(220, 100, 100), format!("exit {}", code));
+                                }
+                            });
+                            if !block.output.is_empty() {
+                                ui.separator();
+                                if let Some(action) = output_pager::render_block_output(
+                                    ui,
+                                    block.id,
+                                    &block.output,
+                                    &block.links,
+                                    &mut block.pager,
+                                    pager_threshold,
+                                    block.is_running,
+                                ) {
+                                    link_action = Some(action);
+                                }
+                            }
+                        });
+                        ui.add_space(5.0);
+                    }
+                });
+            });
+
+        if !open {
+            self.session_recorder.replay = None;
+        }
+        if let Some(action) = link_action {
+            self.handle_link_action(action);
+        }
+    }
+}
+
+fn default_export_path(directory: &Path, recording: &SessionRecording, format: ExportFormat) -> String {
+    directory
+        .join(format!(
+            "session-{}.{}",
+            recording.started_at.with_timezone(&chrono::Local).format("%Y%m%d-%H%M%S"),
+            format.extension()
+        ))
+        .to_string_lossy()
+        .to_string()
+}
@@ -0,0 +1,101 @@
+use super::{AnTraftApp, UIMode};
+use crate::ai::session_summary::SummaryProgress;
+use crate::ai::SessionBlockSummary;
+use log::error;
+
+/// Role used in the AI panel for session summaries, which get a
+/// "Copy as Markdown" button.
+pub const SUMMARY_ROLE: &str = "Summary";
+
+#[derive(Debug, Clone)]
+pub enum SummaryUpdate {
+    Progress(SummaryProgress),
+    /// What `injection::detect_injection` found in the session sent
+    InjectionSuspected(Vec<String>),
+    Finished(Result<String, String>),
+}
+
+impl AnTraftApp {
+    /// Sends the finished blocks of the terminal session to the AI agent and
+    /// shows the resulting writeup in the AI panel.
+    pub(super) fn summarize_session(&mut self) {
+        if self.summary_message.is_some() {
+            return;
+        }
+
+        let blocks: Vec<SessionBlockSummary> = self
+            .terminal_output
+            .iter()
+            .filter(|block| !block.is_running && !block.command.is_empty())
+            .map(|block| SessionBlockSummary::new(block.command.clone(), block.exit_code, &block.output))
+            .collect();
+
+        self.current_mode = UIMode::AiAgent;
+        self.ai_messages.push(("You".to_string(), "Summarize this session".to_string()));
+        if blocks.is_empty() {
+            self.ai_messages.push((
+                "AI".to_string(),
+                "There are no commands in this session to summarize yet.".to_string(),
+            ));
+            return;
+        }
+        self.ai_messages.push(("AI".to_string(), "📝 Summarizing session…".to_string()));
+        self.summary_message = Some(self.ai_messages.len() - 1);
+
+        let ai_agent = self.ai_agent.clone();
+        let sender = self.summary_tx.clone();
+        self.spawn_reported("Summarizing the session", async move {
+            let progress_sender = sender.clone();
+            let result = ai_agent
+                .read()
+                .await
+                .summarize_session(&blocks, move |progress| {
+                    let _ = progress_sender.send(SummaryUpdate::Progress(progress));
+                })
+                .await;
+            let result = result.map(|response| {
+                if !response.injection_signals.is_empty() {
+                    let _ = sender.send(SummaryUpdate::InjectionSuspected(response.injection_signals));
+                }
+                response.content
+            });
+            let _ = sender.send(SummaryUpdate::Finished(result.map_err(|e| e.to_string())));
+        });
+    }
+
+    pub(super) fn handle_summary_update(&mut self, update: SummaryUpdate) {
+        let Some(index) = self.summary_message else {
+            return;
+        };
+        // The warning goes above the summary, which moves down
+        if let SummaryUpdate::InjectionSuspected(signals) = &update {
+            self.insert_injection_warning(index, signals);
+            return;
+        }
+        let Some((role, message)) = self.ai_messages.get_mut(index) else {
+            self.summary_message = None;
+            return;
+        };
+
+        match update {
+            SummaryUpdate::Progress(SummaryProgress { done, total }) => {
+                *message = if total > 1 {
+                    format!("📝 Summarizing session… ({} / {} parts)", done, total)
+                } else {
+                    "📝 Summarizing session…".to_string()
+                };
+            }
+            SummaryUpdate::Finished(Ok(summary)) => {
+                *role = SUMMARY_ROLE.to_string();
+                *message = summary;
+                self.summary_message = None;
+            }
+            SummaryUpdate::Finished(Err(e)) => {
+                error!("Session summary failed: {}", e);
+                *message = format!("Sorry, I couldn't summarize this session: {}", e);
+                self.summary_message = None;
+            }
+            SummaryUpdate::InjectionSuspected(_) => {}
+        }
+    }
+}
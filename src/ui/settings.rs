@@ -0,0 +1,313 @@
+use super::history_import::HistoryImportState;
+use super::{accessibility, AnTraftApp};
+use crate::ai::{redaction, CommitConvention};
+use crate::config::MAX_MIN_FONT_SIZE;
+use crate::security::command_analysis;
+use eframe::egui;
+use std::sync::atomic::Ordering;
+
+impl AnTraftApp {
+    pub(super) fn render_settings(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+
+        egui::Window::new("⚙ Settings")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.collapsing("History", |ui| {
+                    let busy = matches!(
+                        self.history_import,
+                        HistoryImportState::Scanning | HistoryImportState::Running { .. }
+                    );
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(!busy, egui::Button::new("Import shell history"))
+                            .on_hover_text("Import bash/zsh/fish/PowerShell history. Commands already in ANTRAFT's history are skipped.")
+                            .clicked()
+                        {
+                            self.scan_shell_histories();
+                        }
+                        if busy {
+                            accessibility::spinner(ui);
+                        }
+                    });
+                    if let Some(status) = self.history_import_status() {
+                        ui.small(status);
+                    }
+                    ui.separator();
+                    self.render_learned_commands_settings(ui);
+                });
+
+                ui.collapsing("Terminal", |ui| {
+                    if ui
+                        .checkbox(&mut self.config.terminal.show_process_usage, "Show CPU and memory of running commands")
+                        .on_hover_text("In the status bar and on each running block, for the command and the processes it started")
+                        .changed()
+                    {
+                        self.save_config();
+                    }
+                    ui.horizontal(|ui| {
+                        let mut enabled = self.config.terminal.timeout.is_some();
+                        let mut secs = self.config.terminal.timeout.unwrap_or(300);
+                        let mut changed = ui
+                            .checkbox(&mut enabled, "Time out commands after")
+                            .on_hover_text("Kills a command still running then, with whatever it started. `@timeout=30s <command>` gives one command its own; interactive sessions have none")
+                            .changed();
+                        changed |= ui
+                            .add_enabled(enabled, egui::DragValue::new(&mut secs).clamp_range(1..=86_400).suffix(" s"))
+                            .changed();
+                        if changed {
+                            self.config.terminal.timeout = enabled.then_some(secs);
+                            self.save_config();
+                            let terminal_engine = self.terminal_engine.clone();
+                            let terminal = self.config.terminal.clone();
+                            self.runtime_handle.spawn(async move {
+                                terminal_engine.update_config(terminal).await;
+                            });
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Undo steps");
+                        if ui
+                            .add(egui::DragValue::new(&mut self.config.terminal.undo_limit).clamp_range(0..=200))
+                            .on_hover_text("Deleted blocks, cleared terminals and cleared inputs Ctrl+Z can bring back; 0 turns undo off")
+                            .changed()
+                        {
+                            self.undo.set_limit(self.config.terminal.undo_limit);
+                            self.save_config();
+                        }
+                    });
+                    if ui
+                        .checkbox(&mut self.config.terminal.help_completions, "Complete flags from --help and man pages")
+                        .on_hover_text("For commands without a completion spec, runs `<command> --help` in the background the first time you type its arguments")
+                        .changed()
+                    {
+                        self.help_ingestor.set_enabled(self.config.terminal.help_completions);
+                        self.save_config();
+                    }
+                });
+
+                ui.collapsing("Accessibility", |ui| {
+                    self.render_accessibility_settings(ui);
+                });
+
+                ui.collapsing("AI", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Model");
+                        self.render_model_selector(ui, "settings_model_selector");
+                    });
+                    self.render_model_limits(ui);
+                    if ui
+                        .checkbox(&mut self.config.ai.enable_tools, "Let the AI run read-only commands")
+                        .on_hover_text("Allows the chat to run allowlisted commands like ls, ps and git status, and read files, to answer questions")
+                        .changed()
+                    {
+                        self.save_config();
+                    }
+                    self.render_cache_settings(ui);
+                });
+
+                ui.collapsing("Command checks", |ui| {
+                    self.render_command_check_settings(ui);
+                });
+
+                ui.collapsing("Redaction", |ui| {
+                    self.render_redaction_settings(ui);
+                });
+
+                ui.collapsing("Git", |ui| {
+                    let current = self.config.ai.commit_convention;
+                    let mut selected = current;
+                    egui::ComboBox::from_label("Commit message style")
+                        .selected_text(current.label())
+                        .show_ui(ui, |ui| {
+                            for convention in CommitConvention::ALL {
+                                ui.selectable_value(&mut selected, convention, convention.label());
+                            }
+                        });
+                    if selected != current {
+                        self.config.ai.commit_convention = selected;
+                        self.save_config();
+                    }
+                });
+
+                ui.collapsing("Prompt templates", |ui| {
+                    self.render_template_settings(ui);
+                });
+
+                ui.collapsing("Workflows", |ui| {
+                    self.render_workflow_settings(ui);
+                });
+            });
+
+        self.show_settings = open;
+    }
+
+    fn render_accessibility_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.config.accessibility;
+        let mut changed = ui
+            .checkbox(&mut settings.high_contrast, "High contrast")
+            .on_hover_text("White text on black, with a yellow outline around the focused widget")
+            .changed();
+        changed |= ui
+            .checkbox(&mut settings.reduced_motion, "Reduce motion")
+            .on_hover_text("No animations, and a still ⏳ in place of spinners")
+            .changed();
+        ui.horizontal(|ui| {
+            ui.label("Minimum font size");
+            let response = ui
+                .add(egui::Slider::new(&mut settings.min_font_size, 0.0..=MAX_MIN_FONT_SIZE).step_by(1.0))
+                .on_hover_text("Text smaller than this is drawn at this size; 0 leaves the sizes as they are");
+            // Saved once the slider is let go
+            changed |= response.drag_stopped() || (response.changed() && !response.dragged());
+        });
+        if changed {
+            self.save_config();
+        }
+    }
+
+    fn render_command_check_settings(&mut self, ui: &mut egui::Ui) {
+        ui.small("Commands from the AI, workflows and multi-line pastes are checked before they run");
+        let rules = command_analysis::COMMAND_RULES
+            .iter()
+            .map(|rule| (rule.id, rule.title))
+            .chain(std::iter::once((command_analysis::SECRET_IN_COMMAND, "Has a credential written in it")));
+        let mut changed = false;
+        for (id, title) in rules {
+            let disabled = &mut self.config.security.disabled_command_rules;
+            let mut enabled = !disabled.iter().any(|rule| rule == id);
+            if ui.checkbox(&mut enabled, title).on_hover_text(id).changed() {
+                match enabled {
+                    true => disabled.retain(|rule| rule != id),
+                    false => disabled.push(id.to_string()),
+                }
+                changed = true;
+            }
+        }
+        if changed {
+            self.command_review.recheck();
+            self.save_config();
+        }
+    }
+
+    fn render_learned_commands_settings(&mut self, ui: &mut egui::Ui) {
+        let learned = self.learned_commands.read().unwrap().len();
+        ui.small(format!(
+            "Autocomplete has learned {} command lines and arguments from commands that worked",
+            learned
+        ));
+        if ui
+            .add_enabled(learned > 0, egui::Button::new("Clear learned data"))
+            .on_hover_text("Forget them; suggestions from your history stay")
+            .clicked()
+        {
+            let mut learned = self.learned_commands.write().unwrap();
+            learned.clear();
+            if let Err(e) = learned.save() {
+                self.notifications.sender().error("Couldn't clear learned commands", format!("{:#}", e));
+            }
+        }
+    }
+
+    fn render_cache_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.config.ai.cache;
+        let mut changed = ui
+            .checkbox(&mut settings.enabled, "Reuse answers to repeated requests")
+            .on_hover_text(format!(
+                "Explanations, generated commands and the like at temperature {} or below are answered from earlier responses",
+                settings.max_temperature
+            ))
+            .changed();
+        changed |= ui
+            .add_enabled(
+                settings.enabled,
+                egui::Checkbox::new(&mut settings.persist, "Keep cached answers across restarts"),
+            )
+            .changed();
+        let clear = ui.small_button("Clear cached answers").clicked();
+
+        if changed || clear {
+            if changed {
+                self.save_config();
+            }
+            let ai_agent = self.ai_agent.clone();
+            let ai_config = self.config.ai.clone();
+            self.runtime_handle.spawn(async move {
+                let mut ai_agent = ai_agent.write().await;
+                if clear {
+                    ai_agent.clear_cache();
+                }
+                if changed {
+                    ai_agent.update_config(ai_config);
+                }
+            });
+        }
+    }
+
+    fn render_redaction_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.config.ai.redaction;
+        let mut changed = ui
+            .checkbox(&mut settings.enabled, "Redact secrets before sending to the AI")
+            .on_hover_text("Tokens, keys and passwords in prompts are replaced with placeholders like «REDACTED:github_token:1»")
+            .changed();
+        changed |= ui
+            .add_enabled(
+                settings.enabled,
+                egui::Checkbox::new(&mut settings.restore_placeholders, "Show the real values in responses"),
+            )
+            .on_hover_text("Placeholders in AI responses are swapped back locally; the secrets are never sent")
+            .changed();
+
+        ui.small(format!(
+            "{} secrets redacted this session",
+            self.ai_redactions.load(Ordering::Relaxed)
+        ));
+        if settings.enabled {
+            ui.label("Active patterns:");
+            ui.small(redaction::pattern_names(settings).join(", "));
+            ui.small("Add your own under [[ai.redaction.patterns]] in the config file.");
+        }
+
+        if changed {
+            self.save_config();
+            let ai_agent = self.ai_agent.clone();
+            let ai_config = self.config.ai.clone();
+            self.runtime_handle.spawn(async move {
+                ai_agent.write().await.update_config(ai_config);
+            });
+        }
+
+        ui.separator();
+        let privacy = &mut self.config.privacy;
+        let logs_changed = ui
+            .checkbox(&mut privacy.redact_logs, "Redact secrets from logs")
+            .changed();
+        let audit_changed = ui
+            .checkbox(&mut privacy.redact_audit_log, "Redact secrets from the audit log")
+            .on_hover_text("Applies from the next start")
+            .changed();
+        if logs_changed {
+            crate::security::secrets::set_redact_logs(privacy.redact_logs);
+        }
+        if logs_changed || audit_changed {
+            self.save_config();
+        }
+
+        let logging = &mut self.config.logging;
+        if ui
+            .checkbox(&mut logging.file, "Write the log to a file")
+            .on_hover_text(format!(
+                "{}, rotated at {} KB with {} old files kept. \"Show Logs\" in the command palette shows it either way",
+                logging.file_path().display(),
+                logging.max_file_size_kb,
+                logging.max_files
+            ))
+            .changed()
+        {
+            if let Err(e) = crate::logging::configure(logging) {
+                self.notifications.sender().error("Couldn't open the log file", format!("{:#}", e));
+            }
+            self.save_config();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use super::{accessibility, AnTraftApp};
+use eframe::egui;
+use std::time::Duration;
+
+/// How long killed commands get to exit before the window closes anyway.
+const REAP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Closing the window: running commands are confirmed, then killed, history
+/// is flushed and the watchers stopped before the close goes through.
+#[derive(Default)]
+pub enum Shutdown {
+    #[default]
+    Idle,
+    /// Asking whether to kill these running commands
+    Confirming(Vec<String>),
+    InProgress(crossbeam_channel::Receiver<()>),
+    /// Nothing left to wait for; the next close request goes through
+    Done,
+}
+
+impl AnTraftApp {
+    /// Called every frame; holds back close requests until shutdown is done.
+    /// Closing again while it runs quits without waiting.
+    pub(super) fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if let Shutdown::InProgress(done_rx) = &self.shutdown {
+            if done_rx.try_recv().is_ok() {
+                self.shutdown = Shutdown::Done;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                return;
+            }
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+
+        if !ctx.input(|i| i.viewport().close_requested()) {
+            return;
+        }
+        match &self.shutdown {
+            Shutdown::Done => {}
+            Shutdown::InProgress(_) => {
+                log::warn!("Quitting without waiting for shutdown to finish");
+                self.shutdown = Shutdown::Done;
+            }
+            Shutdown::Confirming(_) => ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose),
+            Shutdown::Idle => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                let running: Vec<String> = self
+                    .terminal_output
+                    .iter()
+                    .filter(|block| block.is_running)
+                    .map(|block| block.command.clone())
+                    .collect();
+                if running.is_empty() {
+                    self.start_shutdown();
+                } else {
+                    self.shutdown = Shutdown::Confirming(running);
+                }
+            }
+        }
+    }
+
+    fn start_shutdown(&mut self) {
+        // Watchers stop when dropped
+        self._completion_spec_watcher = None;
+        self.save_layout();
+
+        let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+        self.shutdown = Shutdown::InProgress(done_rx);
+        let engine = self.terminal_engine.clone();
+        let file_explorer = self.file_explorer.clone();
+        let history = self.history.clone();
+        let history_path = self.history_path.clone();
+        self.runtime_handle.spawn(async move {
+            file_explorer.write().await.stop_watching();
+            engine.shutdown_and_reap(REAP_TIMEOUT).await;
+            // After the kills, so the last commands' results are in
+            if let Err(e) = history.read().await.save(&history_path) {
+                log::error!("Failed to save command history: {}", e);
+            }
+            let _ = done_tx.send(());
+        });
+    }
+
+    pub(super) fn render_shutdown(&mut self, ctx: &egui::Context) {
+        let mut decision = None;
+        match &self.shutdown {
+            Shutdown::Confirming(running) => {
+                egui::Window::new("Quit ANTRAFT?")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label(format!(
+                            "{} command{} still running and will be killed:",
+                            running.len(),
+                            if running.len() == 1 { " is" } else { "s are" }
+                        ));
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            for command in running {
+                                ui.monospace(command);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Kill and quit").clicked() {
+                                decision = Some(true);
+                            }
+                            if ui.button("Cancel").clicked() {
+                                decision = Some(false);
+                            }
+                        });
+                    });
+            }
+            Shutdown::InProgress(_) => {
+                egui::Window::new("Shutting down")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            accessibility::spinner(ui);
+                            ui.label("Stopping commands and saving history…");
+                        });
+                        ui.small("Close the window again to quit now.");
+                    });
+            }
+            Shutdown::Idle | Shutdown::Done => {}
+        }
+
+        match decision {
+            Some(true) => self.start_shutdown(),
+            Some(false) => self.shutdown = Shutdown::Idle,
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use super::AnTraftApp;
+use crate::autocomplete::docker::DockerProvider;
+use crate::autocomplete::kubectl::KubectlProvider;
+use crate::autocomplete::specs;
+use crate::docker::{self, DockerCache};
+use crate::kubectl::{self, KubectlCache};
+use crate::security::SecurityScanner;
+use crate::terminal::history::CommandHistory;
+use log::{error, info};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A part of the app that finishes loading in the background, after the
+/// first frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    /// The command history, and autocomplete's index of it
+    History,
+    /// The file explorer's `.gitignore` patterns
+    FileExplorer,
+    /// Completion specs, and docker's and kubectl's completions when
+    /// they're installed
+    Completions,
+    /// Which security scanners are installed, and the custom rules
+    SecurityScanners,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 4] = [
+        Subsystem::History,
+        Subsystem::FileExplorer,
+        Subsystem::Completions,
+        Subsystem::SecurityScanners,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Subsystem::History => "history",
+            Subsystem::FileExplorer => "file index",
+            Subsystem::Completions => "completions",
+            Subsystem::SecurityScanners => "security scanners",
+        }
+    }
+}
+
+/// What a background startup task delivers to the UI thread.
+enum StartupEvent {
+    /// `first_run` when there was no history file yet
+    HistoryLoaded { first_run: bool },
+    FileExplorerLoaded,
+    CompletionsLoaded {
+        docker: Option<Arc<DockerCache>>,
+        kubectl: Option<Arc<KubectlCache>>,
+        spec_watcher: Option<notify::RecommendedWatcher>,
+    },
+    /// `None` when they couldn't be set up
+    ScannersDetected(Option<SecurityScanner>),
+}
+
+/// How far startup has got: which subsystems are ready, and how long the
+/// first frame took.
+pub(super) struct Startup {
+    started: Instant,
+    first_frame: Option<Duration>,
+    ready: HashSet<Subsystem>,
+    tx: crossbeam_channel::Sender<StartupEvent>,
+    rx: crossbeam_channel::Receiver<StartupEvent>,
+}
+
+impl Startup {
+    pub(super) fn new(started: Instant) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        Self {
+            started,
+            first_frame: None,
+            ready: HashSet::new(),
+            tx,
+            rx,
+        }
+    }
+
+    fn pending(&self) -> impl Iterator<Item = Subsystem> + '_ {
+        Subsystem::ALL.into_iter().filter(|subsystem| !self.ready.contains(subsystem))
+    }
+}
+
+impl AnTraftApp {
+    /// Whether `subsystem` has finished loading.
+    pub fn is_ready(&self, subsystem: Subsystem) -> bool {
+        self.startup.ready.contains(&subsystem)
+    }
+
+    /// Whether every subsystem has finished loading.
+    pub fn startup_finished(&self) -> bool {
+        self.startup.pending().next().is_none()
+    }
+
+    /// How long after `new` was called the first frame was drawn.
+    pub fn time_to_first_frame(&self) -> Option<Duration> {
+        self.startup.first_frame
+    }
+
+    /// What's still loading, like "history, completions".
+    pub(super) fn startup_pending(&self) -> String {
+        self.startup.pending().map(|subsystem| subsystem.label()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Loads what `new` left placeholders for, each on its own task: the
+    /// history and the file explorer stay locked until they're loaded, so
+    /// nothing reads or saves the placeholders meanwhile.
+    pub(super) fn start_background_init(&mut self) {
+        let tx = self.startup.tx.clone();
+        let (Ok(mut history), Ok(mut explorer)) =
+            (self.history.clone().try_write_owned(), self.file_explorer.clone().try_write_owned())
+        else {
+            error!("The history or file explorer was in use before startup");
+            return;
+        };
+
+        let history_path = self.history_path.clone();
+        let max_history = self.config.terminal.max_history;
+        let autocomplete_engine = self.autocomplete_engine.clone();
+        let history_tx = tx.clone();
+        self.runtime_handle.spawn(async move {
+            let loaded = tokio::task::spawn_blocking(move || {
+                (!history_path.exists(), CommandHistory::load(&history_path, max_history))
+            })
+            .await;
+            let first_run = match loaded {
+                Ok((first_run, Ok(loaded))) => {
+                    *history = loaded;
+                    first_run
+                }
+                Ok((_, Err(e))) => {
+                    error!("Failed to load command history: {}", e);
+                    false
+                }
+                Err(e) => {
+                    error!("Failed to load command history: {}", e);
+                    false
+                }
+            };
+            {
+                let mut engine = autocomplete_engine.write().await;
+                for entry in history.get_all_entries() {
+                    engine.add_to_history(entry.command.clone(), entry.runs(), entry.timestamp);
+                    if let Some(exit_code) = entry.exit_code {
+                        engine.record_result(&entry.command, exit_code);
+                    }
+                }
+            }
+            drop(history);
+            let _ = history_tx.send(StartupEvent::HistoryLoaded { first_run });
+        });
+
+        let explorer_tx = tx.clone();
+        self.runtime_handle.spawn_blocking(move || {
+            explorer.load_gitignore();
+            drop(explorer);
+            let _ = explorer_tx.send(StartupEvent::FileExplorerLoaded);
+        });
+
+        let autocomplete_engine = self.autocomplete_engine.clone();
+        let runtime = self.runtime_handle.clone();
+        let notifier = self.notifications.sender();
+        let completions_tx = tx.clone();
+        self.runtime_handle.spawn_blocking(move || {
+            // Only looks the CLIs up on PATH; the caches run them when asked
+            let docker = docker::is_available().then(|| DockerCache::new(runtime.clone()));
+            let kubectl = kubectl::is_available().then(|| KubectlCache::new(runtime));
+            let specs_dir = specs::default_specs_dir();
+            {
+                let mut engine = autocomplete_engine.blocking_write();
+                if let Some(docker) = &docker {
+                    engine.add_provider(Box::new(DockerProvider::new(docker.clone())));
+                }
+                if let Some(kubectl) = &kubectl {
+                    engine.add_provider(Box::new(KubectlProvider::new(kubectl.clone())));
+                }
+                specs::install_specs(&mut engine, &specs_dir);
+            }
+            let spec_watcher = specs::watch_specs(&specs_dir, autocomplete_engine)
+                .map_err(|e| notifier.warning("Completion specs won't reload when changed", format!("{:#}", e)))
+                .ok();
+            let _ = completions_tx.send(StartupEvent::CompletionsLoaded { docker, kubectl, spec_watcher });
+        });
+
+        self.detect_security_scanners();
+    }
+
+    /// Looks for the scanners `config.security` enables in the background,
+    /// replacing the current ones when done.
+    pub(super) fn detect_security_scanners(&self) {
+        let tx = self.startup.tx.clone();
+        let security = self.config.security.clone();
+        self.spawn_blocking_reported("Detecting the security scanners", move || {
+            let scanner = SecurityScanner::new(security)
+                .map_err(|e| error!("Failed to set up the security scanners: {:#}", e))
+                .ok();
+            let _ = tx.send(StartupEvent::ScannersDetected(scanner));
+        });
+    }
+
+    /// Takes in what the background startup tasks have loaded.
+    pub fn poll_startup(&mut self) {
+        while let Ok(event) = self.startup.rx.try_recv() {
+            let subsystem = match event {
+                StartupEvent::HistoryLoaded { first_run } => {
+                    if first_run {
+                        self.scan_shell_histories();
+                    }
+                    Subsystem::History
+                }
+                StartupEvent::FileExplorerLoaded => Subsystem::FileExplorer,
+                StartupEvent::CompletionsLoaded { docker, kubectl, spec_watcher } => {
+                    self.docker = docker;
+                    self.kubectl = kubectl;
+                    self._completion_spec_watcher = spec_watcher;
+                    Subsystem::Completions
+                }
+                StartupEvent::ScannersDetected(scanner) => {
+                    if let Some(scanner) = scanner {
+                        self.security_scanner = Arc::new(scanner);
+                    }
+                    Subsystem::SecurityScanners
+                }
+            };
+            self.startup.ready.insert(subsystem);
+            if self.startup_finished() {
+                info!("Startup finished after {} ms", self.startup.started.elapsed().as_millis());
+            }
+        }
+    }
+
+    /// Notes the time to the first frame, then does what had to wait for a
+    /// window: recording the workspace and running the startup commands.
+    pub(super) fn on_first_frame(&mut self) {
+        let elapsed = self.startup.started.elapsed();
+        self.startup.first_frame = Some(elapsed);
+        info!("First frame {} ms after startup", elapsed.as_millis());
+        self.remember_workspace(&self.working_directory.clone());
+        self.start_session_init();
+    }
+}
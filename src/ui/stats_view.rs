@@ -0,0 +1,264 @@
+use super::{accessibility, AnTraftApp};
+use crate::terminal::stats::{self, CommandStats, UsageStats};
+use anyhow::Result;
+use eframe::egui;
+use std::path::PathBuf;
+
+const BAR_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 150, 255);
+const SUCCESS_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 200, 100);
+const FAILURE_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 100, 100);
+
+/// Cached usage statistics; recomputed off the UI thread when the view is
+/// opened or refreshed.
+#[derive(Default)]
+pub struct StatsView {
+    pub open: bool,
+    stats: Option<UsageStats>,
+    stats_rx: Option<crossbeam_channel::Receiver<UsageStats>>,
+    export_status: Option<String>,
+}
+
+impl StatsView {
+    fn is_computing(&self) -> bool {
+        self.stats_rx.is_some()
+    }
+
+    fn poll(&mut self) {
+        if let Some(rx) = &self.stats_rx {
+            if let Ok(stats) = rx.try_recv() {
+                self.stats = Some(stats);
+                self.stats_rx = None;
+            }
+        }
+    }
+}
+
+impl AnTraftApp {
+    pub(super) fn open_usage_stats(&mut self) {
+        self.stats_view.open = true;
+        self.refresh_usage_stats();
+    }
+
+    fn refresh_usage_stats(&mut self) {
+        if self.stats_view.is_computing() {
+            return;
+        }
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.stats_view.stats_rx = Some(rx);
+
+        let history = self.history.clone();
+        self.runtime_handle.spawn(async move {
+            let history = history.read().await;
+            let _ = tx.send(stats::aggregate(history.get_all_entries()));
+        });
+    }
+
+    pub(super) fn render_usage_stats(&mut self, ctx: &egui::Context) {
+        if !self.stats_view.open {
+            return;
+        }
+        self.stats_view.poll();
+        if self.stats_view.is_computing() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut export = false;
+
+        egui::Window::new("📊 Usage Statistics")
+            .open(&mut open)
+            .default_size([560.0, 640.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!self.stats_view.is_computing(), egui::Button::new("⟳ Refresh"))
+                        .clicked()
+                    {
+                        refresh = true;
+                    }
+                    if ui
+                        .add_enabled(self.stats_view.stats.is_some(), egui::Button::new("Export stats as JSON"))
+                        .clicked()
+                    {
+                        export = true;
+                    }
+                    if self.stats_view.is_computing() {
+                        accessibility::spinner(ui);
+                    }
+                });
+                if let Some(status) = &self.stats_view.export_status {
+                    ui.small(status);
+                }
+                ui.separator();
+
+                let Some(stats) = &self.stats_view.stats else {
+                    ui.label("Crunching history…");
+                    return;
+                };
+                if stats.total_commands == 0 {
+                    ui.label("No commands in history yet.");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    render_stats(ui, stats);
+                });
+            });
+
+        if refresh {
+            self.refresh_usage_stats();
+        }
+        if export {
+            self.stats_view.export_status = Some(match self.export_usage_stats() {
+                Ok(path) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Export failed: {}", e),
+            });
+        }
+        self.stats_view.open = open;
+    }
+
+    fn export_usage_stats(&self) -> Result<PathBuf> {
+        let Some(stats) = &self.stats_view.stats else {
+            anyhow::bail!("No statistics computed yet");
+        };
+
+        let dir = crate::config::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "usage-stats-{}.json",
+            stats.generated_at.format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::write(&path, serde_json::to_string_pretty(stats)?)?;
+        Ok(path)
+    }
+}
+
+fn render_stats(ui: &mut egui::Ui, stats: &UsageStats) {
+    ui.label(format!("{} commands in history", stats.total_commands));
+    ui.add_space(8.0);
+
+    ui.strong("Top commands");
+    let max_count = stats.top_commands.first().map(|c| c.count).unwrap_or(0);
+    egui::Grid::new("stats_top_commands").striped(true).show(ui, |ui| {
+        for command in &stats.top_commands {
+            ui.monospace(&command.command);
+            horizontal_bar(ui, command.count as f32 / max_count.max(1) as f32, BAR_COLOR);
+            ui.label(command.count.to_string());
+            success_label(ui, command);
+            ui.end_row();
+        }
+    });
+    ui.add_space(12.0);
+
+    if !stats.slowest_commands.is_empty() {
+        ui.strong("Slowest commands (average duration)");
+        let max_duration = stats
+            .slowest_commands
+            .first()
+            .and_then(|c| c.average_duration_ms())
+            .unwrap_or(0);
+        egui::Grid::new("stats_slowest_commands").striped(true).show(ui, |ui| {
+            for command in &stats.slowest_commands {
+                let average = command.average_duration_ms().unwrap_or(0);
+                ui.monospace(&command.command);
+                horizontal_bar(ui, average as f32 / max_duration.max(1) as f32, FAILURE_COLOR);
+                ui.label(format_duration(average));
+                ui.end_row();
+            }
+        });
+        ui.add_space(12.0);
+    }
+
+    ui.strong("Activity by hour of day");
+    let hours: Vec<(String, usize)> = stats
+        .by_hour
+        .iter()
+        .enumerate()
+        .map(|(hour, count)| (format!("{:02}:00", hour), *count))
+        .collect();
+    column_chart(ui, &hours, 6);
+    ui.add_space(12.0);
+
+    ui.strong("Commands per day (last 30 days)");
+    let days: Vec<(String, usize)> = stats
+        .per_day
+        .iter()
+        .map(|(day, count)| (day.format("%b %d").to_string(), *count))
+        .collect();
+    column_chart(ui, &days, 7);
+}
+
+fn success_label(ui: &mut egui::Ui, command: &CommandStats) {
+    match command.success_ratio() {
+        Some(ratio) => {
+            let color = if ratio >= 0.5 { SUCCESS_COLOR } else { FAILURE_COLOR };
+            ui.colored_label(color, format!("{:.0}% ok", ratio * 100.0))
+                .on_hover_text(format!("{} succeeded, {} failed", command.successes, command.failures));
+        }
+        None => {
+            ui.weak("—");
+        }
+    }
+}
+
+fn horizontal_bar(ui: &mut egui::Ui, fraction: f32, color: egui::Color32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(200.0, 12.0), egui::Sense::hover());
+    let mut filled = rect;
+    filled.set_width(rect.width() * fraction.clamp(0.0, 1.0));
+    ui.painter().rect_filled(rect, egui::Rounding::same(2.0), egui::Color32::from_rgb(30, 30, 35));
+    ui.painter().rect_filled(filled, egui::Rounding::same(2.0), color);
+}
+
+/// Draws one column per value, labelling every `label_every`-th column.
+fn column_chart(ui: &mut egui::Ui, values: &[(String, usize)], label_every: usize) {
+    let max = values.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let height = 80.0;
+    let width = ui.available_width().min(520.0);
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(width, height + 14.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let column_width = width / values.len().max(1) as f32;
+
+    let mut hovered = None;
+    for (index, (label, count)) in values.iter().enumerate() {
+        let left = rect.left() + index as f32 * column_width;
+        let bar_height = height * (*count as f32 / max as f32);
+        let column = egui::Rect::from_min_max(
+            egui::pos2(left + 1.0, rect.top() + height - bar_height),
+            egui::pos2(left + column_width - 1.0, rect.top() + height),
+        );
+        painter.rect_filled(column, egui::Rounding::same(1.0), BAR_COLOR);
+
+        if index % label_every == 0 {
+            painter.text(
+                egui::pos2(left, rect.top() + height + 2.0),
+                egui::Align2::LEFT_TOP,
+                label,
+                egui::FontId::proportional(10.0),
+                egui::Color32::GRAY,
+            );
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            if pointer.x >= left && pointer.x < left + column_width {
+                hovered = Some((label, count));
+            }
+        }
+    }
+
+    if let Some((label, count)) = hovered {
+        response.on_hover_text(format!("{}: {}", label, count));
+    }
+}
+
+fn format_duration(ms: u64) -> String {
+    if ms >= 60_000 {
+        format!("{}m {}s", ms / 60_000, (ms % 60_000) / 1000)
+    } else if ms >= 1000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
+}
@@ -0,0 +1,154 @@
+use super::{AnTraftApp, TerminalBlock};
+use crate::terminal::block::format_duration;
+use crate::terminal::process_usage::{self, ProcessSampler, ProcessUsage};
+use crate::terminal::title;
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often running commands' CPU and memory are sampled.
+const USAGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The CPU and memory of the running commands, sampled in the background
+/// while any run.
+#[derive(Default)]
+pub struct ProcessUsageMonitor {
+    usage: Option<ProcessUsage>,
+    last_sample: Option<Instant>,
+    sample_rx: Option<crossbeam_channel::Receiver<Option<UsageSample>>>,
+    /// Kept between samples, which CPU usage is measured across
+    sampler: Arc<Mutex<ProcessSampler>>,
+    /// Set when processes can't be sampled, so it isn't tried again
+    unavailable: bool,
+}
+
+/// One sample of the running commands, together and by block.
+struct UsageSample {
+    total: ProcessUsage,
+    blocks: Vec<(uuid::Uuid, ProcessUsage)>,
+    at: Instant,
+}
+
+impl AnTraftApp {
+    /// Samples the running commands' usage when due and collects it, into
+    /// the status bar's total and each block's record.
+    pub(super) fn poll_process_usage(&mut self, ctx: &egui::Context) {
+        let monitor = &mut self.process_usage;
+        if let Some(rx) = &monitor.sample_rx {
+            if let Ok(sample) = rx.try_recv() {
+                monitor.unavailable = sample.is_none();
+                monitor.usage = sample.as_ref().map(|sample| sample.total);
+                monitor.sample_rx = None;
+                if let Some(sample) = &sample {
+                    for (id, usage) in &sample.blocks {
+                        if let Some(block) = self.terminal_output.iter_mut().find(|b| b.id == *id && b.is_running) {
+                            block.usage.record(*usage, sample.at);
+                        }
+                    }
+                }
+            }
+        }
+
+        let running = self.terminal_output.iter().any(|block| block.is_running);
+        if !running || !self.config.terminal.show_process_usage || monitor.unavailable {
+            monitor.usage = None;
+            return;
+        }
+        let due = monitor.last_sample.is_none_or(|last| last.elapsed() >= USAGE_INTERVAL);
+        if due && monitor.sample_rx.is_none() {
+            let (tx, rx) = crossbeam_channel::bounded(1);
+            monitor.sample_rx = Some(rx);
+            monitor.last_sample = Some(Instant::now());
+            let engine = self.terminal_engine.clone();
+            let sampler = monitor.sampler.clone();
+            self.runtime_handle.spawn(async move {
+                let commands: Vec<(uuid::Uuid, u32)> = engine
+                    .running_commands()
+                    .await
+                    .into_iter()
+                    .filter_map(|c| Some((c.id, c.pid?)))
+                    .collect();
+                let sample = tokio::task::spawn_blocking(move || {
+                    let processes = sampler.lock().unwrap().sample()?;
+                    let pids: Vec<u32> = commands.iter().map(|(_, pid)| *pid).collect();
+                    let by_root = process_usage::usage_by_root(&processes, &pids);
+                    anyhow::Ok(UsageSample {
+                        total: process_usage::usage_of(&processes, &pids),
+                        blocks: commands.iter().map(|(id, pid)| (*id, by_root[pid])).collect(),
+                        at: Instant::now(),
+                    })
+                })
+                .await;
+                let sample = match sample {
+                    Ok(Ok(sample)) => Some(sample),
+                    Ok(Err(e)) => {
+                        log::warn!("Not showing process usage: {:#}", e);
+                        None
+                    }
+                    Err(_) => None,
+                };
+                let _ = tx.send(sample);
+            });
+        }
+        ctx.request_repaint_after(USAGE_INTERVAL);
+    }
+
+    /// The session's directory and shell, for the status bar. Clicking the
+    /// directory copies it.
+    pub(super) fn render_session_status(&mut self, ui: &mut egui::Ui) {
+        let mut shell = self.config.terminal.shell.clone();
+        if self.config.terminal.login_shell {
+            shell.push_str(" -l");
+        }
+        ui.small(shell).on_hover_text("Shell");
+
+        let directory = self.working_directory.to_string_lossy().to_string();
+        let home = dirs::home_dir();
+        let text = egui::RichText::new(format!("📂 {}", title::shorten_home(&directory, home.as_deref()))).small();
+        if ui
+            .add(egui::Button::new(text).frame(false))
+            .on_hover_text(format!("{}\nClick to copy", directory))
+            .clicked()
+        {
+            ui.output_mut(|o| o.copied_text = directory);
+        }
+    }
+
+    /// CPU and memory of the running commands, for the status bar.
+    pub(super) fn render_process_usage(&self, ui: &mut egui::Ui) {
+        let Some(usage) = self.process_usage.usage.filter(|usage| usage.processes > 0) else {
+            return;
+        };
+        ui.small(format!(
+            "⚙ {:.0}% · {}",
+            usage.cpu_percent,
+            process_usage::format_memory(usage.memory_bytes)
+        ))
+        .on_hover_text(format!(
+            "CPU and memory of the running commands: {} process{}",
+            usage.processes,
+            if usage.processes == 1 { "" } else { "es" }
+        ));
+    }
+}
+
+/// The live CPU and memory of a running block's command, or the peaks it
+/// reached once it's finished.
+pub(super) fn render_block_usage(ui: &mut egui::Ui, block: &TerminalBlock) {
+    if let Some(usage) = block.usage.current.filter(|_| block.is_running) {
+        ui.small(process_usage::format_usage(&usage)).on_hover_text(format!(
+            "CPU and memory of the command and the {} process{} it runs",
+            usage.processes,
+            if usage.processes == 1 { "" } else { "es" }
+        ));
+    } else if !block.is_running && !block.usage.is_empty() {
+        let usage = &block.usage;
+        let peaks = format!(
+            "peak CPU {:.0}% · {}",
+            usage.peak_cpu_percent,
+            process_usage::format_memory(usage.peak_memory_bytes)
+        );
+        ui.weak(egui::RichText::new(peaks).small())
+            .on_hover_text(format!("About {} of CPU time", format_duration((usage.cpu_seconds * 1000.0) as u64)));
+    }
+}
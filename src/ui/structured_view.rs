@@ -0,0 +1,213 @@
+use super::output_pager::PAGER_VISIBLE_LINES;
+use super::table_view::{self, TableView};
+use crate::terminal::json_path::JsonPath;
+use crate::terminal::structured::{self, StructuredFormat, Token, TokenKind};
+use eframe::egui;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+const KEY_COLOR: egui::Color32 = egui::Color32::from_rgb(156, 200, 240);
+const STRING_COLOR: egui::Color32 = egui::Color32::from_rgb(206, 160, 120);
+const NUMBER_COLOR: egui::Color32 = egui::Color32::from_rgb(181, 206, 168);
+const LITERAL_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 160, 220);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StructuredMode {
+    /// The output as it came
+    #[default]
+    Raw,
+    Pretty,
+    Table,
+}
+
+#[derive(Debug, Clone)]
+struct Parsed {
+    format: StructuredFormat,
+    value: Arc<Value>,
+    /// For an array of flat objects
+    table: Option<TableView>,
+}
+
+/// A finished command's JSON or YAML output, parsed in the background and
+/// shown indented and colored, filtered by a path, or as a table. JSON is
+/// shown pretty from the start; YAML, which is readable as it is, raw.
+#[derive(Debug, Clone, Default)]
+pub struct StructuredView {
+    pub mode: StructuredMode,
+    parsed: Option<Parsed>,
+    parse_rx: Option<crossbeam_channel::Receiver<Option<(StructuredFormat, Value)>>>,
+    checked: bool,
+    /// A path like `.items[0].metadata.name`; empty for the whole value
+    filter: String,
+    /// The filter `lines` and `error` are for
+    shown_filter: Option<String>,
+    lines: Vec<Vec<Token>>,
+    error: Option<String>,
+    status: Option<String>,
+}
+
+impl StructuredView {
+    /// Starts parsing the output of a finished command that looks like JSON
+    /// or YAML, once, and picks up the result.
+    pub fn update(&mut self, output: &str, is_running: bool, runtime: &Handle) {
+        if let Some(rx) = &self.parse_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.parse_rx = None;
+                if let Some((format, value)) = result {
+                    self.mode = match format {
+                        StructuredFormat::Json => StructuredMode::Pretty,
+                        StructuredFormat::Yaml => StructuredMode::Raw,
+                    };
+                    self.parsed = Some(Parsed {
+                        format,
+                        table: structured::flat_table(&value).map(TableView::from_table),
+                        value: Arc::new(value),
+                    });
+                }
+            }
+        }
+        if self.checked || is_running {
+            return;
+        }
+        self.checked = true;
+        if structured::detect_format(output).is_none() {
+            return;
+        }
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.parse_rx = Some(rx);
+        let output = output.to_string();
+        runtime.spawn_blocking(move || {
+            let _ = tx.send(structured::parse_structured(&output));
+        });
+    }
+
+    pub fn is_parsing(&self) -> bool {
+        self.parse_rx.is_some()
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.parsed.is_some()
+    }
+
+    /// Whether the output should be shown pretty or as a table.
+    pub fn is_active(&self) -> bool {
+        self.is_available() && self.mode != StructuredMode::Raw
+    }
+
+    /// Re-evaluates the filter when it changed since the lines were made.
+    fn refresh(&mut self) {
+        let Some(parsed) = &self.parsed else {
+            return;
+        };
+        if self.shown_filter.as_ref() == Some(&self.filter) {
+            return;
+        }
+        self.shown_filter = Some(self.filter.clone());
+        self.error = None;
+        self.lines = match self.selection(&parsed.value) {
+            Ok(values) => values.into_iter().flat_map(structured::pretty_lines).collect(),
+            Err(e) => {
+                self.error = Some(e);
+                Vec::new()
+            }
+        };
+    }
+
+    /// What the filter picks out of `value`: all of it without one.
+    fn selection<'a>(&self, value: &'a Value) -> Result<Vec<&'a Value>, String> {
+        if self.filter.trim().is_empty() {
+            return Ok(vec![value]);
+        }
+        JsonPath::parse(&self.filter)
+            .and_then(|path| path.evaluate(value))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Raw / Pretty / Table, for the block header.
+pub fn render_mode_toggle(ui: &mut egui::Ui, view: &mut StructuredView) {
+    let Some(parsed) = &view.parsed else {
+        return;
+    };
+    ui.selectable_value(&mut view.mode, StructuredMode::Raw, "Raw")
+        .on_hover_text("Show the output as it came");
+    ui.selectable_value(&mut view.mode, StructuredMode::Pretty, "Pretty")
+        .on_hover_text(format!("Show the {} as indented, colored JSON", parsed.format.name()));
+    if parsed.table.is_some() {
+        ui.selectable_value(&mut view.mode, StructuredMode::Table, "▦ Table")
+            .on_hover_text("Show the objects as rows of a sortable table");
+    }
+}
+
+pub fn render_structured(ui: &mut egui::Ui, block_id: uuid::Uuid, view: &mut StructuredView) {
+    if view.mode == StructuredMode::Table {
+        if let Some(table) = view.parsed.as_mut().and_then(|parsed| parsed.table.as_mut()) {
+            table_view::render_table(ui, block_id, table);
+        }
+        return;
+    }
+
+    view.refresh();
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut view.filter)
+                .id(egui::Id::new(("structured_filter", block_id)))
+                .hint_text(".items[0].metadata.name")
+                .font(egui::TextStyle::Monospace)
+                .desired_width(260.0),
+        )
+        .on_hover_text("A jq-style path: .key, .[\"some key\"], .[0], .[-1] and .[] for every element");
+        let copy = ui
+            .add_enabled(view.error.is_none(), egui::Button::new("📋 Copy as pretty JSON"))
+            .on_hover_text("Copy what's shown, indented");
+        if copy.clicked() {
+            if let Some(parsed) = &view.parsed {
+                if let Ok(values) = view.selection(&parsed.value) {
+                    let json = structured::to_pretty_json(&values);
+                    ui.output_mut(|o| o.copied_text = json);
+                    view.status = Some("Copied to clipboard".to_string());
+                }
+            }
+        }
+        if let Some(status) = &view.status {
+            ui.small(status);
+        }
+    });
+    if let Some(error) = &view.error {
+        ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+        return;
+    }
+    if view.lines.is_empty() {
+        ui.weak("Nothing matches the path.");
+        return;
+    }
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let spacing = ui.spacing().item_spacing.y;
+    egui::ScrollArea::both()
+        .id_source(("structured_rows", block_id))
+        .max_height((row_height + spacing) * PAGER_VISIBLE_LINES as f32)
+        .auto_shrink([false, true])
+        .show_rows(ui, row_height, view.lines.len(), |ui, range| {
+            for line in &view.lines[range] {
+                ui.add(egui::Label::new(line_job(ui, line)).wrap(false));
+            }
+        });
+}
+
+fn line_job(ui: &egui::Ui, line: &[Token]) -> egui::text::LayoutJob {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let mut job = egui::text::LayoutJob::default();
+    for token in line {
+        let color = match token.kind {
+            TokenKind::Key => KEY_COLOR,
+            TokenKind::String => STRING_COLOR,
+            TokenKind::Number => NUMBER_COLOR,
+            TokenKind::Literal => LITERAL_COLOR,
+            TokenKind::Punctuation => ui.visuals().weak_text_color(),
+        };
+        job.append(&token.text, 0.0, egui::TextFormat::simple(font.clone(), color));
+    }
+    job
+}
@@ -0,0 +1,108 @@
+use super::output_pager::PAGER_VISIBLE_LINES;
+use crate::terminal::table::{self, Table};
+use eframe::egui;
+
+/// A finished block's output split into columns, shown as a grid sorted by
+/// a clicked header when turned on. Output that isn't aligned in columns
+/// is left as text.
+#[derive(Debug, Clone, Default)]
+pub struct TableView {
+    pub shown: bool,
+    table: Option<Table>,
+    /// The column sorted by and whether it's descending
+    sort: Option<(usize, bool)>,
+    /// Row indexes in the order shown
+    order: Vec<usize>,
+    checked: bool,
+}
+
+impl TableView {
+    /// Looks for columns in the output of a finished command, once.
+    pub fn update(&mut self, output: &str, is_running: bool) {
+        if self.checked || is_running {
+            return;
+        }
+        self.checked = true;
+        self.table = table::parse_table(output);
+        self.order = self.table.as_ref().map_or_else(Vec::new, |table| (0..table.rows.len()).collect());
+    }
+
+    /// A view of an already parsed table, shown from the start.
+    pub fn from_table(table: Table) -> Self {
+        Self {
+            shown: true,
+            order: (0..table.rows.len()).collect(),
+            table: Some(table),
+            sort: None,
+            checked: true,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.table.is_some()
+    }
+
+    /// Whether the output should be shown as a table.
+    pub fn is_active(&self) -> bool {
+        self.is_available() && self.shown
+    }
+
+    /// Sorts by `column`, ascending first and flipping on the next click.
+    fn sort_by(&mut self, column: usize) {
+        let Some(table) = &self.table else {
+            return;
+        };
+        let descending = self.sort == Some((column, false));
+        self.sort = Some((column, descending));
+        self.order = table.sorted_rows(column, descending);
+    }
+}
+
+/// The Table toggle, for the block header.
+pub fn render_toggle(ui: &mut egui::Ui, view: &mut TableView) {
+    if ui
+        .selectable_label(view.shown, "▦ Table")
+        .on_hover_text("Show the output's columns as a sortable table")
+        .clicked()
+    {
+        view.shown = !view.shown;
+    }
+}
+
+pub fn render_table(ui: &mut egui::Ui, block_id: uuid::Uuid, view: &mut TableView) {
+    let Some(table) = &view.table else {
+        return;
+    };
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let spacing = ui.spacing().item_spacing.y;
+    let mut sort_by = None;
+    egui::ScrollArea::both()
+        .id_source(("table_rows", block_id))
+        .max_height((row_height + spacing) * PAGER_VISIBLE_LINES as f32)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            egui::Grid::new(("table", block_id)).striped(true).show(ui, |ui| {
+                for (column, header) in table.headers.iter().enumerate() {
+                    let arrow = match view.sort {
+                        Some((sorted, false)) if sorted == column => " ⏶",
+                        Some((sorted, true)) if sorted == column => " ⏷",
+                        _ => "",
+                    };
+                    let text = egui::RichText::new(format!("{}{}", header, arrow)).monospace().strong();
+                    if ui.add(egui::Button::new(text).frame(false)).on_hover_text("Sort").clicked() {
+                        sort_by = Some(column);
+                    }
+                }
+                ui.end_row();
+                for &row in &view.order {
+                    for cell in &table.rows[row] {
+                        ui.add(egui::Label::new(egui::RichText::new(cell).monospace()).wrap(false));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    if let Some(column) = sort_by {
+        view.sort_by(column);
+    }
+}
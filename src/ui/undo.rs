@@ -0,0 +1,122 @@
+//! Undo for deleting a block, clearing the terminal and clearing the
+//! command input, with Ctrl/Cmd+Z; see `terminal::undo`.
+
+use super::{AnTraftApp, TerminalBlock};
+use crate::terminal::undo::{self, UndoEntry};
+use eframe::egui;
+use uuid::Uuid;
+
+impl AnTraftApp {
+    /// Deletes a block, output and all, until undone.
+    pub(super) fn delete_block(&mut self, id: Uuid) {
+        if self.terminal_output.iter().any(|block| block.id == id && block.is_running) {
+            self.notifications.sender().info("A running command's block can't be deleted");
+            return;
+        }
+        let removed = undo::remove_blocks(&mut self.terminal_output, |block| block.id == id);
+        if removed.is_empty() {
+            return;
+        }
+        // Pinned again if it comes back
+        self.pins.unpin(id);
+        self.selected_blocks.remove(&id);
+        if self.block_navigation.as_ref().is_some_and(|navigation| navigation.selected == id) {
+            self.block_navigation = None;
+        }
+        self.undo.push(UndoEntry::RemovedBlocks {
+            blocks: removed,
+            cleared: false,
+        });
+        self.notifications.sender().info(format!("Deleted the block · {} to undo", undo_shortcut()));
+    }
+
+    /// Clears the terminal, keeping pinned blocks, until undone.
+    pub(super) fn clear_terminal(&mut self) {
+        let removed = undo::remove_blocks(&mut self.terminal_output, |block| !block.pinned);
+        self.selected_blocks.clear();
+        let pins = &self.pins;
+        self.quick_marks.retain_blocks(|id| pins.contains(id));
+        if !removed.is_empty() {
+            self.undo.push(UndoEntry::RemovedBlocks {
+                blocks: removed,
+                cleared: true,
+            });
+        }
+    }
+
+    /// Empties the command input, until undone.
+    pub(super) fn clear_command_input(&mut self, ctx: &egui::Context) {
+        if self.command_input.is_empty() {
+            return;
+        }
+        let text = std::mem::take(&mut self.command_input);
+        self.set_command_input(ctx, String::new());
+        self.undo.push(UndoEntry::ClearedInput { text });
+    }
+
+    /// Undoes the newest entry: blocks go back where they were, and the
+    /// input gets its text back.
+    pub(super) fn undo(&mut self, ctx: &egui::Context) {
+        let Some(entry) = self.undo.pop() else {
+            self.notifications.sender().info("Nothing to undo");
+            return;
+        };
+        match entry {
+            UndoEntry::RemovedBlocks { blocks, cleared } => {
+                let restored: Vec<(Uuid, bool)> = blocks.iter().map(|(_, block)| (block.id, block.pinned)).collect();
+                undo::restore_blocks(&mut self.terminal_output, blocks);
+                for (id, pinned) in &restored {
+                    if *pinned && !self.pins.contains(*id) {
+                        self.set_block_pinned(*id, true);
+                    }
+                }
+                if let (false, Some((id, _))) = (cleared, restored.first()) {
+                    self.scroll_to_block = Some(*id);
+                }
+                let count = restored.len();
+                self.notifications.sender().info(match cleared {
+                    true => format!("Restored {} block{}", count, if count == 1 { "" } else { "s" }),
+                    false => "Restored the block".to_string(),
+                });
+            }
+            UndoEntry::ClearedInput { text } => self.set_command_input(ctx, text),
+        }
+    }
+
+    /// Ctrl/Cmd+Z undoes when the input is focused and empty, or a block is
+    /// selected. Anywhere else it's the text field's own undo.
+    pub(super) fn handle_undo_keys(&mut self, ctx: &egui::Context) {
+        let input_focused = ctx.memory(|memory| memory.has_focus(Self::command_input_id()));
+        let ours = (input_focused && self.command_input.is_empty()) || self.block_navigation.is_some();
+        if ours && ctx.input_mut(|i| !i.modifiers.shift && i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+            self.undo(ctx);
+        }
+    }
+
+    /// Esc in the input clears it, when there's nothing else for it to close.
+    pub(super) fn handle_clear_input_key(&mut self, ui: &egui::Ui) {
+        let focused = ui.memory(|memory| memory.has_focus(Self::command_input_id()));
+        if focused
+            && !self.completion.is_open()
+            && !self.command_input.is_empty()
+            && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape))
+        {
+            self.clear_command_input(ui.ctx());
+        }
+    }
+}
+
+/// The 🗑 button in a block's header.
+pub(super) fn render_delete(ui: &mut egui::Ui, block: &TerminalBlock) -> bool {
+    !block.is_running
+        && super::accessibility::icon_button(ui, "🗑", &format!("Delete this block ({} brings it back)", undo_shortcut()))
+            .clicked()
+}
+
+fn undo_shortcut() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Cmd+Z"
+    } else {
+        "Ctrl+Z"
+    }
+}
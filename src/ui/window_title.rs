@@ -0,0 +1,96 @@
+use super::AnTraftApp;
+use crate::terminal::title::SessionTitle;
+use crate::terminal::TerminalEvent;
+use eframe::egui;
+use uuid::Uuid;
+
+/// The window title, recomputed when an engine event or the git branch may
+/// have changed it rather than every frame.
+pub struct WindowTitle {
+    /// As last sent to the window
+    shown: Option<String>,
+    stale: bool,
+    /// Set by a running command with OSC 0 or 2, until it finishes
+    program_title: Option<(Uuid, String)>,
+}
+
+impl Default for WindowTitle {
+    fn default() -> Self {
+        Self {
+            shown: None,
+            stale: true,
+            program_title: None,
+        }
+    }
+}
+
+impl WindowTitle {
+    pub fn mark_stale(&mut self) {
+        self.stale = true;
+    }
+
+    /// Notes the events that change what the title shows.
+    pub fn handle_event(&mut self, event: &TerminalEvent) {
+        match event {
+            TerminalEvent::TitleChanged { id, title } => {
+                self.program_title = (!title.is_empty()).then(|| (*id, title.clone()));
+            }
+            TerminalEvent::CommandFinished { id, .. } => {
+                if self.program_title.as_ref().is_some_and(|(titled, _)| titled == id) {
+                    self.program_title = None;
+                }
+            }
+            TerminalEvent::CommandStarted { .. }
+            | TerminalEvent::NewBlock { .. }
+            | TerminalEvent::RemoteChanged { .. }
+            | TerminalEvent::DirectoryChanged { .. } => {}
+            _ => return,
+        }
+        self.stale = true;
+    }
+}
+
+impl AnTraftApp {
+    /// What the title should describe: the latest running command of the
+    /// session, or its directory and branch.
+    fn session_title(&self) -> SessionTitle {
+        if let Some(block) = self.terminal_output.iter().rev().find(|block| block.is_running) {
+            let program_title = self
+                .window_title
+                .program_title
+                .as_ref()
+                .filter(|(id, _)| *id == block.id)
+                .map(|(_, title)| title.clone());
+            return SessionTitle::Running {
+                command: block.input.clone(),
+                directory: block.working_directory.clone(),
+                program_title,
+            };
+        }
+        match &self.remote.info {
+            Some(info) => SessionTitle::Idle {
+                directory: info.directory.clone(),
+                branch: None,
+                remote: Some(info.label()),
+            },
+            None => SessionTitle::Idle {
+                directory: self.working_directory.to_string_lossy().to_string(),
+                branch: self.git_status.context.as_ref().and_then(|context| context.branch.clone()),
+                remote: None,
+            },
+        }
+    }
+
+    /// Sends the title to the window when something changed it.
+    pub(super) fn update_window_title(&mut self, ctx: &egui::Context) {
+        if !self.window_title.stale {
+            return;
+        }
+        self.window_title.stale = false;
+        let title = self.session_title().window_title(dirs::home_dir().as_deref());
+        if self.window_title.shown.as_ref() != Some(&title) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title.shown = Some(title);
+        }
+    }
+}
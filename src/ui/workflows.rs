@@ -0,0 +1,331 @@
+use super::command_review::InsertedFrom;
+use super::{accessibility, AnTraftApp, UIMode};
+use crate::autocomplete::{AutocompleteContext, AutocompleteItem, AutocompleteProvider};
+use crate::workflows::{Placeholder, PlaceholderKind, Workflow};
+use eframe::egui;
+use std::collections::HashMap;
+
+/// A workflow picked from the `!` list, with a field per placeholder. On
+/// confirm the rendered command goes into the input, to be checked and run.
+pub struct WorkflowForm {
+    pub workflow: Workflow,
+    placeholders: Vec<Placeholder>,
+    values: Vec<String>,
+    focus: bool,
+}
+
+/// A workflow being created or edited in the settings panel.
+pub struct WorkflowEditor {
+    /// Name of the workflow being edited, `None` for a new one
+    pub original_name: Option<String>,
+    pub workflow: Workflow,
+    pub error: Option<String>,
+}
+
+impl WorkflowForm {
+    fn values(&self) -> HashMap<String, String> {
+        self.placeholders
+            .iter()
+            .zip(&self.values)
+            .map(|(placeholder, value)| (placeholder.name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+impl AnTraftApp {
+    /// Workflows matching the text after a leading `!` in the terminal input,
+    /// best match first. `None` when the input isn't a workflow query.
+    pub(super) fn workflow_matches(&self) -> Option<Vec<AutocompleteItem>> {
+        if !self.command_input.starts_with('!') {
+            return None;
+        }
+        let context = AutocompleteContext::new(
+            self.working_directory.to_string_lossy().to_string(),
+            self.config.terminal.shell.clone(),
+        )
+        .with_remote(self.remote.is_remote());
+        Some(self.workflow_provider.get_suggestions(&self.command_input, &context))
+    }
+
+    pub(super) fn open_workflow_picker(&mut self) {
+        self.current_mode = UIMode::Terminal;
+        self.command_input = "!".to_string();
+        self.workflow_picker_index = 0;
+    }
+
+    /// Fills in a workflow: straight into the input when it has no
+    /// placeholders, through the form otherwise.
+    pub(super) fn open_workflow(&mut self, name: &str) {
+        self.command_input.clear();
+        self.workflow_picker_index = 0;
+
+        let Some(workflow) = self.workflows.read().unwrap().get(name).cloned() else {
+            return;
+        };
+        let placeholders = match workflow.placeholders() {
+            Ok(placeholders) => placeholders,
+            Err(e) => {
+                log::error!("Workflow '{}' is invalid: {}", workflow.name, e);
+                return;
+            }
+        };
+        if placeholders.is_empty() {
+            self.command_input = workflow.command;
+            self.mark_inserted(InsertedFrom::Workflow);
+            return;
+        }
+        self.workflow_form = Some(WorkflowForm {
+            values: placeholders.iter().map(Placeholder::initial_value).collect(),
+            placeholders,
+            workflow,
+            focus: true,
+        });
+    }
+
+    /// The `!` workflow list shown above the terminal input.
+    pub(super) fn render_workflow_picker(&mut self, ui: &mut egui::Ui, matches: &[AutocompleteItem]) {
+        let mut chosen = None;
+        ui.group(|ui| {
+            if matches.is_empty() {
+                ui.weak("No matching workflows");
+            }
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for (index, item) in matches.iter().enumerate() {
+                    let selected = index == self.workflow_picker_index;
+                    ui.horizontal(|ui| {
+                        let response = ui.selectable_label(selected, &item.text).on_hover_text(&item.insert_text);
+                        if response.clicked() {
+                            chosen = Some(item.text.clone());
+                        }
+                        if !item.description.is_empty() {
+                            ui.weak(&item.description);
+                        }
+                    });
+                }
+            });
+        });
+
+        if let Some(text) = chosen {
+            self.open_workflow(text.trim_start_matches('!'));
+        }
+    }
+
+    /// The form for the placeholders of the workflow being filled in.
+    pub(super) fn render_workflow_form(&mut self, ui: &mut egui::Ui) {
+        let Some(form) = self.workflow_form.as_mut() else {
+            return;
+        };
+
+        let mut insert = false;
+        let mut cancel = ui.input(|i| i.key_pressed(egui::Key::Escape));
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.strong(format!("!{}", form.workflow.name));
+                if !form.workflow.description.is_empty() {
+                    ui.weak(&form.workflow.description);
+                }
+            });
+            ui.monospace(&form.workflow.command);
+
+            let mut enter = false;
+            egui::Grid::new("workflow_form").num_columns(3).show(ui, |ui| {
+                for (index, (placeholder, value)) in form.placeholders.iter().zip(form.values.iter_mut()).enumerate() {
+                    ui.label(&placeholder.name);
+                    match &placeholder.kind {
+                        PlaceholderKind::Enum(options) => {
+                            egui::ComboBox::from_id_source(("workflow_field", index))
+                                .selected_text(value.as_str())
+                                .show_ui(ui, |ui| {
+                                    for option in options {
+                                        ui.selectable_value(value, option.clone(), option);
+                                    }
+                                });
+                        }
+                        PlaceholderKind::Text | PlaceholderKind::Int => {
+                            let response = ui.add(
+                                egui::TextEdit::singleline(value)
+                                    .id_source(("workflow_field", index))
+                                    .desired_width(240.0)
+                                    .hint_text(if placeholder.kind == PlaceholderKind::Int { "number" } else { "" }),
+                            );
+                            if index == 0 && std::mem::take(&mut form.focus) {
+                                response.request_focus();
+                            }
+                            enter |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        }
+                    }
+                    match placeholder.validate(value) {
+                        Err(e) if !value.is_empty() => {
+                            ui.colored_label(egui::Color32::from_rgb(220, 100, 100), e.to_string());
+                        }
+                        _ => {
+                            ui.label("");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+            let rendered = form.workflow.render(&form.values());
+            match &rendered {
+                Ok(command) => {
+                    ui.horizontal(|ui| {
+                        ui.weak("→");
+                        ui.monospace(command);
+                    });
+                }
+                Err(e) => {
+                    ui.weak(e.to_string());
+                }
+            }
+            ui.horizontal(|ui| {
+                if ui.add_enabled(rendered.is_ok(), egui::Button::new("Insert")).clicked() || (enter && rendered.is_ok()) {
+                    insert = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+        if insert {
+            if let Some(form) = self.workflow_form.take() {
+                if let Ok(command) = form.workflow.render(&form.values()) {
+                    self.command_input = command;
+                    self.mark_inserted(InsertedFrom::Workflow);
+                }
+            }
+        } else if cancel {
+            self.workflow_form = None;
+        }
+    }
+
+    /// Opens the workflow editor in settings with `command` filled in.
+    pub(super) fn save_as_workflow(&mut self, command: String) {
+        self.show_settings = true;
+        self.workflow_editor = Some(WorkflowEditor {
+            original_name: None,
+            workflow: Workflow::new("", "", command),
+            error: None,
+        });
+    }
+
+    /// CRUD for workflows, shown in the settings window.
+    pub(super) fn render_workflow_settings(&mut self, ui: &mut egui::Ui) {
+        let mut edit = None;
+        let mut delete = None;
+
+        for workflow in self.workflows.read().unwrap().workflows() {
+            ui.horizontal(|ui| {
+                ui.label(format!("!{}", workflow.name)).on_hover_text(&workflow.command);
+                if workflow.builtin {
+                    ui.weak("built-in");
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .add_enabled(!workflow.builtin, egui::Button::new("🗑").small())
+                        .on_hover_text("Delete")
+                        .clicked()
+                    {
+                        delete = Some(workflow.name.clone());
+                    }
+                    if accessibility::icon_button(ui, "✏", "Edit").clicked() {
+                        edit = Some(workflow.clone());
+                    }
+                });
+            });
+        }
+
+        if ui.button("➕ New workflow").clicked() {
+            self.workflow_editor = Some(WorkflowEditor {
+                original_name: None,
+                workflow: Workflow::new("", "", ""),
+                error: None,
+            });
+        }
+
+        if let Some(workflow) = edit {
+            self.workflow_editor = Some(WorkflowEditor {
+                original_name: Some(workflow.name.clone()),
+                workflow,
+                error: None,
+            });
+        }
+        if let Some(name) = delete {
+            if let Err(e) = self.workflows.write().unwrap().delete(&name) {
+                log::error!("Failed to delete workflow: {}", e);
+            }
+        }
+
+        self.render_workflow_editor(ui);
+    }
+
+    fn render_workflow_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = self.workflow_editor.as_mut() else {
+            return;
+        };
+
+        let mut save = false;
+        let mut cancel = false;
+        ui.separator();
+        ui.group(|ui| {
+            egui::Grid::new("workflow_editor").num_columns(2).show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut editor.workflow.name);
+                ui.end_row();
+                ui.label("Description");
+                ui.text_edit_singleline(&mut editor.workflow.description);
+                ui.end_row();
+            });
+            ui.label("Command");
+            ui.add(
+                egui::TextEdit::multiline(&mut editor.workflow.command)
+                    .code_editor()
+                    .desired_rows(2)
+                    .desired_width(f32::INFINITY),
+            );
+            ui.small("Placeholders: {{name}}, {{name=default}}, {{count:int=10}}, {{env:enum(dev|prod)}}. Write \\{{ for a literal {{.");
+            if let Some(error) = &editor.error {
+                ui.colored_label(egui::Color32::from_rgb(220, 100, 100), error);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    save = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+        if cancel {
+            self.workflow_editor = None;
+        } else if save {
+            let workflow = editor.workflow.clone();
+            let renamed_from = editor
+                .original_name
+                .clone()
+                .filter(|original| *original != workflow.name);
+
+            let mut workflows = self.workflows.write().unwrap();
+            match workflows.save(workflow) {
+                Ok(()) => {
+                    if let Some(original) = renamed_from {
+                        // Renaming a built-in just adds a copy under the new name
+                        if workflows.workflows().iter().any(|w| w.name == original && !w.builtin) {
+                            if let Err(e) = workflows.delete(&original) {
+                                log::error!("Failed to remove renamed workflow: {}", e);
+                            }
+                        }
+                    }
+                    self.workflow_editor = None;
+                }
+                Err(e) => {
+                    if let Some(editor) = self.workflow_editor.as_mut() {
+                        editor.error = Some(format!("{:#}", e));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+//! Recent workspaces on the welcome screen and in the switcher, and opening
+//! one: the file explorer re-rooted there, a new terminal session in it and
+//! completions asked again from there, without restarting.
+
+use super::{AnTraftApp, UIMode};
+use crate::autocomplete::completion::CompletionState;
+use crate::file_explorer::FileExplorer;
+use crate::terminal::{Block, TerminalEvent};
+use crate::workspaces::{self, ProjectType, RecentWorkspace, RecentWorkspaces};
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// How many recent workspaces fit on the welcome screen.
+const WELCOME_WORKSPACES: usize = 4;
+
+const STALE_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 160, 60);
+
+/// The recent workspaces, and the "Switch Workspace" window.
+pub struct Workspaces {
+    recent: RecentWorkspaces,
+    pub open: bool,
+    filter: String,
+    /// The folder picked in the "Open folder…" dialog, or `None` when it
+    /// was cancelled
+    picked_rx: Option<crossbeam_channel::Receiver<Option<PathBuf>>>,
+    /// Detected once per workspace rather than every frame
+    project_types: HashMap<PathBuf, ProjectType>,
+}
+
+impl Workspaces {
+    pub fn new(recent: RecentWorkspaces) -> Self {
+        Self {
+            recent,
+            open: false,
+            filter: String::new(),
+            picked_rx: None,
+            project_types: HashMap::new(),
+        }
+    }
+
+    fn project_type(&mut self, path: &Path) -> ProjectType {
+        *self
+            .project_types
+            .entry(path.to_path_buf())
+            .or_insert_with(|| ProjectType::detect(path))
+    }
+}
+
+enum WorkspaceAction {
+    Open(PathBuf),
+    Remove(PathBuf),
+    PickFolder,
+}
+
+impl AnTraftApp {
+    pub(super) fn remember_workspace(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.workspaces.recent.record(&path, chrono::Utc::now());
+        self.save_workspaces();
+    }
+
+    /// Moves the recent workspaces older versions kept in the config into
+    /// `workspaces.json`, which `RecentWorkspaces::load` read them into.
+    pub(super) fn import_legacy_workspaces(&mut self) {
+        if self.config.workspaces.recent.is_empty() {
+            return;
+        }
+        self.save_workspaces();
+        self.config.workspaces.recent.clear();
+        self.save_config();
+    }
+
+    fn forget_workspace(&mut self, path: &Path) {
+        if self.workspaces.recent.remove(path) {
+            self.save_workspaces();
+        }
+    }
+
+    fn save_workspaces(&self) {
+        if let Err(e) = self.workspaces.recent.save() {
+            self.notifications
+                .sender()
+                .error("Couldn't save recent workspaces", format!("{:#}", e));
+        }
+    }
+
+    /// Makes `path` the workspace: the file explorer's root, and the
+    /// directory of a new terminal session that becomes the active one.
+    pub(super) fn open_workspace(&mut self, path: PathBuf) {
+        if !path.is_dir() {
+            self.notifications
+                .sender()
+                .warning("That workspace no longer exists", path.display().to_string());
+            return;
+        }
+
+        // The watcher moves over when the explorer isn't busy loading; one
+        // that's busy is being replaced anyway
+        let explorer = match self.file_explorer.try_write() {
+            Ok(mut explorer) => explorer.rerooted(path.clone()),
+            Err(_) => FileExplorer::new(path.clone()),
+        };
+        match explorer {
+            Ok(explorer) => {
+                if let Some(load) = self.tree_load.take() {
+                    load.cancel();
+                }
+                self.file_explorer = Arc::new(RwLock::new(explorer));
+                self.file_tree_loaded = false;
+                self.tree_load_cancelled = false;
+            }
+            Err(e) => {
+                self.notifications
+                    .sender()
+                    .error(format!("Couldn't open {}", path.display()), format!("{:#}", e));
+                return;
+            }
+        }
+
+        let engine = self.terminal_engine.clone();
+        let event_sender = self.terminal_event_tx.clone();
+        let target = path.clone();
+        self.spawn_reported("Opening the workspace", async move {
+            let block = match engine.open_session_in(&target).await {
+                Ok((_, directory)) => Block::system(format!("Opened workspace: {}", directory)),
+                Err(e) => Block::error(format!("{:#}", e)),
+            };
+            let _ = event_sender.send(TerminalEvent::NewBlock { block });
+        });
+
+        // The new session is local and starts here; completions are asked
+        // again for the next input with the new directory and repository
+        self.working_directory = path.clone();
+        self.remote.set_info(None);
+        self.completion = CompletionState::default();
+        self.git_status.context = None;
+        self.refresh_git_status();
+
+        self.remember_workspace(&path);
+        self.workspaces.open = false;
+        self.current_mode = UIMode::Terminal;
+    }
+
+    /// Opens the native folder picker; the folder picked is opened as the
+    /// workspace when `poll_workspaces` sees it.
+    pub(super) fn pick_workspace_folder(&mut self) {
+        if self.workspaces.picked_rx.is_some() {
+            return;
+        }
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.workspaces.picked_rx = Some(rx);
+        let directory = self.working_directory.clone();
+        self.spawn_reported("The folder picker", async move {
+            let picked = rfd::AsyncFileDialog::new()
+                .set_title("Open folder")
+                .set_directory(directory)
+                .pick_folder()
+                .await;
+            let _ = tx.send(picked.map(|folder| folder.path().to_path_buf()));
+        });
+    }
+
+    pub(super) fn poll_workspaces(&mut self) {
+        let Some(rx) = &self.workspaces.picked_rx else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(picked) => {
+                self.workspaces.picked_rx = None;
+                if let Some(path) = picked {
+                    self.open_workspace(path);
+                }
+            }
+            Err(crossbeam_channel::TryRecvError::Disconnected) => self.workspaces.picked_rx = None,
+            Err(crossbeam_channel::TryRecvError::Empty) => {}
+        }
+    }
+
+    pub(super) fn open_workspace_switcher(&mut self) {
+        self.workspaces.open = true;
+        self.workspaces.filter.clear();
+    }
+
+    fn apply_workspace_action(&mut self, action: WorkspaceAction) {
+        match action {
+            WorkspaceAction::Open(path) => self.open_workspace(path),
+            WorkspaceAction::Remove(path) => self.forget_workspace(&path),
+            WorkspaceAction::PickFolder => self.pick_workspace_folder(),
+        }
+    }
+
+    pub(super) fn render_recent_workspaces(&mut self, ui: &mut egui::Ui) {
+        let recent: Vec<RecentWorkspace> = self
+            .workspaces
+            .recent
+            .entries()
+            .iter()
+            .take(WELCOME_WORKSPACES)
+            .cloned()
+            .collect();
+        let now = chrono::Utc::now();
+
+        ui.add_space(30.0);
+        ui.label("Recent workspaces");
+        ui.add_space(10.0);
+
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.add_space(50.0);
+            for workspace in &recent {
+                if workspace.is_missing() {
+                    if render_stale_card(ui, workspace) {
+                        action = Some(WorkspaceAction::Remove(workspace.path.clone()));
+                    }
+                } else {
+                    let project_type = self.workspaces.project_type(&workspace.path);
+                    let used = workspace
+                        .last_used
+                        .map(|last_used| workspaces::format_last_used(last_used, now))
+                        .unwrap_or_else(|| project_type.label().to_string());
+                    let location = workspace
+                        .path
+                        .parent()
+                        .map(|parent| parent.display().to_string())
+                        .unwrap_or_default();
+                    let description = format!("{}\n{}", location, used);
+                    if self.render_action_card(ui, project_type.icon(), &workspace.name(), &description) {
+                        action = Some(WorkspaceAction::Open(workspace.path.clone()));
+                    }
+                }
+                ui.add_space(20.0);
+            }
+
+            if self.render_action_card(ui, "📂", "Open folder…", "Pick a folder to work in") {
+                action = Some(WorkspaceAction::PickFolder);
+            }
+        });
+
+        if let Some(action) = action {
+            self.apply_workspace_action(action);
+        }
+    }
+
+    pub(super) fn render_workspace_switcher(&mut self, ctx: &egui::Context) {
+        if !self.workspaces.open {
+            return;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.workspaces.open = false;
+            return;
+        }
+
+        let now = chrono::Utc::now();
+        let filter = self.workspaces.filter.to_lowercase();
+        let matches: Vec<RecentWorkspace> = self
+            .workspaces
+            .recent
+            .entries()
+            .iter()
+            .filter(|workspace| workspace.path.to_string_lossy().to_lowercase().contains(&filter))
+            .cloned()
+            .collect();
+
+        let mut action = None;
+        let mut open = true;
+        egui::Window::new("🗂 Switch Workspace")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([520.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.workspaces.filter)
+                        .hint_text("Find a workspace…")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    action = matches
+                        .iter()
+                        .find(|workspace| !workspace.is_missing())
+                        .map(|workspace| WorkspaceAction::Open(workspace.path.clone()));
+                }
+                ui.separator();
+
+                if matches.is_empty() {
+                    ui.weak("No recent workspaces");
+                }
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    for workspace in &matches {
+                        ui.horizontal(|ui| {
+                            if workspace.is_missing() {
+                                ui.colored_label(STALE_COLOR, "⚠")
+                                    .on_hover_text("This directory no longer exists");
+                                ui.add_enabled(false, egui::Button::new(workspace.name()).frame(false));
+                                if ui.small_button("Remove").clicked() {
+                                    action = Some(WorkspaceAction::Remove(workspace.path.clone()));
+                                }
+                            } else {
+                                let project_type = self.workspaces.project_type(&workspace.path);
+                                ui.label(project_type.icon()).on_hover_text(project_type.label());
+                                if ui
+                                    .add(egui::Button::new(egui::RichText::new(workspace.name()).strong()).frame(false))
+                                    .clicked()
+                                {
+                                    action = Some(WorkspaceAction::Open(workspace.path.clone()));
+                                }
+                                if let Some(last_used) = workspace.last_used {
+                                    ui.weak(workspaces::format_last_used(last_used, now));
+                                }
+                            }
+                        });
+                        ui.weak(workspace.path.display().to_string());
+                        ui.add_space(4.0);
+                    }
+                });
+
+                ui.separator();
+                if ui.button("📂 Open folder…").clicked() {
+                    action = Some(WorkspaceAction::PickFolder);
+                }
+            });
+
+        if !open {
+            self.workspaces.open = false;
+        }
+        if let Some(action) = action {
+            self.apply_workspace_action(action);
+        }
+    }
+}
+
+/// A recent workspace whose directory is gone, with a button to remove it;
+/// returns whether that was clicked.
+fn render_stale_card(ui: &mut egui::Ui, workspace: &RecentWorkspace) -> bool {
+    let mut remove = false;
+    ui.allocate_ui_with_layout([180.0, 120.0].into(), egui::Layout::top_down(egui::Align::Center), |ui| {
+        egui::Frame::none()
+            .stroke(egui::Stroke::new(1.0, STALE_COLOR))
+            .rounding(egui::Rounding::same(8.0))
+            .inner_margin(egui::Margin::same(8.0))
+            .show(ui, |ui| {
+                ui.set_min_size([164.0, 104.0].into());
+                ui.vertical_centered(|ui| {
+                    ui.colored_label(STALE_COLOR, "⚠");
+                    ui.strong(workspace.name());
+                    ui.weak("No longer exists")
+                        .on_hover_text(workspace.path.display().to_string());
+                    remove = ui.small_button("Remove").clicked();
+                });
+            });
+    });
+    remove
+}
@@ -0,0 +1,379 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A saved command with placeholders the user fills in before running it,
+/// like Warp's workflows. Placeholders are written `{{name}}`, with an
+/// optional type and default: `{{lines:int=100}}`,
+/// `{{shell:enum(sh|bash|zsh)=sh}}` or `{{path=.}}`. Write `\{{` for a
+/// literal `{{`. Anything in braces that doesn't start with a placeholder
+/// name, like `docker inspect -f '{{.State.Status}}'`, is left as it is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workflow {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub command: String,
+    /// Shipped with ANTRAFT rather than loaded from the workflows directory
+    #[serde(skip)]
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaceholderKind {
+    Text,
+    Int,
+    /// One of a fixed set of values
+    Enum(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub kind: PlaceholderKind,
+    /// `Some("")` for `{{name=}}`, which may be left empty
+    pub default: Option<String>,
+}
+
+impl Placeholder {
+    /// The value a form field starts with.
+    pub fn initial_value(&self) -> String {
+        match (&self.default, &self.kind) {
+            (Some(default), _) => default.clone(),
+            (None, PlaceholderKind::Enum(options)) => options.first().cloned().unwrap_or_default(),
+            (None, _) => String::new(),
+        }
+    }
+
+    pub fn validate(&self, value: &str) -> Result<()> {
+        if value.is_empty() {
+            return match self.default.as_deref() {
+                Some("") => Ok(()),
+                _ => Err(anyhow!("{} needs a value", self.name)),
+            };
+        }
+        match &self.kind {
+            PlaceholderKind::Text => Ok(()),
+            PlaceholderKind::Int => value
+                .trim()
+                .parse::<i64>()
+                .map(|_| ())
+                .map_err(|_| anyhow!("{} must be a whole number", self.name)),
+            PlaceholderKind::Enum(options) if options.iter().any(|option| option == value) => Ok(()),
+            PlaceholderKind::Enum(options) => Err(anyhow!("{} must be one of {}", self.name, options.join(", "))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(Placeholder),
+}
+
+impl Workflow {
+    pub fn new(name: impl Into<String>, description: impl Into<String>, command: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            command: command.into(),
+            builtin: false,
+        }
+    }
+
+    /// The command's placeholders, in order of first use. A placeholder used
+    /// more than once takes its type and default from the first use.
+    pub fn placeholders(&self) -> Result<Vec<Placeholder>> {
+        let mut placeholders: Vec<Placeholder> = Vec::new();
+        for segment in parse(&self.command)? {
+            if let Segment::Placeholder(placeholder) = segment {
+                if !placeholders.iter().any(|p| p.name == placeholder.name) {
+                    placeholders.push(placeholder);
+                }
+            }
+        }
+        Ok(placeholders)
+    }
+
+    /// Checks the name and that the placeholders parse and their defaults
+    /// are valid values.
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Workflow name can't be empty");
+        }
+        if self.command.trim().is_empty() {
+            bail!("Workflow command can't be empty");
+        }
+        for placeholder in self.placeholders()? {
+            if let Some(default) = placeholder.default.as_deref().filter(|d| !d.is_empty()) {
+                placeholder
+                    .validate(default)
+                    .with_context(|| format!("Invalid default for {}", placeholder.name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Substitutes `values` into the command, as typed; they aren't quoted
+    /// for the shell. Fails if a value is missing or invalid.
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
+        for placeholder in self.placeholders()? {
+            placeholder.validate(values.get(&placeholder.name).map(String::as_str).unwrap_or_default())?;
+        }
+
+        let mut rendered = String::with_capacity(self.command.len());
+        for segment in parse(&self.command)? {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    rendered.push_str(values.get(&placeholder.name).map(String::as_str).unwrap_or_default())
+                }
+            }
+        }
+        Ok(rendered)
+    }
+
+    fn file_name(&self) -> String {
+        let slug: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect();
+        format!("{}.toml", slug)
+    }
+}
+
+/// Splits a command into literal text and placeholders. `\{{` is an escaped
+/// `{{`; braces that don't hold a placeholder name are kept as text, but a
+/// placeholder with an unknown type is an error rather than a silent
+/// literal.
+fn parse(command: &str) -> Result<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < command.len() {
+        let rest = &command[i..];
+        if rest.starts_with("\\{{") {
+            if text_start < i {
+                segments.push(Segment::Text(&command[text_start..i]));
+            }
+            segments.push(Segment::Text("{{"));
+            i += 3;
+            text_start = i;
+            continue;
+        }
+        if let Some(after_open) = rest.strip_prefix("{{") {
+            if let Some(end) = after_open.find("}}") {
+                if let Some(placeholder) = parse_placeholder(&after_open[..end])? {
+                    if text_start < i {
+                        segments.push(Segment::Text(&command[text_start..i]));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                    i += 2 + end + 2;
+                    text_start = i;
+                    continue;
+                }
+            }
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+
+    if text_start < command.len() {
+        segments.push(Segment::Text(&command[text_start..]));
+    }
+    Ok(segments)
+}
+
+/// Parses `name`, `name:type`, `name=default` or `name:type=default`.
+/// `None` when the text doesn't start with a placeholder name.
+fn parse_placeholder(spec: &str) -> Result<Option<Placeholder>> {
+    let (head, default) = match spec.split_once('=') {
+        Some((head, default)) => (head, Some(default.to_string())),
+        None => (spec, None),
+    };
+    let (name, kind) = match head.split_once(':') {
+        Some((name, kind)) => (name.trim(), Some(kind.trim())),
+        None => (head.trim(), None),
+    };
+    if !is_placeholder_name(name) {
+        return Ok(None);
+    }
+
+    let kind = match kind {
+        None | Some("text") => PlaceholderKind::Text,
+        Some("int") => PlaceholderKind::Int,
+        Some(kind) => {
+            let options = kind
+                .strip_prefix("enum(")
+                .and_then(|options| options.strip_suffix(')'))
+                .ok_or_else(|| anyhow!("Unknown type '{}' for {}; use text, int or enum(a|b)", kind, name))?;
+            let options: Vec<String> = options
+                .split('|')
+                .map(|option| option.trim().to_string())
+                .filter(|option| !option.is_empty())
+                .collect();
+            if options.is_empty() {
+                bail!("enum for {} has no values", name);
+            }
+            PlaceholderKind::Enum(options)
+        }
+    };
+    Ok(Some(Placeholder {
+        name: name.to_string(),
+        kind,
+        default,
+    }))
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+pub fn builtin_workflows() -> Vec<Workflow> {
+    [
+        (
+            "kubectl-logs",
+            "Tail the logs of a Kubernetes pod",
+            "kubectl logs -n {{namespace=default}} {{pod}} --tail {{lines:int=100}}",
+        ),
+        (
+            "docker-shell",
+            "Open a shell in a running container",
+            "docker exec -it {{container}} {{shell:enum(sh|bash|zsh)=sh}}",
+        ),
+        (
+            "git-undo-commits",
+            "Undo recent commits, keeping their changes staged",
+            "git reset --soft HEAD~{{count:int=1}}",
+        ),
+        (
+            "find-large-files",
+            "Find files bigger than a size",
+            "find {{path=.}} -type f -size +{{megabytes:int=100}}M",
+        ),
+        (
+            "port-owner",
+            "Show which process is listening on a port",
+            "lsof -nP -i :{{port:int}}",
+        ),
+        (
+            "ssh-tunnel",
+            "Forward a local port to a port on a remote host",
+            "ssh -N -L {{local_port:int}}:localhost:{{remote_port:int}} {{host}}",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, description, command)| Workflow {
+        builtin: true,
+        ..Workflow::new(name, description, command)
+    })
+    .collect()
+}
+
+pub fn default_workflows_dir() -> PathBuf {
+    crate::config::config_dir().join("workflows")
+}
+
+/// Built-in workflows plus the user's, loaded from one TOML file per
+/// workflow. A user workflow with the same name as a built-in replaces it.
+pub struct WorkflowStore {
+    dir: PathBuf,
+    workflows: Vec<Workflow>,
+}
+
+impl WorkflowStore {
+    /// A store with only the built-in workflows.
+    pub fn builtin(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            workflows: builtin_workflows(),
+        }
+    }
+
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut store = Self::builtin(dir);
+
+        if !dir.exists() {
+            return Ok(store);
+        }
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read workflows directory {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| toml::from_str::<Workflow>(&content).map_err(Into::into))
+                .and_then(|workflow| workflow.validate().map(|_| workflow))
+            {
+                Ok(workflow) => store.insert(workflow),
+                Err(e) => warn!("Skipping invalid workflow {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(store)
+    }
+
+    pub fn workflows(&self) -> &[Workflow] {
+        &self.workflows
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Workflow> {
+        self.workflows.iter().find(|w| w.name == name)
+    }
+
+    /// Writes `workflow` to the workflows directory and adds or replaces it
+    /// in the store.
+    pub fn save(&mut self, mut workflow: Workflow) -> Result<()> {
+        workflow.validate()?;
+        workflow.builtin = false;
+
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(workflow.file_name());
+        std::fs::write(&path, toml::to_string_pretty(&workflow)?)
+            .with_context(|| format!("Failed to write workflow {}", path.display()))?;
+
+        self.insert(workflow);
+        Ok(())
+    }
+
+    /// Deletes a user workflow. Deleting an override of a built-in restores
+    /// the built-in; built-ins themselves can't be deleted.
+    pub fn delete(&mut self, name: &str) -> Result<()> {
+        let Some(index) = self.workflows.iter().position(|w| w.name == name) else {
+            return Err(anyhow!("No workflow named '{}'", name));
+        };
+        if self.workflows[index].builtin {
+            return Err(anyhow!("Built-in workflow '{}' can't be deleted", name));
+        }
+
+        let path = self.dir.join(self.workflows[index].file_name());
+        if path.exists() {
+            std::fs::remove_file(&path).with_context(|| format!("Failed to delete workflow {}", path.display()))?;
+        }
+
+        match builtin_workflows().into_iter().find(|w| w.name == name) {
+            Some(builtin) => self.workflows[index] = builtin,
+            None => {
+                self.workflows.remove(index);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, workflow: Workflow) {
+        match self.workflows.iter_mut().find(|w| w.name == workflow.name) {
+            Some(existing) => *existing = workflow,
+            None => self.workflows.push(workflow),
+        }
+    }
+}
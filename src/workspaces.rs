@@ -0,0 +1,210 @@
+//! The workspace roots used lately: the directories sessions started in
+//! and the file explorer was rooted at, most recent first, kept in
+//! `workspaces.json` in the data directory for the welcome screen and the
+//! workspace switcher.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub fn default_workspaces_path() -> PathBuf {
+    crate::config::data_dir().join("workspaces.json")
+}
+
+/// What a workspace holds, told from the files at its root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectType {
+    Rust,
+    Node,
+    Python,
+    Go,
+    Java,
+    Ruby,
+    Php,
+    DotNet,
+    /// A git repository of none of the kinds above
+    Git,
+    Other,
+}
+
+/// The files that mark each project type, checked in order.
+const PROJECT_MARKERS: &[(ProjectType, &[&str])] = &[
+    (ProjectType::Rust, &["Cargo.toml"]),
+    (ProjectType::Node, &["package.json"]),
+    (ProjectType::Python, &["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"]),
+    (ProjectType::Go, &["go.mod"]),
+    (ProjectType::Java, &["pom.xml", "build.gradle", "build.gradle.kts"]),
+    (ProjectType::Ruby, &["Gemfile"]),
+    (ProjectType::Php, &["composer.json"]),
+];
+
+impl ProjectType {
+    /// The type of the project at `dir`. .NET projects are named after
+    /// themselves, so any `*.sln` or `*.csproj` file counts.
+    pub fn detect(dir: &Path) -> Self {
+        let marked = |markers: &[&str]| markers.iter().any(|marker| dir.join(marker).exists());
+        if let Some((project_type, _)) = PROJECT_MARKERS.iter().find(|(_, markers)| marked(markers)) {
+            return *project_type;
+        }
+        if Self::is_dotnet(dir) {
+            ProjectType::DotNet
+        } else if dir.join(".git").exists() {
+            ProjectType::Git
+        } else {
+            ProjectType::Other
+        }
+    }
+
+    fn is_dotnet(dir: &Path) -> bool {
+        std::fs::read_dir(dir).into_iter().flatten().flatten().any(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.ends_with(".sln") || name.ends_with(".csproj")
+        })
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            ProjectType::Rust => "🦀",
+            ProjectType::Node => "⬢",
+            ProjectType::Python => "🐍",
+            ProjectType::Go => "🐹",
+            ProjectType::Java => "☕",
+            ProjectType::Ruby => "💎",
+            ProjectType::Php => "🐘",
+            ProjectType::DotNet => "#",
+            ProjectType::Git => "🌿",
+            ProjectType::Other => "📂",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectType::Rust => "Rust",
+            ProjectType::Node => "Node.js",
+            ProjectType::Python => "Python",
+            ProjectType::Go => "Go",
+            ProjectType::Java => "Java",
+            ProjectType::Ruby => "Ruby",
+            ProjectType::Php => "PHP",
+            ProjectType::DotNet => ".NET",
+            ProjectType::Git => "Git repository",
+            ProjectType::Other => "Folder",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentWorkspace {
+    pub path: PathBuf,
+    /// `None` for entries carried over from `workspaces.recent` in the
+    /// config, which didn't keep times
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl RecentWorkspace {
+    /// The directory's name, or the whole path for a root like `/`.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+
+    /// Whether the directory is gone, leaving the entry stale.
+    pub fn is_missing(&self) -> bool {
+        !self.path.is_dir()
+    }
+}
+
+/// The recent workspaces, capped at `max` and saved with every change.
+#[derive(Debug)]
+pub struct RecentWorkspaces {
+    path: PathBuf,
+    entries: Vec<RecentWorkspace>,
+    max: usize,
+}
+
+impl RecentWorkspaces {
+    /// No workspaces, saved to `path`.
+    pub fn new(path: &Path, max: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            entries: Vec::new(),
+            max,
+        }
+    }
+
+    /// The workspaces saved in `path`; when there's no such file yet, the
+    /// `legacy` list older versions kept in the config.
+    pub fn load(path: &Path, max: usize, legacy: &[PathBuf]) -> Result<Self> {
+        let mut workspaces = Self::new(path, max);
+        if !path.exists() {
+            workspaces.entries = legacy
+                .iter()
+                .take(max)
+                .map(|path| RecentWorkspace {
+                    path: path.clone(),
+                    last_used: None,
+                })
+                .collect();
+            return Ok(workspaces);
+        }
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        workspaces.entries =
+            serde_json::from_str(&content).with_context(|| format!("Invalid workspaces in {}", path.display()))?;
+        workspaces.entries.truncate(max);
+        Ok(workspaces)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Most recent first.
+    pub fn entries(&self) -> &[RecentWorkspace] {
+        &self.entries
+    }
+
+    /// Moves `dir` to the front as used at `now`, dropping the oldest past
+    /// the cap.
+    pub fn record(&mut self, dir: &Path, now: DateTime<Utc>) {
+        self.entries.retain(|entry| entry.path != dir);
+        self.entries.insert(
+            0,
+            RecentWorkspace {
+                path: dir.to_path_buf(),
+                last_used: Some(now),
+            },
+        );
+        self.entries.truncate(self.max);
+    }
+
+    /// Returns whether `dir` was there to remove.
+    pub fn remove(&mut self, dir: &Path) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.path != dir);
+        self.entries.len() != before
+    }
+}
+
+/// How long before `now` a workspace was last used, like "5 min ago" or
+/// "3 days ago"; the date past a month.
+pub fn format_last_used(last_used: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(last_used);
+    match elapsed.num_minutes() {
+        minutes if minutes < 1 => "just now".to_string(),
+        minutes if minutes < 60 => format!("{} min ago", minutes),
+        minutes if minutes < 24 * 60 => format!("{} h ago", minutes / 60),
+        _ => match elapsed.num_days() {
+            1 => "yesterday".to_string(),
+            days if days < 30 => format!("{} days ago", days),
+            _ => last_used.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string(),
+        },
+    }
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "gui")]
+
+use antraft::autocomplete::highlight::HighlightClass;
+use antraft::config::{AccessibilityConfig, Config};
+use antraft::ui::accessibility::{self, contrast_ratio, Palette};
+use eframe::egui;
+
+fn key(key: egui::Key) -> egui::Event {
+    egui::Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: egui::Modifiers::NONE,
+    }
+}
+
+fn frame(ctx: &egui::Context, events: Vec<egui::Event>, mut ui: impl FnMut(&mut egui::Ui)) -> egui::FullOutput {
+    let input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0))),
+        events,
+        ..Default::default()
+    };
+    ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |panel| ui(panel));
+    })
+}
+
+#[test]
+fn the_high_contrast_palette_reads_at_seven_to_one() {
+    let palette = Palette::HIGH_CONTRAST;
+    for (name, color) in palette.foregrounds() {
+        let ratio = contrast_ratio(color, palette.background);
+        assert!(ratio >= 7.0, "{} is {:.2}:1", name, ratio);
+    }
+    // And is an improvement on the standard palette
+    let standard = Palette::STANDARD;
+    assert!(contrast_ratio(palette.muted, palette.background) > contrast_ratio(standard.muted, standard.background));
+    assert!((contrast_ratio(egui::Color32::WHITE, egui::Color32::BLACK) - 21.0).abs() < 0.01);
+}
+
+#[test]
+fn high_contrast_syntax_colors_read_at_seven_to_one() {
+    let palette = Palette::HIGH_CONTRAST;
+    for class in HighlightClass::ALL {
+        let ratio = contrast_ratio(palette.syntax.color(class), palette.background);
+        assert!(ratio >= 7.0, "{} is {:.2}:1", class.name(), ratio);
+    }
+}
+
+#[test]
+fn the_style_follows_the_settings() {
+    let ctx = egui::Context::default();
+    let settings = AccessibilityConfig {
+        high_contrast: true,
+        reduced_motion: true,
+        min_font_size: 18.0,
+    };
+    accessibility::apply_style(&ctx, &settings);
+    let style = ctx.style();
+    assert!(style.text_styles.values().all(|font| font.size >= 18.0));
+    assert_eq!(style.animation_time, 0.0);
+    assert_eq!(style.visuals.override_text_color, Some(egui::Color32::WHITE));
+    assert!(accessibility::reduced_motion(&ctx));
+
+    accessibility::apply_style(&ctx, &AccessibilityConfig::default());
+    let style = ctx.style();
+    assert!(style.text_styles[&egui::TextStyle::Small].size < 18.0);
+    assert!(style.animation_time > 0.0);
+    assert_eq!(style.visuals.override_text_color, None);
+    assert!(!accessibility::reduced_motion(&ctx));
+}
+
+#[test]
+fn spinners_hold_still_when_motion_is_reduced() {
+    // Whether the last of a few frames asked for the next one straight away
+    let repaints = |settings: AccessibilityConfig| {
+        let ctx = egui::Context::default();
+        accessibility::apply_style(&ctx, &settings);
+        let mut output = None;
+        for _ in 0..3 {
+            output = Some(frame(&ctx, vec![], |ui| {
+                accessibility::spinner(ui);
+            }));
+        }
+        output.unwrap().viewport_output.values().any(|viewport| viewport.repaint_delay.is_zero())
+    };
+    assert!(!repaints(AccessibilityConfig { reduced_motion: true, ..Default::default() }));
+    assert!(repaints(AccessibilityConfig::default()));
+}
+
+#[test]
+fn icon_buttons_are_reached_with_tab_and_pressed_with_enter() {
+    let ctx = egui::Context::default();
+    let mut clicked = Vec::new();
+    let mut focused = None;
+    let steps = [vec![], vec![key(egui::Key::Tab)], vec![key(egui::Key::Tab)], vec![key(egui::Key::Enter)], vec![]];
+    for events in steps {
+        frame(&ctx, events, |ui| {
+            for (icon, label) in [("✏", "Edit"), ("✖", "Unpin")] {
+                let response = accessibility::icon_button(ui, icon, label);
+                if response.has_focus() {
+                    focused = Some(label);
+                }
+                if response.clicked() {
+                    clicked.push(label);
+                }
+            }
+        });
+    }
+    assert_eq!(focused, Some("Unpin"));
+    assert_eq!(clicked, ["Unpin"]);
+}
+
+#[test]
+fn screen_readers_get_the_icon_buttons_labels() {
+    let ctx = egui::Context::default();
+    ctx.enable_accesskit();
+    let output = frame(&ctx, vec![], |ui| {
+        accessibility::icon_button(ui, "⟳", "Refresh");
+    });
+    let update = output.platform_output.accesskit_update.expect("no accesskit update");
+    let button = update
+        .nodes
+        .iter()
+        .map(|(_, node)| node)
+        .find(|node| node.role() == egui::accesskit::Role::Button)
+        .expect("no button");
+    assert_eq!(button.name(), Some("Refresh"));
+}
+
+#[test]
+fn the_font_floor_is_validated() {
+    let mut config = Config::default();
+    config.accessibility.min_font_size = 16.0;
+    config.validate().unwrap();
+    config.accessibility.min_font_size = 90.0;
+    let error = format!("{:#}", config.validate().unwrap_err());
+    assert!(error.contains("`accessibility.min_font_size` must be between 0 and 32, not 90"), "{}", error);
+}
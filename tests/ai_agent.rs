@@ -0,0 +1,108 @@
+mod common;
+
+use antraft::ai::agent::CHAT_CONTEXT_MESSAGES;
+use antraft::ai::chat::MessageRole;
+use antraft::ai::{AiAgent, AiRequest};
+use common::{ai_config, MockProvider};
+
+fn agent(provider: &MockProvider) -> AiAgent {
+    AiAgent::with_provider(ai_config(), Box::new(provider.clone())).with_shell("bash")
+}
+
+async fn chat(agent: &AiAgent, message: &str) {
+    let request = AiRequest::Chat {
+        message: message.to_string(),
+    };
+    agent.process_request(request).await.unwrap();
+}
+
+#[tokio::test]
+async fn chat_prompts_include_the_last_messages() {
+    let provider = MockProvider::new();
+    let agent = agent(&provider);
+    for i in 1..=8 {
+        provider.respond(&format!("answer {}", i));
+        chat(&agent, &format!("question {}", i)).await;
+    }
+    chat(&agent, "question 9").await;
+
+    // Questions and answers 1-8 plus question 9 are 17 messages; the prompt
+    // carries the newest ones, ending with the question being asked
+    let prompt = provider.last_prompt();
+    let history = prompt.split("Conversation history:").nth(1).unwrap();
+    let context_lines: Vec<_> = history
+        .lines()
+        .filter(|line| line.starts_with("User:") || line.starts_with("Assistant:"))
+        .collect();
+    assert_eq!(context_lines.len(), CHAT_CONTEXT_MESSAGES + 1);
+    assert!(prompt.contains("User: question 5"));
+    assert!(prompt.contains("Assistant: answer 8"));
+    assert!(!prompt.contains("question 4\n"));
+    assert!(prompt.trim_end().ends_with("User: question 9"));
+}
+
+#[tokio::test]
+async fn first_chat_prompt_has_no_history_section() {
+    let provider = MockProvider::new();
+    let agent = agent(&provider);
+    chat(&agent, "hello").await;
+    // The message itself is the only context
+    assert_eq!(provider.prompts().len(), 1);
+    assert!(provider.last_prompt().ends_with("User: hello"));
+}
+
+#[tokio::test]
+async fn explain_command_round_trips_into_chat_history() {
+    let provider = MockProvider::new();
+    provider.respond("Lists files.\n\n```bash\nls -la\n```\nTry: ls -lh");
+    let agent = agent(&provider);
+
+    let response = agent
+        .process_request(AiRequest::ExplainCommand {
+            command: "ls -la".to_string(),
+        })
+        .await
+        .unwrap();
+
+    assert!(provider.last_prompt().contains("Explain this command: `ls -la`"));
+    assert_eq!(response.code_snippets.len(), 1);
+    assert_eq!(response.code_snippets[0].code, "ls -la");
+    assert_eq!(response.suggestions, ["ls -lh"]);
+
+    let messages = agent.get_active_chat_messages().await;
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(messages[0].role, MessageRole::User));
+    assert_eq!(messages[0].content, "Explain this command: ls -la");
+    assert!(matches!(messages[1].role, MessageRole::Assistant));
+    assert_eq!(messages[1].content, response.content);
+}
+
+#[tokio::test]
+async fn provider_errors_reach_the_caller() {
+    let provider = MockProvider::new();
+    provider.fail("quota exceeded");
+    let agent = agent(&provider);
+
+    let result = agent
+        .process_request(AiRequest::GenerateCommand {
+            description: "list files".to_string(),
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("quota exceeded"));
+}
+
+#[tokio::test]
+async fn streamed_chat_reports_every_chunk() {
+    let provider = MockProvider::new();
+    provider.respond("one two three");
+    let agent = agent(&provider);
+
+    let mut streamed = String::new();
+    let ticket = agent.queue().enqueue();
+    let response = agent
+        .chat_stream(ticket, "count", |chunk| streamed.push_str(chunk))
+        .await
+        .unwrap();
+    assert_eq!(streamed, "one two three");
+    assert_eq!(response.content, "one two three");
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use antraft::ai::cache::{ResponseCache, ResponseCacheConfig};
+use antraft::ai::{AiAgent, AiConfig, AiRequest, AiRequestOptions, AiResponse};
+use common::{ai_config, MockProvider};
+
+fn agent(provider: &MockProvider, config: AiConfig) -> AiAgent {
+    AiAgent::with_provider(config, Box::new(provider.clone())).with_shell("bash")
+}
+
+fn explain(command: &str) -> AiRequest {
+    AiRequest::ExplainCommand {
+        command: command.to_string(),
+    }
+}
+
+fn response(content: &str) -> AiResponse {
+    AiResponse {
+        content: content.to_string(),
+        suggestions: vec![],
+        code_snippets: vec![],
+        confidence: 0.8,
+        injection_signals: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn repeated_explanations_are_answered_from_the_cache() {
+    let provider = MockProvider::new();
+    provider.respond("Lists files.");
+    let agent = agent(&provider, ai_config());
+
+    let first = agent.process_request(explain("ls  -la")).await.unwrap();
+    let second = agent.process_request(explain(" ls -la ")).await.unwrap();
+    assert_eq!(provider.prompts().len(), 1);
+    assert_eq!(second.content, first.content);
+
+    // Cached answers still show up in the conversation
+    let messages = agent.get_active_chat_messages().await;
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[3].content, "Lists files.");
+
+    agent.process_request(explain("ls -lh")).await.unwrap();
+    assert_eq!(provider.prompts().len(), 2);
+}
+
+#[tokio::test]
+async fn fresh_requests_skip_the_cache_and_replace_its_answer() {
+    let provider = MockProvider::new();
+    provider.respond("feat: first").respond("feat: second");
+    let agent = agent(&provider, ai_config());
+    let request = || AiRequest::CommitMessage {
+        diff: "+fn main() {}".to_string(),
+        convention: Default::default(),
+    };
+
+    agent.process_request(request()).await.unwrap();
+    let regenerated = agent.process_request_fresh(request()).await.unwrap();
+    assert_eq!(regenerated.content, "feat: second");
+    let cached = agent.process_request(request()).await.unwrap();
+    assert_eq!(cached.content, "feat: second");
+    assert_eq!(provider.prompts().len(), 2);
+}
+
+#[tokio::test]
+async fn chats_and_hot_requests_are_never_cached() {
+    let provider = MockProvider::new();
+    let agent = agent(&provider, ai_config());
+    for _ in 0..2 {
+        let chat = AiRequest::Chat {
+            message: "hello".to_string(),
+        };
+        agent.process_request(chat).await.unwrap();
+    }
+    assert_eq!(provider.prompts().len(), 2);
+
+    let mut config = ai_config();
+    config.overrides.insert(
+        "explain_command".to_string(),
+        AiRequestOptions {
+            temperature: Some(0.9),
+            ..Default::default()
+        },
+    );
+    let provider = MockProvider::new();
+    let agent = self::agent(&provider, config);
+    agent.process_request(explain("ls")).await.unwrap();
+    agent.process_request(explain("ls")).await.unwrap();
+    assert_eq!(provider.prompts().len(), 2);
+}
+
+#[tokio::test]
+async fn caching_can_be_turned_off() {
+    let mut config = ai_config();
+    config.cache.enabled = false;
+    let provider = MockProvider::new();
+    let agent = agent(&provider, config);
+    agent.process_request(explain("ls")).await.unwrap();
+    agent.process_request(explain("ls")).await.unwrap();
+    assert_eq!(provider.prompts().len(), 2);
+}
+
+#[tokio::test]
+async fn offline_fallbacks_are_not_cached() {
+    let provider = MockProvider::new();
+    provider.fail("connection refused").respond("Lists files.");
+    let agent = agent(&provider, ai_config());
+
+    let offline = agent.process_request(explain("ls")).await.unwrap();
+    assert!(offline.content.contains("Offline docs"));
+    let online = agent.process_request(explain("ls")).await.unwrap();
+    assert_eq!(online.content, "Lists files.");
+}
+
+#[test]
+fn keys_cover_the_model_and_temperature() {
+    let cache = ResponseCache::load(ResponseCacheConfig::default(), std::path::Path::new("unused.json"));
+    let key = cache.cache_key(&explain("ls"), "gemini-2.0-flash", 0.3).unwrap();
+    assert_eq!(cache.cache_key(&explain("ls"), "gemini-2.0-flash", 0.3), Some(key.clone()));
+    assert_ne!(cache.cache_key(&explain("ls"), "gemini-1.5-pro", 0.3), Some(key.clone()));
+    assert_ne!(cache.cache_key(&explain("ls"), "gemini-2.0-flash", 0.1), Some(key.clone()));
+    assert_eq!(cache.cache_key(&explain("ls"), "gemini-2.0-flash", 0.7), None);
+
+    // Whitespace is significant in code
+    let review = |code: &str| AiRequest::CodeReview {
+        code: code.to_string(),
+        language: Some("python".to_string()),
+    };
+    assert_ne!(
+        cache.cache_key(&review("if x:\n    y()"), "m", 0.1),
+        cache.cache_key(&review("if x:\n  y()"), "m", 0.1)
+    );
+}
+
+#[test]
+fn least_recently_used_answers_are_dropped_first() {
+    let config = ResponseCacheConfig {
+        max_entries: 2,
+        ..Default::default()
+    };
+    let cache = ResponseCache::load(config, std::path::Path::new("unused.json"));
+    cache.insert("a".to_string(), response("A"));
+    cache.insert("b".to_string(), response("B"));
+    assert!(cache.get("a").is_some());
+    cache.insert("c".to_string(), response("C"));
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get("b").is_none());
+    assert_eq!(cache.get("a").unwrap().content, "A");
+    assert_eq!(cache.get("c").unwrap().content, "C");
+}
+
+#[test]
+fn persisted_answers_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ai_cache.json");
+    let config = ResponseCacheConfig {
+        persist: true,
+        ..Default::default()
+    };
+
+    let cache = ResponseCache::load(config.clone(), &path);
+    cache.insert("key".to_string(), response("saved"));
+    let reloaded = ResponseCache::load(config.clone(), &path);
+    assert_eq!(reloaded.get("key").unwrap().content, "saved");
+
+    // Without persistence nothing is read back
+    let unpersisted = ResponseCache::load(ResponseCacheConfig::default(), &path);
+    assert!(unpersisted.is_empty());
+
+    reloaded.clear();
+    assert!(!path.exists());
+    assert!(ResponseCache::load(config, &path).is_empty());
+}
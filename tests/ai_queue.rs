@@ -0,0 +1,76 @@
+use antraft::ai::queue::RequestQueue;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+#[tokio::test]
+async fn requests_run_up_to_the_limit_at_once() {
+    let queue = RequestQueue::new(2);
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let release = Arc::new(Notify::new());
+
+    // The later tickets are already parked in `wait` when the first starts,
+    // so the second only runs if starting the first wakes it
+    let tickets: Vec<_> = (0..3).map(|_| queue.enqueue()).collect();
+    let mut tasks = Vec::new();
+    for ticket in tickets.into_iter().rev() {
+        tasks.insert(0, {
+            let (running, peak, release) = (running.clone(), peak.clone(), release.clone());
+            tokio::spawn(async move {
+                let _permit = ticket.wait().await;
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                release.notified().await;
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // The first two start without either finishing
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while running.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("two requests should run at once");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(running.load(Ordering::SeqCst), 2);
+    // The third waits at the front for a slot
+    assert_eq!(queue.requests_ahead(2), Some(2));
+
+    // Finishing them lets the third run
+    tokio::time::timeout(Duration::from_secs(5), async {
+        for task in tasks {
+            while !task.is_finished() {
+                release.notify_waiters();
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }
+    })
+    .await
+    .expect("every request should finish");
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn requests_ahead_counts_running_and_earlier_tickets() {
+    let queue = RequestQueue::new(1);
+    let first = queue.enqueue();
+    let second = queue.enqueue();
+    let third = queue.enqueue();
+    assert_eq!(queue.requests_ahead(third.id()), Some(2));
+
+    let permit = first.wait().await;
+    assert_eq!(queue.requests_ahead(second.id()), Some(1));
+    assert_eq!(queue.requests_ahead(third.id()), Some(2));
+
+    // Giving up a place moves the ones behind it up
+    drop(second);
+    assert_eq!(queue.requests_ahead(third.id()), Some(1));
+    drop(permit);
+    assert_eq!(queue.requests_ahead(third.id()), Some(0));
+}
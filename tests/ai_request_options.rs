@@ -0,0 +1,96 @@
+mod common;
+
+use antraft::ai::gemini::GeminiClient;
+use antraft::ai::system_prompt::SECURITY_SYSTEM_PROMPT;
+use antraft::ai::{AiAgent, AiConfig, AiRequest, AiRequestOptions};
+use antraft::config::Config;
+use common::{ai_config, MockProvider};
+
+fn agent(provider: &MockProvider, config: AiConfig) -> AiAgent {
+    AiAgent::with_provider(config, Box::new(provider.clone()))
+}
+
+fn options(temperature: Option<f32>, max_tokens: Option<u32>, system_prompt: Option<&str>) -> AiRequestOptions {
+    AiRequestOptions {
+        temperature,
+        max_tokens,
+        system_prompt_override: system_prompt.map(str::to_string),
+    }
+}
+
+#[test]
+fn each_task_has_its_own_defaults() {
+    let agent = agent(&MockProvider::new(), ai_config());
+    assert_eq!(agent.request_options("generate_command"), options(Some(0.1), None, None));
+    assert_eq!(agent.request_options("chat"), options(None, None, None));
+    assert_eq!(
+        agent.request_options("security_analysis"),
+        options(Some(0.1), None, Some(SECURITY_SYSTEM_PROMPT))
+    );
+}
+
+#[test]
+fn overrides_replace_only_the_fields_they_set() {
+    let mut config = ai_config();
+    config.overrides.insert("generate_command".to_string(), options(None, Some(256), None));
+    config.overrides.insert("security_analysis".to_string(), options(Some(0.5), None, None));
+    config.overrides.insert("chat".to_string(), options(None, None, Some("Be terse.")));
+    config.overrides.insert("explain_command".to_string(), options(Some(0.0), Some(64), Some("Explain.")));
+    let agent = agent(&MockProvider::new(), config);
+
+    // The default temperature stays when only the token limit is set
+    assert_eq!(agent.request_options("generate_command"), options(Some(0.1), Some(256), None));
+    // And the default system prompt when only the temperature is
+    assert_eq!(
+        agent.request_options("security_analysis"),
+        options(Some(0.5), None, Some(SECURITY_SYSTEM_PROMPT))
+    );
+    assert_eq!(agent.request_options("chat"), options(None, None, Some("Be terse.")));
+    assert_eq!(agent.request_options("explain_command"), options(Some(0.0), Some(64), Some("Explain.")));
+    // Tasks without overrides keep their defaults
+    assert_eq!(agent.request_options("fix_error"), options(Some(0.2), None, None));
+}
+
+#[test]
+fn overrides_are_read_from_the_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[ai]\ntemperature = 0.9\n\n[ai.overrides.generate_command]\nmax_tokens = 128\n").unwrap();
+    let config = Config::load(Some(&path)).unwrap();
+
+    let agent = agent(&MockProvider::new(), config.ai.clone());
+    assert_eq!(agent.request_options("generate_command"), options(Some(0.1), Some(128), None));
+
+    // What isn't set by the task or its overrides comes from `[ai]`
+    let body = GeminiClient::new(config.ai.clone()).build_request("hi", &agent.request_options("chat"));
+    assert!((body["generationConfig"]["temperature"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+    let body = GeminiClient::new(config.ai).build_request("hi", &agent.request_options("generate_command"));
+    assert!((body["generationConfig"]["temperature"].as_f64().unwrap() - 0.1).abs() < 1e-6);
+    assert_eq!(body["generationConfig"]["maxOutputTokens"], 128);
+}
+
+#[tokio::test]
+async fn requests_reach_the_provider_with_the_merged_options() {
+    let provider = MockProvider::new();
+    let mut config = ai_config();
+    config.overrides.insert("generate_command".to_string(), options(None, Some(256), None));
+    let agent = agent(&provider, config);
+
+    agent
+        .process_request(AiRequest::GenerateCommand {
+            description: "list files".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(provider.last_options(), options(Some(0.1), Some(256), None));
+
+    agent
+        .process_request(AiRequest::SecurityAnalysis {
+            code: "eval(input())".to_string(),
+            language: "python".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(!provider.last_prompt().contains("integrated into ANTRAFT"));
+    assert_eq!(provider.last_options(), options(Some(0.1), None, Some(SECURITY_SYSTEM_PROMPT)));
+}
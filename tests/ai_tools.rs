@@ -0,0 +1,157 @@
+mod common;
+
+use antraft::ai::tools::{validate_readonly_command, ToolCall, ToolStep};
+use antraft::ai::AiAgent;
+use common::{ai_config, MockProvider};
+use std::sync::{Arc, Mutex};
+
+fn agent(provider: &MockProvider) -> AiAgent {
+    AiAgent::with_provider(ai_config(), Box::new(provider.clone())).with_shell("bash")
+}
+
+#[test]
+fn read_only_commands_are_allowed() {
+    for command in [
+        "ls -la",
+        "tail -n 20 app.log",
+        "tail -5 app.log",
+        "rg --type rust fn",
+        "date +%s",
+        "date -u",
+        "file -b Cargo.toml",
+        "tree -L 2 --noreport",
+        "git log --oneline -5",
+        "git branch -a",
+        "find . -name '*.rs' -type f",
+    ] {
+        assert!(validate_readonly_command(command).is_ok(), "{} should be allowed", command);
+    }
+}
+
+#[test]
+fn unlisted_programs_and_shell_syntax_are_refused() {
+    for command in ["rm -rf target", "sh -c ls", "ls | head", "cat $HOME/.bashrc", "git push", "git branch -D main"] {
+        assert!(validate_readonly_command(command).is_err(), "{} should be refused", command);
+    }
+}
+
+#[test]
+fn forbidden_flags_are_refused_in_every_form() {
+    for command in [
+        // Exact
+        "tail -f app.log",
+        "find . -delete",
+        // --flag=value
+        "rg --pre=rm pattern .",
+        "tail --follow=name app.log",
+        "date --set=2020-01-01",
+        "file --compile=magic",
+        // Abbreviated long flags
+        "tail --foll app.log",
+        "date --se 2020-01-01",
+        // Bundled short flags
+        "tail -fn5 app.log",
+        "tail -5f app.log",
+        "tail -nF app.log",
+        // Writes a file
+        "tree -o listing.txt",
+        "tree -ao listing.txt",
+    ] {
+        assert!(validate_readonly_command(command).is_err(), "{} should be refused", command);
+    }
+}
+
+#[tokio::test]
+async fn tool_loop_runs_each_call_and_feeds_back_the_result() {
+    let provider = MockProvider::new();
+    provider
+        .respond("Let me look.\n```tool\n{\"tool\": \"run_readonly_command\", \"args\": {\"command\": \"ls\"}}\n```")
+        .respond("```tool\n{\"tool\": \"read_file\", \"args\": {\"path\": \"Cargo.toml\"}}\n```")
+        .respond("It's a Rust project named demo.");
+    let agent = agent(&provider);
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mut steps = Vec::new();
+    let ticket = agent.queue().enqueue();
+    let response = agent
+        .chat_with_tools(
+            ticket,
+            "What is this project?",
+            |call: ToolCall| {
+                let calls = calls.clone();
+                async move {
+                    let output = match &call {
+                        ToolCall::RunReadonlyCommand { .. } => "Cargo.toml\nsrc".to_string(),
+                        _ => "[package]\nname = \"demo\"".to_string(),
+                    };
+                    let step = ToolStep {
+                        title: call.title(),
+                        output,
+                        success: true,
+                    };
+                    calls.lock().unwrap().push(call);
+                    step
+                }
+            },
+            |step| steps.push(step.title.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.content, "It's a Rust project named demo.");
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            ToolCall::RunReadonlyCommand { command: "ls".to_string() },
+            ToolCall::ReadFile { path: "Cargo.toml".to_string() },
+        ]
+    );
+    assert_eq!(steps, ["$ ls", "read_file Cargo.toml"]);
+
+    // Each prompt carries the results so far
+    let prompts = provider.prompts();
+    assert_eq!(prompts.len(), 3);
+    assert!(prompts[1].contains("Tool result for `$ ls`"));
+    assert!(prompts[1].contains("Cargo.toml\nsrc"));
+    assert!(prompts[2].contains("name = \"demo\""));
+}
+
+#[tokio::test]
+async fn tool_loop_stops_calling_tools_after_the_step_limit() {
+    let provider = MockProvider::new();
+    let call = "```tool\n{\"tool\": \"list_directory\", \"args\": {\"path\": \".\"}}\n```";
+    for _ in 0..10 {
+        provider.respond(call);
+    }
+    let config = antraft::ai::AiConfig {
+        max_tool_steps: 2,
+        ..ai_config()
+    };
+    let agent = AiAgent::with_provider(config, Box::new(provider.clone()));
+
+    let mut executed = 0;
+    let ticket = agent.queue().enqueue();
+    agent
+        .chat_with_tools(
+            ticket,
+            "list it",
+            |call: ToolCall| {
+                executed += 1;
+                async move {
+                    ToolStep {
+                        title: call.title(),
+                        output: "src/".to_string(),
+                        success: true,
+                    }
+                }
+            },
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(executed, 2);
+    let prompts = provider.prompts();
+    assert_eq!(prompts.len(), 3);
+    assert!(prompts[2].contains("You have used all your tool calls"));
+}
@@ -0,0 +1,55 @@
+use antraft::terminal::alias::{expand_aliases, parse_alias_command, AliasCommand};
+use std::collections::HashMap;
+
+fn aliases(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn aliases_can_refer_to_other_aliases() {
+    let aliases = aliases(&[("gs", "g status"), ("g", "git")]);
+    assert_eq!(expand_aliases("gs --short", &aliases).unwrap(), "git status --short");
+}
+
+#[test]
+fn only_the_first_word_is_expanded() {
+    let aliases = aliases(&[("ll", "ls -l"), ("g", "git")]);
+    assert_eq!(expand_aliases("ll g", &aliases).unwrap(), "ls -l g");
+    assert_eq!(expand_aliases("echo ll && ll", &aliases).unwrap(), "echo ll && ll");
+    // A word that merely starts with an alias isn't one
+    assert_eq!(expand_aliases("llama", &aliases).unwrap(), "llama");
+}
+
+#[test]
+fn a_cycle_is_an_error_instead_of_a_loop() {
+    let aliases = aliases(&[("a", "b"), ("b", "a")]);
+    let error = expand_aliases("a -v", &aliases).unwrap_err().to_string();
+    assert!(error.contains("a -> b -> a"), "{}", error);
+
+    let longer = self::aliases(&[("x", "y --flag"), ("y", "z"), ("z", "x")]);
+    assert!(expand_aliases("x", &longer).is_err());
+}
+
+#[test]
+fn an_alias_starting_with_its_own_name_stops_there() {
+    let aliases = aliases(&[("ls", "ls --color=auto")]);
+    assert_eq!(expand_aliases("ls -a", &aliases).unwrap(), "ls --color=auto -a");
+}
+
+#[test]
+fn parses_the_builtins() {
+    assert_eq!(
+        parse_alias_command("alias ll='ls -l'").unwrap().unwrap(),
+        AliasCommand::Define {
+            name: "ll".to_string(),
+            value: "ls -l".to_string(),
+        }
+    );
+    assert_eq!(parse_alias_command("alias").unwrap().unwrap(), AliasCommand::List);
+    assert_eq!(parse_alias_command("alias ll").unwrap().unwrap(), AliasCommand::Show("ll".to_string()));
+    assert_eq!(parse_alias_command("unalias ll").unwrap().unwrap(), AliasCommand::Remove("ll".to_string()));
+    assert!(parse_alias_command("unalias").unwrap().is_err());
+    assert!(parse_alias_command("alias x y=z").unwrap().is_err());
+    assert!(parse_alias_command("alias empty=''").unwrap().is_err());
+    assert!(parse_alias_command("aliases").is_none());
+}
@@ -0,0 +1,131 @@
+use antraft::ai::api_key::{Keyring, API_KEY_ENV_VAR};
+use antraft::ai::{AiConfig, ApiKeySource};
+use antraft::config::Config;
+use std::collections::HashMap;
+use std::path::Path;
+
+const KEY: &str = "AIzaTestKey0123456789";
+const ENV_KEY: &str = "AIzaEnvKey0123456789";
+const KEYRING_KEY: &str = "AIzaKeyringKey0123456789";
+
+/// Keyring entries by service and account.
+struct FakeKeyring(HashMap<(&'static str, &'static str), &'static str>);
+
+impl Keyring for FakeKeyring {
+    fn read(&self, service: &str, account: &str) -> anyhow::Result<String> {
+        match self.0.get(&(service, account)) {
+            Some(key) => Ok(key.to_string()),
+            None => anyhow::bail!("No keyring entry for service \"{}\", account \"{}\"", service, account),
+        }
+    }
+}
+
+fn keyring_source(account: &str) -> ApiKeySource {
+    ApiKeySource::Keyring {
+        service: "antraft".to_string(),
+        account: account.to_string(),
+    }
+}
+
+fn write_secret(dir: &Path, mode: u32) -> std::path::PathBuf {
+    let path = dir.join("gemini.key");
+    std::fs::write(&path, format!("{}\n", KEY)).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    path
+}
+
+fn write_config(dir: &Path, key_path: &Path) -> std::path::PathBuf {
+    let path = dir.join("config.toml");
+    let content = format!(
+        "[ai]\nmodel = \"gemini-2.0-flash\"\napi_key_source = {{ type = \"file\", path = {:?} }}\n",
+        key_path.display().to_string()
+    );
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn loads_the_key_from_a_secrets_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let key_path = write_secret(dir.path(), 0o600);
+    let config = Config::load(Some(&write_config(dir.path(), &key_path))).unwrap();
+
+    assert_eq!(config.ai.api_key, KEY);
+    assert_eq!(config.ai.api_key_source, ApiKeySource::File { path: key_path });
+}
+
+#[cfg(unix)]
+#[test]
+fn refuses_a_secrets_file_others_can_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let key_path = write_secret(dir.path(), 0o644);
+    let config = Config::load(Some(&write_config(dir.path(), &key_path))).unwrap();
+
+    assert_ne!(config.ai.api_key, KEY);
+    let error = format!("{:#}", config.validate().unwrap_err());
+    assert!(error.contains("chmod 600"), "{}", error);
+    assert!(!error.contains(KEY));
+}
+
+#[test]
+fn saving_leaves_the_key_out_of_the_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let key_path = write_secret(dir.path(), 0o600);
+    let config_path = write_config(dir.path(), &key_path);
+    Config::load(Some(&config_path)).unwrap().save().unwrap();
+
+    let saved = std::fs::read_to_string(&config_path).unwrap();
+    assert!(!saved.contains(KEY), "{}", saved);
+    assert!(saved.contains("api_key_source"), "{}", saved);
+    assert_eq!(Config::load(Some(&config_path)).unwrap().ai.api_key, KEY);
+}
+
+#[test]
+fn rejects_unknown_source_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[ai]\napi_key_source = { type = \"keyring\", acount = \"me\" }\n").unwrap();
+
+    assert!(Config::load(Some(&path)).is_err());
+}
+
+#[test]
+fn keys_come_from_the_configured_source_then_the_environment_variable() {
+    std::env::set_var(API_KEY_ENV_VAR, ENV_KEY);
+    let dir = tempfile::tempdir().unwrap();
+    let key_path = write_secret(dir.path(), 0o600);
+    let keyring = FakeKeyring(HashMap::from([(("antraft", "gemini"), KEYRING_KEY), (("antraft", "blank"), "  \n")]));
+    let load = |source: ApiKeySource| {
+        let mut ai = AiConfig {
+            api_key_source: source,
+            ..AiConfig::default()
+        };
+        let loaded = ai.load_api_key_from(&keyring);
+        (ai.api_key, loaded.map_err(|e| format!("{:#}", e)))
+    };
+
+    // Without a source, the environment variable
+    assert_eq!(load(ApiKeySource::Config), (ENV_KEY.to_string(), Ok(())));
+    // A keyring entry or secrets file wins over it
+    assert_eq!(load(keyring_source("gemini")), (KEYRING_KEY.to_string(), Ok(())));
+    assert_eq!(load(ApiKeySource::File { path: key_path }), (KEY.to_string(), Ok(())));
+
+    // It's the fallback when the source can't be read, with the reason
+    let (key, error) = load(keyring_source("missing"));
+    assert_eq!(key, ENV_KEY);
+    assert!(error.unwrap_err().contains("account \"missing\""));
+    let (key, error) = load(keyring_source("blank"));
+    assert_eq!(key, ENV_KEY);
+    assert!(error.unwrap_err().contains("is empty"));
+    let (key, error) = load(ApiKeySource::File {
+        path: dir.path().join("missing.key"),
+    });
+    assert_eq!(key, ENV_KEY);
+    assert!(error.is_err());
+}
@@ -0,0 +1,114 @@
+use antraft::autocomplete::{AutocompleteContext, AutocompleteEngine, AutocompleteItem, AutocompleteProvider};
+use antraft::terminal::history::{CommandHistory, HistoryEntry};
+use chrono::{Duration, Utc};
+
+/// Offers the same fixed commands for any input.
+struct FixedProvider(Vec<&'static str>);
+
+impl AutocompleteProvider for FixedProvider {
+    fn get_suggestions(&self, _input: &str, _context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        self.0
+            .iter()
+            .map(|command| AutocompleteItem::new(command.to_string(), String::new(), "test".to_string()))
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "fixed"
+    }
+}
+
+/// An engine offering only `deploy alpha` and `deploy alphb`, which match
+/// `deploy alph` equally well.
+fn deploy_engine() -> AutocompleteEngine {
+    let mut engine = AutocompleteEngine::new();
+    engine.retain_providers(|_| false);
+    engine.add_provider(Box::new(FixedProvider(vec!["deploy alpha", "deploy alphb"])));
+    engine
+}
+
+fn ranked(engine: &AutocompleteEngine) -> Vec<String> {
+    let context = AutocompleteContext::new("/".to_string(), "bash".to_string());
+    engine
+        .get_suggestions("deploy alph", &context)
+        .into_iter()
+        .map(|item| item.text)
+        .collect()
+}
+
+#[test]
+fn frequent_commands_outrank_rare_ones() {
+    let now = Utc::now();
+    let mut engine = deploy_engine();
+    assert_eq!(ranked(&engine), ["deploy alpha", "deploy alphb"]);
+
+    engine.add_to_history("deploy alpha".to_string(), 1, now);
+    engine.add_to_history("deploy alphb".to_string(), 10, now);
+    assert_eq!(ranked(&engine), ["deploy alphb", "deploy alpha"]);
+
+    // Runs add up across calls
+    engine.add_to_history("deploy alpha".to_string(), 20, now);
+    assert_eq!(ranked(&engine), ["deploy alpha", "deploy alphb"]);
+}
+
+#[test]
+fn recent_commands_outrank_stale_ones() {
+    let now = Utc::now();
+    let mut engine = deploy_engine();
+    engine.add_to_history("deploy alpha".to_string(), 3, now - Duration::days(30));
+    engine.add_to_history("deploy alphb".to_string(), 3, now - Duration::minutes(5));
+    assert_eq!(ranked(&engine), ["deploy alphb", "deploy alpha"]);
+
+    // Running the stale command again makes it recent
+    engine.add_to_history("deploy alpha".to_string(), 1, now);
+    assert_eq!(ranked(&engine), ["deploy alpha", "deploy alphb"]);
+
+    // An older run doesn't move the last use back
+    engine.add_to_history("deploy alpha".to_string(), 0, now - Duration::days(60));
+    assert_eq!(ranked(&engine), ["deploy alpha", "deploy alphb"]);
+}
+
+#[test]
+fn old_runs_decay() {
+    let now = Utc::now();
+
+    // Six runs two weeks ago count for less than two in the last hour...
+    let mut engine = deploy_engine();
+    engine.add_to_history("deploy alpha".to_string(), 6, now - Duration::weeks(2));
+    engine.add_to_history("deploy alphb".to_string(), 2, now - Duration::minutes(10));
+    assert_eq!(ranked(&engine), ["deploy alphb", "deploy alpha"]);
+
+    // ...but for more than two three days ago
+    let mut engine = deploy_engine();
+    engine.add_to_history("deploy alpha".to_string(), 6, now - Duration::weeks(2));
+    engine.add_to_history("deploy alphb".to_string(), 2, now - Duration::days(3));
+    assert_eq!(ranked(&engine), ["deploy alpha", "deploy alphb"]);
+
+    // Within the hour and within the day weigh differently at equal counts
+    let mut engine = deploy_engine();
+    engine.add_to_history("deploy alpha".to_string(), 2, now - Duration::hours(5));
+    engine.add_to_history("deploy alphb".to_string(), 2, now - Duration::minutes(30));
+    assert_eq!(ranked(&engine), ["deploy alphb", "deploy alpha"]);
+}
+
+#[test]
+fn frecency_survives_saving_the_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    let mut history = CommandHistory::new(100);
+    history.add_entry(HistoryEntry::new("deploy alpha".to_string(), "/".to_string()));
+    for _ in 0..5 {
+        history.add_entry(HistoryEntry::new("deploy alphb".to_string(), "/".to_string()));
+    }
+    history.save(&path).unwrap();
+
+    let history = CommandHistory::load(&path, 100).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get_all_entries()[1].runs(), 5);
+
+    let mut engine = deploy_engine();
+    for entry in history.get_all_entries() {
+        engine.add_to_history(entry.command.clone(), entry.runs(), entry.timestamp);
+    }
+    assert_eq!(ranked(&engine), ["deploy alphb", "deploy alpha"]);
+}
@@ -0,0 +1,57 @@
+use std::process::{Command, Output};
+
+/// Runs the binary with `args` in an environment with nothing set but a
+/// fresh home directory, and `env`.
+fn antraft(args: &[&str], env: &[(&str, &str)]) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_antraft"))
+        .args(args)
+        .env_clear()
+        .env("HOME", home.path())
+        .env("PATH", "")
+        .envs(env.iter().copied())
+        .current_dir(home.path())
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn doctor_runs_cleanly_in_a_bare_environment() {
+    let report = stdout(&antraft(&["doctor"], &[]));
+    assert!(report.starts_with(&format!("ANTRAFT {}", antraft::VERSION)), "{}", report);
+    assert!(report.contains("not created yet"), "{}", report);
+    assert!(report.contains("No known shell on PATH"), "{}", report);
+    assert!(report.contains("✗ Semgrep      not installed"), "{}", report);
+    assert!(report.contains("No API key"), "{}", report);
+
+    let report = stdout(&antraft(&["doctor"], &[("GEMINI_API_KEY", "test-key")]));
+    assert!(report.contains("✓ API key from the GEMINI_API_KEY environment variable"), "{}", report);
+    assert!(!report.contains("test-key"), "{}", report);
+}
+
+#[test]
+fn prints_completions_for_the_commands_and_their_values() {
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let script = stdout(&antraft(&["completions", shell], &[]));
+        for word in ["scan", "doctor", "completions", "format"] {
+            assert!(script.contains(word), "{} completions lack {}", shell, word);
+        }
+        // PowerShell's don't offer values
+        if shell != "powershell" {
+            assert!(script.contains("dependencies") && script.contains("json"), "{}", shell);
+        }
+    }
+    assert!(!antraft(&["completions", "tcsh"], &[]).status.success());
+}
+
+#[test]
+fn the_version_names_the_commit() {
+    let version = stdout(&antraft(&["--version"], &[]));
+    assert_eq!(version.trim(), format!("antraft {}", antraft::VERSION));
+    assert!(antraft::VERSION.starts_with(env!("CARGO_PKG_VERSION")));
+}
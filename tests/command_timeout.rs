@@ -0,0 +1,62 @@
+use antraft::terminal::timeout::{effective_timeout, format_timeout, parse_duration, split_timeout, with_timeout};
+use std::time::Duration;
+
+#[test]
+fn the_prefix_is_split_off_the_command() {
+    let (timeout, command) = split_timeout("@timeout=30s curl https://example.com").unwrap();
+    assert_eq!(timeout, Some(Duration::from_secs(30)));
+    assert_eq!(command, "curl https://example.com");
+
+    assert_eq!(split_timeout("git fetch").unwrap(), (None, "git fetch"));
+    assert_eq!(split_timeout("@timeout=off make").unwrap(), (Some(Duration::ZERO), "make"));
+    assert!(split_timeout("@timeout=30s").is_err());
+    assert!(split_timeout("@timeout=soon make").is_err());
+}
+
+#[test]
+fn durations_take_several_units() {
+    assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+    assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+    assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("1m 30s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+    assert!(parse_duration("5 minutes").is_err());
+    assert!(parse_duration("m").is_err());
+    assert!(parse_duration("").is_err());
+}
+
+#[test]
+fn durations_too_long_to_represent_are_invalid() {
+    assert!(split_timeout("@timeout=99999999999999999h make").is_err());
+    assert!(parse_duration("99999999999999999h").is_err());
+    assert!(parse_duration("99999999999999999999m").is_err());
+    assert!(parse_duration(&format!("{}m", u64::MAX / 60 + 1)).is_err());
+    assert!(parse_duration(&format!("{}s 1s", u64::MAX)).is_err());
+    assert_eq!(parse_duration(&format!("{}s", u64::MAX)).unwrap(), Duration::from_secs(u64::MAX));
+}
+
+#[test]
+fn timeouts_round_trip_through_their_text() {
+    for timeout in [
+        Duration::from_millis(500),
+        Duration::from_secs(30),
+        Duration::from_secs(90),
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+    ] {
+        assert_eq!(parse_duration(&format_timeout(timeout)).unwrap(), timeout);
+        let prefixed = with_timeout("sleep 1", timeout);
+        assert_eq!(split_timeout(&prefixed).unwrap(), (Some(timeout), "sleep 1"));
+    }
+    assert_eq!(format_timeout(Duration::from_secs(90)), "1m 30s");
+}
+
+#[test]
+fn a_command_s_own_timeout_beats_the_default() {
+    assert_eq!(effective_timeout(None, None), None);
+    assert_eq!(effective_timeout(None, Some(60)), Some(Duration::from_secs(60)));
+    assert_eq!(effective_timeout(Some(Duration::from_secs(5)), Some(60)), Some(Duration::from_secs(5)));
+    // Zero turns the default off, for one command or all
+    assert_eq!(effective_timeout(Some(Duration::ZERO), Some(60)), None);
+    assert_eq!(effective_timeout(None, Some(0)), None);
+}
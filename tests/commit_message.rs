@@ -0,0 +1,26 @@
+use antraft::ai::commit_message::commit_command;
+
+const MESSAGE: &str = "Don't retry failed uploads\n\nThe server's 4xx answers won't change on retry.";
+
+#[test]
+fn quotes_apostrophes_for_the_configured_shell() {
+    assert_eq!(
+        commit_command(MESSAGE, "pwsh").unwrap(),
+        "git commit -m 'Don''t retry failed uploads' -m 'The server''s 4xx answers won''t change on retry.'"
+    );
+    assert_eq!(
+        commit_command("Don't panic", "/usr/local/bin/pwsh").unwrap(),
+        "git commit -m 'Don''t panic'"
+    );
+    assert_eq!(
+        commit_command("Don't panic", "/usr/bin/fish").unwrap(),
+        r"git commit -m 'Don\'t panic'"
+    );
+    assert_eq!(commit_command("Don't panic", "/bin/bash").unwrap(), r"git commit -m 'Don'\''t panic'");
+}
+
+#[test]
+fn needs_a_summary_line() {
+    assert_eq!(commit_command("  \n", "bash"), None);
+    assert_eq!(commit_command("Fix it\n", "bash").unwrap(), "git commit -m 'Fix it'");
+}
@@ -0,0 +1,164 @@
+//! Fakes for the seams integration tests plug into: an AI provider with
+//! scripted answers, and scanners with canned findings.
+
+#![allow(dead_code)]
+
+use anyhow::{anyhow, Result};
+use antraft::ai::provider::{AiProvider, ProviderFuture};
+use antraft::ai::{AiConfig, AiRequestOptions, ModelInfo};
+use antraft::security::{ScanResult, Scanner, ScannerKind, Severity, Vulnerability};
+use antraft::security::scanner::ScanFuture;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Records every prompt and the options sent with it, and answers with
+/// scripted responses, in order, then with "ok".
+#[derive(Clone, Default)]
+pub struct MockProvider {
+    prompts: Arc<Mutex<Vec<String>>>,
+    options: Arc<Mutex<Vec<AiRequestOptions>>>,
+    responses: Arc<Mutex<VecDeque<Result<String>>>>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn respond(&self, response: &str) -> &Self {
+        self.responses.lock().unwrap().push_back(Ok(response.to_string()));
+        self
+    }
+
+    pub fn fail(&self, error: &str) -> &Self {
+        self.responses.lock().unwrap().push_back(Err(anyhow!(error.to_string())));
+        self
+    }
+
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+
+    pub fn last_prompt(&self) -> String {
+        self.prompts().pop().expect("no prompt was sent")
+    }
+
+    pub fn last_options(&self) -> AiRequestOptions {
+        self.options.lock().unwrap().last().cloned().expect("no prompt was sent")
+    }
+
+    fn answer(&self, prompt: String, options: &AiRequestOptions) -> Result<String> {
+        self.prompts.lock().unwrap().push(prompt);
+        self.options.lock().unwrap().push(options.clone());
+        self.responses.lock().unwrap().pop_front().unwrap_or_else(|| Ok("ok".to_string()))
+    }
+}
+
+impl AiProvider for MockProvider {
+    fn generate_text<'a>(&'a self, prompt: String, options: &'a AiRequestOptions) -> ProviderFuture<'a, String> {
+        Box::pin(async move { self.answer(prompt, options) })
+    }
+
+    fn generate_text_stream<'a>(
+        &'a self,
+        prompt: String,
+        options: &'a AiRequestOptions,
+        on_chunk: &'a mut (dyn FnMut(&str) + Send),
+    ) -> ProviderFuture<'a, String> {
+        Box::pin(async move {
+            let text = self.answer(prompt, options)?;
+            for word in text.split_inclusive(' ') {
+                on_chunk(word);
+            }
+            Ok(text)
+        })
+    }
+
+    fn list_models(&self) -> ProviderFuture<'_, Vec<ModelInfo>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn update_config(&mut self, _config: AiConfig) {}
+}
+
+/// An AI config that counts as having a provider configured.
+pub fn ai_config() -> AiConfig {
+    AiConfig {
+        api_key: "test-key".to_string(),
+        ..AiConfig::default()
+    }
+}
+
+/// Reports the same findings, or the same error, for any path, and for
+/// any image when it `scans_images`.
+pub struct StubScanner {
+    pub name: &'static str,
+    pub kind: ScannerKind,
+    pub findings: Result<Vec<Vulnerability>, String>,
+    pub scans_images: bool,
+}
+
+impl StubScanner {
+    pub fn new(name: &'static str, kind: ScannerKind, findings: Vec<Vulnerability>) -> Self {
+        Self {
+            name,
+            kind,
+            findings: Ok(findings),
+            scans_images: false,
+        }
+    }
+
+    pub fn failing(name: &'static str, kind: ScannerKind, error: &str) -> Self {
+        Self {
+            name,
+            kind,
+            findings: Err(error.to_string()),
+            scans_images: false,
+        }
+    }
+
+    pub fn scanning_images(mut self) -> Self {
+        self.scans_images = true;
+        self
+    }
+
+    fn result(&self) -> ScanResult {
+        match &self.findings {
+            Ok(findings) => ScanResult::Success(findings.clone()),
+            Err(e) => ScanResult::Error(e.clone()),
+        }
+    }
+}
+
+impl Scanner for StubScanner {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn kind(&self) -> ScannerKind {
+        self.kind
+    }
+
+    fn scan<'a>(&'a self, _path: &'a Path) -> ScanFuture<'a> {
+        let result = self.result();
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn scan_image<'a>(&'a self, _image: &'a str) -> Option<ScanFuture<'a>> {
+        let result = self.result();
+        self.scans_images.then(|| -> ScanFuture<'a> { Box::pin(async move { Ok(result) }) })
+    }
+}
+
+pub fn vulnerability(title: &str, severity: Severity, scanner: &str) -> Vulnerability {
+    Vulnerability::new(
+        title.to_string(),
+        format!("{} description", title),
+        severity,
+        "test".to_string(),
+        "src/app.py".to_string(),
+        scanner.to_string(),
+    )
+    .with_location(1, None)
+}
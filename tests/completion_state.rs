@@ -0,0 +1,102 @@
+use antraft::autocomplete::completion::{self, CompletionKey, CompletionState, KeyOutcome};
+use antraft::autocomplete::AutocompleteItem;
+
+fn item(text: &str, category: &str) -> AutocompleteItem {
+    AutocompleteItem::new(text.to_string(), String::new(), category.to_string())
+}
+
+fn open(input: &str, texts: &[&str]) -> CompletionState {
+    let mut state = CompletionState::default();
+    state.update(input, texts.iter().map(|text| item(text, "git")).collect());
+    state
+}
+
+#[test]
+fn tab_and_shift_tab_cycle_the_items() {
+    let mut state = open("git ch", &["git checkout", "git cherry-pick", "git cherry"]);
+    assert!(state.is_open());
+    assert_eq!(state.selected(), None);
+
+    assert_eq!(state.key(CompletionKey::Tab), KeyOutcome::Handled);
+    assert_eq!(state.selected(), Some(0));
+    state.key(CompletionKey::Tab);
+    state.key(CompletionKey::Down);
+    assert_eq!(state.selected(), Some(2));
+    // Past the end, back to the start
+    state.key(CompletionKey::Tab);
+    assert_eq!(state.selected(), Some(0));
+    state.key(CompletionKey::ShiftTab);
+    assert_eq!(state.selected(), Some(2));
+    state.key(CompletionKey::Up);
+    assert_eq!(state.selected_item().unwrap().text, "git cherry-pick");
+
+    // From nothing selected, Shift+Tab starts at the end
+    let mut state = open("git ch", &["git checkout", "git cherry"]);
+    state.key(CompletionKey::ShiftTab);
+    assert_eq!(state.selected(), Some(1));
+}
+
+#[test]
+fn enter_accepts_and_escape_closes() {
+    // Enter without a selection runs the command
+    let mut state = open("git ch", &["git checkout", "git cherry"]);
+    assert_eq!(state.key(CompletionKey::Enter), KeyOutcome::Ignored);
+
+    state.key(CompletionKey::Tab);
+    assert_eq!(state.key(CompletionKey::Enter), KeyOutcome::Accepted("git checkout".to_string()));
+    assert!(!state.is_open());
+    // Closed, the next Enter runs it
+    assert_eq!(state.key(CompletionKey::Enter), KeyOutcome::Ignored);
+    assert!(!state.is_stale("git checkout"));
+
+    let mut state = open("git ch", &["git checkout"]);
+    state.key(CompletionKey::Tab);
+    assert_eq!(state.key(CompletionKey::Escape), KeyOutcome::Handled);
+    assert!(!state.is_open());
+    assert_eq!(state.key(CompletionKey::Tab), KeyOutcome::Ignored);
+    // Until the input changes
+    state.update("git ch", vec![item("git checkout", "git")]);
+    assert!(!state.is_open());
+    state.update("git che", vec![item("git checkout", "git")]);
+    assert!(state.is_open());
+}
+
+#[test]
+fn typing_keeps_the_selection_while_it_matches() {
+    let mut state = open("git c", &["git checkout", "git cherry", "git commit"]);
+    state.key(CompletionKey::Tab);
+    state.key(CompletionKey::Tab);
+    assert_eq!(state.selected_item().unwrap().text, "git cherry");
+
+    state.update("git ch", vec![item("git checkout", "git"), item("git cherry", "git")]);
+    assert_eq!(state.selected(), Some(1));
+    state.update("git co", vec![item("git commit", "git")]);
+    assert_eq!(state.selected(), None);
+    assert!(state.is_open());
+
+    // Items that wouldn't change the input aren't offered
+    let state = open("git commit", &["git commit"]);
+    assert!(!state.is_open());
+}
+
+#[test]
+fn completions_replace_the_line_or_the_path_being_typed() {
+    assert_eq!(completion::apply_completion("git checkout ma", "git checkout main"), "git checkout main");
+    assert_eq!(completion::apply_completion("cat src/ma", "main.rs"), "cat src/main.rs");
+    assert_eq!(completion::apply_completion("cd ", "target/"), "cd target/");
+    assert_eq!(completion::apply_completion("ls", "ls -la"), "ls -la");
+
+    let mut state = CompletionState::default();
+    state.update("cat src/ma", vec![item("main.rs", "file"), item("mod.rs", "file")]);
+    state.key(CompletionKey::Tab);
+    assert_eq!(state.key(CompletionKey::Enter), KeyOutcome::Accepted("cat src/main.rs".to_string()));
+}
+
+#[test]
+fn items_have_icons_by_category() {
+    assert_eq!(completion::item_icon(&item("git status", "history")), "⏱");
+    assert_eq!(completion::item_icon(&item("main.rs", "file")), "📄");
+    assert_eq!(completion::item_icon(&item("git checkout", "git")), "🌿");
+    assert_eq!(completion::item_icon(&item("ls -la", "spec")), "🚩");
+    assert_eq!(completion::item_icon(&item("ls", "filesystem")), "›");
+}
@@ -0,0 +1,127 @@
+use antraft::security::config_audit::{self, ConfigAuditScanner, ConfigFile, CONFIG_RULES};
+use antraft::security::{ScanTarget, ScanType, SecurityConfig, SecurityScanRequest, SecurityScanner, Severity};
+use std::path::{Path, PathBuf};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config_audit")
+}
+
+fn rules(name: &str, content: &str) -> Vec<String> {
+    let path = Path::new(name);
+    config_audit::scan_content(path, ConfigFile::detect(path).unwrap(), content)
+        .into_iter()
+        .map(|finding| finding.id)
+        .collect()
+}
+
+#[test]
+fn finds_misconfigurations_in_the_fixtures() {
+    let found = ConfigAuditScanner::new(&SecurityConfig::default()).scan_path(&fixtures());
+    let mut located: Vec<(String, &str, usize)> = found
+        .iter()
+        .map(|finding| {
+            let file = Path::new(&finding.file_path).file_name().unwrap().to_string_lossy().into_owned();
+            (file, finding.id.as_str(), finding.line_number.unwrap())
+        })
+        .collect();
+    located.sort();
+    let expected = [
+        ("Dockerfile", "dockerfile-latest-tag", 1),
+        ("Dockerfile", "dockerfile-no-healthcheck", 6),
+        ("Dockerfile", "dockerfile-remote-add", 10),
+        ("Dockerfile", "dockerfile-root-user", 13),
+        ("Dockerfile", "dockerfile-secret-env", 8),
+        ("Dockerfile", "dockerfile-secret-env", 9),
+        ("ci.yml", "workflow-pr-target-checkout", 9),
+        ("ci.yml", "workflow-secret-echo", 16),
+        ("ci.yml", "workflow-secret-echo", 19),
+        ("ci.yml", "workflow-unpinned-action", 13),
+        ("docker-compose.yml", "compose-docker-socket", 9),
+        ("docker-compose.yml", "compose-host-network", 5),
+        ("docker-compose.yml", "compose-privileged", 4),
+    ];
+    let expected: Vec<_> = expected.iter().map(|(file, id, line)| (file.to_string(), *id, *line)).collect();
+    assert_eq!(located, expected);
+
+    // Every rule has a case in the fixtures
+    for rule in CONFIG_RULES {
+        assert!(found.iter().any(|finding| finding.id == rule.id), "no fixture for {}", rule.id);
+    }
+
+    let socket = found.iter().find(|finding| finding.id == "compose-docker-socket").unwrap();
+    assert_eq!(socket.category, "config");
+    assert_eq!(socket.scanner, "config-audit");
+    assert_eq!(socket.severity, Severity::High);
+    assert_eq!(socket.code_snippet.as_deref(), Some("- /var/run/docker.sock:/var/run/docker.sock"));
+    assert!(socket.suggested_fix.as_deref().unwrap().contains("docker-socket-proxy"));
+}
+
+#[test]
+fn checks_the_final_build_stage() {
+    let hardened = "FROM golang:1.22 AS build\nRUN go build -o /app\n\nFROM gcr.io/distroless/static:nonroot\nCOPY --from=build /app /app\nHEALTHCHECK CMD [\"/app\", \"health\"]\nUSER nonroot\n";
+    assert!(rules("Dockerfile", hardened).is_empty());
+
+    // A USER in an earlier stage doesn't carry over
+    let multi_stage = "FROM node:20 AS build\nUSER node\nHEALTHCHECK NONE\nFROM node:20-slim\nHEALTHCHECK CMD node health.js\n";
+    assert_eq!(rules("Dockerfile", multi_stage), ["dockerfile-root-user"]);
+    let back_to_root = "FROM alpine:3.20\nUSER app\nRUN id\nuser 0:0\nHEALTHCHECK NONE\n";
+    assert_eq!(rules("build.dockerfile", back_to_root), ["dockerfile-root-user", "dockerfile-no-healthcheck"]);
+}
+
+#[test]
+fn leaves_safe_configs_alone() {
+    let compose = "services:\n  web:\n    image: nginx:1.27\n    privileged: false\n    network_mode: bridge\n    volumes:\n      - ./html:/usr/share/nginx/html:ro\n";
+    assert!(rules("compose.yaml", compose).is_empty());
+
+    // Checkout is only a problem with pull_request_target
+    let workflow = "on: [pull_request]\njobs:\n  test:\n    steps:\n      - uses: actions/checkout@v4\n      - run: echo ${{ github.sha }}\n";
+    assert!(rules(".github/workflows/test.yml", workflow).is_empty());
+}
+
+#[test]
+fn detects_config_files_by_name() {
+    let cases: &[(&str, Option<ConfigFile>)] = &[
+        ("Dockerfile", Some(ConfigFile::Dockerfile)),
+        ("docker/Dockerfile.prod", Some(ConfigFile::Dockerfile)),
+        ("api.Dockerfile", Some(ConfigFile::Dockerfile)),
+        ("Containerfile", Some(ConfigFile::Dockerfile)),
+        ("docker-compose.override.yml", Some(ConfigFile::Compose)),
+        ("compose.yaml", Some(ConfigFile::Compose)),
+        (".github/workflows/release.yaml", Some(ConfigFile::Workflow)),
+        (".github/dependabot.yml", None),
+        ("config.yml", None),
+        ("Dockerfiles/README.md", None),
+    ];
+    for (path, expected) in cases {
+        assert_eq!(ConfigFile::detect(Path::new(path)), *expected, "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn runs_in_full_and_code_only_scans() {
+    let config = SecurityConfig::default();
+    let scanner = SecurityScanner::with_scanners(config.clone(), vec![Box::new(ConfigAuditScanner::new(&config))]);
+    for (scan_type, expected) in [
+        (ScanType::Full, 13),
+        (ScanType::CodeOnly, 13),
+        (ScanType::Quick, 0),
+        (ScanType::DependenciesOnly, 0),
+    ] {
+        let request = SecurityScanRequest {
+            target: ScanTarget::Path(fixtures()),
+            scan_type: scan_type.clone(),
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            honor_ignore_comments: true,
+        };
+        let report = scanner.scan(request).await.unwrap();
+        assert_eq!(report.summary.total_vulnerabilities, expected, "{:?}", scan_type);
+    }
+
+    let disabled = SecurityConfig {
+        enable_config_audit: false,
+        ..SecurityConfig::default()
+    };
+    assert!(!SecurityScanner::new(disabled).unwrap().is_scanner_available("config-audit"));
+    assert!(SecurityScanner::new(config).unwrap().is_scanner_available("config-audit"));
+}
@@ -0,0 +1,215 @@
+use antraft::file_explorer::search::{
+    search_builtin, search_contents, ContentSearchEvent, ContentSearchOptions, FileMatches,
+};
+use antraft::file_explorer::CancellationToken;
+use std::ops::Range;
+use std::path::Path;
+
+fn options(query: &str) -> ContentSearchOptions {
+    ContentSearchOptions {
+        query: query.to_string(),
+        case_sensitive: false,
+        regex: false,
+        whole_word: false,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        excluded_paths: vec!["node_modules".to_string()],
+        max_matches_per_file: 100,
+        max_file_size: 1024 * 1024,
+    }
+}
+
+/// A small git repository with a `.gitignore`.
+fn workspace() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let write = |path: &str, content: &[u8]| {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    std::fs::create_dir(dir.path().join(".git")).unwrap();
+    write(".gitignore", b"target/\n*.log\n");
+    write("src/main.rs", b"fn main() {\n    let todo = 1;\n    // TODO: parse args\n}\n");
+    write("src/lib.rs", b"pub fn add() {}\n// todo later\r\n");
+    write("README.md", b"# Demo\nNothing TODO here.\n");
+    write("target/debug/out.rs", b"// TODO generated\n");
+    write("build.log", b"TODO from the build\n");
+    write("node_modules/pkg/index.js", b"// TODO vendored\n");
+    write(".hidden/notes.txt", b"TODO hidden\n");
+    write("image.bin", b"TODO\0binary");
+    dir
+}
+
+/// Matching files, relative to `root` and sorted, with their line numbers.
+fn search(root: &Path, options: &ContentSearchOptions) -> Vec<(String, Vec<usize>)> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let pattern = options.pattern().unwrap();
+    let found = search_builtin(root, options, &pattern, &CancellationToken::new(), &tx).unwrap();
+    drop(tx);
+    let files: Vec<FileMatches> = rx
+        .iter()
+        .map(|event| match event {
+            ContentSearchEvent::File(file) => file,
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    assert_eq!(found, files.len());
+    summarize(root, files)
+}
+
+fn summarize(root: &Path, files: Vec<FileMatches>) -> Vec<(String, Vec<usize>)> {
+    let mut files: Vec<(String, Vec<usize>)> = files
+        .into_iter()
+        .map(|file| {
+            let path = file.path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            (path, file.matches.iter().map(|line| line.line_number).collect())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn found(files: &[(&str, &[usize])]) -> Vec<(String, Vec<usize>)> {
+    files.iter().map(|(path, lines)| (path.to_string(), lines.to_vec())).collect()
+}
+
+#[test]
+fn matches_report_their_lines_and_ranges() {
+    let dir = workspace();
+    assert_eq!(
+        search(dir.path(), &options("todo")),
+        found(&[("README.md", &[2]), ("src/lib.rs", &[2]), ("src/main.rs", &[2, 3])])
+    );
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut case_sensitive = options("TODO");
+    case_sensitive.case_sensitive = true;
+    case_sensitive.include = vec!["*.rs".to_string()];
+    let pattern = case_sensitive.pattern().unwrap();
+    search_builtin(dir.path(), &case_sensitive, &pattern, &CancellationToken::new(), &tx).unwrap();
+    drop(tx);
+    let files: Vec<ContentSearchEvent> = rx.iter().collect();
+    let [ContentSearchEvent::File(file)] = files.as_slice() else {
+        panic!("expected one file, got {:?}", files);
+    };
+    assert!(file.path.ends_with("src/main.rs"));
+    assert_eq!(file.matches.len(), 1);
+    assert_eq!(file.matches[0].line, "    // TODO: parse args");
+    assert_eq!(file.matches[0].ranges, vec![Range { start: 7, end: 11 }]);
+    assert!(!file.truncated);
+}
+
+#[test]
+fn query_options_shape_the_pattern() {
+    let dir = workspace();
+    let mut whole_word = options("add");
+    whole_word.whole_word = true;
+    assert_eq!(search(dir.path(), &whole_word), found(&[("src/lib.rs", &[1])]));
+    whole_word.query = "ad".to_string();
+    assert!(search(dir.path(), &whole_word).is_empty());
+
+    let mut regex = options(r"fn \w+\(\)");
+    regex.regex = true;
+    assert_eq!(search(dir.path(), &regex), found(&[("src/lib.rs", &[1]), ("src/main.rs", &[1])]));
+    // Without `regex` the same query is literal text
+    regex.regex = false;
+    assert!(search(dir.path(), &regex).is_empty());
+
+    assert_eq!(options("").pattern().unwrap_err().to_string(), "Nothing to search for");
+    let mut invalid = options("(unclosed");
+    invalid.regex = true;
+    assert!(invalid.pattern().unwrap_err().to_string().starts_with("Invalid regex"));
+}
+
+#[test]
+fn ignored_hidden_binary_and_excluded_files_are_skipped() {
+    let dir = workspace();
+    let files: Vec<String> = search(dir.path(), &options("todo")).into_iter().map(|(path, _)| path).collect();
+    for skipped in ["target/debug/out.rs", "build.log", "node_modules/pkg/index.js", ".hidden/notes.txt", "image.bin"] {
+        assert!(!files.contains(&skipped.to_string()), "{} was searched", skipped);
+    }
+
+    let mut globs = options("todo");
+    globs.include = vec!["*.rs".to_string()];
+    globs.exclude = vec!["lib.rs".to_string()];
+    assert_eq!(search(dir.path(), &globs), found(&[("src/main.rs", &[2, 3])]));
+
+    let mut small = options("todo");
+    small.max_file_size = 40;
+    assert_eq!(search(dir.path(), &small), found(&[("README.md", &[2]), ("src/lib.rs", &[2])]));
+
+    let mut bad_glob = options("todo");
+    bad_glob.include = vec!["src/[".to_string()];
+    let (tx, _rx) = crossbeam_channel::unbounded();
+    let pattern = bad_glob.pattern().unwrap();
+    let error = search_builtin(dir.path(), &bad_glob, &pattern, &CancellationToken::new(), &tx).unwrap_err();
+    assert!(error.to_string().contains("Invalid include glob"), "{}", error);
+}
+
+#[test]
+fn matches_per_file_are_limited() {
+    let dir = tempfile::tempdir().unwrap();
+    let content: String = (0..50).map(|i| format!("match {}\n", i)).collect();
+    std::fs::write(dir.path().join("many.txt"), content).unwrap();
+    std::fs::write(dir.path().join("few.txt"), "match\nmatch\n").unwrap();
+
+    let mut limited = options("match");
+    limited.max_matches_per_file = 5;
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let pattern = limited.pattern().unwrap();
+    search_builtin(dir.path(), &limited, &pattern, &CancellationToken::new(), &tx).unwrap();
+    drop(tx);
+    let mut files: Vec<FileMatches> = rx
+        .iter()
+        .filter_map(|event| match event {
+            ContentSearchEvent::File(file) => Some(file),
+            _ => None,
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    assert!(files[0].path.ends_with("few.txt"));
+    assert_eq!(files[0].matches.len(), 2);
+    assert!(!files[0].truncated);
+    assert!(files[1].path.ends_with("many.txt"));
+    let lines: Vec<usize> = files[1].matches.iter().map(|line| line.line_number).collect();
+    assert_eq!(lines, [1, 2, 3, 4, 5]);
+    assert!(files[1].truncated);
+}
+
+#[test]
+fn searches_report_how_they_ended() {
+    let dir = workspace();
+    let (tx, rx) = crossbeam_channel::unbounded();
+    search_contents(dir.path(), &options("todo"), &CancellationToken::new(), &tx);
+    drop(tx);
+    let events: Vec<ContentSearchEvent> = rx.iter().collect();
+    assert!(matches!(events.first(), Some(ContentSearchEvent::Started(_))));
+    assert!(matches!(events.last(), Some(ContentSearchEvent::Finished)));
+    let files = events
+        .iter()
+        .filter_map(|event| match event {
+            ContentSearchEvent::File(file) => Some(file.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        summarize(dir.path(), files),
+        found(&[("README.md", &[2]), ("src/lib.rs", &[2]), ("src/main.rs", &[2, 3])])
+    );
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let (tx, rx) = crossbeam_channel::unbounded();
+    search_contents(dir.path(), &options("todo"), &token, &tx);
+    drop(tx);
+    let events: Vec<ContentSearchEvent> = rx.iter().collect();
+    assert!(matches!(events.last(), Some(ContentSearchEvent::Cancelled)));
+    assert!(!events.iter().any(|event| matches!(event, ContentSearchEvent::File(_))));
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    search_contents(dir.path(), &options(""), &CancellationToken::new(), &tx);
+    drop(tx);
+    let events: Vec<ContentSearchEvent> = rx.iter().collect();
+    assert!(matches!(events.as_slice(), [ContentSearchEvent::Failed(message)] if message == "Nothing to search for"));
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use antraft::ai::chat::{ChatSession, ChatSessionManager, MessageRole, CHAT_SCHEMA_VERSION};
+use antraft::ai::{AiAgent, AiRequest, ChatMessage};
+use common::{ai_config, MockProvider};
+use serde_json::{json, Value};
+
+fn session() -> ChatSession {
+    let mut session = ChatSession::new("Disk usage".to_string());
+    session.add_message(ChatMessage::user("What is using my disk?".to_string()));
+    session.add_message(
+        ChatMessage::assistant("Try `du -sh * | sort -h`.".to_string()).with_metadata(json!({ "command": "du" })),
+    );
+    session.add_message(ChatMessage::system("Context cleared".to_string()));
+    session
+}
+
+fn summary(message: &ChatMessage) -> (String, String, String, Option<Value>) {
+    (
+        message.id.to_string(),
+        format!("{:?}", message.role),
+        message.content.clone(),
+        message.metadata.clone(),
+    )
+}
+
+#[test]
+fn exported_sessions_import_unchanged() {
+    let original = session();
+    let json = original.to_json().unwrap();
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["schema_version"], CHAT_SCHEMA_VERSION);
+
+    let imported = ChatSession::from_json(&json).unwrap();
+    assert_eq!(imported.id, original.id);
+    assert_eq!(imported.title, "Disk usage");
+    assert_eq!(imported.created_at, original.created_at);
+    assert_eq!(imported.updated_at, original.updated_at);
+    assert_eq!(
+        imported.messages.iter().map(summary).collect::<Vec<_>>(),
+        original.messages.iter().map(summary).collect::<Vec<_>>()
+    );
+    assert_eq!(imported.messages[0].timestamp, original.messages[0].timestamp);
+
+    // Exporting the import again gives the same file
+    assert_eq!(imported.to_json().unwrap(), json);
+}
+
+#[test]
+fn long_conversations_keep_every_message() {
+    let mut original = ChatSession::new("Long".to_string());
+    original.max_messages = 500;
+    for i in 0..300 {
+        original.add_message(ChatMessage::user(format!("message {}", i)));
+    }
+
+    let imported = ChatSession::from_json(&original.to_json().unwrap()).unwrap();
+    assert_eq!(imported.messages.len(), 300);
+    assert_eq!(imported.messages[299].content, "message 299");
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let mut value: Value = serde_json::from_str(&session().to_json().unwrap()).unwrap();
+
+    value["schema_version"] = json!(CHAT_SCHEMA_VERSION + 1);
+    let error = ChatSession::from_json(&value.to_string()).unwrap_err().to_string();
+    assert!(error.contains("newer version"), "{}", error);
+
+    value["schema_version"] = json!("2");
+    let error = ChatSession::from_json(&value.to_string()).unwrap_err().to_string();
+    assert!(error.contains("Invalid schema_version"), "{}", error);
+
+    value["schema_version"] = json!(-1);
+    assert!(ChatSession::from_json(&value.to_string()).is_err());
+}
+
+#[test]
+fn files_that_are_not_conversations_are_rejected() {
+    assert!(ChatSession::from_json("not json").is_err());
+    assert!(ChatSession::from_json("[1, 2]").is_err());
+    assert!(ChatSession::from_json(r#"{"schema_version": 2, "title": "No messages"}"#).is_err());
+}
+
+#[test]
+fn unversioned_files_are_migrated() {
+    let v1 = json!({
+        "title": "Old export",
+        "created_at": "2024-01-01T10:00:00Z",
+        "updated_at": "2024-01-01T10:05:00Z",
+        "messages": [
+            { "role": "User", "content": "hi", "timestamp": "2024-01-01T10:00:00Z" },
+            { "role": "Assistant", "content": "hello", "timestamp": "2024-01-01T10:00:01Z" }
+        ]
+    });
+
+    let session = ChatSession::from_json(&v1.to_string()).unwrap();
+    assert_eq!(session.title, "Old export");
+    assert_eq!(session.messages.len(), 2);
+    assert!(matches!(session.messages[1].role, MessageRole::Assistant));
+    assert_ne!(session.messages[0].id, session.messages[1].id);
+    assert!(session.messages[0].metadata.is_none());
+
+    let value: Value = serde_json::from_str(&session.to_json().unwrap()).unwrap();
+    assert_eq!(value["schema_version"], CHAT_SCHEMA_VERSION);
+}
+
+#[test]
+fn imports_with_a_taken_id_get_a_new_one() {
+    let original = session();
+    let json = original.to_json().unwrap();
+    let mut manager = ChatSessionManager::new();
+
+    let first = manager.import_session(ChatSession::from_json(&json).unwrap());
+    assert_eq!(first, original.id);
+    let second = manager.import_session(ChatSession::from_json(&json).unwrap());
+    assert_ne!(second, first);
+    assert_eq!(manager.get_active_session().unwrap().id, second);
+}
+
+#[tokio::test]
+async fn agent_conversations_round_trip() {
+    let provider = MockProvider::new();
+    provider.respond("Use `df -h`.");
+    let agent = AiAgent::with_provider(ai_config(), Box::new(provider));
+    agent
+        .process_request(AiRequest::Chat {
+            message: "How full are my disks?".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let json = agent.export_chat_to_json().await.unwrap().unwrap();
+    let other = AiAgent::with_provider(ai_config(), Box::new(MockProvider::new()));
+    let messages = other.import_chat_from_json(&json).await.unwrap();
+    let contents: Vec<&str> = messages.iter().map(|message| message.content.as_str()).collect();
+    assert_eq!(contents, ["How full are my disks?", "Use `df -h`."]);
+    assert_eq!(other.export_chat_to_json().await.unwrap().unwrap(), json);
+
+    assert!(other.import_chat_from_json(r#"{"schema_version": 99}"#).await.is_err());
+}
@@ -0,0 +1,49 @@
+use antraft::crash::{self, CrashReport, SessionSnapshot};
+use antraft::terminal::block::Block;
+
+#[test]
+fn a_crash_flushes_then_writes_the_report_and_a_marker_read_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = SessionSnapshot {
+        command_input: "cargo tes".to_string(),
+        blocks: vec![Block::output("hello".to_string())],
+        chat: vec![("You".to_string(), "why?".to_string())],
+        ..SessionSnapshot::default()
+    };
+    let session_path = crash::session_path(dir.path());
+    crash::on_crash("session", move || crash::save_session(&session_path, &session));
+    crash::on_crash("broken", || panic!("flush panicked"));
+    let mut report = CrashReport::new("index out of bounds".to_string(), Some("src/ui/mod.rs:1:1".to_string()));
+    report.logs = vec!["[INFO] before the crash".to_string()];
+
+    let marker = crash::write_crash(dir.path(), report).unwrap();
+    let report = std::fs::read_to_string(&marker.report).unwrap();
+    assert!(report.contains("index out of bounds"), "{}", report);
+    assert!(report.contains("at src/ui/mod.rs:1:1"), "{}", report);
+    assert!(report.contains(env!("CARGO_PKG_VERSION")), "{}", report);
+    assert!(report.contains("[INFO] before the crash"), "{}", report);
+    assert!(report.contains("session: saved"), "{}", report);
+    assert!(report.contains("broken: failed: flush panicked"), "{}", report);
+    assert!(report.contains("Backtrace:"), "{}", report);
+
+    assert_eq!(crash::take_crash_marker(dir.path()), Some(marker.clone()));
+    assert_eq!(crash::take_crash_marker(dir.path()), None);
+    let restored = crash::load_session(marker.session.as_deref().unwrap()).unwrap();
+    assert_eq!(restored.command_input, "cargo tes");
+    assert_eq!(restored.blocks[0].content, "hello");
+    assert_eq!(restored.chat.len(), 1);
+}
+
+#[tokio::test]
+async fn panics_in_tasks_become_errors() {
+    let result = tokio::spawn(crash::catch_panic(async {
+        tokio::task::yield_now().await;
+        let items: Vec<u8> = Vec::new();
+        items[3]
+    }))
+    .await
+    .unwrap();
+    assert!(result.unwrap_err().contains("index out of bounds"));
+
+    assert_eq!(crash::catch_panic(async { 7 }).await, Ok(7));
+}
@@ -0,0 +1,86 @@
+use antraft::security::custom_rules::CustomRuleScanner;
+use antraft::security::{SecurityConfig, Severity};
+use std::path::Path;
+
+const RULES: &str = r#"
+[[rules]]
+id = "no-eval"
+pattern = '\beval\('
+severity = "high"
+message = "eval() runs arbitrary code"
+category = "injection"
+languages = ["python", "javascript"]
+fix = "Parse the input instead"
+
+[[rules]]
+id = "todo-security"
+pattern = 'TODO\(security\)'
+severity = "info"
+message = "Open security TODO"
+"#;
+
+fn scanner() -> CustomRuleScanner {
+    CustomRuleScanner::parse(RULES, &SecurityConfig::default()).unwrap()
+}
+
+#[test]
+fn reports_matches_at_their_line_and_column() {
+    let content = "import os\n\nresult = eval(user_input)\n  x = eval(a) + eval(b)\n";
+    let found = scanner().scan_content(Path::new("app.py"), content);
+
+    let lines: Vec<_> = found.iter().map(|v| (v.line_number, v.column_number)).collect();
+    // One finding per line, at the first match
+    assert_eq!(lines, [(Some(3), Some(10)), (Some(4), Some(7))]);
+    assert_eq!(found[0].id, "no-eval");
+    assert_eq!(found[0].title, "eval() runs arbitrary code");
+    assert_eq!(found[0].category, "injection");
+    assert!(matches!(found[0].severity, Severity::High));
+    assert_eq!(found[0].code_snippet.as_deref(), Some("result = eval(user_input)"));
+    assert_eq!(found[0].suggested_fix.as_deref(), Some("Parse the input instead"));
+}
+
+#[test]
+fn limits_rules_to_their_languages() {
+    let content = "eval(x) // TODO(security)\n";
+    let in_go: Vec<_> = scanner()
+        .scan_content(Path::new("main.go"), content)
+        .into_iter()
+        .map(|v| v.id)
+        .collect();
+    assert_eq!(in_go, ["todo-security"]);
+
+    let in_js = scanner().scan_content(Path::new("web/app.js"), content);
+    assert_eq!(in_js.len(), 2);
+}
+
+#[test]
+fn rejects_invalid_rules() {
+    let config = SecurityConfig::default();
+    let invalid = [
+        ("pattern = '('\nseverity = \"high\"", "invalid pattern"),
+        ("pattern = 'x'\nseverity = \"urgent\"", "unknown severity"),
+        ("pattern = 'x'\nseverity = \"low\"\nlanguages = [\"cobol\"]", "unknown language"),
+    ];
+    for (fields, expected) in invalid {
+        let rules = format!("[[rules]]\nid = \"bad\"\nmessage = \"m\"\n{}\n", fields);
+        let error = CustomRuleScanner::parse(&rules, &config).err().unwrap();
+        assert!(format!("{:#}", error).contains(expected), "{:#}", error);
+    }
+
+    let twice = format!("{}\n{}", RULES, RULES);
+    assert!(CustomRuleScanner::parse(&twice, &config).is_err());
+}
+
+#[test]
+fn scans_a_directory_skipping_excluded_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.py"), "print(eval('1'))\n").unwrap();
+    std::fs::create_dir(dir.path().join("node_modules")).unwrap();
+    std::fs::write(dir.path().join("node_modules/lib.js"), "eval(code)\n").unwrap();
+    std::fs::write(dir.path().join("blob.py"), b"eval(\0binary").unwrap();
+
+    let found = scanner().scan_path(dir.path()).unwrap();
+    assert_eq!(found.len(), 1);
+    assert!(found[0].file_path.ends_with("app.py"));
+    assert_eq!(found[0].line_number, Some(1));
+}
@@ -0,0 +1,164 @@
+use antraft::security::inventory::{self, DependencyInventory, Ecosystem, LicenseDenylist, LicenseSource};
+use antraft::security::{SecurityConfig, Severity};
+use std::path::Path;
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inventory").join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn write(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+fn names_and_versions(packages: &[inventory::LockedPackage]) -> Vec<(&str, &str)> {
+    packages.iter().map(|package| (package.name.as_str(), package.version.as_str())).collect()
+}
+
+#[test]
+fn parses_lockfiles() {
+    // Workspace and path crates have no source
+    let crates = inventory::parse_cargo_lock(&fixture("cargo-lock.toml")).unwrap();
+    assert_eq!(names_and_versions(&crates), [("anyhow", "1.0.86"), ("serde", "1.0.203"), ("tool", "0.4.1")]);
+    assert!(crates.iter().all(|package| package.ecosystem == Ecosystem::CratesIo));
+
+    let npm = inventory::parse_package_lock(&fixture("package-lock.json")).unwrap();
+    assert_eq!(
+        names_and_versions(&npm),
+        [("express", "4.19.2"), ("debug", "2.6.9"), ("@scope/copyleft", "2.0.0"), ("leftpad", "1.0.0")]
+    );
+    assert_eq!(npm[2].license.as_deref(), Some("AGPL-3.0-only"));
+    assert_eq!(npm[3].license, None);
+
+    let v1 = r#"{"lockfileVersion": 1, "dependencies": {
+        "a": {"version": "1.0.0", "dependencies": {"b": {"version": "2.0.0"}}},
+        "local": {"version": "file:../local"}
+    }}"#;
+    assert_eq!(names_and_versions(&inventory::parse_package_lock(v1).unwrap()), [("a", "1.0.0"), ("b", "2.0.0")]);
+
+    let python = inventory::parse_requirements(&fixture("requirements.txt"));
+    assert_eq!(
+        names_and_versions(&python),
+        [("Django", "4.2.1"), ("requests", "2.31.0"), ("typing-extensions", "4.7.1")]
+    );
+    let poetry = "[[package]]\nname = \"click\"\nversion = \"8.1.7\"\n\n[metadata]\nlock-version = \"2.0\"\n";
+    assert_eq!(names_and_versions(&inventory::parse_poetry_lock(poetry).unwrap()), [("click", "8.1.7")]);
+    assert!(inventory::parse_cargo_lock("[[package]\n").is_err());
+}
+
+#[test]
+fn reads_licenses_from_package_metadata() {
+    assert_eq!(
+        inventory::package_json_license(&serde_json::json!({"license": {"type": "BSD-3-Clause"}})).as_deref(),
+        Some("BSD-3-Clause")
+    );
+    assert_eq!(
+        inventory::package_json_license(&serde_json::json!({"licenses": [{"type": "MIT"}, {"type": "GPL-2.0"}]}))
+            .as_deref(),
+        Some("MIT OR GPL-2.0")
+    );
+
+    let metadata = "Metadata-Version: 2.1\nName: Django\nLicense: BSD-3-Clause\nClassifier: License :: OSI Approved :: BSD License\n\nLicense: not a header\n";
+    assert_eq!(inventory::python_metadata_license(metadata).as_deref(), Some("BSD-3-Clause"));
+    let metadata = "Name: copyleft\nLicense: UNKNOWN\nClassifier: License :: OSI Approved :: GNU General Public License v3 (GPLv3)\n";
+    assert_eq!(inventory::python_metadata_license(metadata).as_deref(), Some("GPL-3.0"));
+    let metadata = "Name: new\nLicense-Expression: Apache-2.0 OR MIT\nLicense: Apache\n";
+    assert_eq!(inventory::python_metadata_license(metadata).as_deref(), Some("Apache-2.0 OR MIT"));
+
+    let crates_io = serde_json::json!({"version": {"num": "1.0.86", "license": "MIT OR Apache-2.0"}});
+    assert_eq!(
+        inventory::registry_license(Ecosystem::CratesIo, &crates_io).as_deref(),
+        Some("MIT OR Apache-2.0")
+    );
+    let pypi = serde_json::json!({"info": {"license": "Copyright (c) 2023\nAll rights reserved", "classifiers": ["License :: OSI Approved :: MIT License"]}});
+    assert_eq!(inventory::registry_license(Ecosystem::PyPI, &pypi).as_deref(), Some("MIT"));
+}
+
+#[test]
+fn matches_the_denylist() {
+    let denylist = LicenseDenylist::new(&["GPL-3.0".to_string(), "AGPL".to_string()]);
+    let cases: &[(&str, Option<&str>)] = &[
+        ("GPL-3.0", Some("GPL-3.0")),
+        ("GPL-3.0-only", Some("GPL-3.0")),
+        ("gpl-3.0-or-later", Some("GPL-3.0")),
+        ("GPL-3.0+", Some("GPL-3.0")),
+        ("AGPL-3.0-only", Some("AGPL")),
+        ("MIT AND (AGPL-3.0 OR GPL-3.0)", Some("AGPL")),
+        ("GPL-3.0 WITH Classpath-exception-2.0", Some("GPL-3.0")),
+        ("LGPL-3.0", None),
+        ("GPL-2.0", None),
+        ("GPL-3", None),
+        ("AGPLv3-like", None),
+        ("MIT", None),
+        // A choice is fine while one option is allowed
+        ("MIT OR GPL-3.0", None),
+        ("(GPL-3.0-only OR MIT) AND Apache-2.0", None),
+        ("MIT/GPL-3.0", None),
+        ("GPL-3.0/AGPL-3.0", Some("GPL-3.0")),
+    ];
+    for (license, expected) in cases {
+        assert_eq!(denylist.matches(license), *expected, "{}", license);
+    }
+    assert!(LicenseDenylist::new(&[" ".to_string()]).is_empty());
+}
+
+#[test]
+fn builds_an_inventory_from_local_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    write(root, "Cargo.lock", &fixture("cargo-lock.toml"));
+    write(root, "vendor/serde/Cargo.toml", "[package]\nname = \"serde\"\nversion = \"1.0.203\"\nlicense = \"MIT OR Apache-2.0\"\n");
+    // Another version than the lockfile's
+    write(root, "vendor/anyhow/Cargo.toml", "[package]\nname = \"anyhow\"\nversion = \"0.9.0\"\nlicense = \"MIT\"\n");
+    write(root, "web/package-lock.json", &fixture("package-lock.json"));
+    write(root, "web/node_modules/leftpad/package.json", r#"{"name": "leftpad", "version": "1.0.0", "license": "WTFPL"}"#);
+    // Not a lockfile of the project's
+    write(root, "web/node_modules/express/package-lock.json", "{}");
+    write(root, "py/requirements.txt", &fixture("requirements.txt"));
+    write(
+        root,
+        "py/.venv/lib/python3.11/site-packages/Django-4.2.1.dist-info/METADATA",
+        "Name: Django\nVersion: 4.2.1\nLicense: BSD-3-Clause\n",
+    );
+    write(
+        root,
+        "py/.venv/lib/python3.11/site-packages/typing_extensions-4.7.1.dist-info/METADATA",
+        "Name: typing_extensions\nClassifier: License :: OSI Approved :: Python Software Foundation License\n",
+    );
+    write(root, "broken/poetry.lock", "not toml [");
+
+    let inventory = DependencyInventory::local(root, &SecurityConfig::default());
+    assert_eq!(inventory.errors.len(), 1);
+    assert!(inventory.errors[0].contains("poetry.lock"));
+    let license = |name: &str| {
+        let entry = inventory.entries.iter().find(|entry| entry.name == name).unwrap();
+        (entry.license.as_deref(), entry.license_source)
+    };
+    assert_eq!(license("serde"), (Some("MIT OR Apache-2.0"), Some(LicenseSource::Local)));
+    assert_eq!(license("express"), (Some("MIT"), Some(LicenseSource::Lockfile)));
+    assert_eq!(license("leftpad"), (Some("WTFPL"), Some(LicenseSource::Local)));
+    assert_eq!(license("Django"), (Some("BSD-3-Clause"), Some(LicenseSource::Local)));
+    assert_eq!(license("typing-extensions").0, Some("Python Software Foundation License"));
+    // Unknown licenses are listed, not errors
+    let unknown: Vec<_> = inventory.unknown().map(|entry| entry.name.as_str()).collect();
+    assert!(unknown.contains(&"requests") && unknown.contains(&"tool"), "{:?}", unknown);
+    assert_eq!(inventory.entries.len(), 10);
+
+    let counts = inventory.license_counts();
+    assert_eq!(counts[0].1, counts.iter().map(|(_, count)| *count).max().unwrap());
+    assert!(counts.contains(&("MIT".to_string(), 2)));
+    assert!(counts.iter().any(|(license, _)| license == "Unknown"));
+
+    let findings = inventory.findings();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].severity, Severity::Medium);
+    assert_eq!(findings[0].title, "@scope/copyleft 2.0.0 is licensed AGPL-3.0-only");
+    assert_eq!(Path::new(&findings[0].file_path), Path::new("web/package-lock.json"));
+
+    let csv = inventory.to_csv();
+    assert!(csv.starts_with("ecosystem,name,version,license,license_source,lockfile,denied_by\n"));
+    assert!(csv.contains("\nnpm,@scope/copyleft,2.0.0,AGPL-3.0-only,lockfile,"));
+    assert!(csv.lines().any(|line| line.starts_with("crates.io,tool,0.4.1,,,")));
+}
@@ -0,0 +1,60 @@
+use antraft::doctor;
+use antraft::tools::{self, ToolDetector};
+
+#[cfg(unix)]
+fn fake_tool(dir: &std::path::Path, name: &str, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn detects_tools_on_the_search_path_with_their_versions() {
+    let bin = tempfile::tempdir().unwrap();
+    fake_tool(bin.path(), "bandit", "echo; echo 'bandit 1.7.9'; echo '  python version = 3.12'");
+    fake_tool(bin.path(), "semgrep", "echo 'no version' >&2; exit 2");
+    let detector = ToolDetector::with_search_path(bin.path());
+
+    let bandit = detector.detect(&tools::BANDIT).unwrap();
+    assert_eq!(bandit.path, bin.path().join("bandit"));
+    assert_eq!(bandit.version.as_deref(), Some("bandit 1.7.9"));
+    assert_eq!(detector.detect(&tools::SEMGREP).unwrap().version, None);
+    assert_eq!(detector.detect(&tools::TRIVY), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn reports_the_scanners_the_config_enables() {
+    let bin = tempfile::tempdir().unwrap();
+    fake_tool(bin.path(), "semgrep", "echo 1.80.0");
+    fake_tool(bin.path(), "bash", "echo 'GNU bash, version 5.2'");
+    let config = tempfile::tempdir().unwrap();
+    let config_path = config.path().join("config.toml");
+    std::fs::write(&config_path, "[security]\nenable_trivy = false\n").unwrap();
+
+    let diagnosis = doctor::diagnose(Some(&config_path), None, &ToolDetector::with_search_path(bin.path()));
+    assert!(diagnosis.config_exists);
+    assert_eq!(diagnosis.shell.1, Some(bin.path().join("bash")));
+    let shells: Vec<_> = diagnosis.shells.iter().map(|(tool, _)| tool.name).collect();
+    assert_eq!(shells, ["bash"]);
+
+    let report = diagnosis.to_string();
+    assert!(report.contains("✓ Semgrep      1.80.0"), "{}", report);
+    assert!(report.contains("✗ Bandit       not installed"), "{}", report);
+    assert!(report.contains("- Trivy        disabled in the config"), "{}", report);
+}
+
+#[test]
+fn a_config_that_does_not_load_is_reported_with_the_defaults_used() {
+    let config = tempfile::tempdir().unwrap();
+    let config_path = config.path().join("config.toml");
+    std::fs::write(&config_path, "[security\n").unwrap();
+
+    let diagnosis = doctor::diagnose(Some(&config_path), None, &ToolDetector::with_search_path(""));
+    assert_eq!(diagnosis.config_path, config_path);
+    assert!(diagnosis.config_problem.unwrap().contains("Failed to parse config file"));
+    assert_eq!(diagnosis.scanners.len(), 4);
+}
@@ -0,0 +1,95 @@
+use antraft::terminal::editor::{expand_command, is_terminal_editor, EditorIntegration, EditorSource};
+use std::path::Path;
+
+fn expand(template: &str, path: &str, line: Option<u32>, column: Option<u32>) -> Vec<String> {
+    expand_command(template, Path::new(path), line, column).unwrap()
+}
+
+#[test]
+fn placeholders_are_filled_in() {
+    assert_eq!(
+        expand("code --goto {file}:{line}:{col}", "src/main.rs", Some(142), Some(17)),
+        ["code", "--goto", "src/main.rs:142:17"]
+    );
+    assert_eq!(
+        expand("subl {file}:{line}", "src/lib.rs", Some(3), None),
+        ["subl", "src/lib.rs:3"]
+    );
+    // Without a position the file opens at its start
+    assert_eq!(
+        expand("zed {file}:{line}:{col}", "notes.md", None, None),
+        ["zed", "notes.md:1:1"]
+    );
+    // Every occurrence is replaced
+    assert_eq!(
+        expand("editor --file {file} --title {file}", "a.txt", None, None),
+        ["editor", "--file", "a.txt", "--title", "a.txt"]
+    );
+}
+
+#[test]
+fn paths_with_spaces_stay_one_argument() {
+    assert_eq!(
+        expand("code --goto {file}:{line}", "/home/me/My Projects/main.rs", Some(8), None),
+        ["code", "--goto", "/home/me/My Projects/main.rs:8"]
+    );
+    assert_eq!(
+        expand("\"/Applications/Sublime Text.app/Contents/SharedSupport/bin/subl\" '{file}'", "a b.rs", None, None),
+        ["/Applications/Sublime Text.app/Contents/SharedSupport/bin/subl", "a b.rs"]
+    );
+    assert_eq!(expand("gedit", "my notes.txt", Some(2), None), ["gedit", "+2", "my notes.txt"]);
+    // Windows paths can be written without doubling backslashes
+    assert_eq!(
+        expand(r"C:\Tools\edit.exe {file}", r"C:\Users\me\a b.txt", None, None),
+        [r"C:\Tools\edit.exe", r"C:\Users\me\a b.txt"]
+    );
+    assert_eq!(expand(r"my\ editor {file}", "x", None, None), ["my editor", "x"]);
+}
+
+#[test]
+fn templates_without_a_file_get_the_editors_own_form() {
+    let path = "src/main.rs";
+    assert_eq!(expand("code", path, Some(4), Some(2)), ["code", "--goto", "src/main.rs:4:2"]);
+    assert_eq!(expand("code --wait", path, None, None), ["code", "--wait", "--goto", "src/main.rs:1:1"]);
+    assert_eq!(expand("hx", path, Some(4), Some(2)), ["hx", "src/main.rs:4:2"]);
+    assert_eq!(expand("idea", path, Some(4), None), ["idea", "--line", "4", "src/main.rs"]);
+    assert_eq!(expand("/usr/bin/nvim", path, Some(4), None), ["/usr/bin/nvim", "+4", "src/main.rs"]);
+    // No line means no `+1`
+    assert_eq!(expand("vim", path, None, None), ["vim", "src/main.rs"]);
+    assert_eq!(expand("open -t", path, Some(4), None), ["open", "-t", "src/main.rs"]);
+}
+
+#[test]
+fn misconfigured_commands_are_errors() {
+    let path = Path::new("a.txt");
+    let error = expand_command("", path, None, None).unwrap_err().to_string();
+    assert_eq!(error, "the command is empty");
+    let error = expand_command("code \"--goto {file}", path, None, None).unwrap_err().to_string();
+    assert_eq!(error, "unclosed \" quote");
+
+    let editor = EditorIntegration::new(Some("code '{file}".to_string()));
+    let error = format!("{:#}", editor.open(path, None, None).unwrap_err());
+    assert!(error.contains("Invalid editor command \"code '{file}\" (from terminal.editor_command)"), "{}", error);
+    assert!(error.contains("unclosed ' quote"), "{}", error);
+
+    let editor = EditorIntegration::new(Some("nvim".to_string()));
+    let error = format!("{:#}", editor.open(path, Some(1), None).unwrap_err());
+    assert!(error.contains("runs in a terminal"), "{}", error);
+
+    let editor = EditorIntegration::new(Some("definitely-not-an-editor-xyz {file}".to_string()));
+    let error = format!("{:#}", editor.open(path, None, None).unwrap_err());
+    assert!(error.contains("Couldn't start editor `definitely-not-an-editor-xyz`"), "{}", error);
+}
+
+#[test]
+fn the_configured_command_comes_first() {
+    let editor = EditorIntegration::new(Some("code --goto {file}:{line}".to_string()));
+    assert_eq!(editor.command(), ("code --goto {file}:{line}".to_string(), EditorSource::Config));
+    // A blank setting counts as unset
+    assert_ne!(EditorIntegration::new(Some("  ".to_string())).command().1, EditorSource::Config);
+
+    assert!(is_terminal_editor("vim"));
+    assert!(is_terminal_editor("/usr/local/bin/NVIM"));
+    assert!(!is_terminal_editor("code"));
+    assert!(!is_terminal_editor("gvim"));
+}
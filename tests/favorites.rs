@@ -0,0 +1,61 @@
+use antraft::terminal::favorites::Favorites;
+
+#[test]
+fn toggling_stars_and_unstars_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut favorites = Favorites::empty(&dir.path().join("favorites.json"));
+
+    assert!(favorites.toggle("cargo test --workspace"));
+    assert!(favorites.contains("  cargo test --workspace "));
+    // Already a favorite, or nothing to star
+    assert!(!favorites.add("cargo test --workspace"));
+    assert!(!favorites.add("   "));
+    assert_eq!(favorites.len(), 1);
+
+    assert!(!favorites.toggle("cargo test --workspace"));
+    assert!(favorites.is_empty());
+}
+
+#[test]
+fn favorites_persist_with_their_labels() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config").join("favorites.json");
+    let mut favorites = Favorites::empty(&path);
+    favorites.add("docker compose up -d --build");
+    favorites.add("git fetch --prune");
+    favorites.set_label("docker compose up -d --build", "  Rebuild stack ");
+    favorites.save().unwrap();
+
+    let loaded = Favorites::load(&path).unwrap();
+    let commands: Vec<&str> = loaded.all().iter().map(|f| f.command.as_str()).collect();
+    assert_eq!(commands, ["docker compose up -d --build", "git fetch --prune"]);
+    assert_eq!(loaded.all()[0].title(), "Rebuild stack");
+    assert_eq!(loaded.all()[1].title(), "git fetch --prune");
+
+    // A blank label clears it
+    let mut loaded = loaded;
+    loaded.set_label("docker compose up -d --build", " ");
+    assert_eq!(loaded.all()[0].label, None);
+
+    assert!(Favorites::load(&dir.path().join("missing.json")).unwrap().is_empty());
+    std::fs::write(&path, "not json").unwrap();
+    assert!(Favorites::load(&path).is_err());
+}
+
+#[test]
+fn matches_search_labels_and_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut favorites = Favorites::empty(&dir.path().join("favorites.json"));
+    favorites.add("kubectl get pods -A");
+    favorites.add("npm run dev");
+    favorites.set_label("npm run dev", "Frontend server");
+
+    let titles = |query: &str| -> Vec<String> {
+        favorites.matches(query).iter().map(|f| f.title().to_string()).collect()
+    };
+    assert_eq!(titles(""), ["kubectl get pods -A", "Frontend server"]);
+    assert_eq!(titles("pods"), ["kubectl get pods -A"]);
+    assert_eq!(titles("frontend"), ["Frontend server"]);
+    assert_eq!(titles("npm dev"), ["Frontend server"]);
+    assert!(titles("terraform").is_empty());
+}
@@ -0,0 +1,78 @@
+use antraft::file_explorer::{CancellationToken, FileExplorer, FileNode, TreeLoadEvent};
+use std::path::Path;
+use tokio::sync::mpsc;
+
+/// `dirs` directories of `files` files each.
+fn make_tree(root: &Path, dirs: usize, files: usize) {
+    for dir in 0..dirs {
+        let dir = root.join(format!("dir{:02}", dir));
+        std::fs::create_dir(&dir).unwrap();
+        for file in 0..files {
+            std::fs::write(dir.join(format!("file{:03}.txt", file)), "").unwrap();
+        }
+    }
+}
+
+fn count(node: &FileNode) -> usize {
+    1 + node.children.iter().flatten().map(count).sum::<usize>()
+}
+
+fn progress(events: &mut mpsc::UnboundedReceiver<TreeLoadEvent>) -> Vec<usize> {
+    let mut discovered = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        match event {
+            TreeLoadEvent::Progress { discovered: n } => discovered.push(n),
+            other => panic!("the loader only sends progress: {:?}", other),
+        }
+    }
+    discovered
+}
+
+#[test]
+fn loading_reports_progress_every_200_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    make_tree(dir.path(), 5, 99);
+    let loader = FileExplorer::new(dir.path().to_path_buf()).unwrap().tree_loader();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let root = loader.load(&CancellationToken::new(), Some(&tx)).unwrap().unwrap();
+
+    // The root, 5 directories and 495 files
+    assert_eq!(count(&root), 501);
+    assert_eq!(progress(&mut rx), [200, 400]);
+    let first = &root.children.as_ref().unwrap()[0];
+    assert_eq!(first.name, "dir00");
+    assert_eq!(first.children.as_ref().unwrap().len(), 99);
+}
+
+#[test]
+fn a_cancelled_load_stops_part_way() {
+    let dir = tempfile::tempdir().unwrap();
+    make_tree(dir.path(), 40, 100);
+    let loader = FileExplorer::new(dir.path().to_path_buf()).unwrap().tree_loader();
+
+    let token = CancellationToken::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let load = std::thread::spawn({
+        let token = token.clone();
+        move || loader.load(&token, Some(&tx))
+    });
+    // Cancelled once the first progress arrives
+    assert!(matches!(rx.blocking_recv(), Some(TreeLoadEvent::Progress { discovered: 200 })));
+    token.cancel();
+
+    assert!(load.join().unwrap().unwrap().is_none());
+    // 4041 entries would have sent 20
+    assert!(progress(&mut rx).len() < 19);
+}
+
+#[test]
+fn a_load_cancelled_before_it_starts_returns_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    make_tree(dir.path(), 2, 2);
+    let loader = FileExplorer::new(dir.path().to_path_buf()).unwrap().tree_loader();
+
+    let token = CancellationToken::new();
+    token.cancel();
+    assert!(loader.load(&token, None).unwrap().is_none());
+}
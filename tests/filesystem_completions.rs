@@ -0,0 +1,81 @@
+use antraft::autocomplete::filesystem::{DirectoryListing, DirectoryReader, OsDirectoryReader, MAX_ENTRIES_PER_DIRECTORY};
+use antraft::autocomplete::{AutocompleteContext, AutocompleteItem, AutocompleteProvider, FileSystemProvider};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Reads the real filesystem, counting directory reads.
+#[derive(Default)]
+struct CountingReader {
+    reads: AtomicUsize,
+}
+
+impl DirectoryReader for CountingReader {
+    fn read_dir(&self, dir: &Path, limit: usize) -> std::io::Result<DirectoryListing> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        OsDirectoryReader.read_dir(dir, limit)
+    }
+
+    fn modified(&self, dir: &Path) -> std::io::Result<SystemTime> {
+        OsDirectoryReader.modified(dir)
+    }
+}
+
+fn context(dir: &Path) -> AutocompleteContext {
+    AutocompleteContext::new(dir.display().to_string(), "bash".to_string())
+}
+
+/// Asks for suggestions until the background read has filled the cache.
+fn suggestions_once_read(provider: &FileSystemProvider, input: &str, context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+    let started = Instant::now();
+    loop {
+        let suggestions = provider.get_suggestions(input, context);
+        if !suggestions.is_empty() {
+            return suggestions;
+        }
+        assert!(started.elapsed() < Duration::from_secs(10), "directory was never read");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn lists_a_large_directory_once_and_caps_its_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..10_000 {
+        std::fs::write(dir.path().join(format!("file{:05}.txt", i)), "").unwrap();
+    }
+    let reader = Arc::new(CountingReader::default());
+    let provider = FileSystemProvider::with_reader(reader.clone());
+    let context = context(dir.path());
+
+    // Cold: nothing until the background read finishes
+    assert!(provider.get_suggestions("file", &context).is_empty());
+    let first = suggestions_once_read(&provider, "file", &context);
+    assert_eq!(reader.reads.load(Ordering::SeqCst), 1);
+
+    let second = provider.get_suggestions("file0", &context);
+    assert_eq!(reader.reads.load(Ordering::SeqCst), 1);
+    assert_eq!(first.len(), MAX_ENTRIES_PER_DIRECTORY + 1);
+    assert!(!second.is_empty());
+
+    let more = first.last().unwrap();
+    assert_eq!(more.text, "file");
+    assert_eq!(more.description, format!("…{} more not listed", 10_000 - MAX_ENTRIES_PER_DIRECTORY));
+}
+
+#[test]
+fn completes_paths_in_subdirectories() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src/bin")).unwrap();
+    std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+    std::fs::write(dir.path().join("src/.hidden"), "").unwrap();
+    let provider = FileSystemProvider::new();
+
+    let mut names: Vec<String> = suggestions_once_read(&provider, "src/", &context(dir.path()))
+        .into_iter()
+        .map(|item| item.text)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["bin/", "main.rs"]);
+}
@@ -0,0 +1,165 @@
+use antraft::security::findings_db::{self, FindingStatus, FindingsStore, SCHEMA_VERSION};
+use antraft::security::{ScanTarget, ScanType, SecurityReport, Severity, Vulnerability};
+use std::path::PathBuf;
+
+fn finding(id: &str, file: &str, line: usize, snippet: &str) -> Vulnerability {
+    let mut vulnerability = Vulnerability::new(
+        format!("{} finding", id),
+        String::new(),
+        Severity::High,
+        "code".to_string(),
+        format!("/repo/{}", file),
+        "bandit".to_string(),
+    )
+    .with_location(line, None);
+    vulnerability.id = id.to_string();
+    vulnerability.code_snippet = Some(snippet.to_string());
+    vulnerability
+}
+
+fn report(scan_type: ScanType, vulnerabilities: Vec<Vulnerability>) -> SecurityReport {
+    let mut report = SecurityReport::new(&ScanTarget::Path(PathBuf::from("/repo")), scan_type);
+    for vulnerability in vulnerabilities {
+        report.add_vulnerability(vulnerability);
+    }
+    report.finalize(3, 10);
+    report
+}
+
+fn status(store: &FindingsStore, report: &SecurityReport, vulnerability: &Vulnerability) -> FindingStatus {
+    store.get(&findings_db::fingerprint(report, vulnerability)).unwrap().unwrap().status
+}
+
+#[test]
+fn the_schema_is_migrated_once_and_newer_ones_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested/findings.db");
+    let store = FindingsStore::open(&path).unwrap();
+    assert_eq!(store.schema_version().unwrap(), SCHEMA_VERSION);
+    drop(store);
+    // Reopening applies nothing again
+    assert_eq!(FindingsStore::open(&path).unwrap().schema_version().unwrap(), SCHEMA_VERSION);
+
+    // A database from before the scans table keeps its findings
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "DROP TABLE scans;
+         INSERT INTO findings (fingerprint, target, scanner, rule_id, title, severity, file_path, status, first_seen, last_seen)
+         VALUES ('abc', '/repo', 'bandit', 'B602', 'Old', 'High', 'app.py', 'acknowledged',
+                 '2026-01-02T03:04:05+00:00', '2026-01-02T03:04:05+00:00');
+         PRAGMA user_version = 1;",
+    )
+    .unwrap();
+    drop(conn);
+    let mut store = FindingsStore::open(&path).unwrap();
+    assert_eq!(store.schema_version().unwrap(), SCHEMA_VERSION);
+    assert_eq!(store.get("abc").unwrap().unwrap().status, FindingStatus::Acknowledged);
+    store.record_scan(&report(ScanType::Quick, vec![])).unwrap();
+    assert_eq!(store.last_risk_score("/repo").unwrap(), Some(0));
+    drop(store);
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.pragma_update(None, "user_version", 99).unwrap();
+    drop(conn);
+    let error = format!("{:#}", FindingsStore::open(&path).err().unwrap());
+    assert!(error.contains("schema version 99, newer than this version of antraft knows"), "{}", error);
+}
+
+#[test]
+fn fingerprints_survive_moved_lines_but_not_moved_files() {
+    let base = report(ScanType::Full, vec![]);
+    let fingerprint = |v: &Vulnerability| findings_db::fingerprint(&base, v);
+    let original = finding("B602", "app.py", 4, "subprocess.call(cmd, shell=True)");
+    assert_eq!(fingerprint(&original).len(), 32);
+    // Lines added above, and the code reindented
+    assert_eq!(fingerprint(&original), fingerprint(&finding("B602", "app.py", 9, "    subprocess.call(cmd,  shell=True)\n")));
+    assert_ne!(fingerprint(&original), fingerprint(&finding("B602", "lib.py", 4, "subprocess.call(cmd, shell=True)")));
+    assert_ne!(fingerprint(&original), fingerprint(&finding("B603", "app.py", 4, "subprocess.call(cmd, shell=True)")));
+
+    // Without code, the line tells findings apart; made-up ids don't count
+    let mut first = finding("", "app.py", 4, " ");
+    first.id = uuid::Uuid::new_v4().to_string();
+    let mut again = first.clone();
+    again.id = uuid::Uuid::new_v4().to_string();
+    assert_eq!(fingerprint(&first), fingerprint(&again));
+    again.line_number = Some(5);
+    assert_ne!(fingerprint(&first), fingerprint(&again));
+}
+
+#[test]
+fn findings_are_fixed_reopened_and_keep_their_status_across_scans() {
+    let mut store = FindingsStore::open_in_memory().unwrap();
+    let shell = finding("B602", "app.py", 4, "subprocess.call(cmd, shell=True)");
+    let password = finding("B105", "app.py", 9, "password = 'hunter2'");
+    let pickle = finding("B301", "cache.py", 2, "pickle.loads(data)");
+
+    let first = report(ScanType::Full, vec![shell.clone(), password.clone(), pickle.clone()]);
+    let changes = store.record_scan(&first).unwrap();
+    assert_eq!(changes.new.len(), 3);
+    assert!(changes.reopened.is_empty() && changes.fixed.is_empty());
+    store.set_status(&findings_db::fingerprint(&first, &password), FindingStatus::Acknowledged).unwrap();
+    store.set_status(&findings_db::fingerprint(&first, &pickle), FindingStatus::Suppressed).unwrap();
+    store.set_assignment(&findings_db::fingerprint(&first, &shell), " sam ", "").unwrap();
+
+    // Gone from a full scan: fixed
+    let second = report(ScanType::Full, vec![password.clone(), pickle.clone()]);
+    let changes = store.record_scan(&second).unwrap();
+    assert!(changes.new.is_empty());
+    assert_eq!(changes.fixed, [findings_db::fingerprint(&first, &shell)]);
+    let fixed = store.get(&changes.fixed[0]).unwrap().unwrap();
+    assert_eq!(fixed.status, FindingStatus::Fixed);
+    assert!(fixed.fixed_at.is_some());
+    assert_eq!((fixed.assignee.as_deref(), fixed.note), (Some("sam"), None));
+    assert_eq!(status(&store, &second, &password), FindingStatus::Acknowledged);
+    assert_eq!(status(&store, &second, &pickle), FindingStatus::Suppressed);
+
+    // Quick scans and scans with a failed scanner don't fix anything
+    assert!(store.record_scan(&report(ScanType::Quick, vec![])).unwrap().fixed.is_empty());
+    let mut failed = report(ScanType::Full, vec![]);
+    failed.scanner_errors.push("semgrep timed out".to_string());
+    assert!(store.record_scan(&failed).unwrap().fixed.is_empty());
+
+    // Back again: reopened
+    let third = report(ScanType::Full, vec![shell.clone(), password.clone(), pickle.clone()]);
+    let changes = store.record_scan(&third).unwrap();
+    assert_eq!(changes.reopened, [findings_db::fingerprint(&third, &shell)]);
+    let reopened = store.get(&changes.reopened[0]).unwrap().unwrap();
+    assert_eq!((reopened.status, reopened.fixed_at), (FindingStatus::Open, None));
+    assert_eq!(store.findings("/repo").unwrap().len(), 3);
+    assert!(store.findings("/elsewhere").unwrap().is_empty());
+    assert!(store.set_status("missing", FindingStatus::Open).is_err());
+}
+
+#[test]
+fn ignore_comments_suppress_until_they_are_removed() {
+    let mut store = FindingsStore::open_in_memory().unwrap();
+    let shell = finding("B602", "app.py", 4, "subprocess.call(cmd, shell=True)  # nosec B602");
+
+    let mut silenced = report(ScanType::Full, vec![]);
+    silenced.suppressed.push(shell.clone());
+    let changes = store.record_scan(&silenced).unwrap();
+    assert_eq!(changes.new.len(), 1);
+    let tracked = store.get(&changes.new[0]).unwrap().unwrap();
+    assert_eq!(tracked.status, FindingStatus::Suppressed);
+    assert!(tracked.suppressed_by_comment);
+    // Still there, so not fixed
+    assert!(store.record_scan(&silenced).unwrap().fixed.is_empty());
+
+    let unsilenced = report(ScanType::Full, vec![shell.clone()]);
+    assert!(store.record_scan(&unsilenced).unwrap().new.is_empty());
+    let tracked = store.get(&findings_db::fingerprint(&unsilenced, &shell)).unwrap().unwrap();
+    assert_eq!((tracked.status, tracked.suppressed_by_comment), (FindingStatus::Open, false));
+}
+
+#[test]
+fn the_last_scans_risk_score_is_remembered_per_target() {
+    let mut store = FindingsStore::open_in_memory().unwrap();
+    assert_eq!(store.last_risk_score("/repo").unwrap(), None);
+    let mut first = report(ScanType::Full, vec![finding("B602", "app.py", 4, "a"), finding("B105", "app.py", 9, "b")]);
+    first.timestamp -= chrono::Duration::hours(1);
+    store.record_scan(&first).unwrap();
+    let second = report(ScanType::Full, vec![finding("B602", "app.py", 4, "a")]);
+    store.record_scan(&second).unwrap();
+    assert_eq!(store.last_risk_score("/repo").unwrap(), Some(second.summary.risk_score()));
+    assert_ne!(first.summary.risk_score(), second.summary.risk_score());
+}
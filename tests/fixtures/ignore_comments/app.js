@@ -0,0 +1,3 @@
+const value = eval(input); // nosemgrep: eval-detected
+/* nosemgrep */ document.write(input);
+el.innerHTML = input; // nosemgrep: some-other-rule
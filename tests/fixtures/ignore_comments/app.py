@@ -0,0 +1,12 @@
+import subprocess  # nosec B404
+
+def run(cmd):
+    subprocess.call(cmd, shell=True)  # nosec B602
+    subprocess.call(cmd, shell=True)  # nosec B101
+    # nosec B602
+    subprocess.call(cmd, shell=True)
+    eval(cmd)  # antraft-ignore: B307, custom-eval only called with constants
+    password = "hunter2"#nosec
+	x = 1	# nosec: b105 fixture only
+url = "http://example.com/#nosecret"
+#nosec B108
@@ -0,0 +1,3 @@
+import pickle  # nosec B403
+x = 1
+data = pickle.loads(blob)  # antraft-ignore: B301 trusted cache
\ No newline at end of file
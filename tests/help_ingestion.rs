@@ -0,0 +1,131 @@
+use antraft::autocomplete::help::{self, HelpCache};
+use antraft::autocomplete::specs::{CommandSpec, OptionSpec};
+use std::path::Path;
+
+fn parse(binary: &str, fixture: &str) -> CommandSpec {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/help").join(fixture);
+    help::parse_help(binary, &std::fs::read_to_string(path).unwrap())
+}
+
+fn option<'a>(spec: &'a CommandSpec, name: &str) -> &'a OptionSpec {
+    spec.options
+        .iter()
+        .find(|option| option.names().iter().any(|n| n == name))
+        .unwrap_or_else(|| panic!("no {}", name))
+}
+
+fn subcommands(spec: &CommandSpec) -> Vec<&str> {
+    spec.subcommands.iter().map(|subcommand| subcommand.name.as_str()).collect()
+}
+
+#[test]
+fn parses_git_subcommands() {
+    let spec = parse("git", "git.txt");
+    let names = subcommands(&spec);
+    assert_eq!(names.len(), 22);
+    assert_eq!(&names[..3], ["clone", "init", "add"]);
+    assert!(names.contains(&"rebase") && names.contains(&"push"));
+    let clone = &spec.subcommands[0];
+    assert_eq!(clone.description, "Clone a repository into a new directory");
+    // The usage line isn't a list of flags
+    assert!(spec.options.is_empty());
+}
+
+#[test]
+fn parses_cargo_options_and_commands() {
+    let spec = parse("cargo", "cargo.txt");
+    let version = option(&spec, "--version");
+    assert_eq!(version.names(), ["-V", "--version"]);
+    assert_eq!(version.description, "Print version info and exit");
+    assert_eq!(option(&spec, "--explain").arg.as_deref(), Some("CODE"));
+    assert_eq!(option(&spec, "-C").arg.as_deref(), Some("DIRECTORY"));
+    assert_eq!(option(&spec, "--verbose").arg, None);
+    let color = option(&spec, "--color");
+    assert_eq!(color.values, ["auto", "always", "never"]);
+    assert_eq!(color.description, "Coloring");
+
+    // Aliases are commands too; `...` isn't one
+    let names = subcommands(&spec);
+    assert_eq!(&names[..4], ["build", "b", "check", "c"]);
+    assert!(names.contains(&"uninstall"));
+    assert!(!names.contains(&"..."));
+}
+
+#[test]
+fn parses_tar_options() {
+    let spec = parse("tar", "tar.txt");
+    let catenate = option(&spec, "--concatenate");
+    assert_eq!(catenate.names(), ["-A", "--catenate", "--concatenate"]);
+    assert_eq!(catenate.description, "append tar files to an archive");
+    assert_eq!(option(&spec, "--file").arg.as_deref(), Some("ARCHIVE"));
+    assert_eq!(option(&spec, "--occurrence").arg.as_deref(), Some("NUMBER"));
+    // Too long for its column, described on the next line
+    let sparse = option(&spec, "--sparse-version");
+    assert_eq!(sparse.arg.as_deref(), Some("MAJOR[.MINOR]"));
+    assert_eq!(sparse.description, "set version of the sparse format to use (implies");
+    assert_eq!(option(&spec, "--exclude-caches-under").description, "exclude everything under directories containing");
+    assert!(spec.subcommands.is_empty());
+    assert!(spec.options.len() > 100);
+}
+
+#[test]
+fn parses_curl_options() {
+    let spec = parse("curl", "curl.txt");
+    assert_eq!(spec.options.len(), 12);
+    let user = option(&spec, "--user");
+    assert_eq!(user.names(), ["-u", "--user"]);
+    assert_eq!(user.arg.as_deref(), Some("user:password"));
+    assert_eq!(user.description, "Server user and password");
+    assert_eq!(option(&spec, "-O").description, "Write output to a file named as the remote file");
+}
+
+#[test]
+fn parses_man_pages() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/help/ls.1.txt");
+    let page = help::strip_man_formatting(&std::fs::read_to_string(path).unwrap());
+    assert!(page.contains("       -a, --all\n"));
+    let spec = help::parse_help("ls", &page);
+    assert_eq!(option(&spec, "--all").description, "do not ignore entries starting with .");
+    assert_eq!(option(&spec, "--block-size").arg.as_deref(), Some("SIZE"));
+    assert_eq!(option(&spec, "--color").arg.as_deref(), Some("WHEN"));
+    assert_eq!(option(&spec, "-l").description, "use a long listing format");
+    assert_eq!(spec.options.len(), 6);
+}
+
+#[test]
+fn cache_is_dropped_when_the_binary_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("tool");
+    std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+    let cache = HelpCache::new(dir.path().join("cache"));
+    assert!(cache.load(&binary).is_none());
+
+    let spec = help::parse_help("tool", "Options:\n  -x, --extra  Extra output\n");
+    cache.store(&binary, &spec).unwrap();
+    let cached = cache.load(&binary).unwrap();
+    assert_eq!(option(&cached, "-x").description, "Extra output");
+    // Another binary of the same name doesn't get it
+    let other = dir.path().join("other");
+    std::fs::create_dir(&other).unwrap();
+    std::fs::write(other.join("tool"), "#!/bin/sh\n").unwrap();
+    assert!(cache.load(&other.join("tool")).is_none());
+
+    let file = std::fs::File::options().write(true).open(&binary).unwrap();
+    file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+    assert!(cache.load(&binary).is_none());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn reads_help_from_stdout_and_stderr() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("tool");
+    std::fs::write(&binary, "#!/bin/sh\necho '  -x, --extra  Extra output'\necho '  -q  Quiet' >&2\n").unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let help = help::run_for_help(binary.as_os_str(), &["--help"]).await.unwrap();
+    let spec = help::parse_help("tool", &help);
+    assert_eq!(spec.options.len(), 2);
+    assert!(help::run_for_help(dir.path().join("missing").as_os_str(), &["--help"]).await.is_none());
+}
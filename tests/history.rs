@@ -0,0 +1,192 @@
+use antraft::terminal::history::{parse_shell_history, CommandHistory, HistoryEntry};
+use std::path::Path;
+
+fn history(commands: &[&str]) -> CommandHistory {
+    let mut history = CommandHistory::new(100);
+    for command in commands {
+        history.add_entry(HistoryEntry::new(command.to_string(), "/tmp".to_string()));
+    }
+    history
+}
+
+#[test]
+fn walks_back_and_forward_through_entries() {
+    let mut history = history(&["ls", "cd src", "cargo build"]);
+    assert_eq!(history.get_previous().unwrap().command, "cargo build");
+    assert_eq!(history.get_previous().unwrap().command, "cd src");
+    assert_eq!(history.get_previous().unwrap().command, "ls");
+    // The oldest entry stays put
+    assert_eq!(history.get_previous().unwrap().command, "ls");
+    assert_eq!(history.get_next().unwrap().command, "cd src");
+    assert_eq!(history.get_next().unwrap().command, "cargo build");
+    assert!(history.get_next().is_none());
+}
+
+#[test]
+fn folds_repeated_commands_into_one_entry() {
+    let history = history(&["ls", "ls", "ls", "pwd"]);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get_all_entries()[0].runs(), 3);
+}
+
+#[test]
+fn drops_the_oldest_entries_past_the_limit() {
+    let mut history = CommandHistory::new(2);
+    for command in ["one", "two", "three"] {
+        history.add_entry(HistoryEntry::new(command.to_string(), "/tmp".to_string()));
+    }
+    let commands: Vec<_> = history.get_all_entries().iter().map(|entry| entry.command.as_str()).collect();
+    assert_eq!(commands, ["two", "three"]);
+}
+
+#[test]
+fn records_results_and_filters_by_them() {
+    let mut history = history(&["cargo test", "cargo build"]);
+    assert!(history.record_result("cargo test", 101, 1200));
+    assert!(history.record_result("cargo build", 0, 800));
+    assert!(!history.record_result("make", 0, 0));
+
+    assert_eq!(history.get_failed_commands()[0].command, "cargo test");
+    assert_eq!(history.get_successful_commands()[0].command, "cargo build");
+    assert_eq!(history.search("cargo").len(), 2);
+}
+
+#[test]
+fn imports_each_command_once() {
+    let mut history = history(&["ls"]);
+    let entries = || {
+        ["ls", "git status"]
+            .iter()
+            .map(|command| HistoryEntry::new(command.to_string(), String::new()))
+            .collect()
+    };
+    assert_eq!(history.import_entries(entries()), 1);
+    assert_eq!(history.import_entries(entries()), 0);
+    assert!(history.contains_command("git status"));
+}
+
+#[test]
+fn saves_and_loads_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    let mut saved = history(&["ls", "cargo run"]);
+    saved.record_result("cargo run", 0, 42);
+    saved.save(&path).unwrap();
+
+    let loaded = CommandHistory::load(&path, 100).unwrap();
+    let last = loaded.get_all_entries().back().unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(last.command, "cargo run");
+    assert_eq!(last.execution_time, Some(42));
+}
+
+#[test]
+fn repeated_commands_keep_the_latest_run_across_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    let first_seen = chrono::Utc::now() - chrono::Duration::days(90);
+    let mut old = HistoryEntry::new("cargo test".to_string(), "/work".to_string());
+    old.timestamp = first_seen;
+    old.set_result(101, 5000);
+    let mut latest = HistoryEntry::new("cargo test".to_string(), "/work".to_string());
+    latest.set_result(0, 1200);
+    let latest_run = latest.timestamp;
+
+    let mut saved = CommandHistory::new(100);
+    saved.add_entry(old);
+    saved.add_entry(latest);
+    saved.save(&path).unwrap();
+
+    let loaded = CommandHistory::load(&path, 100).unwrap();
+    let entry = loaded.get_all_entries().back().unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(entry.runs(), 2);
+    assert_eq!(entry.timestamp, latest_run);
+    assert_eq!(entry.exit_code, Some(0));
+    assert_eq!(entry.execution_time, Some(1200));
+}
+
+#[test]
+fn parses_extended_zsh_history() {
+    let content = ": 1700000000:0;git status\n: 1700000005:2;echo one \\\ntwo\n";
+    let commands = parse_shell_history("zsh", content);
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[0].command, "git status");
+    assert!(commands[0].timestamp.is_some());
+    assert_eq!(commands[1].command, "echo one \ntwo");
+}
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/history").join(name)).unwrap()
+}
+
+/// The commands in `history` after importing `shell`'s fixture into it.
+fn import_fixture(shell: &str, history: &mut CommandHistory) -> Vec<String> {
+    let entries = parse_shell_history(shell, &fixture(&format!("{}_history", shell)))
+        .into_iter()
+        .map(|parsed| HistoryEntry::new(parsed.command, String::new()))
+        .collect();
+    history.import_entries(entries);
+    history.get_all_entries().iter().map(|entry| entry.command.clone()).collect()
+}
+
+#[test]
+fn imports_bash_history_without_timestamps_or_duplicates() {
+    let mut history = history(&["git status"]);
+    assert_eq!(
+        import_fixture("bash", &mut history),
+        ["git status", "ls -la", "cargo build --release", "echo 'done'"]
+    );
+}
+
+#[test]
+fn imports_zsh_history_in_both_formats() {
+    let commands = parse_shell_history("zsh", &fixture("zsh_history"));
+    assert_eq!(commands[0].timestamp.unwrap().timestamp(), 1700000000);
+    // Plain lines have no time
+    assert_eq!(commands[4].command, "docker ps");
+    assert!(commands[4].timestamp.is_none());
+
+    let mut history = CommandHistory::new(100);
+    assert_eq!(
+        import_fixture("zsh", &mut history),
+        ["git status", "ls -la", "cargo test \n  --workspace", "docker ps", "echo \"a;b\""]
+    );
+}
+
+#[test]
+fn imports_fish_history_with_multi_line_commands() {
+    let mut history = CommandHistory::new(100);
+    assert_eq!(
+        import_fixture("fish", &mut history),
+        [
+            "git status",
+            "cd projects",
+            "for f in *.rs\n    wc -l $f\nend",
+            // An escaped backslash before an `n` isn't a newline
+            "printf 'a\\nb'",
+        ]
+    );
+}
+
+#[test]
+fn imports_powershell_history_with_continued_lines() {
+    let mut history = history(&["Set-Location ~\\projects"]);
+    assert_eq!(
+        import_fixture("powershell", &mut history),
+        [
+            "Set-Location ~\\projects",
+            "Get-ChildItem -Force",
+            "git status",
+            // Every line but the last ends with a backtick
+            "Get-Process |\n  Where-Object CPU -gt 100 |\n  Sort-Object CPU -Descending",
+        ]
+    );
+}
+
+#[test]
+fn importing_a_fixture_again_adds_nothing() {
+    let mut history = CommandHistory::new(100);
+    let first = import_fixture("fish", &mut history);
+    assert_eq!(import_fixture("fish", &mut history), first);
+}
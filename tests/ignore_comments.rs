@@ -0,0 +1,196 @@
+mod common;
+
+use antraft::security::ignore_comments::{self, IgnoreComment};
+use antraft::security::{
+    ScanTarget, ScanType, ScannerKind, SecurityConfig, SecurityScanRequest, SecurityScanner, Severity, Vulnerability,
+};
+use common::StubScanner;
+use std::path::{Path, PathBuf};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ignore_comments")
+}
+
+fn finding(scanner: &str, id: &str, file: &str, line: usize) -> Vulnerability {
+    let mut vulnerability = Vulnerability::new(
+        format!("{} finding", id),
+        String::new(),
+        Severity::High,
+        "code".to_string(),
+        file.to_string(),
+        scanner.to_string(),
+    )
+    .with_location(line, Some(5));
+    vulnerability.id = id.to_string();
+    vulnerability
+}
+
+/// Findings on the lines of `app.py`, `app.js` and the CRLF `windows.py`.
+fn findings() -> Vec<Vulnerability> {
+    let absolute = fixtures().join("windows.py").display().to_string();
+    vec![
+        finding("bandit", "B404", "app.py", 1),
+        finding("bandit", "B602", "app.py", 4),
+        // The comment names another rule
+        finding("bandit", "B602", "app.py", 5),
+        // The comment is on the line above
+        finding("bandit", "B602", "app.py", 7),
+        finding("bandit", "B307", "app.py", 8),
+        finding("custom", "custom-eval", "app.py", 8),
+        finding("bandit", "B105", "app.py", 9),
+        // `nosec` is bandit's
+        finding("semgrep", "python.lang.security.audit.hardcoded-password", "app.py", 9),
+        finding("bandit", "B105", "app.py", 10),
+        finding("bandit", "B310", "app.py", 11),
+        finding("bandit", "B108", "app.py", 12),
+        finding("semgrep", "javascript.browser.security.eval-detected.eval-detected", "app.js", 1),
+        finding("semgrep", "javascript.browser.security.insecure-document-method.insecure-document-method", "app.js", 2),
+        finding("semgrep", "javascript.browser.security.insecure-innerhtml.insecure-innerhtml", "app.js", 3),
+        finding("bandit", "B403", &absolute, 1),
+        finding("bandit", "B311", &absolute, 2),
+        finding("bandit", "B301", &absolute, 3),
+        // Past the end of the file
+        finding("bandit", "B301", &absolute, 99),
+        finding("bandit", "B602", "missing.py", 1),
+    ]
+}
+
+fn located(vulnerabilities: &[Vulnerability]) -> Vec<String> {
+    vulnerabilities
+        .iter()
+        .map(|v| {
+            let file = Path::new(&v.file_path).file_name().unwrap().to_string_lossy().into_owned();
+            format!("{}:{} {}", file, v.line_number.unwrap(), v.id.rsplit('.').next().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn parses_each_kind_of_comment() {
+    let comment = IgnoreComment::parse("subprocess.call(cmd, shell=True)  # nosec B602, B607 -- trusted").unwrap();
+    assert_eq!(comment.scanner, Some("bandit"));
+    assert_eq!(comment.rules, ["B602", "B607"]);
+    assert_eq!(comment.reason.as_deref(), Some("trusted"));
+    assert_eq!(comment.text, "nosec B602, B607 -- trusted");
+
+    // Words that aren't test ids make a blanket nosec
+    let comment = IgnoreComment::parse("x = 1  # nosec reviewed by security").unwrap();
+    assert!(comment.rules.is_empty());
+    assert_eq!(comment.reason.as_deref(), Some("reviewed by security"));
+
+    let comment = IgnoreComment::parse("run(); /* nosemgrep: a.b.rule-one,rule-two */").unwrap();
+    assert_eq!(comment.scanner, Some("semgrep"));
+    assert_eq!(comment.rules, ["a.b.rule-one", "rule-two"]);
+    assert_eq!(comment.text, "nosemgrep: a.b.rule-one,rule-two");
+    let comment = IgnoreComment::parse("run() // nosemgrep this is fine").unwrap();
+    assert!(comment.rules.is_empty());
+
+    let comment = IgnoreComment::parse("<!-- antraft-ignore: xss-raw-html, xss-inline-script only on the admin page -->").unwrap();
+    assert_eq!(comment.scanner, None);
+    assert_eq!(comment.rules, ["xss-raw-html", "xss-inline-script"]);
+    assert_eq!(comment.reason.as_deref(), Some("only on the admin page"));
+
+    for line in ["key = \"nosec\"", "url = \"http://example.com/#nosecret\"", "# antraft-ignored", "nosec B602"] {
+        assert_eq!(IgnoreComment::parse(line), None, "{}", line);
+    }
+}
+
+#[test]
+fn silences_findings_with_a_matching_comment_on_their_line() {
+    let applied = ignore_comments::apply(findings(), &fixtures());
+    assert_eq!(
+        located(&applied.suppressed),
+        [
+            "app.py:1 B404",
+            "app.py:4 B602",
+            "app.py:8 B307",
+            "app.py:8 custom-eval",
+            "app.py:9 B105",
+            "app.py:10 B105",
+            "app.py:12 B108",
+            "app.js:1 eval-detected",
+            "app.js:2 insecure-document-method",
+            "windows.py:1 B403",
+            "windows.py:3 B301",
+        ]
+    );
+    assert_eq!(
+        located(&applied.kept),
+        [
+            "app.py:5 B602",
+            "app.py:7 B602",
+            "app.py:9 hardcoded-password",
+            "app.py:11 B310",
+            "app.js:3 insecure-innerhtml",
+            "windows.py:2 B311",
+            "windows.py:99 B301",
+            "missing.py:1 B602",
+        ]
+    );
+
+    let reasons: Vec<_> = applied
+        .suppressed
+        .iter()
+        .map(|v| {
+            let suppression = v.suppression.as_ref().unwrap();
+            (suppression.comment.as_str(), suppression.reason.as_deref())
+        })
+        .collect();
+    assert_eq!(reasons[0], ("nosec B404", None));
+    assert_eq!(reasons[2], ("antraft-ignore: B307, custom-eval only called with constants", Some("only called with constants")));
+    assert_eq!(reasons[5], ("nosec: b105 fixture only", Some("fixture only")));
+    // Without the CRLF's carriage return, on a last line without a newline
+    assert_eq!(reasons[10], ("antraft-ignore: B301 trusted cache", Some("trusted cache")));
+    assert!(applied.kept.iter().all(|v| v.suppression.is_none()));
+}
+
+#[test]
+fn warns_about_comments_that_dont_name_the_rule() {
+    let applied = ignore_comments::apply(findings(), &fixtures());
+    assert_eq!(
+        applied.warnings,
+        [
+            "app.py:5: `nosec B101` doesn't name B602, so it's still reported",
+            "app.js:3: `nosemgrep: some-other-rule` doesn't name \
+             javascript.browser.security.insecure-innerhtml.insecure-innerhtml, so it's still reported",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn scans_honor_comments_unless_turned_off() {
+    let root = fixtures();
+    let absolute = |mut v: Vulnerability| {
+        v.file_path = root.join(&v.file_path).display().to_string();
+        v
+    };
+    let found = vec![
+        absolute(finding("bandit", "B602", "app.py", 4)),
+        absolute(finding("bandit", "B602", "app.py", 5)),
+        absolute(finding("bandit", "B602", "app.py", 7)),
+    ];
+    let scanner = SecurityScanner::with_scanners(
+        SecurityConfig::default(),
+        vec![Box::new(StubScanner::new("bandit", ScannerKind::Code, found))],
+    );
+    let request = |honor_ignore_comments| SecurityScanRequest {
+        target: ScanTarget::Path(root.clone()),
+        scan_type: ScanType::Full,
+        include_patterns: Vec::new(),
+        exclude_patterns: Vec::new(),
+        honor_ignore_comments,
+    };
+
+    let report = scanner.scan(request(true)).await.unwrap();
+    assert_eq!(report.summary.total_vulnerabilities, 2);
+    assert_eq!(located(&report.suppressed), ["app.py:4 B602"]);
+    assert_eq!(report.ignore_comment_warnings.len(), 1);
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("## Silenced by Ignore Comments\n\n- **B602 finding** (High, B602) at "), "{}", markdown);
+    assert!(markdown.contains("app.py:4: `nosec B602`\n"), "{}", markdown);
+    assert!(markdown.contains("## Ignore Comment Warnings\n\n- "), "{}", markdown);
+
+    let report = scanner.scan(request(false)).await.unwrap();
+    assert_eq!(report.summary.total_vulnerabilities, 3);
+    assert!(report.suppressed.is_empty() && report.ignore_comment_warnings.is_empty());
+}
@@ -0,0 +1,49 @@
+use antraft::terminal::input_history::{on_first_line, on_last_line, InputHistory};
+use std::collections::VecDeque;
+
+fn commands() -> VecDeque<String> {
+    // Newest first
+    VecDeque::from(["for f in *.rs; do\n  wc -l \"$f\"\ndone".to_string(), "ls".to_string()])
+}
+
+#[test]
+fn up_recalls_older_commands_and_down_brings_back_the_draft() {
+    let commands = commands();
+    let mut history = InputHistory::default();
+    assert_eq!(history.newer(&commands), None);
+
+    assert_eq!(history.older(&commands, "git st").as_deref(), Some("for f in *.rs; do\n  wc -l \"$f\"\ndone"));
+    assert_eq!(history.older(&commands, "ignored").as_deref(), Some("ls"));
+    assert_eq!(history.older(&commands, "ignored"), None);
+    assert!(history.is_recalling());
+
+    assert_eq!(history.newer(&commands).as_deref(), Some("for f in *.rs; do\n  wc -l \"$f\"\ndone"));
+    assert_eq!(history.newer(&commands).as_deref(), Some("git st"));
+    assert!(!history.is_recalling());
+    assert_eq!(history.newer(&commands), None);
+}
+
+#[test]
+fn a_multi_line_draft_survives_recalling() {
+    let commands = commands();
+    let mut history = InputHistory::default();
+    let draft = "while true; do\n  date\n";
+    history.older(&commands, draft);
+    history.reset();
+    assert_eq!(history.newer(&commands), None);
+
+    history.older(&commands, draft);
+    history.older(&commands, draft);
+    history.newer(&commands);
+    assert_eq!(history.newer(&commands).as_deref(), Some(draft));
+}
+
+#[test]
+fn the_arrows_recall_only_from_the_first_and_last_lines() {
+    let text = "one\ntwo\nthree";
+    assert!(on_first_line(text, 0) && on_first_line(text, 3));
+    assert!(!on_first_line(text, 4));
+    assert!(on_last_line(text, 8) && on_last_line(text, 13));
+    assert!(!on_last_line(text, 7));
+    assert!(on_first_line("ls", 2) && on_last_line("ls", 0));
+}
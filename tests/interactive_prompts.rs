@@ -0,0 +1,72 @@
+use antraft::terminal::prompts::{detect_prompt, DetectedPrompt};
+
+fn question(text: &str) -> Option<DetectedPrompt> {
+    Some(DetectedPrompt {
+        text: text.to_string(),
+        secret: false,
+    })
+}
+
+fn secret(text: &str) -> Option<DetectedPrompt> {
+    Some(DetectedPrompt {
+        text: text.to_string(),
+        secret: true,
+    })
+}
+
+#[test]
+fn yes_no_questions_are_prompts() {
+    for prompt in [
+        "Do you want to continue? [Y/n]",
+        "Proceed with installation [y/N]",
+        "Remove 3 packages? [yes/no]",
+        "Overwrite existing file? [y/N]:",
+        "Are you sure you want to continue connecting (yes/no/[fingerprint])?",
+        "Are you sure? ",
+        "Replace config.toml?",
+        "Username for 'https://github.com': ",
+        "Enter email:",
+    ] {
+        assert_eq!(detect_prompt(prompt), question(prompt.trim()), "{:?}", prompt);
+    }
+}
+
+#[test]
+fn password_prompts_are_secret() {
+    for prompt in [
+        "Password:",
+        "Password: ",
+        "[sudo] password for ana: ",
+        "Enter passphrase for key '/home/ana/.ssh/id_ed25519': ",
+        "Enter PIN for 'YubiKey':",
+        "ana@example.com's password:",
+    ] {
+        assert_eq!(detect_prompt(prompt), secret(prompt.trim()), "{:?}", prompt);
+    }
+}
+
+#[test]
+fn ordinary_output_is_not_a_prompt() {
+    for output in [
+        "",
+        "   ",
+        "Compiling antraft v0.1.0",
+        "Password changed successfully.",
+        "password: hunter2 was rejected by the server",
+        "Downloading... 45%",
+        "Continue reading the docs at https://example.com",
+        "error: could not compile `antraft`",
+        "Are you sure this works",
+        "The [y/N] flag is deprecated, use --yes",
+        "user: ana",
+    ] {
+        assert_eq!(detect_prompt(output), None, "{:?}", output);
+    }
+}
+
+#[test]
+fn long_partial_lines_are_not_prompts() {
+    let line = format!("{} Continue? [y/N]", "x".repeat(300));
+    assert_eq!(detect_prompt(&line), None);
+    assert_eq!(detect_prompt("\r\n  Continue? [y/N]  "), question("Continue? [y/N]"));
+}
@@ -0,0 +1,40 @@
+use antraft::terminal::pty::{Utf8Decoder, VteProcessor};
+
+fn decode(chunks: &[&[u8]]) -> String {
+    let mut decoder = Utf8Decoder::default();
+    let mut text: String = chunks.iter().map(|chunk| decoder.decode(chunk)).collect();
+    text.push_str(&decoder.finish());
+    text
+}
+
+#[test]
+fn invalid_bytes_become_replacement_characters() {
+    // Latin-1 é, keeping the space after it
+    assert_eq!(decode(&[b"caf\xe9 au lait\n"]), "caf\u{FFFD} au lait\n");
+    assert_eq!(decode(&[b"\xff\xfeab\n"]), "\u{FFFD}\u{FFFD}ab\n");
+    // A sequence cut short by ASCII loses only itself
+    assert_eq!(decode(&[b"\xe2\x82z\n"]), "\u{FFFD}z\n");
+    // Bare continuation bytes, including ones that would be C1 controls
+    assert_eq!(decode(&[b"\x80\x9b\x90ok\n"]), "\u{FFFD}\u{FFFD}\u{FFFD}ok\n");
+    // Same as the standard library's lossy decoding
+    let bytes = b"\xf0\x9f\x98 \xed\xa0\x80 \xc0\xaf \xf4\x90\x80\x80 done";
+    assert_eq!(decode(&[bytes]), String::from_utf8_lossy(bytes));
+}
+
+#[test]
+fn sequences_split_across_reads_are_kept() {
+    assert_eq!(decode(&[b"x\xc3", b"\xa9y"]), "x\u{e9}y");
+    assert_eq!(decode(&[b"\xf0", b"\x9f", b"\x98", b"\x80!"]), "\u{1F600}!");
+    // Cut off by the end of the output
+    assert_eq!(decode(&[b"ok\xe2\x82"]), "ok\u{FFFD}");
+}
+
+#[test]
+fn escape_sequences_still_apply_around_invalid_bytes() {
+    let mut processor = VteProcessor::new();
+    let (text, _) = processor.process_to_text(b"\x1b[31mr\xe9d\x1b[0m \x1b]0;t\xefitle\x07done\xe2");
+    assert_eq!(text, "r\u{FFFD}d done");
+    assert_eq!(processor.take_title().as_deref(), Some("t\u{FFFD}itle"));
+    let (text, _) = processor.finish_to_text();
+    assert_eq!(text, "\u{FFFD}");
+}
@@ -0,0 +1,82 @@
+use antraft::terminal::json_path::{JsonPath, PathSegment};
+use serde_json::{json, Value};
+
+fn select(value: &Value, path: &str) -> Vec<Value> {
+    let path = JsonPath::parse(path).unwrap();
+    path.evaluate(value).unwrap().into_iter().cloned().collect()
+}
+
+fn error(value: &Value, path: &str) -> String {
+    JsonPath::parse(path)
+        .and_then(|path| path.evaluate(value).map(|_| ()))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn parses_keys_indexes_and_iteration() {
+    let path = JsonPath::parse(r#".items[0].metadata["app.kubernetes.io/name"].x-y[-1][]."a \"b\"""#).unwrap();
+    assert_eq!(
+        path.segments,
+        [
+            PathSegment::Key("items".to_string()),
+            PathSegment::Index(0),
+            PathSegment::Key("metadata".to_string()),
+            PathSegment::Key("app.kubernetes.io/name".to_string()),
+            PathSegment::Key("x-y".to_string()),
+            PathSegment::Index(-1),
+            PathSegment::Iterate,
+            PathSegment::Key("a \"b\"".to_string()),
+        ]
+    );
+    assert!(JsonPath::parse(".").unwrap().segments.is_empty());
+    assert_eq!(JsonPath::parse(" .[2] ").unwrap().segments, [PathSegment::Index(2)]);
+}
+
+#[test]
+fn malformed_paths_are_rejected() {
+    for (path, message) in [
+        ("items", "A path starts with '.'"),
+        ("", "A path starts with '.'"),
+        (".items.", "The path ends with '.'"),
+        (".items[0", "Missing ']' for the '[' at 7"),
+        (".items[name]", "[name] isn't an index; quote keys: [\"name\"]"),
+        (".\"open", "Missing closing '\"' for the key at 2"),
+        (".a..b", "Unexpected '.' after '.' at 4"),
+        (".a b", "Unexpected ' ' at 3"),
+    ] {
+        assert_eq!(JsonPath::parse(path).unwrap_err().to_string(), message, "{}", path);
+    }
+}
+
+#[test]
+fn picks_values_like_jq() {
+    let value = json!({
+        "items": [
+            {"name": "web", "ports": [80, 443]},
+            {"name": "worker", "ports": []}
+        ],
+        "kind": "List"
+    });
+    assert_eq!(select(&value, "."), std::slice::from_ref(&value));
+    assert_eq!(select(&value, ".kind"), [json!("List")]);
+    assert_eq!(select(&value, ".items[0].name"), [json!("web")]);
+    assert_eq!(select(&value, ".items[-1].name"), [json!("worker")]);
+    assert_eq!(select(&value, ".items[].name"), [json!("web"), json!("worker")]);
+    assert_eq!(select(&value, ".items[].ports[]"), [json!(80), json!(443)]);
+    assert_eq!(select(&value, ".[\"kind\"]"), [json!("List")]);
+}
+
+#[test]
+fn missing_values_are_null_and_wrong_types_are_errors() {
+    let value = json!({"items": [{"name": "web"}], "count": 1});
+    assert_eq!(select(&value, ".missing"), [Value::Null]);
+    assert_eq!(select(&value, ".missing.deeper[3]"), [Value::Null]);
+    assert_eq!(select(&value, ".items[5]"), [Value::Null]);
+    assert_eq!(select(&value, ".items[-5]"), [Value::Null]);
+
+    assert_eq!(error(&value, ".items.name"), "Cannot index array with \"name\"");
+    assert_eq!(error(&value, ".[0]"), "Cannot index object with a number");
+    assert_eq!(error(&value, ".count[]"), "Cannot iterate over number");
+    assert_eq!(error(&value, ".missing[]"), "Cannot iterate over null");
+}
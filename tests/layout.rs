@@ -0,0 +1,90 @@
+#![cfg(feature = "gui")]
+
+use antraft::ui::layout::{ActiveView, UiState, WindowGeometry, DEFAULT_AI_PANEL_WIDTH, MIN_WINDOW_SIZE};
+
+fn window(size: [f32; 2], position: Option<[f32; 2]>, monitor_size: Option<[f32; 2]>) -> WindowGeometry {
+    WindowGeometry {
+        size,
+        position,
+        maximized: false,
+        monitor_size,
+    }
+}
+
+#[test]
+fn the_layout_survives_a_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ui_state.json");
+    let state = UiState {
+        window: Some(window([1400.0, 900.0], Some([100.0, 50.0]), Some([2560.0, 1440.0]))),
+        file_explorer_open: true,
+        file_explorer_width: 300.0,
+        ai_panel_width: 420.0,
+        zoom: 1.25,
+        view: ActiveView::AiAgent,
+    };
+    state.save(&path).unwrap();
+    assert_eq!(UiState::load(&path).unwrap(), state);
+}
+
+#[test]
+fn missing_fields_get_their_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ui_state.json");
+    std::fs::write(&path, r#"{ "file_explorer_open": true, "window": { "size": [1000, 700] } }"#).unwrap();
+
+    let state = UiState::load(&path).unwrap();
+    assert!(state.file_explorer_open);
+    assert_eq!(state.ai_panel_width, DEFAULT_AI_PANEL_WIDTH);
+    assert_eq!(state.zoom, 1.0);
+    assert_eq!(state.view, ActiveView::Welcome);
+    assert_eq!(state.window, Some(window([1000.0, 700.0], None, None)));
+    assert_eq!(UiState::load(&dir.path().join("missing.json")).unwrap(), UiState::default());
+}
+
+#[test]
+fn impossible_values_are_clamped() {
+    let state = UiState {
+        window: Some(window([100.0, 5000.0], Some([f32::NAN, 0.0]), Some([1920.0, 1080.0]))),
+        file_explorer_width: -20.0,
+        ai_panel_width: f32::INFINITY,
+        zoom: 0.0,
+        ..UiState::default()
+    }
+    .sanitized();
+
+    assert_eq!(state.window, Some(window([MIN_WINDOW_SIZE[0], 1080.0], None, Some([1920.0, 1080.0]))));
+    assert!(state.file_explorer_width > 0.0);
+    assert_eq!(state.ai_panel_width, DEFAULT_AI_PANEL_WIDTH);
+    assert_eq!(state.zoom, 0.5);
+}
+
+#[test]
+fn a_window_on_the_same_monitor_stays_put() {
+    let saved = window([1400.0, 900.0], Some([200.0, 100.0]), Some([1920.0, 1080.0]));
+    assert_eq!(saved.placement_fix([1920.0, 1080.0]), None);
+    // Without a saved position the OS places it anyway
+    assert_eq!(window([1400.0, 900.0], None, None).placement_fix([1920.0, 1080.0]), None);
+}
+
+#[test]
+fn a_window_saved_on_a_4k_monitor_is_brought_onto_a_laptop() {
+    let saved = window([3200.0, 2000.0], Some([2800.0, 300.0]), Some([3840.0, 2160.0]));
+    let fix = saved.placement_fix([1440.0, 900.0]).unwrap();
+    assert!(fix.size[0] <= 1440.0 && fix.size[1] <= 900.0);
+    let position = fix.position.unwrap();
+    assert!(position[0] + fix.size[0] <= 1440.0 && position[1] + fix.size[1] <= 900.0);
+    assert_eq!(fix.monitor_size, Some([1440.0, 900.0]));
+}
+
+#[test]
+fn a_position_from_another_monitor_setup_is_moved() {
+    // The same size fits, but the second monitor it was on may be gone
+    let saved = window([1000.0, 700.0], Some([-1800.0, 200.0]), Some([2560.0, 1440.0]));
+    let fix = saved.placement_fix([1920.0, 1080.0]).unwrap();
+    assert_eq!(fix.size, [1000.0, 700.0]);
+    assert!(fix.position.unwrap().iter().all(|coordinate| *coordinate >= 0.0));
+
+    let maximized = WindowGeometry { maximized: true, ..saved };
+    assert_eq!(maximized.placement_fix([1920.0, 1080.0]), None);
+}
@@ -0,0 +1,118 @@
+use antraft::autocomplete::{
+    ArgumentPatternProvider, AutocompleteContext, AutocompleteEngine, AutocompleteItem, AutocompleteProvider,
+    LearnedCommands,
+};
+use chrono::{Duration, Utc};
+use std::sync::{Arc, RwLock};
+
+fn context() -> AutocompleteContext {
+    AutocompleteContext::new("/".to_string(), "bash".to_string())
+}
+
+fn suggestions(learned: LearnedCommands, input: &str) -> Vec<String> {
+    let provider = ArgumentPatternProvider::new(Arc::new(RwLock::new(learned)));
+    let mut items = provider.get_suggestions(input, &context());
+    items.sort_by_key(|item| std::cmp::Reverse(item.priority));
+    items.into_iter().map(|item| item.text).collect()
+}
+
+/// Offers the same fixed commands for any input.
+struct FixedProvider(Vec<&'static str>);
+
+impl AutocompleteProvider for FixedProvider {
+    fn get_suggestions(&self, _input: &str, _context: &AutocompleteContext) -> Vec<AutocompleteItem> {
+        self.0
+            .iter()
+            .map(|command| AutocompleteItem::new(command.to_string(), String::new(), "test".to_string()))
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "fixed"
+    }
+}
+
+#[test]
+fn failures_demote_suggestions() {
+    let mut engine = AutocompleteEngine::new();
+    engine.add_provider(Box::new(FixedProvider(vec!["deploy alpha", "deploy alphb"])));
+    let now = Utc::now();
+    engine.add_to_history("deploy alpha".to_string(), 4, now);
+    engine.add_to_history("deploy alphb".to_string(), 1, now);
+    let top = |engine: &AutocompleteEngine| engine.get_suggestions("deploy alph", &context())[0].text.clone();
+    assert_eq!(top(&engine), "deploy alpha");
+
+    for _ in 0..4 {
+        engine.record_result("deploy alpha", 1);
+    }
+    assert_eq!(top(&engine), "deploy alphb");
+
+    // Learned command lines sink too, and failed ones aren't learned
+    let mut learned = LearnedCommands::empty(std::path::Path::new("unused.json"));
+    learned.record("docker run -it --rm ubuntu bash", 0, now);
+    learned.record("docker run -it --rm ubuntu:22.04 bash", 0, now);
+    learned.record("docker run -it --rm ubuntu:22.04 bash", 0, now);
+    learned.record("docker run --rm ubunut", 125, now);
+    assert_eq!(
+        suggestions(learned.clone(), "docker run"),
+        ["docker run -it --rm ubuntu:22.04 bash", "docker run -it --rm ubuntu bash"]
+    );
+    learned.record("docker run -it --rm ubuntu:22.04 bash", 125, now);
+    learned.record("docker run -it --rm ubuntu:22.04 bash", 125, now);
+    let invocations = learned.invocations("docker");
+    assert_eq!(invocations[0].0, "docker run -it --rm ubuntu bash");
+    assert_eq!(invocations[1].1.failures, 2);
+}
+
+#[test]
+fn arguments_are_suggested_per_binary() {
+    let now = Utc::now();
+    let mut learned = LearnedCommands::empty(std::path::Path::new("unused.json"));
+    learned.record("git checkout main", 0, now);
+    learned.record("git checkout feature/login", 0, now);
+    learned.record("git checkout feature/login", 0, now);
+    learned.record("svn checkout https://svn.example.com/repo", 0, now);
+    learned.record("git checkout typo-branch", 1, now);
+
+    let next: Vec<&str> = learned.next_arguments("git", "checkout").into_iter().map(|(arg, _)| arg).collect();
+    assert_eq!(next, ["feature/login", "main"]);
+    assert_eq!(learned.next_arguments("svn", "checkout")[0].0, "https://svn.example.com/repo");
+    assert!(learned.next_arguments("hg", "checkout").is_empty());
+    assert_eq!(learned.next_arguments("git", "")[0].0, "checkout");
+
+    let after_checkout = suggestions(learned.clone(), "git checkout ");
+    assert!(after_checkout.contains(&"git checkout feature/login".to_string()));
+    assert!(after_checkout.contains(&"git checkout main".to_string()));
+    assert!(after_checkout.iter().all(|text| !text.contains("svn") && !text.contains("typo")));
+    // The partial word filters
+    assert_eq!(suggestions(learned.clone(), "git checkout fe"), ["git checkout feature/login"]);
+    assert!(suggestions(learned.clone(), "hg checkout ").is_empty());
+    // Nothing while the binary is still being typed
+    assert!(suggestions(learned, "gi").is_empty());
+}
+
+#[test]
+fn keeps_at_most_its_capacity_and_persists() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("learned_commands.json");
+    let mut learned = LearnedCommands::empty(&path);
+    learned.set_capacity(6);
+    let start = Utc::now() - Duration::hours(1);
+    learned.record("make build", 0, start);
+    learned.record("make test", 0, start + Duration::minutes(1));
+    learned.record("cargo test --release", 0, start + Duration::minutes(2));
+    // Each line is an invocation and an entry per argument
+    assert!(learned.len() <= 6);
+    assert!(learned.invocations("make").iter().all(|(command, _)| *command != "make build"));
+    assert_eq!(learned.invocations("cargo")[0].0, "cargo test --release");
+
+    learned.save().unwrap();
+    let loaded = LearnedCommands::load(&path).unwrap();
+    assert_eq!(loaded.len(), learned.len());
+    assert_eq!(loaded.next_arguments("cargo", "test")[0].0, "--release");
+
+    learned.clear();
+    learned.save().unwrap();
+    assert!(!path.exists());
+    assert!(LearnedCommands::load(&path).unwrap().is_empty());
+}
@@ -0,0 +1,125 @@
+use antraft::terminal::links::{detect_links, hyperlink_target, Link, LinkTarget};
+use std::path::{Path, PathBuf};
+
+/// A working directory with `src/main.rs` and `notes.txt`.
+fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+    dir
+}
+
+/// The linked text of each link in `line`.
+fn linked<'a>(line: &'a str, working_directory: &Path) -> Vec<&'a str> {
+    detect_links(line, working_directory)
+        .into_iter()
+        .map(|link| &line[link.range])
+        .collect()
+}
+
+fn file(path: PathBuf, line: Option<u32>, column: Option<u32>) -> LinkTarget {
+    LinkTarget::File { path, line, column }
+}
+
+#[test]
+fn trailing_punctuation_is_not_linked() {
+    let dir = project();
+    let cwd = dir.path();
+    assert_eq!(linked("See https://example.com/docs.", cwd), ["https://example.com/docs"]);
+    assert_eq!(linked("Docs: https://example.com/a?b=1, or ask!", cwd), ["https://example.com/a?b=1"]);
+    assert_eq!(linked("Is it https://example.com/faq?", cwd), ["https://example.com/faq"]);
+    assert_eq!(linked("Edited src/main.rs.", cwd), ["src/main.rs"]);
+    assert_eq!(linked("Wrote notes.txt; done", cwd), ["notes.txt"]);
+    assert_eq!(linked("Failed at src/main.rs:3:", cwd), ["src/main.rs:3"]);
+
+    let links = detect_links("Edited src/main.rs.", cwd);
+    assert_eq!(links[0].target, file(cwd.join("src/main.rs"), None, None));
+}
+
+#[test]
+fn paths_carry_their_line_and_column() {
+    let dir = project();
+    let cwd = dir.path();
+    let line = "error[E0425]: cannot find value `x`\n --> src/main.rs:142:17";
+    let links = detect_links(line, cwd);
+    assert_eq!(links.len(), 1);
+    assert_eq!(&line[links[0].range.clone()], "src/main.rs:142:17");
+    assert_eq!(links[0].target, file(cwd.join("src/main.rs"), Some(142), Some(17)));
+
+    assert_eq!(
+        detect_links("src/main.rs:12", cwd)[0].target,
+        file(cwd.join("src/main.rs"), Some(12), None)
+    );
+    // grep -n output: the match text after the line number isn't part of it
+    assert_eq!(linked("src/main.rs:1:fn main() {}", cwd), ["src/main.rs:1"]);
+    assert_eq!(
+        detect_links("./src/main.rs:7:3", cwd)[0].target,
+        file(cwd.join("./src/main.rs"), Some(7), Some(3))
+    );
+
+    let absolute = cwd.join("notes.txt");
+    let line = format!("{}:9", absolute.display());
+    assert_eq!(detect_links(&line, Path::new("/")), [Link {
+        range: 0..line.len(),
+        target: file(absolute, Some(9), None),
+    }]);
+}
+
+#[test]
+fn urls_inside_parentheses_keep_their_own() {
+    let dir = project();
+    let cwd = dir.path();
+    assert_eq!(linked("(see https://example.com/docs)", cwd), ["https://example.com/docs"]);
+    assert_eq!(linked("(https://example.com).", cwd), ["https://example.com"]);
+    assert_eq!(
+        linked("https://en.wikipedia.org/wiki/Rust_(programming_language)", cwd),
+        ["https://en.wikipedia.org/wiki/Rust_(programming_language)"]
+    );
+    assert_eq!(
+        linked("(from https://en.wikipedia.org/wiki/Rust_(programming_language))", cwd),
+        ["https://en.wikipedia.org/wiki/Rust_(programming_language)"]
+    );
+    assert_eq!(linked("(see src/main.rs:4)", cwd), ["src/main.rs:4"]);
+
+    let links = detect_links("(see https://example.com/docs)", cwd);
+    assert_eq!(links[0].range, 5..29);
+    assert_eq!(links[0].target, LinkTarget::Url("https://example.com/docs".to_string()));
+}
+
+#[test]
+fn partial_tokens_and_missing_files_are_not_linked() {
+    let dir = project();
+    let cwd = dir.path();
+    assert!(linked("xsrc/main.rs", cwd).is_empty());
+    assert!(linked("src/main.rs#L12", cwd).is_empty());
+    assert!(linked("src/missing.rs:3:1", cwd).is_empty());
+    assert!(linked("connecting to example.com:443", cwd).is_empty());
+    assert!(linked("visit xhttps://example.com", cwd).is_empty());
+    assert!(linked("https://", cwd).is_empty());
+    assert!(linked("version 1.2.3 released", cwd).is_empty());
+    assert_eq!(linked("\"src/main.rs\" and 'notes.txt'", cwd), ["src/main.rs", "notes.txt"]);
+}
+
+#[test]
+fn hyperlinks_open_only_known_schemes() {
+    assert_eq!(
+        hyperlink_target("https://example.com/a b"),
+        Some(LinkTarget::Url("https://example.com/a b".to_string()))
+    );
+    assert_eq!(
+        hyperlink_target("MAILTO:dev@example.com"),
+        Some(LinkTarget::Url("MAILTO:dev@example.com".to_string()))
+    );
+    assert_eq!(hyperlink_target("javascript:alert(1)"), None);
+    assert_eq!(hyperlink_target("no scheme"), None);
+
+    assert_eq!(
+        hyperlink_target("file://host/tmp/my%20file.txt"),
+        Some(file(PathBuf::from("/tmp/my file.txt"), None, None))
+    );
+    assert_eq!(
+        hyperlink_target("file:///tmp/a%2"),
+        Some(file(PathBuf::from("/tmp/a%2"), None, None))
+    );
+}
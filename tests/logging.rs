@@ -0,0 +1,69 @@
+use antraft::logging::{LogBuffer, RotatingFile};
+use log::Level;
+
+#[test]
+fn the_buffer_drops_the_oldest_records_past_its_capacity() {
+    let buffer = LogBuffer::new(3);
+    for index in 0..5 {
+        buffer.push(Level::Info, "antraft::ui", format!("record {}", index));
+    }
+    let entries = buffer.entries();
+
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(), [2, 3, 4]);
+    assert_eq!(entries[0].message, "record 2");
+    assert_eq!(buffer.next_seq(), 5);
+    assert!(buffer.recent_lines(1)[0].ends_with("] record 4"), "{:?}", buffer.recent_lines(1));
+
+    buffer.set_capacity(1);
+    assert_eq!(buffer.entries()[0].message, "record 4");
+    buffer.set_capacity(0);
+    buffer.push(Level::Error, "antraft", "dropped".to_string());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn records_are_formatted_with_time_level_and_module() {
+    let buffer = LogBuffer::new(10);
+    buffer.push(Level::Warn, "antraft::terminal::engine", "slow command".to_string());
+    let line = &buffer.recent_lines(10)[0];
+
+    assert!(line.contains(" WARN  antraft::terminal::engine] slow command"), "{}", line);
+}
+
+#[test]
+fn the_file_rotates_before_it_grows_past_its_cap() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("logs").join("antraft.log");
+    let mut file = RotatingFile::open(&path, 100, 2).unwrap();
+    for index in 0..20 {
+        file.write_line(&format!("{:02} {}", index, "x".repeat(30))).unwrap();
+    }
+
+    let mut names: Vec<String> = std::fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["antraft.log", "antraft.log.1", "antraft.log.2"]);
+    for name in &names {
+        let size = std::fs::metadata(dir.path().join("logs").join(name)).unwrap().len();
+        assert!(size <= 100, "{} is {} bytes", name, size);
+    }
+    // The newest lines are in the current file, the ones before in .1
+    let current = std::fs::read_to_string(&path).unwrap();
+    assert!(current.ends_with(&format!("19 {}\n", "x".repeat(30))), "{}", current);
+    let previous = std::fs::read_to_string(dir.path().join("logs/antraft.log.1")).unwrap();
+    assert!(previous.starts_with("15 ") || previous.starts_with("16 "), "{}", previous);
+}
+
+#[test]
+fn reopening_continues_the_file_and_its_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("antraft.log");
+    RotatingFile::open(&path, 100, 1).unwrap().write_line(&"a".repeat(60)).unwrap();
+    RotatingFile::open(&path, 100, 1).unwrap().write_line(&"b".repeat(60)).unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n", "b".repeat(60)));
+    assert_eq!(std::fs::read_to_string(dir.path().join("antraft.log.1")).unwrap(), format!("{}\n", "a".repeat(60)));
+}
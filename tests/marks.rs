@@ -0,0 +1,80 @@
+use antraft::terminal::marks::{NavigationCommand, NavigationKeys, Pins, QuickMarks, MAX_PINNED_BLOCKS};
+use uuid::Uuid;
+
+#[test]
+fn pinning_past_the_cap_unpins_the_oldest() {
+    let mut pins = Pins::new(2);
+    let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    assert_eq!(pins.pin(a), None);
+    assert_eq!(pins.pin(b), None);
+    // Pinning again doesn't make it newer
+    assert_eq!(pins.pin(a), None);
+    assert_eq!(pins.pin(c), Some(a));
+    assert_eq!(pins.ids(), [b, c]);
+
+    assert!(pins.unpin(b));
+    assert!(!pins.unpin(b));
+    assert_eq!(pins.pin(a), None);
+    assert_eq!(pins.len(), 2);
+
+    // The default cap is the app's
+    let mut pins = Pins::default();
+    let ids: Vec<Uuid> = (0..=MAX_PINNED_BLOCKS).map(|_| Uuid::new_v4()).collect();
+    let evicted: Vec<Uuid> = ids.iter().filter_map(|id| pins.pin(*id)).collect();
+    assert_eq!(evicted, [ids[0]]);
+}
+
+#[test]
+fn marks_are_letters_each_on_one_block() {
+    let mut marks = QuickMarks::default();
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    assert_eq!(marks.set('a', a).unwrap(), None);
+    assert_eq!(marks.set('z', a).unwrap(), None);
+    assert_eq!(marks.set('a', a).unwrap(), None);
+    assert_eq!(marks.letters_of(a), ['a', 'z']);
+
+    // Moving a letter says where it was
+    assert_eq!(marks.set('a', b).unwrap(), Some(a));
+    assert_eq!(marks.get('a'), Some(b));
+    assert_eq!(marks.letters_of(a), ['z']);
+    assert!(marks.set('1', a).is_err());
+
+    marks.retain_blocks(|id| id == b);
+    assert_eq!(marks.iter().collect::<Vec<_>>(), [('a', b)]);
+    assert_eq!(marks.remove('a'), Some(b));
+    assert!(marks.is_empty());
+}
+
+#[test]
+fn keys_become_navigation_commands() {
+    let mut keys = NavigationKeys::default();
+    let mut press = |typed: &str| -> Vec<NavigationCommand> { typed.chars().filter_map(|c| keys.press(c)).collect() };
+
+    assert_eq!(
+        press("kjgGpd"),
+        [
+            NavigationCommand::Previous,
+            NavigationCommand::Next,
+            NavigationCommand::First,
+            NavigationCommand::Last,
+            NavigationCommand::TogglePin,
+            NavigationCommand::Delete,
+        ]
+    );
+    assert_eq!(press("ma'a`b"), [NavigationCommand::SetMark('a'), NavigationCommand::JumpToMark('a'), NavigationCommand::JumpToMark('b')]);
+    // The letter after `m` is a mark even when it's a command key
+    assert_eq!(press("mj"), [NavigationCommand::SetMark('j')]);
+    // Anything but a letter cancels
+    assert_eq!(press("m1j"), [NavigationCommand::Next]);
+    assert_eq!(press("x"), []);
+}
+
+#[test]
+fn a_pending_mark_can_be_cancelled() {
+    let mut keys = NavigationKeys::default();
+    assert_eq!(keys.press('\''), None);
+    assert_eq!(keys.pending(), Some('\''));
+    keys.cancel();
+    assert_eq!(keys.pending(), None);
+    assert_eq!(keys.press('k'), Some(NavigationCommand::Previous));
+}
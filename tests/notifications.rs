@@ -0,0 +1,61 @@
+use antraft::notifications::{NotificationLevel, Notifications};
+use std::time::{Duration, Instant};
+
+#[test]
+fn keeps_only_the_newest_notifications() {
+    let mut notifications = Notifications::new(3);
+    let sender = notifications.sender();
+    for i in 0..5 {
+        sender.info(format!("note {}", i));
+    }
+    notifications.drain();
+
+    let titles: Vec<_> = notifications.history().iter().map(|n| n.title.as_str()).collect();
+    assert_eq!(titles, ["note 2", "note 3", "note 4"]);
+    let ids: Vec<_> = notifications.history().iter().map(|n| n.id).collect();
+    assert_eq!(ids, [3, 4, 5]);
+}
+
+#[test]
+fn info_expires_and_errors_stay_until_dismissed() {
+    let mut notifications = Notifications::default();
+    let sender = notifications.sender();
+    sender.info("Scanning");
+    sender.warning("Slow", "took a while");
+    sender.error("Save failed", "disk full");
+    notifications.drain();
+    let sent = notifications.history()[0].sent;
+
+    notifications.expire(sent + Duration::from_secs(1));
+    assert_eq!(notifications.toasts().count(), 3);
+    assert_eq!(notifications.next_expiry(sent + Duration::from_secs(1)), Some(Duration::from_secs(4)));
+
+    notifications.expire(sent + Duration::from_secs(6));
+    let levels: Vec<_> = notifications.toasts().map(|n| n.level).collect();
+    assert_eq!(levels, [NotificationLevel::Warning, NotificationLevel::Error]);
+
+    notifications.expire(sent + Duration::from_secs(3600));
+    let error = notifications.toasts().next().unwrap();
+    assert_eq!(error.level, NotificationLevel::Error);
+    assert_eq!(error.detail.as_deref(), Some("disk full"));
+    assert_eq!(notifications.next_expiry(Instant::now()), None);
+
+    let id = error.id;
+    notifications.dismiss(id);
+    assert_eq!(notifications.toasts().count(), 0);
+    // Still in the history
+    assert_eq!(notifications.history().len(), 3);
+}
+
+#[test]
+fn senders_work_from_other_threads() {
+    let mut notifications = Notifications::default();
+    let sender = notifications.sender();
+    std::thread::spawn(move || sender.success("Scan finished", "no issues"))
+        .join()
+        .unwrap();
+    notifications.drain();
+
+    assert_eq!(notifications.history().len(), 1);
+    assert_eq!(notifications.history()[0].level, NotificationLevel::Success);
+}
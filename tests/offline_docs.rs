@@ -0,0 +1,90 @@
+mod common;
+
+use antraft::ai::offline::OfflineExplainer;
+use antraft::ai::{AiAgent, AiConfig, AiRequest};
+use common::{ai_config, MockProvider};
+
+fn explain(command: &str) -> AiRequest {
+    AiRequest::ExplainCommand {
+        command: command.to_string(),
+    }
+}
+
+/// An agent whose cache is off, so each request reaches the provider.
+fn agent(provider: &MockProvider, mut config: AiConfig) -> AiAgent {
+    config.cache.enabled = false;
+    AiAgent::with_provider(config, Box::new(provider.clone()))
+}
+
+#[test]
+fn common_commands_have_bundled_docs() {
+    let explainer = OfflineExplainer::bundled().unwrap();
+    for (command, doc) in [
+        ("ls -la", "ls"),
+        ("cd ..", "cd"),
+        ("grep -rn TODO src", "grep"),
+        ("find . -name '*.rs'", "find"),
+        ("tar -xzf release.tar.gz", "tar"),
+        ("curl -sSL https://example.com", "curl"),
+        ("ssh deploy@host", "ssh"),
+        ("git commit -m 'fix'", "git commit"),
+        ("git push origin main", "git push"),
+        ("git stash", "git"),
+        ("docker run -it ubuntu", "docker run"),
+        ("cargo test --workspace", "cargo test"),
+        ("kubectl get pods -A", "kubectl get"),
+        ("sudo systemctl restart nginx", "systemctl"),
+        // sudo's own options are skipped too
+        ("sudo -u deploy git pull --rebase", "git pull"),
+        ("ps aux | grep nginx", "ps"),
+    ] {
+        assert_eq!(explainer.lookup(command).map(|doc| doc.name.as_str()), Some(doc), "{}", command);
+    }
+    assert!(explainer.lookup("frobnicate --all").is_none());
+}
+
+#[test]
+fn explanations_list_the_flags_used_and_examples() {
+    let explainer = OfflineExplainer::bundled().unwrap();
+    let response = explainer.explain("ls -lh", "No AI provider is configured.").unwrap();
+    assert!(response.content.starts_with("**📚 Offline docs** · `ls`"));
+    assert!(response.content.contains("- `-l`: long format"));
+    assert!(response.content.contains("- `-h`: print sizes"));
+    assert!(!response.content.contains("- `-t`:"));
+    assert!(response.content.contains("No AI provider is configured."));
+    assert!(response.suggestions.contains(&"ls -a".to_string()));
+}
+
+#[tokio::test]
+async fn without_a_provider_the_bundled_docs_answer() {
+    let provider = MockProvider::new();
+    let config = AiConfig {
+        api_key: String::new(),
+        ..AiConfig::default()
+    };
+    let agent = agent(&provider, config);
+
+    let response = agent.process_request(explain("git push -u origin main")).await.unwrap();
+    assert!(response.content.contains("`git push`"));
+    assert!(response.content.contains("No AI provider is configured."));
+    assert!(provider.prompts().is_empty());
+
+    let unknown = agent.process_request(explain("frobnicate")).await.unwrap_err();
+    assert!(unknown.to_string().contains("No offline docs are available for `frobnicate`"));
+}
+
+#[tokio::test]
+async fn a_failed_provider_falls_back_to_the_bundled_docs() {
+    let provider = MockProvider::new();
+    provider.fail("connection refused").fail("503 Service Unavailable");
+    let agent = agent(&provider, ai_config());
+
+    let response = agent.process_request(explain("sudo tar -czf out.tgz dir")).await.unwrap();
+    assert!(response.content.contains("`tar`"));
+    assert!(response.content.contains("The AI provider couldn't be reached."));
+    assert_eq!(provider.prompts().len(), 1);
+
+    // With no docs either, the provider's error is what's reported
+    let error = agent.process_request(explain("frobnicate")).await.unwrap_err();
+    assert!(error.to_string().contains("503"), "{}", error);
+}
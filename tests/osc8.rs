@@ -0,0 +1,84 @@
+use antraft::terminal::pty::VteProcessor;
+use antraft::terminal::Hyperlink;
+
+/// Text and hyperlinks per chunk, fed to one processor in order.
+fn process(chunks: &[&str]) -> Vec<(String, Vec<Hyperlink>)> {
+    let mut processor = VteProcessor::new();
+    chunks.iter().map(|chunk| processor.process_to_text(chunk.as_bytes())).collect()
+}
+
+fn link(range: std::ops::Range<usize>, uri: &str) -> Hyperlink {
+    Hyperlink {
+        range,
+        uri: uri.to_string(),
+    }
+}
+
+#[test]
+fn bel_terminated_links_are_parsed() {
+    let output = process(&["See \x1b]8;;https://example.com/docs\x07the docs\x1b]8;;\x07 for more\n"]);
+    assert_eq!(output, [(
+        "See the docs for more\n".to_string(),
+        vec![link(4..12, "https://example.com/docs")]
+    )]);
+}
+
+#[test]
+fn st_terminated_links_are_parsed() {
+    let output = process(&["\x1b]8;;file:///tmp/report.txt\x1b\\report.txt\x1b]8;;\x1b\\ written"]);
+    assert_eq!(output, [(
+        "report.txt written".to_string(),
+        vec![link(0..10, "file:///tmp/report.txt")]
+    )]);
+
+    // Both terminators in one line, and params before the URI
+    let output = process(&[concat!(
+        "\x1b]8;id=a;https://a.example\x07a\x1b]8;;\x1b\\ ",
+        "\x1b]8;;https://b.example\x1b\\b\x1b]8;;\x07",
+    )]);
+    assert_eq!(output[0].0, "a b");
+    assert_eq!(output[0].1, [link(0..1, "https://a.example"), link(2..3, "https://b.example")]);
+}
+
+#[test]
+fn uris_keep_their_semicolons() {
+    let output = process(&["\x1b]8;;https://example.com/?a=1;b=2\x07query\x1b]8;;\x07"]);
+    assert_eq!(output[0].1, [link(0..5, "https://example.com/?a=1;b=2")]);
+}
+
+#[test]
+fn sequences_split_across_reads_are_joined() {
+    let output = process(&["\x1b]8;;https://exa", "mple.com\x1b", "\\exam", "ple\x1b]8;;\x07 done"]);
+    let text: String = output.iter().map(|(text, _)| text.as_str()).collect();
+    assert_eq!(text, "example done");
+    assert_eq!(output[2].1, [link(0..4, "https://example.com")]);
+    assert_eq!(output[3].1, [link(0..3, "https://example.com")]);
+}
+
+#[test]
+fn an_unclosed_link_stays_open_until_closed() {
+    let output = process(&["\x1b]8;;https://x.example\x07open", " still\n", "\x1b]8;;\x07after"]);
+    assert_eq!(output[0], ("open".to_string(), vec![link(0..4, "https://x.example")]));
+    assert_eq!(output[1], (" still\n".to_string(), vec![link(0..6, "https://x.example")]));
+    assert_eq!(output[2], ("after".to_string(), vec![]));
+}
+
+#[test]
+fn a_missing_terminator_does_not_swallow_the_output() {
+    let output = process(&["\x1b]8;;https://x.example\nerror: build failed\n"]);
+    assert_eq!(output, [("\nerror: build failed\n".to_string(), vec![])]);
+
+    // The next sequence still works
+    let output = process(&["\x1b]8;;https://broken\n", "\x1b]8;;https://ok.example\x07ok\x1b]8;;\x07\n"]);
+    assert_eq!(output[1], ("ok\n".to_string(), vec![link(0..2, "https://ok.example")]));
+
+    // Another escape sequence ends it too
+    let output = process(&["\x1b]8;;https://x.example\x1b[31mred\x1b[0m\n"]);
+    assert_eq!(output[0].0, "red\n");
+}
+
+#[test]
+fn other_osc_sequences_leave_no_text() {
+    let output = process(&["\x1b]0;window title\x07prompt$ \x1b]7;file://host/tmp\x1b\\ls\n"]);
+    assert_eq!(output, [("prompt$ ls\n".to_string(), vec![])]);
+}
@@ -0,0 +1,147 @@
+use antraft::terminal::output_classifier::{ClassifiedOutput, LineClass, OutputClassifiers};
+use std::ops::Range;
+
+const CARGO_TEST: &str = include_str!("fixtures/output/cargo_test.txt");
+const NPM_INSTALL: &str = include_str!("fixtures/output/npm_install.txt");
+const PYTEST: &str = include_str!("fixtures/output/pytest.txt");
+const PIP_INSTALL: &str = include_str!("fixtures/output/pip_install.txt");
+
+/// `output` as classified for `command`, fed in small chunks the way a
+/// running command streams it.
+fn classify(command: &str, output: &str) -> ClassifiedOutput {
+    let classifier = OutputClassifiers::builtin()
+        .for_command(command)
+        .unwrap_or_else(|| panic!("no classifier for {}", command));
+    let mut classified = ClassifiedOutput::new(classifier);
+    let chunk_ends = (0..output.len()).step_by(7).filter(|&end| output.is_char_boundary(end));
+    for end in chunk_ends {
+        classified.update(&output[..end], false);
+    }
+    classified.update(output, true);
+    classified
+}
+
+fn line_of(output: &str, text: &str) -> usize {
+    output
+        .lines()
+        .position(|line| line.contains(text))
+        .unwrap_or_else(|| panic!("no line with {:?}", text))
+}
+
+#[test]
+fn cargo_test_output() {
+    let classified = classify("cargo test --lib", CARGO_TEST);
+    assert_eq!(classified.classifier_name(), "cargo");
+    assert_eq!(classified.folds().collect::<Vec<Range<usize>>>(), vec![0..8]);
+    assert_eq!(classified.warnings(), 2);
+    assert_eq!(classified.errors(), 4);
+    assert_eq!(classified.class(line_of(CARGO_TEST, "warning: unused")), LineClass::Warning);
+    assert_eq!(classified.class(line_of(CARGO_TEST, "thread 'tests::divides'")), LineClass::Error);
+    assert_eq!(classified.class(line_of(CARGO_TEST, "note: run with")), LineClass::Normal);
+    // Two passing tests are too few to fold
+    assert_eq!(classified.class(line_of(CARGO_TEST, "test tests::adds")), LineClass::Noise);
+}
+
+#[test]
+fn npm_install_output() {
+    let classified = classify("npm install", NPM_INSTALL);
+    assert_eq!(classified.classifier_name(), "npm");
+    assert_eq!(classified.folds().collect::<Vec<_>>(), vec![2..7]);
+    assert_eq!(classified.warnings(), 2);
+    assert_eq!(classified.errors(), 0);
+    assert_eq!(classified.class(line_of(NPM_INSTALL, "added 212")), LineClass::Normal);
+}
+
+#[test]
+fn pytest_output() {
+    let classified = classify("python -m pytest -q", PYTEST);
+    assert_eq!(classified.classifier_name(), "pytest");
+    assert_eq!(classified.folds().collect::<Vec<_>>(), vec![1..6]);
+    assert_eq!(classified.errors(), 4);
+    assert_eq!(classified.class(line_of(PYTEST, "tests/test_api.py")), LineClass::Noise);
+    assert_eq!(classified.class(line_of(PYTEST, "tests/test_models.py .F.")), LineClass::Error);
+    assert_eq!(classified.class(line_of(PYTEST, "E       AssertionError")), LineClass::Error);
+    assert_eq!(classified.class(line_of(PYTEST, "= FAILURES =")), LineClass::Normal);
+}
+
+#[test]
+fn pip_install_output() {
+    let classified = classify("pip install requests", PIP_INSTALL);
+    assert_eq!(classified.classifier_name(), "pip");
+    assert_eq!(classified.folds().collect::<Vec<_>>(), vec![0..8]);
+    assert_eq!(classified.warnings(), 1);
+    assert_eq!(classified.class(line_of(PIP_INSTALL, "Successfully installed")), LineClass::Normal);
+}
+
+#[test]
+fn partial_last_line_waits_until_finished() {
+    let classifier = OutputClassifiers::builtin().for_command("cargo build").unwrap();
+    let mut classified = ClassifiedOutput::new(classifier);
+    classified.update("   Compiling serde v1.0.210\nerror[E0308]: mismatched", false);
+    assert_eq!(classified.errors(), 0);
+    classified.update("   Compiling serde v1.0.210\nerror[E0308]: mismatched types\n", false);
+    assert_eq!(classified.errors(), 1);
+
+    // A rewritten progress line is classified again
+    classified.rewind(0);
+    classified.update("   Compiling serde v1.0.210\nwarning: unused import", true);
+    assert_eq!(classified.errors(), 0);
+    assert_eq!(classified.warnings(), 1);
+}
+
+#[test]
+fn classifiers_are_picked_by_command() {
+    let classifiers = OutputClassifiers::builtin();
+    let name = |command: &str| classifiers.for_command(command).map(|c| c.name().to_string());
+    assert_eq!(name("npx jest --watch").as_deref(), Some("jest"));
+    assert_eq!(name("npm ci").as_deref(), Some("npm"));
+    assert_eq!(name("docker build -t app .").as_deref(), Some("docker build"));
+    assert_eq!(name("docker run app"), None);
+    assert_eq!(name("ls -la"), None);
+}
+
+#[test]
+fn user_rules_come_before_builtin_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("output_rules.toml");
+    std::fs::write(
+        &path,
+        r#"
+[[tools]]
+name = "make"
+command = '^make\b'
+noise = ['^(gcc|cc) ']
+warning = [': warning:']
+error = [': error:', '^make: \*\*\*']
+
+[[tools]]
+name = "quiet cargo"
+command = '^cargo\b'
+noise = ['^\s*Compiling ']
+"#,
+    )
+    .unwrap();
+    let classifiers = OutputClassifiers::load(&path).unwrap();
+    assert_eq!(classifiers.for_command("cargo build").unwrap().name(), "quiet cargo");
+    assert_eq!(classifiers.for_command("npm test").unwrap().name(), "npm");
+
+    let make = classifiers.for_command("make -j4").unwrap();
+    assert_eq!(make.classify("gcc -c main.c -o main.o"), LineClass::Noise);
+    assert_eq!(make.classify("main.c:3:5: warning: unused variable 'x'"), LineClass::Warning);
+    assert_eq!(make.classify("make: *** [Makefile:4: main.o] Error 1"), LineClass::Error);
+
+    // A missing file leaves only the built-in tools
+    let missing = OutputClassifiers::load(&dir.path().join("missing.toml")).unwrap();
+    assert_eq!(missing.for_command("cargo build").unwrap().name(), "cargo");
+}
+
+#[test]
+fn invalid_rules_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("output_rules.toml");
+    std::fs::write(&path, "[[tools]]\nname = \"broken\"\ncommand = '^x'\nerror = ['(']\n").unwrap();
+    let Err(error) = OutputClassifiers::load(&path) else {
+        panic!("an invalid pattern loaded");
+    };
+    assert!(format!("{:#}", error).contains("broken"), "{:#}", error);
+}
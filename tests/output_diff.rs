@@ -0,0 +1,92 @@
+use antraft::git::diff::{parse_unified_diff, DiffLineKind};
+use antraft::terminal::diff::{diff_outputs, Segment, MAX_COMPARE_BYTES};
+
+fn changed(segments: &[Segment]) -> Vec<&str> {
+    segments
+        .iter()
+        .filter(|segment| segment.changed)
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+#[test]
+fn identical_outputs_have_no_hunks() {
+    let output = "test a ... ok\ntest b ... ok\n";
+    let diff = diff_outputs(output, output, false).unwrap();
+    assert!(diff.is_empty());
+    assert_eq!((diff.added, diff.removed), (0, 0));
+}
+
+#[test]
+fn changed_lines_are_grouped_into_hunks_with_context() {
+    let old: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+    let new = old.replace("line 3\n", "line three\n").replace("line 18\n", "");
+
+    let diff = diff_outputs(&old, &new, false).unwrap();
+    assert_eq!(diff.hunks.len(), 2);
+    assert_eq!(diff.hunks[0].header, "@@ -1,6 +1,6 @@");
+    assert_eq!(diff.hunks[1].header, "@@ -15,6 +15,5 @@");
+    assert_eq!((diff.added, diff.removed), (1, 2));
+
+    let kinds: Vec<_> = diff.hunks[0].lines.iter().map(|line| line.kind).collect();
+    assert_eq!(&kinds[1..4], [DiffLineKind::Context, DiffLineKind::Removed, DiffLineKind::Added]);
+    let removed = &diff.hunks[1].lines[3];
+    assert_eq!((removed.kind, removed.old_line, removed.new_line), (DiffLineKind::Removed, Some(18), None));
+}
+
+#[test]
+fn changed_words_within_a_line_are_marked() {
+    let diff = diff_outputs(
+        "test parser::tokens ... ok\ntest result: ok. 12 passed; 0 failed\n",
+        "test parser::tokens ... FAILED\ntest result: FAILED. 11 passed; 1 failed\n",
+        false,
+    )
+    .unwrap();
+    let lines = &diff.hunks[0].lines;
+    assert_eq!(lines[0].kind, DiffLineKind::Removed);
+    assert_eq!(changed(&lines[0].segments), ["ok"]);
+    assert_eq!(lines[2].kind, DiffLineKind::Added);
+    assert_eq!(changed(&lines[2].segments), ["FAILED"]);
+    assert_eq!(lines[2].text(), "test parser::tokens ... FAILED");
+    assert_eq!(changed(&lines[3].segments), ["FAILED.", "11", "1"]);
+
+    // Lines with nothing in common aren't marked word by word
+    let diff = diff_outputs("alpha beta\n", "gamma delta\n", false).unwrap();
+    assert!(diff.hunks[0].lines.iter().all(|line| changed(&line.segments).is_empty()));
+}
+
+#[test]
+fn whitespace_changes_can_be_ignored() {
+    let old = "NAME   READY\nweb    1/1\n";
+    let new = "NAME     READY\nweb      1/1\napi      0/1\n";
+
+    let diff = diff_outputs(old, new, false).unwrap();
+    assert_eq!((diff.added, diff.removed), (3, 2));
+
+    let diff = diff_outputs(old, new, true).unwrap();
+    assert_eq!((diff.added, diff.removed), (1, 0));
+    // Unchanged lines show the later output's spacing
+    assert_eq!(diff.hunks[0].lines[0].text(), "NAME     READY");
+    assert_eq!(diff.hunks[0].lines[2].text(), "api      0/1");
+}
+
+#[test]
+fn patches_apply_as_unified_diffs() {
+    let diff = diff_outputs("a\nb\nc\n", "a\nB\nc\nd\n", false).unwrap();
+    let patch = diff.to_patch("before", "after");
+    assert_eq!(patch, "--- before\n+++ after\n@@ -1,3 +1,4 @@\n a\n-b\n+B\n c\n+d\n");
+
+    let parsed = parse_unified_diff(&format!("diff --git a/out b/out\n{}", patch)).unwrap();
+    assert_eq!(parsed.files[0].hunks[0].lines.len(), 5);
+
+    // Output that was empty before
+    let diff = diff_outputs("", "new\n", false).unwrap();
+    assert_eq!(diff.hunks[0].header, "@@ -0,0 +1 @@");
+}
+
+#[test]
+fn huge_outputs_are_refused_with_their_size() {
+    let huge = "x".repeat(MAX_COMPARE_BYTES * 3);
+    let error = diff_outputs("small", &huge, false).unwrap_err().to_string();
+    assert_eq!(error, "The later output is 3.0 MB; only outputs up to 1.0 MB can be compared");
+}
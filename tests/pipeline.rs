@@ -0,0 +1,93 @@
+use antraft::terminal::block::metadata_keys;
+use antraft::terminal::pipeline::StepStatus;
+use antraft::terminal::{TerminalConfig, TerminalEngine, TerminalEvent, TerminalEventReceiver};
+use std::time::Duration;
+
+fn engine() -> (TerminalEngine, TerminalEventReceiver) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let config = TerminalConfig {
+        shell: "bash".to_string(),
+        ..TerminalConfig::default()
+    };
+    (TerminalEngine::new(config, tx).unwrap(), rx)
+}
+
+fn commands(commands: &[&str]) -> Vec<String> {
+    commands.iter().map(|command| command.to_string()).collect()
+}
+
+fn statuses(pipeline: &antraft::terminal::pipeline::Pipeline) -> Vec<StepStatus> {
+    pipeline.steps.iter().map(|step| step.status).collect()
+}
+
+#[tokio::test]
+async fn stops_at_the_first_failure_when_asked() {
+    let (engine, _events) = engine();
+    let pipeline = tokio::time::timeout(
+        Duration::from_secs(10),
+        engine.run_sequence(commands(&["true", "exit 3", "echo never"]), true),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        statuses(&pipeline),
+        [StepStatus::Finished(0), StepStatus::Finished(3), StepStatus::Skipped]
+    );
+    assert_eq!(pipeline.exit_code(), Some(3));
+    assert!(pipeline.steps[2].block_id.is_none());
+
+    let pipeline = engine
+        .run_sequence(commands(&["exit 2", "true", "exit 4"]), false)
+        .await
+        .unwrap();
+    assert_eq!(
+        statuses(&pipeline),
+        [StepStatus::Finished(2), StepStatus::Finished(0), StepStatus::Finished(4)]
+    );
+    // The first failure is the pipeline's
+    assert_eq!(pipeline.exit_code(), Some(2));
+
+    assert!(engine.run_sequence(Vec::new(), true).await.is_err());
+}
+
+#[tokio::test]
+async fn runs_steps_in_order_in_the_sessions_directory() {
+    let (engine, mut events) = engine();
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().canonicalize().unwrap();
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    let steps = [
+        format!("cd {}", dir.display()),
+        "echo first > order.txt".to_string(),
+        "cd sub".to_string(),
+        "echo second >> ../order.txt".to_string(),
+    ];
+    let pipeline = engine.run_sequence(steps.to_vec(), true).await.unwrap();
+    assert_eq!(pipeline.exit_code(), Some(0));
+    assert_eq!(std::fs::read_to_string(dir.join("order.txt")).unwrap(), "first\nsecond\n");
+
+    // Each step has a block of its own, marked as the pipeline's, and the
+    // pipeline reported each step starting and finishing
+    let mut step_blocks = Vec::new();
+    let mut updates = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        match event {
+            TerminalEvent::CommandStarted { id, metadata, .. } => {
+                assert_eq!(metadata.get(metadata_keys::PIPELINE), Some(&pipeline.id.to_string()));
+                step_blocks.push(id);
+            }
+            TerminalEvent::NewBlock { block } => {
+                assert_eq!(block.get_metadata(metadata_keys::PIPELINE), Some(&pipeline.id.to_string()));
+                step_blocks.push(block.id);
+            }
+            TerminalEvent::PipelineUpdated { pipeline } => updates.push(pipeline),
+            _ => {}
+        }
+    }
+    let ids: Vec<_> = pipeline.steps.iter().map(|step| step.block_id.unwrap()).collect();
+    assert_eq!(step_blocks, ids);
+    assert_eq!(statuses(&updates[0]), [StepStatus::Pending; 4]);
+    assert_eq!(updates.last(), Some(&pipeline));
+    assert!(updates.iter().any(|update| update.steps[1].status == StepStatus::Running));
+}
@@ -0,0 +1,150 @@
+use antraft::terminal::process_usage::{
+    format_memory, format_usage, usage_by_root, usage_of, ProcessSample, ProcessSampler, ProcessUsage, UsageRecord,
+};
+use std::time::{Duration, Instant};
+
+fn sample(pid: u32, parent: u32, cpu_percent: f32, memory_kb: u64) -> ProcessSample {
+    ProcessSample {
+        pid,
+        parent,
+        cpu_percent,
+        memory_bytes: memory_kb * 1024,
+    }
+}
+
+fn samples() -> Vec<ProcessSample> {
+    vec![
+        sample(1, 0, 0.0, 12000),
+        sample(100, 1, 1.5, 8000),
+        sample(200, 100, 50.0, 204800),
+        sample(201, 200, 25.5, 10240),
+        sample(300, 1, 9.0, 4096),
+    ]
+}
+
+#[test]
+fn usage_covers_every_descendant() {
+    let samples = samples();
+    let usage = usage_of(&samples, &[100]);
+    assert_eq!(usage.processes, 3);
+    assert_eq!(usage.cpu_percent, 77.0);
+    assert_eq!(usage.memory_bytes, (8000 + 204800 + 10240) * 1024);
+
+    // Roots are counted once even when one descends from another
+    assert_eq!(usage_of(&samples, &[100, 200]).processes, 3);
+    // Exited commands count for nothing
+    assert_eq!(usage_of(&samples, &[999]).processes, 0);
+}
+
+#[test]
+fn each_root_gets_its_own_tree() {
+    let samples = samples();
+    let usage = usage_by_root(&samples, &[200, 300, 999]);
+    assert_eq!(usage[&200].processes, 2);
+    assert_eq!(usage[&200].cpu_percent, 75.5);
+    assert_eq!(usage[&300].processes, 1);
+    assert_eq!(usage[&999], ProcessUsage::default());
+}
+
+#[test]
+fn a_record_keeps_the_peaks_after_the_processes_exit() {
+    let start = Instant::now();
+    let mut record = UsageRecord::default();
+    assert!(record.is_empty());
+    let busy = ProcessUsage {
+        cpu_percent: 320.0,
+        memory_bytes: 512 * 1024 * 1024,
+        processes: 5,
+    };
+    let idle = ProcessUsage {
+        cpu_percent: 10.0,
+        memory_bytes: 2 * 1024 * 1024 * 1024,
+        processes: 1,
+    };
+    record.record(busy, start);
+    record.record(busy, start + Duration::from_secs(2));
+    record.record(idle, start + Duration::from_secs(3));
+    assert_eq!(record.current, Some(idle));
+    assert_eq!(record.peak_cpu_percent, 320.0);
+    assert_eq!(record.peak_memory_bytes, 2 * 1024 * 1024 * 1024);
+    assert!((record.cpu_seconds - 6.5).abs() < 1e-6, "{}", record.cpu_seconds);
+
+    // Exited between samples
+    record.record(ProcessUsage::default(), start + Duration::from_secs(4));
+    assert_eq!(record.current, None);
+    assert_eq!(record.peak_cpu_percent, 320.0);
+    assert!(!record.is_empty());
+    assert_eq!(format_usage(&busy), "CPU 320% · 512 MB");
+}
+
+#[test]
+fn peaks_come_from_different_samples() {
+    let start = Instant::now();
+    let mut record = UsageRecord::default();
+    let usage = |cpu_percent, megabytes: u64| ProcessUsage {
+        cpu_percent,
+        memory_bytes: megabytes * 1024 * 1024,
+        processes: 2,
+    };
+    record.record(usage(90.0, 100), start);
+    record.record(usage(250.0, 40), start + Duration::from_secs(1));
+    record.record(usage(30.0, 700), start + Duration::from_secs(2));
+    record.record(usage(60.0, 200), start + Duration::from_secs(3));
+
+    // The highest CPU and the most memory, though no one sample had both
+    assert_eq!(record.peak_cpu_percent, 250.0);
+    assert_eq!(record.peak_memory_bytes, 700 * 1024 * 1024);
+    assert_eq!(record.current, Some(usage(60.0, 200)));
+    // Each sample's CPU is counted for the time since the one before
+    assert!((record.cpu_seconds - 3.4).abs() < 1e-6, "{}", record.cpu_seconds);
+}
+
+#[test]
+fn memory_is_shown_in_the_largest_fitting_unit() {
+    assert_eq!(format_memory(820 * 1024), "820 KB");
+    assert_eq!(format_memory(312 * 1024 * 1024 + 5), "312 MB");
+    assert_eq!(format_memory(3 * 1024 * 1024 * 1024 / 2), "1.5 GB");
+}
+
+#[cfg(unix)]
+#[test]
+fn samples_a_running_process_and_its_children() {
+    let mut child = std::process::Command::new("sh")
+        .args(["-c", "sleep 5 & sleep 5; wait"])
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let processes = ProcessSampler::new().sample().unwrap();
+    let usage = usage_of(&processes, &[child.id()]);
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(usage.processes >= 3, "{:?}", usage);
+    assert!(usage.memory_bytes > 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn busy_children_are_added_to_their_command() {
+    let mut busy = std::process::Command::new("sh")
+        .args(["-c", "yes > /dev/null & a=$!; yes > /dev/null & b=$!; sleep 1.5; kill $a $b"])
+        .spawn()
+        .unwrap();
+    let mut idle = std::process::Command::new("sleep").arg("1.5").spawn().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // CPU is what each used between the two samples
+    let mut sampler = ProcessSampler::new();
+    sampler.sample().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let processes = sampler.sample().unwrap();
+    let usage = usage_by_root(&processes, &[busy.id(), idle.id()]);
+    // The script stops its own children
+    for child in [&mut busy, &mut idle] {
+        child.wait().unwrap();
+    }
+    let (busy, idle) = (usage[&busy.id()], usage[&idle.id()]);
+    // sh, both yes and sleep
+    assert!(busy.processes >= 4, "{:?}", busy);
+    assert!(busy.cpu_percent > idle.cpu_percent, "{:?} vs {:?}", busy, idle);
+    assert_eq!(idle.processes, 1);
+}
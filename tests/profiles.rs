@@ -0,0 +1,148 @@
+use antraft::config::profiles::{apply_profile, profile_names};
+use antraft::config::Config;
+use std::path::{Path, PathBuf};
+
+const CONFIG: &str = r#"
+[ai]
+model = "gemini-2.0-flash"
+temperature = 0.3
+
+[ai.overrides.explain]
+temperature = 0.1
+
+[terminal.aliases]
+ll = "ls -l"
+
+[profiles.work.ai]
+model = "gemini-1.5-pro"
+
+[profiles.work.ai.overrides.explain]
+max_tokens = 512
+
+[profiles.work.security]
+excluded_paths = ["vendor"]
+
+[profiles.work.terminal.aliases]
+k = "kubectl"
+
+[profiles.personal.terminal]
+timeout = 60
+"#;
+
+fn write_config(dir: &Path, content: &str) -> PathBuf {
+    let path = dir.join("config.toml");
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn a_profile_wins_over_the_file_which_wins_over_the_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_config(dir.path(), CONFIG);
+    let config = Config::load_profile(Some(&path), Some("work")).unwrap();
+
+    assert_eq!(config.active_profile.as_ref().unwrap().name, "work");
+    assert_eq!(config.ai.model, "gemini-1.5-pro");
+    assert_eq!(config.ai.temperature, 0.3);
+    assert_eq!(config.security.excluded_paths, ["vendor"]);
+    assert_eq!(config.terminal.timeout, None);
+    // Tables merge key by key
+    let explain = &config.ai.overrides["explain"];
+    assert_eq!((explain.temperature, explain.max_tokens), (Some(0.1), Some(512)));
+    assert_eq!(config.terminal.aliases.len(), 2);
+
+    let config = Config::load(Some(&path)).unwrap();
+    assert!(config.active_profile.is_none());
+    assert_eq!(config.ai.model, "gemini-2.0-flash");
+    assert_eq!(config.profile_names(), ["personal", "work"]);
+}
+
+#[test]
+fn the_file_can_choose_the_profile_and_switching_can_drop_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_config(dir.path(), &format!("profile = \"personal\"\n{}", CONFIG));
+
+    let config = Config::load_profile(Some(&path), None).unwrap();
+    assert_eq!(config.terminal.timeout, Some(60));
+
+    let work = config.reload_with_profile(Some("work")).unwrap();
+    assert_eq!((work.terminal.timeout, work.ai.model.as_str()), (None, "gemini-1.5-pro"));
+    let without = config.reload_with_profile(None).unwrap();
+    assert!(without.active_profile.is_none());
+    assert_eq!(without.terminal.timeout, None);
+}
+
+#[test]
+fn a_missing_profile_is_an_error_naming_the_others() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_config(dir.path(), CONFIG);
+    let error = format!("{:#}", Config::load_profile(Some(&path), Some("home")).unwrap_err());
+
+    assert!(error.contains("`home`"), "{}", error);
+    assert!(error.contains("personal, work"), "{}", error);
+}
+
+#[test]
+fn saving_puts_each_setting_back_where_it_came_from() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_config(dir.path(), CONFIG);
+    let mut config = Config::load_profile(Some(&path), Some("work")).unwrap();
+    config.ai.model = "gemini-2.5-pro".to_string();
+    config.ai.temperature = 0.7;
+    config.save().unwrap();
+
+    let base = Config::load(Some(&path)).unwrap();
+    assert_eq!(base.ai.model, "gemini-2.0-flash");
+    assert_eq!(base.ai.temperature, 0.7);
+    assert_eq!(base.terminal.aliases.len(), 1);
+    assert_eq!(base.security.excluded_paths, Config::default().security.excluded_paths);
+
+    let work = Config::load_profile(Some(&path), Some("work")).unwrap();
+    assert_eq!(work.ai.model, "gemini-2.5-pro");
+    assert_eq!(work.security.excluded_paths, ["vendor"]);
+}
+
+#[test]
+fn a_profile_replaces_the_key_source_whole() {
+    let file: toml::Table = r#"
+        [ai]
+        api_key_source = { type = "keyring", service = "antraft", account = "me" }
+        [profiles.ci.ai]
+        api_key_source = { type = "config" }
+    "#
+    .parse()
+    .unwrap();
+    let (merged, _) = apply_profile(&file, "ci").unwrap();
+
+    let source = merged["ai"]["api_key_source"].as_table().unwrap();
+    assert_eq!(source.keys().collect::<Vec<_>>(), ["type"]);
+    assert_eq!(source["type"].as_str(), Some("config"));
+}
+
+#[test]
+fn keyring_keys_are_looked_up_per_profile() {
+    let file: toml::Table = r#"
+        [ai]
+        api_key_source = { type = "keyring", service = "antraft", account = "me" }
+        [profiles.work.ai]
+        model = "gemini-1.5-pro"
+    "#
+    .parse()
+    .unwrap();
+    let (merged, _) = apply_profile(&file, "work").unwrap();
+
+    assert_eq!(merged["ai"]["api_key_source"]["account"].as_str(), Some("me/work"));
+    assert_eq!(profile_names(&file), ["work"]);
+}
+
+#[test]
+fn checks_profile_settings_for_typos() {
+    let dir = tempfile::tempdir().unwrap();
+    let typos = "[profiles.home.ai]\nmodle = \"x\"\n[profiles.home.workspaces]\nrecent = []\n";
+    let path = write_config(dir.path(), &format!("{}{}", CONFIG, typos));
+    let error = format!("{:#}", Config::load(Some(&path)).unwrap().validate().unwrap_err());
+
+    assert!(error.contains("`profiles.home.ai.modle`"), "{}", error);
+    assert!(error.contains("`profiles.home.workspaces`"), "{}", error);
+    assert!(!error.contains("aliases"), "{}", error);
+}
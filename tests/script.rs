@@ -0,0 +1,90 @@
+use antraft::ai::script::{fallback_script, shell_quote, shebang, write_script, ScriptCommand};
+use antraft::terminal::block::{metadata_keys, Block};
+use antraft::terminal::TerminalSession;
+
+fn command(command: &str, directory: &str) -> ScriptCommand {
+    ScriptCommand::new(command.to_string(), directory.to_string())
+}
+
+fn ran(command: &str, exit_code: Option<i32>, directory: &str) -> Block {
+    let mut block = Block::command(command.to_string());
+    block.exit_code = exit_code;
+    block.set_metadata(metadata_keys::WORKING_DIRECTORY.to_string(), directory.to_string());
+    block
+}
+
+#[test]
+fn the_fallback_starts_with_the_shebang_and_keeps_the_order() {
+    let commands = [
+        command("cargo build", "/work/app"),
+        command("cd ../docs", "/work/app"),
+        command("make html", "/work/docs"),
+        command("ls _build", "/work/docs"),
+    ];
+    let script = fallback_script(&commands, "/usr/bin/bash");
+    let lines: Vec<&str> = script.lines().collect();
+
+    assert_eq!(lines[0], "#!/usr/bin/env bash");
+    assert!(lines.contains(&"set -euo pipefail"));
+    let body: Vec<&str> = lines[3..].iter().copied().filter(|line| !line.is_empty()).collect();
+    // `cd`s become a `cd` wherever the directory changes
+    assert_eq!(body, ["cd /work/app", "cargo build", "cd /work/docs", "make html", "ls _build"]);
+}
+
+#[test]
+fn each_shell_gets_its_own_shebang_and_error_handling() {
+    assert_eq!(shebang("zsh"), "#!/usr/bin/env zsh");
+    assert_eq!(shebang("/bin/sh"), "#!/bin/sh");
+    assert_eq!(shebang("pwsh.exe"), "#!/usr/bin/env pwsh");
+
+    let fish = fallback_script(&[command("make", "/src")], "fish");
+    assert!(fish.starts_with("#!/usr/bin/env fish\n"));
+    assert!(fish.ends_with("make\nor exit $status\n"));
+    assert!(fallback_script(&[], "sh").contains("\nset -eu\n"));
+    assert!(fallback_script(&[], "pwsh").contains("$ErrorActionPreference = 'Stop'"));
+}
+
+#[test]
+fn directories_are_quoted_and_commands_kept_as_typed() {
+    let commands = [command("grep -r 'it'\"'\"'s' .", "/home/me/My Project's")];
+    let script = fallback_script(&commands, "bash");
+    assert!(script.contains("cd '/home/me/My Project'\\''s'\n"), "{}", script);
+    assert!(script.contains("\ngrep -r 'it'\"'\"'s' .\n"));
+
+    assert_eq!(shell_quote("/plain/path-1.0/~x", "bash"), "/plain/path-1.0/~x");
+    assert_eq!(shell_quote("a b", "bash"), "'a b'");
+    assert_eq!(shell_quote("it's", "fish"), r"'it\'s'");
+    assert_eq!(shell_quote(r"C:\it's", "fish"), r"'C:\\it\'s'");
+    assert_eq!(shell_quote("it's", "powershell"), "'it''s'");
+    assert_eq!(shell_quote("$HOME", "bash"), "'$HOME'");
+}
+
+#[test]
+fn exported_sessions_leave_out_failed_and_running_commands() {
+    let mut session = TerminalSession::new();
+    session.add_block(ran("cargo build", Some(0), "/work"));
+    session.add_block(ran("cargo tset", Some(101), "/work"));
+    session.add_block(ran("cd tests", Some(0), "/work"));
+    session.add_block(ran("cargo test", Some(0), "/work/tests"));
+    session.add_block(ran("cargo watch", None, "/work/tests"));
+    session.add_block(Block::output("test result: ok".to_string()));
+
+    let script = session.export_as_script("bash");
+    assert!(script.starts_with("#!/usr/bin/env bash\n"));
+    let body: Vec<&str> = script.lines().filter(|line| !line.is_empty() && !line.starts_with('#')).collect();
+    assert_eq!(body, ["set -e", "cd /work", "cargo build", "cd /work/tests", "cargo test"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn saved_scripts_are_executable_and_get_a_shebang() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("scripts/build.sh");
+    write_script(&path, "echo built\n", "zsh").unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "#!/usr/bin/env zsh\necho built\n");
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o111, 0o111);
+    let output = std::process::Command::new("sh").arg(&path).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "built\n");
+}
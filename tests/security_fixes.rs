@@ -0,0 +1,118 @@
+use antraft::security::fixes::{self, FixAction};
+use antraft::security::{osv, semgrep, Remediation};
+use std::cmp::Ordering;
+use std::path::Path;
+
+fn fixture(name: &str) -> serde_json::Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/security").join(name);
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn osv_findings_offer_an_upgrade_command() {
+    let findings = osv::parse_results(&fixture("osv_lodash.json"));
+    assert_eq!(findings.len(), 2);
+
+    // The lowest fix past the version in use, from lodash's own ranges
+    let fixable = &findings[0];
+    assert_eq!(fixable.suggested_fix.as_deref(), Some("Update lodash to >= 4.17.21"));
+    assert_eq!(
+        fixable.remediation,
+        Some(Remediation::Upgrade {
+            ecosystem: "npm".to_string(),
+            package: "lodash".to_string(),
+            version: Some("4.17.15".to_string()),
+            fixed_version: Some("4.17.21".to_string()),
+            source: Some("web/package-lock.json".into()),
+        })
+    );
+    let root = Path::new("/project");
+    assert_eq!(
+        fixes::fix_action(fixable, root).unwrap(),
+        Some(FixAction::Command {
+            command: "npm install lodash@4.17.21".to_string(),
+            directory: root.join("web"),
+        })
+    );
+
+    // Without a fixed version, as far as the constraints allow
+    let unfixed = &findings[1];
+    assert_eq!(unfixed.suggested_fix.as_deref(), Some("Update lodash to a secure version"));
+    let Some(FixAction::Command { command, .. }) = fixes::fix_action(unfixed, root).unwrap() else {
+        panic!("expected a command");
+    };
+    assert_eq!(command, "npm update lodash");
+}
+
+#[test]
+fn upgrade_commands_follow_the_package_manager() {
+    let command = |ecosystem, package, version, fixed, source: Option<&str>| {
+        fixes::upgrade_command(ecosystem, package, version, fixed, source.map(Path::new))
+    };
+    assert_eq!(
+        command("npm", "lodash", None, Some("4.17.21"), Some("yarn.lock")).as_deref(),
+        Some("yarn upgrade lodash@4.17.21")
+    );
+    assert_eq!(
+        command("npm", "@babel/traverse", None, Some("7.23.2"), Some("app/pnpm-lock.yaml")).as_deref(),
+        Some("pnpm update @babel/traverse@7.23.2")
+    );
+    assert_eq!(
+        command("crates.io", "h2", Some("0.3.20"), Some("0.3.24"), Some("Cargo.lock")).as_deref(),
+        Some("cargo update -p h2@0.3.20 --precise 0.3.24")
+    );
+    assert_eq!(command("crates.io", "h2", None, None, None).as_deref(), Some("cargo update -p h2"));
+    assert_eq!(
+        command("PyPI", "requests", None, Some("2.31.0"), Some("requirements.txt")).as_deref(),
+        Some("pip install --upgrade 'requests>=2.31.0'")
+    );
+    assert_eq!(
+        command("PyPI", "requests", None, Some("2.31.0"), Some("poetry.lock")).as_deref(),
+        Some("poetry update requests")
+    );
+    assert_eq!(
+        command("Go", "golang.org/x/net", None, Some("0.17.0"), Some("go.mod")).as_deref(),
+        Some("go get golang.org/x/net@v0.17.0")
+    );
+    assert_eq!(command("Go", "stdlib", None, Some("1.21.3"), None), None);
+    assert_eq!(command("Maven", "org.example:lib", None, Some("2.0"), None), None);
+    // Scanner output never reaches the shell with anything odd in it
+    assert_eq!(command("npm", "lodash; rm -rf ~", None, Some("4.17.21"), None), None);
+    assert_eq!(command("npm", "lodash", None, Some("$(curl evil)"), None), None);
+    assert_eq!(command("npm", "--global", None, None, None), None);
+}
+
+#[test]
+fn versions_compare_numerically() {
+    assert_eq!(fixes::compare_versions("4.17.21", "4.17.9"), Ordering::Greater);
+    assert_eq!(fixes::compare_versions("1.2", "1.2.0"), Ordering::Equal);
+    assert_eq!(fixes::compare_versions("v1.10.0", "1.9.3"), Ordering::Greater);
+    assert_eq!(fixes::compare_versions("1.0.0-rc.1", "1.0.0"), Ordering::Less);
+    assert_eq!(fixes::compare_versions("1.0.0-rc.10", "1.0.0-rc.9"), Ordering::Greater);
+    assert_eq!(fixes::compare_versions("1.0.0+build.5", "1.0.0"), Ordering::Equal);
+}
+
+#[test]
+fn semgrep_autofix_is_applied_as_a_patch() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = "import subprocess\n\n\ndef run(command):\n    return subprocess.run(command, shell=True)\n";
+    std::fs::write(dir.path().join("app.py"), source).unwrap();
+
+    let findings = semgrep::parse_results(&fixture("semgrep_autofix.json"), "security");
+    assert_eq!(findings[0].title, "Found 'subprocess' function 'run' with 'shell=True'.");
+    assert!(findings[1].remediation.is_none());
+    assert!(fixes::fix_action(&findings[1], dir.path()).unwrap().is_none());
+
+    let Some(FixAction::Patch(patch)) = fixes::fix_action(&findings[0], dir.path()).unwrap() else {
+        panic!("expected a patch");
+    };
+    assert!(patch.fixed.ends_with("subprocess.run(command, shell=False)\n"));
+    let diff = patch.diff().unwrap();
+    assert_eq!((diff.added, diff.removed), (1, 1));
+
+    patch.apply().unwrap();
+    assert_eq!(std::fs::read_to_string(dir.path().join("app.py")).unwrap(), patch.fixed);
+    // A file that changed since isn't overwritten
+    assert!(patch.apply().is_err());
+    assert!(fixes::apply_replacement("short", 2, 40, "x").is_err());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use antraft::security::{
+    ScanTarget, ScanType, ScannerKind, SecurityConfig, SecurityScanRequest, SecurityScanner, Severity,
+};
+use common::{vulnerability, StubScanner};
+
+fn mixed_findings() -> Vec<antraft::security::Vulnerability> {
+    vec![
+        vulnerability("SQL injection", Severity::Critical, "code"),
+        vulnerability("Weak hash", Severity::High, "code"),
+        vulnerability("Debug enabled", Severity::Medium, "code"),
+        vulnerability("Verbose errors", Severity::Low, "code"),
+        vulnerability("Missing header", Severity::Info, "code"),
+    ]
+}
+
+fn scanner() -> SecurityScanner {
+    SecurityScanner::with_scanners(
+        SecurityConfig::default(),
+        vec![
+            Box::new(StubScanner::new("code", ScannerKind::Code, mixed_findings())),
+            Box::new(StubScanner::new(
+                "deps",
+                ScannerKind::Dependencies,
+                vec![vulnerability("Vulnerable lodash", Severity::High, "deps")],
+            )),
+            Box::new(StubScanner::failing("broken", ScannerKind::Code, "not installed")),
+        ],
+    )
+}
+
+fn request(scan_type: ScanType) -> SecurityScanRequest {
+    SecurityScanRequest {
+        target: ScanTarget::Path(std::env::temp_dir()),
+        scan_type,
+        include_patterns: Vec::new(),
+        exclude_patterns: Vec::new(),
+        honor_ignore_comments: true,
+    }
+}
+
+#[tokio::test]
+async fn merges_findings_and_scores_them() {
+    let report = scanner().scan(request(ScanType::Full)).await.unwrap();
+    let summary = &report.summary;
+
+    assert_eq!(summary.total_vulnerabilities, 6);
+    assert_eq!(
+        (summary.critical_count, summary.high_count, summary.medium_count, summary.low_count, summary.info_count),
+        (1, 2, 1, 1, 1)
+    );
+    // 10 + 2×7 + 4 + 2 + 1
+    assert_eq!(summary.risk_score(), 31);
+    assert_eq!(summary.risk_level(), "High");
+    // The failing scanner is skipped, not fatal, and reported
+    assert_eq!(summary.files_scanned, 2);
+    assert_eq!(report.scanner_errors, ["broken: not installed"]);
+}
+
+#[tokio::test]
+async fn runs_only_the_scanners_a_scan_type_covers() {
+    let dependencies = scanner().scan(request(ScanType::DependenciesOnly)).await.unwrap();
+    let titles: Vec<_> = dependencies.vulnerabilities.iter().map(|v| v.title.as_str()).collect();
+    assert_eq!(titles, ["Vulnerable lodash"]);
+
+    let code = scanner().scan(request(ScanType::CodeOnly)).await.unwrap();
+    assert_eq!(code.summary.total_vulnerabilities, 5);
+
+    // Stubs don't offer a quick scan
+    let quick = scanner().scan(request(ScanType::Quick)).await.unwrap();
+    assert_eq!(quick.summary.total_vulnerabilities, 0);
+}
+
+#[tokio::test]
+async fn report_markdown_lists_each_severity() {
+    let report = scanner().scan(request(ScanType::Full)).await.unwrap();
+    let markdown = report.to_markdown();
+
+    for section in ["**Critical:** 1", "**High:** 2", "**Medium:** 1", "**Low:** 1", "**Info:** 1"] {
+        assert!(markdown.contains(section), "missing {}", section);
+    }
+    assert!(markdown.contains("**Risk Score:** 31"));
+    assert!(markdown.contains("SQL injection"));
+}
+
+#[tokio::test]
+async fn rejects_missing_paths() {
+    let mut missing = request(ScanType::Full);
+    missing.target = ScanTarget::Path(std::env::temp_dir().join("antraft-no-such-dir"));
+    assert!(scanner().scan(missing).await.is_err());
+}
+
+#[test]
+fn lists_its_scanners() {
+    let scanner = scanner();
+    assert_eq!(scanner.get_available_scanners(), ["code", "deps", "broken"]);
+    assert!(scanner.is_scanner_available("deps"));
+    assert!(!scanner.is_scanner_available("bandit"));
+}
@@ -0,0 +1,101 @@
+use antraft::security::{self, ScanTarget, ScanType, SecurityReport, Severity, Vulnerability};
+use std::path::PathBuf;
+
+fn finding(file: &str, category: &str, severity: Severity) -> Vulnerability {
+    Vulnerability::new(
+        format!("{} issue", category),
+        String::new(),
+        severity,
+        category.to_string(),
+        file.to_string(),
+        "test".to_string(),
+    )
+}
+
+/// Five high and critical findings, three of them in `src/auth.py`.
+fn report() -> SecurityReport {
+    let mut report = SecurityReport::new(&ScanTarget::Path(PathBuf::from("/repo")), ScanType::Full);
+    for vulnerability in [
+        finding("src/auth.py", "injection", Severity::Critical),
+        finding("src/auth.py", "secret", Severity::High),
+        finding("src/auth.py", "injection", Severity::High),
+        finding("src/auth.py", "injection", Severity::Low),
+        finding("Cargo.lock", "dependency", Severity::High),
+        finding("Cargo.lock", "dependency", Severity::Medium),
+        finding("src/db.py", "injection", Severity::High),
+        finding("src/db.py", "injection", Severity::Low),
+        finding("web/app.js", "secret", Severity::Medium),
+        finding("", "dependency", Severity::Info),
+    ] {
+        report.add_vulnerability(vulnerability);
+    }
+    report.finalize(12, 30);
+    report
+}
+
+#[test]
+fn counts_findings_per_category_and_file() {
+    let report = report();
+    let summary = &report.summary;
+    assert_eq!(summary.by_category.len(), 3);
+    assert_eq!(summary.by_category["injection"], 5);
+    assert_eq!(summary.categories(), [("injection", 5), ("dependency", 3), ("secret", 2)]);
+
+    // Ties go by path; findings without a file aren't counted
+    assert_eq!(
+        summary.top_files,
+        [("src/auth.py".to_string(), 4), ("Cargo.lock".to_string(), 2), ("src/db.py".to_string(), 2)]
+    );
+    assert_eq!(security::top_files(&report.vulnerabilities, 1), [("src/auth.py".to_string(), 4)]);
+    assert!(security::top_files(&[], security::TOP_FILES).is_empty());
+}
+
+#[test]
+fn recommendations_point_at_the_files_with_serious_findings() {
+    let report = report();
+    let recommendations = &report.recommendations;
+    assert!(recommendations.contains(&"🎯 `src/auth.py` accounts for 60% of high and critical findings.".to_string()));
+    assert!(recommendations.contains(&"🎯 `Cargo.lock` accounts for 20% of high and critical findings.".to_string()));
+    assert!(recommendations.contains(&"📦 Update dependencies to latest secure versions (3 findings).".to_string()));
+    assert!(recommendations
+        .contains(&"🔐 Secrets detected in code (2 findings). Use environment variables or secret management.".to_string()));
+    assert!(report.summary.previous_risk_score.is_none());
+    assert!(!recommendations.iter().any(|recommendation| recommendation.contains("since the last scan")));
+
+    // A single serious finding isn't a hotspot
+    let mut single = SecurityReport::new(&ScanTarget::Path(PathBuf::from("/repo")), ScanType::Full);
+    single.add_vulnerability(finding("src/auth.py", "code", Severity::High));
+    single.finalize(1, 1);
+    assert!(!single.recommendations.iter().any(|recommendation| recommendation.starts_with("🎯")));
+}
+
+#[test]
+fn the_risk_trend_compares_with_the_last_scan() {
+    let mut report = report();
+    let score = report.summary.risk_score();
+    assert_eq!(score, 51);
+    report.set_previous_risk_score(62);
+    assert_eq!(report.summary.risk_trend().as_deref(), Some("62 → 51"));
+    assert_eq!(report.recommendations[0], "📉 Risk score 62 → 51 since the last scan.");
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("- **Risk Score:** 51 (last scan: 62 → 51)"), "{}", markdown);
+    assert!(markdown.contains("### By Category\n\n- **injection:** 5\n- **dependency:** 3\n- **secret:** 2\n"));
+    assert!(markdown.contains("### Top Files\n\n- `src/auth.py`: 4\n- `Cargo.lock`: 2\n- `src/db.py`: 2\n"));
+
+    let mut clean = SecurityReport::new(&ScanTarget::Path(PathBuf::from("/repo")), ScanType::Full);
+    clean.finalize(3, 1);
+    clean.set_previous_risk_score(0);
+    assert_eq!(
+        clean.recommendations,
+        ["➖ Risk score 0 → 0 since the last scan.", "✅ No significant security issues found. Good job!"]
+    );
+
+    // Older reports without the new fields still load
+    let mut json = serde_json::to_value(&report).unwrap();
+    let summary = json["summary"].as_object_mut().unwrap();
+    summary.remove("by_category");
+    summary.remove("top_files");
+    summary.remove("previous_risk_score");
+    let loaded: SecurityReport = serde_json::from_value(json).unwrap();
+    assert!(loaded.summary.by_category.is_empty() && loaded.summary.previous_risk_score.is_none());
+}
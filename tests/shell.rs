@@ -0,0 +1,174 @@
+use antraft::terminal::shell::{encode_powershell_command, supports_login, ShellArgs, ShellKind};
+
+fn args(args: &[&str]) -> ShellArgs {
+    ShellArgs::Args(args.iter().map(|arg| arg.to_string()).collect())
+}
+
+fn lookup(name: &str) -> Option<String> {
+    match name {
+        "HOME" => Some("/home/ash".to_string()),
+        "PATH" => Some("/usr/bin:/bin".to_string()),
+        "PROJECTS" => Some("C:\\Users\\Ash\\Projects".to_string()),
+        "ProgramFiles(x86)" => Some("C:\\Program Files (x86)".to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn kinds_are_detected_from_the_shell_binary() {
+    let cases = [
+        ("bash", ShellKind::Posix),
+        ("/usr/local/bin/fish", ShellKind::Posix),
+        ("C:\\Program Files\\Git\\bin\\bash.exe", ShellKind::Posix),
+        ("pwsh", ShellKind::PowerShell),
+        ("PowerShell.exe", ShellKind::PowerShell),
+        ("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe", ShellKind::PowerShell),
+        ("cmd", ShellKind::Cmd),
+        ("C:\\Windows\\System32\\CMD.EXE", ShellKind::Cmd),
+        ("wsl.exe", ShellKind::Wsl),
+        ("nu", ShellKind::Posix),
+    ];
+    for (shell, kind) in cases {
+        assert_eq!(ShellKind::detect(shell), kind, "{}", shell);
+    }
+}
+
+#[test]
+fn command_lines_are_built_for_each_kind() {
+    let cases = [
+        (ShellKind::Posix, "echo \"a b\" 'c'", args(&["-c", "echo \"a b\" 'c'"])),
+        (ShellKind::Posix, "printf '%s\\n' $PATH", args(&["-c", "printf '%s\\n' $PATH"])),
+        (ShellKind::Posix, "echo héllo ✓", args(&["-c", "echo héllo ✓"])),
+        (ShellKind::Wsl, "ls -la \"$HOME/my dir\"", args(&["-e", "sh", "-c", "ls -la \"$HOME/my dir\""])),
+        // PowerShell gets UTF-16LE in base64, which no quoting can mangle
+        (
+            ShellKind::PowerShell,
+            "Write-Output \"a b\"",
+            args(&["-NoLogo", "-EncodedCommand", "VwByAGkAdABlAC0ATwB1AHQAcAB1AHQAIAAiAGEAIABiACIA"]),
+        ),
+        (
+            ShellKind::PowerShell,
+            "echo 'héllo wörld' $env:PATH",
+            args(&[
+                "-NoLogo",
+                "-EncodedCommand",
+                "ZQBjAGgAbwAgACcAaADpAGwAbABvACAAdwD2AHIAbABkACcAIAAkAGUAbgB2ADoAUABBAFQASAA=",
+            ]),
+        ),
+        // cmd.exe takes the line as typed, in outer quotes `/S` strips
+        (
+            ShellKind::Cmd,
+            "echo \"a b\" %PATH% & dir",
+            ShellArgs::Raw("/D /S /C \"echo \"a b\" %PATH% & dir\"".to_string()),
+        ),
+        (ShellKind::Cmd, "echo ✓", ShellArgs::Raw("/D /S /C \"echo ✓\"".to_string())),
+    ];
+    for (kind, command, expected) in cases {
+        assert_eq!(kind.command_args(command, false), expected, "{:?}: {}", kind, command);
+    }
+    assert_eq!(
+        encode_powershell_command("git commit -m \"fix: \\\"quoted\\\" ✓\""),
+        "ZwBpAHQAIABjAG8AbQBtAGkAdAAgAC0AbQAgACIAZgBpAHgAOgAgAFwAIgBxAHUAbwB0AGUAZABcACIAIAATJyIA"
+    );
+}
+
+#[test]
+fn login_shells_get_each_shells_flag() {
+    let cases = [
+        ("bash", true),
+        ("/bin/zsh", true),
+        ("fish", true),
+        ("pwsh", true),
+        ("wsl.exe", true),
+        // Only `-l` alone logs in
+        ("/bin/tcsh", false),
+        ("csh", false),
+        // Windows PowerShell has no `-Login`
+        ("powershell.exe", false),
+        ("cmd.exe", false),
+    ];
+    for (shell, expected) in cases {
+        assert_eq!(supports_login(shell), expected, "{}", shell);
+    }
+
+    assert_eq!(ShellKind::Posix.command_args("nvm use", true), args(&["-l", "-c", "nvm use"]));
+    assert_eq!(
+        ShellKind::Wsl.command_args("pyenv versions", true),
+        args(&["-e", "sh", "-l", "-c", "pyenv versions"])
+    );
+    assert_eq!(
+        ShellKind::PowerShell.command_args("Write-Output \"a b\"", true),
+        args(&["-Login", "-NoLogo", "-EncodedCommand", "VwByAGkAdABlAC0ATwB1AHQAcAB1AHQAIAAiAGEAIABiACIA"])
+    );
+    assert_eq!(
+        ShellKind::Cmd.command_args("ver", true),
+        ShellArgs::Raw("/D /S /C \"ver\"".to_string())
+    );
+}
+
+#[test]
+fn environment_variables_use_each_kinds_syntax() {
+    let cases = [
+        (ShellKind::Posix, "$PATH:${HOME}/bin", "/usr/bin:/bin:/home/ash/bin"),
+        (ShellKind::Posix, "%PATH% $MISSING.", "%PATH% ."),
+        (ShellKind::Wsl, "$HOME", "/home/ash"),
+        (ShellKind::PowerShell, "$env:PATH;${env:HOME}", "/usr/bin:/bin;/home/ash"),
+        (ShellKind::PowerShell, "$ENV:HOME $PATH", "/home/ash $PATH"),
+        (ShellKind::Cmd, "%PATH%;%ProgramFiles(x86)%", "/usr/bin:/bin;C:\\Program Files (x86)"),
+        // cmd.exe leaves what it can't expand
+        (ShellKind::Cmd, "$PATH %MISSING% 100%", "$PATH %MISSING% 100%"),
+    ];
+    for (kind, text, expected) in cases {
+        assert_eq!(kind.expand_env(text, lookup), expected, "{:?}: {}", kind, text);
+    }
+    assert_eq!(ShellKind::Posix.env_var("PATH"), "$PATH");
+    assert_eq!(ShellKind::PowerShell.env_var("PATH"), "$env:PATH");
+    assert_eq!(ShellKind::Cmd.env_var("PATH"), "%PATH%");
+}
+
+#[test]
+fn cd_is_recognized_in_each_kinds_spelling() {
+    let cases = [
+        (ShellKind::Posix, "cd src", Some("src")),
+        (ShellKind::Posix, "cd", Some("")),
+        (ShellKind::Posix, "cdk deploy", None),
+        (ShellKind::Posix, "Set-Location src", None),
+        (ShellKind::PowerShell, "Set-Location  src ", Some("src")),
+        (ShellKind::PowerShell, "sl ~", Some("~")),
+        (ShellKind::Cmd, "CD /d D:\\work", Some("/d D:\\work")),
+        (ShellKind::Cmd, "chdir src", Some("src")),
+        (ShellKind::Cmd, "cd..", Some("..")),
+        (ShellKind::Cmd, "cd\\", Some("\\")),
+        (ShellKind::Cmd, "cdk", None),
+    ];
+    for (kind, command, expected) in cases {
+        assert_eq!(kind.cd_arguments(command), expected, "{:?}: {}", kind, command);
+    }
+}
+
+#[test]
+fn cd_targets_are_unquoted_and_expanded() {
+    let cases = [
+        (ShellKind::Posix, "", Some("/home/ash")),
+        (ShellKind::Posix, "~/src", Some("/home/ash/src")),
+        (ShellKind::Posix, "\"$HOME/my dir\"", Some("/home/ash/my dir")),
+        (ShellKind::Posix, "'$HOME'", Some("$HOME")),
+        (ShellKind::Posix, "~other", Some("~other")),
+        (ShellKind::PowerShell, "$env:PROJECTS\\antraft", Some("C:\\Users\\Ash\\Projects\\antraft")),
+        (ShellKind::PowerShell, "'C:\\Program Files'", Some("C:\\Program Files")),
+        (ShellKind::Cmd, "/d %PROJECTS%", Some("C:\\Users\\Ash\\Projects")),
+        (ShellKind::Cmd, "\"%ProgramFiles(x86)%\"", Some("C:\\Program Files (x86)")),
+        (ShellKind::Cmd, "~", Some("~")),
+        // A bare cd only prints the directory in cmd.exe
+        (ShellKind::Cmd, "", None),
+    ];
+    for (kind, arguments, expected) in cases {
+        assert_eq!(
+            kind.cd_target(arguments, lookup).as_deref(),
+            expected,
+            "{:?}: {}",
+            kind,
+            arguments
+        );
+    }
+}
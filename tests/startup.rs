@@ -0,0 +1,57 @@
+#![cfg(all(feature = "gui", unix))]
+
+use antraft::config::Config;
+use antraft::ui::{AnTraftApp, Subsystem};
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
+use std::time::{Duration, Instant};
+
+/// Binaries startup could be tempted to run; each notes in `ran` that it was.
+const TRIPWIRES: &[&str] = &[
+    "docker", "kubectl", "git", "bandit", "semgrep", "trivy", "osv-scanner", "ssh", "sh", "bash", "zsh", "curl",
+];
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_app_starts_without_running_binaries_or_touching_the_network() {
+    // Everything the app reads and writes stays in a fresh home
+    let home = tempfile::tempdir().unwrap();
+    let bin = home.path().join("bin");
+    let project = home.path().join("project");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join(".gitignore"), "target/\n").unwrap();
+    let ran = home.path().join("ran");
+    for name in TRIPWIRES {
+        let path = bin.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\necho {} >> {}\n", name, ran.display())).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    // Any HTTP request would go through this proxy
+    let proxy = TcpListener::bind("127.0.0.1:0").unwrap();
+    proxy.set_nonblocking(true).unwrap();
+    let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+
+    std::env::set_var("HOME", home.path());
+    for var in ["XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_CACHE_HOME", "XDG_STATE_HOME", "NO_PROXY", "no_proxy"] {
+        std::env::remove_var(var);
+    }
+    for var in ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"] {
+        std::env::set_var(var, &proxy_url);
+    }
+    std::env::set_var("PATH", &bin);
+    std::env::set_current_dir(&project).unwrap();
+
+    let mut app = AnTraftApp::new(Config::default()).await.unwrap();
+    assert!(app.time_to_first_frame().is_none());
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !app.startup_finished() {
+        assert!(Instant::now() < deadline, "startup didn't finish");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        app.poll_startup();
+    }
+    assert!(Subsystem::ALL.iter().all(|subsystem| app.is_ready(*subsystem)));
+
+    assert!(!ran.exists(), "ran {}", std::fs::read_to_string(&ran).unwrap_or_default());
+    assert_eq!(proxy.accept().unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+}
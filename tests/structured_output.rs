@@ -0,0 +1,96 @@
+use antraft::terminal::structured::{
+    detect_format, flat_table, parse_structured, pretty_lines, to_pretty_json, StructuredFormat, TokenKind,
+    MAX_STRUCTURED_BYTES,
+};
+use serde_json::{json, Value};
+
+const KUBECTL_GET_PODS: &str = include_str!("fixtures/structured/kubectl_get_pods.json");
+const DEPLOYMENT: &str = include_str!("fixtures/structured/deployment.yaml");
+
+#[test]
+fn only_output_that_starts_like_json_or_yaml_is_considered() {
+    assert_eq!(detect_format(KUBECTL_GET_PODS), Some(StructuredFormat::Json));
+    assert_eq!(detect_format("\n  [1, 2]"), Some(StructuredFormat::Json));
+    assert_eq!(detect_format(DEPLOYMENT), Some(StructuredFormat::Yaml));
+    assert_eq!(detect_format("name: web\n"), None);
+    assert_eq!(detect_format("total 8\n"), None);
+
+    let huge = format!("[{}]", "1,".repeat(MAX_STRUCTURED_BYTES) + "1");
+    assert_eq!(detect_format(&huge), None);
+}
+
+#[test]
+fn parses_json_and_yaml_documents() {
+    let (format, value) = parse_structured(KUBECTL_GET_PODS).unwrap();
+    assert_eq!(format, StructuredFormat::Json);
+    assert_eq!(value["items"][1]["metadata"]["name"], "worker-5f6c7d8e9-lq2mz");
+    // Keys keep the order they came in
+    let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["apiVersion", "items", "kind", "metadata"]);
+
+    // Several YAML documents become an array of them
+    let (format, value) = parse_structured(DEPLOYMENT).unwrap();
+    assert_eq!(format, StructuredFormat::Yaml);
+    assert_eq!(value[0]["spec"]["replicas"], 3);
+    assert_eq!(value[1]["spec"]["ports"][0]["targetPort"], 8080);
+
+    // As do values printed one after another, like jq does
+    let (_, value) = parse_structured("{\"a\": 1}\n{\"a\": 2}\n").unwrap();
+    assert_eq!(value, json!([{"a": 1}, {"a": 2}]));
+}
+
+#[test]
+fn output_that_only_looks_structured_is_left_alone() {
+    assert!(parse_structured("{a,b}.txt").is_none());
+    assert!(parse_structured("[INFO] Building project\n").is_none());
+    assert!(parse_structured("[1, 2]\nDone in 3s\n").is_none());
+    assert!(parse_structured("--- a/file\n+++ b/file\n").is_none());
+    // Scalars aren't worth another view
+    assert!(parse_structured("--- hello\n").is_none());
+}
+
+#[test]
+fn pretty_lines_match_serde_json_and_are_colored() {
+    let (_, value) = parse_structured(KUBECTL_GET_PODS).unwrap();
+    let lines = pretty_lines(&value);
+    let text: Vec<String> = lines
+        .iter()
+        .map(|line| line.iter().map(|token| token.text.as_str()).collect())
+        .collect();
+    assert_eq!(text.join("\n"), serde_json::to_string_pretty(&value).unwrap());
+
+    let value = json!({"name": "web", "ready": true, "restarts": 0, "node": null, "ports": [], "labels": {}});
+    let kinds = |line: usize| -> Vec<TokenKind> { pretty_lines(&value)[line].iter().map(|token| token.kind).collect() };
+    assert_eq!(kinds(1), [TokenKind::Punctuation, TokenKind::Key, TokenKind::Punctuation, TokenKind::String, TokenKind::Punctuation]);
+    assert_eq!(kinds(2)[3], TokenKind::Literal);
+    assert_eq!(kinds(3)[3], TokenKind::Number);
+    assert_eq!(kinds(4)[3], TokenKind::Literal);
+}
+
+#[test]
+fn several_values_are_copied_one_after_another() {
+    let values = [json!({"a": 1}), json!("b")];
+    let refs: Vec<&Value> = values.iter().collect();
+    assert_eq!(to_pretty_json(&refs), "{\n  \"a\": 1\n}\n\"b\"");
+}
+
+#[test]
+fn arrays_of_flat_objects_become_tables() {
+    let value = json!([
+        {"name": "web", "replicas": 3, "ready": true},
+        {"name": "worker", "image": "worker:1.2", "replicas": 10},
+        {"name": "cron", "replicas": null}
+    ]);
+    let table = flat_table(&value).unwrap();
+    assert!(table.has_header);
+    assert_eq!(table.headers, ["name", "replicas", "ready", "image"]);
+    assert_eq!(table.rows[1], ["worker", "10", "", "worker:1.2"]);
+    assert_eq!(table.rows[2], ["cron", "", "", ""]);
+    // Sorted by value, like any other table
+    assert_eq!(table.sorted_rows(1, false), [0, 1, 2]);
+
+    assert!(flat_table(&json!([{"name": "web", "labels": {"app": "web"}}])).is_none());
+    assert!(flat_table(&json!([1, 2, 3])).is_none());
+    assert!(flat_table(&json!([])).is_none());
+    assert!(flat_table(&json!({"name": "web"})).is_none());
+}
@@ -0,0 +1,65 @@
+use antraft::autocomplete::highlight::{highlights_as_bash, HighlightClass, SyntaxHighlighter};
+
+/// The highlighted pieces of `text` with their classes.
+fn classes(text: &str) -> Vec<(String, HighlightClass)> {
+    SyntaxHighlighter::new()
+        .highlight(text)
+        .into_iter()
+        .map(|span| (text[span.range].to_string(), span.class))
+        .collect()
+}
+
+fn class_of(text: &str, piece: &str) -> Option<HighlightClass> {
+    classes(text).into_iter().find(|(found, _)| found == piece).map(|(_, class)| class)
+}
+
+#[test]
+fn a_command_its_flags_strings_and_comments_get_their_classes() {
+    let text = r#"git commit -m "fix the build" # later"#;
+    assert_eq!(class_of(text, "git"), Some(HighlightClass::Command));
+    assert_eq!(class_of(text, "-m"), Some(HighlightClass::Flag));
+    assert_eq!(class_of(text, r#""fix the build""#), Some(HighlightClass::String));
+    assert_eq!(class_of(text, "# later"), Some(HighlightClass::Comment));
+    // A plain argument stays plain
+    assert_eq!(class_of(text, "commit"), None);
+}
+
+#[test]
+fn keywords_and_operators_are_highlighted() {
+    let text = "for f in *.rs; do wc -l $f | sort; done";
+    let found = classes(text);
+    for keyword in ["for", "in", "do", "done"] {
+        assert!(found.contains(&(keyword.to_string(), HighlightClass::Keyword)), "{} in {:?}", keyword, found);
+    }
+    assert_eq!(class_of(text, "|"), Some(HighlightClass::Operator));
+    assert_eq!(class_of(text, "wc"), Some(HighlightClass::Command));
+}
+
+#[test]
+fn a_variable_inside_a_string_is_a_variable() {
+    let text = r#"echo "hi $USER""#;
+    assert_eq!(class_of(text, "$USER"), Some(HighlightClass::Variable));
+    assert_eq!(class_of(text, r#""hi "#), Some(HighlightClass::String));
+}
+
+#[test]
+fn spans_are_ordered_and_never_overlap() {
+    let mut highlighter = SyntaxHighlighter::new();
+    let text = "if [ -f \"$HOME/.bashrc\" ]; then\n  source ~/.bashrc 2>/dev/null # quiet\nfi";
+    let spans = highlighter.highlight(text);
+    assert!(!spans.is_empty());
+    assert!(spans.windows(2).all(|pair| pair[0].range.end <= pair[1].range.start));
+    // A half-typed command still highlights what it can
+    assert_eq!(highlighter.highlight("echo \"unterminated").first().map(|span| span.class), Some(HighlightClass::Command));
+}
+
+#[test]
+fn only_bash_like_shells_are_highlighted() {
+    assert!(highlights_as_bash("/bin/bash"));
+    assert!(highlights_as_bash("zsh"));
+    assert!(highlights_as_bash("wsl.exe"));
+    assert!(!highlights_as_bash("pwsh"));
+    assert!(!highlights_as_bash("cmd.exe"));
+    assert!(!highlights_as_bash("/usr/bin/fish"));
+    assert!(!highlights_as_bash("nu"));
+}
@@ -0,0 +1,53 @@
+use antraft::ai::system_prompt::{render_system_prompt, PromptContext, DEFAULT_SYSTEM_PROMPT};
+
+fn context() -> PromptContext {
+    PromptContext {
+        os: "linux".to_string(),
+        shell: "zsh".to_string(),
+        cwd: "/home/me/project".to_string(),
+        date: "2026-10-16".to_string(),
+    }
+}
+
+#[test]
+fn the_default_prompt_is_filled_in() {
+    let rendered = render_system_prompt(DEFAULT_SYSTEM_PROMPT, &context());
+    assert!(rendered.contains("The user is on linux using zsh, working in /home/me/project; today is 2026-10-16."));
+    assert!(!rendered.contains('{'));
+}
+
+#[test]
+fn every_variable_form_is_substituted() {
+    assert_eq!(
+        render_system_prompt("{os} ${shell} $cwd on $date.", &context()),
+        "linux zsh /home/me/project on 2026-10-16."
+    );
+    // A bare name ends at the first character that can't be in one
+    assert_eq!(render_system_prompt("$shell-specific, $os/$cwd", &context()), "zsh-specific, linux//home/me/project");
+}
+
+#[test]
+fn undefined_variables_are_left_alone() {
+    let template = "Use $HOME, ${PATH}, {user}, $oses and {os-name} with { or $ or }.";
+    assert_eq!(render_system_prompt(template, &context()), template);
+    assert_eq!(render_system_prompt("unclosed {os and ${shell", &context()), "unclosed {os and ${shell");
+}
+
+#[test]
+fn a_backslash_keeps_a_variable_literal() {
+    assert_eq!(
+        render_system_prompt(r"Write \{os}, \${os} or \$os for the OS ({os})", &context()),
+        "Write {os}, ${os} or $os for the OS (linux)"
+    );
+    // Other backslashes stay
+    assert_eq!(render_system_prompt(r"C:\Users\me on {os}\n", &context()), r"C:\Users\me on linux\n");
+}
+
+#[test]
+fn values_are_not_substituted_again() {
+    let context = PromptContext {
+        cwd: "/tmp/{os}/$shell".to_string(),
+        ..context()
+    };
+    assert_eq!(render_system_prompt("in {cwd}", &context), "in /tmp/{os}/$shell");
+}
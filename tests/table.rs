@@ -0,0 +1,97 @@
+use antraft::terminal::table::{compare_cells, parse_table, Table};
+use std::cmp::Ordering;
+
+const LS_L: &str = include_str!("fixtures/table/ls_l.txt");
+const DF_H: &str = include_str!("fixtures/table/df_h.txt");
+const PS: &str = include_str!("fixtures/table/ps.txt");
+const KUBECTL_GET_PODS: &str = include_str!("fixtures/table/kubectl_get_pods.txt");
+const DOCKER_PS: &str = include_str!("fixtures/table/docker_ps.txt");
+
+fn table(output: &str) -> Table {
+    parse_table(output).expect("output aligned in columns")
+}
+
+fn column(table: &Table, index: usize) -> Vec<&str> {
+    table.rows.iter().map(|row| row[index].as_str()).collect()
+}
+
+#[test]
+fn ls_l_has_no_header_and_keeps_spaces_in_names() {
+    let table = table(LS_L);
+    assert!(!table.has_header);
+    assert_eq!(table.headers.len(), 9);
+    assert_eq!(table.headers[0], "Column 1");
+    assert_eq!(table.rows.len(), 4);
+    assert_eq!(
+        table.rows[1],
+        ["-rw-r--r--", "1", "ash", "staff", "1234", "Mar", "14", "18:40", "Cargo.toml"]
+    );
+    assert_eq!(column(&table, 8), ["src", "Cargo.toml", "Cargo.lock", "release notes.md"]);
+}
+
+#[test]
+fn df_joins_multi_word_headers() {
+    let table = table(DF_H);
+    assert!(table.has_header);
+    assert_eq!(table.headers, ["Filesystem", "Size", "Used", "Avail", "Use%", "Mounted on"]);
+    assert_eq!(column(&table, 5), ["/dev", "/run", "/", "/dev/shm"]);
+    assert_eq!(table.rows[2][0], "/dev/nvme0n1p2");
+}
+
+#[test]
+fn ps_keeps_command_arguments_in_the_last_column() {
+    let table = table(PS);
+    assert_eq!(table.headers, ["PID", "TTY", "TIME", "CMD"]);
+    assert_eq!(column(&table, 0), ["4242", "98765", "733"]);
+    assert_eq!(column(&table, 3), ["bash", "cargo test --workspace", "ps"]);
+}
+
+#[test]
+fn upper_case_headers_with_spaces_in_cells() {
+    let pods = table(KUBECTL_GET_PODS);
+    assert_eq!(pods.headers, ["NAME", "READY", "STATUS", "RESTARTS", "AGE"]);
+    assert_eq!(column(&pods, 3), ["0", "2 (5h ago)", "12"]);
+
+    let containers = table(DOCKER_PS);
+    assert_eq!(
+        containers.headers,
+        ["CONTAINER ID", "IMAGE", "COMMAND", "CREATED", "STATUS", "PORTS", "NAMES"]
+    );
+    assert_eq!(column(&containers, 3), ["2 hours ago", "5 days ago"]);
+    assert_eq!(column(&containers, 5), ["0.0.0.0:5432->5432/tcp", ""]);
+    assert_eq!(column(&containers, 6), ["db", "cache"]);
+}
+
+#[test]
+fn text_that_isnt_a_table_is_left_alone() {
+    assert_eq!(parse_table(""), None);
+    assert_eq!(parse_table("Hello, world!\n"), None);
+    // A single row
+    assert_eq!(parse_table("NAME   STATUS\n"), None);
+    assert_eq!(parse_table("total 0\n"), None);
+    // Prose doesn't line up
+    assert_eq!(
+        parse_table("error: could not compile `antraft` due to 2 previous errors\nwarning: build failed, waiting for other jobs to finish\n"),
+        None
+    );
+    // Wrapped or indented continuation lines
+    assert_eq!(parse_table("usage: git   [--version]\n             [--help]\n"), None);
+}
+
+#[test]
+fn sorting_compares_numbers_and_sizes_by_value() {
+    assert_eq!(compare_cells("9", "10"), Ordering::Less);
+    assert_eq!(compare_cells("512", "1.6G"), Ordering::Less);
+    assert_eq!(compare_cells("120M", "2.1M"), Ordering::Greater);
+    assert_eq!(compare_cells("49%", "2%"), Ordering::Greater);
+    assert_eq!(compare_cells("4KiB", "4096"), Ordering::Equal);
+    assert_eq!(compare_cells("12", "api"), Ordering::Less);
+    assert_eq!(compare_cells("build.rs", "Cargo.toml"), Ordering::Less);
+
+    let disks = table(DF_H);
+    let mounts = |order: Vec<usize>| -> Vec<&str> { order.into_iter().map(|row| disks.rows[row][5].as_str()).collect() };
+    assert_eq!(mounts(disks.sorted_rows(2, false)), ["/dev", "/run", "/dev/shm", "/"]);
+    assert_eq!(mounts(disks.sorted_rows(2, true)), ["/", "/dev/shm", "/run", "/dev"]);
+    // Ties keep the output's order
+    assert_eq!(mounts(disks.sorted_rows(1, false)), ["/run", "/dev", "/dev/shm", "/"]);
+}
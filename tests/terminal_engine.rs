@@ -0,0 +1,378 @@
+use antraft::terminal::audit::CommandOrigin;
+use antraft::terminal::block::{metadata_keys, BlockType};
+use antraft::terminal::timeout::TIMED_OUT_EXIT_CODE;
+use antraft::terminal::{TerminalConfig, TerminalEngine, TerminalEvent, TerminalEventReceiver};
+use std::time::Duration;
+use uuid::Uuid;
+
+fn engine() -> (TerminalEngine, TerminalEventReceiver) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let config = TerminalConfig {
+        shell: "bash".to_string(),
+        ..TerminalConfig::default()
+    };
+    (TerminalEngine::new(config, tx).unwrap(), rx)
+}
+
+/// Runs `command`, returning its output and exit code.
+async fn run(engine: &TerminalEngine, events: &mut TerminalEventReceiver, command: &str) -> (String, i32) {
+    let id = engine.execute_command(command.to_string()).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(10), collect(events, id))
+        .await
+        .expect("command didn't finish")
+}
+
+async fn collect(events: &mut TerminalEventReceiver, id: Uuid) -> (String, i32) {
+    let mut output = String::new();
+    while let Some(event) = events.recv().await {
+        match event {
+            TerminalEvent::CommandOutput { id: event_id, output: chunk, .. } if event_id == id => {
+                output.push_str(&chunk);
+            }
+            TerminalEvent::CommandFinished { id: event_id, exit_code } if event_id == id => {
+                return (output, exit_code);
+            }
+            _ => {}
+        }
+    }
+    panic!("event channel closed");
+}
+
+#[tokio::test]
+async fn runs_a_command_and_reports_its_output() {
+    let (engine, mut events) = engine();
+    let (output, exit_code) = run(&engine, &mut events, "echo hello").await;
+    assert_eq!(output.trim(), "hello");
+    assert_eq!(exit_code, 0);
+}
+
+#[tokio::test]
+async fn keeps_output_that_isnt_utf8() {
+    let (engine, mut events) = engine();
+    // Latin-1, stray bytes, and a sequence cut off by the end of the output
+    let (output, exit_code) = run(&engine, &mut events, r"printf 'caf\351 au lait\n\377\376ab\n\342\202'").await;
+    assert_eq!(output, "caf\u{FFFD} au lait\n\u{FFFD}\u{FFFD}ab\n\u{FFFD}");
+    assert_eq!(exit_code, 0);
+}
+
+#[tokio::test]
+async fn reports_the_exit_code_of_a_failing_command() {
+    let (engine, mut events) = engine();
+    let (_, exit_code) = run(&engine, &mut events, "exit 3").await;
+    assert_eq!(exit_code, 3);
+}
+
+#[tokio::test]
+async fn runs_commands_in_a_login_shell_when_asked() {
+    let (engine, mut events) = engine();
+    let (_, exit_code) = run(&engine, &mut events, "shopt -q login_shell").await;
+    assert_eq!(exit_code, 1);
+
+    let (tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let config = TerminalConfig {
+        shell: "bash".to_string(),
+        login_shell: true,
+        ..TerminalConfig::default()
+    };
+    let engine = TerminalEngine::new(config, tx).unwrap();
+    let (_, exit_code) = run(&engine, &mut events, "shopt -q login_shell").await;
+    assert_eq!(exit_code, 0);
+}
+
+#[tokio::test]
+async fn runs_a_command_in_another_directory_without_moving_the_session() {
+    let (engine, mut events) = engine();
+    let session_id = engine.create_session().await.unwrap();
+    let directory = tempfile::tempdir().unwrap();
+    let original = directory.path().canonicalize().unwrap().to_string_lossy().to_string();
+
+    let id = engine
+        .execute_command_in("pwd -P".to_string(), original.clone(), CommandOrigin::User)
+        .await
+        .unwrap();
+    let (output, exit_code) = tokio::time::timeout(Duration::from_secs(10), collect(&mut events, id))
+        .await
+        .expect("command didn't finish");
+    assert_eq!(exit_code, 0);
+    assert_eq!(output.trim(), original);
+
+    let session = engine.get_active_session().await.unwrap();
+    assert_eq!(session.id, session_id);
+    assert_ne!(session.current_directory, original);
+}
+
+#[tokio::test]
+async fn sessions_change_directory_independently() {
+    let (engine, mut events) = engine();
+    let process_directory = std::env::current_dir().unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path().canonicalize().unwrap();
+    std::fs::create_dir_all(root.join("a/nested")).unwrap();
+    std::fs::create_dir(root.join("b")).unwrap();
+    let path = |relative: &str| root.join(relative).to_string_lossy().to_string();
+
+    let first = engine.create_session().await.unwrap();
+    let second = engine.create_session().await.unwrap();
+    let cd = |target: String| format!("cd \"{}\"", target);
+    assert!(matches!(engine.handle_builtin_command(&cd(path("a/nested"))).await, Some(Ok(_))));
+    // Relative to the session's directory, not the process's
+    assert!(matches!(engine.handle_builtin_command("cd ..").await, Some(Ok(_))));
+    engine.switch_session(second).await.unwrap();
+    assert!(matches!(engine.handle_builtin_command(&cd(path("b"))).await, Some(Ok(_))));
+    assert!(matches!(engine.handle_builtin_command("cd missing").await, Some(Err(_))));
+
+    let (output, _) = run(&engine, &mut events, "pwd -P").await;
+    assert_eq!(output.trim(), path("b"));
+    let pwd = engine.handle_builtin_command("pwd").await.unwrap().unwrap();
+    assert_eq!(pwd.content, path("b"));
+
+    engine.switch_session(first).await.unwrap();
+    assert_eq!(engine.current_directory().await, path("a"));
+    let (output, _) = run(&engine, &mut events, "pwd -P").await;
+    assert_eq!(output.trim(), path("a"));
+
+    assert_eq!(std::env::current_dir().unwrap(), process_directory);
+}
+
+#[tokio::test]
+async fn opening_a_session_in_a_workspace_leaves_the_others_where_they_are() {
+    let (engine, mut events) = engine();
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path().canonicalize().unwrap();
+    let first = engine.create_session().await.unwrap();
+    let before = engine.current_directory().await;
+
+    let (opened, directory) = engine.open_session_in(&root).await.unwrap();
+    assert_ne!(opened, first);
+    assert_eq!(directory, root.to_string_lossy());
+    assert_eq!(engine.get_active_session().await.unwrap().id, opened);
+    let (output, _) = run(&engine, &mut events, "pwd -P").await;
+    assert_eq!(output.trim(), root.to_string_lossy());
+
+    engine.switch_session(first).await.unwrap();
+    assert_eq!(engine.current_directory().await, before);
+}
+
+#[tokio::test]
+async fn records_commands_in_the_active_session() {
+    let (engine, mut events) = engine();
+    let session_id = engine.create_session().await.unwrap();
+    let (_, exit_code) = run(&engine, &mut events, "true").await;
+    assert_eq!(exit_code, 0);
+
+    let blocks = engine.get_session_blocks(session_id).await.unwrap();
+    assert!(blocks.iter().any(|block| block.content == "true"));
+}
+
+#[tokio::test]
+async fn expands_aliases_defined_with_the_builtin() {
+    let (engine, mut events) = engine();
+    let defined = engine.handle_builtin_command("alias greet='echo hi there'").await;
+    assert!(matches!(defined, Some(Ok(_))));
+    assert_eq!(
+        engine.get_aliases().await.get("greet").map(String::as_str),
+        Some("echo hi there")
+    );
+
+    let (output, _) = run(&engine, &mut events, "greet").await;
+    assert_eq!(output.trim(), "hi there");
+}
+
+#[tokio::test]
+async fn unalias_removes_the_alias() {
+    let (engine, mut events) = engine();
+    engine.handle_builtin_command("alias greet='echo hi'").await.unwrap().unwrap();
+    engine.handle_builtin_command("unalias greet").await.unwrap().unwrap();
+    assert!(engine.get_aliases().await.is_empty());
+
+    // The shell gets the word as typed
+    let (output, exit_code) = run(&engine, &mut events, "greet").await;
+    assert_ne!(exit_code, 0);
+    assert!(output.contains("greet"), "{}", output);
+    // And it can't be removed twice
+    let again = engine.handle_builtin_command("unalias greet").await.unwrap().unwrap();
+    assert!(matches!(again.block_type, BlockType::Error));
+}
+
+#[tokio::test]
+async fn alias_cycles_are_refused_instead_of_run() {
+    let (engine, _events) = engine();
+    engine.handle_builtin_command("alias a=b").await.unwrap().unwrap();
+    engine.handle_builtin_command("alias b=a").await.unwrap().unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(5), engine.execute_command("a".to_string()))
+        .await
+        .expect("expansion should stop");
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("cycle"), "{}", error);
+}
+
+#[tokio::test]
+async fn aliases_for_builtins_run_as_builtins() {
+    let (engine, mut events) = engine();
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path().canonicalize().unwrap();
+    std::fs::create_dir_all(root.join("project/src")).unwrap();
+    let path = |relative: &str| root.join(relative).to_string_lossy().to_string();
+    engine.create_session().await.unwrap();
+
+    let define = format!("alias proj='cd \"{}\"'", path("project"));
+    engine.handle_builtin_command(&define).await.unwrap().unwrap();
+    engine.handle_builtin_command("alias ..='cd ..'").await.unwrap().unwrap();
+    engine.handle_builtin_command("alias here=pwd").await.unwrap().unwrap();
+
+    assert!(matches!(engine.handle_builtin_command("proj").await, Some(Ok(_))));
+    assert_eq!(engine.current_directory().await, path("project"));
+    let (output, _) = run(&engine, &mut events, "pwd -P").await;
+    assert_eq!(output.trim(), path("project"));
+    let pwd = engine.handle_builtin_command("here").await.unwrap().unwrap();
+    assert_eq!(pwd.content, path("project"));
+
+    engine.run_init_commands(vec!["cd src".to_string(), "..".to_string(), "..".to_string()]).await;
+    assert_eq!(engine.current_directory().await, root.to_string_lossy());
+    engine.run_sequence(vec!["proj".to_string()], true).await.unwrap();
+    assert_eq!(engine.current_directory().await, path("project"));
+}
+
+#[tokio::test]
+async fn leaves_other_commands_to_the_shell() {
+    let (engine, _events) = engine();
+    assert!(engine.handle_builtin_command("ls -la").await.is_none());
+}
+
+/// Whether `pid` is a live process; zombies count as gone.
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "stat=", "-p", &pid.to_string()])
+        .output()
+        .unwrap();
+    let stat = String::from_utf8_lossy(&output.stdout);
+    !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn shutdown_and_reap_kills_running_commands_and_their_children() {
+    let (engine, mut events) = engine();
+    let id = engine.execute_command("sleep 30".to_string()).await.unwrap();
+    // The shell forks for a list, so the background sleep is a grandchild
+    let job_id = engine.execute_command("sleep 30 & echo $!; wait".to_string()).await.unwrap();
+
+    let job_pid: u32 = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match events.recv().await.expect("event channel closed") {
+                TerminalEvent::CommandOutput { id, output, .. } if id == job_id => {
+                    return output.trim().parse().unwrap();
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("no pid printed");
+    let running = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let running = engine.running_commands().await;
+            if running.len() == 2 {
+                return running;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("commands didn't start");
+    let pids: Vec<u32> = running.iter().filter_map(|command| command.pid).chain([job_pid]).collect();
+    assert!(running.iter().any(|command| command.id == id && command.command == "sleep 30"));
+    assert!(pids.iter().all(|pid| is_alive(*pid)));
+
+    assert_eq!(engine.shutdown_and_reap(Duration::from_secs(5)).await, 2);
+
+    assert!(engine.running_commands().await.is_empty());
+    assert!(!engine.is_running());
+    // The group is killed at once, but the orphaned grandchild is reaped by init
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while pids.iter().any(|pid| is_alive(*pid)) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("processes survived shutdown");
+}
+
+#[tokio::test]
+async fn runs_a_multi_line_script_as_one_command() {
+    let (engine, mut events) = engine();
+    let script = "cd /\nfor word in one two; do\n  echo \"$word\"\ndone\npwd";
+    assert!(engine.handle_builtin_command(script).await.is_none());
+    let (output, exit_code) = run(&engine, &mut events, script).await;
+    assert_eq!(output.replace("\r\n", "\n").trim(), "one\ntwo\n/");
+    assert_eq!(exit_code, 0);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn a_command_past_its_timeout_is_killed_with_its_children() {
+    let (engine, mut events) = engine();
+    let started = std::time::Instant::now();
+    let id = engine
+        .execute_command("@timeout=500ms sleep 30 & echo $!; wait".to_string())
+        .await
+        .unwrap();
+
+    let mut child_pid = None;
+    let mut timed_out = None;
+    let exit_code = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match events.recv().await.expect("event channel closed") {
+                TerminalEvent::CommandStarted { id: event_id, command, .. } if event_id == id => {
+                    assert_eq!(command, "sleep 30 & echo $!; wait");
+                }
+                TerminalEvent::CommandOutput { id: event_id, output, .. } if event_id == id => {
+                    child_pid = output.trim().parse::<u32>().ok();
+                }
+                TerminalEvent::CommandTimedOut { id: event_id, timeout } if event_id == id => timed_out = Some(timeout),
+                TerminalEvent::CommandFinished { id: event_id, exit_code } if event_id == id => return exit_code,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("command wasn't killed");
+
+    assert_eq!(exit_code, TIMED_OUT_EXIT_CODE);
+    assert_eq!(timed_out, Some(Duration::from_millis(500)));
+    assert!(started.elapsed() >= Duration::from_millis(500));
+    let child_pid = child_pid.expect("no pid printed");
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while is_alive(child_pid) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the background sleep survived");
+
+    let blocks = engine.get_session_blocks(engine.get_active_session().await.unwrap().id).await.unwrap();
+    let block = blocks.iter().find(|block| block.id == id).unwrap();
+    assert_eq!(block.get_metadata(metadata_keys::TIMED_OUT).map(String::as_str), Some("500ms"));
+}
+
+#[tokio::test]
+async fn a_command_finishing_inside_its_timeout_is_left_alone() {
+    let (tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let config = TerminalConfig {
+        shell: "bash".to_string(),
+        timeout: Some(5),
+        ..TerminalConfig::default()
+    };
+    let engine = TerminalEngine::new(config, tx).unwrap();
+    // Just inside the default
+    let (output, exit_code) = run(&engine, &mut events, "sleep 0.3; echo done").await;
+    assert_eq!((output.trim(), exit_code), ("done", 0));
+
+    // A command's own timeout overrides the default, either way
+    let (_, exit_code) = run(&engine, &mut events, "@timeout=200ms sleep 2").await;
+    assert_eq!(exit_code, TIMED_OUT_EXIT_CODE);
+    let (_, exit_code) = run(&engine, &mut events, "@timeout=off sleep 0.1").await;
+    assert_eq!(exit_code, 0);
+}
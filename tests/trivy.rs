@@ -0,0 +1,127 @@
+mod common;
+
+use antraft::security::fixes::{self, FixAction};
+use antraft::security::trivy;
+use antraft::security::{
+    Remediation, ScanTarget, ScanType, ScannerKind, SecurityConfig, SecurityScanRequest, SecurityScanner, Severity,
+};
+use common::{vulnerability, StubScanner};
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> serde_json::Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/security").join(name);
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn parses_image_reports() {
+    let found = trivy::parse_results(&fixture("trivy_image.json"), None);
+    let ids: Vec<_> = found.iter().map(|finding| finding.id.as_str()).collect();
+    assert_eq!(ids, ["CVE-2024-2511", "CVE-2023-45853", "GHSA-c2qf-rxjj-qqgw"]);
+
+    let openssl = &found[0];
+    assert_eq!(openssl.title, "CVE-2024-2511: openssl: Unbounded memory growth with session handling in TLSv1.3");
+    assert_eq!(openssl.severity, Severity::High);
+    assert_eq!(openssl.category, "container");
+    assert_eq!(openssl.scanner, "trivy");
+    assert_eq!(openssl.file_path, "nginx:1.25 (debian 12.5)");
+    assert_eq!(
+        openssl.suggested_fix.as_deref(),
+        Some("Upgrade libssl3 from 3.0.11-1~deb12u2 to 3.0.13-1~deb12u1")
+    );
+    // The primary URL first, without duplicates
+    assert_eq!(
+        openssl.references,
+        ["https://avd.aquasec.com/nvd/cve-2024-2511", "https://www.openssl.org/news/secadv/20240408.txt"]
+    );
+    // Image packages can't be upgraded in place
+    assert!(found.iter().all(|finding| finding.remediation.is_none()));
+
+    let zlib = &found[1];
+    assert_eq!(zlib.title, "CVE-2023-45853 in zlib1g");
+    assert_eq!(zlib.severity, Severity::Critical);
+    assert_eq!(zlib.suggested_fix.as_deref(), Some("No fixed version of zlib1g yet (installed: 1:1.2.13.dfsg-1)"));
+
+    // Of several fixed versions, the lowest past the installed one
+    let semver = &found[2];
+    assert_eq!(semver.category, "dependency");
+    assert_eq!(semver.suggested_fix.as_deref(), Some("Upgrade semver from 7.5.1 to 7.5.2"));
+}
+
+#[test]
+fn parses_filesystem_reports_with_upgrades() {
+    let root = PathBuf::from("/repo");
+    let found = trivy::parse_results(&fixture("trivy_fs.json"), Some(&root));
+    assert_eq!(found.len(), 2);
+
+    let lodash = &found[0];
+    assert_eq!(lodash.file_path, "web/package-lock.json");
+    assert_eq!(
+        lodash.remediation,
+        Some(Remediation::Upgrade {
+            ecosystem: "npm".to_string(),
+            package: "lodash".to_string(),
+            version: Some("4.17.20".to_string()),
+            fixed_version: Some("4.17.21".to_string()),
+            source: Some(root.join("web/package-lock.json")),
+        })
+    );
+    assert_eq!(
+        fixes::fix_action(lodash, &root).unwrap(),
+        Some(FixAction::Command {
+            command: "npm install lodash@4.17.21".to_string(),
+            directory: root.join("web"),
+        })
+    );
+
+    let rsa = &found[1];
+    assert_eq!(rsa.severity, Severity::Medium);
+    assert_eq!(rsa.suggested_fix.as_deref(), Some("No fixed version of rsa yet (installed: 0.9.6)"));
+    assert!(matches!(
+        &rsa.remediation,
+        Some(Remediation::Upgrade { ecosystem, fixed_version: None, .. }) if ecosystem == "crates.io"
+    ));
+
+    assert!(trivy::parse_results(&serde_json::json!({"SchemaVersion": 2}), None).is_empty());
+}
+
+fn image_request(image: &str) -> SecurityScanRequest {
+    SecurityScanRequest {
+        target: ScanTarget::ContainerImage(image.to_string()),
+        scan_type: ScanType::Quick,
+        include_patterns: Vec::new(),
+        exclude_patterns: Vec::new(),
+        honor_ignore_comments: true,
+    }
+}
+
+#[tokio::test]
+async fn scans_images_with_the_scanners_that_can() {
+    let code = StubScanner::new("code", ScannerKind::Code, vec![vulnerability("Weak hash", Severity::High, "code")]);
+    let images = StubScanner::new(
+        "images",
+        ScannerKind::Dependencies,
+        vec![vulnerability("CVE-2024-2511", Severity::High, "images")],
+    )
+    .scanning_images();
+    let scanner = SecurityScanner::with_scanners(SecurityConfig::default(), vec![Box::new(code), Box::new(images)]);
+
+    // The scan type doesn't apply to images
+    let report = scanner.scan(image_request("nginx:1.25")).await.unwrap();
+    let titles: Vec<_> = report.vulnerabilities.iter().map(|finding| finding.title.as_str()).collect();
+    assert_eq!(titles, ["CVE-2024-2511"]);
+    assert_eq!(report.image.as_deref(), Some("nginx:1.25"));
+    assert_eq!(report.target_name(), "nginx:1.25");
+    assert!(report.to_markdown().contains("**Image:** nginx:1.25"));
+
+    assert!(scanner.scan(image_request("  ")).await.is_err());
+}
+
+#[tokio::test]
+async fn explains_how_to_get_trivy_when_nothing_scans_images() {
+    let code = StubScanner::new("code", ScannerKind::Code, Vec::new());
+    let scanner = SecurityScanner::with_scanners(SecurityConfig::default(), vec![Box::new(code)]);
+    let error = scanner.scan(image_request("nginx:1.25")).await.unwrap_err().to_string();
+    assert!(error.starts_with("Can't scan container images: trivy isn't installed"), "{}", error);
+    assert!(error.contains("aquasecurity.github.io/trivy"), "{}", error);
+}
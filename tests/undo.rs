@@ -0,0 +1,73 @@
+use antraft::terminal::undo::{remove_blocks, restore_blocks, UndoEntry, UndoStack};
+
+fn blocks(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// Deletes `name` and records it, as deleting a block does.
+fn delete(stack: &mut UndoStack<String>, blocks: &mut Vec<String>, name: &str) {
+    let removed = remove_blocks(blocks, |block| block == name);
+    stack.push(UndoEntry::RemovedBlocks {
+        blocks: removed,
+        cleared: false,
+    });
+}
+
+fn undo(stack: &mut UndoStack<String>, blocks: &mut Vec<String>) {
+    match stack.pop() {
+        Some(UndoEntry::RemovedBlocks { blocks: removed, .. }) => restore_blocks(blocks, removed),
+        other => panic!("expected removed blocks, got {:?}", other),
+    }
+}
+
+#[test]
+fn undoing_several_deletions_restores_the_original_order() {
+    let mut stack = UndoStack::new(10);
+    let mut terminal = blocks(&["a", "b", "c", "d", "e"]);
+    delete(&mut stack, &mut terminal, "b");
+    delete(&mut stack, &mut terminal, "e");
+    delete(&mut stack, &mut terminal, "a");
+    assert_eq!(terminal, blocks(&["c", "d"]));
+
+    undo(&mut stack, &mut terminal);
+    assert_eq!(terminal, blocks(&["a", "c", "d"]));
+    undo(&mut stack, &mut terminal);
+    undo(&mut stack, &mut terminal);
+    assert_eq!(terminal, blocks(&["a", "b", "c", "d", "e"]));
+    assert!(stack.is_empty());
+}
+
+#[test]
+fn clearing_keeps_what_it_should_and_comes_back_around_it() {
+    let mut terminal = blocks(&["a", "pinned", "b", "c"]);
+    let removed = remove_blocks(&mut terminal, |block| block != "pinned");
+    assert_eq!(terminal, blocks(&["pinned"]));
+    assert_eq!(removed.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![0, 2, 3]);
+
+    // A command run since the clear stays after the restored blocks
+    terminal.push("new".to_string());
+    restore_blocks(&mut terminal, removed);
+    assert_eq!(terminal, blocks(&["a", "pinned", "b", "c", "new"]));
+}
+
+#[test]
+fn blocks_go_at_the_end_when_their_place_is_gone() {
+    let mut terminal = blocks(&["a"]);
+    restore_blocks(&mut terminal, vec![(5, "z".to_string())]);
+    assert_eq!(terminal, blocks(&["a", "z"]));
+}
+
+#[test]
+fn the_oldest_entries_expire_past_the_limit() {
+    let mut stack = UndoStack::new(2);
+    for text in ["one", "two", "three"] {
+        stack.push(UndoEntry::<String>::ClearedInput { text: text.to_string() });
+    }
+    assert_eq!(stack.len(), 2);
+    assert_eq!(stack.pop(), Some(UndoEntry::ClearedInput { text: "three".to_string() }));
+
+    stack.set_limit(0);
+    assert!(stack.is_empty());
+    stack.push(UndoEntry::ClearedInput { text: "ignored".to_string() });
+    assert!(stack.pop().is_none());
+}
@@ -0,0 +1,75 @@
+use antraft::terminal::pty::VteProcessor;
+use antraft::terminal::title::{truncate, SessionTitle, MAX_TAB_LABEL_CHARS};
+use std::path::Path;
+
+fn home() -> Option<&'static Path> {
+    Some(Path::new("/home/dev"))
+}
+
+#[test]
+fn running_command_shows_with_its_directory() {
+    let title = SessionTitle::Running {
+        command: "npm test".to_string(),
+        directory: "/home/dev/proj".to_string(),
+        program_title: None,
+    };
+    assert_eq!(title.window_title(home()), "⏳ npm test — ~/proj");
+}
+
+#[test]
+fn program_title_replaces_the_computed_one() {
+    let title = SessionTitle::Running {
+        command: "vim notes.md".to_string(),
+        directory: "/home/dev".to_string(),
+        program_title: Some("notes.md (~) - VIM".to_string()),
+    };
+    assert_eq!(title.window_title(home()), "notes.md (~) - VIM");
+}
+
+#[test]
+fn idle_title_shows_directory_and_branch() {
+    let local = SessionTitle::Idle {
+        directory: "/home/dev/proj".to_string(),
+        branch: Some("main".to_string()),
+        remote: None,
+    };
+    assert_eq!(local.window_title(home()), "proj — ⎇ main");
+
+    let home_directory = SessionTitle::Idle {
+        directory: "/home/dev".to_string(),
+        branch: None,
+        remote: None,
+    };
+    assert_eq!(home_directory.window_title(home()), "~");
+
+    let remote = SessionTitle::Idle {
+        directory: "/srv/app".to_string(),
+        branch: None,
+        remote: Some("deploy@web1".to_string()),
+    };
+    assert_eq!(remote.window_title(home()), "🌐 deploy@web1 — app");
+}
+
+#[test]
+fn tab_labels_are_truncated() {
+    let title = SessionTitle::Running {
+        command: "cargo test --workspace --all-features".to_string(),
+        directory: "/home/dev/proj".to_string(),
+        program_title: None,
+    };
+    let label = title.tab_label(home());
+    assert_eq!(label.chars().count(), MAX_TAB_LABEL_CHARS);
+    assert!(label.starts_with("⏳ cargo test"));
+    assert!(label.ends_with('…'));
+    assert_eq!(truncate("short", 10), "short");
+}
+
+#[test]
+fn osc_title_sequences_are_taken_out_of_the_output() {
+    let mut processor = VteProcessor::new();
+    let (text, _) = processor.process_to_text(b"\x1b]0;htop\x07running\n\x1b]2;build: 50%\x1b\\");
+    assert_eq!(text, "running\n");
+    // The last title wins
+    assert_eq!(processor.take_title().as_deref(), Some("build: 50%"));
+    assert_eq!(processor.take_title(), None);
+}
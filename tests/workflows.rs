@@ -0,0 +1,151 @@
+use antraft::workflows::{builtin_workflows, Placeholder, PlaceholderKind, Workflow, WorkflowStore};
+use std::collections::HashMap;
+
+fn workflow(command: &str) -> Workflow {
+    Workflow::new("test", "", command)
+}
+
+fn placeholder(name: &str, kind: PlaceholderKind, default: Option<&str>) -> Placeholder {
+    Placeholder {
+        name: name.to_string(),
+        kind,
+        default: default.map(str::to_string),
+    }
+}
+
+fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+fn options(values: &[&str]) -> PlaceholderKind {
+    PlaceholderKind::Enum(values.iter().map(|value| value.to_string()).collect())
+}
+
+#[test]
+fn placeholders_are_parsed_with_their_types_and_defaults() {
+    let workflow = workflow(concat!(
+        "run {{name}} {{lines:int=100}} ",
+        "{{shell:enum(sh| bash |zsh)=sh}} {{path=.}} {{note:text=}}"
+    ));
+    assert_eq!(
+        workflow.placeholders().unwrap(),
+        [
+            placeholder("name", PlaceholderKind::Text, None),
+            placeholder("lines", PlaceholderKind::Int, Some("100")),
+            placeholder("shell", options(&["sh", "bash", "zsh"]), Some("sh")),
+            placeholder("path", PlaceholderKind::Text, Some(".")),
+            placeholder("note", PlaceholderKind::Text, Some("")),
+        ]
+    );
+
+    // The first use of a repeated placeholder defines it
+    let repeated = self::workflow("cp {{file:text=a.txt}} {{file}}.bak");
+    assert_eq!(
+        repeated.placeholders().unwrap(),
+        [placeholder("file", PlaceholderKind::Text, Some("a.txt"))]
+    );
+}
+
+#[test]
+fn braces_that_are_not_placeholders_stay_text() {
+    let docker = workflow("docker inspect -f '{{.State.Status}}' {{container}}");
+    assert_eq!(docker.placeholders().unwrap(), [placeholder("container", PlaceholderKind::Text, None)]);
+    assert_eq!(
+        docker.render(&values(&[("container", "web")])).unwrap(),
+        "docker inspect -f '{{.State.Status}}' web"
+    );
+
+    let escaped = workflow(r"echo \{{name}} {{name}} {{ unclosed");
+    assert_eq!(escaped.placeholders().unwrap().len(), 1);
+    assert_eq!(escaped.render(&values(&[("name", "x")])).unwrap(), "echo {{name}} x {{ unclosed");
+
+    assert!(workflow("echo {{1st}} {{}} {{-x}}").placeholders().unwrap().is_empty());
+}
+
+#[test]
+fn unknown_types_are_errors() {
+    let error = workflow("sleep {{seconds:float}}").placeholders().unwrap_err().to_string();
+    assert_eq!(error, "Unknown type 'float' for seconds; use text, int or enum(a|b)");
+    let error = workflow("run {{mode:enum( | )}}").placeholders().unwrap_err().to_string();
+    assert_eq!(error, "enum for mode has no values");
+    assert!(workflow("run {{mode:enum(a|b}}").validate().is_err());
+}
+
+#[test]
+fn values_are_validated_against_their_type() {
+    let lines = placeholder("lines", PlaceholderKind::Int, None);
+    assert!(lines.validate("42").is_ok());
+    assert!(lines.validate("-3").is_ok());
+    assert_eq!(lines.validate("ten").unwrap_err().to_string(), "lines must be a whole number");
+    assert_eq!(lines.validate("").unwrap_err().to_string(), "lines needs a value");
+
+    let shell = placeholder("shell", options(&["sh", "bash"]), None);
+    assert!(shell.validate("bash").is_ok());
+    assert_eq!(shell.validate("fish").unwrap_err().to_string(), "shell must be one of sh, bash");
+    assert_eq!(shell.initial_value(), "sh");
+
+    // `{{name=}}` may be left empty; a default doesn't make empty valid
+    assert!(placeholder("note", PlaceholderKind::Text, Some("")).validate("").is_ok());
+    let path = placeholder("path", PlaceholderKind::Text, Some("."));
+    assert!(path.validate("").is_err());
+    assert_eq!(path.initial_value(), ".");
+
+    assert!(workflow("head -n {{lines:int=10}}").validate().is_ok());
+    let error = workflow("head -n {{lines:int=ten}}").validate().unwrap_err();
+    assert_eq!(format!("{:#}", error), "Invalid default for lines: lines must be a whole number");
+    assert!(Workflow::new(" ", "", "ls").validate().is_err());
+    assert!(Workflow::new("empty", "", "  ").validate().is_err());
+}
+
+#[test]
+fn values_are_substituted_as_typed() {
+    let workflow = workflow("ssh -N -L {{local:int}}:localhost:{{remote:int}} {{host}} # {{host}}");
+    let rendered = workflow
+        .render(&values(&[("local", "8080"), ("remote", "80"), ("host", "my host; rm -rf /")]))
+        .unwrap();
+    assert_eq!(rendered, "ssh -N -L 8080:localhost:80 my host; rm -rf / # my host; rm -rf /");
+
+    let error = workflow.render(&values(&[("local", "8080"), ("host", "h")])).unwrap_err();
+    assert_eq!(error.to_string(), "remote needs a value");
+    let error = workflow
+        .render(&values(&[("local", "x"), ("remote", "80"), ("host", "h")]))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "local must be a whole number");
+
+    let optional = self::workflow("ls {{flags=}}");
+    assert_eq!(optional.render(&HashMap::new()).unwrap(), "ls ");
+}
+
+#[test]
+fn builtin_workflows_are_valid() {
+    for workflow in builtin_workflows() {
+        workflow.validate().unwrap_or_else(|e| panic!("{}: {:#}", workflow.name, e));
+        assert!(workflow.builtin);
+    }
+}
+
+#[test]
+fn user_workflows_are_saved_and_override_builtins() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = WorkflowStore::load(dir.path()).unwrap();
+    let builtins = store.workflows().len();
+
+    store.save(Workflow::new("tail-log", "Follow a log", "tail -n {{lines:int=50}} -f {{file}}")).unwrap();
+    store.save(Workflow::new("port-owner", "Mine", "ss -ltnp 'sport = :{{port:int}}'")).unwrap();
+    assert!(store.save(Workflow::new("bad", "", "sleep {{s:float}}")).is_err());
+    std::fs::write(dir.path().join("broken.toml"), "name = \"broken\"").unwrap();
+
+    let mut store = WorkflowStore::load(dir.path()).unwrap();
+    assert_eq!(store.workflows().len(), builtins + 1);
+    assert_eq!(store.get("tail-log").unwrap().placeholders().unwrap().len(), 2);
+    let owner = store.get("port-owner").unwrap();
+    assert!(!owner.builtin);
+    assert_eq!(owner.render(&values(&[("port", "22")])).unwrap(), "ss -ltnp 'sport = :22'");
+
+    store.delete("port-owner").unwrap();
+    assert!(store.get("port-owner").unwrap().builtin);
+    assert!(store.delete("port-owner").is_err());
+    store.delete("tail-log").unwrap();
+    assert!(store.get("tail-log").is_none());
+    assert_eq!(WorkflowStore::load(dir.path()).unwrap().workflows().len(), builtins);
+}
@@ -0,0 +1,78 @@
+use antraft::workspaces::{self, ProjectType, RecentWorkspaces};
+use chrono::{Duration, TimeZone, Utc};
+use std::path::PathBuf;
+
+#[test]
+fn recording_moves_a_workspace_to_the_front_and_drops_the_oldest() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("workspaces.json");
+    let now = Utc::now();
+    let mut recent = RecentWorkspaces::new(&path, 3);
+    for (minutes, name) in [(4, "a"), (3, "b"), (2, "c"), (1, "b"), (0, "d")] {
+        recent.record(&PathBuf::from(name), now - Duration::minutes(minutes));
+    }
+    let paths = |recent: &RecentWorkspaces| recent.entries().iter().map(|w| w.path.clone()).collect::<Vec<_>>();
+    assert_eq!(paths(&recent), ["d", "b", "c"].map(PathBuf::from));
+    assert_eq!(recent.entries()[0].last_used, Some(now));
+
+    assert!(recent.remove(&PathBuf::from("b")));
+    assert!(!recent.remove(&PathBuf::from("a")));
+    recent.save().unwrap();
+    let loaded = RecentWorkspaces::load(&path, 3, &[PathBuf::from("legacy")]).unwrap();
+    assert_eq!(paths(&loaded), ["d", "c"].map(PathBuf::from));
+}
+
+#[test]
+fn the_config_list_is_read_until_there_is_a_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let legacy = ["one", "two", "three"].map(PathBuf::from);
+    let recent = RecentWorkspaces::load(&dir.path().join("workspaces.json"), 2, &legacy).unwrap();
+    assert_eq!(recent.entries().len(), 2);
+    assert_eq!(recent.entries()[0].path, legacy[0]);
+    assert_eq!(recent.entries()[0].last_used, None);
+}
+
+#[test]
+fn stale_workspaces_are_the_ones_whose_directory_is_gone() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    std::fs::create_dir(&project).unwrap();
+    let mut recent = RecentWorkspaces::new(&dir.path().join("workspaces.json"), 8);
+    recent.record(&project, Utc::now());
+    assert!(!recent.entries()[0].is_missing());
+    assert_eq!(recent.entries()[0].name(), "project");
+
+    std::fs::remove_dir(&project).unwrap();
+    assert!(recent.entries()[0].is_missing());
+}
+
+#[test]
+fn detects_the_project_type_from_marker_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = |name: &str, files: &[&str]| {
+        let path = dir.path().join(name);
+        std::fs::create_dir(&path).unwrap();
+        for file in files {
+            std::fs::create_dir_all(path.join(file).parent().unwrap()).unwrap();
+            std::fs::write(path.join(file), "").unwrap();
+        }
+        path
+    };
+    assert_eq!(ProjectType::detect(&project("rust", &["Cargo.toml", "package.json"])), ProjectType::Rust);
+    assert_eq!(ProjectType::detect(&project("python", &["requirements.txt"])), ProjectType::Python);
+    assert_eq!(ProjectType::detect(&project("dotnet", &["App.csproj"])), ProjectType::DotNet);
+    assert_eq!(ProjectType::detect(&project("repo", &[".git/HEAD"])), ProjectType::Git);
+    assert_eq!(ProjectType::detect(&project("plain", &["notes.txt"])), ProjectType::Other);
+}
+
+#[test]
+fn last_used_times_read_relative_to_now() {
+    let now = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+    let ago = |duration: Duration| workspaces::format_last_used(now - duration, now);
+    assert_eq!(ago(Duration::seconds(20)), "just now");
+    assert_eq!(ago(Duration::minutes(5)), "5 min ago");
+    assert_eq!(ago(Duration::hours(3)), "3 h ago");
+    assert_eq!(ago(Duration::hours(30)), "yesterday");
+    assert_eq!(ago(Duration::days(4)), "4 days ago");
+    assert!(ago(Duration::days(60)).starts_with("2024-04-1"));
+}